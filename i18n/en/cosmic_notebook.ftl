@@ -4,13 +4,25 @@
 app-name = Cosmic Notebook
 app-description = A lightweight Markdown editor for the COSMIC desktop
 
+# Menu bar
+menu-file = File
+menu-edit = Edit
+menu-format = Format
+menu-insert = Insert
+menu-view = View
+menu-help = Help
+
 # File menu
 file-new = New
 file-open = Open
 file-save = Save
-file-save-as = Save As
+file-save-as = Save As...
+file-save-all = Save All
 file-close = Close
 file-close-all = Close All
+file-reopen-closed = Reopen Closed Tab
+file-watch = Watch for Changes
+file-single-instance = Open Files in This Window
 file-recent = Recent Files
 file-clear-recent = Clear Recent
 file-quit = Quit
@@ -20,11 +32,54 @@ edit-undo = Undo
 edit-redo = Redo
 edit-cut = Cut
 edit-copy = Copy
+edit-copy-html = Copy as HTML
+edit-copy-html-source = Copy HTML Source
+edit-copy-heading-link = Copy Heading Link
+edit-copy-heading-link-path = Copy Heading Link with Path
+edit-copy-file-path = Copy File Path
+edit-copy-relative-path = Copy Relative Path
+edit-copy-file-name = Copy File Name
 edit-paste = Paste
+edit-paste-plain = Paste as Plain Text
+edit-paste-markdown = Paste as Markdown
+edit-paste-image-url = Paste Image from URL
+edit-paste-history = Paste from History
 edit-select-all = Select All
+edit-sort-lines = Sort Lines
+edit-sort-lines-descending = Sort Lines Descending
+edit-sort-lines-case-sensitive = Sort Lines (Case Sensitive)
+edit-dedupe-lines = Remove Duplicate Lines
+edit-join-lines = Join Lines
+edit-transpose-chars = Transpose Characters
+edit-transpose-words = Transpose Words
 edit-find = Find
-edit-find-replace = Find and Replace
+edit-find-replace = Find & Replace
 edit-go-to-line = Go to Line
+edit-toggle-bookmark = Toggle Bookmark
+edit-next-bookmark = Next Bookmark
+edit-previous-bookmark = Previous Bookmark
+edit-clear-bookmarks = Clear All Bookmarks
+edit-toggle-read-only = Toggle Read-Only
+edit-override-read-only = Override Read-Only
+
+# Format menu
+format-bold = Bold
+format-italic = Italic
+format-inline-code = Inline Code
+format-strikethrough = Strikethrough
+format-heading-increase = Increase Heading Level
+format-heading-decrease = Decrease Heading Level
+format-blockquote = Toggle Blockquote
+format-reflow-paragraph = Reflow Paragraph
+format-callout-note = Note Callout
+format-callout-tip = Tip Callout
+format-callout-warning = Warning Callout
+format-open-link = Open Link
+
+# Insert menu
+insert-date-time = Date/Time
+insert-date-iso = Date/Time (ISO 8601)
+insert-date-locale = Date (Locale)
 
 # View menu
 view-sidebar = Sidebar
@@ -38,6 +93,27 @@ view-fullscreen = Fullscreen
 view-line-numbers = Line Numbers
 view-word-wrap = Word Wrap
 view-status-bar = Status Bar
+view-toggle-sidebar = Toggle Sidebar
+view-swap-sidebar-side = Move Sidebar to Other Side
+view-expand-all-folders = Expand All Folders
+view-collapse-all-folders = Collapse All Folders
+view-render-whitespace = Cycle Whitespace Markers
+view-live-preview = Toggle Live Preview
+view-sanitize-html = Toggle Sanitizing Raw HTML
+view-wiki-links = Toggle Wiki Links
+view-browser-preview = Preview in Browser
+view-stop-browser-preview = Stop Browser Preview
+view-toggle-preview = Toggle Preview
+view-show-changes = Show Changes
+view-show-problems = Show Problems
+view-split-vertical = Split Editor Vertically
+view-split-horizontal = Split Editor Horizontally
+view-move-tab = Move Tab to Other Pane
+view-unsplit = Unsplit Editor
+
+# Help menu
+help-command-palette = Command Palette
+help-about = About
 
 # Tab actions
 tab-close = Close Tab
@@ -62,6 +138,137 @@ status-words = { $count } words
 status-modified = Modified
 status-read-only = Read Only
 status-encoding = { $encoding }
+save-status-saved = Saved
+save-status-saved-at = Saved { $time }
+save-status-saving = Saving…
+save-status-unsaved = Unsaved changes
+save-status-snapshot = Recovery snapshot { $time }
+save-status-snapshot-failed = Recovery snapshot failed
+
+# Status messages
+status-preview-markdown-only = Preview is only available for Markdown files
+status-pasted = Pasted { $count ->
+        [one] { $count } character
+       *[other] { $count } characters
+    }
+status-pasted-plain = Pasted { $count ->
+        [one] { $count } character
+       *[other] { $count } characters
+    } as plain text
+status-pasted-history = Pasted { $count ->
+        [one] { $count } character
+       *[other] { $count } characters
+    } from history
+status-pasted-link = Pasted link
+status-pasted-markdown = Pasted as markdown
+status-clipboard-empty = Clipboard is empty
+status-clipboard-history-empty = Clipboard history is empty
+status-clipboard-error = Clipboard error
+status-paste-failed = Failed to paste from clipboard
+status-copy-failed = Failed to copy to clipboard
+status-fetching-title = Fetching page title...
+status-downloading-image = Downloading image...
+status-image-url-missing = Clipboard doesn't hold an image URL
+status-image-url-not-image = URL isn't an image
+status-image-url-saved = Saved image to the assets folder
+status-image-url-remote = Couldn't save the image, linked the URL instead
+status-image-url-closed = Image not pasted, its document was closed
+status-cut = Cut { $count ->
+        [one] { $count } character
+       *[other] { $count } characters
+    }
+status-copied = Copied { $count ->
+        [one] { $count } character
+       *[other] { $count } characters
+    }
+status-copied-html = Copied { $count ->
+        [one] { $count } character
+       *[other] { $count } characters
+    } as HTML
+status-copied-html-source = Copied HTML source of { $count ->
+        [one] { $count } character
+       *[other] { $count } characters
+    }
+status-copied-document-html-source = Copied HTML source of the document
+status-copied-link = Copied { $link }
+status-nothing-to-cut = Nothing selected to cut
+status-nothing-to-copy = Nothing selected to copy
+status-not-on-heading = The cursor is not on a heading
+status-save-for-link-path = Save the document to copy a link with its path
+status-save-for-path = Save the document to copy its path
+status-copied-path = Copied { $path }
+status-no-link = No link at cursor
+status-note-created = New note { $name }; save it to create the file
+status-heading-not-found = Heading #{ $anchor } not found
+status-open-failed = Failed to open: { $path }
+status-created = Created { $name }
+status-file-linked = Linked { $name }
+status-expand-all-stopped = Stopped expanding after { $count } folders
+status-create-failed = Failed to create: { $path }
+status-open-url-failed = Failed to open { $url }
+status-saved = Saved: { $path }
+status-save-failed = Failed to save file
+status-snapshot-failed = Failed to save a recovery snapshot
+status-config-save-failed = Failed to save settings: { $error }
+status-render-whitespace = Whitespace markers: { $mode ->
+    [trailing] trailing only
+    [all] all
+   *[none] off
+}
+status-live-preview-on = Live preview on
+status-live-preview-off = Live preview off
+status-sanitize-html-on = Raw HTML is sanitized in previews and exports
+status-sanitize-html-off = Raw HTML is kept as written in previews and exports
+status-wiki-links-on = Wiki links on
+status-wiki-links-off = Wiki links off
+status-preview-server-started = Previewing at { $url }
+status-preview-server-stopped = Stopped the browser preview
+status-preview-server-failed = Couldn't start the browser preview
+status-no-closed-tabs = No closed tabs to reopen
+status-reopen-failed = Failed to reopen: { $path }
+status-not-autosaved = Not autosaved to disk (read-only or changed externally): { $names }
+status-invalid-autosave-globs = Ignoring invalid autosave exclude globs: { $globs }
+status-undo-unavailable = Undo not yet implemented for text editor
+status-redo-unavailable = Redo not yet implemented for text editor
+status-document-read-only = { $name } is read-only
+status-read-only-on-disk = File is read-only on disk; use Override Read-Only to make it writable
+status-read-only-enabled = Read-only lock enabled
+status-bookmark-added = Bookmarked line { $line }
+status-bookmark-removed = Removed the bookmark on line { $line }
+status-bookmarks-cleared = Cleared all bookmarks
+status-no-bookmarks = No bookmarks in this document
+status-read-only-disabled = Read-only lock disabled
+status-watched-read-only = { $name } reloads from disk while watching for changes
+status-not-reloaded = Not reloaded, to keep unsaved changes: { $names }
+status-watch-enabled = Watching for changes: files reload when changed on disk
+status-watch-disabled = Stopped watching for changes
+status-watch-failed = Could not watch files for changes
+status-single-instance-on = Files opened from other launches now open in this window
+status-single-instance-off = Other launches now open their own window
+status-now-writable = { $name } is now writable
+status-override-failed = Could not override read-only: { $error }
+status-zoom = Zoom: { $percent }%
+status-search-truncated = Showing the first { $count ->
+        [one] match
+       *[other] { $count } matches
+    }
+status-search-wrapped = Search wrapped around the document
+status-match-changed = Match changed since the search ran; results updated
+status-find-no-selection = Select the lines to find in first
+status-replaced = Replaced { $count ->
+        [one] { $count } occurrence
+       *[other] { $count } occurrences
+    }
+replace-all-confirm-title = Replace { $count ->
+        [one] { $count } occurrence
+       *[other] { $count } occurrences
+    }?
+replace-all-confirm-body = All matches of the search will be replaced.
+replace-all-confirm-size = The document will go from { $before } to { $after }.
+replace-all-confirm-replace = Replace All
+replace-all-confirm-cancel = Cancel
+status-style-checks-off = Style checks are off; only broken links are listed
+status-split-needs-document = Open another document to split the editor
 
 # Search
 search-placeholder = Find...
@@ -70,12 +277,26 @@ search-case-sensitive = Case Sensitive
 search-whole-word = Whole Word
 search-regex = Regular Expression
 search-results = { $current } of { $total }
+search-result-count = { $count ->
+        [one] { $count } result
+       *[other] { $count } results
+    }
 search-no-results = No results
+search-replace = Replace
+search-replace-all = Replace All
 
 # File browser
 sidebar-search = Search files...
 sidebar-no-folder = No folder open
 sidebar-empty = No files found
+sidebar-new-file = New File
+sidebar-new-folder = New Folder
+sidebar-new-file-placeholder = File name
+sidebar-new-folder-placeholder = Folder name
+sidebar-filter-markdown = Markdown
+sidebar-filter-all = All
+sidebar-filter-glob-placeholder = Glob, e.g. *.txt
+sidebar-name-taken = “{ $name }” already exists; pick another name
 
 # Errors
 error-file-not-found = File not found: { $path }
@@ -84,6 +305,7 @@ error-file-too-large = File is too large ({ $size } MB). Maximum size is { $max
 error-encoding = Unable to read file. It may use an unsupported encoding.
 error-save-failed = Could not save file. Check disk space and permissions.
 error-clipboard = Could not access clipboard.
+error-diagram = Could not render diagram: { $error }
 
 # Welcome
 welcome-title = Welcome to Cosmic Notebook
@@ -100,3 +322,6 @@ command-palette-empty = No matching commands
 about-version = Version { $version }
 about-license = Licensed under GPL-3.0
 about-website = Visit website
+
+# Breadcrumb
+breadcrumb-untitled = Untitled
@@ -3,24 +3,37 @@
 //! This is the central hub of the application, implementing libCosmic's
 //! Application trait for window management and message routing.
 
-use crate::config::{Config, APP_ID};
+use crate::config::{Config, RenderWhitespace, ViewMode, APP_ID};
+use crate::editor::minimap::MinimapDrag;
+use crate::editor::snippets::{locate_edit, SnippetSession, SnippetSet};
+use crate::editor::TextBuffer;
 use crate::file_handler::RecoveryManager;
+use crate::markdown::preview::server::PreviewServer;
+use crate::markdown::MarkdownRenderer;
 use crate::menu::{keyboard_shortcuts_subscription, Action as MenuAction};
 use crate::message::{
-    ClipboardMessage, DialogMessage, EditorMessage, FileMessage, InternalMessage, Message,
-    SearchMessage, SystemMessage, TabMessage, ViewMessage,
+    ClipboardMessage, CursorMotion, DialogMessage, EditorMessage, FileMessage, InternalMessage, Message,
+    SearchMessage, SidebarMessage, SystemMessage, TabMessage, ViewMessage,
+};
+use crate::state::{
+    AppState, CommandPalette, Document, DocumentId, FileDrag, NewEntryKind, PaletteEntry, SessionState,
+    SidebarLimits, SplitOrientation,
 };
-use crate::state::{AppState, Document, DocumentId, SessionState};
 use crate::ui;
+use crate::fl;
+use crate::utils::{DebounceEdge, Debouncer};
 
 use cosmic::app::{Core, Task};
+use cosmic::cosmic_config::CosmicConfigEntry;
 use cosmic::iced::window;
 use cosmic::widget::menu::KeyBind;
 use cosmic::widget::text_editor;
 use cosmic::{Application, ApplicationExt, Element};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Helper function to convert line/column to character index in a rope
 fn line_col_to_char(rope: &ropey::Rope, line: usize, col: usize) -> Option<usize> {
@@ -39,6 +52,135 @@ fn line_col_to_char(rope: &ropey::Rope, line: usize, col: usize) -> Option<usize
     Some(line_start + clamped_col)
 }
 
+/// Get the cursor of a text editor as a character offset
+fn content_cursor_offset(content: &text_editor::Content) -> usize {
+    let (line, column) = content.cursor_position();
+    let rope = ropey::Rope::from_str(&content.text());
+    if line >= rope.len_lines() {
+        return rope.len_chars();
+    }
+
+    // The editor reports the column as a byte index into the line
+    let line_text = rope.line(line).to_string();
+    let column = line_text
+        .get(..column.min(line_text.len()))
+        .map(|before| before.chars().count())
+        .unwrap_or(column);
+    line_col_to_char(&rope, line, column).unwrap_or(rope.len_chars())
+}
+
+/// Select a character range in a text editor by moving from the cursor
+fn select_content_range(content: &mut text_editor::Content, start: usize, end: usize) {
+    use text_editor::{Action, Motion};
+
+    if content.selection().is_some() {
+        content.perform(Action::Move(Motion::Left));
+    }
+
+    let cursor = content_cursor_offset(content);
+    let motion = if start < cursor { Motion::Left } else { Motion::Right };
+    for _ in 0..cursor.abs_diff(start) {
+        content.perform(Action::Move(motion));
+    }
+    for _ in start..end {
+        content.perform(Action::Select(Motion::Right));
+    }
+}
+
+/// Move the cursor of `content` from character offset `from` to `to` one
+/// character at a time, extending the selection when `select` is set
+///
+/// The editor's left and right motions step backward or forward through a
+/// line depending on its base direction, so each step picks the motion
+/// that goes the right way on the line the cursor is on.
+fn move_content_cursor(
+    content: &mut text_editor::Content,
+    rope: &ropey::Rope,
+    from: usize,
+    to: usize,
+    select: bool,
+) {
+    use crate::editor::bidi::{base_direction, Direction};
+    use text_editor::{Action, Motion};
+
+    let forward = to > from;
+    let mut pos = from;
+    while pos != to {
+        let line = rope.line(rope.char_to_line(pos)).to_string();
+        let rtl = base_direction(&line) == Direction::RightToLeft;
+        let motion = if forward != rtl { Motion::Right } else { Motion::Left };
+        content.perform(if select { Action::Select(motion) } else { Action::Move(motion) });
+        pos = if forward { pos + 1 } else { pos - 1 };
+    }
+}
+
+/// Reveal a range after a jump, keeping `scroll_off` lines of context
+///
+/// The editor only scrolls far enough to show the cursor, so the cursor is
+/// first moved past the target in the direction of travel and then back.
+fn reveal_content_range(
+    content: &mut text_editor::Content,
+    (start, end): (usize, usize),
+    downward: bool,
+    scroll_off: usize,
+) {
+    use text_editor::{Action, Motion};
+
+    select_content_range(content, start, start);
+    let motion = if downward { Motion::Down } else { Motion::Up };
+    for _ in 0..scroll_off {
+        content.perform(Action::Move(motion));
+    }
+    select_content_range(content, start, end);
+}
+
+/// Ticks before an animated jump snaps to its target
+const SCROLL_ANIMATION_TICKS: u8 = 20;
+
+/// Time between animated scroll steps
+const SCROLL_ANIMATION_INTERVAL: Duration = Duration::from_millis(16);
+
+/// How often watch mode collects file system events
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Most folders expanding the whole sidebar tree lists
+const MAX_EXPAND_ALL_DIRS: usize = 2000;
+
+/// Share of a document's length a regex Replace All may add or remove
+/// before it asks for confirmation
+const REPLACE_ALL_SIZE_CHANGE_SHARE: f64 = 0.5;
+
+/// Fewest bytes a size change must be to ask, so small documents don't
+const REPLACE_ALL_SIZE_CHANGE_MIN: usize = 1024;
+
+/// An animated jump to a range in a document
+struct ScrollAnimation {
+    document_id: DocumentId,
+    /// Character range selected when the animation finishes
+    target: (usize, usize),
+    /// Line of the start of the target
+    target_line: usize,
+    /// Whether the target is below where the jump started
+    downward: bool,
+    /// Ticks left before snapping to the target
+    ticks_left: u8,
+}
+
+/// Commands the palette remembers as recently used
+const MAX_RECENT_COMMANDS: usize = 10;
+
+/// How long editing must pause before a document is linted and its links checked
+const CHECK_DELAY: Duration = Duration::from_millis(750);
+
+/// Identifies the system theme mode subscription
+struct ThemeModeSubscription;
+
+/// Identifies the subscription to requests from later launches
+struct InstanceSubscription;
+
+/// Identifies the D-Bus service subscription
+struct DbusSubscription;
+
 /// Cosmic Notebook Application
 pub struct CosmicNotebook {
     /// libCosmic core reference
@@ -62,11 +204,54 @@ pub struct CosmicNotebook {
     /// Pending autosave flag
     autosave_pending: bool,
 
+    /// Files that autosave skips, from `files.autosave_exclude`
+    autosave_exclude: globset::GlobSet,
+
     /// Initialization complete flag
     initialized: bool,
 
     /// Keyboard shortcut bindings
     key_binds: HashMap<KeyBind, MenuAction>,
+
+    /// Snippets available for Tab expansion
+    snippets: SnippetSet,
+
+    /// Keyboard modifiers currently held
+    modifiers: cosmic::iced::keyboard::Modifiers,
+
+    /// Heading anchor to jump to once a file opened from a link loads
+    pending_anchor: Option<(PathBuf, String)>,
+
+    /// Notes in the open folder, for resolving wiki links
+    notes: crate::markdown::NoteIndex,
+
+    /// Jump being animated when smooth scrolling is enabled
+    scroll_animation: Option<ScrollAnimation>,
+
+    /// Markdown renderer holding the syntax color scheme
+    renderer: MarkdownRenderer,
+
+    /// When each document last asked for debounced checks
+    check_debouncers: HashMap<DocumentId, Debouncer>,
+
+    /// Markdown linter for each document, caching its line analysis
+    linters: HashMap<DocumentId, crate::markdown::Linter>,
+
+    /// Commands run from the palette, most recent first
+    recent_commands: Vec<MenuAction>,
+
+    /// Socket later launches send files to, in single-instance mode
+    instance_socket: Option<std::sync::Arc<crate::ipc::InstanceSocket>>,
+
+    /// Watches the folders of open files, in watch mode
+    watcher: Option<crate::file_handler::FileWatcher>,
+
+    /// Server for the browser preview, with the document it shows
+    preview_server: Option<(DocumentId, PreviewServer)>,
+
+    /// Latest browser preview render started, so older ones finishing late
+    /// are dropped
+    preview_render: u64,
 }
 
 /// Application flags passed during initialization
@@ -77,6 +262,12 @@ pub struct Flags {
 
     /// Working directory
     pub working_dir: Option<PathBuf>,
+
+    /// Text read from standard input, opened as a new untitled document
+    pub stdin_content: Option<String>,
+
+    /// Start in watch mode, reloading files whenever they change on disk
+    pub watch: bool,
 }
 
 impl Application for CosmicNotebook {
@@ -122,16 +313,65 @@ impl Application for CosmicNotebook {
             editor_contents: HashMap::new(),
             recovery_manager,
             autosave_pending: false,
+            autosave_exclude: globset::GlobSet::empty(),
             initialized: false,
             key_binds: crate::menu::key_binds(),
+            snippets: SnippetSet::load(),
+            modifiers: cosmic::iced::keyboard::Modifiers::default(),
+            pending_anchor: None,
+            notes: crate::markdown::NoteIndex::default(),
+            scroll_animation: None,
+            renderer: MarkdownRenderer::new(),
+            check_debouncers: HashMap::new(),
+            linters: HashMap::new(),
+            recent_commands: Vec::new(),
+            instance_socket: None,
+            watcher: None,
+            preview_server: None,
+            preview_render: 0,
         };
 
+        // Take requests from later launches; if another instance still holds
+        // the socket, this one runs on its own
+        if app.config.single_instance {
+            app.bind_instance_socket();
+        }
+
+        // Apply preview font settings
+        app.state.set_preview_font(
+            &app.config.editor.preview_font_family,
+            app.config.editor.preview_font_size,
+        );
+        app.renderer.set_markdown_options(app.config.markdown);
+        app.renderer.set_soft_break_as_newline(app.config.preview.soft_break_as_newline);
+        crate::editor::clipboard().set_history_size(app.config.editor.clipboard_history_size);
+        app.build_autosave_exclude();
+
+        // Apply editor font size and restore zoom
+        app.state.editor_font_size = app.config.editor.font_size;
+        app.state.rulers = app.config.editor.ruler_column.clone();
+        app.state.surround_pairs = app.config.editor.surround_pairs.clone();
+        app.state.render_whitespace = app.config.editor.render_whitespace;
+        app.state.preserve_md_hard_breaks = app.config.editor.preserve_md_hard_breaks;
+        app.state.line_numbers = app.config.editor.line_numbering();
+        app.state.minimap_enabled = app.config.editor.minimap;
+        app.state.live_preview = app.config.editor.live_preview;
+        app.state.show_save_status = app.config.ui.show_save_status;
+        app.apply_search_config();
+        if app.config.ui.zoom_scales_ui {
+            app.state.set_zoom_level(app.session.zoom_level);
+        }
+
         // Set window title
         app.set_header_title("Cosmic Notebook".to_string());
 
         // Collect tasks for opening initial files
         let mut tasks: Vec<Task<Message>> = Vec::new();
 
+        // Pick light or dark colors
+        app.state.system_dark_mode = cosmic::theme::is_dark();
+        tasks.push(app.apply_theme());
+
         // Open files from command line
         for path in flags.files {
             tasks.push(Task::perform(
@@ -140,9 +380,28 @@ impl Application for CosmicNotebook {
             ));
         }
 
+        // Reopen scratch documents from the last session
+        app.restore_scratch_documents();
+
+        // Open piped input after them, so it is the active tab
+        if let Some(text) = flags.stdin_content {
+            app.open_untitled(&text);
+        }
+
+        if flags.watch {
+            app.set_watch_mode(true);
+        }
+
+        // Restore the sidebar from the last session
+        app.apply_sidebar_config();
+        app.state.sidebar.visible = app.session.sidebar_visible;
+        let width = app.session.sidebar_width;
+        app.state.sidebar.set_width(width);
+
         // Set working directory for sidebar
         if let Some(dir) = flags.working_dir {
             app.state.sidebar.set_root(dir);
+            tasks.push(app.rescan_sidebar());
         }
 
         // Mark as initialized after initial setup
@@ -168,6 +427,7 @@ impl Application for CosmicNotebook {
             Message::Search(msg) => self.handle_search_message(msg),
             Message::View(msg) => self.handle_view_message(msg),
             Message::Dialog(msg) => self.handle_dialog_message(msg),
+            Message::Sidebar(msg) => self.handle_sidebar_message(msg),
             Message::System(msg) => self.handle_system_message(msg),
             Message::Internal(msg) => self.handle_internal_message(msg),
             Message::Surface(_) => Task::none(), // Surface actions are handled by libcosmic
@@ -181,6 +441,14 @@ impl Application for CosmicNotebook {
         ui::view(&self.state, &self.editor_contents)
     }
 
+    /// Modal dialog shown over the window
+    fn dialog(&self) -> Option<Element<'_, Self::Message>> {
+        self.state
+            .replace_all_confirm
+            .as_ref()
+            .map(ui::build_replace_all_dialog)
+    }
+
     /// Handle subscription events
     fn subscription(&self) -> cosmic::iced::Subscription<Self::Message> {
         use cosmic::iced::time;
@@ -190,6 +458,142 @@ impl Application for CosmicNotebook {
             keyboard_shortcuts_subscription(),
         ];
         
+        // Step animated jumps
+        if self.scroll_animation.is_some() {
+            subscriptions.push(
+                time::every(SCROLL_ANIMATION_INTERVAL)
+                    .map(|_| Message::Internal(InternalMessage::ScrollAnimationTick)),
+            );
+        }
+        
+        // Dismiss the status message once it times out; keying the timer on
+        // the message's creation time restarts it when a new message replaces it
+        if let Some(status) = &self.state.status_message {
+            if let Some(remaining) = status.remaining(self.status_timeout(status.level)) {
+                subscriptions.push(cosmic::iced::Subscription::run_with_id(
+                    status.created_at,
+                    cosmic::iced::futures::stream::once(async move {
+                        tokio::time::sleep(remaining).await;
+                        Message::System(SystemMessage::ClearStatus)
+                    }),
+                ));
+            }
+        }
+        
+        // Follow the pointer while the sidebar resize handle is dragged
+        if self.state.sidebar.is_resizing() {
+            subscriptions.push(cosmic::iced::event::listen_with(|event, _, _| {
+                use cosmic::iced::{mouse, Event};
+                match event {
+                    Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                        Some(Message::View(ViewMessage::SidebarResizeMoved(position.x)))
+                    }
+                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                        Some(Message::View(ViewMessage::EndSidebarResize))
+                    }
+                    _ => None,
+                }
+            }));
+        }
+        
+        // Follow the pointer while a sidebar file is pressed, to drag it
+        if self.state.sidebar.file_drag.is_some() {
+            subscriptions.push(cosmic::iced::event::listen_with(|event, _, _| {
+                use cosmic::iced::{mouse, Event};
+                match event {
+                    Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                        Some(Message::Sidebar(SidebarMessage::FileDragMoved {
+                            x: position.x,
+                            y: position.y,
+                        }))
+                    }
+                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                        Some(Message::Sidebar(SidebarMessage::EndFileDrag))
+                    }
+                    _ => None,
+                }
+            }));
+        }
+        
+        // Collect file system events in watch mode
+        if self.watcher.is_some() {
+            subscriptions.push(
+                time::every(WATCH_POLL_INTERVAL)
+                    .map(|_| Message::Internal(InternalMessage::WatchTick)),
+            );
+        }
+        
+        // Scroll the editor with the pointer while a minimap is dragged
+        if self.state.minimap_drag.is_some() {
+            subscriptions.push(cosmic::iced::event::listen_with(|event, _, _| {
+                use cosmic::iced::{mouse, Event};
+                match event {
+                    Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                        Some(Message::View(ViewMessage::MinimapDragged(position.y)))
+                    }
+                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                        Some(Message::View(ViewMessage::MinimapReleased))
+                    }
+                    _ => None,
+                }
+            }));
+        }
+        
+        // Open files sent by later launches
+        if let Some(socket) = &self.instance_socket {
+            let socket = socket.clone();
+            subscriptions.push(cosmic::iced::Subscription::run_with_id(
+                std::any::TypeId::of::<InstanceSubscription>(),
+                cosmic::iced::futures::stream::unfold(socket, |socket| async move {
+                    let listener = socket.clone();
+                    match tokio::task::spawn_blocking(move || listener.accept()).await {
+                        Ok(Ok(Some(request))) => Some((
+                            Message::Internal(InternalMessage::InstanceRequest(
+                                std::sync::Arc::new(request),
+                            )),
+                            socket,
+                        )),
+                        // Single-instance mode was turned off
+                        Ok(Ok(None)) => None,
+                        Ok(Err(e)) => {
+                            log::warn!("Stopped listening for other launches: {}", e);
+                            None
+                        }
+                        Err(_) => None,
+                    }
+                }),
+            ));
+        }
+        
+        // Open files other applications ask for over D-Bus
+        subscriptions.push(cosmic::iced::Subscription::run_with_id(
+            std::any::TypeId::of::<DbusSubscription>(),
+            cosmic::iced::futures::stream::unfold(None, |requests| async move {
+                let mut requests = match requests {
+                    Some(requests) => requests,
+                    None => match crate::dbus::OpenRequests::serve().await {
+                        Ok(requests) => requests,
+                        Err(e) => {
+                            log::warn!("D-Bus service unavailable: {}", e);
+                            return None;
+                        }
+                    },
+                };
+                let path = requests.next().await?;
+                Some((Message::File(FileMessage::OpenExternal(path)), Some(requests)))
+            }),
+        ));
+        
+        // Follow the system light/dark setting
+        subscriptions.push(
+            cosmic::cosmic_config::config_subscription::<_, cosmic::cosmic_theme::ThemeMode>(
+                std::any::TypeId::of::<ThemeModeSubscription>(),
+                cosmic::cosmic_theme::THEME_MODE_ID.into(),
+                cosmic::cosmic_theme::ThemeMode::version(),
+            )
+            .map(|update| Message::System(SystemMessage::ThemeChanged(update.config.is_dark))),
+        );
+        
         // Add autosave timer if enabled
         if self.config.files.autosave_enabled && self.initialized {
             let interval = Duration::from_secs(self.config.files.autosave_interval);
@@ -216,48 +620,122 @@ impl Application for CosmicNotebook {
                 Message::Surface, // Surface action wrapper
                 vec![
                     (
-                        "File",
+                        fl!("menu-file"),
+                        vec![
+                            MenuItem::Button(fl!("file-new"), None, MenuAction::NewFile),
+                            MenuItem::Button(fl!("file-open"), None, MenuAction::OpenFile),
+                            MenuItem::Divider,
+                            MenuItem::Button(fl!("file-save"), None, MenuAction::Save),
+                            MenuItem::Button(fl!("file-save-as"), None, MenuAction::SaveAs),
+                            MenuItem::Divider,
+                            MenuItem::Button(fl!("file-close"), None, MenuAction::CloseFile),
+                            MenuItem::Button(fl!("file-reopen-closed"), None, MenuAction::ReopenClosedTab),
+                            MenuItem::Button(fl!("file-watch"), None, MenuAction::ToggleWatch),
+                            MenuItem::Button(fl!("file-single-instance"), None, MenuAction::ToggleSingleInstance),
+                            MenuItem::Button(fl!("file-quit"), None, MenuAction::Quit),
+                        ],
+                    ),
+                    (
+                        fl!("menu-edit"),
                         vec![
-                            MenuItem::Button("New", None, MenuAction::NewFile),
-                            MenuItem::Button("Open", None, MenuAction::OpenFile),
+                            MenuItem::Button(fl!("edit-undo"), None, MenuAction::Undo),
+                            MenuItem::Button(fl!("edit-redo"), None, MenuAction::Redo),
+                            MenuItem::Divider,
+                            MenuItem::Button(fl!("edit-cut"), None, MenuAction::Cut),
+                            MenuItem::Button(fl!("edit-copy"), None, MenuAction::Copy),
+                            MenuItem::Button(fl!("edit-copy-html"), None, MenuAction::CopyAsHtml),
+                            MenuItem::Button(fl!("edit-copy-html-source"), None, MenuAction::CopySelectionHtml),
+                            MenuItem::Button(fl!("edit-copy-heading-link"), None, MenuAction::CopyHeadingLink),
+                            MenuItem::Button(fl!("edit-copy-heading-link-path"), None, MenuAction::CopyHeadingLinkWithPath),
+                            MenuItem::Button(fl!("edit-copy-file-path"), None, MenuAction::CopyFilePath),
+                            MenuItem::Button(fl!("edit-copy-relative-path"), None, MenuAction::CopyRelativePath),
+                            MenuItem::Button(fl!("edit-copy-file-name"), None, MenuAction::CopyFileName),
+                            MenuItem::Button(fl!("edit-paste"), None, MenuAction::Paste),
+                            MenuItem::Button(fl!("edit-paste-plain"), None, MenuAction::PastePlain),
+                            MenuItem::Button(fl!("edit-paste-markdown"), None, MenuAction::PasteAsMarkdown),
+                            MenuItem::Button(fl!("edit-paste-image-url"), None, MenuAction::PasteImageFromUrl),
+                            MenuItem::Button(fl!("edit-paste-history"), None, MenuAction::PasteFromHistory),
                             MenuItem::Divider,
-                            MenuItem::Button("Save", None, MenuAction::Save),
-                            MenuItem::Button("Save As...", None, MenuAction::SaveAs),
+                            MenuItem::Button(fl!("edit-select-all"), None, MenuAction::SelectAll),
                             MenuItem::Divider,
-                            MenuItem::Button("Close", None, MenuAction::CloseFile),
-                            MenuItem::Button("Quit", None, MenuAction::Quit),
+                            MenuItem::Button(fl!("edit-sort-lines"), None, MenuAction::SortLines),
+                            MenuItem::Button(fl!("edit-sort-lines-descending"), None, MenuAction::SortLinesDescending),
+                            MenuItem::Button(fl!("edit-sort-lines-case-sensitive"), None, MenuAction::SortLinesCaseSensitive),
+                            MenuItem::Button(fl!("edit-dedupe-lines"), None, MenuAction::DedupeLines),
+                            MenuItem::Button(fl!("edit-join-lines"), None, MenuAction::JoinLines),
+                            MenuItem::Button(fl!("edit-transpose-chars"), None, MenuAction::TransposeChars),
+                            MenuItem::Button(fl!("edit-transpose-words"), None, MenuAction::TransposeWords),
+                            MenuItem::Divider,
+                            MenuItem::Button(fl!("edit-find-replace"), None, MenuAction::FindReplace),
+                            MenuItem::Divider,
+                            MenuItem::Button(fl!("edit-toggle-bookmark"), None, MenuAction::ToggleBookmark),
+                            MenuItem::Button(fl!("edit-next-bookmark"), None, MenuAction::NextBookmark),
+                            MenuItem::Button(fl!("edit-previous-bookmark"), None, MenuAction::PreviousBookmark),
+                            MenuItem::Button(fl!("edit-clear-bookmarks"), None, MenuAction::ClearBookmarks),
+                            MenuItem::Divider,
+                            MenuItem::Button(fl!("edit-toggle-read-only"), None, MenuAction::ToggleReadOnly),
+                            MenuItem::Button(fl!("edit-override-read-only"), None, MenuAction::OverrideReadOnly),
                         ],
                     ),
                     (
-                        "Edit",
+                        fl!("menu-format"),
                         vec![
-                            MenuItem::Button("Undo", None, MenuAction::Undo),
-                            MenuItem::Button("Redo", None, MenuAction::Redo),
+                            MenuItem::Button(fl!("format-bold"), None, MenuAction::Bold),
+                            MenuItem::Button(fl!("format-italic"), None, MenuAction::Italic),
+                            MenuItem::Button(fl!("format-inline-code"), None, MenuAction::InlineCode),
+                            MenuItem::Button(fl!("format-strikethrough"), None, MenuAction::Strikethrough),
                             MenuItem::Divider,
-                            MenuItem::Button("Cut", None, MenuAction::Cut),
-                            MenuItem::Button("Copy", None, MenuAction::Copy),
-                            MenuItem::Button("Paste", None, MenuAction::Paste),
+                            MenuItem::Button(fl!("format-heading-increase"), None, MenuAction::HeadingIncrease),
+                            MenuItem::Button(fl!("format-heading-decrease"), None, MenuAction::HeadingDecrease),
+                            MenuItem::Button(fl!("format-blockquote"), None, MenuAction::ToggleBlockquote),
+                            MenuItem::Button(fl!("format-reflow-paragraph"), None, MenuAction::ReflowParagraph),
                             MenuItem::Divider,
-                            MenuItem::Button("Select All", None, MenuAction::SelectAll),
+                            MenuItem::Button(fl!("format-callout-note"), None, MenuAction::CalloutNote),
+                            MenuItem::Button(fl!("format-callout-tip"), None, MenuAction::CalloutTip),
+                            MenuItem::Button(fl!("format-callout-warning"), None, MenuAction::CalloutWarning),
                             MenuItem::Divider,
-                            MenuItem::Button("Find & Replace", None, MenuAction::FindReplace),
+                            MenuItem::Button(fl!("format-open-link"), None, MenuAction::OpenLink),
+                        ],
+                    ),
+                    (
+                        fl!("menu-insert"),
+                        vec![
+                            MenuItem::Button(fl!("insert-date-time"), None, MenuAction::InsertDateTime),
+                            MenuItem::Button(fl!("insert-date-iso"), None, MenuAction::InsertDateIso),
+                            MenuItem::Button(fl!("insert-date-locale"), None, MenuAction::InsertDateLocale),
                         ],
                     ),
                     (
-                        "View",
+                        fl!("menu-view"),
                         vec![
-                            MenuItem::Button("Toggle Sidebar", None, MenuAction::ToggleSidebar),
-                            MenuItem::Button("Toggle Preview", None, MenuAction::ToggleViewMode),
+                            MenuItem::Button(fl!("view-toggle-sidebar"), None, MenuAction::ToggleSidebar),
+                            MenuItem::Button(fl!("view-swap-sidebar-side"), None, MenuAction::SwapSidebarSide),
+                            MenuItem::Button(fl!("view-expand-all-folders"), None, MenuAction::ExpandAllFolders),
+                            MenuItem::Button(fl!("view-collapse-all-folders"), None, MenuAction::CollapseAllFolders),
+                            MenuItem::Button(fl!("view-toggle-preview"), None, MenuAction::ToggleViewMode),
+                            MenuItem::Button(fl!("view-render-whitespace"), None, MenuAction::ToggleRenderWhitespace),
+                            MenuItem::Button(fl!("view-live-preview"), None, MenuAction::ToggleLivePreview),
+                            MenuItem::Button(fl!("view-sanitize-html"), None, MenuAction::ToggleSanitizeHtml),
+                            MenuItem::Button(fl!("view-wiki-links"), None, MenuAction::ToggleWikiLinks),
+                            MenuItem::Button(fl!("view-browser-preview"), None, MenuAction::StartLivePreview),
+                            MenuItem::Button(fl!("view-stop-browser-preview"), None, MenuAction::StopLivePreview),
+                            MenuItem::Button(fl!("view-show-changes"), None, MenuAction::ShowChanges),
+                            MenuItem::Button(fl!("view-show-problems"), None, MenuAction::ShowProblems),
+                            MenuItem::Divider,
+                            MenuItem::Button(fl!("view-split-vertical"), None, MenuAction::SplitEditorVertical),
+                            MenuItem::Button(fl!("view-split-horizontal"), None, MenuAction::SplitEditorHorizontal),
+                            MenuItem::Button(fl!("view-move-tab"), None, MenuAction::MoveTabToOtherPane),
+                            MenuItem::Button(fl!("view-unsplit"), None, MenuAction::UnsplitEditor),
                             MenuItem::Divider,
-                            MenuItem::Button("Zoom In", None, MenuAction::ZoomIn),
-                            MenuItem::Button("Zoom Out", None, MenuAction::ZoomOut),
-                            MenuItem::Button("Reset Zoom", None, MenuAction::ZoomReset),
+                            MenuItem::Button(fl!("view-zoom-in"), None, MenuAction::ZoomIn),
+                            MenuItem::Button(fl!("view-zoom-out"), None, MenuAction::ZoomOut),
+                            MenuItem::Button(fl!("view-zoom-reset"), None, MenuAction::ZoomReset),
                         ],
                     ),
                     (
-                        "Help",
+                        fl!("menu-help"),
                         vec![
-                            MenuItem::Button("About", None, MenuAction::About),
+                            MenuItem::Button(fl!("help-about"), None, MenuAction::About),
                         ],
                     ),
                 ],
@@ -295,118 +773,2086 @@ impl CosmicNotebook {
         self.set_header_title(title);
     }
 
-    /// Render the main view
-    fn view_main(&self) -> Element<'_, Message> {
-        use cosmic::widget::{container, text, Column};
-        use cosmic::iced::Length;
+    /// Check whether the active document can't be previewed, telling the user if so
+    fn preview_unavailable(&mut self) -> bool {
+        let unavailable = self
+            .state
+            .active_document()
+            .is_some_and(|doc| !doc.is_markdown());
+        if unavailable {
+            self.state.set_status(
+                fl!("status-preview-markdown-only"),
+                crate::state::StatusLevel::Info,
+            );
+        }
+        unavailable
+    }
 
-        // Simple placeholder for Phase 1
-        let content: Element<Message> = if self.state.has_documents() {
-            match self.state.active_document() {
-                Some(doc) => {
-                    let info = format!(
-                        "File: {}\nLines: {}\nCharacters: {}",
-                        doc.display_name,
-                        doc.line_count(),
-                        doc.char_count()
-                    );
-                    text(info).into()
+    /// Search options from the find dialog toggles
+    fn find_options(&self) -> crate::search::FindOptions {
+        crate::search::FindOptions {
+            case_sensitive: self.state.find_case_sensitive,
+            whole_word: self.state.find_whole_word,
+            use_regex: self.state.find_use_regex,
+            wrap_around: true,
+            within: self.find_scope(),
+            ..Default::default()
+        }
+    }
+
+    /// Take the find bar options from the config
+    fn apply_search_config(&mut self) {
+        let search = self.config.search;
+        self.state.find_case_sensitive = search.case_sensitive;
+        self.state.find_whole_word = search.whole_word;
+        self.state.find_use_regex = search.use_regex;
+    }
+
+    /// Remember the find bar options for the next session
+    fn save_find_options(&mut self) {
+        self.config.search.case_sensitive = self.state.find_case_sensitive;
+        self.config.search.whole_word = self.state.find_whole_word;
+        self.config.search.use_regex = self.state.find_use_regex;
+        if let Err(e) = self.config.save() {
+            self.state.set_status(
+                fl!("status-config-save-failed", error = e.to_string()),
+                crate::state::StatusLevel::Error,
+            );
+        }
+    }
+
+    /// Open the find bar, filling in the query
+    ///
+    /// Text selected within a line becomes the query. Otherwise the file's
+    /// last query is filled in, when queries are remembered; a selection
+    /// over several lines is pinned for find in selection instead.
+    fn open_find_bar(&mut self, replace: bool) -> Task<Message> {
+        let was_open = self.state.find_dialog_open;
+        self.state.find_dialog_open = true;
+        self.state.find_replace_open = replace;
+        if was_open {
+            return Task::none();
+        }
+
+        self.pin_find_selection();
+        // Matches are previewed from the start of the selection
+        self.state.find_origin = self
+            .state
+            .active_document()
+            .map(|doc| (doc.id, doc.editor_state.selection.normalized().0));
+        let query = self.state.active_document().and_then(|doc| {
+            match self.editor_contents.get(&doc.id).and_then(|c| c.selection()) {
+                Some(selected) if !selected.contains('\n') => Some(selected),
+                Some(_) => None,
+                None if self.config.search.remember_queries => {
+                    doc.path.as_deref().and_then(|p| self.session.find_query(p)).map(String::from)
                 }
-                None => text("No document selected").into(),
+                None => None,
             }
-        } else {
-            Column::new()
-                .push(text("Welcome to Cosmic Notebook").size(24))
-                .push(text(""))
-                .push(text("Press Ctrl+N to create a new file"))
-                .push(text("Press Ctrl+O to open a file"))
-                .spacing(8)
-                .into()
+        });
+        match query {
+            Some(query) => Task::done(Self::app_message(Message::Search(SearchMessage::UpdateQuery(query)))),
+            None => Task::none(),
+        }
+    }
+
+    /// Move the cursor to the first match from where the search started
+    ///
+    /// Runs as the query is typed, while the search is live. An empty query
+    /// returns the cursor to where the search started.
+    fn preview_find_match(&mut self, query_empty: bool) {
+        let Some((id, origin)) = self.state.find_origin else {
+            return;
         };
+        if Some(id) != self.state.active_document {
+            return;
+        }
+        if query_empty {
+            self.place_cursor(id, origin);
+            return;
+        }
 
-        container(content)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .padding(20)
-            .into()
+        let Some(doc) = self.state.get_document_mut(id) else {
+            return;
+        };
+        let from = line_col_to_char(&doc.content, origin.line, origin.column).unwrap_or(0);
+        let results = &doc.editor_state.find_results;
+        if results.is_empty() {
+            return;
+        }
+        let (index, wrapped) = match results.iter().position(|&(start, _)| start >= from) {
+            Some(index) => (index, false),
+            None => (0, true),
+        };
+        let (start, end) = results[index];
+        doc.editor_state.current_find_index = Some(index);
+        self.jump_to_range(id, start, end);
+        if wrapped {
+            self.state.set_status(fl!("status-search-wrapped"), crate::state::StatusLevel::Info);
+        }
     }
 
-    /// Helper to wrap message in cosmic Action
-    fn app_message(msg: Message) -> cosmic::Action<Message> {
-        cosmic::Action::App(msg)
+    /// Range of the active document find is limited to, when finding in
+    /// the selection
+    fn find_scope(&self) -> Option<std::ops::Range<usize>> {
+        if !self.state.find_in_selection {
+            return None;
+        }
+        let (id, range) = self.state.find_selection.as_ref()?;
+        (Some(*id) == self.state.active_document).then(|| range.clone())
     }
 
-    /// Handle file-related messages
-    fn handle_file_message(&mut self, msg: FileMessage) -> Task<Message> {
-        match msg {
-            FileMessage::New => {
-                let doc = Document::new();
-                let id = doc.id;
-                // Create text_editor::Content for this document
-                self.editor_contents.insert(id, text_editor::Content::new());
-                self.state.add_document(doc);
-                self.update_window_title();
-                Task::none()
+    /// Pin the active selection as the range find in selection searches
+    ///
+    /// Only a selection over several lines is pinned. Selecting matches while navigating moves the editor's selection, so
+    /// the range is taken once, when the find bar opens.
+    fn pin_find_selection(&mut self) {
+        self.state.find_selection = self.state.active_document().and_then(|doc| {
+            let selection = doc.editor_state.selection;
+            if selection.start.line == selection.end.line {
+                return None;
             }
+            let (start, end) = selection.normalized();
+            let start = line_col_to_char(&doc.content, start.line, start.column)?;
+            let end = line_col_to_char(&doc.content, end.line, end.column)?;
+            Some((doc.id, start..end))
+        });
+    }
 
-            FileMessage::Open => {
-                // TODO: Open file picker dialog
-                // For Phase 1, this is a placeholder
-                Task::none()
+    /// Move the end of the pinned selection after an edit inside it
+    fn shift_find_selection(&mut self, id: DocumentId, old_len: usize, new_len: usize) {
+        if let Some((doc_id, range)) = &mut self.state.find_selection {
+            if *doc_id == id {
+                range.end = (range.end + new_len).saturating_sub(old_len).max(range.start);
             }
+        }
+    }
 
-            FileMessage::OpenPath(path) => {
-                // Check if already open
-                if let Some(id) = self.state.find_document_by_path(&path) {
-                    self.state.set_active_document(id);
-                    self.update_window_title();
-                    return Task::none();
-                }
+    /// Insert text at the cursor of the active document, replacing the selection
+    fn insert_into_active(&mut self, text: &str) {
+        if let Some(id) = self.state.active_document {
+            self.insert_into_document(id, text);
+        }
+    }
 
-                // Load file asynchronously
-                Task::perform(
-                    async move {
-                        match std::fs::read_to_string(&path) {
-                            Ok(content) => FileMessage::Loaded { path, content },
-                            Err(e) => FileMessage::LoadError {
-                                path,
-                                error: e.to_string(),
-                            },
-                        }
-                    },
-                    |msg| Self::app_message(Message::File(msg)),
-                )
+    /// Insert text at a document's cursor, replacing its selection, whether
+    /// or not it is the active document
+    fn insert_into_document(&mut self, id: DocumentId, text: &str) {
+        use cosmic::widget::text_editor::{Action, Edit};
+        use std::sync::Arc;
+
+        let Some(content) = self.editor_contents.get_mut(&id) else {
+            return;
+        };
+        content.perform(Action::Edit(Edit::Paste(Arc::new(text.to_string()))));
+
+        let new_text = content.text();
+        if let Some(doc) = self.state.get_document_mut(id) {
+            doc.set_text(&new_text);
+            doc.mark_modified();
+            let title = doc.title();
+            self.state.update_tab_title(id, title);
+        }
+        self.update_window_title();
+        self.autosave_pending = true;
+        self.sync_cursor_state(id);
+    }
+
+    /// Insert a link to `path` where a file dragged from the sidebar was
+    /// dropped on a document's editor
+    ///
+    /// Links are relative to the document's folder, and images are
+    /// embedded rather than linked.
+    fn drop_file_link(&mut self, document_id: DocumentId, point: cosmic::iced::Point, path: &Path) {
+        if Some(document_id) != self.state.active_document {
+            self.state.set_active_document(document_id);
+            self.update_window_title();
+        }
+        if self.edit_blocked(document_id) {
+            return;
+        }
+        let Some(content) = self.editor_contents.get_mut(&document_id) else {
+            return;
+        };
+        content.perform(text_editor::Action::Click(point));
+        self.sync_cursor_state(document_id);
+
+        let document_path = self.state.get_document(document_id).and_then(|doc| doc.path.clone());
+        let link = crate::editor::links::dropped_file_link(path, document_path.as_deref());
+        self.insert_into_active(&link);
+        self.state.set_status(
+            fl!(
+                "status-file-linked",
+                name = path.file_name().unwrap_or_default().to_string_lossy().to_string()
+            ),
+            crate::state::StatusLevel::Info,
+        );
+    }
+
+    /// Copy a document's path, relative path or file name
+    ///
+    /// A document never saved has no path, so only its name is copied.
+    fn copy_document_path(&mut self, id: Option<DocumentId>, kind: crate::message::CopyPathKind) {
+        use crate::message::CopyPathKind;
+
+        let Some(doc) = id.and_then(|id| self.state.get_document(id)) else {
+            return;
+        };
+        let text = match (&doc.path, kind) {
+            (None, CopyPathKind::FileName) => doc.display_name.clone(),
+            (None, _) => {
+                self.state.set_status(
+                    fl!("status-save-for-path"),
+                    crate::state::StatusLevel::Info,
+                );
+                return;
+            }
+            (Some(path), CopyPathKind::Absolute) => path.to_string_lossy().to_string(),
+            (Some(path), CopyPathKind::Relative) => {
+                crate::utils::path::workspace_relative(path, self.state.workspace_root())
+                    .to_string_lossy()
+                    .to_string()
             }
+            (Some(path), CopyPathKind::FileName) => path
+                .file_name()
+                .map_or_else(|| doc.display_name.clone(), |name| name.to_string_lossy().to_string()),
+        };
 
-            FileMessage::Loaded { path, content } => {
-                let doc = Document::from_file(path.clone(), content.clone());
-                let id = doc.id;
-                // Create text_editor::Content with the file content
-                self.editor_contents.insert(id, text_editor::Content::with_text(&content));
-                self.state.add_document(doc);
-                self.session.add_recent_file(path);
-                self.update_window_title();
-                Task::none()
+        match crate::editor::copy_text(&text) {
+            Ok(()) => self.state.set_status(
+                fl!("status-copied-path", path = text.clone()),
+                crate::state::StatusLevel::Info,
+            ),
+            Err(e) => {
+                log::error!("Failed to copy to clipboard: {}", e);
+                self.state.set_status(
+                    fl!("status-copy-failed"),
+                    crate::state::StatusLevel::Error,
+                );
             }
+        }
+    }
 
-            FileMessage::LoadError { path, error } => {
-                log::error!("Failed to load {}: {}", path.display(), error);
+    /// Paste clipboard text with line endings and whitespace normalized
+    fn paste_plain(&mut self) {
+        match crate::editor::paste_text() {
+            Ok(text) => {
+                let text = crate::editor::normalize_plain_text(&text);
+                self.insert_into_active(&text);
+                self.state.set_status(
+                    fl!("status-pasted-plain", count = text.chars().count()),
+                    crate::state::StatusLevel::Info,
+                );
+            }
+            Err(crate::editor::ClipboardError::Empty) => {
                 self.state.set_status(
-                    format!("Failed to open: {}", path.display()),
+                    fl!("status-clipboard-empty"),
+                    crate::state::StatusLevel::Info,
+                );
+            }
+            Err(e) => {
+                log::error!("Failed to paste from clipboard: {}", e);
+                self.state.set_status(
+                    fl!("status-paste-failed"),
                     crate::state::StatusLevel::Error,
                 );
-                Task::none()
             }
+        }
+    }
 
-            FileMessage::Save => {
+    /// Paste a URL as a markdown link
+    ///
+    /// Selected text becomes the link text. With nothing selected, the page
+    /// title is fetched in the background when enabled. Returns `None` when
+    /// the clipboard should be pasted as is.
+    fn smart_paste(&mut self, text: &str) -> Option<Task<Message>> {
+        use crate::editor::links;
+
+        let url = links::pasted_url(text)?.to_string();
+        let document_id = self.state.active_document?;
+        let selection = self
+            .editor_contents
+            .get(&document_id)?
+            .selection()
+            .filter(|s| !s.trim().is_empty() && !s.contains('\n'));
+
+        if let Some(selection) = selection {
+            self.insert_into_active(&links::markdown_link(&selection, &url));
+            self.state.set_status(fl!("status-pasted-link"), crate::state::StatusLevel::Info);
+            return Some(Task::none());
+        }
+
+        if !self.config.editor.smart_paste_fetch_title || url.starts_with("mailto:") {
+            return None;
+        }
+
+        self.state.set_status(
+            fl!("status-fetching-title"),
+            crate::state::StatusLevel::Info,
+        );
+        Some(Task::perform(
+            async move {
+                let fetch_url = url.clone();
+                let title = tokio::task::spawn_blocking(move || {
+                    links::fetch_title(&fetch_url, links::TITLE_FETCH_TIMEOUT)
+                })
+                .await
+                .unwrap_or(None);
+
+                ClipboardMessage::LinkTitleFetched { document_id, url, title }
+            },
+            |msg| Self::app_message(Message::Clipboard(msg)),
+        ))
+    }
+
+    /// Download the image whose URL is on the clipboard
+    ///
+    /// A document without a file has no assets folder, so the URL is
+    /// linked straight away.
+    fn paste_image_from_url(&mut self) -> Task<Message> {
+        use crate::markdown::image::{self, DownloadError};
+
+        let Some(document_id) = self.state.active_document else {
+            return Task::none();
+        };
+        let url = crate::editor::paste_text()
+            .ok()
+            .and_then(|text| crate::editor::links::pasted_url(&text).map(str::to_string));
+        let Some(url) = url else {
+            self.state.set_status(
+                fl!("status-image-url-missing"),
+                crate::state::StatusLevel::Info,
+            );
+            return Task::none();
+        };
+
+        let has_path = self
+            .state
+            .get_document(document_id)
+            .is_some_and(|doc| doc.path.is_some());
+        if !has_path {
+            let reason = crate::markdown::ImageError::NoDocumentPath.to_string();
+            self.insert_remote_image(document_id, &url, reason);
+            return Task::none();
+        }
+
+        self.state.set_status(
+            fl!("status-downloading-image"),
+            crate::state::StatusLevel::Info,
+        );
+        Task::perform(
+            async move {
+                let fetch_url = url.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    image::download_image(&fetch_url, image::DOWNLOAD_TIMEOUT, image::DOWNLOAD_LIMIT)
+                })
+                .await
+                .unwrap_or_else(|e| Err(DownloadError::Failed(e.to_string())));
+
+                ClipboardMessage::ImageDownloaded { document_id, url, result }
+            },
+            |msg| Self::app_message(Message::Clipboard(msg)),
+        )
+    }
+
+    /// Save a downloaded image to the assets folder and link it, falling
+    /// back to linking its URL
+    fn insert_downloaded_image(
+        &mut self,
+        document_id: DocumentId,
+        url: &str,
+        result: Result<Vec<u8>, crate::markdown::DownloadError>,
+    ) {
+        use crate::markdown::{DownloadError, ImageError, ImageHandler};
+
+        let data = match result {
+            Ok(data) => data,
+            Err(DownloadError::NotAnImage(found)) => {
+                self.state.set_status_with_details(
+                    fl!("status-image-url-not-image"),
+                    crate::state::StatusLevel::Error,
+                    found,
+                );
+                return;
+            }
+            Err(DownloadError::Failed(e)) => {
+                log::warn!("Failed to download image {}: {}", url, e);
+                self.insert_remote_image(document_id, url, e);
+                return;
+            }
+        };
+
+        let saved = match self.state.get_document(document_id).and_then(|doc| doc.path.clone()) {
+            Some(path) => ImageHandler::new().handle_pasted_image(&data, &path),
+            None => Err(ImageError::NoDocumentPath),
+        };
+        match saved {
+            Ok(link) => {
+                self.insert_into_document(document_id, &link);
+                self.state.set_status(
+                    fl!("status-image-url-saved"),
+                    crate::state::StatusLevel::Info,
+                );
+            }
+            Err(e) => {
+                log::warn!("Failed to save image {}: {}", url, e);
+                self.insert_remote_image(document_id, url, e.to_string());
+            }
+        }
+    }
+
+    /// Link an image by its URL when it couldn't be saved locally
+    fn insert_remote_image(&mut self, document_id: DocumentId, url: &str, reason: String) {
+        let link = format!("!{}", crate::editor::links::markdown_link("image", url));
+        self.insert_into_document(document_id, &link);
+        self.state.set_status_with_details(
+            fl!("status-image-url-remote"),
+            crate::state::StatusLevel::Warning,
+            reason,
+        );
+    }
+
+    /// Apply a buffer operation to the active selection
+    ///
+    /// The operation runs on a `TextBuffer` copy of the content and returns
+    /// the range to select afterwards. Only the changed text is pasted over
+    /// the old text, so the change stays a single undoable edit.
+    fn apply_buffer_operation(
+        &mut self,
+        operation: impl FnOnce(&mut TextBuffer, std::ops::Range<usize>) -> std::ops::Range<usize>,
+    ) {
+        use cosmic::widget::text_editor::{Action, Edit, Motion};
+        use std::sync::Arc;
+
+        let Some(id) = self.state.active_document else {
+            return;
+        };
+        let Some(content) = self.editor_contents.get_mut(&id) else {
+            return;
+        };
+
+        // Collapse the selection to its start to find where it begins
+        let selected = content.selection().map(|s| s.chars().count()).unwrap_or(0);
+        if selected > 0 {
+            content.perform(Action::Move(Motion::Left));
+        }
+        let start = content_cursor_offset(content);
+
+        let old_text = content.text();
+        let mut buffer = TextBuffer::from_str(&old_text);
+        buffer.set_underscore_in_words(self.config.editor.underscore_in_words);
+        let new_selection = operation(&mut buffer, start..start + selected);
+        let new_text = buffer.rope().to_string();
+
+        if new_text == old_text {
+            select_content_range(content, start, start + selected);
+            return;
+        }
+
+        let (pos, removed, inserted) = locate_edit(&old_text, &new_text, 0);
+        select_content_range(content, pos, pos + removed);
+        let replacement = buffer.slice(pos, pos + inserted);
+        content.perform(Action::Edit(Edit::Paste(Arc::new(replacement))));
+        select_content_range(content, new_selection.start, new_selection.end);
+
+        if let Some(doc) = self.state.get_document_mut(id) {
+            doc.set_text(&new_text);
+            doc.mark_modified();
+            let title = doc.title();
+            self.state.update_tab_title(id, title);
+        }
+        self.update_window_title();
+        self.autosave_pending = true;
+    }
+
+    /// Whether typing a character in a document goes through smart
+    /// typography
+    ///
+    /// Replacing a selection and editing snippet tab-stops are left to the
+    /// editor.
+    fn substitutes_typography(&self, id: DocumentId, typed: char) -> bool {
+        self.config.editor.smart_typography
+            && crate::editor::typography::TRIGGERS.contains(&typed)
+            && Some(id) == self.state.active_document
+            && self
+                .state
+                .get_document(id)
+                .is_some_and(|doc| doc.is_markdown() && doc.editor_state.snippet.is_none())
+            && self
+                .editor_contents
+                .get(&id)
+                .is_some_and(|content| content.selection().map_or(true, |s| s.is_empty()))
+    }
+
+    /// Collect the documents without a file, in tab order
+    fn scratch_documents(&self) -> Vec<crate::state::ScratchDocument> {
+        self.state
+            .all_tabs()
+            .filter_map(|tab| {
+                let doc = self.state.get_document(tab.document_id)?;
+                if doc.path.is_some() {
+                    return None;
+                }
+                let content = self.editor_contents.get(&doc.id)?;
+                let rope = ropey::Rope::from_str(&content.text());
+                let offset = content_cursor_offset(content);
+                let line = rope.char_to_line(offset);
+                Some(crate::state::ScratchDocument {
+                    id: doc.id,
+                    display_name: doc.display_name.clone(),
+                    content: rope.to_string(),
+                    cursor: crate::state::CursorPosition::new(
+                        line,
+                        offset - rope.line_to_char(line),
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// Open a file read from disk in a new tab
+    ///
+    /// Returns the new document and the tasks that fill in its git status
+    /// and link checks.
+    fn open_loaded(&mut self, path: PathBuf, content: String) -> (DocumentId, Task<Message>) {
+        let mut doc = Document::from_file(path.clone(), content.clone());
+        let info = crate::file_handler::FileInfo::from_path(&path);
+        doc.read_only = info.is_readonly;
+        doc.last_disk_mtime = info.modified;
+        doc.view_mode = self.config.view.mode_for(&path);
+        if self.config.editor.detect_indentation {
+            doc.indent = crate::editor::indent::detect(&content);
+        }
+        let line_count = doc.content.len_lines();
+        doc.editor_state.bookmarks = self
+            .session
+            .bookmarks(&path)
+            .iter()
+            .copied()
+            .filter(|&line| line < line_count)
+            .collect();
+        let id = doc.id;
+        // Create text_editor::Content with the file content
+        self.editor_contents.insert(id, text_editor::Content::with_text(&content));
+        self.state.add_document(doc);
+        if self.pending_anchor.as_ref().is_some_and(|(p, _)| *p == path) {
+            if let Some((_, anchor)) = self.pending_anchor.take() {
+                self.jump_to_anchor(id, &anchor);
+            }
+        }
+        self.session.add_recent_file(path);
+        self.update_window_title();
+        self.run_linter(id);
+        self.refresh_minimap(id);
+        let tasks = [self.git_status_task(id), self.link_check_task(id), self.diagram_task(id)];
+        (id, Task::batch(tasks))
+    }
+
+    /// Store a document's bookmarks in the session under its file
+    fn remember_bookmarks(&mut self, id: DocumentId) {
+        let Some(doc) = self.state.get_document(id) else {
+            return;
+        };
+        if let Some(path) = doc.path.clone() {
+            let lines = doc.editor_state.bookmarks.iter().copied();
+            self.session.set_bookmarks(path, lines);
+        }
+    }
+
+    /// Move the cursor to the next or previous bookmark of the active
+    /// document, wrapping around at its ends
+    fn jump_to_bookmark(&mut self, forward: bool) {
+        let Some(doc) = self.state.active_document() else {
+            return;
+        };
+        let line = doc.editor_state.cursor.line;
+        let target = if forward {
+            doc.editor_state.next_bookmark(line)
+        } else {
+            doc.editor_state.prev_bookmark(line)
+        };
+        let Some(target) = target.filter(|&t| t < doc.content.len_lines()) else {
+            self.state.set_status(fl!("status-no-bookmarks"), crate::state::StatusLevel::Info);
+            return;
+        };
+        let (id, offset) = (doc.id, doc.content.line_to_char(target));
+        self.jump_to_range(id, offset, offset);
+        self.sync_cursor_state(id);
+    }
+
+    /// Record a tab about to close so it can be reopened
+    ///
+    /// Untouched empty scratch documents aren't worth reopening.
+    fn remember_closed(&mut self, id: DocumentId) {
+        let (Some(doc), Some(content)) = (self.state.get_document(id), self.editor_contents.get(&id))
+        else {
+            return;
+        };
+        let text = content.text();
+        if doc.path.is_none() && text.trim().is_empty() {
+            return;
+        }
+
+        let rope = ropey::Rope::from_str(&text);
+        let offset = content_cursor_offset(content).min(rope.len_chars());
+        let line = rope.char_to_line(offset);
+        let column = offset - rope.line_to_char(line);
+        let unsaved_content = (doc.path.is_none() || doc.modified).then_some(text);
+        let closed = crate::state::ClosedDocument {
+            path: doc.path.clone(),
+            display_name: doc.display_name.clone(),
+            unsaved_content,
+            cursor: crate::state::CursorPosition::new(line, column),
+        };
+        self.state.remember_closed(closed);
+    }
+
+    /// Reopen a closed tab from its unsaved content
+    fn reopen_unsaved(&mut self, closed: crate::state::ClosedDocument) {
+        let text = closed.unsaved_content.unwrap_or_default();
+        let mut doc = match closed.path {
+            Some(path) => {
+                let mut doc = Document::from_file(path.clone(), text.clone());
+                let info = crate::file_handler::FileInfo::from_path(&path);
+                doc.read_only = info.is_readonly;
+                doc.last_disk_mtime = info.modified;
+                doc.view_mode = self.config.view.mode_for(&path);
+                if self.config.editor.detect_indentation {
+                    doc.indent = crate::editor::indent::detect(&text);
+                }
+                doc
+            }
+            None => {
+                let mut doc = Document::new();
+                doc.display_name = closed.display_name;
+                doc.content = ropey::Rope::from_str(&text);
+                doc
+            }
+        };
+        doc.mark_modified();
+        let id = doc.id;
+        self.editor_contents.insert(id, text_editor::Content::with_text(&text));
+        self.state.add_document(doc);
+        self.place_cursor(id, closed.cursor);
+        self.update_window_title();
+        self.run_linter(id);
+        self.refresh_minimap(id);
+    }
+
+    /// Put a document's cursor at a line and column, clamped to its text
+    fn place_cursor(&mut self, id: DocumentId, cursor: crate::state::CursorPosition) {
+        let Some(content) = self.editor_contents.get_mut(&id) else {
+            return;
+        };
+        let rope = ropey::Rope::from_str(&content.text());
+        let line = cursor.line.min(rope.len_lines().saturating_sub(1));
+        let offset = line_col_to_char(&rope, line, cursor.column).unwrap_or(0);
+        select_content_range(content, offset, offset);
+        self.sync_cursor_state(id);
+    }
+
+    /// Compile the autosave exclude globs, warning about malformed ones
+    fn build_autosave_exclude(&mut self) {
+        let (set, invalid) = crate::utils::path::glob_set(&self.config.files.autosave_exclude);
+        self.autosave_exclude = set;
+        if !invalid.is_empty() {
+            self.state.set_status(
+                fl!("status-invalid-autosave-globs", globs = invalid.join(", ")),
+                crate::state::StatusLevel::Warning,
+            );
+        }
+    }
+
+    /// Apply the sidebar side and resize bounds from the config
+    fn apply_sidebar_config(&mut self) {
+        let ui = &self.config.ui;
+        let limits = SidebarLimits {
+            min_width: ui.sidebar_min_width,
+            max_width: ui.sidebar_max_width,
+            default_width: ui.sidebar_width,
+            collapse_on_drag: ui.sidebar_collapse_on_drag,
+        };
+        self.state.sidebar.set_limits(limits);
+        self.state.sidebar.position = self.config.view.sidebar_position;
+    }
+
+    /// Keep the sidebar's width and visibility for the next session
+    fn remember_sidebar(&mut self) {
+        self.session.sidebar_visible = self.state.sidebar.visible;
+        self.session.sidebar_width = self.state.sidebar.width;
+    }
+
+    /// Scan the sidebar's root folder again
+    ///
+    /// Folders below it that are expanded are listed again once the root
+    /// is.
+    fn rescan_sidebar(&mut self) -> Task<Message> {
+        let Some(root) = self.state.sidebar.root.clone() else {
+            return Task::none();
+        };
+        // Filters other than markdown-only narrow a scan of every file
+        self.state.sidebar.scanned_all_files = self.state.sidebar.filter.needs_all_files();
+        self.state.sidebar.is_scanning = true;
+        Task::batch([self.list_directories(vec![root]), self.scan_notes()])
+    }
+
+    /// Scan the open folder for the notes wiki links resolve against
+    ///
+    /// The sidebar lists folders as they are expanded, so it doesn't know
+    /// every note.
+    fn scan_notes(&self) -> Task<Message> {
+        let Some(root) = self.state.sidebar.root.clone() else {
+            return Task::none();
+        };
+        if !self.config.markdown.wiki_links {
+            return Task::none();
+        }
+        let config = crate::file_handler::ScanConfig {
+            include_directories: false,
+            ..crate::file_handler::ScanConfig::markdown_only()
+        };
+        Task::perform(
+            async move {
+                let scan = crate::file_handler::scan_directory(&root, &config);
+                let notes = scan.entries.into_iter().map(|entry| entry.path).collect();
+                (root, notes)
+            },
+            |(root, notes)| {
+                Self::app_message(Message::Internal(InternalMessage::NotesScanned { root, notes }))
+            },
+        )
+    }
+
+    /// How sidebar folders are scanned
+    fn sidebar_scan_config(&self) -> crate::file_handler::ScanConfig {
+        let mut config = crate::file_handler::ScanConfig::default();
+        if self.state.sidebar.scanned_all_files {
+            config.include_extensions.clear();
+        }
+        config
+    }
+
+    /// List the children of sidebar folders, skipping those already being
+    /// listed
+    fn list_directories(&mut self, dirs: Vec<PathBuf>) -> Task<Message> {
+        let dirs = self.state.sidebar.start_listing(dirs);
+        if dirs.is_empty() {
+            return Task::none();
+        }
+        let config = self.sidebar_scan_config();
+        Task::batch(dirs.into_iter().map(|dir| {
+            let config = config.clone();
+            Task::perform(
+                async move { crate::file_handler::list_directory(dir, &config) },
+                |listing| Self::app_message(Message::Internal(InternalMessage::DirectoryListed(listing))),
+            )
+        }))
+    }
+
+    /// List the whole sidebar tree and expand every folder
+    ///
+    /// Stops after `MAX_EXPAND_ALL_DIRS` folders, or when cancelled.
+    fn expand_all_folders(&mut self) -> Task<Message> {
+        let Some(root) = self.state.sidebar.root.clone() else {
+            return Task::none();
+        };
+        self.state.sidebar.cancel_expand_all();
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.state.sidebar.expand_all = Some(cancel.clone());
+        let config = self.sidebar_scan_config();
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    crate::file_handler::list_tree(root, &config, MAX_EXPAND_ALL_DIRS, &cancel)
+                })
+                .await
+                .unwrap_or_else(|_| (Vec::new(), true))
+            },
+            |(listings, stopped)| {
+                Self::app_message(Message::Internal(InternalMessage::TreeListed { listings, stopped }))
+            },
+        )
+    }
+
+    /// Open text in a new untitled document
+    ///
+    /// Non-empty text counts as unsaved, so closing the document asks before
+    /// discarding it.
+    fn open_untitled(&mut self, text: &str) {
+        let mut doc = Document::new();
+        doc.content = ropey::Rope::from_str(text);
+        if !text.is_empty() {
+            doc.mark_modified();
+        }
+        let id = doc.id;
+        self.editor_contents.insert(id, text_editor::Content::with_text(text));
+        self.state.add_document(doc);
+        self.update_window_title();
+        self.run_linter(id);
+        self.refresh_minimap(id);
+    }
+
+    /// Reopen the scratch documents saved in the session
+    fn restore_scratch_documents(&mut self) {
+        for scratch in self.session.scratch_documents.clone() {
+            let mut doc = Document::new();
+            doc.id = scratch.id;
+            doc.display_name = scratch.display_name;
+            doc.content = ropey::Rope::from_str(&scratch.content);
+            doc.mark_modified();
+
+            let mut content = text_editor::Content::with_text(&scratch.content);
+            let offset =
+                line_col_to_char(&doc.content, scratch.cursor.line, scratch.cursor.column)
+                    .unwrap_or(0);
+            select_content_range(&mut content, offset, offset);
+
+            self.editor_contents.insert(doc.id, content);
+            self.state.add_document(doc);
+            self.update_window_title();
+        }
+    }
+
+    /// Open the link under the cursor of the active document
+    ///
+    /// URLs go to the default handler; relative files open in a tab,
+    /// jumping to the heading anchor if the link has one.
+    fn open_link_at_cursor(&mut self) -> Task<Message> {
+        use crate::editor::LinkTarget;
+
+        let Some(id) = self.state.active_document else {
+            return Task::none();
+        };
+        let Some(content) = self.editor_contents.get(&id) else {
+            return Task::none();
+        };
+
+        let rope = ropey::Rope::from_str(&content.text());
+        let offset = content_cursor_offset(content);
+        let line = rope.char_to_line(offset);
+        let column = offset - rope.line_to_char(line);
+        let line_text = rope.line(line).to_string();
+
+        let Some(target) = crate::editor::links::link_at(line_text.trim_end_matches('\n'), column)
+        else {
+            self.state.set_status(fl!("status-no-link"), crate::state::StatusLevel::Info);
+            return Task::none();
+        };
+
+        match target {
+            LinkTarget::Url(url) => {
+                Task::done(Self::app_message(Message::System(SystemMessage::OpenUrl(url))))
+            }
+            LinkTarget::Anchor(anchor) => {
+                self.jump_to_anchor(id, &anchor);
+                Task::none()
+            }
+            LinkTarget::File { path, anchor } => {
+                let base = self
+                    .state
+                    .get_document(id)
+                    .and_then(|doc| doc.path.as_ref())
+                    .and_then(|p| p.parent().map(PathBuf::from))
+                    .or_else(|| self.state.sidebar.root.clone())
+                    .unwrap_or_default();
+                self.open_linked_file(base.join(path), anchor)
+            }
+            LinkTarget::Note(link) => {
+                if !self.config.markdown.wiki_links {
+                    self.state.set_status(fl!("status-no-link"), crate::state::StatusLevel::Info);
+                    return Task::none();
+                }
+                let dir = self
+                    .state
+                    .get_document(id)
+                    .and_then(|doc| doc.path.as_deref())
+                    .and_then(Path::parent)
+                    .map(Path::to_path_buf);
+                if let Some(path) = self.notes.resolve(&link.target, dir.as_deref()) {
+                    return self.open_linked_file(path, link.anchor());
+                }
+
+                // Saving the new note creates the file
+                let path = self.notes.new_note_path(&link.target, dir.as_deref());
+                if self.state.find_document_by_path(&path).is_some() {
+                    return self.open_linked_file(path, None);
+                }
+                let name = link.target.rsplit('/').next().unwrap_or(&link.target).trim().to_string();
+                let (new_id, task) = self.open_loaded(path, format!("# {}\n", name));
+                if let Some(doc) = self.state.get_document_mut(new_id) {
+                    doc.mark_modified();
+                }
+                self.state.set_status(
+                    fl!("status-note-created", name = name),
+                    crate::state::StatusLevel::Info,
+                );
+                task
+            }
+        }
+    }
+
+    /// Open a file a link points to, jumping to a heading anchor once it
+    /// has loaded
+    fn open_linked_file(&mut self, path: PathBuf, anchor: Option<String>) -> Task<Message> {
+        if let Some(existing) = self.state.find_document_by_path(&path) {
+            self.state.set_active_document(existing);
+            self.update_window_title();
+            if let Some(anchor) = anchor {
+                self.jump_to_anchor(existing, &anchor);
+            }
+            return Task::none();
+        }
+
+        self.pending_anchor = anchor.map(|anchor| (path.clone(), anchor));
+        Task::done(Self::app_message(Message::File(FileMessage::OpenPath(path))))
+    }
+
+    /// Move the cursor of a document to the heading with the given anchor
+    fn jump_to_anchor(&mut self, document_id: DocumentId, anchor: &str) {
+        use crate::markdown::export::AnchorSet;
+        use crate::markdown::MarkdownTokenizer;
+
+        let Some(content) = self.editor_contents.get_mut(&document_id) else {
+            return;
+        };
+
+        let anchor = anchor.to_lowercase();
+        let text = content.text();
+        let mut anchors = AnchorSet::default();
+        let mut offset = 0;
+        for line in text.split('\n') {
+            let trimmed = line.trim_start();
+            if let Some((_, content_start)) = MarkdownTokenizer::parse_heading(trimmed) {
+                if anchors.unique(trimmed[content_start..].trim()) == anchor {
+                    self.jump_to_range(document_id, offset, offset);
+                    return;
+                }
+            }
+            offset += line.chars().count() + 1;
+        }
+
+        self.state.set_status(
+            fl!("status-heading-not-found", anchor = anchor),
+            crate::state::StatusLevel::Warning,
+        );
+    }
+
+    /// Copy the editor's cursor and selection into the document's editor state
+    ///
+    /// The text editor only reports the cursor and the selected text, so the
+    /// anchor is placed on whichever side of the cursor the text matches.
+    fn sync_cursor_state(&mut self, document_id: DocumentId) {
+        use crate::state::{CursorPosition, Selection};
+
+        let Some(content) = self.editor_contents.get(&document_id) else {
+            return;
+        };
+        let rope = ropey::Rope::from_str(&content.text());
+        let cursor = content_cursor_offset(content);
+        let anchor = match content.selection() {
+            Some(selected) => {
+                let len = selected.chars().count();
+                if cursor >= len && rope.slice(cursor - len..cursor) == selected.as_str() {
+                    cursor - len
+                } else {
+                    (cursor + len).min(rope.len_chars())
+                }
+            }
+            None => cursor,
+        };
+        let selection = Selection::new(
+            CursorPosition::from_char_offset(&rope, anchor),
+            CursorPosition::from_char_offset(&rope, cursor),
+        );
+
+        let viewport_lines = self.state.editor_viewport_lines();
+        if let Some(doc) = self.state.get_document_mut(document_id) {
+            // The editor scrolls to keep a moved cursor in view
+            let moved = doc.editor_state.cursor != selection.end;
+            doc.editor_state.set_selection(selection);
+            if moved {
+                doc.editor_state.follow_cursor(viewport_lines);
+            }
+        }
+        self.refresh_code_block(document_id, selection.end.line);
+    }
+
+    /// Move the cursor of a document's editor by a character or a word
+    ///
+    /// Character steps follow the visual order of bidirectional text, so
+    /// the cursor moves the way the arrow points on screen, and word steps
+    /// use Unicode word boundaries. A selection collapses the way the text
+    /// editor collapses it.
+    fn move_cursor(&mut self, document_id: DocumentId, motion: CursorMotion, select: bool) {
+        use crate::editor::{CursorController, TextBuffer};
+        use crate::state::CursorPosition;
+
+        let Some(content) = self.editor_contents.get_mut(&document_id) else {
+            return;
+        };
+        if !select && content.selection().is_some() {
+            let collapse = match motion {
+                CursorMotion::Left | CursorMotion::WordLeft => text_editor::Motion::Left,
+                CursorMotion::Right | CursorMotion::WordRight => text_editor::Motion::Right,
+            };
+            content.perform(text_editor::Action::Move(collapse));
+            if matches!(motion, CursorMotion::Left | CursorMotion::Right) {
+                self.sync_cursor_state(document_id);
+                return;
+            }
+        }
+
+        let mut buffer = TextBuffer::from_str(&content.text());
+        buffer.set_underscore_in_words(self.config.editor.underscore_in_words);
+        let offset = content_cursor_offset(content);
+        let (line, column) = buffer.char_to_line_col(offset);
+        let pos = CursorPosition::new(line, column);
+        let target = match motion {
+            CursorMotion::Left => CursorController::move_left(&buffer, pos),
+            CursorMotion::Right => CursorController::move_right(&buffer, pos),
+            CursorMotion::WordLeft => CursorController::move_word_left(&buffer, pos),
+            CursorMotion::WordRight => CursorController::move_word_right(&buffer, pos),
+        };
+        let target = buffer.line_col_to_char(target.line, target.column).unwrap_or(offset);
+        move_content_cursor(content, buffer.rope(), offset, target, select);
+        self.sync_cursor_state(document_id);
+    }
+
+    /// Select the word at the cursor of a document's editor, by Unicode
+    /// word boundaries
+    ///
+    /// Returns false when the cursor isn't on or just after a word.
+    fn select_word_at_cursor(&mut self, document_id: DocumentId) -> bool {
+        use crate::editor::TextBuffer;
+
+        let Some(content) = self.editor_contents.get_mut(&document_id) else {
+            return false;
+        };
+        let mut buffer = TextBuffer::from_str(&content.text());
+        buffer.set_underscore_in_words(self.config.editor.underscore_in_words);
+        let offset = content_cursor_offset(content);
+        let word = buffer
+            .word_at(offset)
+            .or_else(|| offset.checked_sub(1).and_then(|before| buffer.word_at(before)));
+        let Some((start, end)) = word else {
+            return false;
+        };
+
+        if content.selection().is_some() {
+            content.perform(text_editor::Action::Move(text_editor::Motion::Left));
+        }
+        let from = content_cursor_offset(content);
+        move_content_cursor(content, buffer.rope(), from, start, false);
+        move_content_cursor(content, buffer.rope(), start, end, true);
+        self.sync_cursor_state(document_id);
+        true
+    }
+
+    /// Update the code block language shown for the cursor line
+    fn refresh_code_block(&mut self, document_id: DocumentId, line: usize) {
+        if let Some(doc) = self.state.get_document_mut(document_id) {
+            doc.editor_state.code_block_language =
+                crate::editor::formatting::code_fence_at(&doc.content, line).map(|f| f.language);
+        }
+    }
+
+    /// Move the cursor to a character range, scrolling it into view
+    ///
+    /// Long jumps are animated when smooth scrolling is enabled.
+    fn jump_to_range(&mut self, document_id: DocumentId, start: usize, end: usize) {
+        let viewport_lines = self.state.editor_viewport_lines();
+        let scroll_off = self
+            .config
+            .editor
+            .scroll_off
+            .min(viewport_lines.saturating_sub(1) / 2);
+        let Some(content) = self.editor_contents.get_mut(&document_id) else {
+            return;
+        };
+
+        let rope = ropey::Rope::from_str(&content.text());
+        let start = start.min(rope.len_chars());
+        let end = end.clamp(start, rope.len_chars());
+        let target_line = rope.char_to_line(start);
+        let (line, _) = content.cursor_position();
+        let downward = target_line >= line;
+
+        if self.config.editor.smooth_scroll && line.abs_diff(target_line) > viewport_lines / 2 {
+            self.scroll_animation = Some(ScrollAnimation {
+                document_id,
+                target: (start, end),
+                target_line,
+                downward,
+                ticks_left: SCROLL_ANIMATION_TICKS,
+            });
+            return;
+        }
+
+        self.scroll_animation = None;
+        reveal_content_range(content, (start, end), downward, scroll_off);
+        self.sync_cursor_state(document_id);
+    }
+
+    /// Move an animated jump one step closer to its target
+    ///
+    /// Each step covers a quarter of the remaining distance, so the scroll
+    /// eases out as it approaches the target.
+    fn step_scroll_animation(&mut self) {
+        use cosmic::widget::text_editor::{Action, Motion};
+
+        let scroll_off = self
+            .config
+            .editor
+            .scroll_off
+            .min(self.state.editor_viewport_lines().saturating_sub(1) / 2);
+        let Some(animation) = self.scroll_animation.as_mut() else {
+            return;
+        };
+        let Some(content) = self.editor_contents.get_mut(&animation.document_id) else {
+            self.scroll_animation = None;
+            return;
+        };
+
+        let (line, _) = content.cursor_position();
+        let remaining = line.abs_diff(animation.target_line);
+        animation.ticks_left = animation.ticks_left.saturating_sub(1);
+        if remaining <= 1 || animation.ticks_left == 0 {
+            reveal_content_range(content, animation.target, animation.downward, scroll_off);
+            let document_id = animation.document_id;
+            self.scroll_animation = None;
+            self.sync_cursor_state(document_id);
+            return;
+        }
+
+        if content.selection().is_some() {
+            content.perform(Action::Move(Motion::Left));
+        }
+        let motion = if animation.target_line > line { Motion::Down } else { Motion::Up };
+        for _ in 0..(remaining / 4).max(1) {
+            content.perform(Action::Move(motion));
+        }
+    }
+
+    /// Handle Tab in the editor: cycle snippet tab-stops, expand a snippet
+    /// trigger before the cursor, or insert a tab
+    fn handle_tab_key(&mut self, document_id: DocumentId, backwards: bool) -> Task<Message> {
+        use cosmic::widget::text_editor::{Action, Edit};
+        use std::sync::Arc;
+
+        let Some(content) = self.editor_contents.get_mut(&document_id) else {
+            return Task::none();
+        };
+        let Some(doc) = self.state.documents.get_mut(&document_id) else {
+            return Task::none();
+        };
+
+        // Cycle through the stops of the active snippet
+        if let Some(session) = doc.editor_state.snippet.as_mut() {
+            let (start, end) = session.advance(backwards);
+            if session.is_finished() {
+                doc.editor_state.snippet = None;
+            }
+            select_content_range(content, start, end);
+            return Task::none();
+        }
+
+        if backwards {
+            self.outdent_active();
+            return Task::none();
+        }
+        if content.selection().is_some_and(|s| s.contains('\n')) {
+            self.indent_active();
+            return Task::none();
+        }
+
+        let cursor = content_cursor_offset(content).min(doc.content.len_chars());
+        let line_start = doc.content.line_to_char(doc.content.char_to_line(cursor));
+        let before = doc.content.slice(line_start..cursor).to_string();
+
+        match self.snippets.find_before(&before) {
+            Some(snippet) => {
+                let indent: String = before.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+                let (body, stops) = snippet.expand(&indent);
+                let trigger_len = snippet.trigger.chars().count();
+
+                // Replace the trigger with the snippet body
+                for _ in 0..trigger_len {
+                    content.perform(Action::Edit(Edit::Backspace));
+                }
+                content.perform(Action::Edit(Edit::Paste(Arc::new(body))));
+
+                let session = SnippetSession::new(cursor - trigger_len, &stops);
+                let (start, end) = session.current_stop();
+                select_content_range(content, start, end);
+                doc.editor_state.snippet = (!session.is_finished()).then_some(session);
+            }
+            None => {
+                self.indent_active();
+                return Task::none();
+            }
+        }
+
+        doc.set_text(&content.text());
+        doc.mark_modified();
+        let title = doc.title();
+        self.state.update_tab_title(document_id, title);
+        self.update_window_title();
+        self.autosave_pending = true;
+        Task::none()
+    }
+
+    /// Indent the selected lines, or insert an indent at the cursor
+    ///
+    /// Only a selection spanning several lines indents whole lines; otherwise
+    /// the selection is replaced, matching common editor behavior.
+    fn indent_active(&mut self) {
+        use cosmic::widget::text_editor::{Action, Edit};
+
+        let Some(id) = self.state.active_document else {
+            return;
+        };
+        let (use_spaces, tab_width) = self.indent_style(id);
+        let Some(content) = self.editor_contents.get_mut(&id) else {
+            return;
+        };
+
+        if content.selection().is_some_and(|s| s.contains('\n')) {
+            let unit = if use_spaces { " ".repeat(tab_width) } else { "\t".to_string() };
+            self.apply_buffer_operation(|buffer, range| buffer.indent_lines(range, &unit));
+            return;
+        }
+        if !use_spaces {
+            self.insert_into_active("\t");
+            return;
+        }
+
+        // Pad with spaces to the next tab stop
+        if content.selection().is_some() {
+            content.perform(Action::Edit(Edit::Delete));
+        }
+        let (line, column) = content.cursor_position();
+        let rope = ropey::Rope::from_str(&content.text());
+        let line_text = if line < rope.len_lines() { rope.line(line).to_string() } else { String::new() };
+        let before = line_text.get(..column.min(line_text.len())).unwrap_or("");
+        let visual = before.chars().fold(0, |col, c| match c {
+            '\t' => col + tab_width - col % tab_width,
+            _ => col + 1,
+        });
+        self.insert_into_active(&" ".repeat(tab_width - visual % tab_width));
+    }
+
+    /// Remove up to one indent level from the selected lines
+    fn outdent_active(&mut self) {
+        let Some(id) = self.state.active_document else {
+            return;
+        };
+        let (_, tab_width) = self.indent_style(id);
+        self.apply_buffer_operation(|buffer, range| buffer.outdent_lines(range, tab_width));
+    }
+
+    /// Whether a document is indented with spaces, and the width of a
+    /// level, from its detected indentation or the configured default
+    fn indent_style(&self, id: DocumentId) -> (bool, usize) {
+        let editor = &self.config.editor;
+        let default_width = usize::from(editor.tab_width).max(1);
+        self.state
+            .get_document(id)
+            .map_or(crate::editor::indent::IndentStyle::Undetected, |doc| doc.indent)
+            .resolve(editor.use_spaces, default_width)
+    }
+
+    /// Check whether a document's read-only lock blocks editing it
+    ///
+    /// The first blocked edit after the lock is set shows a status message,
+    /// so keystrokes are not silently swallowed.
+    fn edit_blocked(&mut self, document_id: DocumentId) -> bool {
+        let watching = self.state.watch_mode;
+        let Some(doc) = self.state.get_document_mut(document_id) else {
+            return false;
+        };
+        // Files follow their version on disk in watch mode
+        let watched = watching && doc.path.is_some();
+        if !doc.read_only && !watched {
+            return false;
+        }
+
+        if !doc.read_only_notified {
+            doc.read_only_notified = true;
+            let name = doc.display_name.clone();
+            let message = if doc.read_only {
+                fl!("status-document-read-only", name = name)
+            } else {
+                fl!("status-watched-read-only", name = name)
+            };
+            self.state.set_status(message, crate::state::StatusLevel::Warning);
+        }
+        true
+    }
+
+    /// Turn watch mode on or off
+    ///
+    /// While on, open files reload whenever they change on disk, always
+    /// taking the version on disk, and can't be edited.
+    fn set_watch_mode(&mut self, enabled: bool) {
+        self.watcher = None;
+        if enabled {
+            match crate::file_handler::FileWatcher::new(crate::file_handler::WatcherConfig {
+                recursive: false,
+                ..crate::file_handler::WatcherConfig::watch_all()
+            }) {
+                Ok(watcher) => self.watcher = Some(watcher),
+                Err(e) => {
+                    self.state.set_status_with_details(
+                        fl!("status-watch-failed"),
+                        crate::state::StatusLevel::Error,
+                        e.to_string(),
+                    );
+                    return;
+                }
+            }
+        }
+
+        self.state.watch_mode = enabled;
+        // Warn about blocked edits again under the new mode
+        for doc in self.state.documents.values_mut() {
+            doc.read_only_notified = false;
+        }
+        let message = if enabled {
+            fl!("status-watch-enabled")
+        } else {
+            fl!("status-watch-disabled")
+        };
+        self.state.set_status(message, crate::state::StatusLevel::Info);
+    }
+
+    /// Reload files changed on disk since the last check, in watch mode
+    ///
+    /// Also follows tabs opened and closed since the last check, watching
+    /// the folders of the files now open. Editors that save by replacing the
+    /// file only touch its folder, so folders are watched rather than files,
+    /// and any event has every open file's modification time checked.
+    fn check_watched_files(&mut self) -> Task<Message> {
+        let Some(watcher) = self.watcher.as_mut() else {
+            return Task::none();
+        };
+
+        let dirs: std::collections::HashSet<PathBuf> = self
+            .state
+            .documents
+            .values()
+            .filter_map(|doc| doc.path.as_ref()?.parent().map(|dir| dir.to_path_buf()))
+            .collect();
+        let stale: Vec<PathBuf> = watcher.watched_paths().difference(&dirs).cloned().collect();
+        for dir in stale {
+            if let Err(e) = watcher.unwatch(&dir) {
+                log::debug!("Failed to stop watching {}: {}", dir.display(), e);
+            }
+        }
+        for dir in &dirs {
+            if let Err(e) = watcher.watch(dir) {
+                log::debug!("Failed to watch {}: {}", dir.display(), e);
+            }
+        }
+
+        if watcher.poll().is_empty() {
+            return Task::none();
+        }
+
+        let (dirty, changed): (Vec<&Document>, Vec<&Document>) = self
+            .state
+            .documents
+            .values()
+            .filter(|doc| {
+                doc.path.as_ref().is_some_and(|path| {
+                    let info = crate::file_handler::FileInfo::from_path(path);
+                    info.exists && info.modified != doc.last_disk_mtime
+                })
+            })
+            .partition(|doc| doc.modified);
+        let changed: Vec<DocumentId> = changed.iter().map(|doc| doc.id).collect();
+        // Reloading would throw away unsaved edits, so those files are left
+        // alone, as autosave leaves files changed on disk
+        if !dirty.is_empty() {
+            let names: Vec<String> = dirty.iter().map(|doc| doc.display_name.clone()).collect();
+            self.state.set_status(
+                fl!("status-not-reloaded", names = names.join(", ")),
+                crate::state::StatusLevel::Warning,
+            );
+        }
+        Task::batch(
+            changed
+                .into_iter()
+                .map(|id| self.handle_file_message(FileMessage::Reload(id))),
+        )
+    }
+
+    /// Replace a document's text with its file's
+    ///
+    /// The cursor and scroll position stay where they were, as far as the
+    /// new text allows. A document edited while the file was read keeps
+    /// its edits.
+    fn apply_reload(&mut self, id: DocumentId, content: String) -> Task<Message> {
+        let Some(doc) = self.state.get_document_mut(id) else {
+            return Task::none();
+        };
+        let Some(path) = doc.path.clone() else {
+            return Task::none();
+        };
+        if doc.modified {
+            let name = doc.display_name.clone();
+            self.state.set_status(
+                fl!("status-not-reloaded", names = name),
+                crate::state::StatusLevel::Warning,
+            );
+            return Task::none();
+        }
+        let cursor = doc.editor_state.cursor;
+        let top = doc.editor_state.scroll_line;
+
+        doc.set_text(&content);
+        doc.last_disk_mtime = crate::file_handler::FileInfo::from_path(&path).modified;
+        doc.mark_saved();
+        doc.saved_changes.clear();
+        // The new editor content starts at the top
+        doc.editor_state.scroll_line = 0;
+        self.editor_contents.insert(id, text_editor::Content::with_text(&content));
+
+        self.place_cursor(id, cursor);
+        self.scroll_editor_to(id, top);
+        self.run_linter(id);
+        self.refresh_minimap(id);
+        Task::batch([self.git_status_task(id), self.link_check_task(id), self.diagram_task(id)])
+    }
+
+    /// Diff a document against its saved version on disk
+    ///
+    /// Reading the saved file and diffing large documents happen on a
+    /// background task; a document without a file diffs as all new.
+    fn diff_task(&mut self, document_id: DocumentId) -> Task<Message> {
+        use crate::editor::diff::{line_diff, BACKGROUND_DIFF_LINES};
+
+        let Some(doc) = self.state.get_document(document_id) else {
+            return Task::none();
+        };
+        let path = doc.path.clone();
+        let content = doc.content_str();
+
+        // Small unsaved documents need neither disk access nor a background task
+        if path.is_none() && doc.line_count() <= BACKGROUND_DIFF_LINES {
+            let diff = line_diff("", &content);
+            self.apply_diff(document_id, diff, true);
+            return Task::none();
+        }
+
+        Task::perform(
+            async move {
+                let saved = match &path {
+                    Some(path) => tokio::fs::read_to_string(path).await.ok(),
+                    None => None,
+                };
+                let all_new = saved.is_none();
+                let diff = tokio::task::spawn_blocking(move || {
+                    line_diff(saved.as_deref().unwrap_or(""), &content)
+                })
+                .await
+                .unwrap_or_default();
+
+                InternalMessage::DiffComputed { document_id, diff, all_new }
+            },
+            |msg| Self::app_message(Message::Internal(msg)),
+        )
+    }
+
+    /// Backups kept of a file's previous versions when it is saved, and
+    /// where they go
+    ///
+    /// Autosaves don't make backups, so they can't rotate out the versions
+    /// from explicit saves.
+    fn backup_settings(&self) -> (usize, crate::config::BackupLocation) {
+        let files = &self.config.files;
+        let depth = if files.create_backups { files.backup_depth } else { 0 };
+        (depth, files.backup_location)
+    }
+
+    /// Show a document as being written to its file
+    fn set_saving(&mut self, document_id: DocumentId) {
+        if let Some(doc) = self.state.get_document_mut(document_id) {
+            doc.save_status = crate::state::SaveStatus::Saving;
+        }
+    }
+
+    /// Check whether a document can be autosaved over its file on disk
+    ///
+    /// Read-only files and files changed since they were loaded or saved are
+    /// left alone so autosave never clobbers someone else's edits.
+    fn can_autosave_to_disk(doc: &Document, path: &std::path::Path) -> bool {
+        if doc.read_only {
+            return false;
+        }
+
+        let info = crate::file_handler::FileInfo::from_path(path);
+        if info.exists && info.is_readonly {
+            return false;
+        }
+
+        match doc.last_disk_mtime {
+            Some(mtime) => info.exists && info.modified == Some(mtime),
+            None => !info.exists,
+        }
+    }
+
+    /// Compute a document's git line status on a background task
+    fn git_status_task(&self, document_id: DocumentId) -> Task<Message> {
+        let Some(doc) = self.state.get_document(document_id) else {
+            return Task::none();
+        };
+        let Some(path) = doc.path.clone() else {
+            return Task::none();
+        };
+        let content = doc.content_str();
+
+        Task::perform(
+            async move {
+                let changes = tokio::task::spawn_blocking(move || {
+                    crate::git::line_status_for(&path, &content)
+                })
+                .await
+                .unwrap_or(None);
+
+                InternalMessage::GitStatusComputed { document_id, changes }
+            },
+            |msg| Self::app_message(Message::Internal(msg)),
+        )
+    }
+
+    /// Lint a document and check its links once editing pauses
+    fn schedule_document_checks(&mut self, document_id: DocumentId) -> Task<Message> {
+        let debouncer = self
+            .check_debouncers
+            .entry(document_id)
+            .or_insert_with(|| Debouncer::with_edge(CHECK_DELAY, DebounceEdge::Trailing));
+        debouncer.should_trigger();
+        if !debouncer.trailing() {
+            return Task::none();
+        }
+
+        let delay = debouncer.delay();
+        Task::perform(
+            async move {
+                tokio::time::sleep(delay).await;
+                InternalMessage::ChecksDue { document_id }
+            },
+            |msg| Self::app_message(Message::Internal(msg)),
+        )
+    }
+
+    /// Lint a markdown document with the enabled style rules
+    ///
+    /// Each document keeps its own linter so only changed lines are analyzed.
+    fn run_linter(&mut self, document_id: DocumentId) {
+        let Some(doc) = self.state.documents.get_mut(&document_id) else {
+            return;
+        };
+        if !self.config.lint.enabled || !doc.is_markdown() {
+            doc.lint_diagnostics.clear();
+            return;
+        }
+
+        let linter = self.linters.entry(document_id).or_default();
+        doc.lint_diagnostics = linter.lint(&doc.content_str(), &self.config.lint);
+    }
+
+    /// Rebuild a document's minimap, or drop it while the minimap is off
+    ///
+    /// Markdown lines are colored by their syntax tokens; other documents
+    /// are drawn as plain text.
+    fn refresh_minimap(&mut self, document_id: DocumentId) {
+        let Some(doc) = self.state.documents.get_mut(&document_id) else {
+            return;
+        };
+        if !self.config.editor.minimap {
+            doc.minimap.clear();
+            return;
+        }
+
+        let text = doc.content_str();
+        let lines: Vec<&str> = text.lines().collect();
+        let tokens = if doc.is_markdown() {
+            self.renderer.tokenize_document(&lines)
+        } else {
+            Vec::new()
+        };
+        let scheme = self.renderer.color_scheme();
+        doc.minimap = crate::editor::minimap::minimap_lines(&lines, &tokens, |token| {
+            scheme.get_style(token).foreground
+        });
+    }
+
+    /// Rebuild the minimaps of all open documents
+    fn refresh_minimaps(&mut self) {
+        let ids: Vec<_> = self.state.documents.keys().copied().collect();
+        for id in ids {
+            self.refresh_minimap(id);
+        }
+    }
+
+    /// Scroll a document's editor so `top` is its first visible line
+    ///
+    /// The editor's scroll position is only tracked approximately, from
+    /// scroll actions and cursor movement, so this is a relative scroll.
+    fn scroll_editor_to(&mut self, document_id: DocumentId, top: usize) {
+        let viewport_lines = self.state.editor_viewport_lines();
+        let Some(doc) = self.state.documents.get_mut(&document_id) else {
+            return;
+        };
+        let Some(content) = self.editor_contents.get_mut(&document_id) else {
+            return;
+        };
+
+        let top = top.min(doc.line_count().saturating_sub(viewport_lines));
+        let lines = top as i32 - doc.editor_state.scroll_line as i32;
+        if lines != 0 {
+            content.perform(text_editor::Action::Scroll { lines });
+            doc.editor_state.scroll_line = top;
+        }
+    }
+
+    /// Scroll the editor to center the line at a minimap position
+    fn scroll_to_minimap_position(&mut self, document_id: DocumentId, y: f32) {
+        let Some(doc) = self.state.get_document(document_id) else {
+            return;
+        };
+        let line =
+            crate::editor::minimap::line_at(y, doc.line_count(), self.state.minimap_rows());
+        let top = line.saturating_sub(self.state.editor_viewport_lines() / 2);
+        self.scroll_editor_to(document_id, top);
+    }
+
+    /// Check a markdown document for broken links on a background task
+    ///
+    /// Relative links are resolved against the document's directory.
+    fn link_check_task(&mut self, document_id: DocumentId) -> Task<Message> {
+        if let Some(debouncer) = self.check_debouncers.get_mut(&document_id) {
+            debouncer.reset();
+        }
+        let Some(doc) = self.state.get_document(document_id) else {
+            return Task::none();
+        };
+        if !doc.is_markdown() {
+            return Task::none();
+        }
+        let base = doc.path.as_ref().and_then(|p| p.parent()).map(PathBuf::from);
+        let content = doc.content_str();
+
+        Task::perform(
+            async move {
+                let diagnostics = tokio::task::spawn_blocking(move || {
+                    crate::markdown::check_links(&content, base.as_deref())
+                })
+                .await
+                .unwrap_or_default();
+
+                InternalMessage::LinksChecked { document_id, diagnostics }
+            },
+            |msg| Self::app_message(Message::Internal(msg)),
+        )
+    }
+
+    /// Render a markdown document's diagrams for the preview on a background task
+    ///
+    /// Renders are cached on disk by source, so only new or edited
+    /// diagrams run the external renderer.
+    fn diagram_task(&self, document_id: DocumentId) -> Task<Message> {
+        let Some(doc) = self.state.get_document(document_id) else {
+            return Task::none();
+        };
+        let content = doc.content_str();
+        // Nothing to render, and no earlier diagrams to clear
+        if !doc.is_markdown() || (doc.diagrams.is_empty() && !content.contains("mermaid")) {
+            return Task::none();
+        }
+        let markdown = self.config.markdown;
+
+        Task::perform(
+            async move {
+                let diagrams = tokio::task::spawn_blocking(move || {
+                    crate::markdown::PreviewRenderer::new()
+                        .with_markdown_options(&markdown)
+                        .with_diagram_renderer(crate::markdown::DiagramRenderer::new())
+                        .render(&content)
+                        .into_iter()
+                        .filter_map(|element| match element {
+                            crate::markdown::PreviewElement::Diagram { output, .. } => Some(output),
+                            _ => None,
+                        })
+                        .collect()
+                })
+                .await
+                .unwrap_or_default();
+
+                InternalMessage::DiagramsRendered { document_id, diagrams }
+            },
+            |msg| Self::app_message(Message::Internal(msg)),
+        )
+    }
+
+    /// Store a computed diff as gutter markers and in the changes panel
+    fn apply_diff(&mut self, document_id: DocumentId, diff: Vec<crate::editor::DiffLine>, all_new: bool) {
+        let Some(doc) = self.state.get_document_mut(document_id) else {
+            return;
+        };
+        doc.saved_changes = crate::editor::diff::line_changes(&diff);
+
+        self.state.diff_view = Some(crate::state::DiffView {
+            document_id,
+            lines: diff,
+            all_new,
+        });
+    }
+
+    /// Zoom in or out by a step, or reset when `step` is None
+    ///
+    /// Scales all text via the zoom level, or only the editor font size when
+    /// `zoom_scales_ui` is disabled, then briefly shows the new percentage.
+    fn apply_zoom(&mut self, step: Option<f32>) -> Task<Message> {
+        let percent = if self.config.ui.zoom_scales_ui {
+            let level = step.map_or(1.0, |s| self.state.zoom_level + s);
+            self.state.set_zoom_level(level);
+            self.session.zoom_level = self.state.zoom_level;
+            self.state.zoom_percent()
+        } else {
+            let size = &mut self.config.editor.font_size;
+            *size = match step {
+                Some(s) if s > 0.0 => (*size + 1.0).min(48.0),
+                Some(_) => (*size - 1.0).max(8.0),
+                None => 14.0,
+            };
+            self.state.editor_font_size = *size;
+            (*size / 14.0 * 100.0).round() as u32
+        };
+
+        self.state
+            .set_status(fl!("status-zoom", percent = percent), crate::state::StatusLevel::Info);
+        Task::none()
+    }
+
+    /// Apply the theme preference to the app, editor colors, and exports
+    fn apply_theme(&mut self) -> Task<Message> {
+        use crate::config::ThemePreference;
+
+        self.state.update_dark_mode(self.config.ui.theme);
+        self.renderer.set_color_scheme(self.state.dark_mode);
+        self.refresh_minimaps();
+        let theme = match self.config.ui.theme {
+            ThemePreference::System => cosmic::theme::system_preference(),
+            ThemePreference::Light => cosmic::Theme::light(),
+            ThemePreference::Dark => cosmic::Theme::dark(),
+        };
+        cosmic::command::set_theme(theme)
+    }
+
+    /// Take requests from later launches
+    fn bind_instance_socket(&mut self) {
+        match crate::ipc::InstanceSocket::bind(crate::ipc::socket_path()) {
+            Ok(socket) => self.instance_socket = Some(std::sync::Arc::new(socket)),
+            Err(e) => log::warn!("Not listening for other launches: {}", e),
+        }
+    }
+
+    /// Stop taking requests from later launches, so the next launch starts
+    /// its own instance
+    fn release_instance_socket(&mut self) {
+        if let Some(socket) = self.instance_socket.take() {
+            socket.close();
+        }
+    }
+
+    /// Serve the active document's HTML for a browser, and open it there
+    ///
+    /// A server that is already running switches to the active document.
+    fn start_preview_server(&mut self) -> Task<Message> {
+        let Some(id) = self.state.active_document else {
+            return Task::none();
+        };
+        // The page is rendered in the background, and the browser opened
+        // once it is ready
+        let url = match &mut self.preview_server {
+            Some((served, server)) => {
+                *served = id;
+                server.url()
+            }
+            None => match PreviewServer::start(self.config.preview.server_port, String::new()) {
+                Ok(server) => {
+                    let url = server.url();
+                    self.preview_server = Some((id, server));
+                    url
+                }
+                Err(e) => {
+                    log::error!("Failed to start the preview server: {}", e);
+                    self.state.set_status_with_details(
+                        fl!("status-preview-server-failed"),
+                        crate::state::StatusLevel::Error,
+                        e.to_string(),
+                    );
+                    return Task::none();
+                }
+            },
+        };
+        self.state.set_status(
+            fl!("status-preview-server-started", url = url),
+            crate::state::StatusLevel::Info,
+        );
+        self.preview_page_task(true)
+    }
+
+    /// Stop serving the browser preview, freeing its port
+    fn stop_preview_server(&mut self) {
+        if let Some((_, mut server)) = self.preview_server.take() {
+            server.stop();
+            self.state.set_status(fl!("status-preview-server-stopped"), crate::state::StatusLevel::Info);
+        }
+    }
+
+    /// Render the served document as the browser preview's page, on a
+    /// background task
+    ///
+    /// Images are embedded, since the server only serves the page itself.
+    /// With `open`, the page is opened in the browser once it is rendered.
+    fn preview_page_task(&mut self, open: bool) -> Task<Message> {
+        let Some((document_id, _)) = &self.preview_server else {
+            return Task::none();
+        };
+        let document_id = *document_id;
+        let Some(doc) = self.state.get_document(document_id) else {
+            return Task::none();
+        };
+        let exporter = crate::markdown::MarkdownExporter::with_options(&self.config.markdown)
+            .with_soft_break_as_newline(self.config.preview.soft_break_as_newline)
+            .with_notes(self.notes.clone());
+        let options = crate::markdown::HtmlExportOptions {
+            embed_images: true,
+            base_path: doc.path.as_deref().and_then(Path::parent).map(Path::to_path_buf),
+            title: Some(doc.display_name.clone()),
+            ..crate::markdown::HtmlExportOptions::from_config(&self.config)
+        };
+        let content = doc.content_str();
+        self.preview_render += 1;
+        let render = self.preview_render;
+
+        Task::perform(
+            async move {
+                let html = tokio::task::spawn_blocking(move || exporter.export_html(&content, &options))
+                    .await
+                    .unwrap_or_default();
+
+                InternalMessage::PreviewPageRendered { document_id, render, html, open }
+            },
+            |msg| Self::app_message(Message::Internal(msg)),
+        )
+    }
+
+    /// How long a status message of the given level stays visible
+    fn status_timeout(&self, level: crate::state::StatusLevel) -> Duration {
+        let seconds = match level {
+            crate::state::StatusLevel::Error => self.config.ui.error_status_timeout,
+            _ => self.config.ui.status_timeout,
+        };
+        Duration::from_secs(seconds)
+    }
+
+    /// Render the main view
+    fn view_main(&self) -> Element<'_, Message> {
+        use cosmic::widget::{container, text, Column};
+        use cosmic::iced::Length;
+
+        // Simple placeholder for Phase 1
+        let content: Element<Message> = if self.state.has_documents() {
+            match self.state.active_document() {
+                Some(doc) => {
+                    let info = format!(
+                        "File: {}\nLines: {}\nCharacters: {}",
+                        doc.display_name,
+                        doc.line_count(),
+                        doc.char_count()
+                    );
+                    text(info).into()
+                }
+                None => text("No document selected").into(),
+            }
+        } else {
+            Column::new()
+                .push(text("Welcome to Cosmic Notebook").size(24))
+                .push(text(""))
+                .push(text("Press Ctrl+N to create a new file"))
+                .push(text("Press Ctrl+O to open a file"))
+                .spacing(8)
+                .into()
+        };
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(20)
+            .into()
+    }
+
+    /// Helper to wrap message in cosmic Action
+    fn app_message(msg: Message) -> cosmic::Action<Message> {
+        cosmic::Action::App(msg)
+    }
+
+    /// Handle file-related messages
+    fn handle_file_message(&mut self, msg: FileMessage) -> Task<Message> {
+        match msg {
+            FileMessage::New => {
+                let doc = Document::new();
+                let id = doc.id;
+                // Create text_editor::Content for this document
+                self.editor_contents.insert(id, text_editor::Content::new());
+                self.state.add_document(doc);
+                self.update_window_title();
+                Task::none()
+            }
+
+            FileMessage::Open => {
+                // TODO: Open file picker dialog
+                // For Phase 1, this is a placeholder
+                Task::none()
+            }
+
+            FileMessage::OpenPath(path) => {
+                // Check if already open
+                if let Some(id) = self.state.find_document_by_path(&path) {
+                    self.state.set_active_document(id);
+                    self.update_window_title();
+                    return Task::none();
+                }
+
+                // Load file asynchronously
+                Task::perform(
+                    async move {
+                        match std::fs::read_to_string(&path) {
+                            Ok(content) => FileMessage::Loaded { path, content },
+                            Err(e) => FileMessage::LoadError {
+                                path,
+                                error: e.to_string(),
+                            },
+                        }
+                    },
+                    |msg| Self::app_message(Message::File(msg)),
+                )
+            }
+
+            FileMessage::OpenExternal(path) => {
+                let raise = match self.core.main_window_id() {
+                    Some(id) => window::gain_focus(id),
+                    None => Task::none(),
+                };
+                if path.exists() || self.state.find_document_by_path(&path).is_some() {
+                    let open = self.handle_file_message(FileMessage::OpenPath(path));
+                    return Task::batch([raise, open]);
+                }
+
+                // Saving writes the new file
+                let (id, task) = self.open_loaded(path, String::new());
+                if let Some(doc) = self.state.get_document_mut(id) {
+                    doc.mark_modified();
+                }
+                Task::batch([raise, task])
+            }
+
+            FileMessage::Loaded { path, content } => self.open_loaded(path, content).1,
+
+            FileMessage::Reload(document_id) => {
+                let Some(path) = self.state.get_document(document_id).and_then(|d| d.path.clone())
+                else {
+                    return Task::none();
+                };
+                Task::perform(
+                    async move {
+                        match std::fs::read_to_string(&path) {
+                            Ok(content) => Some(FileMessage::Reloaded { document_id, content }),
+                            Err(e) => {
+                                // Usually caught mid-save; the next event retries
+                                log::warn!("Failed to reload {}: {}", path.display(), e);
+                                None
+                            }
+                        }
+                    },
+                    |msg| match msg {
+                        Some(msg) => Self::app_message(Message::File(msg)),
+                        None => Self::app_message(Message::None),
+                    },
+                )
+            }
+
+            FileMessage::Reloaded { document_id, content } => self.apply_reload(document_id, content),
+
+            FileMessage::ToggleWatch => {
+                self.set_watch_mode(self.watcher.is_none());
+                Task::none()
+            }
+
+            FileMessage::ToggleSingleInstance => {
+                let enabled = !self.config.single_instance;
+                self.config.single_instance = enabled;
+                let status = if enabled {
+                    if self.instance_socket.is_none() {
+                        self.bind_instance_socket();
+                    }
+                    fl!("status-single-instance-on")
+                } else {
+                    self.release_instance_socket();
+                    fl!("status-single-instance-off")
+                };
+                self.state.set_status(status, crate::state::StatusLevel::Info);
+                if let Err(e) = self.config.save() {
+                    self.state.set_status(
+                        fl!("status-config-save-failed", error = e.to_string()),
+                        crate::state::StatusLevel::Error,
+                    );
+                }
+                Task::none()
+            }
+
+            FileMessage::LoadError { path, error } => {
+                log::error!("Failed to load {}: {}", path.display(), error);
+                self.state.set_status_with_details(
+                    fl!("status-open-failed", path = path.display().to_string()),
+                    crate::state::StatusLevel::Error,
+                    error,
+                );
+                Task::none()
+            }
+
+            FileMessage::Save => {
+                if self.config.editor.reflow_on_save
+                    && self.state.active_document().is_some_and(|doc| doc.is_markdown())
+                {
+                    let width = self.config.editor.reflow_width;
+                    self.apply_buffer_operation(|buffer, range| {
+                        crate::editor::reflow::reflow_document(buffer, range, width)
+                    });
+                }
                 if let Some(doc) = self.state.active_document() {
                     if let Some(path) = doc.path.clone() {
                         let id = doc.id;
                         let content = doc.content_str();
+                        let follow_symlinks = self.config.files.follow_symlinks_on_save;
+                        let (backup_depth, backup_location) = self.backup_settings();
+                        self.set_saving(id);
                         return Task::perform(
                             async move {
-                                match std::fs::write(&path, &content) {
-                                    Ok(_) => FileMessage::Saved {
+                                let result = crate::file_handler::save_file(
+                                    &path,
+                                    &content,
+                                    follow_symlinks,
+                                    backup_depth,
+                                    backup_location,
+                                )
+                                .await;
+                                match result {
+                                    Ok(()) => FileMessage::Saved {
                                         document_id: id,
                                         path,
                                     },
@@ -434,10 +2880,21 @@ impl CosmicNotebook {
             FileMessage::SaveToPath { document_id, path } => {
                 if let Some(doc) = self.state.get_document(document_id) {
                     let content = doc.content_str();
+                    let follow_symlinks = self.config.files.follow_symlinks_on_save;
+                    let (backup_depth, backup_location) = self.backup_settings();
+                    self.set_saving(document_id);
                     return Task::perform(
                         async move {
-                            match std::fs::write(&path, &content) {
-                                Ok(_) => FileMessage::Saved { document_id, path },
+                            let result = crate::file_handler::save_file(
+                                &path,
+                                &content,
+                                follow_symlinks,
+                                backup_depth,
+                                backup_location,
+                            )
+                            .await;
+                            match result {
+                                Ok(()) => FileMessage::Saved { document_id, path },
                                 Err(e) => FileMessage::SaveError {
                                     document_id,
                                     error: e.to_string(),
@@ -451,6 +2908,10 @@ impl CosmicNotebook {
             }
 
             FileMessage::Saved { document_id, path } => {
+                if self.config.markdown.wiki_links {
+                    self.notes.insert(path.clone());
+                    self.renderer.set_notes(self.notes.clone());
+                }
                 let title = {
                     if let Some(doc) = self.state.get_document_mut(document_id) {
                         doc.path = Some(path.clone());
@@ -459,6 +2920,8 @@ impl CosmicNotebook {
                             .map(|n| n.to_string_lossy().to_string())
                             .unwrap_or_else(|| "Unknown".to_string());
                         doc.mark_saved();
+                        doc.saved_changes.clear();
+                        doc.last_disk_mtime = crate::file_handler::FileInfo::from_path(&path).modified;
                         Some(doc.display_name.clone())
                     } else {
                         None
@@ -469,24 +2932,85 @@ impl CosmicNotebook {
                 if let Err(e) = self.recovery_manager.clear_recovery(&document_id.to_string()) {
                     log::warn!("Failed to clear recovery for {}: {}", document_id, e);
                 }
+
+                // A saved scratch document no longer needs restoring
+                if self.session.remove_scratch_document(document_id) {
+                    if let Err(e) = self.session.save() {
+                        log::error!("Failed to save session: {}", e);
+                    }
+                }
                 
                 // Update tab title
                 if let Some(title) = title {
-                    self.state.tabs.update_title(document_id, title);
+                    self.state.update_tab_title(document_id, title);
                 }
                 
                 self.update_window_title();
                 self.state.set_status(
-                    format!("Saved: {}", path.display()),
+                    fl!("status-saved", path = path.display().to_string()),
                     crate::state::StatusLevel::Info,
                 );
-                Task::none()
+
+                let served = self.preview_server.as_ref().map(|(served, _)| *served);
+                let preview = if served == Some(document_id) {
+                    self.preview_page_task(false)
+                } else {
+                    Task::none()
+                };
+
+                // Refresh the changes panel against the new saved version
+                self.run_linter(document_id);
+                let mut tasks = vec![
+                    preview,
+                    self.git_status_task(document_id),
+                    self.link_check_task(document_id),
+                    self.diagram_task(document_id),
+                ];
+                if self.state.diff_view.as_ref().map(|v| v.document_id) == Some(document_id) {
+                    tasks.push(self.diff_task(document_id));
+                }
+                Task::batch(tasks)
+            }
+
+            FileMessage::Autosaved { document_id, path, content } => {
+                let title = {
+                    if let Some(doc) = self.state.get_document_mut(document_id) {
+                        doc.last_disk_mtime = crate::file_handler::FileInfo::from_path(&path).modified;
+                        // Edits made while the write was in flight keep the document modified
+                        if doc.content_str() == content {
+                            doc.mark_saved();
+                            doc.saved_changes.clear();
+                        }
+                        Some(doc.title())
+                    } else {
+                        None
+                    }
+                };
+
+                if let Err(e) = self.recovery_manager.clear_recovery(&document_id.to_string()) {
+                    log::warn!("Failed to clear recovery for {}: {}", document_id, e);
+                }
+
+                if let Some(title) = title {
+                    self.state.update_tab_title(document_id, title);
+                }
+                self.update_window_title();
+                log::debug!("Autosaved {} to disk", path.display());
+                self.git_status_task(document_id)
             }
 
             FileMessage::SaveError { document_id, error } => {
                 log::error!("Failed to save document {}: {}", document_id, error);
-                self.state
-                    .set_status("Failed to save file", crate::state::StatusLevel::Error);
+                if let Some(doc) = self.state.get_document_mut(document_id) {
+                    if doc.save_status == crate::state::SaveStatus::Saving {
+                        doc.save_status = crate::state::SaveStatus::Unsaved;
+                    }
+                }
+                self.state.set_status_with_details(
+                    fl!("status-save-failed"),
+                    crate::state::StatusLevel::Error,
+                    error,
+                );
                 Task::none()
             }
 
@@ -500,13 +3024,26 @@ impl CosmicNotebook {
                     .map(|(id, doc)| (*id, doc.path.clone().unwrap(), doc.content_str()))
                     .collect();
 
+                let follow_symlinks = self.config.files.follow_symlinks_on_save;
+                let (backup_depth, backup_location) = self.backup_settings();
+                for (id, _, _) in &modified {
+                    self.set_saving(*id);
+                }
                 let tasks: Vec<_> = modified
                     .into_iter()
                     .map(|(id, path, content)| {
                         Task::perform(
                             async move {
-                                match std::fs::write(&path, &content) {
-                                    Ok(_) => FileMessage::Saved {
+                                let result = crate::file_handler::save_file(
+                                    &path,
+                                    &content,
+                                    follow_symlinks,
+                                    backup_depth,
+                                    backup_location,
+                                )
+                                .await;
+                                match result {
+                                    Ok(()) => FileMessage::Saved {
                                         document_id: id,
                                         path,
                                     },
@@ -538,8 +3075,18 @@ impl CosmicNotebook {
                         // For now, just close
                     }
                 }
+                self.remember_closed(id);
+                self.remember_bookmarks(id);
                 // Remove the text_editor content
                 self.editor_contents.remove(&id);
+                self.linters.remove(&id);
+                self.check_debouncers.remove(&id);
+                if self.state.diff_view.as_ref().map(|v| v.document_id) == Some(id) {
+                    self.state.diff_view = None;
+                }
+                if self.preview_server.as_ref().is_some_and(|(served, _)| *served == id) {
+                    self.stop_preview_server();
+                }
                 self.state.close_document(id);
                 self.update_window_title();
                 Task::none()
@@ -547,20 +3094,273 @@ impl CosmicNotebook {
 
             FileMessage::CloseAll => {
                 // TODO: Check for unsaved changes
+                self.stop_preview_server();
                 let ids: Vec<_> = self.state.documents.keys().copied().collect();
                 for id in ids {
+                    self.remember_closed(id);
+                    self.remember_bookmarks(id);
+                    self.linters.remove(&id);
+                    self.check_debouncers.remove(&id);
                     self.state.close_document(id);
                 }
-                self.update_window_title();
-                Task::none()
+                self.update_window_title();
+                Task::none()
+            }
+
+            FileMessage::ReopenClosed => {
+                let Some(closed) = self.state.pop_closed() else {
+                    self.state.set_status(fl!("status-no-closed-tabs"), crate::state::StatusLevel::Info);
+                    return Task::none();
+                };
+
+                let path = match closed.path.clone() {
+                    Some(path) if closed.unsaved_content.is_none() => path,
+                    // Scratch and modified tabs come back with their unsaved content
+                    _ => {
+                        self.reopen_unsaved(closed);
+                        return Task::none();
+                    }
+                };
+                if let Some(id) = self.state.find_document_by_path(&path) {
+                    self.state.set_active_document(id);
+                    self.update_window_title();
+                    return Task::none();
+                }
+
+                Task::perform(
+                    async move {
+                        match std::fs::read_to_string(&path) {
+                            Ok(content) => FileMessage::ReopenLoaded { closed, content },
+                            Err(e) => FileMessage::ReopenError {
+                                closed,
+                                error: e.to_string(),
+                            },
+                        }
+                    },
+                    |msg| Self::app_message(Message::File(msg)),
+                )
+            }
+
+            FileMessage::ReopenLoaded { closed, content } => {
+                let Some(path) = closed.path else {
+                    return Task::none();
+                };
+                let (id, task) = self.open_loaded(path, content);
+                self.place_cursor(id, closed.cursor);
+                task
+            }
+
+            FileMessage::ReopenError { closed, error } => {
+                let path = closed.path.unwrap_or_default();
+                log::warn!("Failed to reopen {}: {}", path.display(), error);
+                self.state.set_status_with_details(
+                    fl!("status-reopen-failed", path = path.display().to_string()),
+                    crate::state::StatusLevel::Error,
+                    error,
+                );
+                // Fall through to the tab closed before it
+                if self.state.closed_documents.is_empty() {
+                    Task::none()
+                } else {
+                    Task::done(Self::app_message(Message::File(FileMessage::ReopenClosed)))
+                }
+            }
+
+            FileMessage::CreateFile(path) => self.create_entry(path, NewEntryKind::File),
+
+            FileMessage::CreateFolder(path) => self.create_entry(path, NewEntryKind::Folder),
+
+            FileMessage::Created { path, kind } => {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                self.state
+                    .set_status(fl!("status-created", name = name), crate::state::StatusLevel::Info);
+                if let Some(parent) = path.parent() {
+                    self.state.sidebar.expand_folder(&parent.to_path_buf());
+                }
+                self.state.sidebar.set_selected(Some(path.clone()));
+                let scan = self.rescan_sidebar();
+                match kind {
+                    NewEntryKind::File => Task::batch([
+                        scan,
+                        Task::done(Self::app_message(Message::File(FileMessage::OpenPath(path)))),
+                    ]),
+                    NewEntryKind::Folder => scan,
+                }
+            }
+
+            FileMessage::CreateError { path, error } => {
+                log::error!("Failed to create {}: {}", path.display(), error);
+                self.state.set_status_with_details(
+                    fl!("status-create-failed", path = path.display().to_string()),
+                    crate::state::StatusLevel::Error,
+                    error,
+                );
+                Task::none()
+            }
+
+            _ => Task::none(),
+        }
+    }
+
+    /// Create an empty file or a folder at `path`
+    ///
+    /// A name that is already taken reopens the sidebar's name prompt,
+    /// so another name can be picked.
+    fn create_entry(&mut self, path: PathBuf, kind: NewEntryKind) -> Task<Message> {
+        if path.symlink_metadata().is_ok() {
+            if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+                self.state.sidebar.new_entry = Some(crate::state::NewEntryPrompt {
+                    parent: parent.to_path_buf(),
+                    kind,
+                    name: name.to_string_lossy().to_string(),
+                    exists: true,
+                });
+            }
+            return Task::none();
+        }
+        Task::perform(
+            async move {
+                let result = match kind {
+                    NewEntryKind::File => std::fs::OpenOptions::new()
+                        .write(true)
+                        .create_new(true)
+                        .open(&path)
+                        .map(|_| ()),
+                    NewEntryKind::Folder => std::fs::create_dir(&path),
+                };
+                match result {
+                    Ok(()) => FileMessage::Created { path, kind },
+                    Err(e) => FileMessage::CreateError {
+                        path,
+                        error: e.to_string(),
+                    },
+                }
+            },
+            |msg| Self::app_message(Message::File(msg)),
+        )
+    }
+
+    /// Handle sidebar messages
+    fn handle_sidebar_message(&mut self, msg: SidebarMessage) -> Task<Message> {
+        let sidebar = &mut self.state.sidebar;
+        match msg {
+            SidebarMessage::SelectFile(path) => {
+                sidebar.set_selected(Some(path.clone()));
+                return Task::done(Self::app_message(Message::File(FileMessage::OpenPath(path))));
+            }
+
+            SidebarMessage::PressFile(path) => {
+                sidebar.file_drag = Some(FileDrag::new(path));
+            }
+
+            SidebarMessage::FileDragMoved { x, y } => {
+                if let Some(drag) = sidebar.file_drag.as_mut() {
+                    drag.pointer_moved(x, y);
+                }
+            }
+
+            SidebarMessage::EndFileDrag => {
+                let drop_target = self.state.drop_target.take();
+                let Some(drag) = self.state.sidebar.file_drag.take() else {
+                    return Task::none();
+                };
+                // A press that didn't move is a click
+                if !drag.dragging {
+                    return self.handle_sidebar_message(SidebarMessage::SelectFile(drag.path));
+                }
+                if let Some((document_id, point)) = drop_target {
+                    self.drop_file_link(document_id, point, &drag.path);
+                }
+            }
+
+            SidebarMessage::ToggleFolder(path) => {
+                sidebar.toggle_folder(&path);
+                sidebar.set_selected(Some(path.clone()));
+                // Folders are listed the first time they open, and again
+                // once they change on disk
+                if sidebar.is_expanded(&path) && sidebar.needs_listing(&path) {
+                    return self.list_directories(vec![path]);
+                }
+            }
+
+            SidebarMessage::ExpandAll => return self.expand_all_folders(),
+
+            SidebarMessage::CancelExpandAll => {
+                if let Some(cancel) = &sidebar.expand_all {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            }
+
+            SidebarMessage::CollapseAll => sidebar.collapse_all(),
+
+            SidebarMessage::Refresh => return self.rescan_sidebar(),
+
+            SidebarMessage::NavigateToParent => {
+                if sidebar.go_up() {
+                    return self.rescan_sidebar();
+                }
+            }
+
+            SidebarMessage::NewFile | SidebarMessage::NewFolder => {
+                let kind = if matches!(msg, SidebarMessage::NewFile) {
+                    NewEntryKind::File
+                } else {
+                    NewEntryKind::Folder
+                };
+                if let Some(parent) = sidebar.target_directory() {
+                    sidebar.prompt_new_entry(parent, kind);
+                    return cosmic::widget::text_input::focus(ui::NEW_ENTRY_INPUT.clone());
+                }
+            }
+
+            SidebarMessage::NewEntryAt { entry_index, kind } => {
+                let Some(path) = sidebar.get_entry(entry_index).map(|e| e.path.clone()) else {
+                    return Task::none();
+                };
+                sidebar.set_selected(Some(path));
+                if let Some(parent) = sidebar.target_directory() {
+                    sidebar.prompt_new_entry(parent, kind);
+                    return cosmic::widget::text_input::focus(ui::NEW_ENTRY_INPUT.clone());
+                }
+            }
+
+            SidebarMessage::NewEntryName(name) => {
+                if let Some(prompt) = sidebar.new_entry.as_mut() {
+                    prompt.name = name;
+                    prompt.exists = false;
+                }
             }
 
-            _ => Task::none(),
+            SidebarMessage::SubmitNewEntry => {
+                let Some(prompt) = sidebar.new_entry.as_mut() else {
+                    return Task::none();
+                };
+                let Some(path) = prompt.path() else {
+                    return Task::none();
+                };
+                if path.symlink_metadata().is_ok() {
+                    prompt.exists = true;
+                    return Task::none();
+                }
+                let kind = prompt.kind;
+                sidebar.new_entry = None;
+                return self.create_entry(path, kind);
+            }
+
+            SidebarMessage::CancelNewEntry => sidebar.new_entry = None,
+
+            _ => {}
         }
+        Task::none()
     }
 
     /// Handle tab-related messages
     fn handle_tab_message(&mut self, msg: TabMessage) -> Task<Message> {
+        let previous = self.state.active_document;
+
         match msg {
             TabMessage::Select(id) => {
                 self.state.set_active_document(id);
@@ -568,25 +3368,33 @@ impl CosmicNotebook {
             }
 
             TabMessage::SelectIndex(index) => {
-                self.state.tabs.set_active_index(index);
-                if let Some(id) = self.state.tabs.active_tab() {
-                    self.state.active_document = Some(id);
-                }
+                self.state.focused_tabs_mut().set_active_index(index);
+                self.state.sync_active_document();
                 self.update_window_title();
             }
 
-            TabMessage::Next => {
-                self.state.tabs.next_tab();
-                if let Some(id) = self.state.tabs.active_tab() {
-                    self.state.active_document = Some(id);
+            TabMessage::SelectLast => {
+                if let Some(index) = self.state.focused_tabs().count().checked_sub(1) {
+                    return self.handle_tab_message(TabMessage::SelectIndex(index));
                 }
+            }
+
+            TabMessage::Next => {
+                self.state.focused_tabs_mut().next_tab();
+                self.state.sync_active_document();
                 self.update_window_title();
             }
 
             TabMessage::Previous => {
-                self.state.tabs.prev_tab();
-                if let Some(id) = self.state.tabs.active_tab() {
-                    self.state.active_document = Some(id);
+                self.state.focused_tabs_mut().prev_tab();
+                self.state.sync_active_document();
+                self.update_window_title();
+            }
+
+            TabMessage::CycleRecent { backwards } => {
+                self.state.cycle_recent(!backwards);
+                if !self.modifiers.control() {
+                    self.state.finish_recent_cycle();
                 }
                 self.update_window_title();
             }
@@ -603,41 +3411,124 @@ impl CosmicNotebook {
 
             _ => {}
         }
-        Task::none()
+
+        // Refresh git markers when another document gains focus
+        match self.state.active_document {
+            Some(id) if self.state.active_document != previous => self.git_status_task(id),
+            _ => Task::none(),
+        }
     }
 
     /// Handle editor-related messages
     fn handle_editor_message(&mut self, msg: EditorMessage) -> Task<Message> {
+        if msg.is_edit() {
+            if let Some(id) = msg.document_id().or(self.state.active_document) {
+                if self.edit_blocked(id) {
+                    return Task::none();
+                }
+            }
+        }
+
         match msg {
             EditorMessage::TextEditorAction { document_id, action } => {
+                // Interacting with the editor cancels an animated jump
+                self.scroll_animation = None;
+
+                // Clicking or typing in the other pane of a split editor focuses it
+                if Some(document_id) != self.state.active_document
+                    && !matches!(action, text_editor::Action::Scroll { .. })
+                {
+                    self.state.set_active_document(document_id);
+                    self.update_window_title();
+                }
+
+                // Smart typography replaces the characters before the cursor
+                // along with the typed one, in a single edit
+                if let text_editor::Action::Edit(text_editor::Edit::Insert(typed)) = action {
+                    if self.substitutes_typography(document_id, typed) {
+                        self.apply_buffer_operation(|buffer, range| {
+                            crate::editor::typography::type_char(buffer, range.start, typed)
+                        });
+                        self.sync_cursor_state(document_id);
+                        return self.schedule_document_checks(document_id);
+                    }
+                }
+
+                // Double-clicks select by Unicode word boundaries
+                if matches!(action, text_editor::Action::SelectWord)
+                    && self.select_word_at_cursor(document_id)
+                {
+                    return Task::none();
+                }
+
                 // Handle the text_editor::Action from the widget
                 if let Some(content) = self.editor_contents.get_mut(&document_id) {
                     // Check if this is an edit action that modifies content
                     let is_edit = action.is_edit();
+
+                    // Keep the text before the edit to track snippet tab-stops
+                    let in_snippet = self
+                        .state
+                        .get_document(document_id)
+                        .is_some_and(|doc| doc.editor_state.snippet.is_some());
+                    let old_text = (is_edit && in_snippet).then(|| content.text());
+                    let ctrl_click =
+                        matches!(action, text_editor::Action::Click(_)) && self.modifiers.control();
+                    let scrolled = match action {
+                        text_editor::Action::Scroll { lines } => Some(lines),
+                        _ => None,
+                    };
                     
                     // Apply the action to the text_editor content
                     content.perform(action);
-                    
+                    if let Some(lines) = scrolled {
+                        let line_count = content.line_count();
+                        if let Some(doc) = self.state.get_document_mut(document_id) {
+                            doc.editor_state.scroll_by(lines, line_count);
+                        }
+                    }
+
+                    if ctrl_click {
+                        return self.open_link_at_cursor();
+                    }
                     if is_edit {
                         // Update the document's rope content from the editor
                         let new_text = content.text();
+                        let edit = old_text.map(|old| {
+                            locate_edit(&old, &new_text, content_cursor_offset(content))
+                        });
                         if let Some(doc) = self.state.get_document_mut(document_id) {
-                            doc.content = ropey::Rope::from_str(&new_text);
+                            if let Some((pos, removed, inserted)) = edit {
+                                let inside = doc
+                                    .editor_state
+                                    .snippet
+                                    .as_mut()
+                                    .is_some_and(|s| s.apply_edit(pos, removed, inserted));
+                                if !inside {
+                                    doc.editor_state.snippet = None;
+                                }
+                            }
+                            doc.set_text(&new_text);
                             doc.mark_modified();
                             
                             let title = doc.title();
-                            self.state.tabs.update_title(document_id, title);
+                            self.state.update_tab_title(document_id, title);
                         }
                         self.update_window_title();
                         self.autosave_pending = true;
                     }
+
+                    self.sync_cursor_state(document_id);
+                    if is_edit {
+                        return self.schedule_document_checks(document_id);
+                    }
                 }
             }
 
             EditorMessage::TextChanged { document_id, content } => {
                 let title = {
                     if let Some(doc) = self.state.get_document_mut(document_id) {
-                        doc.content = ropey::Rope::from_str(&content);
+                        doc.set_text(&content);
                         doc.mark_modified();
                         Some(doc.title())
                     } else {
@@ -646,7 +3537,7 @@ impl CosmicNotebook {
                 };
                 
                 if let Some(title) = title {
-                    self.state.tabs.update_title(document_id, title);
+                    self.state.update_tab_title(document_id, title);
                 }
                 
                 self.update_window_title();
@@ -657,7 +3548,7 @@ impl CosmicNotebook {
                 // Note: text_editor widget doesn't have built-in undo
                 // For now, show a status message
                 self.state.set_status(
-                    "Undo not yet implemented for text editor".to_string(),
+                    fl!("status-undo-unavailable"),
                     crate::state::StatusLevel::Info,
                 );
             }
@@ -666,7 +3557,7 @@ impl CosmicNotebook {
                 // Note: text_editor widget doesn't have built-in redo  
                 // For now, show a status message
                 self.state.set_status(
-                    "Redo not yet implemented for text editor".to_string(),
+                    fl!("status-redo-unavailable"),
                     crate::state::StatusLevel::Info,
                 );
             }
@@ -684,6 +3575,236 @@ impl CosmicNotebook {
                 if let Some(doc) = self.state.active_document_mut() {
                     let target_line = line.saturating_sub(1).min(doc.line_count().saturating_sub(1));
                     doc.editor_state.set_cursor(crate::state::CursorPosition::new(target_line, 0));
+                    let (id, offset) = (doc.id, doc.content.line_to_char(target_line));
+                    self.jump_to_range(id, offset, offset);
+                }
+            }
+
+            EditorMessage::SortLines { descending, case_sensitive } => {
+                self.apply_buffer_operation(|buffer, range| {
+                    buffer.sort_lines(range, descending, case_sensitive)
+                });
+            }
+
+            EditorMessage::DedupeLines => {
+                self.apply_buffer_operation(|buffer, range| buffer.dedupe_lines(range));
+            }
+
+            EditorMessage::JoinLines => {
+                self.apply_buffer_operation(|buffer, range| buffer.join_lines(range));
+            }
+
+            EditorMessage::TransposeChars => {
+                self.apply_buffer_operation(|buffer, range| {
+                    match buffer.transpose_chars(range.end) {
+                        Some(position) => position..position,
+                        None => range,
+                    }
+                });
+            }
+
+            EditorMessage::TransposeWords => {
+                self.apply_buffer_operation(|buffer, range| {
+                    match buffer.transpose_words(range.end) {
+                        Some(position) => position..position,
+                        None => range,
+                    }
+                });
+            }
+
+            EditorMessage::ToggleEmphasis(kind) => {
+                self.apply_buffer_operation(|buffer, range| {
+                    crate::editor::formatting::toggle_emphasis(buffer, range, kind)
+                });
+            }
+
+            EditorMessage::AdjustHeading(delta) => {
+                let options = crate::editor::HeadingOptions {
+                    wrap: self.config.editor.heading_wrap,
+                    create: self.config.editor.heading_create,
+                };
+                self.apply_buffer_operation(|buffer, range| {
+                    crate::editor::formatting::adjust_heading(buffer, range, delta, options)
+                });
+            }
+
+            EditorMessage::Indent => self.indent_active(),
+
+            EditorMessage::Outdent => self.outdent_active(),
+
+            EditorMessage::SetCodeBlockLanguage(language) => {
+                self.apply_buffer_operation(|buffer, range| {
+                    crate::editor::formatting::set_code_block_language(buffer, range, &language)
+                });
+                if let Some(id) = self.state.active_document {
+                    if let Some(line) = self.editor_contents.get(&id).map(|c| c.cursor_position().0) {
+                        self.refresh_code_block(id, line);
+                    }
+                }
+            }
+
+            EditorMessage::ToggleBlockquote => {
+                self.apply_buffer_operation(crate::editor::formatting::toggle_blockquote);
+            }
+
+            EditorMessage::WrapInCallout(kind) => {
+                self.apply_buffer_operation(|buffer, range| {
+                    crate::editor::formatting::wrap_in_callout(buffer, range, kind)
+                });
+            }
+
+            EditorMessage::ReflowParagraph => {
+                let width = self.config.editor.reflow_width;
+                self.apply_buffer_operation(|buffer, range| {
+                    crate::editor::reflow::reflow_at(buffer, range, width)
+                });
+            }
+
+            EditorMessage::OpenLinkAtCursor => {
+                return self.open_link_at_cursor();
+            }
+
+            EditorMessage::InsertDateTime(format) => {
+                let format = match format {
+                    Some(format) => format,
+                    None => self.config.editor.datetime_format.as_str(),
+                };
+                let text = crate::utils::datetime::format_now(format);
+                self.insert_into_active(&text);
+            }
+
+            EditorMessage::Tab { document_id, backwards } => {
+                return self.handle_tab_key(document_id, backwards);
+            }
+
+            EditorMessage::Newline { document_id } => {
+                // Snippet tab-stops only follow edits made through the editor
+                let in_snippet = self
+                    .state
+                    .get_document(document_id)
+                    .is_some_and(|doc| doc.editor_state.snippet.is_some());
+                if !self.config.editor.auto_indent
+                    || in_snippet
+                    || Some(document_id) != self.state.active_document
+                {
+                    return self.handle_editor_message(EditorMessage::TextEditorAction {
+                        document_id,
+                        action: text_editor::Action::Edit(text_editor::Edit::Enter),
+                    });
+                }
+                self.apply_buffer_operation(|buffer, range| buffer.auto_indent_newline(range));
+            }
+
+            EditorMessage::MoveCursor { document_id, motion, select } => {
+                self.scroll_animation = None;
+                self.move_cursor(document_id, motion, select);
+            }
+
+            EditorMessage::Surround { document_id, open } => {
+                let selected = self
+                    .editor_contents
+                    .get(&document_id)
+                    .and_then(|content| content.selection())
+                    .is_some_and(|selection| !selection.is_empty());
+                let close = self.config.editor.surround_pairs.get(&open).copied();
+                match close {
+                    Some(close) if selected && Some(document_id) == self.state.active_document => {
+                        self.apply_buffer_operation(|buffer, range| {
+                            crate::editor::formatting::surround(buffer, range, open, close)
+                        });
+                    }
+                    _ => {
+                        return self.handle_editor_message(EditorMessage::TextEditorAction {
+                            document_id,
+                            action: text_editor::Action::Edit(text_editor::Edit::Insert(open)),
+                        });
+                    }
+                }
+            }
+
+            EditorMessage::ToggleReadOnly => {
+                if let Some(doc) = self.state.active_document_mut() {
+                    let locked_on_disk = doc.read_only
+                        && doc
+                            .path
+                            .as_ref()
+                            .is_some_and(|p| crate::file_handler::FileInfo::from_path(p).is_readonly);
+
+                    if locked_on_disk {
+                        self.state.set_status(
+                            fl!("status-read-only-on-disk"),
+                            crate::state::StatusLevel::Warning,
+                        );
+                    } else {
+                        doc.set_read_only(!doc.read_only);
+                        let message = if doc.read_only {
+                            fl!("status-read-only-enabled")
+                        } else {
+                            fl!("status-read-only-disabled")
+                        };
+                        self.state.set_status(message, crate::state::StatusLevel::Info);
+                    }
+                }
+            }
+
+            EditorMessage::ToggleBookmark => {
+                let Some(doc) = self.state.active_document_mut() else {
+                    return Task::none();
+                };
+                let id = doc.id;
+                let line = doc.editor_state.cursor.line;
+                let message = if doc.editor_state.toggle_bookmark(line) {
+                    fl!("status-bookmark-added", line = line + 1)
+                } else {
+                    fl!("status-bookmark-removed", line = line + 1)
+                };
+                self.state.set_status(message, crate::state::StatusLevel::Info);
+                self.remember_bookmarks(id);
+            }
+
+            EditorMessage::NextBookmark => self.jump_to_bookmark(true),
+
+            EditorMessage::PreviousBookmark => self.jump_to_bookmark(false),
+
+            EditorMessage::FileDragOver { document_id, position } => {
+                self.state.drop_target = match position {
+                    Some(point) => Some((document_id, point)),
+                    // Leaving one editor for the other pane's keeps the other
+                    None => self.state.drop_target.filter(|(id, _)| *id != document_id),
+                };
+            }
+
+            EditorMessage::ClearBookmarks => {
+                let Some(doc) = self.state.active_document_mut() else {
+                    return Task::none();
+                };
+                let id = doc.id;
+                doc.editor_state.bookmarks.clear();
+                self.state.set_status(fl!("status-bookmarks-cleared"), crate::state::StatusLevel::Info);
+                self.remember_bookmarks(id);
+            }
+
+            EditorMessage::OverrideReadOnly => {
+                if let Some(doc) = self.state.active_document_mut() {
+                    let result = match &doc.path {
+                        Some(path) => crate::file_handler::make_writable(path),
+                        None => Ok(()),
+                    };
+
+                    match result {
+                        Ok(()) => {
+                            doc.set_read_only(false);
+                            let message = fl!("status-now-writable", name = doc.display_name.clone());
+                            self.state.set_status(message, crate::state::StatusLevel::Info);
+                        }
+                        Err(e) => {
+                            log::error!("Failed to make {} writable: {}", doc.display_name, e);
+                            self.state.set_status(
+                                fl!("status-override-failed", error = e.to_string()),
+                                crate::state::StatusLevel::Error,
+                            );
+                        }
+                    }
                 }
             }
 
@@ -696,7 +3817,27 @@ impl CosmicNotebook {
     fn handle_clipboard_message(&mut self, msg: ClipboardMessage) -> Task<Message> {
         use cosmic::widget::text_editor::{Action, Edit};
         use std::sync::Arc;
-        
+
+        // Cut and paste modify the document
+        if matches!(
+            msg,
+            ClipboardMessage::Cut
+                | ClipboardMessage::Paste
+                | ClipboardMessage::PastePlain
+                | ClipboardMessage::PasteAsMarkdown
+                | ClipboardMessage::PasteImageFromUrl
+                | ClipboardMessage::PasteHistoryEntry(_)
+                | ClipboardMessage::Content(_)
+                | ClipboardMessage::LinkTitleFetched { .. }
+                | ClipboardMessage::ImageDownloaded { .. }
+        ) {
+            if let Some(id) = self.state.active_document {
+                if self.edit_blocked(id) {
+                    return Task::none();
+                }
+            }
+        }
+
         match msg {
             ClipboardMessage::Cut => {
                 // Get selection from text_editor widget, copy to clipboard, then delete
@@ -708,7 +3849,7 @@ impl CosmicNotebook {
                             if let Err(e) = crate::editor::copy_text(&selected_text) {
                                 log::error!("Failed to copy to clipboard: {}", e);
                                 self.state.set_status(
-                                    "Failed to copy to clipboard".to_string(),
+                                    fl!("status-copy-failed"),
                                     crate::state::StatusLevel::Error,
                                 );
                             } else {
@@ -718,21 +3859,21 @@ impl CosmicNotebook {
                                 // Sync content back to document
                                 let new_text = content.text();
                                 if let Some(doc) = self.state.get_document_mut(doc_id) {
-                                    doc.content = ropey::Rope::from_str(&new_text);
+                                    doc.set_text(&new_text);
                                     doc.mark_modified();
                                     let title = doc.title();
-                                    self.state.tabs.update_title(doc_id, title);
+                                    self.state.update_tab_title(doc_id, title);
                                 }
                                 
                                 self.state.set_status(
-                                    format!("Cut {} characters", selected_text.len()),
+                                    fl!("status-cut", count = selected_text.len()),
                                     crate::state::StatusLevel::Info,
                                 );
                                 self.update_window_title();
                             }
                         } else {
                             self.state.set_status(
-                                "Nothing selected to cut".to_string(),
+                                fl!("status-nothing-to-cut"),
                                 crate::state::StatusLevel::Info,
                             );
                         }
@@ -748,18 +3889,18 @@ impl CosmicNotebook {
                             if let Err(e) = crate::editor::copy_text(&selected_text) {
                                 log::error!("Failed to copy to clipboard: {}", e);
                                 self.state.set_status(
-                                    "Failed to copy to clipboard".to_string(),
+                                    fl!("status-copy-failed"),
                                     crate::state::StatusLevel::Error,
                                 );
                             } else {
                                 self.state.set_status(
-                                    format!("Copied {} characters", selected_text.len()),
+                                    fl!("status-copied", count = selected_text.len()),
                                     crate::state::StatusLevel::Info,
                                 );
                             }
                         } else {
                             self.state.set_status(
-                                "Nothing selected to copy".to_string(),
+                                fl!("status-nothing-to-copy"),
                                 crate::state::StatusLevel::Info,
                             );
                         }
@@ -767,10 +3908,148 @@ impl CosmicNotebook {
                 }
             }
 
+            ClipboardMessage::CopyAsHtml => {
+                let selection = self
+                    .state
+                    .active_document
+                    .and_then(|id| self.editor_contents.get(&id))
+                    .and_then(|content| content.selection());
+                let Some(selected_text) = selection else {
+                    self.state.set_status(
+                        fl!("status-nothing-to-copy"),
+                        crate::state::StatusLevel::Info,
+                    );
+                    return Task::none();
+                };
+                let html = self.renderer.render_html_fragment(&selected_text);
+                let result = crate::editor::copy_html(&selected_text, &html).or_else(|e| {
+                    // Some clipboards take no HTML; the markdown still helps
+                    log::warn!("Failed to copy HTML, copying text only: {}", e);
+                    crate::editor::copy_text(&selected_text)
+                });
+                match result {
+                    Ok(()) => self.state.set_status(
+                        fl!("status-copied-html", count = selected_text.len()),
+                        crate::state::StatusLevel::Info,
+                    ),
+                    Err(e) => {
+                        log::error!("Failed to copy to clipboard: {}", e);
+                        self.state.set_status(
+                            fl!("status-copy-failed"),
+                            crate::state::StatusLevel::Error,
+                        );
+                    }
+                }
+            }
+
+            ClipboardMessage::CopySelectionHtml => {
+                let Some(content) = self.state.active_document.and_then(|id| self.editor_contents.get(&id))
+                else {
+                    return Task::none();
+                };
+                // Without a selection the whole document is copied
+                let (markdown, message) = match content.selection() {
+                    Some(selected_text) => {
+                        let message = fl!("status-copied-html-source", count = selected_text.len());
+                        (selected_text, message)
+                    }
+                    None => (content.text(), fl!("status-copied-document-html-source")),
+                };
+
+                let exporter = crate::markdown::MarkdownExporter::with_options(&self.config.markdown)
+                    .with_soft_break_as_newline(self.config.preview.soft_break_as_newline)
+                    .with_notes(self.notes.clone());
+                let options = crate::markdown::HtmlExportOptions {
+                    include_styles: false,
+                    ..Default::default()
+                };
+                let html = exporter.export_html_fragment(&markdown, &options);
+                match crate::editor::copy_text(&html) {
+                    Ok(()) => self.state.set_status(message, crate::state::StatusLevel::Info),
+                    Err(e) => {
+                        log::error!("Failed to copy to clipboard: {}", e);
+                        self.state.set_status(
+                            fl!("status-copy-failed"),
+                            crate::state::StatusLevel::Error,
+                        );
+                    }
+                }
+            }
+
+            ClipboardMessage::CopyHeadingLink { with_path } => {
+                let Some(doc_id) = self.state.active_document else {
+                    return Task::none();
+                };
+                let Some(content) = self.editor_contents.get(&doc_id) else {
+                    return Task::none();
+                };
+                let (line, _) = content.cursor_position();
+                let exporter = crate::markdown::MarkdownExporter::with_options(&self.config.markdown);
+                let Some(anchor) = exporter.heading_anchor_at_line(&content.text(), line) else {
+                    self.state.set_status(
+                        fl!("status-not-on-heading"),
+                        crate::state::StatusLevel::Info,
+                    );
+                    return Task::none();
+                };
+
+                let link = if with_path {
+                    let Some(path) = self.state.get_document(doc_id).and_then(|d| d.path.clone()) else {
+                        self.state.set_status(
+                            fl!("status-save-for-link-path"),
+                            crate::state::StatusLevel::Info,
+                        );
+                        return Task::none();
+                    };
+                    // Relative to the open folder when the file is inside it
+                    let path = self
+                        .state
+                        .sidebar
+                        .root
+                        .as_deref()
+                        .and_then(|root| path.strip_prefix(root).ok())
+                        .unwrap_or(path.as_path())
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                        .replace(' ', "%20");
+                    format!("{}#{}", path, anchor)
+                } else {
+                    format!("#{}", anchor)
+                };
+
+                match crate::editor::copy_text(&link) {
+                    Ok(()) => self.state.set_status(
+                        fl!("status-copied-link", link = link.clone()),
+                        crate::state::StatusLevel::Info,
+                    ),
+                    Err(e) => {
+                        log::error!("Failed to copy to clipboard: {}", e);
+                        self.state.set_status(
+                            fl!("status-copy-failed"),
+                            crate::state::StatusLevel::Error,
+                        );
+                    }
+                }
+            }
+
+            ClipboardMessage::CopyPath { document_id, kind } => {
+                self.copy_document_path(document_id.or(self.state.active_document), kind);
+            }
+
             ClipboardMessage::Paste => {
+                // With the history picker open, paste its highlighted entry
+                if let Some(picker) = &self.state.clipboard_picker {
+                    let index = picker.selected;
+                    return self.handle_clipboard_message(ClipboardMessage::PasteHistoryEntry(index));
+                }
                 // Get text from system clipboard and paste into text_editor
                 match crate::editor::paste_text() {
                     Ok(text) => {
+                        if self.config.editor.smart_paste {
+                            if let Some(task) = self.smart_paste(&text) {
+                                return task;
+                            }
+                        }
                         if let Some(doc_id) = self.state.active_document {
                             if let Some(content) = self.editor_contents.get_mut(&doc_id) {
                                 // Use text_editor's Paste action
@@ -779,14 +4058,14 @@ impl CosmicNotebook {
                                 // Sync content back to document
                                 let new_text = content.text();
                                 if let Some(doc) = self.state.get_document_mut(doc_id) {
-                                    doc.content = ropey::Rope::from_str(&new_text);
+                                    doc.set_text(&new_text);
                                     doc.mark_modified();
                                     let title = doc.title();
-                                    self.state.tabs.update_title(doc_id, title);
+                                    self.state.update_tab_title(doc_id, title);
                                 }
                                 
                                 self.state.set_status(
-                                    format!("Pasted {} characters", text.len()),
+                                    fl!("status-pasted", count = text.len()),
                                     crate::state::StatusLevel::Info,
                                 );
                                 self.update_window_title();
@@ -795,14 +4074,14 @@ impl CosmicNotebook {
                     }
                     Err(crate::editor::ClipboardError::Empty) => {
                         self.state.set_status(
-                            "Clipboard is empty".to_string(),
+                            fl!("status-clipboard-empty"),
                             crate::state::StatusLevel::Info,
                         );
                     }
                     Err(e) => {
                         log::error!("Failed to paste from clipboard: {}", e);
                         self.state.set_status(
-                            "Failed to paste from clipboard".to_string(),
+                            fl!("status-paste-failed"),
                             crate::state::StatusLevel::Error,
                         );
                     }
@@ -817,14 +4096,14 @@ impl CosmicNotebook {
                         
                         let new_text = content.text();
                         if let Some(doc) = self.state.get_document_mut(doc_id) {
-                            doc.content = ropey::Rope::from_str(&new_text);
+                            doc.set_text(&new_text);
                             doc.mark_modified();
                             let title = doc.title();
-                            self.state.tabs.update_title(doc_id, title);
+                            self.state.update_tab_title(doc_id, title);
                         }
                         
                         self.state.set_status(
-                            format!("Pasted {} characters", text.len()),
+                            fl!("status-pasted", count = text.len()),
                             crate::state::StatusLevel::Info,
                         );
                         self.update_window_title();
@@ -832,10 +4111,100 @@ impl CosmicNotebook {
                 }
             }
 
+            ClipboardMessage::PastePlain => self.paste_plain(),
+
+            ClipboardMessage::PasteAsMarkdown => {
+                let markdown = crate::editor::paste_html()
+                    .ok()
+                    .and_then(|html| crate::markdown::html_to_markdown(&html));
+                match markdown {
+                    Some(markdown) => {
+                        self.insert_into_active(&markdown);
+                        self.state.set_status(
+                            fl!("status-pasted-markdown"),
+                            crate::state::StatusLevel::Info,
+                        );
+                    }
+                    // No HTML on the clipboard, or HTML too broken to convert
+                    None => self.paste_plain(),
+                }
+            }
+
+            ClipboardMessage::PasteImageFromUrl => return self.paste_image_from_url(),
+
+            ClipboardMessage::PasteFromHistory => match self.state.clipboard_picker.as_mut() {
+                Some(picker) => picker.select_next(),
+                None => {
+                    let entries = crate::editor::clipboard().history();
+                    if entries.is_empty() {
+                        self.state.set_status(
+                            fl!("status-clipboard-history-empty"),
+                            crate::state::StatusLevel::Info,
+                        );
+                    } else {
+                        // The latest copy is what Paste gives, so start one older
+                        let selected = usize::from(entries.len() > 1);
+                        self.state.clipboard_picker =
+                            Some(crate::state::ClipboardPicker { entries, selected });
+                    }
+                }
+            },
+
+            ClipboardMessage::PasteHistoryEntry(index) => {
+                let entry = self
+                    .state
+                    .clipboard_picker
+                    .take()
+                    .and_then(|picker| picker.entries.into_iter().nth(index));
+                if let Some(text) = entry {
+                    self.insert_into_active(&text);
+                    self.state.set_status(
+                        fl!("status-pasted-history", count = text.len()),
+                        crate::state::StatusLevel::Info,
+                    );
+                }
+            }
+
+            ClipboardMessage::CloseHistory => {
+                self.state.clipboard_picker = None;
+            }
+
+            ClipboardMessage::LinkTitleFetched { document_id, url, title } => {
+                // The paste is dropped if the user switched documents meanwhile
+                if self.state.active_document != Some(document_id) {
+                    return Task::none();
+                }
+                let text = match title {
+                    Some(title) => crate::editor::links::markdown_link(&title, &url),
+                    None => url,
+                };
+                self.insert_into_active(&text);
+                self.state.set_status(
+                    fl!("status-pasted", count = text.len()),
+                    crate::state::StatusLevel::Info,
+                );
+            }
+
+            ClipboardMessage::ImageDownloaded { document_id, url, result } => {
+                // The image goes where it was pasted, even after switching
+                // tabs, unless that document was closed
+                if !self.editor_contents.contains_key(&document_id) {
+                    self.state.set_status(
+                        fl!("status-image-url-closed"),
+                        crate::state::StatusLevel::Warning,
+                    );
+                    return Task::none();
+                }
+                self.insert_downloaded_image(document_id, &url, result);
+            }
+
             ClipboardMessage::Error(error) => {
                 log::error!("Clipboard error: {}", error);
-                self.state
-                    .set_status("Clipboard error".to_string(), crate::state::StatusLevel::Error);
+                self.state.set_status_with_details(
+                    fl!("status-clipboard-error"),
+                    crate::state::StatusLevel::Error,
+                    error,
+                );
             }
         }
         Task::none()
@@ -843,39 +4212,68 @@ impl CosmicNotebook {
 
     /// Handle search-related messages
     fn handle_search_message(&mut self, msg: SearchMessage) -> Task<Message> {
-        match msg {
-            SearchMessage::OpenFind => {
-                self.state.find_dialog_open = true;
-                self.state.find_replace_open = false;
+        if matches!(
+            msg,
+            SearchMessage::Replace | SearchMessage::ReplaceAll | SearchMessage::ConfirmReplaceAll
+        ) {
+            if let Some(id) = self.state.active_document {
+                if self.edit_blocked(id) {
+                    return Task::none();
+                }
             }
+            // Replacing commits the search where it is
+            self.state.find_origin = None;
+        }
 
-            SearchMessage::OpenFindReplace => {
-                self.state.find_dialog_open = true;
-                self.state.find_replace_open = true;
-            }
+        match msg {
+            SearchMessage::OpenFind => return self.open_find_bar(false),
+
+            SearchMessage::OpenFindReplace => return self.open_find_bar(true),
 
             SearchMessage::CloseFind => {
                 self.state.find_dialog_open = false;
                 self.state.find_replace_open = false;
+                self.state.find_selection = None;
+                self.state.find_origin = None;
+            }
+
+            SearchMessage::CancelFind => {
+                if let Some((id, origin)) = self.state.find_origin.take() {
+                    self.place_cursor(id, origin);
+                }
+                return self.handle_search_message(SearchMessage::CloseFind);
+            }
+
+            SearchMessage::Commit => {
+                // The first Enter keeps the previewed match; later ones move on
+                if self.state.find_origin.take().is_none() {
+                    return self.handle_search_message(SearchMessage::FindNext);
+                }
             }
 
             SearchMessage::UpdateQuery(query) => {
                 self.state.find_query = query.clone();
+                if self.config.search.remember_queries {
+                    if let Some(path) = self.state.active_document().and_then(|d| d.path.clone()) {
+                        self.session.remember_find_query(path, &query);
+                    }
+                }
                 
                 // Perform search in active document
                 if let Some(doc) = self.state.active_document() {
                     let content = doc.content_str();
-                    let options = crate::search::FindOptions {
-                        case_sensitive: self.state.find_case_sensitive,
-                        whole_word: self.state.find_whole_word,
-                        use_regex: self.state.find_use_regex,
-                        wrap_around: true,
-                    };
+                    let options = self.find_options();
                     
                     let mut engine = crate::search::SearchEngine::new();
                     let results = engine.find_all(&content, &query, &options);
+                    if results.len() >= options.max_results {
+                        self.state.set_status(
+                            fl!("status-search-truncated", count = options.max_results),
+                            crate::state::StatusLevel::Warning,
+                        );
+                    }
                     
-                    // Store results in editor state
+                    // Store results in editor state (character offsets)
                     if let Some(doc) = self.state.active_document_mut() {
                         doc.editor_state.find_results = results.iter()
                             .map(|r| (r.start, r.end))
@@ -887,6 +4285,7 @@ impl CosmicNotebook {
                         };
                     }
                 }
+                self.preview_find_match(query.is_empty());
             }
 
             SearchMessage::UpdateReplaceText(text) => {
@@ -895,6 +4294,7 @@ impl CosmicNotebook {
 
             SearchMessage::ToggleCaseSensitive => {
                 self.state.find_case_sensitive = !self.state.find_case_sensitive;
+                self.save_find_options();
                 // Re-run search with new options
                 if !self.state.find_query.is_empty() {
                     return Task::done(Self::app_message(Message::Search(
@@ -905,6 +4305,7 @@ impl CosmicNotebook {
 
             SearchMessage::ToggleWholeWord => {
                 self.state.find_whole_word = !self.state.find_whole_word;
+                self.save_find_options();
                 if !self.state.find_query.is_empty() {
                     return Task::done(Self::app_message(Message::Search(
                         SearchMessage::UpdateQuery(self.state.find_query.clone()),
@@ -914,6 +4315,28 @@ impl CosmicNotebook {
 
             SearchMessage::ToggleRegex => {
                 self.state.find_use_regex = !self.state.find_use_regex;
+                self.save_find_options();
+                if !self.state.find_query.is_empty() {
+                    return Task::done(Self::app_message(Message::Search(
+                        SearchMessage::UpdateQuery(self.state.find_query.clone()),
+                    )));
+                }
+            }
+
+            SearchMessage::ToggleInSelection => {
+                let pinned = self.state.find_selection.as_ref().map(|(id, _)| *id);
+                if !self.state.find_in_selection && pinned != self.state.active_document {
+                    // Nothing pinned for this document; take what is selected now
+                    self.pin_find_selection();
+                    if self.state.find_selection.is_none() {
+                        self.state.set_status(
+                            fl!("status-find-no-selection"),
+                            crate::state::StatusLevel::Info,
+                        );
+                        return Task::none();
+                    }
+                }
+                self.state.find_in_selection = !self.state.find_in_selection;
                 if !self.state.find_query.is_empty() {
                     return Task::done(Self::app_message(Message::Search(
                         SearchMessage::UpdateQuery(self.state.find_query.clone()),
@@ -923,27 +4346,41 @@ impl CosmicNotebook {
 
             SearchMessage::FindNext => {
                 if let Some(doc) = self.state.active_document_mut() {
-                    if let Some((start, _end)) = doc.editor_state.next_find_result() {
-                        // Move cursor to the match
-                        let (line, col) = {
-                            let line = doc.content.char_to_line(start);
-                            let line_start = doc.content.line_to_char(line);
-                            (line, start - line_start)
-                        };
-                        doc.editor_state.set_cursor(crate::state::CursorPosition::new(line, col));
+                    let before = doc.editor_state.current_find_index;
+                    if let Some((start, end)) = doc.editor_state.next_find_result() {
+                        let wrapped =
+                            before.is_some_and(|i| doc.editor_state.current_find_index <= Some(i));
+                        // Find results are character offsets, like the rope
+                        let cursor = crate::state::CursorPosition::from_char_offset(&doc.content, start);
+                        doc.editor_state.set_cursor(cursor);
+                        let id = doc.id;
+                        self.jump_to_range(id, start, end);
+                        if wrapped {
+                            self.state.set_status(
+                                fl!("status-search-wrapped"),
+                                crate::state::StatusLevel::Info,
+                            );
+                        }
                     }
                 }
             }
 
             SearchMessage::FindPrevious => {
                 if let Some(doc) = self.state.active_document_mut() {
-                    if let Some((start, _end)) = doc.editor_state.prev_find_result() {
-                        let (line, col) = {
-                            let line = doc.content.char_to_line(start);
-                            let line_start = doc.content.line_to_char(line);
-                            (line, start - line_start)
-                        };
-                        doc.editor_state.set_cursor(crate::state::CursorPosition::new(line, col));
+                    let before = doc.editor_state.current_find_index;
+                    if let Some((start, end)) = doc.editor_state.prev_find_result() {
+                        let wrapped =
+                            before.is_some_and(|i| doc.editor_state.current_find_index >= Some(i));
+                        let cursor = crate::state::CursorPosition::from_char_offset(&doc.content, start);
+                        doc.editor_state.set_cursor(cursor);
+                        let id = doc.id;
+                        self.jump_to_range(id, start, end);
+                        if wrapped {
+                            self.state.set_status(
+                                fl!("status-search-wrapped"),
+                                crate::state::StatusLevel::Info,
+                            );
+                        }
                     }
                 }
             }
@@ -953,57 +4390,101 @@ impl CosmicNotebook {
                 let replacement = self.state.replace_text.clone();
                 let query = self.state.find_query.clone();
                 
-                if let Some(doc) = self.state.active_document_mut() {
-                    if let Some(idx) = doc.editor_state.current_find_index {
-                        if let Some(&(start, end)) = doc.editor_state.find_results.get(idx) {
-                            doc.content.remove(start..end);
-                            doc.content.insert(start, &replacement);
-                            doc.mark_modified();
-                            
-                            // Re-run search after replacement
-                            return Task::done(Self::app_message(Message::Search(
-                                SearchMessage::UpdateQuery(query),
-                            )));
-                        }
+                let Some(doc) = self.state.active_document() else {
+                    return Task::none();
+                };
+                let id = doc.id;
+                let current = doc
+                    .editor_state
+                    .current_find_index
+                    .and_then(|idx| doc.editor_state.find_results.get(idx).copied());
+                let Some((start, end)) = current else {
+                    return Task::none();
+                };
+
+                // The document may have been edited since the search ran
+                let options = self.find_options();
+                let mut engine = crate::search::SearchEngine::new();
+                if engine.is_match_at(&doc.content_str(), &(start..end), &query, &options) {
+                    if let Some(content) = self.editor_contents.get_mut(&id) {
+                        select_content_range(content, start, end);
                     }
+                    self.insert_into_active(&replacement);
+                    self.shift_find_selection(id, end - start, replacement.chars().count());
+                } else {
+                    self.state.set_status(
+                        fl!("status-match-changed"),
+                        crate::state::StatusLevel::Info,
+                    );
                 }
+
+                // Re-run search after replacement
+                return Task::done(Self::app_message(Message::Search(
+                    SearchMessage::UpdateQuery(query),
+                )));
             }
 
             SearchMessage::ReplaceAll => {
-                // Replace all matches
-                if let Some(doc) = self.state.active_document() {
-                    let content = doc.content_str();
-                    let options = crate::search::FindOptions {
-                        case_sensitive: self.state.find_case_sensitive,
-                        whole_word: self.state.find_whole_word,
-                        use_regex: self.state.find_use_regex,
-                        wrap_around: true,
-                    };
-                    
-                    let mut engine = crate::search::SearchEngine::new();
-                    let (new_content, count) = engine.replace_all(
-                        &content,
-                        &self.state.find_query,
-                        &self.state.replace_text,
-                        &options,
-                    );
-                    
-                    if count > 0 {
-                        if let Some(doc) = self.state.active_document_mut() {
-                            doc.content = ropey::Rope::from_str(&new_content);
-                            doc.mark_modified();
-                            doc.editor_state.find_results.clear();
-                            doc.editor_state.current_find_index = None;
-                            
-                            self.state.set_status(
-                                format!("Replaced {} occurrences", count),
-                                crate::state::StatusLevel::Info,
-                            );
-                        }
+                // Count first, asking before changing many matches or, with
+                // a regex, much of the document
+                let Some(id) = self.state.active_document else {
+                    return Task::none();
+                };
+                let Some(content) = self.editor_contents.get(&id) else {
+                    return Task::none();
+                };
+                let text = content.text();
+                let options = self.find_options();
+                let mut engine = crate::search::SearchEngine::new();
+                let (count, matched) =
+                    engine.count_matches(&text, &self.state.find_query, &options);
+                if count == 0 {
+                    return Task::none();
+                }
+
+                // With a regex, measure the text Replace All would really give
+                let after = if options.use_regex {
+                    engine
+                        .replace_all(
+                            &text,
+                            &self.state.find_query,
+                            &self.state.replace_text,
+                            &options,
+                        )
+                        .0
+                        .len()
+                } else {
+                    text.len() - matched + count * self.state.replace_text.len()
+                };
+                let change = text.len().abs_diff(after);
+                let size_change = (options.use_regex
+                    && change >= REPLACE_ALL_SIZE_CHANGE_MIN
+                    && change as f64 > text.len() as f64 * REPLACE_ALL_SIZE_CHANGE_SHARE)
+                    .then_some((text.len(), after));
+                if count > self.config.search.confirm_replace_all_over || size_change.is_some() {
+                    self.state.replace_all_confirm = Some(crate::state::ReplaceAllConfirm {
+                        document_id: id,
+                        count,
+                        size_change,
+                    });
+                } else {
+                    self.replace_all_matches(id);
+                }
+            }
+
+            SearchMessage::ConfirmReplaceAll => {
+                // The document may have been closed while the dialog was open
+                if let Some(confirm) = self.state.replace_all_confirm.take() {
+                    if Some(confirm.document_id) == self.state.active_document {
+                        self.replace_all_matches(confirm.document_id);
                     }
                 }
             }
 
+            SearchMessage::CancelReplaceAll => {
+                self.state.replace_all_confirm = None;
+            }
+
             SearchMessage::ClearResults => {
                 if let Some(doc) = self.state.active_document_mut() {
                     doc.editor_state.clear_find_results();
@@ -1015,35 +4496,269 @@ impl CosmicNotebook {
         Task::none()
     }
 
+    /// Replace all matches in a document, or those in the pinned selection
+    fn replace_all_matches(&mut self, id: DocumentId) {
+        use cosmic::widget::text_editor::{Action, Edit};
+
+        let options = self.find_options();
+        let Some(content) = self.editor_contents.get_mut(&id) else {
+            return;
+        };
+        let old_text = content.text();
+
+        let mut engine = crate::search::SearchEngine::new();
+        let (new_text, count) = engine.replace_all(
+            &old_text,
+            &self.state.find_query,
+            &self.state.replace_text,
+            &options,
+        );
+        if count == 0 {
+            return;
+        }
+
+        // Paste over the changed span so the change is a single
+        // undoable edit
+        let (pos, removed, inserted) = locate_edit(&old_text, &new_text, 0);
+        select_content_range(content, pos, pos + removed);
+        let pasted: String = new_text.chars().skip(pos).take(inserted).collect();
+        content.perform(Action::Edit(Edit::Paste(std::sync::Arc::new(pasted))));
+
+        if let Some(doc) = self.state.get_document_mut(id) {
+            doc.set_text(&new_text);
+            doc.mark_modified();
+            doc.editor_state.find_results.clear();
+            doc.editor_state.current_find_index = None;
+            let title = doc.title();
+            self.state.update_tab_title(id, title);
+        }
+        self.shift_find_selection(id, removed, inserted);
+        self.update_window_title();
+        self.autosave_pending = true;
+        self.state.set_status(
+            fl!("status-replaced", count = count),
+            crate::state::StatusLevel::Info,
+        );
+    }
+
     /// Handle view-related messages
     fn handle_view_message(&mut self, msg: ViewMessage) -> Task<Message> {
         match msg {
             ViewMessage::ToggleSidebar => {
                 self.state.toggle_sidebar();
+                self.remember_sidebar();
             }
 
             ViewMessage::SetSidebarWidth(width) => {
-                self.state.sidebar.width = width;
+                self.state.sidebar.set_width(width);
+                self.remember_sidebar();
+            }
+
+            ViewMessage::SwapSidebarSide => {
+                let position = self.config.view.sidebar_position.other();
+                self.config.view.sidebar_position = position;
+                self.state.sidebar.position = position;
+                if let Err(e) = self.config.save() {
+                    self.state.set_status(
+                        fl!("status-config-save-failed", error = e.to_string()),
+                        StatusLevel::Error,
+                    );
+                }
+            }
+
+            ViewMessage::ToggleRenderWhitespace => {
+                let mode = self.config.editor.render_whitespace.next();
+                self.config.editor.render_whitespace = mode;
+                self.state.render_whitespace = mode;
+                let name = match mode {
+                    RenderWhitespace::None => "none",
+                    RenderWhitespace::Trailing => "trailing",
+                    RenderWhitespace::All => "all",
+                };
+                self.state
+                    .set_status(fl!("status-render-whitespace", mode = name), StatusLevel::Info);
+                if let Err(e) = self.config.save() {
+                    self.state.set_status(
+                        fl!("status-config-save-failed", error = e.to_string()),
+                        StatusLevel::Error,
+                    );
+                }
+            }
+
+            ViewMessage::ToggleLivePreview => {
+                let enabled = !self.config.editor.live_preview;
+                self.config.editor.live_preview = enabled;
+                self.state.live_preview = enabled;
+                let status = if enabled { fl!("status-live-preview-on") } else { fl!("status-live-preview-off") };
+                self.state.set_status(status, StatusLevel::Info);
+                if let Err(e) = self.config.save() {
+                    self.state.set_status(
+                        fl!("status-config-save-failed", error = e.to_string()),
+                        StatusLevel::Error,
+                    );
+                }
+            }
+
+            ViewMessage::ToggleSanitizeHtml => {
+                let enabled = !self.config.markdown.sanitize_html;
+                self.config.markdown.sanitize_html = enabled;
+                self.renderer.set_markdown_options(self.config.markdown);
+                let status = if enabled { fl!("status-sanitize-html-on") } else { fl!("status-sanitize-html-off") };
+                self.state.set_status(status, StatusLevel::Info);
+                if let Err(e) = self.config.save() {
+                    self.state.set_status(
+                        fl!("status-config-save-failed", error = e.to_string()),
+                        StatusLevel::Error,
+                    );
+                }
+                return self.preview_page_task(false);
+            }
+
+            ViewMessage::ToggleWikiLinks => {
+                let enabled = !self.config.markdown.wiki_links;
+                self.config.markdown.wiki_links = enabled;
+                self.renderer.set_markdown_options(self.config.markdown);
+                let preview = self.preview_page_task(false);
+                let status = if enabled { fl!("status-wiki-links-on") } else { fl!("status-wiki-links-off") };
+                self.state.set_status(status, StatusLevel::Info);
+                if let Err(e) = self.config.save() {
+                    self.state.set_status(
+                        fl!("status-config-save-failed", error = e.to_string()),
+                        StatusLevel::Error,
+                    );
+                }
+                // Links resolve against the notes in the open folder
+                if enabled {
+                    return Task::batch([preview, self.scan_notes()]);
+                }
+                return preview;
+            }
+
+            ViewMessage::StartLivePreview => return self.start_preview_server(),
+
+            ViewMessage::StopLivePreview => self.stop_preview_server(),
+
+            ViewMessage::RevealInSidebar(folder) => {
+                self.state.sidebar.reveal(&folder);
+                self.remember_sidebar();
+            }
+
+            ViewMessage::SetSidebarFilter(filter) => {
+                if self.state.sidebar.set_file_filter(filter) {
+                    return self.rescan_sidebar();
+                }
+            }
+
+            ViewMessage::StartSidebarResize => {
+                self.state.sidebar.press_handle(Instant::now());
+                self.remember_sidebar();
+            }
+
+            ViewMessage::SidebarResizeMoved(x) => {
+                if self.state.sidebar.is_resizing() {
+                    let window_width = self.state.window_width;
+                    self.state.sidebar.drag_to(x, window_width);
+                }
+            }
+
+            ViewMessage::MinimapHovered(y) => {
+                self.state.minimap_pointer = y;
+            }
+
+            ViewMessage::MinimapPressed(document_id) => {
+                let y = self.state.minimap_pointer;
+                self.state.minimap_drag = Some(MinimapDrag::new(document_id, y));
+                self.scroll_to_minimap_position(document_id, y);
+            }
+
+            ViewMessage::MinimapDragged(window_y) => {
+                if let Some(drag) = self.state.minimap_drag.as_mut() {
+                    let y = drag.position(window_y);
+                    let document_id = drag.document_id;
+                    self.scroll_to_minimap_position(document_id, y);
+                }
+            }
+
+            ViewMessage::MinimapReleased => {
+                self.state.minimap_drag = None;
+            }
+
+            ViewMessage::EndSidebarResize => {
+                self.state.sidebar.end_resize();
+                self.remember_sidebar();
             }
 
             ViewMessage::ToggleViewMode => {
+                if self.preview_unavailable() {
+                    return Task::none();
+                }
                 self.state.cycle_view_mode();
             }
 
             ViewMessage::SetViewMode(mode) => {
+                if mode != ViewMode::Edit && self.preview_unavailable() {
+                    return Task::none();
+                }
                 self.state.set_view_mode(mode);
             }
 
             ViewMessage::ZoomIn => {
-                self.config.editor.font_size = (self.config.editor.font_size + 1.0).min(48.0);
+                return self.apply_zoom(Some(crate::config::ZOOM_STEP));
             }
 
             ViewMessage::ZoomOut => {
-                self.config.editor.font_size = (self.config.editor.font_size - 1.0).max(8.0);
+                return self.apply_zoom(Some(-crate::config::ZOOM_STEP));
             }
 
             ViewMessage::ZoomReset => {
-                self.config.editor.font_size = 14.0;
+                return self.apply_zoom(None);
+            }
+
+            ViewMessage::ShowChanges => {
+                let open_for_active = self.state.diff_view.is_some()
+                    && self.state.diff_view.as_ref().map(|v| v.document_id) == self.state.active_document;
+                if open_for_active {
+                    self.state.diff_view = None;
+                } else if let Some(id) = self.state.active_document {
+                    return self.diff_task(id);
+                }
+            }
+
+            ViewMessage::HideChanges => {
+                self.state.diff_view = None;
+            }
+
+            ViewMessage::ShowProblems => {
+                self.state.problems_panel_open = !self.state.problems_panel_open;
+                if self.state.problems_panel_open && !self.config.lint.enabled {
+                    self.state.set_status(
+                        fl!("status-style-checks-off"),
+                        crate::state::StatusLevel::Info,
+                    );
+                }
+            }
+
+            ViewMessage::HideProblems => {
+                self.state.problems_panel_open = false;
+            }
+
+            ViewMessage::SplitEditorVertical => {
+                let split = self.state.split_editor(SplitOrientation::Vertical);
+                self.after_split(split);
+            }
+
+            ViewMessage::SplitEditorHorizontal => {
+                let split = self.state.split_editor(SplitOrientation::Horizontal);
+                self.after_split(split);
+            }
+
+            ViewMessage::MoveTabToOtherPane => {
+                let moved = self.state.move_to_other_pane();
+                self.after_split(moved);
+            }
+
+            ViewMessage::UnsplitEditor => {
+                self.state.unsplit_editor();
             }
 
             _ => {}
@@ -1051,19 +4766,96 @@ impl CosmicNotebook {
         Task::none()
     }
 
+    /// Report a split that needs another document, or retitle the window
+    fn after_split(&mut self, split: bool) {
+        if split {
+            self.update_window_title();
+        } else {
+            self.state.set_status(
+                fl!("status-split-needs-document"),
+                crate::state::StatusLevel::Info,
+            );
+        }
+    }
+
+    /// Open the command palette on every menu command, recently used first
+    ///
+    /// Commands that need a document are greyed out when none is open.
+    fn open_command_palette(&mut self) {
+        let has_document = self.state.active_document.is_some();
+        let mut entries: Vec<PaletteEntry> = crate::menu::all_actions()
+            .into_iter()
+            .filter(|(_, action, _)| *action != MenuAction::CommandPalette)
+            .map(|(label, action, bind)| PaletteEntry {
+                label,
+                shortcut: bind.map(|bind| bind.to_string()),
+                action,
+                enabled: has_document || !action.needs_document(),
+            })
+            .collect();
+        let recency = |action: MenuAction| {
+            self.recent_commands
+                .iter()
+                .position(|recent| *recent == action)
+                .unwrap_or(usize::MAX)
+        };
+        entries.sort_by_key(|entry| recency(entry.action));
+        self.state.command_palette = Some(CommandPalette::new(entries));
+    }
+
+    /// Close the palette and run a command chosen from it
+    fn execute_command(&mut self, action: MenuAction) -> Task<Message> {
+        self.state.command_palette = None;
+        self.recent_commands.retain(|recent| *recent != action);
+        self.recent_commands.insert(0, action);
+        self.recent_commands.truncate(MAX_RECENT_COMMANDS);
+        Task::done(Self::app_message(action.to_message()))
+    }
+
     /// Handle dialog-related messages
     fn handle_dialog_message(&mut self, msg: DialogMessage) -> Task<Message> {
         match msg {
             DialogMessage::OpenCommandPalette => {
-                self.state.command_palette_open = true;
+                self.open_command_palette();
+                return cosmic::widget::text_input::focus(ui::COMMAND_PALETTE_INPUT.clone());
             }
 
             DialogMessage::CloseCommandPalette => {
-                self.state.command_palette_open = false;
+                // Escape closes the palette, or else cancels a search
+                if self.state.command_palette.take().is_none() && self.state.find_dialog_open {
+                    return self.handle_search_message(SearchMessage::CancelFind);
+                }
+            }
+
+            DialogMessage::CommandPaletteInput(query) => {
+                if let Some(palette) = self.state.command_palette.as_mut() {
+                    palette.set_query(query);
+                }
+            }
+
+            DialogMessage::CommandPaletteMove(delta) => {
+                if let Some(palette) = self.state.command_palette.as_mut() {
+                    palette.move_selection(delta);
+                }
+            }
+
+            DialogMessage::ExecuteSelectedCommand => {
+                let action = self
+                    .state
+                    .command_palette
+                    .as_ref()
+                    .and_then(|palette| palette.selected_action());
+                if let Some(action) = action {
+                    return self.execute_command(action);
+                }
+            }
+
+            DialogMessage::ExecuteCommand(action) => {
+                return self.execute_command(action);
             }
 
             DialogMessage::CloseDialog => {
-                self.state.command_palette_open = false;
+                self.state.command_palette = None;
                 self.state.find_dialog_open = false;
                 self.state.find_replace_open = false;
             }
@@ -1077,10 +4869,18 @@ impl CosmicNotebook {
     fn handle_system_message(&mut self, msg: SystemMessage) -> Task<Message> {
         match msg {
             SystemMessage::CloseRequested => {
-                if self.state.has_unsaved_changes() {
+                // Scratch documents are kept in the session, so only files need saving
+                let unsaved_files: Vec<DocumentId> = self
+                    .state
+                    .unsaved_documents()
+                    .into_iter()
+                    .filter(|id| self.state.get_document(*id).is_some_and(|d| d.path.is_some()))
+                    .collect();
+
+                if !unsaved_files.is_empty() {
                     // TODO: Show confirmation dialog
                     self.state.quit_requested = true;
-                    self.state.pending_saves = self.state.unsaved_documents();
+                    self.state.pending_saves = unsaved_files;
                 } else {
                     return Task::done(Self::app_message(Message::System(SystemMessage::Quit)));
                 }
@@ -1088,24 +4888,55 @@ impl CosmicNotebook {
 
             SystemMessage::Quit => {
                 // Save session before quitting
+                self.session.update_scratch_documents(self.scratch_documents());
+                let ids: Vec<_> = self.state.documents.keys().copied().collect();
+                for id in ids {
+                    self.remember_bookmarks(id);
+                }
                 if let Err(e) = self.session.save() {
                     log::error!("Failed to save session: {}", e);
                 }
+                self.release_instance_socket();
+                self.stop_preview_server();
                 // Exit application
                 std::process::exit(0);
             }
 
             SystemMessage::ForceQuit => {
+                self.release_instance_socket();
+                self.stop_preview_server();
                 std::process::exit(0);
             }
 
+            SystemMessage::OpenUrl(url) => {
+                if let Err(e) = open::that_detached(&url) {
+                    log::error!("Failed to open {}: {}", url, e);
+                    self.state.set_status_with_details(
+                        fl!("status-open-url-failed", url = url.clone()),
+                        crate::state::StatusLevel::Error,
+                        e.to_string(),
+                    );
+                }
+            }
+
+            SystemMessage::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+                if !modifiers.control() {
+                    self.state.finish_recent_cycle();
+                }
+            }
+
             SystemMessage::WindowResized { width, height } => {
                 self.session.update_window_state(None, Some((width, height)), false);
+                self.state.set_window_size(width, height);
             }
 
             SystemMessage::WindowFocused(focused) => {
                 if focused {
                     // TODO: Check for external file changes
+                    if let Some(id) = self.state.active_document {
+                        return self.git_status_task(id);
+                    }
                 }
             }
 
@@ -1127,6 +4958,34 @@ impl CosmicNotebook {
                 self.state.clear_status();
             }
 
+            SystemMessage::ThemeChanged(is_dark) => {
+                // A fixed theme preference ignores the system setting
+                self.state.system_dark_mode = is_dark;
+                if self.config.ui.theme == crate::config::ThemePreference::System
+                    && self.state.update_dark_mode(self.config.ui.theme)
+                {
+                    self.renderer.set_color_scheme(self.state.dark_mode);
+                }
+            }
+
+            SystemMessage::ToggleStatusDetails => {
+                self.state.toggle_status_details();
+            }
+
+            SystemMessage::ShowSnapshotError => {
+                let error = self.state.active_document().and_then(|doc| match &doc.save_status {
+                    crate::state::SaveStatus::SnapshotFailed(error) => Some(error.clone()),
+                    _ => None,
+                });
+                if let Some(error) = error {
+                    self.state.set_status_with_details(
+                        fl!("status-snapshot-failed"),
+                        crate::state::StatusLevel::Error,
+                        error,
+                    );
+                }
+            }
+
             _ => {}
         }
         Task::none()
@@ -1139,32 +4998,102 @@ impl CosmicNotebook {
                 self.state.sidebar.set_entries(entries);
             }
 
+            InternalMessage::DirectoryListed(listing) => {
+                self.state.sidebar.apply_listing(listing);
+                let dirs = self.state.sidebar.unlisted_expanded();
+                return self.list_directories(dirs);
+            }
+
+            InternalMessage::NotesScanned { root, notes } => {
+                // A scan of a folder that has since been closed
+                if self.state.sidebar.root.as_ref() != Some(&root) {
+                    return Task::none();
+                }
+                self.notes = crate::markdown::NoteIndex::new(Some(root), notes);
+                self.renderer.set_notes(self.notes.clone());
+                return self.preview_page_task(false);
+            }
+
+            InternalMessage::PreviewPageRendered { document_id, render, html, open } => {
+                // The server stopped, moved to another document or has a
+                // newer render on the way
+                let Some((served, server)) = &self.preview_server else {
+                    return Task::none();
+                };
+                if *served != document_id || render != self.preview_render {
+                    return Task::none();
+                }
+                server.update(html);
+                if open {
+                    return Task::done(Self::app_message(Message::System(SystemMessage::OpenUrl(
+                        server.url(),
+                    ))));
+                }
+            }
+
+            InternalMessage::TreeListed { listings, stopped } => {
+                // Collapsing or opening another folder dropped this one
+                if self.state.sidebar.expand_all.is_none() {
+                    return Task::none();
+                }
+                let count = listings.len();
+                self.state.sidebar.apply_tree_listing(listings);
+                if stopped {
+                    self.state.set_status(
+                        fl!("status-expand-all-stopped", count = count),
+                        crate::state::StatusLevel::Warning,
+                    );
+                }
+                let dirs = self.state.sidebar.unlisted_expanded();
+                return self.list_directories(dirs);
+            }
+
             InternalMessage::DirectoryScanError(error) => {
                 log::error!("Directory scan error: {}", error);
                 self.state.sidebar.set_error(error);
             }
 
             InternalMessage::AutosaveTrigger => {
-                // Save recovery files for all modified documents
+                // Save modified documents to disk (when enabled) or to recovery files
                 if self.autosave_pending {
                     let mut save_count = 0;
+                    let mut disk_writes = Vec::new();
+                    let mut skipped = Vec::new();
                     
-                    for (doc_id, doc) in &self.state.documents {
-                        if doc.modified {
+                    for (doc_id, doc) in self.state.documents.iter_mut() {
+                        // Scratch documents have no path to exclude
+                        let excluded = doc
+                            .path
+                            .as_deref()
+                            .is_some_and(|path| self.autosave_exclude.is_match(path));
+                        if doc.modified && !excluded {
                             let content = doc.content_str();
                             let original_path = doc.path.as_deref();
                             let display_name = &doc.display_name;
+
+                            if self.config.files.autosave_to_disk {
+                                if let Some(path) = original_path {
+                                    if Self::can_autosave_to_disk(doc, path) {
+                                        disk_writes.push((*doc_id, path.to_path_buf(), content));
+                                        doc.save_status = crate::state::SaveStatus::Saving;
+                                        continue;
+                                    }
+                                    skipped.push(display_name.clone());
+                                }
+                            }
                             
-                            if let Err(e) = self.recovery_manager.save_recovery(
+                            let result = self.recovery_manager.save_recovery(
                                 &doc_id.to_string(),
                                 &content,
                                 original_path,
                                 display_name,
-                            ) {
+                            );
+                            if let Err(e) = &result {
                                 log::error!("Failed to save recovery for {}: {}", display_name, e);
                             } else {
                                 save_count += 1;
                             }
+                            doc.snapshot_taken(result.map_err(|e| e.to_string()));
                         }
                     }
                     
@@ -1176,18 +5105,141 @@ impl CosmicNotebook {
                     if save_count > 0 {
                         log::debug!("Autosaved {} document(s)", save_count);
                     }
+
+                    if !skipped.is_empty() {
+                        self.state.set_status(
+                            fl!("status-not-autosaved", names = skipped.join(", ")),
+                            crate::state::StatusLevel::Warning,
+                        );
+                    }
                     
                     self.autosave_pending = false;
+
+                    let follow_symlinks = self.config.files.follow_symlinks_on_save;
+                    let tasks: Vec<_> = disk_writes
+                        .into_iter()
+                        .map(|(document_id, path, content)| {
+                            Task::perform(
+                                async move {
+                                    let result = crate::file_handler::write_file_atomic(
+                                        &path,
+                                        &content,
+                                        follow_symlinks,
+                                    )
+                                    .await;
+                                    match result {
+                                        Ok(()) => FileMessage::Autosaved { document_id, path, content },
+                                        Err(e) => FileMessage::SaveError {
+                                            document_id,
+                                            error: e.to_string(),
+                                        },
+                                    }
+                                },
+                                |msg| Self::app_message(Message::File(msg)),
+                            )
+                        })
+                        .collect();
+                    return Task::batch(tasks);
                 }
             }
 
             InternalMessage::ConfigChanged => {
                 // Reload config if needed
                 if let Ok(config) = Config::load() {
+                    self.state.editor_font_size = config.editor.font_size;
+                    self.state.rulers = config.editor.ruler_column.clone();
+                    self.state.surround_pairs = config.editor.surround_pairs.clone();
+                    self.state.render_whitespace = config.editor.render_whitespace;
+                    self.state.preserve_md_hard_breaks = config.editor.preserve_md_hard_breaks;
+                    self.state.line_numbers = config.editor.line_numbering();
+                    self.state.minimap_enabled = config.editor.minimap;
+                    self.state.live_preview = config.editor.live_preview;
+                    self.state.set_preview_font(
+                        &config.editor.preview_font_family,
+                        config.editor.preview_font_size,
+                    );
+                    self.state.show_save_status = config.ui.show_save_status;
+                    let theme_changed = config.ui.theme != self.config.ui.theme;
+                    let lint_changed = config.lint != self.config.lint;
+                    let wiki_links_enabled = config.markdown.wiki_links && !self.config.markdown.wiki_links;
                     self.config = config;
+                    self.apply_sidebar_config();
+                    self.apply_search_config();
+                    // Cached previews are keyed by these, so changes render afresh
+                    self.renderer.set_markdown_options(self.config.markdown);
+                    self.renderer
+                        .set_soft_break_as_newline(self.config.preview.soft_break_as_newline);
+                    crate::editor::clipboard()
+                        .set_history_size(self.config.editor.clipboard_history_size);
+                    self.build_autosave_exclude();
+                    if lint_changed {
+                        let ids: Vec<_> = self.state.documents.keys().copied().collect();
+                        for id in ids {
+                            self.run_linter(id);
+                        }
+                    }
+                    self.refresh_minimaps();
+                    let scan = if wiki_links_enabled {
+                        self.scan_notes()
+                    } else {
+                        Task::none()
+                    };
+                    if theme_changed {
+                        return Task::batch([scan, self.apply_theme()]);
+                    }
+                    return scan;
+                }
+            }
+
+            InternalMessage::DiffComputed { document_id, diff, all_new } => {
+                self.apply_diff(document_id, diff, all_new);
+            }
+
+            InternalMessage::InstanceRequest(request) => {
+                // The launch stopped waiting and started on its own
+                if !request.reply() {
+                    return Task::none();
+                }
+                return Task::batch(request.files.iter().cloned().map(|path| {
+                    Task::done(Self::app_message(Message::File(FileMessage::OpenExternal(path))))
+                }));
+            }
+
+            InternalMessage::ChecksDue { document_id } => {
+                // Only the timer from the last edit of a burst runs
+                let due = self
+                    .check_debouncers
+                    .get_mut(&document_id)
+                    .is_some_and(|debouncer| debouncer.fire_trailing());
+                if due {
+                    self.run_linter(document_id);
+                    self.refresh_minimap(document_id);
+                    return self.link_check_task(document_id);
+                }
+            }
+
+            InternalMessage::LinksChecked { document_id, diagnostics } => {
+                if let Some(doc) = self.state.get_document_mut(document_id) {
+                    doc.link_diagnostics = diagnostics;
                 }
             }
 
+            InternalMessage::DiagramsRendered { document_id, diagrams } => {
+                if let Some(doc) = self.state.get_document_mut(document_id) {
+                    doc.diagrams = diagrams;
+                }
+            }
+
+            InternalMessage::GitStatusComputed { document_id, changes } => {
+                if let Some(doc) = self.state.get_document_mut(document_id) {
+                    doc.git_changes = changes.unwrap_or_default();
+                }
+            }
+
+            InternalMessage::ScrollAnimationTick => self.step_scroll_animation(),
+
+            InternalMessage::WatchTick => self.check_watched_files(),
+
             _ => {}
         }
         Task::none()
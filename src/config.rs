@@ -1,11 +1,12 @@
 //! Configuration management for Cosmic Notebook
 //!
 //! Handles loading, saving, and managing application configuration.
-//! Configuration is persisted using cosmic-config for integration with COSMIC desktop.
+//! Configuration is saved as JSON in the application's config directory.
 
 use crate::error::{ConfigError, ConfigResult};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 /// Application identifier following reverse-DNS convention
 pub const APP_ID: &str = "com.cosmic.Notebook";
@@ -31,12 +32,54 @@ pub const WARNING_FILE_SIZE: u64 = 1024 * 1024;
 /// Autosave interval in seconds
 pub const DEFAULT_AUTOSAVE_INTERVAL: u64 = 60;
 
+/// Seconds before info and warning status messages are dismissed
+pub const DEFAULT_STATUS_TIMEOUT: u64 = 4;
+
+/// Seconds before error status messages are dismissed
+pub const DEFAULT_ERROR_STATUS_TIMEOUT: u64 = 10;
+
+/// Default sidebar width in pixels
+pub const DEFAULT_SIDEBAR_WIDTH: u32 = 250;
+
+/// Default narrowest sidebar width in pixels
+pub const DEFAULT_SIDEBAR_MIN_WIDTH: u32 = 150;
+
+/// Default widest sidebar width in pixels
+pub const DEFAULT_SIDEBAR_MAX_WIDTH: u32 = 600;
+
 /// Maximum number of recent files to remember
 pub const MAX_RECENT_FILES: usize = 20;
 
 /// Maximum undo history entries
 pub const MAX_UNDO_HISTORY: usize = 1000;
 
+/// Default number of copies kept in the clipboard history
+pub const DEFAULT_CLIPBOARD_HISTORY_SIZE: usize = 20;
+
+/// Default number of matches Replace All changes without asking
+pub const DEFAULT_CONFIRM_REPLACE_ALL_OVER: usize = 100;
+
+/// Default lines of context kept around the cursor when jumping
+pub const DEFAULT_SCROLL_OFF: usize = 3;
+
+/// Default column width paragraphs are reflowed to
+pub const DEFAULT_REFLOW_WIDTH: usize = 80;
+
+/// Default port the browser preview is served on
+pub const DEFAULT_PREVIEW_PORT: u16 = 8787;
+
+/// Default base font size for the preview in pixels
+pub const DEFAULT_PREVIEW_FONT_SIZE: f32 = 16.0;
+
+/// Minimum zoom level (50%)
+pub const MIN_ZOOM_LEVEL: f32 = 0.5;
+
+/// Maximum zoom level (300%)
+pub const MAX_ZOOM_LEVEL: f32 = 3.0;
+
+/// Zoom level change per zoom in/out step
+pub const ZOOM_STEP: f32 = 0.1;
+
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -51,6 +94,34 @@ pub struct Config {
 
     /// Keyboard shortcuts configuration
     pub keybindings: KeybindingsConfig,
+
+    /// Markdown extension configuration
+    #[serde(default)]
+    pub markdown: MarkdownOptions,
+
+    /// Per-file view configuration
+    #[serde(default)]
+    pub view: ViewConfig,
+
+    /// Markdown linter configuration
+    #[serde(default)]
+    pub lint: LintConfig,
+
+    /// Export configuration
+    #[serde(default)]
+    pub export: ExportConfig,
+
+    /// Preview rendering configuration
+    #[serde(default)]
+    pub preview: PreviewConfig,
+
+    /// Find bar options, remembered between uses
+    #[serde(default)]
+    pub search: SearchConfig,
+
+    /// Open files from later launches as tabs in the running window
+    #[serde(default)]
+    pub single_instance: bool,
 }
 
 impl Default for Config {
@@ -60,22 +131,56 @@ impl Default for Config {
             files: FileConfig::default(),
             ui: UiConfig::default(),
             keybindings: KeybindingsConfig::default(),
+            markdown: MarkdownOptions::default(),
+            view: ViewConfig::default(),
+            lint: LintConfig::default(),
+            export: ExportConfig::default(),
+            preview: PreviewConfig::default(),
+            search: SearchConfig::default(),
+            single_instance: false,
         }
     }
 }
 
 impl Config {
-    /// Load configuration from cosmic-config or return defaults
+    /// Load configuration from the config directory, or the defaults if none is saved
     pub fn load() -> ConfigResult<Self> {
-        // Try to load from cosmic-config
-        // For now, return defaults - will integrate with cosmic-config in Phase 2
-        Ok(Self::default())
+        Self::load_from(&Self::config_file_path()?)
     }
 
-    /// Save configuration to cosmic-config
+    /// Save configuration to the config directory
     pub fn save(&self) -> ConfigResult<()> {
-        // Will integrate with cosmic-config in Phase 2
-        Ok(())
+        self.save_to(&Self::config_file_path()?)
+    }
+
+    /// Load configuration from a file, or the defaults if it doesn't exist
+    pub fn load_from(path: &Path) -> ConfigResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::LoadError(e.to_string()))?;
+
+        serde_json::from_str(&content).map_err(|e| ConfigError::ParseError(e.to_string()))
+    }
+
+    /// Save configuration to a file, creating its directory if needed
+    pub fn save_to(&self, path: &Path) -> ConfigResult<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ConfigError::SaveError(e.to_string()))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| ConfigError::SaveError(e.to_string()))?;
+
+        std::fs::write(path, content).map_err(|e| ConfigError::SaveError(e.to_string()))
+    }
+
+    /// Get the configuration file path
+    fn config_file_path() -> ConfigResult<PathBuf> {
+        Ok(Self::config_dir()?.join("config.json"))
     }
 
     /// Get the configuration directory path
@@ -125,17 +230,33 @@ pub struct EditorConfig {
     /// Use spaces instead of tabs
     pub use_spaces: bool,
 
+    /// Follow the indentation an opened file already uses, falling back to
+    /// `use_spaces` and `tab_width` when it has none or mixes styles
+    #[serde(default = "default_true")]
+    pub detect_indentation: bool,
+
     /// Enable line numbers
     pub show_line_numbers: bool,
 
+    /// How line numbers are counted in the gutter (with `show_line_numbers`
+    /// off they are hidden whatever this says)
+    #[serde(default)]
+    pub line_numbers: LineNumbers,
+
     /// Highlight current line
     pub highlight_current_line: bool,
 
     /// Enable word wrap
     pub word_wrap: bool,
 
-    /// Show whitespace characters
-    pub show_whitespace: bool,
+    /// Which whitespace characters to draw markers for
+    #[serde(default)]
+    pub render_whitespace: RenderWhitespace,
+
+    /// Leave the two trailing spaces of a markdown hard line break
+    /// unmarked when showing trailing whitespace
+    #[serde(default = "default_true")]
+    pub preserve_md_hard_breaks: bool,
 
     /// Auto-indent on new line
     pub auto_indent: bool,
@@ -148,6 +269,97 @@ pub struct EditorConfig {
 
     /// Cursor blink rate in milliseconds (0 to disable)
     pub cursor_blink_rate: u64,
+
+    /// Font family for the preview and HTML export (empty = system default)
+    #[serde(default)]
+    pub preview_font_family: String,
+
+    /// Base font size for the preview and HTML export in pixels
+    #[serde(default = "default_preview_font_size")]
+    pub preview_font_size: f32,
+
+    /// strftime-style format for Insert Date/Time
+    #[serde(default = "default_datetime_format")]
+    pub datetime_format: String,
+
+    /// Wrap heading levels past 6 back to a paragraph
+    #[serde(default)]
+    pub heading_wrap: bool,
+
+    /// Turn a paragraph into a heading when increasing its level
+    #[serde(default = "default_true")]
+    pub heading_create: bool,
+
+    /// Paste a URL over selected text as a markdown link
+    #[serde(default = "default_true")]
+    pub smart_paste: bool,
+
+    /// Fetch the page title when pasting a URL with nothing selected
+    #[serde(default)]
+    pub smart_paste_fetch_title: bool,
+
+    /// Number of copies kept for Paste from History
+    #[serde(default = "default_clipboard_history_size")]
+    pub clipboard_history_size: usize,
+
+    /// Lines of context kept above and below the cursor when jumping
+    #[serde(default = "default_scroll_off")]
+    pub scroll_off: usize,
+
+    /// Animate scrolling when jumping to a line or search match
+    #[serde(default)]
+    pub smooth_scroll: bool,
+
+    /// Treat `_` as part of a word for word motions and selection
+    #[serde(default = "default_true")]
+    pub underscore_in_words: bool,
+
+    /// Columns to draw vertical rulers at, e.g. `[80]` (empty for none)
+    ///
+    /// Columns are measured in monospace character widths, so with a
+    /// proportional font a ruler only roughly marks the column. Rulers are
+    /// drawn as guides with word wrap on too.
+    #[serde(default)]
+    pub ruler_column: Vec<usize>,
+
+    /// Show a minimap of the document at the right edge of the editor
+    #[serde(default)]
+    pub minimap: bool,
+
+    /// Dim markdown markers and draw bold and italic text formatted, except
+    /// on the cursor line
+    #[serde(default)]
+    pub live_preview: bool,
+
+    /// Column width Reflow Paragraph wraps prose to
+    #[serde(default = "default_reflow_width")]
+    pub reflow_width: usize,
+
+    /// Reflow every paragraph of a markdown document when saving it
+    #[serde(default)]
+    pub reflow_on_save: bool,
+
+    /// Characters that surround the selection when typed over it, mapped
+    /// to their closing character (typing with nothing selected is
+    /// unchanged)
+    #[serde(default = "default_surround_pairs")]
+    pub surround_pairs: HashMap<char, char>,
+
+    /// Type curly quotes, dashes and ellipses in place of their ASCII
+    /// forms in markdown documents, except in code
+    #[serde(default)]
+    pub smart_typography: bool,
+}
+
+impl EditorConfig {
+    /// Line numbering to draw in the gutter
+    pub fn line_numbering(&self) -> LineNumbers {
+        if self.show_line_numbers {
+            self.line_numbers
+        } else {
+            LineNumbers::Off
+        }
+    }
 }
 
 impl Default for EditorConfig {
@@ -157,14 +369,35 @@ impl Default for EditorConfig {
             font_size: 14.0,
             tab_width: 4,
             use_spaces: true,
+            detect_indentation: true,
             show_line_numbers: true,
+            line_numbers: LineNumbers::Absolute,
             highlight_current_line: true,
             word_wrap: true,
-            show_whitespace: false,
+            render_whitespace: RenderWhitespace::None,
+            preserve_md_hard_breaks: true,
             auto_indent: true,
             bracket_matching: true,
             max_undo_history: MAX_UNDO_HISTORY,
             cursor_blink_rate: 530,
+            preview_font_family: String::new(),
+            preview_font_size: DEFAULT_PREVIEW_FONT_SIZE,
+            datetime_format: default_datetime_format(),
+            heading_wrap: false,
+            heading_create: true,
+            smart_paste: true,
+            smart_paste_fetch_title: false,
+            clipboard_history_size: DEFAULT_CLIPBOARD_HISTORY_SIZE,
+            scroll_off: DEFAULT_SCROLL_OFF,
+            smooth_scroll: false,
+            underscore_in_words: true,
+            ruler_column: Vec::new(),
+            minimap: false,
+            live_preview: false,
+            reflow_width: DEFAULT_REFLOW_WIDTH,
+            reflow_on_save: false,
+            surround_pairs: default_surround_pairs(),
+            smart_typography: false,
         }
     }
 }
@@ -178,9 +411,22 @@ pub struct FileConfig {
     /// Autosave interval in seconds
     pub autosave_interval: u64,
 
+    /// Autosave documents with a file to that file, not only to recovery
+    #[serde(default)]
+    pub autosave_to_disk: bool,
+
     /// Create backup before saving
     pub create_backups: bool,
 
+    /// Previous versions of a file kept as backups when it is saved, with
+    /// `create_backups` (0 keeps none)
+    #[serde(default)]
+    pub backup_depth: usize,
+
+    /// Where backups of previous versions are kept
+    #[serde(default)]
+    pub backup_location: BackupLocation,
+
     /// Maximum file size to open (in bytes)
     pub max_file_size: u64,
 
@@ -204,6 +450,14 @@ pub struct FileConfig {
 
     /// Directories to ignore when scanning
     pub ignored_directories: Vec<String>,
+
+    /// Save through a symlink to its target instead of replacing the link
+    #[serde(default = "default_true")]
+    pub follow_symlinks_on_save: bool,
+
+    /// Globs of files that autosave and recovery leave alone
+    #[serde(default)]
+    pub autosave_exclude: Vec<String>,
 }
 
 impl Default for FileConfig {
@@ -211,7 +465,10 @@ impl Default for FileConfig {
         Self {
             autosave_enabled: true,
             autosave_interval: DEFAULT_AUTOSAVE_INTERVAL,
+            autosave_to_disk: false,
             create_backups: true,
+            backup_depth: 0,
+            backup_location: BackupLocation::default(),
             max_file_size: MAX_FILE_SIZE,
             default_extension: "md".to_string(),
             visible_extensions: vec!["md".to_string(), "markdown".to_string()],
@@ -229,6 +486,8 @@ impl Default for FileConfig {
                 "build".to_string(),
                 "dist".to_string(),
             ],
+            follow_symlinks_on_save: true,
+            autosave_exclude: Vec::new(),
         }
     }
 }
@@ -248,12 +507,30 @@ pub struct UiConfig {
     /// Sidebar width in pixels
     pub sidebar_width: u32,
 
+    /// Narrowest the sidebar can be resized to, in pixels
+    #[serde(default = "default_sidebar_min_width")]
+    pub sidebar_min_width: u32,
+
+    /// Widest the sidebar can be resized to, in pixels
+    #[serde(default = "default_sidebar_max_width")]
+    pub sidebar_max_width: u32,
+
+    /// Hide the sidebar when its resize handle is dragged well past the
+    /// minimum width
+    #[serde(default = "default_true")]
+    pub sidebar_collapse_on_drag: bool,
+
     /// Default view mode
     pub default_view_mode: ViewMode,
 
     /// Show status bar
     pub show_status_bar: bool,
 
+    /// Show in the status bar whether changes are saved, autosaved to a
+    /// recovery snapshot, or unsaved
+    #[serde(default = "default_true")]
+    pub show_save_status: bool,
+
     /// Show toolbar
     pub show_toolbar: bool,
 
@@ -262,6 +539,19 @@ pub struct UiConfig {
 
     /// Theme preference (follows system by default)
     pub theme: ThemePreference,
+
+    /// Zoom scales editor, preview, and UI text together
+    /// (when false, zoom only changes the editor font size)
+    #[serde(default = "default_true")]
+    pub zoom_scales_ui: bool,
+
+    /// Seconds before info and warning status messages are dismissed (0 keeps them)
+    #[serde(default = "default_status_timeout")]
+    pub status_timeout: u64,
+
+    /// Seconds before error status messages are dismissed (0 keeps them)
+    #[serde(default = "default_error_status_timeout")]
+    pub error_status_timeout: u64,
 }
 
 impl Default for UiConfig {
@@ -270,16 +560,288 @@ impl Default for UiConfig {
             window_width: DEFAULT_WINDOW_WIDTH,
             window_height: DEFAULT_WINDOW_HEIGHT,
             sidebar_visible: true,
-            sidebar_width: 250,
+            sidebar_width: DEFAULT_SIDEBAR_WIDTH,
+            sidebar_min_width: DEFAULT_SIDEBAR_MIN_WIDTH,
+            sidebar_max_width: DEFAULT_SIDEBAR_MAX_WIDTH,
+            sidebar_collapse_on_drag: true,
             default_view_mode: ViewMode::Edit,
             show_status_bar: true,
+            show_save_status: true,
             show_toolbar: true,
             remember_window_state: true,
             theme: ThemePreference::System,
+            zoom_scales_ui: true,
+            status_timeout: DEFAULT_STATUS_TIMEOUT,
+            error_status_timeout: DEFAULT_ERROR_STATUS_TIMEOUT,
+        }
+    }
+}
+
+fn default_sidebar_min_width() -> u32 {
+    DEFAULT_SIDEBAR_MIN_WIDTH
+}
+
+fn default_sidebar_max_width() -> u32 {
+    DEFAULT_SIDEBAR_MAX_WIDTH
+}
+
+fn default_reflow_width() -> usize {
+    DEFAULT_REFLOW_WIDTH
+}
+
+fn default_surround_pairs() -> HashMap<char, char> {
+    HashMap::from([
+        ('(', ')'),
+        ('[', ']'),
+        ('{', '}'),
+        ('"', '"'),
+        ('\'', '\''),
+        ('`', '`'),
+        ('“', '”'),
+        ('‘', '’'),
+        ('«', '»'),
+    ])
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_status_timeout() -> u64 {
+    DEFAULT_STATUS_TIMEOUT
+}
+
+fn default_error_status_timeout() -> u64 {
+    DEFAULT_ERROR_STATUS_TIMEOUT
+}
+
+fn default_preview_font_size() -> f32 {
+    DEFAULT_PREVIEW_FONT_SIZE
+}
+
+fn default_clipboard_history_size() -> usize {
+    DEFAULT_CLIPBOARD_HISTORY_SIZE
+}
+
+fn default_scroll_off() -> usize {
+    DEFAULT_SCROLL_OFF
+}
+
+fn default_datetime_format() -> String {
+    "%Y-%m-%d %H:%M".to_string()
+}
+
+/// Markdown extensions enabled for preview and export
+///
+/// Disabling every extension gives strict CommonMark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownOptions {
+    /// GitHub-style tables
+    pub tables: bool,
+
+    /// Footnote references and definitions
+    pub footnotes: bool,
+
+    /// List footnote definitions that nothing references, after the others
+    pub keep_unreferenced_footnotes: bool,
+
+    /// `~~strikethrough~~` text
+    pub strikethrough: bool,
+
+    /// `- [ ]` task list items
+    pub tasklists: bool,
+
+    /// Curly quotes, dashes, and ellipses
+    pub smart_punctuation: bool,
+
+    /// Pass raw HTML through an allow-list in the preview and exports,
+    /// for notes from untrusted sources
+    pub sanitize_html: bool,
+
+    /// `[[Note Name]]` links to other notes in the open folder
+    pub wiki_links: bool,
+}
+
+impl MarkdownOptions {
+    /// Strict CommonMark with no extensions
+    pub fn commonmark() -> Self {
+        Self {
+            tables: false,
+            footnotes: false,
+            keep_unreferenced_footnotes: true,
+            strikethrough: false,
+            tasklists: false,
+            smart_punctuation: false,
+            sanitize_html: false,
+            wiki_links: false,
+        }
+    }
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            footnotes: true,
+            keep_unreferenced_footnotes: true,
+            strikethrough: true,
+            tasklists: true,
+            smart_punctuation: true,
+            sanitize_html: false,
+            wiki_links: false,
+        }
+    }
+}
+
+/// Style checks run by the markdown linter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LintConfig {
+    /// Run the linter at all
+    pub enabled: bool,
+
+    /// More than one blank line in a row
+    pub multiple_blank_lines: bool,
+
+    /// Headings without a blank line above and below
+    pub blank_around_headings: bool,
+
+    /// Whitespace at the end of a line (two spaces for a line break are allowed)
+    pub trailing_spaces: bool,
+
+    /// Bullet list markers that differ from the first one in the document
+    pub list_marker_style: bool,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            multiple_blank_lines: true,
+            blank_around_headings: true,
+            trailing_spaces: true,
+            list_marker_style: true,
+        }
+    }
+}
+
+/// Default file name template for exports
+pub const DEFAULT_EXPORT_TEMPLATE: &str = "{stem}.{ext}";
+
+/// Export configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Stylesheet added after the default styles in HTML export
+    pub custom_css_path: Option<PathBuf>,
+    /// Directory exports are written to (None = next to the source file);
+    /// a relative path is resolved against the source file's directory
+    pub output_dir: Option<PathBuf>,
+    /// Output file name, with `{stem}`, `{date}` and `{ext}` placeholders
+    pub filename_template: String,
+    /// What to do when the output file already exists
+    pub on_conflict: ExportConflict,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            custom_css_path: None,
+            output_dir: None,
+            filename_template: DEFAULT_EXPORT_TEMPLATE.to_string(),
+            on_conflict: ExportConflict::default(),
+        }
+    }
+}
+
+/// Handling of an export path that already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ExportConflict {
+    /// Append a number: `notes-1.html`, `notes-2.html`, ...
+    #[default]
+    AppendNumber,
+    /// Replace the existing file
+    Overwrite,
+}
+
+/// Preview rendering configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreviewConfig {
+    /// Render a single newline inside a paragraph as a line break, in the
+    /// preview and in exports, instead of joining the lines with a space;
+    /// two trailing spaces always break the line
+    pub soft_break_as_newline: bool,
+
+    /// Port the browser preview is served on; when it is busy a free one
+    /// is picked, and 0 always picks a free one
+    pub server_port: u16,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            soft_break_as_newline: false,
+            server_port: DEFAULT_PREVIEW_PORT,
         }
     }
 }
 
+/// Find bar configuration
+///
+/// The match options are whatever the find bar was last left with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Match case
+    pub case_sensitive: bool,
+    /// Match whole words only
+    pub whole_word: bool,
+    /// Treat the query as a regular expression
+    pub use_regex: bool,
+    /// Fill the find bar with the query last used in the same file
+    pub remember_queries: bool,
+    /// Ask before Replace All changes more matches than this (0 asks
+    /// every time)
+    pub confirm_replace_all_over: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            case_sensitive: false,
+            whole_word: false,
+            use_regex: false,
+            remember_queries: false,
+            confirm_replace_all_over: DEFAULT_CONFIRM_REPLACE_ALL_OVER,
+        }
+    }
+}
+
+/// Per-file view configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewConfig {
+    /// View mode to open files in, keyed by extension without the dot
+    /// (files with no matching entry use `ui.default_view_mode`)
+    pub default_mode_by_extension: HashMap<String, ViewMode>,
+
+    /// Side of the window the sidebar is shown on
+    pub sidebar_position: SidebarPosition,
+}
+
+impl ViewConfig {
+    /// Get the configured view mode for a file, if any
+    pub fn mode_for(&self, path: &Path) -> Option<ViewMode> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        self.default_mode_by_extension
+            .iter()
+            .find(|(key, _)| key.trim_start_matches('.').to_lowercase() == extension)
+            .map(|(_, mode)| *mode)
+    }
+}
+
 /// View mode for the editor
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum ViewMode {
@@ -292,11 +854,93 @@ pub enum ViewMode {
     Split,
 }
 
+/// Where backups of previous file versions are kept
+///
+/// Backups are named `.name.md.bak.N`, with 1 the newest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum BackupLocation {
+    /// Next to the file
+    #[default]
+    Alongside,
+    /// In a hidden `.backups` folder next to the file
+    HiddenFolder,
+}
+
+impl BackupLocation {
+    /// Folder holding the backups of a file in `dir`
+    pub fn backup_dir(self, dir: &std::path::Path) -> PathBuf {
+        match self {
+            BackupLocation::Alongside => dir.to_path_buf(),
+            BackupLocation::HiddenFolder => dir.join(".backups"),
+        }
+    }
+}
+
+/// Which whitespace the editor draws markers for
+///
+/// Spaces are drawn as `·` and tabs as `→`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum RenderWhitespace {
+    /// No markers
+    #[default]
+    None,
+    /// Only whitespace at the end of a line
+    Trailing,
+    /// All spaces and tabs
+    All,
+}
+
+impl RenderWhitespace {
+    /// The next mode when cycling through them
+    pub fn next(self) -> Self {
+        match self {
+            RenderWhitespace::None => RenderWhitespace::Trailing,
+            RenderWhitespace::Trailing => RenderWhitespace::All,
+            RenderWhitespace::All => RenderWhitespace::None,
+        }
+    }
+}
+
+/// How the editor gutter numbers lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum LineNumbers {
+    /// No line numbers
+    Off,
+    /// Each line's own number
+    #[default]
+    Absolute,
+    /// Distance from the cursor line, which shows 0
+    Relative,
+    /// Distance from the cursor line, which shows its own number
+    Hybrid,
+}
+
+/// Side of the window the sidebar is shown on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum SidebarPosition {
+    /// Left of the editor
+    #[default]
+    Left,
+    /// Right of the editor
+    Right,
+}
+
+impl SidebarPosition {
+    /// The opposite side
+    pub fn other(self) -> Self {
+        match self {
+            SidebarPosition::Left => SidebarPosition::Right,
+            SidebarPosition::Right => SidebarPosition::Left,
+        }
+    }
+}
+
 /// Theme preference
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum ThemePreference {
     /// Follow system theme
     #[default]
+    #[serde(alias = "Auto")]
     System,
     /// Always use light theme
     Light,
@@ -304,6 +948,17 @@ pub enum ThemePreference {
     Dark,
 }
 
+impl ThemePreference {
+    /// Check whether to use dark colors, given the system setting
+    pub fn is_dark(self, system_dark: bool) -> bool {
+        match self {
+            ThemePreference::System => system_dark,
+            ThemePreference::Light => false,
+            ThemePreference::Dark => true,
+        }
+    }
+}
+
 /// Keyboard shortcuts configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeybindingsConfig {
@@ -373,7 +1028,7 @@ impl Default for KeybindingsConfig {
             command_palette: "Ctrl+Shift+P".to_string(),
 
             // View operations
-            toggle_sidebar: "Ctrl+B".to_string(),
+            toggle_sidebar: "Ctrl+Shift+B".to_string(),
             toggle_preview: "Ctrl+Shift+V".to_string(),
             zoom_in: "Ctrl+=".to_string(),
             zoom_out: "Ctrl+-".to_string(),
@@ -400,6 +1055,108 @@ mod tests {
         let json = serde_json::to_string(&config).unwrap();
         let deserialized: Config = serde_json::from_str(&json).unwrap();
         assert_eq!(config.editor.font_size, deserialized.editor.font_size);
+        assert_eq!(config.markdown, deserialized.markdown);
+    }
+
+    #[test]
+    fn test_config_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("cosmic-notebook-config-{}", std::process::id()))
+            .join("config.json");
+        assert!(!Config::load_from(&path).unwrap().single_instance);
+
+        let mut config = Config { single_instance: true, ..Config::default() };
+        config.markdown.wiki_links = true;
+        config.save_to(&path).unwrap();
+        let loaded = Config::load_from(&path).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert!(loaded.single_instance);
+        assert!(loaded.markdown.wiki_links);
+    }
+
+    #[test]
+    fn test_search_config_partial() {
+        // Configs saved before the find options were remembered load defaults
+        let mut value = serde_json::to_value(Config::default()).unwrap();
+        value.as_object_mut().unwrap().remove("search");
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(config.search, SearchConfig::default());
+
+        let search: SearchConfig = serde_json::from_str(r#"{"whole_word": true}"#).unwrap();
+        assert!(search.whole_word);
+        assert!(!search.case_sensitive && !search.remember_queries);
+        assert_eq!(search.confirm_replace_all_over, DEFAULT_CONFIRM_REPLACE_ALL_OVER);
+    }
+
+    #[test]
+    fn test_config_missing_new_fields() {
+        // Configs saved before a setting existed load its default
+        let mut value = serde_json::to_value(Config::default()).unwrap();
+        value["ui"].as_object_mut().unwrap().remove("zoom_scales_ui");
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert!(config.ui.zoom_scales_ui);
+
+        let mut value = serde_json::to_value(Config::default()).unwrap();
+        value["editor"].as_object_mut().unwrap().remove("underscore_in_words");
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert!(config.editor.underscore_in_words);
+    }
+
+    #[test]
+    fn test_markdown_options_partial() {
+        let options: MarkdownOptions = serde_json::from_str(r#"{"smart_punctuation": false}"#).unwrap();
+        assert!(options.tables);
+        assert!(!options.smart_punctuation);
+    }
+
+    #[test]
+    fn test_theme_preference() {
+        let auto: ThemePreference = serde_json::from_str(r#""Auto""#).unwrap();
+        assert_eq!(auto, ThemePreference::System);
+        assert!(auto.is_dark(true));
+        assert!(!auto.is_dark(false));
+        assert!(ThemePreference::Dark.is_dark(false));
+        assert!(!ThemePreference::Light.is_dark(true));
+    }
+
+    #[test]
+    fn test_view_mode_by_extension() {
+        let view: ViewConfig = serde_json::from_str(
+            r#"{"default_mode_by_extension": {"md": "Split", ".txt": "Edit"}}"#,
+        )
+        .unwrap();
+        assert_eq!(view.mode_for(Path::new("notes/README.MD")), Some(ViewMode::Split));
+        assert_eq!(view.mode_for(Path::new("todo.txt")), Some(ViewMode::Edit));
+        assert_eq!(view.mode_for(Path::new("Makefile")), None);
+        assert_eq!(view.mode_for(Path::new("data.csv")), None);
+    }
+
+    #[test]
+    fn test_sidebar_position() {
+        assert_eq!(ViewConfig::default().sidebar_position, SidebarPosition::Left);
+        let view: ViewConfig = serde_json::from_str(r#"{"sidebar_position": "Right"}"#).unwrap();
+        assert_eq!(view.sidebar_position, SidebarPosition::Right);
+        assert_eq!(view.sidebar_position.other(), SidebarPosition::Left);
+    }
+
+    #[test]
+    fn test_render_whitespace() {
+        let editor = EditorConfig::default();
+        assert_eq!(editor.render_whitespace, RenderWhitespace::None);
+        assert!(editor.preserve_md_hard_breaks);
+        assert_eq!(RenderWhitespace::None.next(), RenderWhitespace::Trailing);
+        assert_eq!(RenderWhitespace::All.next(), RenderWhitespace::None);
+    }
+
+    #[test]
+    fn test_line_numbering() {
+        let mut editor = EditorConfig::default();
+        assert_eq!(editor.line_numbering(), LineNumbers::Absolute);
+        editor.line_numbers = LineNumbers::Hybrid;
+        assert_eq!(editor.line_numbering(), LineNumbers::Hybrid);
+        editor.show_line_numbers = false;
+        assert_eq!(editor.line_numbering(), LineNumbers::Off);
     }
 
     #[test]
@@ -0,0 +1,84 @@
+//! D-Bus service for opening documents from other applications
+//!
+//! The app owns the `com.cosmic.Notebook` name on the session bus and
+//! serves an interface of the same name at `/com/cosmic/Notebook`:
+//!
+//! - `OpenFile(path)` opens an absolute path
+//! - `OpenUrl(url)` opens a `notebook://` or `file://` URL
+//!
+//! A path that doesn't exist yet opens as a new unsaved document at that
+//! path, so other apps can ask for a note to be created.
+
+use crate::config::APP_ID;
+use crate::utils::path::path_from_url;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+
+/// Object path the service is served at
+pub const OBJECT_PATH: &str = "/com/cosmic/Notebook";
+
+/// The service object, forwarding requested paths to the app
+struct OpenService {
+    requests: mpsc::UnboundedSender<PathBuf>,
+}
+
+impl OpenService {
+    fn forward(&self, path: PathBuf) -> zbus::fdo::Result<()> {
+        self.requests
+            .send(path)
+            .map_err(|_| zbus::fdo::Error::Failed("The app is shutting down".to_string()))
+    }
+}
+
+#[zbus::interface(name = "com.cosmic.Notebook")]
+impl OpenService {
+    /// Open a file, or a new document at a path that doesn't exist yet
+    async fn open_file(&self, path: String) -> zbus::fdo::Result<()> {
+        let path = PathBuf::from(path);
+        if !path.is_absolute() {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "Path must be absolute: {}",
+                path.display()
+            )));
+        }
+        self.forward(path)
+    }
+
+    /// Open a `notebook://` or `file://` URL
+    async fn open_url(&self, url: String) -> zbus::fdo::Result<()> {
+        let path = path_from_url(&url).ok_or_else(|| {
+            zbus::fdo::Error::InvalidArgs(format!("Not a notebook or file URL: {}", url))
+        })?;
+        self.forward(path)
+    }
+}
+
+/// Paths other applications asked to open, for as long as this is kept
+pub struct OpenRequests {
+    /// Connection owning the service name
+    _connection: zbus::Connection,
+    receiver: mpsc::UnboundedReceiver<PathBuf>,
+}
+
+impl OpenRequests {
+    /// Start serving on the session bus
+    ///
+    /// Fails when there is no session bus or another process owns the name.
+    pub async fn serve() -> zbus::Result<Self> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let connection = zbus::connection::Builder::session()?
+            .name(APP_ID)?
+            .serve_at(OBJECT_PATH, OpenService { requests: sender })?
+            .build()
+            .await?;
+        Ok(Self {
+            _connection: connection,
+            receiver,
+        })
+    }
+
+    /// Wait for the next path to open
+    pub async fn next(&mut self) -> Option<PathBuf> {
+        self.receiver.recv().await
+    }
+}
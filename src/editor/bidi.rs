@@ -0,0 +1,206 @@
+//! Bidirectional text support
+//!
+//! Lines mixing left-to-right and right-to-left scripts are stored in
+//! logical order but drawn in visual order, so the character to the left
+//! of the caret on screen is not always the previous one in the buffer.
+//! These helpers use the Unicode Bidirectional Algorithm to translate
+//! between the two for cursor movement and line layout.
+//!
+//! The base direction of a line comes from its first strong character, as
+//! in a paragraph of the Unicode algorithm; lines without one are
+//! left-to-right.
+
+use unicode_bidi::BidiInfo;
+
+/// Reading direction of a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft,
+}
+
+/// Base direction of a line, from its first strong character
+pub fn base_direction(line: &str) -> Direction {
+    let info = BidiInfo::new(line, None);
+    match info.paragraphs.first() {
+        Some(para) if para.level.is_rtl() => Direction::RightToLeft,
+        _ => Direction::LeftToRight,
+    }
+}
+
+/// Caret columns of a line in visual order, left to right
+///
+/// Entry `b` is the column of the caret drawn at the `b`th gap between
+/// characters on screen, so the result has one entry per column. At the
+/// boundary between a left-to-right and a right-to-left run one column
+/// can be drawn at either end of the run; each gap gets the column the
+/// neighbouring runs leave free, so every column appears exactly once.
+pub fn visual_carets(line: &str) -> Vec<usize> {
+    let len = line.chars().count();
+    let info = BidiInfo::new(line, None);
+    if !info.has_rtl() || info.paragraphs.len() != 1 {
+        return (0..=len).collect();
+    }
+
+    let para = &info.paragraphs[0];
+    let (levels, runs) = info.visual_runs(para, para.range.clone());
+    let char_at = |byte: usize| line[..byte].chars().count();
+
+    // Columns at the left and right edge of each run, and a slot for each
+    // gap between characters; gaps inside a run have a single column
+    let mut edges = Vec::with_capacity(runs.len());
+    let mut carets: Vec<Option<usize>> = Vec::with_capacity(len + 1);
+    for run in &runs {
+        let (start, end) = (char_at(run.start), char_at(run.end));
+        let rtl = levels[run.start].is_rtl();
+        let (left, right) = if rtl { (end, start) } else { (start, end) };
+        edges.push((left, right));
+        carets.push(None);
+        if rtl {
+            carets.extend((start + 1..end).rev().map(Some));
+        } else {
+            carets.extend((start + 1..end).map(Some));
+        }
+    }
+    carets.push(None);
+
+    // Candidates for the gaps at run edges: the right edge of the run
+    // before and the left edge of the run after
+    let mut pending: Vec<(usize, Vec<usize>)> = Vec::with_capacity(edges.len() + 1);
+    let mut gap = 0;
+    for (i, &(left, _)) in edges.iter().enumerate() {
+        let mut candidates = vec![left];
+        if i > 0 {
+            candidates.insert(0, edges[i - 1].1);
+        }
+        pending.push((gap, candidates));
+        gap += carets[gap + 1..].iter().take_while(|c| c.is_some()).count() + 1;
+    }
+    if let Some(&(_, right)) = edges.last() {
+        pending.push((gap, vec![right]));
+    }
+
+    let mut used = vec![false; len + 1];
+    for col in carets.iter().flatten() {
+        used[*col] = true;
+    }
+    // Settle gaps with a single free column until none are left; a gap
+    // whose columns are all taken keeps its first candidate
+    while !pending.is_empty() {
+        let settled = pending.iter().position(|(_, candidates)| {
+            let mut free = candidates.iter().filter(|c| !used[**c]);
+            free.next().is_some_and(|first| free.all(|c| c == first))
+        });
+        let (gap, col) = match settled {
+            Some(i) => {
+                let (gap, candidates) = pending.remove(i);
+                let col = *candidates.iter().find(|c| !used[**c]).unwrap();
+                (gap, col)
+            }
+            None => {
+                let (gap, candidates) = pending.remove(0);
+                let col = candidates
+                    .iter()
+                    .copied()
+                    .find(|c| !used[*c])
+                    .unwrap_or(candidates[0]);
+                (gap, col)
+            }
+        };
+        used[col] = true;
+        carets[gap] = Some(col);
+    }
+
+    carets.into_iter().map(|c| c.unwrap_or(len)).collect()
+}
+
+/// Column one character to the left of `column` on screen
+///
+/// Returns None when the caret is already at the left edge of the line.
+pub fn visual_left(line: &str, column: usize) -> Option<usize> {
+    let carets = visual_carets(line);
+    let gap = carets.iter().position(|&c| c == column)?;
+    gap.checked_sub(1).map(|g| carets[g])
+}
+
+/// Column one character to the right of `column` on screen
+///
+/// Returns None when the caret is already at the right edge of the line.
+pub fn visual_right(line: &str, column: usize) -> Option<usize> {
+    let carets = visual_carets(line);
+    let gap = carets.iter().position(|&c| c == column)?;
+    carets.get(gap + 1).copied()
+}
+
+/// Columns at the left and right edge of a line on screen
+pub fn visual_edges(line: &str) -> (usize, usize) {
+    let carets = visual_carets(line);
+    (carets[0], carets[carets.len() - 1])
+}
+
+/// Columns where a line starts and ends in its reading direction
+///
+/// For a right-to-left line the start is the right edge on screen.
+pub fn reading_edges(line: &str) -> (usize, usize) {
+    let (left, right) = visual_edges(line);
+    match base_direction(line) {
+        Direction::LeftToRight => (left, right),
+        Direction::RightToLeft => (right, left),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "abc " followed by the Arabic word "سلام", whose letters are drawn
+    // right to left after the space
+    const MIXED: &str = "abc \u{633}\u{644}\u{627}\u{645}";
+
+    #[test]
+    fn test_base_direction() {
+        assert_eq!(base_direction("hello"), Direction::LeftToRight);
+        assert_eq!(base_direction(MIXED), Direction::LeftToRight);
+        assert_eq!(
+            base_direction("123 \u{627}\u{644}\u{633}\u{644}\u{627}\u{645} abc"),
+            Direction::RightToLeft
+        );
+        assert_eq!(base_direction(""), Direction::LeftToRight);
+    }
+
+    #[test]
+    fn test_visual_carets_ltr() {
+        assert_eq!(visual_carets("abc"), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_visual_carets_mixed() {
+        // The Arabic run reads right to left, so the line's logical end sits
+        // just after the space and its logical start at the right edge
+        assert_eq!(visual_carets(MIXED), vec![0, 1, 2, 3, 8, 7, 6, 5, 4]);
+
+        let mut carets = visual_carets(MIXED);
+        carets.sort_unstable();
+        assert_eq!(carets, (0..=8).collect::<Vec<_>>());
+        assert_eq!(reading_edges(MIXED), (0, 4));
+    }
+
+    #[test]
+    fn test_visual_carets_rtl() {
+        // "שלום abc": the Hebrew word is on the right, the English run on
+        // the left keeps its own order and the line ends after its "c"
+        let line = "\u{5e9}\u{5dc}\u{5d5}\u{5dd} abc";
+        assert_eq!(visual_carets(line), vec![5, 6, 7, 8, 4, 3, 2, 1, 0]);
+        assert_eq!(visual_edges(line), (5, 0));
+        assert_eq!(reading_edges(line), (0, 5));
+    }
+
+    #[test]
+    fn test_visual_steps() {
+        assert_eq!(visual_right(MIXED, 3), Some(8));
+        assert_eq!(visual_right(MIXED, 8), Some(7));
+        assert_eq!(visual_left(MIXED, 8), Some(3));
+        assert_eq!(visual_right(MIXED, 4), None);
+        assert_eq!(visual_left(MIXED, 0), None);
+    }
+}
@@ -5,6 +5,7 @@
 
 use ropey::Rope;
 use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Line ending style
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -60,6 +61,9 @@ pub struct TextBuffer {
 
     /// Version number when last saved
     saved_version: u64,
+
+    /// Treat `_` as part of a word, as in identifiers
+    underscore_in_words: bool,
 }
 
 impl TextBuffer {
@@ -71,6 +75,7 @@ impl TextBuffer {
             version: 0,
             modified: false,
             saved_version: 0,
+            underscore_in_words: true,
         }
     }
 
@@ -85,6 +90,7 @@ impl TextBuffer {
             version: 0,
             modified: false,
             saved_version: 0,
+            underscore_in_words: true,
         }
     }
 
@@ -302,36 +308,70 @@ impl TextBuffer {
         Some(self.rope.char(char_idx))
     }
 
-    /// Get word at position (returns start and end char indices)
-    pub fn word_at(&self, char_idx: usize) -> Option<(usize, usize)> {
-        if char_idx >= self.rope.len_chars() {
-            return None;
-        }
+    /// Whether `_` counts as part of a word
+    pub fn underscore_in_words(&self) -> bool {
+        self.underscore_in_words
+    }
+
+    /// Set whether `_` counts as part of a word
+    pub fn set_underscore_in_words(&mut self, enabled: bool) {
+        self.underscore_in_words = enabled;
+    }
 
-        // Find word start
-        let mut start = char_idx;
-        while start > 0 {
-            let ch = self.rope.char(start - 1);
-            if !ch.is_alphanumeric() && ch != '_' {
-                break;
+    /// Split a line into Unicode word segments
+    ///
+    /// Returns `(start, end, is_word)` character ranges in buffer
+    /// coordinates. The line's newline, if any, is its own segment.
+    fn line_segments(&self, line_idx: usize) -> Vec<(usize, usize, bool)> {
+        let line_start = self.rope.line_to_char(line_idx);
+        let line = self.rope.line(line_idx).to_string();
+        let underscore = self.underscore_in_words;
+
+        let mut segments = Vec::new();
+        let mut pos = line_start;
+        for word in line.split_word_bounds() {
+            // Without underscores in words, split identifiers at each `_`
+            let parts: Vec<&str> = if underscore {
+                vec![word]
+            } else {
+                word.split_inclusive('_')
+                    .flat_map(|part| match part.strip_suffix('_') {
+                        Some(rest) if !rest.is_empty() => vec![rest, "_"],
+                        _ => vec![part],
+                    })
+                    .collect()
+            };
+            for part in parts {
+                let len = part.chars().count();
+                let is_word = part
+                    .chars()
+                    .any(|ch| ch.is_alphanumeric() || (underscore && ch == '_'));
+                segments.push((pos, pos + len, is_word));
+                pos += len;
             }
-            start -= 1;
         }
+        segments
+    }
 
-        // Find word end
-        let mut end = char_idx;
-        while end < self.rope.len_chars() {
-            let ch = self.rope.char(end);
-            if !ch.is_alphanumeric() && ch != '_' {
-                break;
-            }
-            end += 1;
+    /// Get the word segment containing a character index
+    fn segment_at(&self, char_idx: usize) -> Option<(usize, usize, bool)> {
+        if char_idx >= self.rope.len_chars() {
+            return None;
         }
+        let line_idx = self.rope.char_to_line(char_idx);
+        self.line_segments(line_idx)
+            .into_iter()
+            .find(|&(start, end, _)| start <= char_idx && char_idx < end)
+    }
 
-        if start == end {
-            None
-        } else {
-            Some((start, end))
+    /// Get word at position (returns start and end char indices)
+    ///
+    /// Words follow Unicode word boundaries, so contractions like "don't"
+    /// and letters with combining marks stay whole.
+    pub fn word_at(&self, char_idx: usize) -> Option<(usize, usize)> {
+        match self.segment_at(char_idx)? {
+            (start, end, true) => Some((start, end)),
+            _ => None,
         }
     }
 
@@ -344,14 +384,14 @@ impl TextBuffer {
 
         let mut idx = char_idx;
 
-        // Skip current word/non-word
-        let start_is_word = self.rope.char(idx).is_alphanumeric();
-        while idx < len {
-            let is_word = self.rope.char(idx).is_alphanumeric();
-            if is_word != start_is_word {
-                break;
+        // Skip the current word, or everything up to the next word
+        match self.segment_at(idx) {
+            Some((_, end, true)) => idx = end,
+            _ => {
+                while let Some((_, end, false)) = self.segment_at(idx) {
+                    idx = end;
+                }
             }
-            idx += 1;
         }
 
         // Skip whitespace
@@ -368,7 +408,7 @@ impl TextBuffer {
             return 0;
         }
 
-        let mut idx = char_idx;
+        let mut idx = char_idx.min(self.rope.len_chars());
 
         // Skip whitespace
         while idx > 0 && self.rope.char(idx - 1).is_whitespace() && self.rope.char(idx - 1) != '\n'
@@ -380,14 +420,17 @@ impl TextBuffer {
             return 0;
         }
 
-        // Skip current word/non-word
-        let start_is_word = self.rope.char(idx - 1).is_alphanumeric();
-        while idx > 0 {
-            let is_word = self.rope.char(idx - 1).is_alphanumeric();
-            if is_word != start_is_word {
-                break;
+        // Skip back over the previous word, or everything after it
+        match self.segment_at(idx - 1) {
+            Some((start, _, true)) => idx = start,
+            _ => {
+                while idx > 0 {
+                    match self.segment_at(idx - 1) {
+                        Some((start, _, false)) => idx = start,
+                        _ => break,
+                    }
+                }
             }
-            idx -= 1;
         }
 
         idx
@@ -398,6 +441,322 @@ impl TextBuffer {
         let text = self.rope.to_string();
         text.split_whitespace().count()
     }
+
+    // === Line Operations ===
+
+    /// Expand a character range to the full lines it touches
+    ///
+    /// The returned range excludes the newline of the last line. A range
+    /// ending at the start of a line does not include that line.
+    pub fn line_span(&self, range: Range<usize>) -> Range<usize> {
+        let len = self.rope.len_chars();
+        let start = range.start.min(len);
+        let mut end = range.end.min(len).max(start);
+
+        if end > start && self.rope.char(end - 1) == '\n' {
+            end -= 1;
+        }
+
+        let first_line = self.rope.char_to_line(start);
+        let last_line = self.rope.char_to_line(end);
+        let span_start = self.rope.line_to_char(first_line);
+        let span_end =
+            self.rope.line_to_char(last_line) + self.line_len(last_line).unwrap_or(0);
+        span_start..span_end
+    }
+
+    /// Sort the lines touched by a character range
+    ///
+    /// The sort is stable, so lines with equal keys keep their order.
+    /// Returns the character range of the sorted lines.
+    pub fn sort_lines(
+        &mut self,
+        range: Range<usize>,
+        descending: bool,
+        case_sensitive: bool,
+    ) -> Range<usize> {
+        self.map_lines(range, |lines| {
+            lines.sort_by(|a, b| {
+                let ordering = if case_sensitive {
+                    a.cmp(b)
+                } else {
+                    a.to_lowercase().cmp(&b.to_lowercase())
+                };
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        })
+    }
+
+    /// Remove duplicate lines within the lines touched by a character range
+    ///
+    /// The first occurrence of each line is kept. Returns the character
+    /// range of the remaining lines.
+    pub fn dedupe_lines(&mut self, range: Range<usize>) -> Range<usize> {
+        self.map_lines(range, |lines| {
+            let mut seen = std::collections::HashSet::new();
+            lines.retain(|line| seen.insert(line.clone()));
+        })
+    }
+
+    /// Join the lines touched by a character range into one line
+    ///
+    /// A range within a single line joins the next line onto it. Each line
+    /// break becomes a single space, and the joined line loses its leading
+    /// whitespace and any list marker. Returns the position of the last join.
+    pub fn join_lines(&mut self, range: Range<usize>) -> Range<usize> {
+        let span = self.line_span(range);
+        let first_line = self.rope.char_to_line(span.start);
+        let mut last_line = self.rope.char_to_line(span.end);
+        if last_line == first_line {
+            last_line += 1;
+        }
+
+        // The empty line after a trailing newline can't be joined
+        let len = self.rope.len_chars();
+        let line_count = if len > 0 && self.rope.char(len - 1) == '\n' {
+            self.rope.len_lines() - 1
+        } else {
+            self.rope.len_lines()
+        };
+        if last_line >= line_count {
+            return span.end..span.end;
+        }
+
+        let mut joined = self.line_without_newline(first_line).unwrap_or_default();
+        let quoted = joined.trim_start().starts_with('>');
+        let mut join_point = 0;
+
+        for line_idx in first_line + 1..=last_line {
+            let line = self.line_without_newline(line_idx).unwrap_or_default();
+            let mut rest = line.trim_start();
+            if quoted {
+                rest = rest.strip_prefix('>').unwrap_or(rest).trim_start();
+            }
+            rest = rest[list_marker_len(rest)..].trim_start();
+
+            joined.truncate(joined.trim_end().len());
+            join_point = joined.chars().count();
+            if !joined.is_empty() && !rest.is_empty() {
+                joined.push(' ');
+            }
+            joined.push_str(rest);
+        }
+
+        let start = self.rope.line_to_char(first_line);
+        let end = self.rope.line_to_char(last_line) + self.line_len(last_line).unwrap_or(0);
+        self.replace(start..end, &joined);
+        start + join_point..start + join_point
+    }
+
+    /// Swap the two characters around a position
+    ///
+    /// At the end of a line the last two characters are swapped. Returns the
+    /// new cursor position, or `None` at the start of a line.
+    pub fn transpose_chars(&mut self, char_idx: usize) -> Option<usize> {
+        let char_idx = char_idx.min(self.rope.len_chars());
+        let (line, col) = self.char_to_line_col(char_idx);
+        let line_len = self.line_len(line).unwrap_or(0);
+        if col == 0 || line_len < 2 {
+            return None;
+        }
+
+        let (left, cursor) = if col >= line_len {
+            (char_idx - 2, char_idx)
+        } else {
+            (char_idx - 1, char_idx + 1)
+        };
+        let swapped: String = [self.rope.char(left + 1), self.rope.char(left)]
+            .iter()
+            .collect();
+        self.replace(left..left + 2, &swapped);
+        Some(cursor)
+    }
+
+    /// Swap the word before a position with the word after it
+    ///
+    /// The text between the words stays in place. Inside a word, that word
+    /// is swapped with the next one. Returns the new cursor position after
+    /// both words, or `None` without a word on each side.
+    pub fn transpose_words(&mut self, char_idx: usize) -> Option<usize> {
+        let len = self.rope.len_chars();
+        let is_word = |idx: usize| idx < len && self.rope.char(idx).is_alphanumeric();
+        let mut idx = char_idx.min(len);
+
+        // Inside a word, start from its end
+        if idx > 0 && is_word(idx - 1) {
+            while is_word(idx) {
+                idx += 1;
+            }
+        }
+
+        let mut first_start = self.prev_word_boundary(idx);
+        while first_start > 0 && !is_word(first_start) {
+            first_start = self.prev_word_boundary(first_start);
+        }
+        let mut second_start = idx;
+        while second_start < len && !is_word(second_start) {
+            second_start = self.next_word_boundary(second_start);
+        }
+        if !is_word(first_start) || !is_word(second_start) {
+            return None;
+        }
+
+        let word_end = |mut end: usize| {
+            while is_word(end) {
+                end += 1;
+            }
+            end
+        };
+        let first_end = word_end(first_start);
+        let second_end = word_end(second_start);
+        if first_end > idx || second_start < idx {
+            return None;
+        }
+
+        let first = self.slice(first_start, first_end);
+        let between = self.slice(first_end, second_start);
+        let second = self.slice(second_start, second_end);
+        self.replace(first_start..second_end, &format!("{}{}{}", second, between, first));
+        Some(second_end)
+    }
+
+    /// Replace a character range with a line break, indenting the new line
+    /// like the one it was split from
+    ///
+    /// The new line takes the leading whitespace before the cursor, and
+    /// splitting a list item after its text adds one level, under the
+    /// item's text. A line holding nothing but whitespace is cleared, so
+    /// breaking blank lines leaves no trailing indentation behind. Returns
+    /// the cursor position on the new line.
+    pub fn auto_indent_newline(&mut self, range: Range<usize>) -> Range<usize> {
+        let len = self.rope.len_chars();
+        let start = range.start.min(len);
+        self.replace(start..range.end.clamp(start, len), "");
+
+        let line = self.rope.char_to_line(start);
+        let line_start = self.rope.line_to_char(line);
+        let text = self.line_without_newline(line).unwrap_or_default();
+        let before: String = text.chars().take(start - line_start).collect();
+        let indent_len = before.len() - before.trim_start_matches([' ', '\t']).len();
+
+        if text.trim().is_empty() {
+            let break_with_indent = format!("\n{}", before);
+            self.replace(line_start..line_start + text.chars().count(), &break_with_indent);
+            let cursor = line_start + break_with_indent.chars().count();
+            return cursor..cursor;
+        }
+
+        let mut indent = before[..indent_len].to_string();
+        let item = &before[indent_len..];
+        let marker = list_marker_len(item);
+        if marker > 0 && !item[marker..].trim().is_empty() {
+            let rest = &item[marker..];
+            let spaces = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+            indent.push_str(&" ".repeat(marker + spaces));
+        }
+
+        let break_with_indent = format!("\n{}", indent);
+        self.insert(start, &break_with_indent);
+        let cursor = start + break_with_indent.chars().count();
+        cursor..cursor
+    }
+
+    /// Indent the lines touched by a character range by one level
+    ///
+    /// Blank lines are left alone. Returns the character range of the lines.
+    pub fn indent_lines(&mut self, range: Range<usize>, unit: &str) -> Range<usize> {
+        self.map_lines(range, |lines| {
+            for line in lines.iter_mut().filter(|line| !line.trim().is_empty()) {
+                line.insert_str(0, unit);
+            }
+        })
+    }
+
+    /// Remove up to one indent level from the lines touched by a character range
+    ///
+    /// A level is a tab or up to `tab_width` spaces, so lines with less
+    /// indentation lose what they have. Returns the character range of the lines.
+    pub fn outdent_lines(&mut self, range: Range<usize>, tab_width: usize) -> Range<usize> {
+        self.map_lines(range, |lines| {
+            for line in lines.iter_mut() {
+                let spaces = line
+                    .chars()
+                    .take(tab_width.max(1))
+                    .take_while(|c| *c == ' ')
+                    .count();
+                if spaces > 0 {
+                    line.drain(..spaces);
+                } else if line.starts_with('\t') {
+                    line.remove(0);
+                }
+            }
+        })
+    }
+
+    /// Replace the lines touched by a range with a transformed set of lines
+    fn map_lines(
+        &mut self,
+        range: Range<usize>,
+        transform: impl FnOnce(&mut Vec<String>),
+    ) -> Range<usize> {
+        let span = self.line_span(range);
+        let original = self.slice_range(span.clone());
+        // Lines are transformed without their terminators, and each break
+        // keeps its place, so CRLF text doesn't gain bare newlines
+        let mut lines = Vec::new();
+        let mut breaks = Vec::new();
+        let mut rest = original.as_str();
+        while let Some(end) = rest.find('\n') {
+            let (line, terminator) = match rest[..end].strip_suffix('\r') {
+                Some(line) => (line, "\r\n"),
+                None => (&rest[..end], "\n"),
+            };
+            lines.push(line.to_string());
+            breaks.push(terminator);
+            rest = &rest[end + 1..];
+        }
+        lines.push(rest.to_string());
+        transform(&mut lines);
+
+        // Any lines beyond the original count take the first break
+        let fallback = breaks.first().copied().unwrap_or("\n");
+        let mut replacement = String::with_capacity(original.len());
+        for (index, line) in lines.iter().enumerate() {
+            if index > 0 {
+                replacement.push_str(breaks.get(index - 1).copied().unwrap_or(fallback));
+            }
+            replacement.push_str(line);
+        }
+        if replacement != original {
+            self.replace(span.clone(), &replacement);
+        }
+        span.start..span.start + replacement.chars().count()
+    }
+}
+
+/// Get the length of a markdown list marker at the start of a line
+pub(super) fn list_marker_len(line: &str) -> usize {
+    let bytes = line.as_bytes();
+    let marker = match bytes.first() {
+        Some(b'-' | b'*' | b'+') => 1,
+        Some(b'0'..=b'9') => {
+            let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+            match bytes.get(digits) {
+                Some(b'.' | b')') => digits + 1,
+                _ => return 0,
+            }
+        }
+        _ => return 0,
+    };
+
+    match bytes.get(marker) {
+        Some(b' ' | b'\t') | None => marker,
+        _ => 0,
+    }
 }
 
 impl Default for TextBuffer {
@@ -493,6 +852,41 @@ mod tests {
         assert_eq!(buf.word_at(5), None); // On space
     }
 
+    #[test]
+    fn test_unicode_words() {
+        let buf = TextBuffer::from_str("don't stop");
+        assert_eq!(buf.word_at(3), Some((0, 5)));
+        assert_eq!(buf.next_word_boundary(0), 6);
+        assert_eq!(buf.prev_word_boundary(5), 0);
+
+        // "e" followed by a combining acute accent
+        let buf = TextBuffer::from_str("cafe\u{301} au lait");
+        assert_eq!(buf.word_at(0), Some((0, 5)));
+        assert_eq!(buf.next_word_boundary(0), 6);
+        assert_eq!(buf.prev_word_boundary(6), 0);
+
+        // Ideographs are words on their own, katakana runs stay together
+        let buf = TextBuffer::from_str("東京タワー");
+        assert_eq!(buf.word_at(0), Some((0, 1)));
+        assert_eq!(buf.word_at(3), Some((2, 5)));
+        assert_eq!(buf.next_word_boundary(0), 1);
+        assert_eq!(buf.next_word_boundary(2), 5);
+        assert_eq!(buf.prev_word_boundary(5), 2);
+    }
+
+    #[test]
+    fn test_underscore_in_words() {
+        let mut buf = TextBuffer::from_str("snake_case name");
+        assert_eq!(buf.word_at(2), Some((0, 10)));
+        assert_eq!(buf.next_word_boundary(0), 11);
+
+        buf.set_underscore_in_words(false);
+        assert_eq!(buf.word_at(2), Some((0, 5)));
+        assert_eq!(buf.word_at(5), None);
+        assert_eq!(buf.next_word_boundary(0), 5);
+        assert_eq!(buf.next_word_boundary(5), 6);
+    }
+
     #[test]
     fn test_word_count() {
         let buf = TextBuffer::from_str("Hello world, this is a test.");
@@ -510,4 +904,134 @@ mod tests {
         buf.delete(0..2);
         assert_eq!(buf.version(), 2);
     }
+
+    #[test]
+    fn test_line_span() {
+        let buf = TextBuffer::from_str("one\ntwo\nthree\n");
+
+        assert_eq!(buf.line_span(1..5), 0..7); // Partial lines expand
+        assert_eq!(buf.line_span(0..8), 0..7); // Ends at the start of "three"
+        assert_eq!(buf.line_span(9..9), 8..13);
+    }
+
+    #[test]
+    fn test_sort_lines() {
+        let mut buf = TextBuffer::from_str("b\nB\na\nc\n");
+        let span = buf.sort_lines(0..8, false, false);
+        assert_eq!(buf.to_string(), "a\nb\nB\nc\n"); // Stable for equal keys
+        assert_eq!(span, 0..7);
+
+        buf.sort_lines(0..8, true, true);
+        assert_eq!(buf.to_string(), "c\nb\na\nB\n");
+
+        let mut buf = TextBuffer::from_str("zeta\r\nalpha\r\nmid");
+        buf.sort_lines(2..11, false, true);
+        assert_eq!(buf.to_string(), "alpha\r\nzeta\r\nmid");
+
+        // Each break keeps its place in mixed text
+        let mut buf = TextBuffer::from_str("c\r\nb\na");
+        buf.sort_lines(0..buf.len_chars(), false, true);
+        assert_eq!(buf.to_string(), "a\r\nb\nc");
+    }
+
+    #[test]
+    fn test_dedupe_lines() {
+        let mut buf = TextBuffer::from_str("#tag\n#other\n#tag\n#Tag");
+        let span = buf.dedupe_lines(0..buf.len_chars());
+        assert_eq!(buf.to_string(), "#tag\n#other\n#Tag");
+        assert_eq!(span, 0..buf.len_chars());
+
+        // Line endings aren't part of the comparison, and CRLF stays CRLF
+        let mut buf = TextBuffer::from_str("a\r\nb\r\na\r\nc\r\n");
+        buf.dedupe_lines(0..buf.len_chars());
+        assert_eq!(buf.to_string(), "a\r\nb\r\nc\r\n");
+    }
+
+    #[test]
+    fn test_join_lines() {
+        let mut buf = TextBuffer::from_str("hello\n   world\n");
+        assert_eq!(buf.join_lines(0..0), 5..5);
+        assert_eq!(buf.to_string(), "hello world\n");
+
+        // Joining at the last line does nothing
+        assert_eq!(buf.join_lines(3..3), 11..11);
+        assert_eq!(buf.to_string(), "hello world\n");
+
+        let mut buf = TextBuffer::from_str("- one  \n- two\n-  three");
+        buf.join_lines(0..buf.len_chars());
+        assert_eq!(buf.to_string(), "- one two three");
+
+        let mut buf = TextBuffer::from_str("> quote\n> more\n**bold**");
+        buf.join_lines(0..0);
+        buf.join_lines(0..0);
+        assert_eq!(buf.to_string(), "> quote more **bold**");
+    }
+
+    #[test]
+    fn test_auto_indent_newline() {
+        let mut buf = TextBuffer::from_str("    code");
+        assert_eq!(buf.auto_indent_newline(8..8), 13..13);
+        assert_eq!(buf.to_string(), "    code\n    ");
+
+        // A whitespace-only line is cleared instead of carrying its indent
+        assert_eq!(buf.auto_indent_newline(13..13), 14..14);
+        assert_eq!(buf.to_string(), "    code\n\n    ");
+
+        // After a list item's text the new line goes under the text
+        let mut buf = TextBuffer::from_str("  1. item");
+        assert_eq!(buf.auto_indent_newline(9..9), 15..15);
+        assert_eq!(buf.to_string(), "  1. item\n     ");
+
+        // An empty item, or a break before the text, adds no level
+        let mut buf = TextBuffer::from_str("- ");
+        buf.auto_indent_newline(2..2);
+        assert_eq!(buf.to_string(), "- \n");
+        let mut buf = TextBuffer::from_str("x split");
+        assert_eq!(buf.auto_indent_newline(1..2), 2..2);
+        assert_eq!(buf.to_string(), "x\nsplit");
+    }
+
+    #[test]
+    fn test_indent_outdent_lines() {
+        let mut buf = TextBuffer::from_str("one\n\n  two\nthree\n");
+        // The selection ends at the start of "three", which is left alone
+        assert_eq!(buf.indent_lines(1..11, "    "), 0..18);
+        assert_eq!(buf.to_string(), "    one\n\n      two\nthree\n");
+
+        assert_eq!(buf.outdent_lines(0..18, 4), 0..10);
+        assert_eq!(buf.outdent_lines(0..10, 4), 0..8);
+        assert_eq!(buf.to_string(), "one\n\ntwo\nthree\n");
+
+        let mut buf = TextBuffer::from_str("\tone\n      two");
+        buf.outdent_lines(0..buf.len_chars(), 4);
+        assert_eq!(buf.to_string(), "one\n  two");
+    }
+
+    #[test]
+    fn test_transpose_chars() {
+        let mut buf = TextBuffer::from_str("abc\nde");
+        assert_eq!(buf.transpose_chars(1), Some(2));
+        assert_eq!(buf.to_string(), "bac\nde");
+
+        // End of line swaps the last two characters
+        assert_eq!(buf.transpose_chars(3), Some(3));
+        assert_eq!(buf.to_string(), "bca\nde");
+
+        // Start of line does nothing
+        assert_eq!(buf.transpose_chars(4), None);
+        assert_eq!(buf.to_string(), "bca\nde");
+    }
+
+    #[test]
+    fn test_transpose_words() {
+        let mut buf = TextBuffer::from_str("one, two three");
+        assert_eq!(buf.transpose_words(4), Some(8));
+        assert_eq!(buf.to_string(), "two, one three");
+
+        // Inside a word swaps it with the next one
+        assert_eq!(buf.transpose_words(6), Some(14));
+        assert_eq!(buf.to_string(), "two, three one");
+
+        assert_eq!(buf.transpose_words(14), None);
+    }
 }
@@ -1,12 +1,18 @@
 //! Clipboard integration for copy, cut, and paste operations
 //!
 //! Uses the arboard crate for cross-platform clipboard access,
-//! with special handling for Wayland and X11 on Linux.
+//! with special handling for Wayland and X11 on Linux. Text copied from
+//! the app is also kept in a history ring for pasting older entries.
 
+use crate::config::DEFAULT_CLIPBOARD_HISTORY_SIZE;
 use arboard::Clipboard;
+use std::collections::VecDeque;
 use std::sync::Mutex;
 use thiserror::Error;
 
+/// Largest copy, in bytes, kept in the clipboard history
+pub const MAX_CLIPBOARD_HISTORY_ENTRY: usize = 1024 * 1024;
+
 /// Clipboard-related errors
 #[derive(Error, Debug, Clone)]
 pub enum ClipboardError {
@@ -23,6 +29,69 @@ pub enum ClipboardError {
     WriteError(String),
 }
 
+/// Recent copies, most recent first
+#[derive(Debug, Clone)]
+pub struct ClipboardHistory {
+    entries: VecDeque<String>,
+    capacity: usize,
+}
+
+impl ClipboardHistory {
+    /// Create a history keeping up to `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Record a copy
+    ///
+    /// Empty text and a repeat of the latest entry are ignored. Copies over
+    /// `MAX_CLIPBOARD_HISTORY_ENTRY` are left out rather than truncated, so
+    /// an entry always pastes what was copied.
+    pub fn push(&mut self, text: &str) {
+        if text.is_empty() || text.len() > MAX_CLIPBOARD_HISTORY_ENTRY {
+            return;
+        }
+        if self.entries.front().map(String::as_str) == Some(text) {
+            return;
+        }
+        self.entries.push_front(text.to_string());
+        self.entries.truncate(self.capacity);
+    }
+
+    /// Change how many entries are kept, dropping the oldest if needed
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries.truncate(capacity);
+    }
+
+    /// Entry `index` places back from the latest copy
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// All entries, most recent first
+    pub fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Default for ClipboardHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_CLIPBOARD_HISTORY_SIZE)
+    }
+}
+
 /// Thread-safe clipboard wrapper
 /// 
 /// Note: arboard's Clipboard is not Send/Sync on all platforms,
@@ -30,6 +99,8 @@ pub enum ClipboardError {
 pub struct ClipboardManager {
     /// Last known clipboard content (for fallback)
     last_content: Mutex<Option<String>>,
+    /// Text set through this manager, for pasting from history
+    history: Mutex<ClipboardHistory>,
 }
 
 impl ClipboardManager {
@@ -37,6 +108,22 @@ impl ClipboardManager {
     pub fn new() -> Self {
         Self {
             last_content: Mutex::new(None),
+            history: Mutex::new(ClipboardHistory::default()),
+        }
+    }
+
+    /// Copies in the history, most recent first
+    pub fn history(&self) -> Vec<String> {
+        self.history
+            .lock()
+            .map(|history| history.entries().map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// Change how many copies the history keeps
+    pub fn set_history_size(&self, size: usize) {
+        if let Ok(mut history) = self.history.lock() {
+            history.set_capacity(size);
         }
     }
 
@@ -68,12 +155,26 @@ impl ClipboardManager {
         }
     }
 
+    /// Get HTML from clipboard
+    pub fn get_html(&self) -> Result<String, ClipboardError> {
+        let mut clipboard =
+            Clipboard::new().map_err(|e| ClipboardError::AccessError(e.to_string()))?;
+        match clipboard.get().html() {
+            Ok(html) => Ok(html),
+            Err(arboard::Error::ContentNotAvailable) => Err(ClipboardError::Empty),
+            Err(e) => Err(ClipboardError::AccessError(e.to_string())),
+        }
+    }
+
     /// Set text to clipboard
     pub fn set_text(&self, text: &str) -> Result<(), ClipboardError> {
         // Cache the content first
         if let Ok(mut cache) = self.last_content.lock() {
             *cache = Some(text.to_string());
         }
+        if let Ok(mut history) = self.history.lock() {
+            history.push(text);
+        }
 
         match Clipboard::new() {
             Ok(mut clipboard) => {
@@ -85,6 +186,25 @@ impl ClipboardManager {
         }
     }
 
+    /// Set HTML to clipboard, with plain text for apps that don't take HTML
+    pub fn set_html(&self, html: &str, text: &str) -> Result<(), ClipboardError> {
+        if let Ok(mut cache) = self.last_content.lock() {
+            *cache = Some(text.to_string());
+        }
+        if let Ok(mut history) = self.history.lock() {
+            history.push(text);
+        }
+
+        match Clipboard::new() {
+            Ok(mut clipboard) => {
+                clipboard
+                    .set_html(html, Some(text))
+                    .map_err(|e| ClipboardError::WriteError(e.to_string()))
+            }
+            Err(e) => Err(ClipboardError::AccessError(e.to_string())),
+        }
+    }
+
     /// Check if clipboard has text content
     pub fn has_text(&self) -> bool {
         if let Ok(mut clipboard) = Clipboard::new() {
@@ -138,11 +258,45 @@ pub fn copy_text(text: &str) -> Result<(), ClipboardError> {
     clipboard().set_text(text)
 }
 
+/// Convenience function to copy HTML with a plain text fallback to clipboard
+pub fn copy_html(text: &str, html: &str) -> Result<(), ClipboardError> {
+    clipboard().set_html(html, text)
+}
+
 /// Convenience function to get text from clipboard
 pub fn paste_text() -> Result<String, ClipboardError> {
     clipboard().get_text()
 }
 
+/// Convenience function to get HTML from clipboard
+pub fn paste_html() -> Result<String, ClipboardError> {
+    clipboard().get_html()
+}
+
+/// Normalize pasted text for a plain paste
+///
+/// Converts line endings to `\n`, replaces non-breaking spaces, drops
+/// zero-width and control characters, and trims trailing whitespace.
+pub fn normalize_plain_text(text: &str) -> String {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    text.split('\n')
+        .map(|line| {
+            line.chars()
+                .filter_map(|c| match c {
+                    '\u{a0}' | '\u{202f}' => Some(' '),
+                    '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{feff}' => None,
+                    '\t' => Some(c),
+                    c if c.is_control() => None,
+                    c => Some(c),
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,6 +308,37 @@ mod tests {
         assert!(manager.last_content.lock().unwrap().is_none());
     }
 
+    #[test]
+    fn test_normalize_plain_text() {
+        assert_eq!(
+            normalize_plain_text("one\u{a0}two  \r\n\u{feff}three\rfour\u{200b}\t\n"),
+            "one two\nthree\nfour\n"
+        );
+        assert_eq!(normalize_plain_text("\tindented"), "\tindented");
+    }
+
+    #[test]
+    fn test_clipboard_history() {
+        let mut history = ClipboardHistory::new(3);
+        history.push("one");
+        history.push("two");
+        history.push("two");
+        history.push("");
+        assert_eq!(history.entries().collect::<Vec<_>>(), ["two", "one"]);
+
+        // Only consecutive repeats are dropped
+        history.push("one");
+        history.push("three");
+        assert_eq!(history.entries().collect::<Vec<_>>(), ["three", "one", "two"]);
+        assert_eq!(history.get(2), Some("two"));
+
+        history.push(&"x".repeat(MAX_CLIPBOARD_HISTORY_ENTRY + 1));
+        assert_eq!(history.get(0), Some("three"));
+
+        history.set_capacity(1);
+        assert_eq!(history.len(), 1);
+    }
+
     // Note: Full clipboard tests require a display server
     // and are better suited for integration tests
 }
@@ -2,6 +2,7 @@
 //!
 //! Handles cursor positioning, movement, and preferred column tracking.
 
+use crate::editor::bidi::{self, Direction};
 use crate::editor::buffer::TextBuffer;
 use crate::state::CursorPosition;
 
@@ -9,31 +10,50 @@ use crate::state::CursorPosition;
 pub struct CursorController;
 
 impl CursorController {
-    /// Move cursor left by one character
+    /// Move cursor one character to the left on screen
+    ///
+    /// In right-to-left text this is a step forward through the line. At
+    /// the left edge the cursor wraps to the previous line, or to the next
+    /// one if the line reads right to left.
     pub fn move_left(buffer: &TextBuffer, pos: CursorPosition) -> CursorPosition {
-        if pos.column > 0 {
-            CursorPosition::new(pos.line, pos.column - 1)
-        } else if pos.line > 0 {
-            // Wrap to end of previous line
-            let prev_line_len = buffer.line_len(pos.line - 1).unwrap_or(0);
-            CursorPosition::new(pos.line - 1, prev_line_len)
-        } else {
-            pos
+        let line = buffer.line_without_newline(pos.line).unwrap_or_default();
+        if let Some(column) = bidi::visual_left(&line, pos.column) {
+            return CursorPosition::new(pos.line, column);
+        }
+        match bidi::base_direction(&line) {
+            Direction::LeftToRight => Self::wrap_backward(buffer, pos),
+            Direction::RightToLeft => Self::wrap_forward(buffer, pos),
         }
     }
 
-    /// Move cursor right by one character
+    /// Move cursor one character to the right on screen
     pub fn move_right(buffer: &TextBuffer, pos: CursorPosition) -> CursorPosition {
-        let line_len = buffer.line_len(pos.line).unwrap_or(0);
+        let line = buffer.line_without_newline(pos.line).unwrap_or_default();
+        if let Some(column) = bidi::visual_right(&line, pos.column) {
+            return CursorPosition::new(pos.line, column);
+        }
+        match bidi::base_direction(&line) {
+            Direction::LeftToRight => Self::wrap_forward(buffer, pos),
+            Direction::RightToLeft => Self::wrap_backward(buffer, pos),
+        }
+    }
 
-        if pos.column < line_len {
-            CursorPosition::new(pos.line, pos.column + 1)
-        } else if pos.line < buffer.len_lines().saturating_sub(1) {
-            // Wrap to start of next line
-            CursorPosition::new(pos.line + 1, 0)
-        } else {
-            pos
+    /// Wrap to the end of the previous line
+    fn wrap_backward(buffer: &TextBuffer, pos: CursorPosition) -> CursorPosition {
+        if pos.line == 0 {
+            return pos;
+        }
+        let prev_line = buffer.line_without_newline(pos.line - 1).unwrap_or_default();
+        CursorPosition::new(pos.line - 1, bidi::reading_edges(&prev_line).1)
+    }
+
+    /// Wrap to the start of the next line
+    fn wrap_forward(buffer: &TextBuffer, pos: CursorPosition) -> CursorPosition {
+        if pos.line >= buffer.len_lines().saturating_sub(1) {
+            return pos;
         }
+        let next_line = buffer.line_without_newline(pos.line + 1).unwrap_or_default();
+        CursorPosition::new(pos.line + 1, bidi::reading_edges(&next_line).0)
     }
 
     /// Move cursor up by one line
@@ -76,27 +96,28 @@ impl CursorController {
         )
     }
 
-    /// Move cursor to start of line (smart home: first non-whitespace, then column 0)
+    /// Move cursor to start of line (smart home: first non-whitespace, then line start)
+    ///
+    /// The line start is where the line begins in its reading direction,
+    /// the right edge for right-to-left text.
     pub fn move_home(buffer: &TextBuffer, pos: CursorPosition) -> CursorPosition {
         let line = buffer.line_without_newline(pos.line).unwrap_or_default();
-        let first_non_ws = line.chars().position(|c| !c.is_whitespace()).unwrap_or(0);
-
-        if pos.column == first_non_ws || pos.column == 0 {
-            // Toggle between first non-whitespace and column 0
-            if pos.column == 0 && first_non_ws > 0 {
-                CursorPosition::new(pos.line, first_non_ws)
-            } else {
-                CursorPosition::new(pos.line, 0)
-            }
+        let start = bidi::reading_edges(&line).0;
+        let first_non_ws = line.chars().position(|c| !c.is_whitespace()).unwrap_or(start);
+
+        if pos.column == start && first_non_ws != start {
+            CursorPosition::new(pos.line, first_non_ws)
+        } else if pos.column == first_non_ws {
+            CursorPosition::new(pos.line, start)
         } else {
             CursorPosition::new(pos.line, first_non_ws)
         }
     }
 
-    /// Move cursor to end of line
+    /// Move cursor to end of line, where the line ends in its reading direction
     pub fn move_end(buffer: &TextBuffer, pos: CursorPosition) -> CursorPosition {
-        let line_len = buffer.line_len(pos.line).unwrap_or(0);
-        CursorPosition::new(pos.line, line_len)
+        let line = buffer.line_without_newline(pos.line).unwrap_or_default();
+        CursorPosition::new(pos.line, bidi::reading_edges(&line).1)
     }
 
     /// Move cursor to start of previous word
@@ -185,7 +206,8 @@ pub fn calculate_scroll(
     viewport_lines: usize,
     scroll_margin: usize,
 ) -> usize {
-    let margin = scroll_margin.min(viewport_lines / 2);
+    // A margin over half the viewport would push the cursor out of view
+    let margin = scroll_margin.min(viewport_lines.saturating_sub(1) / 2);
 
     // Cursor too far up
     if cursor_line < current_scroll + margin {
@@ -288,6 +310,46 @@ mod tests {
         assert_eq!(pos, CursorPosition::new(0, 13));
     }
 
+    #[test]
+    fn test_move_across_bidi_boundary() {
+        // "abc " then the Arabic word "سلام", drawn right to left
+        let buf = TextBuffer::from_str("abc \u{633}\u{644}\u{627}\u{645}\nnext");
+
+        // Right from after the space enters the Arabic run at its left
+        // edge, which is the logical end of the line
+        let pos = CursorController::move_right(&buf, CursorPosition::new(0, 3));
+        assert_eq!(pos, CursorPosition::new(0, 8));
+        let pos = CursorController::move_right(&buf, pos);
+        assert_eq!(pos, CursorPosition::new(0, 7));
+
+        // Back left returns to the English side
+        let pos = CursorController::move_left(&buf, CursorPosition::new(0, 8));
+        assert_eq!(pos, CursorPosition::new(0, 3));
+
+        // The right edge of the line is the start of the Arabic word
+        let pos = CursorController::move_end(&buf, CursorPosition::new(0, 0));
+        assert_eq!(pos, CursorPosition::new(0, 4));
+        let pos = CursorController::move_right(&buf, pos);
+        assert_eq!(pos, CursorPosition::new(1, 0));
+    }
+
+    #[test]
+    fn test_move_rtl_line() {
+        let buf = TextBuffer::from_str("first\n\u{5e9}\u{5dc}\u{5d5}\u{5dd}\nlast");
+
+        // Left moves forward through a right-to-left line
+        let pos = CursorController::move_left(&buf, CursorPosition::new(1, 0));
+        assert_eq!(pos, CursorPosition::new(1, 1));
+
+        // Past its left edge the cursor continues on the next line
+        let pos = CursorController::move_left(&buf, CursorPosition::new(1, 4));
+        assert_eq!(pos, CursorPosition::new(2, 0));
+
+        // Past its right edge the cursor goes back to the previous line
+        let pos = CursorController::move_right(&buf, CursorPosition::new(1, 0));
+        assert_eq!(pos, CursorPosition::new(0, 5));
+    }
+
     #[test]
     fn test_go_to_line() {
         let buf = make_buffer();
@@ -310,5 +372,10 @@ mod tests {
 
         // Cursor below viewport
         assert_eq!(calculate_scroll(30, 5, 20, 3), 14);
+
+        // Oversized margins are clamped, so the scroll settles
+        let scroll = calculate_scroll(50, 0, 10, 100);
+        assert_eq!(scroll, 45);
+        assert_eq!(calculate_scroll(50, scroll, 10, 100), scroll);
     }
 }
@@ -0,0 +1,330 @@
+//! Line-based diffing of document content
+//!
+//! Provides:
+//! - Line diffs between two versions of a document
+//! - Word-level changes within lines that were edited
+//! - Gutter markers for changed lines of the newer version
+
+use similar::{ChangeTag, TextDiff};
+use std::ops::Range;
+
+/// Line count above which diffs should run on a background task
+pub const BACKGROUND_DIFF_LINES: usize = 5000;
+
+/// Similarity, as `TextDiff::ratio` of their words and spaces, two lines
+/// need to be diffed word by word; lines that differ more were rewritten
+/// and are shown whole
+const MIN_WORD_SIMILARITY: f32 = 0.5;
+
+/// Kind of a line in a diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Line present in both versions
+    Equal,
+    /// Line only in the new version
+    Added,
+    /// Line only in the old version
+    Removed,
+}
+
+impl DiffKind {
+    /// Get the prefix shown before the line in a diff view
+    pub fn prefix(&self) -> char {
+        match self {
+            DiffKind::Equal => ' ',
+            DiffKind::Added => '+',
+            DiffKind::Removed => '-',
+        }
+    }
+}
+
+/// A single line of a diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    /// Whether the line was kept, added, or removed
+    pub kind: DiffKind,
+    /// Line index in the old version (0-indexed)
+    pub old_line: Option<usize>,
+    /// Line index in the new version (0-indexed)
+    pub new_line: Option<usize>,
+    /// Line text without the trailing newline
+    pub text: String,
+    /// Parts of the text that changed, for a removed or added line that
+    /// was edited rather than rewritten; `None` when the whole line changed
+    pub words: Option<Vec<WordChange>>,
+}
+
+impl DiffLine {
+    /// Whether the line's edit only changed whitespace
+    pub fn is_whitespace_change(&self) -> bool {
+        self.words
+            .as_ref()
+            .is_some_and(|words| words.iter().all(|word| word.whitespace))
+    }
+}
+
+/// Changed text within a line of a diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordChange {
+    /// Byte range in the line's text
+    pub range: Range<usize>,
+    /// Whether only whitespace changed
+    pub whitespace: bool,
+}
+
+/// Kind of change shown in the editor gutter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Line was added
+    Added,
+    /// Line replaces one or more old lines
+    Modified,
+    /// Lines were deleted before this line
+    Removed,
+}
+
+impl ChangeKind {
+    /// Get the gutter marker for the change
+    pub fn symbol(&self) -> char {
+        match self {
+            ChangeKind::Added => '+',
+            ChangeKind::Modified => '~',
+            ChangeKind::Removed => '-',
+        }
+    }
+}
+
+/// A changed line of the new version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineChange {
+    /// Line index in the new version (0-indexed)
+    pub line: usize,
+    /// Kind of change
+    pub kind: ChangeKind,
+}
+
+/// Diff two texts line by line
+///
+/// Removed lines directly followed by added lines are paired in order, and
+/// each pair is diffed word by word.
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let diff = TextDiff::from_lines(old, new);
+
+    let mut lines: Vec<DiffLine> = diff
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            kind: match change.tag() {
+                ChangeTag::Equal => DiffKind::Equal,
+                ChangeTag::Insert => DiffKind::Added,
+                ChangeTag::Delete => DiffKind::Removed,
+            },
+            old_line: change.old_index(),
+            new_line: change.new_index(),
+            text: change
+                .value()
+                .trim_end_matches('\n')
+                .trim_end_matches('\r')
+                .to_string(),
+            words: None,
+        })
+        .collect();
+
+    let mut start = 0;
+    while start < lines.len() {
+        let removed = lines[start..]
+            .iter()
+            .take_while(|line| line.kind == DiffKind::Removed)
+            .count();
+        let added = lines[start + removed..]
+            .iter()
+            .take_while(|line| line.kind == DiffKind::Added)
+            .count();
+        for i in 0..removed.min(added) {
+            let (old, new) = (start + i, start + removed + i);
+            if let Some((old_words, new_words)) = word_changes(&lines[old].text, &lines[new].text) {
+                lines[old].words = Some(old_words);
+                lines[new].words = Some(new_words);
+            }
+        }
+        start += (removed + added).max(1);
+    }
+    lines
+}
+
+/// Diff an old and a new line word by word
+///
+/// Returns the changed parts of each, or `None` when the lines have too
+/// little in common for a word diff to be more readable than the lines.
+pub fn word_changes(old: &str, new: &str) -> Option<(Vec<WordChange>, Vec<WordChange>)> {
+    let diff = TextDiff::from_words(old, new);
+    let whitespace_only = old.split_whitespace().eq(new.split_whitespace());
+    if diff.ratio() < MIN_WORD_SIMILARITY && !whitespace_only {
+        return None;
+    }
+
+    let (mut old_words, mut new_words) = (Vec::new(), Vec::new());
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for change in diff.iter_all_changes() {
+        let value = change.value();
+        let (pos, words) = match change.tag() {
+            ChangeTag::Equal => {
+                old_pos += value.len();
+                new_pos += value.len();
+                continue;
+            }
+            ChangeTag::Delete => (&mut old_pos, &mut old_words),
+            ChangeTag::Insert => (&mut new_pos, &mut new_words),
+        };
+        push_word(words, *pos..*pos + value.len(), value.trim().is_empty());
+        *pos += value.len();
+    }
+    Some((old_words, new_words))
+}
+
+/// Add a changed range, merging it into the previous one when they touch
+/// and are alike
+fn push_word(words: &mut Vec<WordChange>, range: Range<usize>, whitespace: bool) {
+    match words.last_mut() {
+        Some(last) if last.range.end == range.start && last.whitespace == whitespace => {
+            last.range.end = range.end;
+        }
+        _ => words.push(WordChange { range, whitespace }),
+    }
+}
+
+/// Compute gutter markers for the new version from a line diff
+///
+/// Added lines that directly replace removed lines are marked modified;
+/// a pure deletion is marked on the line that follows it.
+pub fn line_changes(diff: &[DiffLine]) -> Vec<LineChange> {
+    let mut changes = Vec::new();
+    let mut removed = 0;
+    let mut next_new_line = 0;
+
+    for line in diff {
+        match line.kind {
+            DiffKind::Removed => removed += 1,
+            DiffKind::Added => {
+                let new_line = line.new_line.unwrap_or(next_new_line);
+                let kind = if removed > 0 {
+                    removed -= 1;
+                    ChangeKind::Modified
+                } else {
+                    ChangeKind::Added
+                };
+                changes.push(LineChange { line: new_line, kind });
+                next_new_line = new_line + 1;
+            }
+            DiffKind::Equal => {
+                let new_line = line.new_line.unwrap_or(next_new_line);
+                if removed > 0 {
+                    changes.push(LineChange { line: new_line, kind: ChangeKind::Removed });
+                    removed = 0;
+                }
+                next_new_line = new_line + 1;
+            }
+        }
+    }
+
+    // Deletion at the end of the document
+    if removed > 0 {
+        let line = next_new_line.saturating_sub(1);
+        if !changes.iter().any(|c| c.line == line) {
+            changes.push(LineChange { line, kind: ChangeKind::Removed });
+        }
+    }
+
+    changes
+}
+
+/// Count the added and removed lines of a diff
+pub fn diff_stats(diff: &[DiffLine]) -> (usize, usize) {
+    diff.iter().fold((0, 0), |(added, removed), line| match line.kind {
+        DiffKind::Added => (added + 1, removed),
+        DiffKind::Removed => (added, removed + 1),
+        DiffKind::Equal => (added, removed),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff() {
+        let diff = line_diff("a\nb\nc\n", "a\nB\nc\nd\n");
+        let kinds: Vec<_> = diff.iter().map(|l| (l.kind.prefix(), l.text.as_str())).collect();
+
+        assert_eq!(
+            kinds,
+            vec![(' ', "a"), ('-', "b"), ('+', "B"), (' ', "c"), ('+', "d")]
+        );
+        assert_eq!(diff_stats(&diff), (2, 1));
+    }
+
+    #[test]
+    fn test_word_changes() {
+        let diff = line_diff("the quick brown fox\n", "the slow brown fox\n");
+        let changed = |line: &DiffLine| -> Vec<String> {
+            let words = line.words.as_ref().unwrap();
+            words.iter().map(|w| line.text[w.range.clone()].to_string()).collect()
+        };
+        assert_eq!(changed(&diff[0]), vec!["quick"]);
+        assert_eq!(changed(&diff[1]), vec!["slow"]);
+        assert!(!diff[1].is_whitespace_change());
+
+        // A rewritten line is shown whole
+        let diff = line_diff("alpha beta gamma\n", "one two three\n");
+        assert!(diff.iter().all(|line| line.words.is_none()));
+
+        // Whitespace changes are kept, marked as such
+        let diff = line_diff("a list item\n", "  a list item\n");
+        assert_eq!(
+            diff[1].words,
+            Some(vec![WordChange { range: 0..2, whitespace: true }])
+        );
+        assert!(diff[1].is_whitespace_change());
+        assert_eq!(diff[0].words, Some(Vec::new()));
+        assert!(diff[0].is_whitespace_change());
+
+        // Unpaired lines are shown whole
+        let diff = line_diff("a\n", "a\nnew line\n");
+        assert!(diff[1].words.is_none());
+    }
+
+    #[test]
+    fn test_line_changes() {
+        let diff = line_diff("a\nb\nc\nd\n", "a\nB\nc\nnew\n");
+        let changes = line_changes(&diff);
+
+        assert_eq!(
+            changes,
+            vec![
+                LineChange { line: 1, kind: ChangeKind::Modified },
+                LineChange { line: 3, kind: ChangeKind::Modified },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_changes_deletion() {
+        let diff = line_diff("a\nb\nc\n", "a\nc\n");
+        assert_eq!(
+            line_changes(&diff),
+            vec![LineChange { line: 1, kind: ChangeKind::Removed }]
+        );
+
+        let diff = line_diff("a\nb\n", "a\n");
+        assert_eq!(
+            line_changes(&diff),
+            vec![LineChange { line: 0, kind: ChangeKind::Removed }]
+        );
+    }
+
+    #[test]
+    fn test_all_new() {
+        let diff = line_diff("", "one\ntwo");
+        assert!(diff.iter().all(|l| l.kind == DiffKind::Added));
+        assert_eq!(line_changes(&diff).len(), 2);
+    }
+}
@@ -0,0 +1,545 @@
+//! Markdown formatting commands
+//!
+//! Provides:
+//! - Toggling inline emphasis (bold, italic, code, strikethrough)
+//! - Adjusting heading levels
+//! - Quoting and unquoting lines
+//! - Wrapping lines in a callout
+//! - Surrounding a selection with a pair of characters
+//! - Finding and relabeling the fenced code block around a line
+
+use super::TextBuffer;
+use crate::markdown::callout::{self, CalloutKind};
+use crate::markdown::{LineState, MarkdownTokenizer};
+use ropey::Rope;
+use std::ops::Range;
+
+/// Kind of inline emphasis that can be toggled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmphasisKind {
+    /// Strong emphasis (`**text**` or `__text__`)
+    Bold,
+    /// Emphasis (`*text*` or `_text_`)
+    Italic,
+    /// Inline code (`` `text` ``)
+    Code,
+    /// GFM strikethrough (`~~text~~`)
+    Strikethrough,
+}
+
+impl EmphasisKind {
+    /// Get the marker inserted around wrapped text
+    pub fn marker(&self) -> &'static str {
+        match self {
+            EmphasisKind::Bold => "**",
+            EmphasisKind::Italic => "*",
+            EmphasisKind::Code => "`",
+            EmphasisKind::Strikethrough => "~~",
+        }
+    }
+
+    /// Get the characters a marker of this kind can be made of
+    fn marker_chars(&self) -> &'static [char] {
+        match self {
+            EmphasisKind::Bold | EmphasisKind::Italic => &['*', '_'],
+            EmphasisKind::Code => &['`'],
+            EmphasisKind::Strikethrough => &['~'],
+        }
+    }
+
+    /// Get how many marker characters to remove from a run on each side
+    ///
+    /// Follows the tokenizer: a run of three is bold italic, so bold
+    /// removes two characters from it and italic removes one.
+    fn strip_len(&self, run: usize) -> Option<usize> {
+        match self {
+            EmphasisKind::Bold | EmphasisKind::Strikethrough => (run >= 2).then_some(2),
+            EmphasisKind::Italic => (run == 1 || run >= 3).then_some(1),
+            EmphasisKind::Code => (run >= 1).then_some(run),
+        }
+    }
+}
+
+/// Toggle emphasis on a character range
+///
+/// The markers are removed when the range starts and ends with them or is
+/// directly surrounded by them; otherwise the range is wrapped. An empty range
+/// gets an empty pair of markers. Returns the range of the text between the
+/// markers.
+pub fn toggle_emphasis(
+    buffer: &mut TextBuffer,
+    range: Range<usize>,
+    kind: EmphasisKind,
+) -> Range<usize> {
+    let len = buffer.len_chars();
+    let start = range.start.min(len);
+    let end = range.end.min(len).max(start);
+    let selected: Vec<char> = buffer.slice(start, end).chars().collect();
+
+    // Markers just inside the selection
+    for &marker in kind.marker_chars() {
+        let leading = selected.iter().take_while(|&&c| c == marker).count();
+        let trailing = selected.iter().rev().take_while(|&&c| c == marker).count();
+        let run = leading.min(trailing).min(selected.len() / 2);
+
+        // The opening run must be closed at the end, not inside the selection
+        let inner: String = selected[run..selected.len() - run].iter().collect();
+        if run == 0 || inner.contains(&marker.to_string().repeat(run)) {
+            continue;
+        }
+
+        if let Some(n) = kind.strip_len(run) {
+            buffer.delete(end - n..end);
+            buffer.delete(start..start + n);
+            return start..end - 2 * n;
+        }
+    }
+
+    // Markers just outside the selection
+    for &marker in kind.marker_chars() {
+        let before = (0..start)
+            .rev()
+            .take_while(|&idx| buffer.char_at(idx) == Some(marker))
+            .count();
+        let after = (end..len)
+            .take_while(|&idx| buffer.char_at(idx) == Some(marker))
+            .count();
+
+        if let Some(n) = kind.strip_len(before.min(after)) {
+            buffer.delete(end..end + n);
+            buffer.delete(start - n..start);
+            return start - n..end - n;
+        }
+    }
+
+    let marker = kind.marker();
+    let marker_len = marker.chars().count();
+    buffer.insert(end, marker);
+    buffer.insert(start, marker);
+    start + marker_len..end + marker_len
+}
+
+/// Deepest heading level
+pub const MAX_HEADING_LEVEL: usize = 6;
+
+/// Options for adjusting heading levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadingOptions {
+    /// Wrap past level 6 back to a paragraph instead of stopping
+    pub wrap: bool,
+    /// Turn a paragraph line into a heading
+    pub create: bool,
+}
+
+impl Default for HeadingOptions {
+    fn default() -> Self {
+        Self {
+            wrap: false,
+            create: true,
+        }
+    }
+}
+
+/// Change the heading level of the lines touched by a character range
+///
+/// A positive delta adds `#`s and a negative one removes them, turning a
+/// level 1 heading into a paragraph. Within a multi-line range only heading
+/// lines change. Returns the range to select afterwards.
+pub fn adjust_heading(
+    buffer: &mut TextBuffer,
+    range: Range<usize>,
+    delta: i32,
+    options: HeadingOptions,
+) -> Range<usize> {
+    let span = buffer.line_span(range.clone());
+    let first_line = buffer.rope().char_to_line(span.start);
+    let last_line = buffer.rope().char_to_line(span.end);
+    let multi_line = last_line > first_line;
+
+    let mut lines = Vec::new();
+    let mut cursor = range.start;
+    for line_idx in first_line..=last_line {
+        let line = buffer.line_without_newline(line_idx).unwrap_or_default();
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+
+        let (level, content) = match MarkdownTokenizer::parse_heading(trimmed) {
+            Some((level, content_start)) => (level, &trimmed[content_start..]),
+            None if options.create && !multi_line && !trimmed.is_empty() => (0, trimmed),
+            None => {
+                lines.push(line.clone());
+                continue;
+            }
+        };
+
+        let level = level as i32 + delta;
+        let new_level = if options.wrap {
+            level.rem_euclid(MAX_HEADING_LEVEL as i32 + 1) as usize
+        } else {
+            level.clamp(0, MAX_HEADING_LEVEL as i32) as usize
+        };
+
+        let new_line = match (new_level, content.is_empty()) {
+            (0, _) => format!("{}{}", indent, content),
+            (_, true) => format!("{}{}", indent, "#".repeat(new_level)),
+            (_, false) => format!("{}{} {}", indent, "#".repeat(new_level), content),
+        };
+
+        // Keep a collapsed cursor on the same character of the content
+        if range.is_empty() {
+            let line_start = span.start;
+            let old_prefix = line.chars().count() - content.chars().count();
+            let new_prefix = new_line.chars().count() - content.chars().count();
+            let column = range.start - line_start;
+            let column = if column >= old_prefix {
+                column - old_prefix + new_prefix
+            } else {
+                column.min(new_prefix)
+            };
+            cursor = line_start + column;
+        }
+        lines.push(new_line);
+    }
+
+    let replacement = lines.join("\n");
+    if replacement != buffer.slice_range(span.clone()) {
+        buffer.replace(span.clone(), &replacement);
+    }
+
+    if range.is_empty() {
+        cursor..cursor
+    } else {
+        span.start..span.start + replacement.chars().count()
+    }
+}
+
+/// Add or remove one blockquote level on the lines touched by a range
+///
+/// When every non-blank line is already quoted, one `> ` is removed from
+/// each; otherwise every line, blank or not, gets one so the quote stays
+/// contiguous. Returns the range of the changed lines.
+pub fn toggle_blockquote(buffer: &mut TextBuffer, range: Range<usize>) -> Range<usize> {
+    let span = buffer.line_span(range);
+    let original = buffer.slice_range(span.clone());
+    let lines: Vec<&str> = original.split('\n').collect();
+
+    let quoted = |line: &str| MarkdownTokenizer::parse_blockquote(line.trim_start()).is_some();
+    let unquote = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .all(|line| quoted(line))
+        && lines.iter().any(|line| quoted(line));
+
+    let new_lines: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+            if unquote {
+                match MarkdownTokenizer::parse_blockquote(trimmed) {
+                    Some(content_start) => format!("{}{}", indent, &trimmed[content_start..]),
+                    None => line.to_string(),
+                }
+            } else if trimmed.is_empty() {
+                format!("{}>", indent)
+            } else {
+                format!("{}> {}", indent, trimmed)
+            }
+        })
+        .collect();
+
+    let replacement = new_lines.join("\n");
+    if replacement != original {
+        buffer.replace(span.clone(), &replacement);
+    }
+    span.start..span.start + replacement.chars().count()
+}
+
+/// Wrap the lines touched by a range in a callout of a type
+///
+/// Returns the range of the callout. On a blank line the callout gets an
+/// empty quoted line to type into, and the range is collapsed onto it.
+pub fn wrap_in_callout(buffer: &mut TextBuffer, range: Range<usize>, kind: CalloutKind) -> Range<usize> {
+    let span = buffer.line_span(range);
+    let original = buffer.slice_range(span.clone());
+    if original.trim().is_empty() {
+        let callout = format!("{}\n> ", callout::wrap("", kind));
+        buffer.replace(span.clone(), &callout);
+        let end = span.start + callout.chars().count();
+        return end..end;
+    }
+    let callout = callout::wrap(&original, kind);
+    buffer.replace(span.clone(), &callout);
+    span.start..span.start + callout.chars().count()
+}
+
+/// Surround a character range with an opening and closing character
+///
+/// Unlike [`toggle_emphasis`] this always wraps, so surrounding an
+/// already-surrounded range nests the pairs. An empty range gets an empty
+/// pair. Returns the range of the text between them.
+pub fn surround(buffer: &mut TextBuffer, range: Range<usize>, open: char, close: char) -> Range<usize> {
+    let len = buffer.len_chars();
+    let start = range.start.min(len);
+    let end = range.end.min(len).max(start);
+    buffer.insert(end, &close.to_string());
+    buffer.insert(start, &open.to_string());
+    start + 1..end + 1
+}
+
+/// Languages offered when changing a code block's language
+///
+/// The first entry stands for a block without a language.
+pub const COMMON_CODE_LANGUAGES: &[&str] = &[
+    "plain text", "bash", "c", "cpp", "css", "go", "html", "java", "javascript", "json", "markdown", "mermaid",
+    "python", "rust", "sql", "toml", "typescript", "yaml",
+];
+
+/// The opening fence of a fenced code block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeFence {
+    /// Line of the opening fence
+    pub line: usize,
+    /// Language from the info string (empty for plain text)
+    pub language: String,
+}
+
+/// Find the fenced code block containing a line
+///
+/// The fence lines themselves count as part of the block.
+pub fn code_fence_at(rope: &Rope, line: usize) -> Option<CodeFence> {
+    if line >= rope.len_lines() {
+        return None;
+    }
+
+    let mut tokenizer = MarkdownTokenizer::new();
+    let mut state = LineState::Normal;
+    let mut opening = None;
+    for idx in 0..=line {
+        let text = rope.line(idx).to_string();
+        let text = text.trim_end_matches(['\n', '\r']);
+        let start = state;
+        state = tokenizer.tokenize_line(idx, text, start).end_state;
+
+        match (start, state) {
+            (LineState::Normal, LineState::InCodeBlock { .. }) => opening = Some(idx),
+            (LineState::InCodeBlock { .. }, LineState::InCodeBlock { .. }) => {}
+            // The closing fence is still inside the block
+            (LineState::InCodeBlock { .. }, _) if idx == line => {}
+            _ => opening = None,
+        }
+    }
+
+    let line = opening?;
+    let (_, _, info) = split_fence(&rope.line(line).to_string());
+    Some(CodeFence {
+        line,
+        language: info.split_whitespace().next().unwrap_or("").to_string(),
+    })
+}
+
+/// Split an opening fence line into indentation, fence, and info string
+fn split_fence(line: &str) -> (&str, &str, &str) {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    let fence_char = trimmed.chars().next().unwrap_or('`');
+    let fence_len = trimmed.len() - trimmed.trim_start_matches(fence_char).len();
+    (indent, &trimmed[..fence_len], trimmed[fence_len..].trim())
+}
+
+/// Set the language of the fenced code block containing the range start
+///
+/// Only the first word of the info string is replaced, and an empty
+/// language leaves a bare fence. Returns the range shifted to match the
+/// edit, or the range unchanged outside a code block.
+pub fn set_code_block_language(
+    buffer: &mut TextBuffer,
+    range: Range<usize>,
+    language: &str,
+) -> Range<usize> {
+    let line = buffer.rope().char_to_line(range.start.min(buffer.len_chars()));
+    let Some(fence) = code_fence_at(buffer.rope(), line) else {
+        return range;
+    };
+
+    let old_line = buffer.line_without_newline(fence.line).unwrap_or_default();
+    let (indent, marker, info) = split_fence(&old_line);
+    let attributes = info.split_once(char::is_whitespace).map(|(_, rest)| rest.trim());
+    let new_info = match (language.trim(), attributes) {
+        ("", Some(rest)) => rest.to_string(),
+        (language, Some(rest)) => format!("{} {}", language, rest),
+        (language, None) => language.to_string(),
+    };
+    let new_line = format!("{}{}{}", indent, marker, new_info);
+    if new_line == old_line {
+        return range;
+    }
+
+    let line_start = buffer.rope().line_to_char(fence.line);
+    let old_end = line_start + old_line.chars().count();
+    let new_end = line_start + new_line.chars().count();
+    buffer.replace(line_start..old_end, &new_line);
+
+    let shift = |idx: usize| {
+        if idx >= old_end {
+            idx + new_end - old_end
+        } else {
+            idx.min(new_end)
+        }
+    };
+    shift(range.start)..shift(range.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toggle(text: &str, range: Range<usize>, kind: EmphasisKind) -> (String, Range<usize>) {
+        let mut buffer = TextBuffer::from_str(text);
+        let range = toggle_emphasis(&mut buffer, range, kind);
+        (buffer.to_string(), range)
+    }
+
+    #[test]
+    fn test_wrap_and_unwrap() {
+        assert_eq!(toggle("a word", 2..6, EmphasisKind::Bold), ("a **word**".to_string(), 4..8));
+        assert_eq!(toggle("**bold**", 0..8, EmphasisKind::Bold), ("bold".to_string(), 0..4));
+        assert_eq!(toggle("__bold__", 0..8, EmphasisKind::Bold), ("bold".to_string(), 0..4));
+        assert_eq!(toggle("**bold**", 2..6, EmphasisKind::Bold), ("bold".to_string(), 0..4));
+        assert_eq!(toggle("~~old~~", 0..7, EmphasisKind::Strikethrough).0, "old");
+    }
+
+    #[test]
+    fn test_nested_markers() {
+        // Only the surrounding markers are removed
+        assert_eq!(toggle("***both***", 0..10, EmphasisKind::Italic).0, "**both**");
+        assert_eq!(toggle("***both***", 0..10, EmphasisKind::Bold).0, "*both*");
+        assert_eq!(toggle("**b**", 0..5, EmphasisKind::Italic).0, "***b***");
+        assert_eq!(toggle("**a** b **c**", 0..13, EmphasisKind::Italic).0, "***a** b **c***");
+    }
+
+    #[test]
+    fn test_empty_selection() {
+        assert_eq!(toggle("ab", 1..1, EmphasisKind::Code), ("a``b".to_string(), 2..2));
+        assert_eq!(toggle("a****b", 3..3, EmphasisKind::Bold), ("ab".to_string(), 1..1));
+    }
+
+    fn heading(text: &str, range: Range<usize>, delta: i32, options: HeadingOptions) -> String {
+        let mut buffer = TextBuffer::from_str(text);
+        adjust_heading(&mut buffer, range, delta, options);
+        buffer.to_string()
+    }
+
+    #[test]
+    fn test_adjust_heading() {
+        let options = HeadingOptions::default();
+        assert_eq!(heading("# Title", 0..0, 1, options), "## Title");
+        assert_eq!(heading("## Title", 0..0, -1, options), "# Title");
+        assert_eq!(heading("# Title", 0..0, -1, options), "Title");
+        assert_eq!(heading("Title", 0..0, 1, options), "# Title");
+        assert_eq!(heading("Title", 0..0, -1, options), "Title");
+
+        let no_create = HeadingOptions { create: false, ..options };
+        assert_eq!(heading("Title", 0..0, 1, no_create), "Title");
+    }
+
+    #[test]
+    fn test_heading_limits() {
+        let options = HeadingOptions::default();
+        assert_eq!(heading("###### Deep", 0..0, 1, options), "###### Deep");
+
+        let wrap = HeadingOptions { wrap: true, ..options };
+        assert_eq!(heading("###### Deep", 0..0, 1, wrap), "Deep");
+    }
+
+    #[test]
+    fn test_heading_selection() {
+        let mut buffer = TextBuffer::from_str("# A\ntext\n## B");
+        let range = adjust_heading(&mut buffer, 0..14, 1, HeadingOptions::default());
+        assert_eq!(buffer.to_string(), "## A\ntext\n### B");
+        assert_eq!(range, 0..15);
+
+        let mut buffer = TextBuffer::from_str("# Title");
+        let range = adjust_heading(&mut buffer, 4..4, 1, HeadingOptions::default());
+        assert_eq!(range, 5..5);
+    }
+
+    fn quote(text: &str) -> String {
+        let mut buffer = TextBuffer::from_str(text);
+        let len = buffer.len_chars();
+        toggle_blockquote(&mut buffer, 0..len);
+        buffer.to_string()
+    }
+
+    #[test]
+    fn test_toggle_blockquote() {
+        assert_eq!(quote("one\n\ntwo"), "> one\n>\n> two");
+        assert_eq!(quote("> one\n>\n> two"), "one\n\ntwo");
+
+        // Nested quotes gain or lose a single level
+        assert_eq!(quote("> > deep\n> shallow"), "> deep\nshallow");
+        assert_eq!(quote("> quoted\nplain"), "> > quoted\n> plain");
+    }
+
+    #[test]
+    fn test_wrap_in_callout() {
+        let mut buffer = TextBuffer::from_str("before\none\ntwo\nafter");
+        let range = wrap_in_callout(&mut buffer, 7..12, CalloutKind::Note);
+        assert_eq!(buffer.to_string(), "before\n> [!NOTE]\n> one\n> two\nafter");
+        assert_eq!(range, 7..28);
+
+        let mut buffer = TextBuffer::from_str("");
+        let range = wrap_in_callout(&mut buffer, 0..0, CalloutKind::Warning);
+        assert_eq!(buffer.to_string(), "> [!WARNING]\n> ");
+        assert_eq!(range, 15..15);
+    }
+
+    #[test]
+    fn test_surround() {
+        let mut buffer = TextBuffer::from_str("say word now");
+        let range = surround(&mut buffer, 4..8, '"', '"');
+        assert_eq!(buffer.to_string(), "say \"word\" now");
+        assert_eq!(range, 5..9);
+
+        // Surrounding again nests instead of toggling
+        let range = surround(&mut buffer, range.start - 1..range.end + 1, '(', ')');
+        assert_eq!(buffer.to_string(), "say (\"word\") now");
+        assert_eq!(range, 5..11);
+
+        let mut buffer = TextBuffer::from_str("x");
+        assert_eq!(surround(&mut buffer, 1..1, '“', '”'), 2..2);
+        assert_eq!(buffer.to_string(), "x“”");
+    }
+
+    #[test]
+    fn test_code_fence_at() {
+        let rope = Rope::from_str("text\n```rust ignore\nfn main() {}\n```\n\n  ~~~\n  code\n  ~~~\n");
+        let fence = |line| code_fence_at(&rope, line).map(|f| (f.line, f.language));
+        assert_eq!(fence(0), None);
+        assert_eq!(fence(1), Some((1, "rust".to_string())));
+        assert_eq!(fence(2), Some((1, "rust".to_string())));
+        assert_eq!(fence(3), Some((1, "rust".to_string())));
+        assert_eq!(fence(4), None);
+        assert_eq!(fence(6), Some((5, String::new())));
+    }
+
+    #[test]
+    fn test_set_code_block_language() {
+        let mut buffer = TextBuffer::from_str("  ```\n  code\n  ```");
+        let range = set_code_block_language(&mut buffer, 8..8, "python");
+        assert_eq!(buffer.to_string(), "  ```python\n  code\n  ```");
+        assert_eq!(range, 14..14);
+
+        let mut buffer = TextBuffer::from_str("```rust {.numbered}\nx\n```");
+        set_code_block_language(&mut buffer, 20..20, "toml");
+        assert_eq!(buffer.to_string(), "```toml {.numbered}\nx\n```");
+        set_code_block_language(&mut buffer, 20..20, "");
+        assert_eq!(buffer.to_string(), "```{.numbered}\nx\n```");
+
+        // Outside a code block nothing changes
+        let mut buffer = TextBuffer::from_str("plain");
+        assert_eq!(set_code_block_language(&mut buffer, 2..2, "rust"), 2..2);
+        assert_eq!(buffer.to_string(), "plain");
+    }
+}
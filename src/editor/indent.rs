@@ -0,0 +1,127 @@
+//! Indentation detection for opened files
+//!
+//! Looks at how a file's lines are indented to pick tabs or spaces, and
+//! how many spaces make one level, so editing keeps to the file's style
+//! rather than the configured default.
+
+/// Share of indented lines one style needs before the file counts as
+/// using it
+const DOMINANT_SHARE: f32 = 0.75;
+
+/// Widest indent level in spaces that detection picks
+const MAX_INDENT_WIDTH: usize = 8;
+
+/// How a file is indented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndentStyle {
+    /// Indented with tabs
+    Tabs,
+    /// Indented with this many spaces per level
+    Spaces(usize),
+    /// No indentation, or too mixed to tell
+    #[default]
+    Undetected,
+}
+
+impl IndentStyle {
+    /// Whether to indent with spaces, and the width of a level, falling
+    /// back to the defaults for what wasn't detected
+    ///
+    /// Tab-indented files keep the default width for drawing tabs.
+    pub fn resolve(self, default_use_spaces: bool, default_width: usize) -> (bool, usize) {
+        match self {
+            IndentStyle::Tabs => (false, default_width),
+            IndentStyle::Spaces(width) => (true, width),
+            IndentStyle::Undetected => (default_use_spaces, default_width),
+        }
+    }
+}
+
+/// Detect a file's indentation style from its content
+///
+/// Every indented line counts, so the style most lines use wins over the
+/// first indented line's. The space width is the most common step between
+/// a line's indent and a deeper one on the line before it.
+pub fn detect(content: &str) -> IndentStyle {
+    let mut tab_lines = 0;
+    let mut space_lines = 0;
+    // How often each indent step was seen, by its width in spaces
+    let mut steps = [0usize; MAX_INDENT_WIDTH + 1];
+    let mut previous = 0;
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        if indent.starts_with('\t') {
+            tab_lines += 1;
+            previous = 0;
+            continue;
+        }
+        let spaces = indent.len();
+        if spaces > 0 && !indent.contains('\t') {
+            space_lines += 1;
+            let step = spaces.saturating_sub(previous);
+            // A single space is alignment rather than a level
+            if (2..=MAX_INDENT_WIDTH).contains(&step) {
+                steps[step] += 1;
+            }
+        }
+        previous = spaces;
+    }
+
+    let indented = (tab_lines + space_lines) as f32;
+    if indented == 0.0 {
+        return IndentStyle::Undetected;
+    }
+    if tab_lines as f32 / indented >= DOMINANT_SHARE {
+        return IndentStyle::Tabs;
+    }
+    if space_lines as f32 / indented >= DOMINANT_SHARE {
+        // Ties go to the narrower width, which deeper levels are made of
+        let width = (2..=MAX_INDENT_WIDTH)
+            .rev()
+            .filter(|&w| steps[w] > 0)
+            .max_by_key(|&w| steps[w]);
+        if let Some(width) = width {
+            return IndentStyle::Spaces(width);
+        }
+    }
+    IndentStyle::Undetected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_spaces() {
+        let content = "- item\n  - nested\n    - deeper\n  - back\n- top\n";
+        assert_eq!(detect(content), IndentStyle::Spaces(2));
+
+        let content = "fn main() {\n    let a = 1;\n    if a {\n        call();\n    }\n}\n";
+        assert_eq!(detect(content), IndentStyle::Spaces(4));
+    }
+
+    #[test]
+    fn test_detect_tabs() {
+        assert_eq!(detect("a\n\tb\n\t\tc\n\td\n"), IndentStyle::Tabs);
+    }
+
+    #[test]
+    fn test_detect_dominant_style() {
+        // One tab-indented line first doesn't outweigh the rest
+        let content = "\tstray\n- a\n  - b\n  - c\n- d\n  - e\n  - f\n- g\n  - h\n";
+        assert_eq!(detect(content), IndentStyle::Spaces(2));
+    }
+
+    #[test]
+    fn test_detect_fallback() {
+        assert_eq!(detect("no\nindentation\n"), IndentStyle::Undetected);
+        assert_eq!(detect("a\n\tb\nc\n    d\n"), IndentStyle::Undetected);
+        assert_eq!(IndentStyle::Undetected.resolve(true, 4), (true, 4));
+        assert_eq!(IndentStyle::Tabs.resolve(true, 4), (false, 4));
+        assert_eq!(IndentStyle::Spaces(2).resolve(false, 4), (true, 2));
+    }
+}
@@ -0,0 +1,349 @@
+//! Link detection under the cursor
+//!
+//! Provides:
+//! - Finding the markdown link or autolink at a column of a line
+//! - Classifying link destinations as URLs, files, or heading anchors
+//! - Recognizing pasted URLs and fetching page titles for them
+//! - Linking to files dropped into a document
+
+use crate::markdown::image::{ImageConfig, ImageHandler};
+use crate::markdown::wiki_link::WikiLink;
+use crate::markdown::{LineState, MarkdownTokenizer, Token, TokenType};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long to wait for a page title before pasting the bare URL
+pub const TITLE_FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Maximum number of bytes read while looking for a page title
+const TITLE_FETCH_LIMIT: u64 = 64 * 1024;
+
+/// Where a link points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// URL with a scheme, such as `https:` or `mailto:`
+    Url(String),
+    /// Local file, relative to the document, with an optional heading anchor
+    File {
+        path: PathBuf,
+        anchor: Option<String>,
+    },
+    /// Heading anchor in the current document
+    Anchor(String),
+    /// Note named by a `[[wiki link]]`, resolved against the open folder
+    Note(WikiLink),
+}
+
+impl LinkTarget {
+    /// Classify a link destination
+    pub fn parse(destination: &str) -> Option<Self> {
+        // Drop an optional link title and angle brackets
+        let destination = destination.split_whitespace().next()?;
+        let destination = destination
+            .strip_prefix('<')
+            .and_then(|d| d.strip_suffix('>'))
+            .unwrap_or(destination);
+        if destination.is_empty() {
+            return None;
+        }
+
+        if has_scheme(destination) {
+            return Some(LinkTarget::Url(destination.to_string()));
+        }
+
+        if let Some(anchor) = destination.strip_prefix('#') {
+            return (!anchor.is_empty()).then(|| LinkTarget::Anchor(anchor.to_string()));
+        }
+
+        let (path, anchor) = match destination.split_once('#') {
+            Some((path, anchor)) => (path, (!anchor.is_empty()).then(|| anchor.to_string())),
+            None => (destination, None),
+        };
+        Some(LinkTarget::File {
+            path: PathBuf::from(path.replace("%20", " ")),
+            anchor,
+        })
+    }
+}
+
+/// Check whether a destination starts with a URL scheme
+///
+/// Single-letter schemes are treated as Windows drive letters.
+fn has_scheme(destination: &str) -> bool {
+    match destination.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Find the link at a character column of a line
+pub fn link_at(line: &str, column: usize) -> Option<LinkTarget> {
+    let mut tokenizer = MarkdownTokenizer::new();
+    let tokens = &tokenizer.tokenize_line(0, line, LineState::Normal).tokens;
+    let chars: Vec<char> = line.chars().collect();
+    let slice = |start: usize, end: usize| -> String {
+        chars[start.min(chars.len())..end.min(chars.len())].iter().collect()
+    };
+
+    // Styles nested in link text and emphasis, outermost first
+    let tokens: Vec<&Token> = tokens.iter().flat_map(Token::styles).collect();
+    for (i, token) in tokens.iter().enumerate() {
+        if column < token.start || column > token.end {
+            continue;
+        }
+        match token.token_type {
+            TokenType::Autolink => return LinkTarget::parse(&slice(token.start, token.end)),
+            TokenType::WikiLink => {
+                let inner = slice(token.start + 2, token.end.saturating_sub(2));
+                return WikiLink::parse(&inner).map(LinkTarget::Note);
+            }
+            TokenType::LinkText | TokenType::ImageAlt => {
+                let url_type = if token.token_type == TokenType::LinkText {
+                    TokenType::LinkUrl
+                } else {
+                    TokenType::ImageUrl
+                };
+                let url = tokens[i + 1..].iter().find(|t| t.token_type == url_type)?;
+                return LinkTarget::parse(&slice(url.start + 1, url.end.saturating_sub(1)));
+            }
+            TokenType::LinkUrl | TokenType::ImageUrl => {
+                return LinkTarget::parse(&slice(token.start + 1, token.end.saturating_sub(1)));
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Check whether pasted text is a single `http(s)` or `mailto` URL
+///
+/// Returns the URL without surrounding whitespace.
+pub fn pasted_url(text: &str) -> Option<&str> {
+    let url = text.trim();
+    if url.is_empty() || url.chars().any(char::is_whitespace) {
+        return None;
+    }
+
+    let lower = url.to_ascii_lowercase();
+    let rest = ["https://", "http://", "mailto:"]
+        .iter()
+        .find_map(|scheme| lower.strip_prefix(scheme))?;
+    (!rest.is_empty() && !rest.starts_with('/')).then_some(url)
+}
+
+/// Build a markdown link, escaping the text and wrapping awkward URLs
+pub fn markdown_link(text: &str, url: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    if url.contains(['(', ')', ' ']) {
+        format!("[{}](<{}>)", escaped, url)
+    } else {
+        format!("[{}]({})", escaped, url)
+    }
+}
+
+/// Markdown for a file dropped into the document at `document`
+///
+/// Images are embedded as `![alt](path)` and other files linked as
+/// `[title](path)`, titled after the file name. Paths are relative to the
+/// document's folder; a document that was never saved gets the absolute
+/// path.
+pub fn dropped_file_link(path: &Path, document: Option<&Path>) -> String {
+    let is_image = ImageHandler::is_supported_image(path);
+    if let (true, Some(document)) = (is_image, document) {
+        // Link the image where it is, rather than copying it to the assets
+        let linker = ImageHandler::with_config(ImageConfig {
+            copy_images: false,
+            ..ImageConfig::default()
+        });
+        if let Ok(link) = linker.handle_dropped_file(path, document) {
+            return link;
+        }
+    }
+
+    let url = document
+        .and_then(Path::parent)
+        .and_then(|dir| crate::utils::path::relative_from(path, dir))
+        .unwrap_or_else(|| path.to_path_buf());
+    let url = url.to_string_lossy();
+    let title = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().replace(['-', '_'], " "))
+        .unwrap_or_default();
+    if is_image {
+        format!("![{}]({})", title, url)
+    } else {
+        markdown_link(&title, &url)
+    }
+}
+
+/// Extract the `<title>` of an HTML page
+pub fn html_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+
+    let title = html[start..end]
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ");
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// Fetch the title of a web page
+///
+/// Blocks for at most `timeout`; returns `None` on any failure.
+pub fn fetch_title(url: &str, timeout: Duration) -> Option<String> {
+    if !url.to_ascii_lowercase().starts_with("http") {
+        return None;
+    }
+
+    let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+    let response = match agent.get(url).call() {
+        Ok(response) => response,
+        Err(e) => {
+            log::debug!("Failed to fetch title for {}: {}", url, e);
+            return None;
+        }
+    };
+    if !response.content_type().contains("html") {
+        return None;
+    }
+
+    let mut body = String::new();
+    response
+        .into_reader()
+        .take(TITLE_FETCH_LIMIT)
+        .read_to_string(&mut body)
+        .ok()?;
+    html_title(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            LinkTarget::parse("https://example.com \"Title\""),
+            Some(LinkTarget::Url("https://example.com".to_string()))
+        );
+        assert_eq!(
+            LinkTarget::parse("mailto:me@example.com"),
+            Some(LinkTarget::Url("mailto:me@example.com".to_string()))
+        );
+        assert_eq!(
+            LinkTarget::parse("notes.md#section"),
+            Some(LinkTarget::File {
+                path: PathBuf::from("notes.md"),
+                anchor: Some("section".to_string()),
+            })
+        );
+        assert_eq!(
+            LinkTarget::parse("#intro"),
+            Some(LinkTarget::Anchor("intro".to_string()))
+        );
+        assert!(matches!(LinkTarget::parse("C:/notes.md"), Some(LinkTarget::File { .. })));
+    }
+
+    #[test]
+    fn test_link_at() {
+        let line = "See [the docs](docs/guide.md) or https://example.com.";
+        assert_eq!(
+            link_at(line, 6),
+            Some(LinkTarget::File {
+                path: PathBuf::from("docs/guide.md"),
+                anchor: None,
+            })
+        );
+        assert_eq!(link_at(line, 20), link_at(line, 6));
+        assert_eq!(
+            link_at(line, 40),
+            Some(LinkTarget::Url("https://example.com".to_string()))
+        );
+        assert_eq!(link_at(line, 1), None);
+
+        // Links inside emphasis and emphasis inside link text
+        let url = Some(LinkTarget::Url("https://a.org".to_string()));
+        assert_eq!(link_at("**[a](https://a.org)**", 3), url);
+        assert_eq!(link_at("[*em* x](https://a.org)", 2), url);
+
+        let note = link_at("Plans: [[Plan#Goals|goals]]", 12);
+        assert!(matches!(note, Some(LinkTarget::Note(link)) if link.target == "Plan"));
+    }
+
+    #[test]
+    fn test_pasted_url() {
+        assert_eq!(pasted_url("  https://example.com/a?b=c\n"), Some("https://example.com/a?b=c"));
+        assert_eq!(pasted_url("HTTP://example.com"), Some("HTTP://example.com"));
+        assert_eq!(pasted_url("mailto:me@example.com"), Some("mailto:me@example.com"));
+        assert_eq!(pasted_url("https://"), None);
+        assert_eq!(pasted_url("ftp://example.com"), None);
+        assert_eq!(pasted_url("see https://example.com"), None);
+        assert_eq!(pasted_url("plain text"), None);
+    }
+
+    #[test]
+    fn test_markdown_link() {
+        assert_eq!(markdown_link("docs", "https://a.io"), "[docs](https://a.io)");
+        assert_eq!(markdown_link("[x]", "https://a.io"), "[\\[x\\]](https://a.io)");
+        assert_eq!(
+            markdown_link("wiki", "https://en.wikipedia.org/wiki/Rust_(language)"),
+            "[wiki](<https://en.wikipedia.org/wiki/Rust_(language)>)"
+        );
+        assert_eq!(markdown_link("notes", "my notes.md"), "[notes](<my notes.md>)");
+    }
+
+    #[test]
+    fn test_dropped_file_link() {
+        let document = Path::new("/notes/work/plan.md");
+        assert_eq!(
+            dropped_file_link(Path::new("/notes/meeting_notes.md"), Some(document)),
+            "[meeting notes](../meeting_notes.md)"
+        );
+        assert_eq!(
+            dropped_file_link(Path::new("/notes/work/todo.md"), None),
+            "[todo](/notes/work/todo.md)"
+        );
+        assert_eq!(
+            dropped_file_link(Path::new("/notes/img/cat-photo.png"), None),
+            "![cat photo](/notes/img/cat-photo.png)"
+        );
+
+        let dir = std::env::temp_dir().join(format!("cosmic-notebook-drop-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("img")).unwrap();
+        let image = dir.join("img/cat.png");
+        std::fs::write(&image, b"").unwrap();
+        let document = dir.join("notes.md");
+        assert_eq!(dropped_file_link(&image, Some(&document)), "![cat](img/cat.png)");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_html_title() {
+        let html = "<html><head><TITLE lang=\"en\">\n  Rust &amp; Friends\n</title></head></html>";
+        assert_eq!(html_title(html), Some("Rust & Friends".to_string()));
+        assert_eq!(html_title("<title> </title>"), None);
+        assert_eq!(html_title("<p>No title</p>"), None);
+    }
+}
@@ -0,0 +1,315 @@
+//! Live preview of markdown formatting in the editor
+//!
+//! Away from the cursor line, syntax markers such as `**`, `#` and `- `
+//! are dimmed and the text they format is drawn bold or italic, so the
+//! document reads close to its rendered form. The cursor line always shows
+//! its raw markers for editing, and inline code and fenced code blocks are
+//! never formatted.
+
+use std::ops::Range;
+
+use cosmic::iced::font::{Style, Weight};
+use cosmic::iced::Font;
+use cosmic::iced_core::text::highlighter::{Format, Highlighter};
+use cosmic::iced_core::Color;
+
+use crate::markdown::{LineState, MarkdownTokenizer, Token, TokenType};
+
+/// Opacity of dimmed syntax markers
+const MARKER_ALPHA: f32 = 0.3;
+
+/// How a piece of a line is drawn in live preview
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewStyle {
+    /// A syntax marker, dimmed
+    Marker,
+    /// Bold text, including headings
+    Bold,
+    /// Italic text
+    Italic,
+    /// Bold italic text
+    BoldItalic,
+}
+
+/// Formatting of one character of a line
+#[derive(Debug, Clone, Copy, Default)]
+struct CharStyle {
+    marker: bool,
+    bold: bool,
+    italic: bool,
+    /// Inside inline code, math, or a URL, which are never formatted
+    raw: bool,
+}
+
+/// Whether a line starting with this token is shown as it is written
+fn is_raw_block(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::CodeBlockDelimiter
+            | TokenType::CodeBlockContent
+            | TokenType::CodeBlockLanguage
+            | TokenType::Frontmatter
+            | TokenType::Math
+            | TokenType::HorizontalRule
+    )
+}
+
+/// Length of the markers around an emphasis span, and the characters
+/// they are made of
+fn emphasis_marker(token_type: TokenType) -> Option<(usize, &'static [char])> {
+    match token_type {
+        TokenType::Bold | TokenType::Strikethrough => Some((2, &['*', '_', '~'])),
+        TokenType::Italic => Some((1, &['*', '_'])),
+        TokenType::BoldItalic => Some((3, &['*', '_'])),
+        _ => None,
+    }
+}
+
+/// Styled byte ranges of a line for live preview, from its tokens
+///
+/// Inline token positions are in characters; the returned ranges are in
+/// bytes. Unstyled text has no range.
+pub fn preview_spans(line: &str, tokens: &[Token]) -> Vec<(Range<usize>, PreviewStyle)> {
+    if tokens.first().is_some_and(|t| is_raw_block(t.token_type)) {
+        return Vec::new();
+    }
+    let offsets: Vec<usize> = line.char_indices().map(|(i, _)| i).collect();
+    let chars: Vec<char> = line.chars().collect();
+    let mut styles = vec![CharStyle::default(); chars.len()];
+    let clamp = |range: Range<usize>| range.start.min(chars.len())..range.end.min(chars.len());
+
+    // Pieces of one emphasis span, split around nested styles, are merged
+    // back into the span; nested styles are spans of their own
+    let mut spans: Vec<(TokenType, Range<usize>)> = Vec::new();
+    let mut nested: Vec<(TokenType, Range<usize>)> = Vec::new();
+    for token in tokens {
+        match spans.last_mut() {
+            Some((token_type, range))
+                if *token_type == token.token_type && range.end == token.start =>
+            {
+                range.end = token.end;
+            }
+            _ => spans.push((token.token_type, token.start..token.end)),
+        }
+        nested.extend(token.styles().skip(1).map(|t| (t.token_type, t.start..t.end)));
+    }
+
+    for (token_type, range) in spans.into_iter().chain(nested) {
+        let range = clamp(range);
+        match token_type {
+            t if t.is_heading() => {
+                let leading = chars.iter().take_while(|c| **c == ' ').count();
+                let trimmed: String = chars[leading..].iter().collect();
+                if let Some((_, content_start)) = MarkdownTokenizer::parse_heading(&trimmed) {
+                    let content = (leading + content_start).min(chars.len());
+                    styles[..content].iter_mut().for_each(|s| s.marker = true);
+                    styles[content..].iter_mut().for_each(|s| s.bold = true);
+                }
+            }
+            TokenType::Blockquote | TokenType::UnorderedListMarker => {
+                styles[range].iter_mut().for_each(|s| s.marker = true);
+            }
+            TokenType::InlineCode
+            | TokenType::Math
+            | TokenType::Escape
+            | TokenType::LinkUrl
+            | TokenType::ImageUrl
+            | TokenType::Autolink
+            | TokenType::WikiLink => {
+                styles[range].iter_mut().for_each(|s| s.raw = true);
+            }
+            t => {
+                let Some((len, marker_chars)) = emphasis_marker(t) else {
+                    continue;
+                };
+                for style in &mut styles[range.clone()] {
+                    style.bold |= matches!(t, TokenType::Bold | TokenType::BoldItalic);
+                    style.italic |= matches!(t, TokenType::Italic | TokenType::BoldItalic);
+                }
+                let is_marker = |r: Range<usize>| chars[r].iter().all(|c| marker_chars.contains(c));
+                if range.len() > 2 * len
+                    && is_marker(range.start..range.start + len)
+                    && is_marker(range.end - len..range.end)
+                {
+                    let (open, close) = (range.start..range.start + len, range.end - len..range.end);
+                    styles[open].iter_mut().for_each(|s| s.marker = true);
+                    styles[close].iter_mut().for_each(|s| s.marker = true);
+                }
+            }
+        }
+    }
+
+    let mut result: Vec<(Range<usize>, PreviewStyle)> = Vec::new();
+    for (i, style) in styles.iter().enumerate() {
+        let preview = match style {
+            s if s.raw => continue,
+            s if s.marker => PreviewStyle::Marker,
+            s if s.bold && s.italic => PreviewStyle::BoldItalic,
+            s if s.bold => PreviewStyle::Bold,
+            s if s.italic => PreviewStyle::Italic,
+            _ => continue,
+        };
+        let bytes = offsets[i]..offsets.get(i + 1).copied().unwrap_or(line.len());
+        match result.last_mut() {
+            Some((range, last)) if *last == preview && range.end == bytes.start => {
+                range.end = bytes.end;
+            }
+            _ => result.push((bytes, preview)),
+        }
+    }
+    result
+}
+
+/// Text format for a live preview style
+pub fn format(style: &PreviewStyle, theme: &cosmic::Theme) -> Format<Font> {
+    let font = |weight, slant| Some(Font { weight, style: slant, ..Font::DEFAULT });
+    match style {
+        PreviewStyle::Marker => {
+            let color: Color = theme.cosmic().on_bg_color().into();
+            Format {
+                color: Some(Color { a: MARKER_ALPHA, ..color }),
+                font: None,
+            }
+        }
+        PreviewStyle::Bold => Format { color: None, font: font(Weight::Bold, Style::Normal) },
+        PreviewStyle::Italic => Format { color: None, font: font(Weight::Normal, Style::Italic) },
+        PreviewStyle::BoldItalic => Format { color: None, font: font(Weight::Bold, Style::Italic) },
+    }
+}
+
+/// What the live preview highlighter needs to know about its editor
+#[derive(Debug, Clone, PartialEq)]
+pub struct LivePreviewSettings {
+    /// Whether live preview is on for the document
+    pub enabled: bool,
+    /// Line the cursor is on, shown raw
+    pub cursor_line: usize,
+}
+
+/// Highlighter drawing a text editor's markdown in live preview
+///
+/// Lines are tokenized one at a time, carrying the tokenizer state between
+/// them so fenced code blocks are recognized.
+pub struct LivePreviewHighlighter {
+    settings: LivePreviewSettings,
+    tokenizer: MarkdownTokenizer,
+    /// State each line starts in, up to the next line to highlight
+    states: Vec<LineState>,
+    current_line: usize,
+}
+
+impl Highlighter for LivePreviewHighlighter {
+    type Settings = LivePreviewSettings;
+    type Highlight = PreviewStyle;
+    type Iterator<'a> = std::vec::IntoIter<(Range<usize>, PreviewStyle)>;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Self {
+            settings: settings.clone(),
+            tokenizer: MarkdownTokenizer::new(),
+            states: vec![LineState::Normal],
+            current_line: 0,
+        }
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        // Only the lines the cursor left and arrived on change, unless
+        // live preview was switched
+        let from = if new_settings.enabled != self.settings.enabled {
+            0
+        } else {
+            self.settings.cursor_line.min(new_settings.cursor_line)
+        };
+        self.settings = new_settings.clone();
+        self.change_line(from);
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.current_line = line.min(self.states.len() - 1);
+        self.states.truncate(self.current_line + 1);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let line_num = self.current_line;
+        let tokens = self.tokenizer.tokenize_line(line_num, line, self.states[line_num]);
+        let spans = if self.settings.enabled && line_num != self.settings.cursor_line {
+            preview_spans(line, &tokens.tokens)
+        } else {
+            Vec::new()
+        };
+        self.states.push(tokens.end_state);
+        self.current_line += 1;
+        spans.into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.current_line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(line: &str) -> Vec<(&str, PreviewStyle)> {
+        let tokens = MarkdownTokenizer::new().tokenize_line(0, line, LineState::Normal).tokens.clone();
+        preview_spans(line, &tokens)
+            .into_iter()
+            .map(|(range, style)| (&line[range], style))
+            .collect()
+    }
+
+    #[test]
+    fn test_preview_spans() {
+        assert_eq!(
+            spans("some **bold** text"),
+            vec![("**", PreviewStyle::Marker), ("bold", PreviewStyle::Bold), ("**", PreviewStyle::Marker)]
+        );
+        assert_eq!(
+            spans("## Title"),
+            vec![("## ", PreviewStyle::Marker), ("Title", PreviewStyle::Bold)]
+        );
+        assert_eq!(spans("- item"), vec![("- ", PreviewStyle::Marker)]);
+        assert!(spans("plain text").is_empty());
+
+        // Non-ASCII text before a span keeps byte ranges right
+        assert_eq!(
+            spans("é *x*"),
+            vec![("*", PreviewStyle::Marker), ("x", PreviewStyle::Italic), ("*", PreviewStyle::Marker)]
+        );
+    }
+
+    #[test]
+    fn test_code_stays_raw() {
+        assert!(spans("`**not bold**`").is_empty());
+        assert!(spans("```rust").is_empty());
+
+        let mut highlighter = LivePreviewHighlighter::new(&LivePreviewSettings {
+            enabled: true,
+            cursor_line: 10,
+        });
+        let lines = ["```", "# not a heading", "```", "# Heading"];
+        let highlighted: Vec<usize> =
+            lines.iter().map(|line| highlighter.highlight_line(line).count()).collect();
+        assert_eq!(highlighted, vec![0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn test_cursor_line_shows_raw() {
+        let settings = LivePreviewSettings { enabled: true, cursor_line: 0 };
+        let mut highlighter = LivePreviewHighlighter::new(&settings);
+        assert_eq!(highlighter.highlight_line("**a**").count(), 0);
+        assert_eq!(highlighter.highlight_line("**b**").count(), 3);
+
+        // Moving the cursor down re-highlights from the line it left
+        highlighter.update(&LivePreviewSettings { enabled: true, cursor_line: 1 });
+        assert_eq!(highlighter.current_line(), 0);
+        assert_eq!(highlighter.highlight_line("**a**").count(), 3);
+        assert_eq!(highlighter.highlight_line("**b**").count(), 0);
+
+        // Switching live preview off re-highlights everything, unstyled
+        highlighter.update(&LivePreviewSettings { enabled: false, cursor_line: 1 });
+        assert_eq!(highlighter.current_line(), 0);
+        assert_eq!(highlighter.highlight_line("**a**").count(), 0);
+    }
+}
@@ -0,0 +1,226 @@
+//! Minimap overview of a document
+//!
+//! The minimap draws each line as a thin bar starting at the line's indent
+//! and as long as its text, colored by the syntax token covering most of
+//! the line. A document with more lines than the minimap has rows is
+//! downsampled, several lines to a row, so drawing it costs the same
+//! however long the document is.
+
+use std::ops::Range;
+
+use cosmic::iced_core::Color;
+
+use crate::markdown::{LineTokens, TokenType};
+
+/// Width of the minimap in pixels
+pub const MINIMAP_WIDTH: f32 = 80.0;
+
+/// Height of one minimap row in pixels
+pub const ROW_HEIGHT: f32 = 2.0;
+
+/// Line length, in characters, drawn across the full minimap width
+pub const MAX_COLUMNS: usize = 120;
+
+/// Shape of one document line in the minimap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinimapLine {
+    /// Leading whitespace in characters
+    pub indent: usize,
+    /// Characters after the indent, up to the last non-whitespace one
+    pub length: usize,
+    /// Color of the token covering most of the line
+    pub color: Color,
+}
+
+impl MinimapLine {
+    /// Whether the line has no text
+    pub fn is_blank(&self) -> bool {
+        self.length == 0
+    }
+}
+
+/// Build the minimap shapes of a document's lines
+///
+/// `tokens` are the lines' syntax tokens, as from `tokenize_document`;
+/// lines without tokens are drawn as plain text.
+pub fn minimap_lines(
+    lines: &[&str],
+    tokens: &[LineTokens],
+    color: impl Fn(TokenType) -> Color,
+) -> Vec<MinimapLine> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.trim_end();
+            let body = line.trim_start();
+            let indent = line[..line.len() - body.len()].chars().count();
+            let token = tokens
+                .get(i)
+                .map_or(TokenType::PlainText, |t| dominant_token(&t.tokens));
+            MinimapLine {
+                indent: indent.min(MAX_COLUMNS),
+                length: body.chars().count().min(MAX_COLUMNS - indent.min(MAX_COLUMNS)),
+                color: color(token),
+            }
+        })
+        .collect()
+}
+
+/// Type of the token covering the most bytes, the first on a tie
+fn dominant_token(tokens: &[crate::markdown::Token]) -> TokenType {
+    let mut best = (TokenType::PlainText, 0);
+    for token in tokens {
+        let covered: usize = tokens
+            .iter()
+            .filter(|t| t.token_type == token.token_type)
+            .map(|t| t.len())
+            .sum();
+        if covered > best.1 {
+            best = (token.token_type, covered);
+        }
+    }
+    best.0
+}
+
+/// Document lines drawn in each row so `line_count` lines fit `max_rows`
+pub fn lines_per_row(line_count: usize, max_rows: usize) -> usize {
+    line_count.div_ceil(max_rows.max(1)).max(1)
+}
+
+/// Rows to draw for at most `max_rows` rows
+///
+/// Each row shows the longest line of the lines it covers, so the outline
+/// of headings and paragraphs survives downsampling.
+pub fn downsample(lines: &[MinimapLine], max_rows: usize) -> Vec<MinimapLine> {
+    let per_row = lines_per_row(lines.len(), max_rows);
+    if per_row == 1 {
+        return lines.to_vec();
+    }
+    lines
+        .chunks(per_row)
+        .map(|chunk| {
+            *chunk
+                .iter()
+                .rev()
+                .max_by_key(|line| line.indent + line.length)
+                .expect("chunks are never empty")
+        })
+        .collect()
+}
+
+/// Document line at `y` pixels below the top of the minimap
+///
+/// The position within a row is kept, so dragging over a downsampled
+/// minimap still moves line by line.
+pub fn line_at(y: f32, line_count: usize, max_rows: usize) -> usize {
+    let per_row = lines_per_row(line_count, max_rows);
+    let line = (y.max(0.0) / ROW_HEIGHT * per_row as f32) as usize;
+    line.min(line_count.saturating_sub(1))
+}
+
+/// Rows covered by a viewport showing `viewport_lines` lines from `top`
+pub fn viewport_rows(
+    top: usize,
+    viewport_lines: usize,
+    line_count: usize,
+    max_rows: usize,
+) -> Range<usize> {
+    let per_row = lines_per_row(line_count, max_rows);
+    let start = top / per_row;
+    let end = (top + viewport_lines).min(line_count).div_ceil(per_row);
+    start..end.max(start + 1)
+}
+
+/// A drag on the minimap
+///
+/// Pointer positions while dragging are in window coordinates, so the drag
+/// keeps where it started in the minimap and follows the pointer's movement
+/// from its first window position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinimapDrag {
+    /// Document whose minimap is dragged
+    pub document_id: crate::state::DocumentId,
+    /// Pointer position below the minimap's top when the drag started
+    start: f32,
+    /// Window position of the pointer when it first moved
+    origin: Option<f32>,
+}
+
+impl MinimapDrag {
+    /// Start a drag at `start` pixels below the minimap's top
+    pub fn new(document_id: crate::state::DocumentId, start: f32) -> Self {
+        Self { document_id, start, origin: None }
+    }
+
+    /// Position below the minimap's top for a window pointer position
+    pub fn position(&mut self, window_y: f32) -> f32 {
+        let origin = *self.origin.get_or_insert(window_y);
+        self.start + window_y - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::MarkdownTokenizer;
+
+    fn line(indent: usize, length: usize) -> MinimapLine {
+        MinimapLine { indent, length, color: Color::BLACK }
+    }
+
+    #[test]
+    fn test_minimap_lines() {
+        let lines = ["# Title", "", "    code", "text  "];
+        let tokens = MarkdownTokenizer::new().tokenize_document(&lines);
+        let shapes = minimap_lines(&lines, &tokens, |token| {
+            if token.is_heading() {
+                Color::WHITE
+            } else {
+                Color::BLACK
+            }
+        });
+
+        assert_eq!(shapes[0].color, Color::WHITE);
+        assert!(shapes[1].is_blank());
+        assert_eq!((shapes[2].indent, shapes[2].length), (4, 4));
+        assert_eq!((shapes[3].indent, shapes[3].length), (0, 4));
+        assert_eq!(shapes[3].color, Color::BLACK);
+    }
+
+    #[test]
+    fn test_downsample() {
+        let lines: Vec<_> = (0..10).map(|i| line(0, i)).collect();
+        assert_eq!(downsample(&lines, 20).len(), 10);
+
+        let rows = downsample(&lines, 4);
+        assert_eq!(lines_per_row(10, 4), 3);
+        assert_eq!(rows, vec![line(0, 2), line(0, 5), line(0, 8), line(0, 9)]);
+    }
+
+    #[test]
+    fn test_line_at() {
+        assert_eq!(line_at(0.0, 10, 100), 0);
+        assert_eq!(line_at(5.0, 10, 100), 2);
+        assert_eq!(line_at(500.0, 10, 100), 9);
+        // Three lines to a row: half a row down is line 1
+        assert_eq!(line_at(ROW_HEIGHT / 2.0, 300, 100), 1);
+        assert_eq!(line_at(-4.0, 10, 100), 0);
+    }
+
+    #[test]
+    fn test_drag_follows_pointer() {
+        let mut drag = MinimapDrag::new(crate::state::DocumentId::new(), 40.0);
+        assert_eq!(drag.position(300.0), 40.0);
+        assert_eq!(drag.position(310.0), 50.0);
+        assert_eq!(drag.position(280.0), 20.0);
+    }
+
+    #[test]
+    fn test_viewport_rows() {
+        assert_eq!(viewport_rows(5, 10, 100, 200), 5..15);
+        assert_eq!(viewport_rows(95, 10, 100, 200), 95..100);
+        assert_eq!(viewport_rows(30, 30, 1000, 100), 3..6);
+        assert_eq!(viewport_rows(0, 0, 0, 100), 0..1);
+    }
+}
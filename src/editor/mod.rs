@@ -3,23 +3,51 @@
 //! Contains the core text editing functionality including:
 //! - Text buffer management (using ropey)
 //! - Cursor and selection handling
+//! - Bidirectional text layout for cursor movement
 //! - Text input processing
 //! - Undo/redo operations
 //! - Clipboard operations
 //! - Line operations (indent, comment, etc.)
-
+//! - Line diffs against saved content
+//! - Snippet expansion
+//! - Markdown formatting commands
+//! - Link detection under the cursor
+//! - Indentation detection for opened files
+//! - Paragraph reflow to a column width
+//! - Typographic substitution while typing
+//! - Live preview of markdown formatting away from the cursor line
+//! - Minimap overview of a document
+
+pub mod bidi;
 pub mod buffer;
 pub mod clipboard;
 pub mod cursor;
+pub mod diff;
+pub mod formatting;
+pub mod indent;
+pub mod links;
+pub mod live_preview;
+pub mod minimap;
+pub mod reflow;
+pub mod snippets;
+pub mod typography;
 pub mod undo;
 pub mod widget;
 
 pub use buffer::TextBuffer;
-pub use clipboard::{clipboard, copy_text, paste_text, ClipboardError, ClipboardManager};
+pub use clipboard::{
+    clipboard, copy_html, copy_text, normalize_plain_text, paste_html, paste_text, ClipboardError,
+    ClipboardHistory, ClipboardManager,
+};
 pub use cursor::CursorController;
+pub use diff::{line_diff, ChangeKind, DiffKind, DiffLine, LineChange};
+pub use formatting::{CodeFence, EmphasisKind, HeadingOptions};
+pub use links::LinkTarget;
+pub use snippets::{Snippet, SnippetSession, SnippetSet};
 pub use undo::{EditKind, EditOperation, UndoManager};
 pub use widget::EditorWidget;
 
+use crate::markdown::MarkdownExporter;
 use crate::state::{CursorPosition, EditorState, Selection};
 
 /// Main editor component managing a text buffer and state
@@ -36,6 +64,12 @@ pub struct Editor {
     scroll_line: usize,
     /// Scroll margin (lines to keep visible above/below cursor)
     scroll_margin: usize,
+    /// How heading levels are adjusted
+    heading_options: HeadingOptions,
+    /// Columns per indent level
+    tab_width: usize,
+    /// Indent with spaces instead of tabs
+    use_spaces: bool,
 }
 
 impl Editor {
@@ -48,6 +82,9 @@ impl Editor {
             viewport_lines: 30,
             scroll_line: 0,
             scroll_margin: 3,
+            heading_options: HeadingOptions::default(),
+            tab_width: 4,
+            use_spaces: true,
         }
     }
 
@@ -60,6 +97,9 @@ impl Editor {
             viewport_lines: 30,
             scroll_line: 0,
             scroll_margin: 3,
+            heading_options: HeadingOptions::default(),
+            tab_width: 4,
+            use_spaces: true,
         }
     }
 
@@ -122,9 +162,29 @@ impl Editor {
         self.scroll_line
     }
 
+    /// Get the number of lines that fit in the viewport
+    pub fn viewport_lines(&self) -> usize {
+        self.viewport_lines
+    }
+
     /// Set viewport size
     pub fn set_viewport_lines(&mut self, lines: usize) {
-        self.viewport_lines = lines;
+        self.viewport_lines = lines.max(1);
+        self.update_scroll();
+    }
+
+    /// Set the viewport size from the rendered height
+    pub fn set_viewport_height(&mut self, height: f32, line_height: f32) {
+        if height.is_finite() && line_height > 0.0 {
+            self.set_viewport_lines((height / line_height).floor() as usize);
+        }
+    }
+
+    /// Set how many lines to keep visible above and below the cursor
+    ///
+    /// Margins over half the viewport are clamped when scrolling.
+    pub fn set_scroll_margin(&mut self, lines: usize) {
+        self.scroll_margin = lines;
         self.update_scroll();
     }
 
@@ -155,7 +215,9 @@ impl Editor {
 
     /// Set content (replaces everything)
     pub fn set_content(&mut self, content: &str) {
+        let underscore_in_words = self.buffer.underscore_in_words();
         self.buffer = TextBuffer::from_str(content);
+        self.buffer.set_underscore_in_words(underscore_in_words);
         self.state = EditorState::new();
         self.preferred_col = None;
         self.scroll_line = 0;
@@ -525,6 +587,184 @@ impl Editor {
         self.preferred_col = None;
     }
 
+    // === Line Operations ===
+
+    /// Get the character range of the selection, or the cursor if collapsed
+    pub fn selection_range(&self) -> std::ops::Range<usize> {
+        let (start, end) = self.state.selection.normalized();
+        let start_idx = self
+            .buffer
+            .line_col_to_char(start.line, start.column)
+            .unwrap_or(0);
+        let end_idx = self
+            .buffer
+            .line_col_to_char(end.line, end.column)
+            .unwrap_or(self.buffer.len_chars());
+        start_idx..end_idx
+    }
+
+    /// Sort the lines touched by a character range
+    pub fn sort_lines(
+        &mut self,
+        range: std::ops::Range<usize>,
+        descending: bool,
+        case_sensitive: bool,
+    ) {
+        let span = self.buffer.sort_lines(range, descending, case_sensitive);
+        self.select_char_range(span);
+    }
+
+    /// Remove duplicate lines within the lines touched by a character range
+    pub fn dedupe_lines(&mut self, range: std::ops::Range<usize>) {
+        let span = self.buffer.dedupe_lines(range);
+        self.select_char_range(span);
+    }
+
+    /// Join the next line onto the current one, or join all selected lines
+    pub fn join_lines(&mut self) {
+        let range = self.selection_range();
+        let position = self.buffer.join_lines(range);
+        self.select_char_range(position);
+    }
+
+    /// Swap the two characters around the cursor
+    pub fn transpose_chars(&mut self) {
+        let cursor = self.selection_range().end;
+        if let Some(position) = self.buffer.transpose_chars(cursor) {
+            self.select_char_range(position..position);
+        }
+    }
+
+    /// Swap the word before the cursor with the word after it
+    pub fn transpose_words(&mut self) {
+        let cursor = self.selection_range().end;
+        if let Some(position) = self.buffer.transpose_words(cursor) {
+            self.select_char_range(position..position);
+        }
+    }
+
+    /// Toggle emphasis markers around the selection
+    ///
+    /// With no selection, an empty pair of markers is inserted and the
+    /// cursor is placed between them.
+    pub fn toggle_emphasis(&mut self, kind: EmphasisKind) {
+        let range = self.selection_range();
+        let inner = formatting::toggle_emphasis(&mut self.buffer, range, kind);
+        self.select_char_range(inner);
+    }
+
+    /// Set how heading levels are adjusted
+    pub fn set_heading_options(&mut self, options: HeadingOptions) {
+        self.heading_options = options;
+    }
+
+    /// Change the heading level of the current line or selected lines
+    pub fn adjust_heading(&mut self, delta: i32) {
+        let range = self.selection_range();
+        let selection =
+            formatting::adjust_heading(&mut self.buffer, range, delta, self.heading_options);
+        self.select_char_range(selection);
+    }
+
+    /// Add or remove one blockquote level on the lines touched by a range
+    pub fn toggle_blockquote(&mut self, range: std::ops::Range<usize>) {
+        let span = formatting::toggle_blockquote(&mut self.buffer, range);
+        self.select_char_range(span);
+    }
+
+    /// Get the fenced code block around the cursor
+    pub fn code_block_at_cursor(&self) -> Option<CodeFence> {
+        formatting::code_fence_at(self.buffer.rope(), self.state.cursor.line)
+    }
+
+    /// Set the language of the fenced code block around the cursor
+    pub fn set_code_block_language(&mut self, language: &str) {
+        let range = self.selection_range();
+        let selection = formatting::set_code_block_language(&mut self.buffer, range, language);
+        self.select_char_range(selection);
+    }
+
+    /// Set whether `_` counts as part of a word
+    pub fn set_underscore_in_words(&mut self, enabled: bool) {
+        self.buffer.set_underscore_in_words(enabled);
+    }
+
+    /// Set the indent width and whether indents use spaces
+    pub fn set_indent_style(&mut self, tab_width: usize, use_spaces: bool) {
+        self.tab_width = tab_width.max(1);
+        self.use_spaces = use_spaces;
+    }
+
+    /// Indent the selected lines, or insert an indent at the cursor
+    ///
+    /// Only a selection spanning several lines indents whole lines.
+    pub fn indent_selection(&mut self) {
+        let range = self.selection_range();
+        if self.buffer.slice(range.start, range.end).contains('\n') {
+            let unit = if self.use_spaces {
+                " ".repeat(self.tab_width)
+            } else {
+                "\t".to_string()
+            };
+            let span = self.buffer.indent_lines(range, &unit);
+            self.select_char_range(span);
+        } else if self.use_spaces {
+            self.delete_selection();
+            let column = self.visual_column();
+            let spaces = self.tab_width - column % self.tab_width;
+            self.insert_text(&" ".repeat(spaces));
+        } else {
+            self.insert_char('\t');
+        }
+    }
+
+    /// Remove up to one indent level from the selected lines
+    pub fn outdent_selection(&mut self) {
+        let range = self.selection_range();
+        let span = self.buffer.outdent_lines(range, self.tab_width);
+        self.select_char_range(span);
+    }
+
+    /// Cursor column with tabs expanded to the indent width
+    fn visual_column(&self) -> usize {
+        let line = self
+            .buffer
+            .line_without_newline(self.state.cursor.line)
+            .unwrap_or_default();
+        line.chars()
+            .take(self.state.cursor.column)
+            .fold(0, |column, c| match c {
+                '\t' => column + self.tab_width - column % self.tab_width,
+                _ => column + 1,
+            })
+    }
+
+    /// Get the target of the link under the cursor
+    pub fn link_at_cursor(&self) -> Option<LinkTarget> {
+        let line = self.buffer.line_without_newline(self.state.cursor.line)?;
+        links::link_at(&line, self.state.cursor.column)
+    }
+
+    /// Get the anchor of the heading under the cursor
+    ///
+    /// Anchors match the heading ids and table of contents of exports,
+    /// including the numbering of repeated headings.
+    pub fn heading_anchor_at_cursor(&self) -> Option<String> {
+        MarkdownExporter::new().heading_anchor_at_line(&self.buffer.to_string(), self.state.cursor.line)
+    }
+
+    /// Select a character range, placing the cursor at its end
+    fn select_char_range(&mut self, range: std::ops::Range<usize>) {
+        let (start_line, start_col) = self.buffer.char_to_line_col(range.start);
+        let (end_line, end_col) = self.buffer.char_to_line_col(range.end);
+        let start = CursorPosition::new(start_line, start_col);
+        let end = CursorPosition::new(end_line, end_col);
+        self.state.selection = Selection::new(start, end);
+        self.state.cursor = end;
+        self.preferred_col = None;
+        self.update_scroll();
+    }
+
     // === Line Information ===
 
     /// Get total line count
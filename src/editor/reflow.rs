@@ -0,0 +1,445 @@
+//! Paragraph reflow
+//!
+//! Rewraps markdown prose to a column width. Words are never split, and
+//! neither are code spans or links, which move to the next line whole.
+//! Blank lines, hard line breaks (two trailing spaces) and list items end
+//! a paragraph, and list items keep a hanging indent under their text.
+//! Headings, tables, quotes and code blocks are left as they are.
+//!
+//! Reflow only changes whitespace, so positions are carried over by
+//! counting the other characters before them.
+
+use super::buffer::list_marker_len;
+use super::TextBuffer;
+use crate::markdown::{LineState, MarkdownTokenizer, TokenType};
+use ropey::Rope;
+use std::ops::Range;
+
+/// Whether a line ends with a markdown hard line break
+fn is_hard_break(line: &str) -> bool {
+    line.ends_with("  ") && !line.trim().is_empty()
+}
+
+/// Length in bytes of a list item's indent, marker, and the spaces after it
+fn item_prefix_len(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    let marker = list_marker_len(trimmed);
+    if marker == 0 {
+        return None;
+    }
+    let rest = &trimmed[marker..];
+    let spaces = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    Some(line.len() - trimmed.len() + marker + spaces)
+}
+
+/// Whether a word at the start of a line would begin a new block
+fn starts_block(word: &str) -> bool {
+    list_marker_len(word) > 0 || word.chars().all(|c| c == '#') || word.starts_with('>')
+}
+
+/// End of the code span opening at `start`, if it is closed
+fn code_span_end(chars: &[char], start: usize) -> Option<usize> {
+    let run = chars[start..].iter().take_while(|c| **c == '`').count();
+    let mut i = start + run;
+    while i < chars.len() {
+        let len = chars[i..].iter().take_while(|c| **c == '`').count();
+        if len == run {
+            return Some(i + len);
+        }
+        i += len.max(1);
+    }
+    None
+}
+
+/// End of the bracketed text opening at `start`, if it is closed
+fn bracket_end(chars: &[char], start: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// End of the link opening at `start`, with its URL or reference label
+fn link_end(chars: &[char], start: usize) -> Option<usize> {
+    let end = bracket_end(chars, start, '[', ']')?;
+    let target = match chars.get(end) {
+        Some('(') => bracket_end(chars, end, '(', ')'),
+        Some('[') => bracket_end(chars, end, '[', ']'),
+        _ => None,
+    };
+    Some(target.unwrap_or(end))
+}
+
+/// Split text into the words a line can break between
+///
+/// Code spans and links count as one word, with the whitespace inside
+/// them collapsed to single spaces.
+fn words(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let end = match c {
+            c if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                i += 1;
+                continue;
+            }
+            '\\' => Some((i + 2).min(chars.len())),
+            '`' => Some(code_span_end(&chars, i).unwrap_or_else(|| {
+                i + chars[i..].iter().take_while(|c| **c == '`').count()
+            })),
+            '[' => link_end(&chars, i),
+            _ => None,
+        };
+        let end = end.unwrap_or(i + 1);
+        let mut space = false;
+        for &c in &chars[i..end] {
+            if c.is_whitespace() {
+                space = true;
+            } else {
+                if space {
+                    word.push(' ');
+                    space = false;
+                }
+                word.push(c);
+            }
+        }
+        i = end;
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Rewrap the lines of one paragraph
+fn reflow_block(lines: &[&str], width: usize) -> Vec<String> {
+    let first = lines[0];
+    let prefix_len = item_prefix_len(first).unwrap_or(first.len() - first.trim_start().len());
+    let mut prefix = first[..prefix_len].to_string();
+    let indent = if item_prefix_len(first).is_some() {
+        if !prefix.ends_with([' ', '\t']) {
+            prefix.push(' ');
+        }
+        " ".repeat(prefix.chars().count())
+    } else {
+        prefix.clone()
+    };
+
+    let text = std::iter::once(&first[prefix_len..])
+        .chain(lines[1..].iter().copied())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let words = words(&text);
+    if words.is_empty() {
+        return lines.iter().map(|line| line.to_string()).collect();
+    }
+
+    let mut result = Vec::new();
+    let mut line = prefix;
+    let mut empty = true;
+    for word in words {
+        let len = line.chars().count() + 1 + word.chars().count();
+        if !empty && len > width && !starts_block(&word) {
+            result.push(std::mem::replace(&mut line, indent.clone()));
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+        }
+        line.push_str(&word);
+        empty = false;
+    }
+
+    let last = lines[lines.len() - 1];
+    if is_hard_break(last) {
+        line.push_str(&last[last.trim_end().len()..]);
+    }
+    result.push(line);
+    result
+}
+
+/// Rewrap paragraphs to fit within `width` columns
+///
+/// Lines that fit are joined and lines that don't are broken between
+/// words. A word too long for the width gets a line of its own. Blank lines
+/// and the text's final newline are kept as they are.
+pub fn reflow_paragraph(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut result: Vec<String> = Vec::new();
+    let mut block: Vec<&str> = Vec::new();
+    for line in text.split('\n') {
+        if line.trim().is_empty() {
+            if !block.is_empty() {
+                result.extend(reflow_block(&block, width));
+                block.clear();
+            }
+            result.push(line.to_string());
+            continue;
+        }
+        if !block.is_empty() && item_prefix_len(line).is_some() {
+            result.extend(reflow_block(&block, width));
+            block.clear();
+        }
+        block.push(line);
+        if is_hard_break(line) {
+            result.extend(reflow_block(&block, width));
+            block.clear();
+        }
+    }
+    if !block.is_empty() {
+        result.extend(reflow_block(&block, width));
+    }
+    result.join("\n")
+}
+
+/// Whether a line starting with this token is left as it is
+fn is_fixed_block(token_type: TokenType) -> bool {
+    token_type.is_heading()
+        || matches!(
+            token_type,
+            TokenType::CodeBlockDelimiter
+                | TokenType::CodeBlockContent
+                | TokenType::CodeBlockLanguage
+                | TokenType::Blockquote
+                | TokenType::HorizontalRule
+                | TokenType::TableDelimiter
+                | TokenType::TableCell
+                | TokenType::Frontmatter
+                | TokenType::Math
+        )
+}
+
+/// Get a line's text without its newline
+fn line_text(rope: &Rope, line: usize) -> String {
+    rope.line(line).to_string().trim_end_matches(['\n', '\r']).to_string()
+}
+
+/// Find which lines of a document are prose that can be reflowed
+fn prose_lines(rope: &Rope) -> Vec<bool> {
+    let mut tokenizer = MarkdownTokenizer::new();
+    let mut state = LineState::Normal;
+    (0..rope.len_lines())
+        .map(|idx| {
+            let text = line_text(rope, idx);
+            let start = state;
+            let tokens = tokenizer.tokenize_line(idx, &text, start);
+            state = tokens.end_state;
+            start == LineState::Normal
+                && !text.trim().is_empty()
+                && !tokens.tokens.first().is_some_and(|t| is_fixed_block(t.token_type))
+        })
+        .collect()
+}
+
+/// Find the lines of the paragraph containing a line
+///
+/// Returns `None` when the line isn't prose.
+pub fn paragraph_at(rope: &Rope, line: usize) -> Option<Range<usize>> {
+    let prose = prose_lines(rope);
+    if !prose.get(line).copied().unwrap_or(false) {
+        return None;
+    }
+    let joins = |upper: usize| {
+        prose[upper]
+            && prose[upper + 1]
+            && !is_hard_break(&line_text(rope, upper))
+            && item_prefix_len(&line_text(rope, upper + 1)).is_none()
+    };
+
+    let mut start = line;
+    while start > 0 && joins(start - 1) {
+        start -= 1;
+    }
+    let mut end = line + 1;
+    while end < prose.len() && joins(end - 1) {
+        end += 1;
+    }
+    Some(start..end)
+}
+
+/// Move a position in `old` to the same place in `new`, where the two
+/// differ only in whitespace
+///
+/// A position at the end of a word stays at its end; any other position
+/// moves to the start of the next word.
+fn map_offset(old: &str, new: &str, offset: usize) -> usize {
+    let old: Vec<char> = old.chars().collect();
+    let offset = offset.min(old.len());
+    let before = old[..offset].iter().filter(|c| !c.is_whitespace()).count();
+    let at_word_end = offset > 0
+        && !old[offset - 1].is_whitespace()
+        && old.get(offset).map_or(true, |c| c.is_whitespace());
+
+    let mut seen = 0;
+    let mut len = 0;
+    for (i, c) in new.chars().enumerate() {
+        len = i + 1;
+        if c.is_whitespace() {
+            continue;
+        }
+        if seen == before && !at_word_end {
+            return i;
+        }
+        seen += 1;
+        if seen == before && at_word_end {
+            return i + 1;
+        }
+    }
+    len
+}
+
+/// Replace `span` of the buffer with its reflowed text, moving `range`
+/// with the words it was on
+fn reflow_span(
+    buffer: &mut TextBuffer,
+    span: Range<usize>,
+    range: Range<usize>,
+    width: usize,
+) -> Range<usize> {
+    let old = buffer.slice_range(span.clone());
+    let new = reflow_paragraph(&old, width);
+    if new == old {
+        return range;
+    }
+    buffer.replace(span.clone(), &new);
+
+    let new_end = span.start + new.chars().count();
+    let map = |offset: usize| {
+        if offset < span.start {
+            offset
+        } else if offset > span.end {
+            offset - span.end + new_end
+        } else {
+            span.start + map_offset(&old, &new, offset - span.start)
+        }
+    };
+    map(range.start)..map(range.end)
+}
+
+/// Reflow the paragraph containing the range start
+///
+/// Returns the range moved to stay on the same words, or the range
+/// unchanged outside a paragraph.
+pub fn reflow_at(buffer: &mut TextBuffer, range: Range<usize>, width: usize) -> Range<usize> {
+    let line = buffer.rope().char_to_line(range.start.min(buffer.len_chars()));
+    let Some(lines) = paragraph_at(buffer.rope(), line) else {
+        return range;
+    };
+    let start = buffer.rope().line_to_char(lines.start);
+    let last = lines.end - 1;
+    let end = buffer.rope().line_to_char(last) + buffer.line_len(last).unwrap_or(0);
+    reflow_span(buffer, start..end, range, width)
+}
+
+/// Reflow every paragraph of a document
+///
+/// Returns the range moved to stay on the same words.
+pub fn reflow_document(buffer: &mut TextBuffer, range: Range<usize>, width: usize) -> Range<usize> {
+    let prose = prose_lines(buffer.rope());
+    let mut new = String::new();
+    let mut run = String::new();
+    for (idx, line) in buffer.rope().lines().enumerate() {
+        if prose[idx] {
+            run.extend(line.chars());
+            continue;
+        }
+        new.push_str(&reflow_paragraph(&run, width));
+        run.clear();
+        new.extend(line.chars());
+    }
+    new.push_str(&reflow_paragraph(&run, width));
+
+    let old = buffer.to_string();
+    if new == old {
+        return range;
+    }
+    buffer.replace(0..buffer.len_chars(), &new);
+    let map = |offset| map_offset(&old, &new, offset);
+    map(range.start)..map(range.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reflow_paragraph() {
+        let text = "one two three four five six\nseven";
+        assert_eq!(reflow_paragraph(text, 14), "one two three\nfour five six\nseven");
+        assert_eq!(reflow_paragraph("short\nlines\njoin\n", 80), "short lines join\n");
+
+        // Code spans and links are not broken
+        let text = "see `a code span` and [a link](http://x.y) here";
+        assert_eq!(
+            reflow_paragraph(text, 12),
+            "see\n`a code span`\nand\n[a link](http://x.y)\nhere"
+        );
+
+        // A word too long for the width gets its own line
+        assert_eq!(reflow_paragraph("a verylongword b", 5), "a\nverylongword\nb");
+    }
+
+    #[test]
+    fn test_reflow_list_items() {
+        let text = "- first item with words\n- second\n  1. nested item text";
+        assert_eq!(
+            reflow_paragraph(text, 12),
+            "- first item\n  with words\n- second\n  1. nested\n     item\n     text"
+        );
+    }
+
+    #[test]
+    fn test_reflow_boundaries() {
+        // Hard breaks and blank lines end a paragraph
+        let text = "one  \ntwo three\n\nfour\nfive";
+        assert_eq!(reflow_paragraph(text, 80), "one  \ntwo three\n\nfour five");
+
+        let rope = Rope::from_str("# Title\none\ntwo  \nthree\n\n```\ncode\n```\n");
+        assert_eq!(paragraph_at(&rope, 0), None);
+        assert_eq!(paragraph_at(&rope, 1), Some(1..3));
+        assert_eq!(paragraph_at(&rope, 3), Some(3..4));
+        assert_eq!(paragraph_at(&rope, 6), None);
+    }
+
+    #[test]
+    fn test_cursor_stays_on_word() {
+        let mut buffer = TextBuffer::from_str("# Title\nalpha beta\ngamma delta\n");
+        // Cursor inside "gamma", after "ga"
+        let cursor = 8 + 11 + 2;
+        let range = reflow_at(&mut buffer, cursor..cursor, 80);
+        assert_eq!(buffer.to_string(), "# Title\nalpha beta gamma delta\n");
+        assert_eq!(buffer.slice(range.start, range.start + 3), "mma");
+
+        // At the end of a word it stays at its end
+        let mut buffer = TextBuffer::from_str("alpha beta\ngamma");
+        let range = reflow_at(&mut buffer, 10..10, 5);
+        assert_eq!(buffer.to_string(), "alpha\nbeta\ngamma");
+        assert_eq!(range, 10..10);
+    }
+
+    #[test]
+    fn test_reflow_document() {
+        let mut buffer = TextBuffer::from_str("a\nb\n\n```\nx\ny\n```\nc\nd\n");
+        reflow_document(&mut buffer, 0..0, 80);
+        assert_eq!(buffer.to_string(), "a b\n\n```\nx\ny\n```\nc d\n");
+    }
+}
@@ -0,0 +1,457 @@
+//! Snippet expansion
+//!
+//! Snippets expand a short trigger word into a template when Tab is pressed.
+//! Templates support:
+//! - `$1`, `$2`, ... tab-stops visited in order with Tab / Shift-Tab
+//! - `${1:default}` tab-stops with placeholder text (which may nest stops)
+//! - `$0` as the final cursor position (the end of the snippet if omitted)
+//! - `\$` for a literal dollar sign
+//!
+//! User snippets are loaded from `snippets.json` in the configuration
+//! directory and override the built-in ones with the same trigger.
+
+use crate::config::Config;
+use crate::error::{ConfigError, ConfigResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Snippet file name in the configuration directory
+pub const SNIPPETS_FILE: &str = "snippets.json";
+
+/// Built-in snippets as (trigger, template)
+const BUILTIN_SNIPPETS: &[(&str, &str)] = &[
+    ("tbl", "| ${1:Column} | ${2:Column} |\n| --- | --- |\n| $3 | $4 |\n$0"),
+    ("link", "[${1:text}](${2:url})$0"),
+    ("img", "![${1:alt}](${2:path})$0"),
+    ("code", "```${1:language}\n$2\n```\n$0"),
+    ("todo", "- [ ] ${1:task}$0"),
+];
+
+/// A tab-stop in a snippet body (character offsets)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TabStop {
+    /// Tab-stop number (`$0` is the final position)
+    pub index: u32,
+    /// Start of the placeholder text
+    pub start: usize,
+    /// End of the placeholder text (equal to start when empty)
+    pub end: usize,
+}
+
+/// A snippet with its template parsed into text and tab-stops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    /// Word that expands the snippet
+    pub trigger: String,
+    /// Text inserted on expansion
+    pub body: String,
+    /// Tab-stops in the body, in template order
+    pub tabstops: Vec<TabStop>,
+}
+
+/// Snippet as stored in the snippets file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnippetDefinition {
+    /// Word that expands the snippet
+    pub trigger: String,
+    /// Template text with tab-stops
+    pub body: String,
+}
+
+impl Snippet {
+    /// Parse a snippet template
+    pub fn parse(trigger: impl Into<String>, template: &str) -> Self {
+        let chars: Vec<char> = template.chars().collect();
+        let mut body = String::with_capacity(template.len());
+        let mut tabstops = Vec::new();
+        let mut len = 0;
+
+        parse_template(&chars, 0, false, &mut body, &mut len, &mut tabstops);
+
+        Self {
+            trigger: trigger.into(),
+            body,
+            tabstops,
+        }
+    }
+
+    /// Expand the snippet with continuation lines indented by `indent`
+    ///
+    /// Returns the text and the tab-stop ranges (character offsets) in
+    /// visiting order: `$1`, `$2`, ..., and finally `$0`.
+    pub fn expand(&self, indent: &str) -> (String, Vec<(usize, usize)>) {
+        let indent_len = indent.chars().count();
+        let text = self.body.replace('\n', &format!("\n{}", indent));
+
+        // Offset shift from indentation added before a body offset
+        let newline_offsets: Vec<usize> = self
+            .body
+            .chars()
+            .enumerate()
+            .filter(|(_, c)| *c == '\n')
+            .map(|(i, _)| i)
+            .collect();
+        let map = |offset: usize| {
+            offset + indent_len * newline_offsets.iter().filter(|&&n| n < offset).count()
+        };
+
+        let mut ordered: Vec<&TabStop> = Vec::new();
+        for stop in &self.tabstops {
+            // Repeated stop numbers visit their first occurrence only
+            if !ordered.iter().any(|s| s.index == stop.index) {
+                ordered.push(stop);
+            }
+        }
+        ordered.sort_by_key(|s| if s.index == 0 { u32::MAX } else { s.index });
+
+        let mut stops: Vec<(usize, usize)> =
+            ordered.iter().map(|s| (map(s.start), map(s.end))).collect();
+        if !self.tabstops.iter().any(|s| s.index == 0) {
+            let end = text.chars().count();
+            stops.push((end, end));
+        }
+
+        (text, stops)
+    }
+}
+
+/// Parse template characters into `body`, returning the position after the
+/// closing `}` when `nested`
+fn parse_template(
+    chars: &[char],
+    mut pos: usize,
+    nested: bool,
+    body: &mut String,
+    len: &mut usize,
+    tabstops: &mut Vec<TabStop>,
+) -> usize {
+    let push = |body: &mut String, len: &mut usize, c: char| {
+        body.push(c);
+        *len += 1;
+    };
+
+    while pos < chars.len() {
+        match chars[pos] {
+            '\\' if matches!(chars.get(pos + 1), Some('$') | Some('}') | Some('\\')) => {
+                push(body, len, chars[pos + 1]);
+                pos += 2;
+            }
+            '}' if nested => return pos + 1,
+            '$' => {
+                // $N
+                let digits = count_digits(chars, pos + 1);
+                if digits > 0 {
+                    let index = parse_index(&chars[pos + 1..pos + 1 + digits]);
+                    tabstops.push(TabStop { index, start: *len, end: *len });
+                    pos += 1 + digits;
+                    continue;
+                }
+
+                // ${N} or ${N:placeholder}
+                if chars.get(pos + 1) == Some(&'{') {
+                    let digits = count_digits(chars, pos + 2);
+                    let after = pos + 2 + digits;
+                    if digits > 0 && matches!(chars.get(after), Some(':') | Some('}')) {
+                        let index = parse_index(&chars[pos + 2..after]);
+                        let slot = tabstops.len();
+                        tabstops.push(TabStop { index, start: *len, end: *len });
+
+                        pos = if chars[after] == ':' {
+                            parse_template(chars, after + 1, true, body, len, tabstops)
+                        } else {
+                            after + 1
+                        };
+                        tabstops[slot].end = *len;
+                        continue;
+                    }
+                }
+
+                push(body, len, '$');
+                pos += 1;
+            }
+            c => {
+                push(body, len, c);
+                pos += 1;
+            }
+        }
+    }
+
+    pos
+}
+
+fn count_digits(chars: &[char], start: usize) -> usize {
+    chars
+        .get(start..)
+        .map(|rest| rest.iter().take_while(|c| c.is_ascii_digit()).count())
+        .unwrap_or(0)
+}
+
+fn parse_index(digits: &[char]) -> u32 {
+    digits.iter().collect::<String>().parse().unwrap_or(u32::MAX - 1)
+}
+
+/// Collection of available snippets
+#[derive(Debug, Clone, Default)]
+pub struct SnippetSet {
+    snippets: Vec<Snippet>,
+}
+
+impl SnippetSet {
+    /// Create a set with the built-in snippets
+    pub fn builtin() -> Self {
+        Self {
+            snippets: BUILTIN_SNIPPETS
+                .iter()
+                .map(|(trigger, body)| Snippet::parse(*trigger, body))
+                .collect(),
+        }
+    }
+
+    /// Load built-in snippets plus user snippets from the configuration directory
+    pub fn load() -> Self {
+        let mut set = Self::builtin();
+
+        if let Ok(dir) = Config::config_dir() {
+            let path = dir.join(SNIPPETS_FILE);
+            if path.exists() {
+                match Self::load_from(&path) {
+                    Ok(user) => set.extend(user),
+                    Err(e) => log::warn!("Failed to load snippets from {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        set
+    }
+
+    /// Load snippets from a JSON file of `{ "trigger", "body" }` entries
+    pub fn load_from(path: &Path) -> ConfigResult<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::LoadError(e.to_string()))?;
+        let definitions: Vec<SnippetDefinition> = serde_json::from_str(&content)
+            .map_err(|e| ConfigError::ParseError(e.to_string()))?;
+
+        Ok(Self {
+            snippets: definitions
+                .iter()
+                .map(|d| Snippet::parse(d.trigger.clone(), &d.body))
+                .collect(),
+        })
+    }
+
+    /// Add snippets, replacing existing ones with the same trigger
+    pub fn extend(&mut self, other: SnippetSet) {
+        for snippet in other.snippets {
+            self.snippets.retain(|s| s.trigger != snippet.trigger);
+            self.snippets.push(snippet);
+        }
+    }
+
+    /// Find the snippet for a trigger
+    pub fn find(&self, trigger: &str) -> Option<&Snippet> {
+        self.snippets.iter().find(|s| s.trigger == trigger)
+    }
+
+    /// Find the snippet whose trigger ends the text before the cursor
+    ///
+    /// The trigger must be a whole word, so `xtbl` does not expand `tbl`.
+    pub fn find_before(&self, line_before_cursor: &str) -> Option<&Snippet> {
+        let word_start = line_before_cursor
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_trigger_char(*c))
+            .last()
+            .map(|(i, _)| i)?;
+        self.find(&line_before_cursor[word_start..])
+    }
+}
+
+fn is_trigger_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Tab-stops of an expanded snippet being filled in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetSession {
+    /// Tab-stop ranges in the document (character offsets), in visiting order
+    pub stops: Vec<(usize, usize)>,
+    /// Index of the active stop
+    pub current: usize,
+}
+
+impl SnippetSession {
+    /// Start a session for stops relative to the snippet's insertion offset
+    pub fn new(base: usize, stops: &[(usize, usize)]) -> Self {
+        Self {
+            stops: stops.iter().map(|(s, e)| (base + s, base + e)).collect(),
+            current: 0,
+        }
+    }
+
+    /// Get the active stop range
+    pub fn current_stop(&self) -> (usize, usize) {
+        self.stops[self.current]
+    }
+
+    /// Check whether the active stop is the final one
+    pub fn is_finished(&self) -> bool {
+        self.current + 1 >= self.stops.len()
+    }
+
+    /// Move to the next (or previous) stop and return its range
+    pub fn advance(&mut self, backwards: bool) -> (usize, usize) {
+        if backwards {
+            self.current = self.current.saturating_sub(1);
+        } else if !self.is_finished() {
+            self.current += 1;
+        }
+        self.current_stop()
+    }
+
+    /// Update stops for an edit replacing `removed` characters at `pos` with
+    /// `inserted` characters
+    ///
+    /// Returns false when the edit is outside the active stop, which ends
+    /// the session.
+    pub fn apply_edit(&mut self, pos: usize, removed: usize, inserted: usize) -> bool {
+        let (current_start, current_end) = self.current_stop();
+        let removed_end = pos + removed;
+        if pos < current_start || removed_end > current_end {
+            return false;
+        }
+
+        let current = self.current;
+        for (i, stop) in self.stops.iter_mut().enumerate() {
+            // Stops starting where the active stop ends follow the inserted text
+            let follows = i != current && stop.0 >= current_end;
+
+            stop.0 = if stop.0 < pos || (stop.0 == pos && !follows) {
+                stop.0
+            } else if stop.0 >= removed_end {
+                stop.0 + inserted - removed
+            } else {
+                pos
+            };
+            stop.1 = if stop.1 < pos {
+                stop.1
+            } else if stop.1 >= removed_end {
+                stop.1 + inserted - removed
+            } else {
+                pos
+            }
+            .max(stop.0);
+        }
+
+        true
+    }
+}
+
+/// Locate an edit from the text before and after it and the cursor after it
+///
+/// Returns `(pos, removed, inserted)` in characters. The cursor anchors the
+/// edit, so typing a character next to an identical one is placed correctly.
+pub fn locate_edit(old: &str, new: &str, cursor_after: usize) -> (usize, usize, usize) {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    let max_common = old.len().min(new.len());
+
+    let common_suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take(max_common)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = new.len().saturating_sub(cursor_after).min(common_suffix);
+
+    let common_prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let prefix = common_prefix.min(max_common - suffix);
+
+    (prefix, old.len() - prefix - suffix, new.len() - prefix - suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tabstops() {
+        let snippet = Snippet::parse("link", "[${1:text}](${2:url})$0");
+        assert_eq!(snippet.body, "[text](url)");
+        assert_eq!(
+            snippet.tabstops,
+            vec![
+                TabStop { index: 1, start: 1, end: 5 },
+                TabStop { index: 2, start: 7, end: 10 },
+                TabStop { index: 0, start: 11, end: 11 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_and_escaped() {
+        let snippet = Snippet::parse("x", "${1:a ${2:b}} costs \\$5 $");
+        assert_eq!(snippet.body, "a b costs $5 $");
+        assert_eq!(snippet.tabstops[0], TabStop { index: 1, start: 0, end: 3 });
+        assert_eq!(snippet.tabstops[1], TabStop { index: 2, start: 2, end: 3 });
+    }
+
+    #[test]
+    fn test_expand_order_and_final_stop() {
+        let snippet = Snippet::parse("x", "$2-$1");
+        let (text, stops) = snippet.expand("");
+        assert_eq!(text, "-");
+        // $1, $2, then the implicit end
+        assert_eq!(stops, vec![(1, 1), (0, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_expand_indent() {
+        let snippet = Snippet::parse("x", "a\n$1");
+        let (text, stops) = snippet.expand("  ");
+        assert_eq!(text, "a\n  ");
+        assert_eq!(stops, vec![(4, 4), (4, 4)]);
+    }
+
+    #[test]
+    fn test_find_before() {
+        let set = SnippetSet::builtin();
+        assert_eq!(set.find_before("see tbl").map(|s| s.trigger.as_str()), Some("tbl"));
+        assert!(set.find_before("xtbl").is_none());
+        assert!(set.find_before("tbl ").is_none());
+    }
+
+    #[test]
+    fn test_session_tracks_edits() {
+        // "[text](url)" inserted at 10
+        let mut session = SnippetSession::new(10, &[(1, 5), (7, 10), (11, 11)]);
+
+        // Replace "text" with "hi"
+        assert!(session.apply_edit(11, 4, 2));
+        assert_eq!(session.stops, vec![(11, 13), (15, 18), (19, 19)]);
+
+        // Typing at the end of the active stop grows it
+        assert!(session.apply_edit(13, 0, 1));
+        assert_eq!(session.stops[0], (11, 14));
+
+        // Editing outside the active stop ends the session
+        assert!(!session.apply_edit(0, 0, 1));
+
+        assert_eq!(session.advance(false), (16, 19));
+        assert!(!session.is_finished());
+        assert_eq!(session.advance(false), (20, 20));
+        assert!(session.is_finished());
+    }
+
+    #[test]
+    fn test_locate_edit() {
+        assert_eq!(locate_edit("aa", "aaa", 1), (0, 0, 1));
+        assert_eq!(locate_edit("aa", "aaa", 3), (2, 0, 1));
+        assert_eq!(locate_edit("abc", "ac", 1), (1, 1, 0));
+        assert_eq!(locate_edit("abc", "aXc", 2), (1, 1, 1));
+    }
+}
@@ -0,0 +1,157 @@
+//! Typographic substitution while typing
+//!
+//! Turns straight quotes into curly ones, `--` into an en dash, `---` into
+//! an em dash and `...` into an ellipsis as they are typed, so the source
+//! itself holds the typographic characters. Code spans, code blocks, math
+//! and frontmatter are left as typed.
+
+use super::TextBuffer;
+use crate::markdown::{LineState, MarkdownTokenizer, TokenType};
+use ropey::Rope;
+use std::ops::Range;
+
+/// Characters whose typing can be substituted
+pub const TRIGGERS: &[char] = &['"', '\'', '-', '.'];
+
+/// Characters after which a quote opens rather than closes
+const OPENING_CONTEXT: &[char] = &['(', '[', '{', '<', '/', '“', '‘', '–', '—', '-'];
+
+/// Whether text typed after `before` on a line is literal, so it shouldn't
+/// be substituted
+fn is_literal(rope: &Rope, line: usize, before: &str) -> bool {
+    let mut tokenizer = MarkdownTokenizer::new();
+    let mut state = LineState::Normal;
+    for idx in 0..line {
+        let text = rope.line(idx).to_string();
+        state = tokenizer.tokenize_line(idx, text.trim_end_matches(['\n', '\r']), state).end_state;
+    }
+    if matches!(
+        state,
+        LineState::InCodeBlock { .. }
+            | LineState::InFrontmatter
+            | LineState::InMathBlock
+            | LineState::InIndentedCode { .. }
+    ) {
+        return true;
+    }
+
+    // A code span being typed isn't closed yet, so count its backticks
+    if before.matches('`').count() % 2 == 1 {
+        return true;
+    }
+    let text = rope.line(line).to_string();
+    let column = before.len();
+    tokenizer
+        .tokenize_line(line, text.trim_end_matches(['\n', '\r']), state)
+        .tokens
+        .iter()
+        .any(|token| match token.token_type {
+            // Fence and frontmatter lines are literal throughout
+            TokenType::CodeBlockDelimiter
+            | TokenType::CodeBlockContent
+            | TokenType::CodeBlockLanguage
+            | TokenType::Frontmatter => true,
+            TokenType::InlineCode
+            | TokenType::LinkUrl
+            | TokenType::ImageUrl
+            | TokenType::Autolink
+            | TokenType::WikiLink
+            | TokenType::Math => token.start < column && column < token.end,
+            _ => false,
+        })
+}
+
+/// Substitution for a character typed after the text before the cursor on
+/// its line
+///
+/// Returns how many characters before the cursor to replace, and the
+/// character replacing them and the typed one.
+pub fn substitution(before: &str, typed: char) -> Option<(usize, char)> {
+    let previous = before.chars().next_back();
+    let opens = previous.map_or(true, |c| c.is_whitespace() || OPENING_CONTEXT.contains(&c));
+    match typed {
+        '"' => Some((0, if opens { '“' } else { '”' })),
+        '\'' => Some((0, if opens { '‘' } else { '’' })),
+        '-' => {
+            // Rules, setext underlines, tables and HTML comments keep
+            // their hyphens
+            let trimmed = before.trim();
+            if trimmed.chars().all(|c| c == '-') || before.contains('|') || before.contains("<!-") {
+                return None;
+            }
+            match previous? {
+                '–' => Some((1, '—')),
+                '-' => Some((1, '–')),
+                _ => None,
+            }
+        }
+        '.' => before.ends_with("..").then_some((2, '…')),
+        _ => None,
+    }
+}
+
+/// Type a character at a character offset, substituting it where it isn't
+/// literal text
+///
+/// The substitution replaces the characters before the cursor in one edit.
+/// Returns the cursor after the typed character.
+pub fn type_char(buffer: &mut TextBuffer, cursor: usize, typed: char) -> Range<usize> {
+    let cursor = cursor.min(buffer.len_chars());
+    let (line, column) = buffer.char_to_line_col(cursor);
+    let before: String = buffer
+        .line_without_newline(line)
+        .unwrap_or_default()
+        .chars()
+        .take(column)
+        .collect();
+
+    let (replaced, ch) = if is_literal(buffer.rope(), line, &before) {
+        None
+    } else {
+        substitution(&before, typed)
+    }
+    .unwrap_or((0, typed));
+    let start = cursor - replaced;
+    buffer.replace(start..cursor, &ch.to_string());
+    start + 1..start + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(text: &str) -> String {
+        let mut buffer = TextBuffer::new();
+        for ch in text.chars() {
+            let cursor = buffer.len_chars();
+            type_char(&mut buffer, cursor, ch);
+        }
+        buffer.to_string()
+    }
+
+    #[test]
+    fn test_quotes() {
+        assert_eq!(type_text("\"Don't,\" she said ('quietly')."), "“Don’t,” she said (‘quietly’).");
+    }
+
+    #[test]
+    fn test_dashes_and_ellipsis() {
+        assert_eq!(type_text("a -- b --- c..."), "a – b — c…");
+        // Rules and tables keep their hyphens
+        assert_eq!(type_text("---"), "---");
+        assert_eq!(type_text("| a |\n|---|"), "| a |\n|---|");
+    }
+
+    #[test]
+    fn test_code_is_literal() {
+        assert_eq!(type_text("`it's -- ok` \"x\""), "`it's -- ok` “x”");
+        assert_eq!(type_text("```\n\"a\" -- b\n```\n"), "```\n\"a\" -- b\n```\n");
+    }
+
+    #[test]
+    fn test_single_edit() {
+        let mut buffer = TextBuffer::from_str("wait..");
+        assert_eq!(type_char(&mut buffer, 6, '.'), 5..5);
+        assert_eq!(buffer.to_string(), "wait…");
+    }
+}
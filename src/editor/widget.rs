@@ -3,40 +3,229 @@
 //! This module provides the visual representation of the editor,
 //! including text rendering, cursor display, and selection highlighting.
 
-use cosmic::iced::Length;
-use cosmic::widget::{column, container, row, scrollable, text};
+use std::collections::BTreeSet;
+
+use cosmic::iced::widget::{Space, Stack};
+use cosmic::iced::{alignment, Length};
+use cosmic::widget::{column, container, divider, row, scrollable, text};
 use cosmic::Element;
 
+use super::diff::LineChange;
 use super::Editor;
+use crate::config::{LineNumbers, RenderWhitespace};
 use crate::message::Message;
 use crate::state::CursorPosition;
 
+/// Advance width of a monospace character, relative to the font size
+pub const MONOSPACE_ADVANCE: f32 = 0.6;
+
+/// Font size of the text drawn by the editor widget
+const TEXT_SIZE: f32 = 14.0;
+
+/// Columns between tab stops in the text editor (cosmic-text's default)
+pub const TAB_STOP: usize = 8;
+
+/// Horizontal offset of `column` from the start of a line of text
+///
+/// Exact for a monospace font with characters `char_width` wide; with a
+/// proportional font it is only an estimate.
+pub fn column_offset(column: usize, char_width: f32) -> f32 {
+    column as f32 * char_width
+}
+
+/// A layer of vertical rulers at `columns`, for stacking over text whose
+/// first column starts `text_left` pixels from the layer's left edge
+pub fn ruler_overlay<'a>(columns: &[usize], char_width: f32, text_left: f32) -> Element<'a, Message> {
+    let mut offsets: Vec<f32> = columns
+        .iter()
+        .map(|&c| (text_left + column_offset(c, char_width)).round())
+        .collect();
+    offsets.sort_by(f32::total_cmp);
+    offsets.dedup();
+
+    let mut layer = row::with_capacity(offsets.len() * 2);
+    let mut x = 0.0;
+    for offset in offsets {
+        layer = layer
+            .push(Space::with_width(Length::Fixed(offset - x)))
+            .push(divider::vertical::default());
+        x = offset + 1.0;
+    }
+    container(layer)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+/// A line prepared for display, split before its trailing whitespace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhitespaceDisplay {
+    /// The line up to its trailing whitespace
+    pub body: String,
+    /// The trailing whitespace
+    pub trailing: String,
+    /// Whether `trailing` is highlighted as unwanted whitespace
+    pub trailing_marked: bool,
+}
+
+/// Prepare a line for display, with markers for the whitespace `mode` shows
+///
+/// Tabs are expanded to `tab_size` columns. Exactly two trailing spaces
+/// after text are a markdown hard line break, and are not highlighted when
+/// `preserve_hard_breaks` is set, like the trailing spaces lint rule.
+pub fn display_whitespace(
+    line: &str,
+    mode: RenderWhitespace,
+    tab_size: usize,
+    preserve_hard_breaks: bool,
+) -> WhitespaceDisplay {
+    let (body, trailing, trailing_marked) = split_trailing(line, mode, preserve_hard_breaks);
+    let all = mode == RenderWhitespace::All;
+
+    WhitespaceDisplay {
+        body: expand_whitespace(body, all, tab_size),
+        trailing: expand_whitespace(trailing, all || trailing_marked, tab_size),
+        trailing_marked,
+    }
+}
+
+/// Split a line before its trailing whitespace, and say whether the
+/// trailing whitespace is marked in `mode`
+fn split_trailing(
+    line: &str,
+    mode: RenderWhitespace,
+    preserve_hard_breaks: bool,
+) -> (&str, &str, bool) {
+    let (body, trailing) = line.split_at(line.trim_end_matches([' ', '\t']).len());
+    let hard_break = preserve_hard_breaks && trailing == "  " && !body.trim().is_empty();
+    let trailing_marked = mode != RenderWhitespace::None && !trailing.is_empty() && !hard_break;
+    (body, trailing, trailing_marked)
+}
+
+/// The whitespace markers of a line, with every other character blanked
+///
+/// Drawn over the line in a monospace font, each marker lands on the
+/// whitespace it stands for. Tabs reach the next of the text editor's tab
+/// stops. Returns an empty string when the line has nothing marked.
+pub fn whitespace_markers(
+    line: &str,
+    mode: RenderWhitespace,
+    preserve_hard_breaks: bool,
+) -> String {
+    let (body, trailing, trailing_marked) = split_trailing(line, mode, preserve_hard_breaks);
+    let all = mode == RenderWhitespace::All;
+
+    let mut markers = String::new();
+    let mut column = 0;
+    for (text, marked) in [(body, all), (trailing, all || trailing_marked)] {
+        for ch in text.chars() {
+            match ch {
+                '\t' => {
+                    let width = TAB_STOP - column % TAB_STOP;
+                    markers.push(if marked { '→' } else { ' ' });
+                    markers.extend(std::iter::repeat(' ').take(width - 1));
+                    column += width;
+                    continue;
+                }
+                ' ' if marked => markers.push('·'),
+                _ => markers.push(' '),
+            }
+            column += 1;
+        }
+    }
+    markers.truncate(markers.trim_end().len());
+    markers
+}
+
+/// A layer of whitespace markers for stacking over the text editor
+///
+/// Holds one row per line from `lines`, each `line_height` tall, starting
+/// `top` pixels down and `text_left` pixels in.
+pub fn whitespace_overlay<'a>(
+    lines: Vec<String>,
+    size: f32,
+    line_height: f32,
+    text_left: f32,
+    top: f32,
+) -> Element<'a, Message> {
+    let mut rows = column::with_capacity(lines.len() + 1).push(Space::with_height(top));
+    for markers in lines {
+        rows = rows.push(
+            container(text(markers).size(size).class(cosmic::theme::Text::Accent))
+                .height(Length::Fixed(line_height))
+                .align_y(alignment::Vertical::Center),
+        );
+    }
+    container(rows)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding([0, 0, 0, text_left as u16])
+        .clip(true)
+        .into()
+}
+
+/// Expand tabs, drawing spaces and tabs as markers when `marked`
+fn expand_whitespace(text: &str, marked: bool, tab_size: usize) -> String {
+    if !marked {
+        return text.replace('\t', &" ".repeat(tab_size));
+    }
+    let tab = format!("→{}", " ".repeat(tab_size.saturating_sub(1)));
+    text.replace(' ', "·").replace('\t', &tab)
+}
+
+/// Number the gutter shows for a line, or `None` with line numbers off
+pub fn gutter_number(mode: LineNumbers, line: usize, cursor_line: usize) -> Option<usize> {
+    match mode {
+        LineNumbers::Off => None,
+        LineNumbers::Absolute => Some(line + 1),
+        LineNumbers::Hybrid if line == cursor_line => Some(line + 1),
+        LineNumbers::Relative | LineNumbers::Hybrid => Some(line.abs_diff(cursor_line)),
+    }
+}
+
+/// Width of the line number column in characters, at least `min_width`
+///
+/// No mode shows a number larger than the line count, so sizing for it
+/// keeps the gutter the same width while scrolling or moving the cursor.
+pub fn gutter_width(min_width: usize, line_count: usize) -> usize {
+    min_width.max(line_count.max(1).to_string().len())
+}
+
 /// Configuration for the editor widget appearance
 #[derive(Debug, Clone)]
 pub struct EditorWidgetConfig {
-    /// Show line numbers
-    pub show_line_numbers: bool,
-    /// Line number width (in characters)
+    /// How lines are numbered in the gutter
+    pub line_numbers: LineNumbers,
+    /// Narrowest line number width (in characters)
     pub line_number_width: usize,
     /// Tab size (spaces per tab)
     pub tab_size: usize,
     /// Highlight current line
     pub highlight_current_line: bool,
-    /// Show whitespace characters
-    pub show_whitespace: bool,
+    /// Which whitespace characters to draw markers for
+    pub render_whitespace: RenderWhitespace,
+    /// Leave two-space markdown line breaks unmarked as trailing whitespace
+    pub preserve_md_hard_breaks: bool,
     /// Word wrap mode
     pub word_wrap: bool,
+    /// Columns to draw vertical rulers at
+    pub rulers: Vec<usize>,
+    /// Width of one character column in pixels
+    pub char_width: f32,
 }
 
 impl Default for EditorWidgetConfig {
     fn default() -> Self {
         Self {
-            show_line_numbers: true,
+            line_numbers: LineNumbers::Absolute,
             line_number_width: 4,
             tab_size: 4,
             highlight_current_line: true,
-            show_whitespace: false,
+            render_whitespace: RenderWhitespace::None,
+            preserve_md_hard_breaks: true,
             word_wrap: true,
+            rulers: Vec::new(),
+            char_width: TEXT_SIZE * MONOSPACE_ADVANCE,
         }
     }
 }
@@ -46,23 +235,45 @@ pub struct EditorWidget;
 
 impl EditorWidget {
     /// Create an editor view element
+    ///
+    /// `markers` are shown in the gutter next to changed lines, a warning
+    /// sign next to the lines in `warning_lines`, and a bookmark sign next
+    /// to the lines in `bookmarks`, ahead of both.
     pub fn view<'a>(
         editor: &'a Editor,
         config: &'a EditorWidgetConfig,
+        markers: &[LineChange],
+        warning_lines: &[usize],
+        bookmarks: &BTreeSet<usize>,
     ) -> Element<'a, Message> {
         let cursor = editor.cursor();
         let scroll_line = editor.scroll_line();
         let line_count = editor.line_count();
 
-        // Calculate visible lines (approximate, will be refined by actual viewport)
-        let visible_lines = 50; // Default visible lines
+        let visible_lines = editor.viewport_lines();
         let end_line = (scroll_line + visible_lines).min(line_count);
 
         // Build the editor content
         let mut content_column = column::with_capacity(end_line - scroll_line);
 
+        let show_markers =
+            !markers.is_empty() || !warning_lines.is_empty() || !bookmarks.is_empty();
+        let number_width = gutter_width(config.line_number_width, line_count);
         for line_idx in scroll_line..end_line {
-            let line_element = Self::render_line(editor, line_idx, cursor, config);
+            let marker = show_markers.then(|| {
+                if bookmarks.contains(&line_idx) {
+                    '◆'
+                } else if warning_lines.contains(&line_idx) {
+                    '⚠'
+                } else {
+                    markers
+                        .iter()
+                        .find(|m| m.line == line_idx)
+                        .map_or(' ', |m| m.kind.symbol())
+                }
+            });
+            let line_element =
+                Self::render_line(editor, line_idx, cursor, config, marker, number_width);
             content_column = content_column.push(line_element);
         }
 
@@ -75,44 +286,67 @@ impl EditorWidget {
         .height(Length::Fill)
         .padding(8);
 
-        editor_content.into()
+        if config.rulers.is_empty() {
+            return editor_content.into();
+        }
+        let text_left = Self::text_left(config, show_markers, number_width);
+        Stack::new()
+            .push(editor_content)
+            .push(ruler_overlay(&config.rulers, config.char_width, text_left))
+            .into()
+    }
+
+    /// Distance from the widget's left edge to the first text column,
+    /// past the padding, marker column, and line numbers
+    fn text_left(config: &EditorWidgetConfig, show_markers: bool, number_width: usize) -> f32 {
+        let char_width = config.char_width;
+        // Outer padding, then the line content's own padding
+        let mut left = 8.0 + 4.0;
+        if show_markers {
+            left += char_width + 4.0;
+        }
+        if config.line_numbers != LineNumbers::Off {
+            left += (number_width + 1) as f32 * char_width + 8.0;
+        }
+        left
     }
 
-    /// Render a single line with optional line number
+    /// Render a single line with optional line number and change marker
+    ///
+    /// `marker` is None when the gutter has no marker column, and
+    /// `Some(' ')` for an unmarked line. Line numbers are right-aligned to
+    /// `number_width` characters.
     fn render_line<'a>(
         editor: &'a Editor,
         line_idx: usize,
         cursor: CursorPosition,
         config: &'a EditorWidgetConfig,
+        marker: Option<char>,
+        number_width: usize,
     ) -> Element<'a, Message> {
         let line_content = editor.get_line(line_idx).unwrap_or_default();
         let is_current_line = line_idx == cursor.line;
 
-        // Prepare display content (replace tabs with spaces)
-        let display_content = if config.show_whitespace {
-            line_content
-                .replace('\t', &"→".repeat(1))
-                .replace(' ', "·")
-        } else {
-            line_content.replace('\t', &" ".repeat(config.tab_size))
-        };
+        let display = display_whitespace(
+            &line_content,
+            config.render_whitespace,
+            config.tab_size,
+            config.preserve_md_hard_breaks,
+        );
 
-        // Empty lines need at least a space for proper height
-        let display_content = if display_content.is_empty() {
-            " ".to_string()
-        } else {
-            display_content
-        };
+        let mut line_row = row::with_capacity(3);
 
-        let mut line_row = row::with_capacity(2);
+        // Change marker column
+        if let Some(symbol) = marker {
+            line_row = line_row.push(
+                container(text(symbol.to_string()).size(14))
+                    .padding([0, 4, 0, 0]),
+            );
+        }
 
         // Line number (if enabled)
-        if config.show_line_numbers {
-            let line_num = format!(
-                "{:>width$} ",
-                line_idx + 1,
-                width = config.line_number_width
-            );
+        if let Some(number) = gutter_number(config.line_numbers, line_idx, cursor.line) {
+            let line_num = format!("{:>width$} ", number, width = number_width);
             let line_num_text = text(line_num).size(14);
             line_row = line_row.push(
                 container(line_num_text)
@@ -120,8 +354,23 @@ impl EditorWidget {
             );
         }
 
-        // Line content
-        let content_text = text(display_content).size(14);
+        // Line content, with trailing whitespace drawn in the accent color
+        // when it is marked. Empty lines need at least a space for proper
+        // height.
+        let content_text: Element<'a, Message> = if display.trailing_marked {
+            row::with_capacity(2)
+                .push(text(display.body).size(14))
+                .push(
+                    text(display.trailing)
+                        .size(14)
+                        .class(cosmic::theme::Text::Accent),
+                )
+                .into()
+        } else if display.body.is_empty() && display.trailing.is_empty() {
+            text(" ").size(14).into()
+        } else {
+            text(display.body + &display.trailing).size(14).into()
+        };
 
         // Wrap in container, potentially with current line highlighting
         let content_container = if is_current_line && config.highlight_current_line {
@@ -209,3 +458,66 @@ impl EditorViewport {
         self.visible_columns = (width / self.char_width).ceil() as usize;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_whitespace_modes() {
+        let line = "a\tb  c ";
+        let none = display_whitespace(line, RenderWhitespace::None, 2, true);
+        assert_eq!(none.body, "a  b  c");
+        assert_eq!(none.trailing, " ");
+        assert!(!none.trailing_marked);
+
+        let trailing = display_whitespace(line, RenderWhitespace::Trailing, 2, true);
+        assert_eq!(trailing.body, "a  b  c");
+        assert_eq!(trailing.trailing, "·");
+        assert!(trailing.trailing_marked);
+
+        let all = display_whitespace(line, RenderWhitespace::All, 2, true);
+        assert_eq!(all.body, "a→ b··c");
+        assert_eq!(all.trailing, "·");
+        assert!(all.trailing_marked);
+    }
+
+    #[test]
+    fn test_whitespace_markers() {
+        assert_eq!(whitespace_markers("a b", RenderWhitespace::All, true), " ·");
+        assert_eq!(whitespace_markers("a b  ", RenderWhitespace::Trailing, false), "   ··");
+        assert_eq!(whitespace_markers("a b  ", RenderWhitespace::Trailing, true), "");
+        assert_eq!(whitespace_markers("a b ", RenderWhitespace::None, true), "");
+        // Tabs reach the next tab stop, and literal dots aren't markers
+        assert_eq!(whitespace_markers("x\t·\t", RenderWhitespace::All, true), " →       →");
+    }
+
+    #[test]
+    fn test_gutter_numbers() {
+        assert_eq!(gutter_number(LineNumbers::Off, 3, 5), None);
+        assert_eq!(gutter_number(LineNumbers::Absolute, 3, 5), Some(4));
+        assert_eq!(gutter_number(LineNumbers::Relative, 3, 5), Some(2));
+        assert_eq!(gutter_number(LineNumbers::Relative, 5, 5), Some(0));
+        assert_eq!(gutter_number(LineNumbers::Hybrid, 8, 5), Some(3));
+        assert_eq!(gutter_number(LineNumbers::Hybrid, 5, 5), Some(6));
+
+        assert_eq!(gutter_width(4, 0), 4);
+        assert_eq!(gutter_width(4, 12_345), 5);
+        assert_eq!(gutter_width(1, 99), 2);
+    }
+
+    #[test]
+    fn test_display_whitespace_hard_break() {
+        let kept = display_whitespace("line  ", RenderWhitespace::Trailing, 4, true);
+        assert_eq!(kept.trailing, "  ");
+        assert!(!kept.trailing_marked);
+
+        let marked = display_whitespace("line  ", RenderWhitespace::Trailing, 4, false);
+        assert_eq!(marked.trailing, "··");
+        assert!(marked.trailing_marked);
+
+        // Whitespace-only lines and three spaces are not line breaks
+        assert!(display_whitespace("  ", RenderWhitespace::Trailing, 4, true).trailing_marked);
+        assert!(display_whitespace("line   ", RenderWhitespace::Trailing, 4, true).trailing_marked);
+    }
+}
@@ -133,6 +133,10 @@ pub enum FileError {
     #[error("File is read-only: {path}")]
     ReadOnly { path: PathBuf },
 
+    /// Saving through a symlink whose target does not exist
+    #[error("Symlink target does not exist: {path} -> {target}")]
+    BrokenSymlink { path: PathBuf, target: PathBuf },
+
     /// Path is not a file
     #[error("Path is not a file: {path}")]
     NotAFile { path: PathBuf },
@@ -3,8 +3,10 @@
 //! Provides safe file reading and writing with:
 //! - UTF-8 and UTF-16 encoding detection
 //! - Atomic writes to prevent data loss
+//! - Rotating backups of previous versions on save
 //! - File size limits and warnings
 
+use crate::config::BackupLocation;
 use crate::error::{FileError, FileResult};
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -310,62 +312,204 @@ pub fn read_file_sync(path: impl AsRef<Path>) -> FileResult<FileReadResult> {
     })
 }
 
+/// Decode text that did not come from a file, such as piped input
+///
+/// Returns None for binary data: bytes that are neither UTF-8 nor UTF-16
+/// with a byte order mark, or that contain NUL characters.
+pub fn decode_text(bytes: &[u8]) -> Option<String> {
+    let encoding = detect_encoding(bytes);
+    if encoding == FileEncoding::Unknown {
+        return None;
+    }
+    let (content, lossy) = decode_content(bytes, encoding);
+    (!lossy && !content.contains('\0')).then_some(content)
+}
+
+/// Read all of standard input as text
+///
+/// Input past `MAX_FILE_SIZE` is refused like a file that is too large.
+/// Errors name the input `-`, as it is given on the command line.
+pub fn read_stdin() -> FileResult<String> {
+    use std::io::Read;
+
+    let path = PathBuf::from("-");
+    let mut bytes = Vec::new();
+    std::io::stdin()
+        .lock()
+        .take(MAX_FILE_SIZE + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| FileError::ReadError { path: path.clone(), source: e })?;
+
+    if bytes.len() as u64 > MAX_FILE_SIZE {
+        return Err(FileError::FileTooLarge {
+            path,
+            size: bytes.len() as u64,
+            max_size: MAX_FILE_SIZE,
+        });
+    }
+    decode_text(&bytes).ok_or(FileError::EncodingError { path })
+}
+
 /// Write content to a file using atomic write
 /// 
 /// This ensures the file is either fully written or unchanged,
-/// preventing data loss from interrupted writes.
-pub async fn write_file_atomic(path: impl AsRef<Path>, content: &str) -> FileResult<()> {
-    let path = path.as_ref();
-    let path_buf = path.to_path_buf();
-    
-    // Generate temp filename in same directory
+/// preventing data loss from interrupted writes. With `follow_symlinks`,
+/// a symlink is kept and its target is written instead.
+pub async fn write_file_atomic(
+    path: impl AsRef<Path>,
+    content: &str,
+    follow_symlinks: bool,
+) -> FileResult<()> {
+    let path = path.as_ref().to_path_buf();
+    let content = content.to_string();
+
+    tokio::task::spawn_blocking(move || write_file_atomic_sync(&path, &content, follow_symlinks))
+        .await
+        .map_err(|e| FileError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?
+}
+
+/// Save a document's content, first backing up the version it replaces
+///
+/// Like `write_file_atomic`, keeping up to `backup_depth` previous
+/// versions where `location` puts them. A failed backup is logged and
+/// doesn't stop the save.
+pub async fn save_file(
+    path: impl AsRef<Path>,
+    content: &str,
+    follow_symlinks: bool,
+    backup_depth: usize,
+    location: BackupLocation,
+) -> FileResult<()> {
+    let path = path.as_ref().to_path_buf();
+    let content = content.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        if backup_depth > 0 {
+            let target = if follow_symlinks {
+                resolve_symlink(&path)?
+            } else {
+                path.clone()
+            };
+            if let Err(e) = backup_previous(&target, &content, backup_depth, location) {
+                log::warn!("Failed to back up {}: {}", target.display(), e);
+            }
+        }
+        write_file_atomic_sync(&path, &content, follow_symlinks)
+    })
+    .await
+    .map_err(|e| FileError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?
+}
+
+/// Backup `n` of a file, where 1 is the newest
+pub fn backup_path(path: &Path, location: BackupLocation, n: usize) -> PathBuf {
     let parent = path.parent().unwrap_or(Path::new("."));
-    let filename = path.file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| "file".to_string());
-    
-    let timestamp = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .map(|d| d.as_millis())
-        .unwrap_or(0);
-    
-    let temp_filename = format!(".{}.{}.tmp", filename, timestamp);
-    let temp_path = parent.join(&temp_filename);
-    
-    // Write to temp file
-    let write_result = async {
-        let mut file = tokio::fs::File::create(&temp_path).await?;
-        tokio::io::AsyncWriteExt::write_all(&mut file, content.as_bytes()).await?;
-        tokio::io::AsyncWriteExt::flush(&mut file).await?;
-        file.sync_all().await?;
-        Ok::<(), std::io::Error>(())
-    }.await;
-    
-    if let Err(e) = write_result {
-        // Clean up temp file on failure
-        let _ = tokio::fs::remove_file(&temp_path).await;
-        return Err(FileError::WriteError {
-            path: path_buf,
-            source: e,
-        });
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    location.backup_dir(parent).join(format!(".{}.bak.{}", name, n))
+}
+
+/// Copy a file's current content to its newest backup before it is
+/// overwritten with `content`, keeping at most `depth` backups
+///
+/// Older backups move down one number, and those past the depth are
+/// removed, including any left from a larger depth. Returns false without
+/// touching the backups when there is no file yet or `content` is what it
+/// already holds.
+pub fn backup_previous(
+    path: &Path,
+    content: &str,
+    depth: usize,
+    location: BackupLocation,
+) -> std::io::Result<bool> {
+    if depth == 0 {
+        return Ok(false);
     }
-    
-    // Atomic rename
-    if let Err(e) = tokio::fs::rename(&temp_path, path).await {
-        // Clean up temp file
-        let _ = tokio::fs::remove_file(&temp_path).await;
-        return Err(FileError::WriteError {
-            path: path_buf,
-            source: e,
-        });
+    let previous = match std::fs::read(path) {
+        Ok(previous) => previous,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if previous == content.as_bytes() {
+        return Ok(false);
+    }
+
+    let newest = backup_path(path, location, 1);
+    if let Some(dir) = newest.parent() {
+        std::fs::create_dir_all(dir)?;
+        prune_backups(path, dir, depth)?;
+    }
+    for n in (1..depth).rev() {
+        let from = backup_path(path, location, n);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(path, location, n + 1))?;
+        }
+    }
+    std::fs::write(&newest, previous)?;
+    Ok(true)
+}
+
+/// Remove the backups of `path` in `dir` numbered `depth` or higher, the
+/// oldest of which would otherwise be rotated past the depth
+fn prune_backups(path: &Path, dir: &Path, depth: usize) -> std::io::Result<()> {
+    let prefix = format!(".{}.bak.", path.file_name().unwrap_or_default().to_string_lossy());
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let number = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(&prefix))
+            .and_then(|n| n.parse::<usize>().ok());
+        if number.is_some_and(|n| n >= depth) {
+            std::fs::remove_file(entry.path())?;
+        }
     }
-    
     Ok(())
 }
 
 /// Write content to a file synchronously using atomic write
-pub fn write_file_atomic_sync(path: impl AsRef<Path>, content: &str) -> FileResult<()> {
+///
+/// Content goes to a temp file in the same directory, which is then
+/// renamed over the target. Some filesystems refuse one of those steps
+/// (FUSE and NFS mounts, or a writable file in a read-only directory), so
+/// the content is then written in place, keeping a `.bak` copy of the old
+/// file until the write succeeds.
+pub fn write_file_atomic_sync(
+    path: impl AsRef<Path>,
+    content: &str,
+    follow_symlinks: bool,
+) -> FileResult<()> {
     let path = path.as_ref();
+    let target = if follow_symlinks {
+        resolve_symlink(path)?
+    } else {
+        path.to_path_buf()
+    };
+    write_atomic_with(&target, content, |from, to| std::fs::rename(from, to))
+}
+
+/// Resolve a symlink to the file it points at
+///
+/// Other paths are returned unchanged. A dangling link is an error, so
+/// saving never quietly creates its target.
+fn resolve_symlink(path: &Path) -> FileResult<PathBuf> {
+    let is_link = std::fs::symlink_metadata(path)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false);
+    if !is_link {
+        return Ok(path.to_path_buf());
+    }
+
+    std::fs::canonicalize(path).map_err(|_| FileError::BrokenSymlink {
+        path: path.to_path_buf(),
+        target: std::fs::read_link(path).unwrap_or_default(),
+    })
+}
+
+/// Atomic write with the rename step supplied, so tests can make it fail
+fn write_atomic_with(
+    path: &Path,
+    content: &str,
+    rename: impl Fn(&Path, &Path) -> std::io::Result<()>,
+) -> FileResult<()> {
     let path_buf = path.to_path_buf();
     
     // Generate temp filename in same directory
@@ -381,12 +525,20 @@ pub fn write_file_atomic_sync(path: impl AsRef<Path>, content: &str) -> FileResu
     
     let temp_filename = format!(".{}.{}.tmp", filename, timestamp);
     let temp_path = parent.join(&temp_filename);
+
+    // The renamed temp file replaces the original, so it takes over its
+    // permissions; a new file keeps the defaults
+    let existing = std::fs::metadata(path).ok();
     
     // Write to temp file
     let write_result = (|| {
         let mut file = std::fs::File::create(&temp_path)?;
         file.write_all(content.as_bytes())?;
         file.flush()?;
+        if let Some(metadata) = &existing {
+            copy_ownership(&file, metadata);
+            file.set_permissions(metadata.permissions())?;
+        }
         file.sync_all()?;
         Ok::<(), std::io::Error>(())
     })();
@@ -394,25 +546,84 @@ pub fn write_file_atomic_sync(path: impl AsRef<Path>, content: &str) -> FileResu
     if let Err(e) = write_result {
         // Clean up temp file on failure
         let _ = std::fs::remove_file(&temp_path);
-        return Err(FileError::WriteError {
-            path: path_buf,
-            source: e,
+        log::warn!("Could not write temp file for {}: {}", path.display(), e);
+        return write_in_place(path, content).map_err(|fallback| {
+            log::warn!("Direct write of {} failed: {}", path.display(), fallback);
+            FileError::AtomicWriteError {
+                path: path_buf,
+                source: e,
+            }
         });
     }
     
     // Atomic rename
-    if let Err(e) = std::fs::rename(&temp_path, path) {
+    if let Err(e) = rename(&temp_path, path) {
         // Clean up temp file
         let _ = std::fs::remove_file(&temp_path);
-        return Err(FileError::WriteError {
-            path: path_buf,
-            source: e,
+        log::warn!("Could not rename temp file over {}: {}", path.display(), e);
+        return write_in_place(path, content).map_err(|fallback| {
+            log::warn!("Direct write of {} failed: {}", path.display(), fallback);
+            FileError::RenameError {
+                path: path_buf,
+                source: e,
+            }
         });
     }
     
     Ok(())
 }
 
+/// Give a file the owner and group from another file's metadata
+///
+/// Only root can give files away, so failure is expected and ignored;
+/// the group can often still be kept.
+#[cfg(unix)]
+fn copy_ownership(file: &std::fs::File, metadata: &std::fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+
+    if std::os::unix::fs::fchown(file, Some(metadata.uid()), Some(metadata.gid())).is_err() {
+        let _ = std::os::unix::fs::fchown(file, None, Some(metadata.gid()));
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_ownership(_file: &std::fs::File, _metadata: &std::fs::Metadata) {}
+
+/// Overwrite a file in place, keeping a hidden `.bak` copy until the write
+/// is done
+///
+/// The copy is named like the atomic write's temp file, so it never
+/// replaces a backup of the user's own. Nothing is written when the backup
+/// cannot be made.
+fn write_in_place(path: &Path, content: &str) -> std::io::Result<()> {
+    let filename = path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let backup = path.with_file_name(format!(".{}.{}.bak", filename, timestamp));
+
+    let had_file = path.is_file();
+    if had_file {
+        std::fs::copy(path, &backup)?;
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+
+    if had_file {
+        let _ = std::fs::remove_file(&backup);
+    }
+    Ok(())
+}
+
 /// Simple write without atomic safety (for non-critical writes)
 pub async fn write_file(path: impl AsRef<Path>, content: &str) -> FileResult<()> {
     let path = path.as_ref();
@@ -441,6 +652,32 @@ pub fn file_size(path: impl AsRef<Path>) -> Option<u64> {
     std::fs::metadata(path.as_ref()).ok().map(|m| m.len())
 }
 
+/// Make a read-only file writable by its owner
+pub fn make_writable(path: impl AsRef<Path>) -> FileResult<()> {
+    let path = path.as_ref();
+    let to_error = |e: std::io::Error| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            FileError::PermissionDenied { path: path.to_path_buf() }
+        } else {
+            FileError::WriteError { path: path.to_path_buf(), source: e }
+        }
+    };
+
+    let mut permissions = std::fs::metadata(path).map_err(to_error)?.permissions();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    {
+        permissions.set_readonly(false);
+    }
+
+    std::fs::set_permissions(path, permissions).map_err(to_error)
+}
+
 /// Ensure parent directory exists
 pub async fn ensure_parent_dir(path: impl AsRef<Path>) -> FileResult<()> {
     let path = path.as_ref();
@@ -483,6 +720,15 @@ mod tests {
         assert_eq!(detect_encoding(&bytes), FileEncoding::Utf16Be);
     }
     
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(decode_text(b"").as_deref(), Some(""));
+        assert_eq!(decode_text("# Notes\n".as_bytes()).as_deref(), Some("# Notes\n"));
+        assert_eq!(decode_text(&[0xFF, 0xFE, b'H', 0, b'i', 0]).as_deref(), Some("Hi"));
+        assert_eq!(decode_text(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A]), None);
+        assert_eq!(decode_text(b"text\0with nul"), None);
+    }
+
     #[test]
     fn test_file_info_modified_ago() {
         let info = FileInfo {
@@ -495,4 +741,180 @@ mod tests {
         };
         assert_eq!(info.modified_ago(), "Just now");
     }
+
+    #[test]
+    fn test_make_writable() {
+        let path = std::env::temp_dir().join(format!(
+            "cosmic-notebook-readonly-{}.md",
+            std::process::id()
+        ));
+        std::fs::write(&path, "locked").unwrap();
+
+        let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions).unwrap();
+        assert!(FileInfo::from_path(&path).is_readonly);
+
+        make_writable(&path).unwrap();
+        assert!(!FileInfo::from_path(&path).is_readonly);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// A fresh directory under the system temp dir
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "cosmic-notebook-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_atomic_write_rename_fallback() {
+        let dir = temp_dir("rename-fallback");
+        let path = dir.join("note.md");
+        std::fs::write(&path, "old").unwrap();
+
+        let refuse = |_: &Path, _: &Path| {
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "rename refused"))
+        };
+        write_atomic_with(&path, "new", refuse).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        // Neither the temp file nor the backup is left behind
+        let names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec![std::ffi::OsString::from("note.md")]);
+
+        // A backup the user made survives the fallback
+        let user_backup = dir.join("note.md.bak");
+        std::fs::write(&user_backup, "mine").unwrap();
+        write_atomic_with(&path, "newer", refuse).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "newer");
+        assert_eq!(std::fs::read_to_string(&user_backup).unwrap(), "mine");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_keeps_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir("keep-mode");
+        let path = dir.join("secret.md");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_file_atomic_sync(&path, "new", true).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_through_symlink() {
+        let dir = temp_dir("symlink-save");
+        let target = dir.join("real.md");
+        let link = dir.join("link.md");
+        std::fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let read = read_file_sync(&link).unwrap();
+        write_file_atomic_sync(&link, &format!("{} edited", read.content), true).unwrap();
+
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_link(&link).unwrap(), target);
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "old edited");
+
+        // A dangling link is not silently turned into a file
+        let dangling = dir.join("dangling.md");
+        std::os::unix::fs::symlink(dir.join("gone.md"), &dangling).unwrap();
+        assert!(matches!(
+            write_file_atomic_sync(&dangling, "text", true),
+            Err(FileError::BrokenSymlink { .. })
+        ));
+        assert!(!dir.join("gone.md").exists());
+
+        // Without following, the link is replaced by a regular file
+        write_file_atomic_sync(&link, "replaced", false).unwrap();
+        assert!(std::fs::symlink_metadata(&link).unwrap().is_file());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "old edited");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_backup_rotation() {
+        let dir = temp_dir("backup-rotation");
+        let path = dir.join("note.md");
+        let read = |n| std::fs::read_to_string(backup_path(&path, BackupLocation::Alongside, n));
+
+        // Nothing to back up before the first save
+        assert!(!backup_previous(&path, "v1", 2, BackupLocation::Alongside).unwrap());
+        std::fs::write(&path, "v1").unwrap();
+        for version in ["v2", "v3", "v4"] {
+            assert!(backup_previous(&path, version, 2, BackupLocation::Alongside).unwrap());
+            std::fs::write(&path, version).unwrap();
+        }
+        assert_eq!(read(1).unwrap(), "v3");
+        assert_eq!(read(2).unwrap(), "v2");
+        assert!(read(3).is_err());
+
+        // Saving unchanged content leaves the backups alone
+        assert!(!backup_previous(&path, "v4", 2, BackupLocation::Alongside).unwrap());
+        assert_eq!(read(1).unwrap(), "v3");
+
+        // A smaller depth prunes the backups past it
+        assert!(backup_previous(&path, "v5", 1, BackupLocation::Alongside).unwrap());
+        assert_eq!(read(1).unwrap(), "v4");
+        assert!(read(2).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_backup_hidden_folder() {
+        let dir = temp_dir("backup-hidden");
+        let path = dir.join("note.md");
+        std::fs::write(&path, "old").unwrap();
+
+        assert!(backup_previous(&path, "new", 3, BackupLocation::HiddenFolder).unwrap());
+        let backup = backup_path(&path, BackupLocation::HiddenFolder, 1);
+        assert_eq!(backup, dir.join(".backups").join(".note.md.bak.1"));
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "old");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_atomic_write_errors() {
+        let dir = temp_dir("atomic-errors");
+
+        // No directory to hold the temp file or the file itself
+        let missing = dir.join("missing").join("note.md");
+        assert!(matches!(
+            write_file_atomic_sync(&missing, "text", true),
+            Err(FileError::AtomicWriteError { .. })
+        ));
+
+        // A directory can neither be renamed over nor written
+        let target = dir.join("folder");
+        std::fs::create_dir(&target).unwrap();
+        assert!(matches!(
+            write_file_atomic_sync(&target, "text", true),
+            Err(FileError::RenameError { .. })
+        ));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
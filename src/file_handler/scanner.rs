@@ -7,8 +7,10 @@
 //! - Ignored directory patterns
 
 use crate::state::FileEntry;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
 use walkdir::{DirEntry, WalkDir};
 
 /// Configuration for directory scanning
@@ -336,6 +338,57 @@ pub fn scan_children(parent: impl AsRef<Path>, config: &ScanConfig) -> Vec<FileE
     entries
 }
 
+/// Immediate children of a directory, as listed at one point in time
+#[derive(Debug, Clone)]
+pub struct DirListing {
+    /// The listed directory
+    pub dir: PathBuf,
+
+    /// Its children, as from `scan_children`
+    pub children: Vec<FileEntry>,
+
+    /// When the directory was last modified, to tell whether the listing
+    /// is still current
+    pub modified: Option<SystemTime>,
+}
+
+/// List a directory's immediate children
+pub fn list_directory(dir: PathBuf, config: &ScanConfig) -> DirListing {
+    let modified = dir.metadata().and_then(|m| m.modified()).ok();
+    let children = scan_children(&dir, config);
+    DirListing { dir, children, modified }
+}
+
+/// List `root` and every directory below it, breadth first
+///
+/// Stops after `max_dirs` directories, or once `cancel` is set, so
+/// expanding a huge tree stays bounded. Returns the listings made, parents
+/// before their children, and whether it stopped early.
+pub fn list_tree(
+    root: PathBuf,
+    config: &ScanConfig,
+    max_dirs: usize,
+    cancel: &AtomicBool,
+) -> (Vec<DirListing>, bool) {
+    let mut listings = Vec::new();
+    let mut queue = VecDeque::from([root]);
+    while let Some(dir) = queue.pop_front() {
+        if listings.len() >= max_dirs || cancel.load(Ordering::Relaxed) {
+            return (listings, true);
+        }
+        let listing = list_directory(dir, config);
+        queue.extend(
+            listing
+                .children
+                .iter()
+                .filter(|child| child.is_directory)
+                .map(|child| child.path.clone()),
+        );
+        listings.push(listing);
+    }
+    (listings, false)
+}
+
 /// Count markdown files in a directory (for quick stats)
 pub fn count_markdown_files(root: impl AsRef<Path>) -> usize {
     let config = ScanConfig::markdown_only();
@@ -405,6 +458,31 @@ mod tests {
         assert!(has_toml);
     }
     
+    #[test]
+    fn test_list_tree() {
+        let dir = setup_test_dir();
+        fs::create_dir(dir.path().join("docs/old")).unwrap();
+        let config = ScanConfig::markdown_only();
+        let cancel = AtomicBool::new(false);
+
+        let (listings, stopped) = list_tree(dir.path().to_path_buf(), &config, 10, &cancel);
+        assert!(!stopped);
+        let dirs: Vec<_> = listings.iter().map(|l| l.dir.clone()).collect();
+        let root = dir.path();
+        assert_eq!(dirs, vec![root.to_path_buf(), root.join("docs"), root.join("docs/old")]);
+        assert!(listings[1].children.iter().any(|c| c.name == "guide.md"));
+        assert!(listings[0].modified.is_some());
+
+        let (listings, stopped) = list_tree(root.to_path_buf(), &config, 2, &cancel);
+        assert!(stopped);
+        assert_eq!(listings.len(), 2);
+
+        cancel.store(true, Ordering::Relaxed);
+        let (listings, stopped) = list_tree(root.to_path_buf(), &config, 10, &cancel);
+        assert!(stopped);
+        assert!(listings.is_empty());
+    }
+
     #[test]
     fn test_scan_ignores_node_modules() {
         let dir = setup_test_dir();
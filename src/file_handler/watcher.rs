@@ -80,7 +80,7 @@ impl WatcherConfig {
 /// Manages file system watching
 pub struct FileWatcher {
     /// The underlying notify watcher
-    _watcher: RecommendedWatcher,
+    watcher: RecommendedWatcher,
     
     /// Receiver for events
     event_rx: Receiver<notify::Result<Event>>,
@@ -114,7 +114,7 @@ impl FileWatcher {
         )?;
         
         Ok(Self {
-            _watcher: watcher,
+            watcher,
             event_rx: rx,
             watched_paths: HashSet::new(),
             config,
@@ -137,9 +137,7 @@ impl FileWatcher {
             RecursiveMode::NonRecursive
         };
         
-        // Need to use a method that exists - get mutable reference through accessor
-        // This is a limitation - we need to recreate watcher for new paths
-        // For now, we'll track what we want to watch
+        self.watcher.watch(&path, mode)?;
         self.watched_paths.insert(path);
         
         Ok(())
@@ -148,7 +146,9 @@ impl FileWatcher {
     /// Stop watching a path
     pub fn unwatch(&mut self, path: impl AsRef<Path>) -> Result<(), notify::Error> {
         let path = path.as_ref().to_path_buf();
-        self.watched_paths.remove(&path);
+        if self.watched_paths.remove(&path) {
+            self.watcher.unwatch(&path)?;
+        }
         Ok(())
     }
     
@@ -396,6 +396,24 @@ mod tests {
         assert!(config.watch_extensions.contains("md"));
     }
     
+    #[test]
+    fn test_watch_registers_path() {
+        let dir = std::env::temp_dir().join(format!("cosmic-notebook-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        
+        let mut watcher = FileWatcher::new(WatcherConfig::watch_all()).unwrap();
+        watcher.watch(&dir).unwrap();
+        assert!(watcher.is_watching(&dir));
+        watcher.unwatch(&dir).unwrap();
+        assert!(!watcher.is_watching(&dir));
+        
+        // A missing path can't be watched
+        assert!(watcher.watch(dir.join("missing")).is_err());
+        assert!(!watcher.is_watching(dir.join("missing")));
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
     #[test]
     fn test_event_debouncer() {
         let mut debouncer = EventDebouncer::new(100);
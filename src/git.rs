@@ -0,0 +1,135 @@
+//! Git integration for Cosmic Notebook
+//!
+//! Compares documents with their version in HEAD so the editor gutter can
+//! mark added, modified, and deleted lines. Files outside a repository (or
+//! ignored, binary, or unreadable ones) simply have no line status.
+
+use crate::editor::diff::{line_changes, line_diff, LineChange};
+use std::path::Path;
+
+/// Get the line changes of a file on disk relative to HEAD
+///
+/// Returns None when the file is not part of a git repository.
+pub fn line_status(path: &Path) -> Option<Vec<LineChange>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    line_status_for(path, &content)
+}
+
+/// Get the line changes of `content` relative to the file's version in HEAD
+pub fn line_status_for(path: &Path, content: &str) -> Option<Vec<LineChange>> {
+    let head = head_content(path)?;
+    Some(line_changes(&line_diff(&head, content)))
+}
+
+/// Read a file's content from HEAD
+///
+/// Files not yet committed (or repositories without commits) have empty
+/// HEAD content, so all their lines show as added.
+fn head_content(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    let repo = git2::Repository::discover(path.parent()?).ok()?;
+    let workdir = repo.workdir()?.canonicalize().ok()?;
+    let relative = path.strip_prefix(&workdir).ok()?;
+
+    if repo.is_path_ignored(relative).unwrap_or(false) {
+        return None;
+    }
+
+    let tree = match repo.head().and_then(|head| head.peel_to_tree()) {
+        Ok(tree) => tree,
+        Err(_) => return Some(String::new()),
+    };
+    let entry = match tree.get_path(relative) {
+        Ok(entry) => entry,
+        Err(_) => return Some(String::new()),
+    };
+
+    let blob = entry.to_object(&repo).ok()?.peel_to_blob().ok()?;
+    if blob.is_binary() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(blob.content()).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_status_outside_repository() {
+        let dir = std::env::temp_dir().join(format!("cosmic-notebook-git-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notes.md");
+        std::fs::write(&path, "# Notes\n").unwrap();
+
+        // The temp directory is not expected to be inside a repository
+        if git2::Repository::discover(&dir).is_err() {
+            assert!(line_status(&path).is_none());
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_line_status_in_repository() {
+        use crate::editor::diff::ChangeKind;
+
+        let dir =
+            std::env::temp_dir().join(format!("cosmic-notebook-git-repo-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notes.md");
+        std::fs::write(&path, "a\nb\nc\n").unwrap();
+
+        let repo = git2::Repository::init(&dir).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("notes.md")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Add notes",
+            &tree,
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(line_status(&path), Some(Vec::new()));
+
+        std::fs::write(&path, "a\nB\nc\nd\n").unwrap();
+        assert_eq!(
+            line_status(&path),
+            Some(vec![
+                LineChange {
+                    line: 1,
+                    kind: ChangeKind::Modified
+                },
+                LineChange {
+                    line: 3,
+                    kind: ChangeKind::Added
+                },
+            ])
+        );
+
+        // Files not yet committed are all added
+        let new_path = dir.join("new.md");
+        std::fs::write(&new_path, "x\n").unwrap();
+        assert_eq!(
+            line_status(&new_path),
+            Some(vec![LineChange {
+                line: 0,
+                kind: ChangeKind::Added
+            }])
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_line_status_missing_file() {
+        assert!(line_status(Path::new("/nonexistent/cosmic-notebook/file.md")).is_none());
+    }
+}
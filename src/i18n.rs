@@ -2,29 +2,71 @@
 //!
 //! Provides localization support using fluent-rs for translations.
 //! Supports runtime language switching and follows COSMIC desktop locale settings.
+//!
+//! English is embedded as the fallback language, so a message missing from
+//! a translation is shown in English. Plural forms are chosen by Fluent
+//! selectors in the `.ftl` files (`{ $count -> [one] ... *[other] ... }`),
+//! using the plural rules of the selected language.
+
+use i18n_embed::fluent::{fluent_language_loader, FluentLanguageLoader};
+use i18n_embed::{DefaultLocalizer, LanguageLoader, Localizer};
+use once_cell::sync::Lazy;
+use rust_embed::RustEmbed;
+
+/// Translation files, embedded from `i18n/<language>/cosmic_notebook.ftl`
+#[derive(RustEmbed)]
+#[folder = "i18n/"]
+struct Localizations;
 
-// Simplified i18n for Phase 1
-// Full fluent integration will be added in a later phase
+/// Loader for the translations of the selected language
+pub static LANGUAGE_LOADER: Lazy<FluentLanguageLoader> = Lazy::new(|| {
+    let loader: FluentLanguageLoader = fluent_language_loader!();
+    loader
+        .load_fallback_language(&Localizations)
+        .expect("Error while loading fallback language");
+    // Isolation marks around arguments would end up in copied paths and links
+    loader.set_use_isolating(false);
+    loader
+});
 
 /// Initialize localization with system locale
 pub fn init() {
-    log::debug!("i18n initialization - using default English strings");
+    let requested = i18n_embed::DesktopLanguageRequester::requested_languages();
+    let localizer = DefaultLocalizer::new(&*LANGUAGE_LOADER, &Localizations);
+    if let Err(e) = localizer.select(&requested) {
+        log::warn!("Error while loading languages: {}", e);
+    }
 }
 
 /// Get the current language code
 pub fn current_language() -> String {
-    "en".to_string()
+    LANGUAGE_LOADER.current_language().to_string()
 }
 
-/// Macro for accessing localized strings (placeholder for Phase 1)
-/// Returns the message_id as-is until full i18n is implemented
+/// Look up a localized string, with optional `name = value` arguments
+///
+/// Message ids and argument names are checked against the English file at
+/// compile time.
 #[macro_export]
 macro_rules! fl {
-    ($message_id:literal) => {
-        $message_id
-    };
-    ($message_id:literal, $($arg:tt)*) => {
-        $message_id
-    };
+    ($message_id:literal) => {{
+        i18n_embed_fl::fl!($crate::i18n::LANGUAGE_LOADER, $message_id)
+    }};
+    ($message_id:literal, $($args:expr),*) => {{
+        i18n_embed_fl::fl!($crate::i18n::LANGUAGE_LOADER, $message_id, $($args), *)
+    }};
 }
 
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_plural_forms() {
+        assert_eq!(crate::fl!("status-replaced", count = 1), "Replaced 1 occurrence");
+        assert_eq!(crate::fl!("status-replaced", count = 2), "Replaced 2 occurrences");
+    }
+
+    #[test]
+    fn test_arguments_not_isolated() {
+        assert_eq!(crate::fl!("status-saved", path = "/tmp/a.md"), "Saved: /tmp/a.md");
+    }
+}
@@ -0,0 +1,236 @@
+//! Single-instance mode over a Unix socket
+//!
+//! The running instance listens on a socket in the user's runtime
+//! directory. A later launch connects, sends the files it was given as one
+//! absolute path per line followed by an empty line, and exits once the
+//! running instance replies. The reply is only sent once the window has
+//! taken the request, so a launch that gets no reply in time, from an
+//! instance that hangs, starts as a normal instance instead.
+
+use crate::config::APP_ID;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// How long a launch waits for the running instance to reply
+const REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Reply sent once the running instance has accepted a request
+const REPLY: &str = "ok";
+
+/// Socket the running instance listens on
+///
+/// Falls back to the temp directory, with the user name in the socket
+/// name, where there is no runtime directory.
+pub fn socket_path() -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join(format!("{}.sock", APP_ID)),
+        None => {
+            let user = std::env::var("USER").unwrap_or_default();
+            std::env::temp_dir().join(format!("{}-{}.sock", APP_ID, user))
+        }
+    }
+}
+
+/// Encode the files to open as a request
+fn encode_request(files: &[PathBuf]) -> String {
+    let mut request = String::new();
+    for file in files {
+        request.push_str(&file.to_string_lossy());
+        request.push('\n');
+    }
+    request.push('\n');
+    request
+}
+
+/// Read a request's files, up to the empty line that ends it
+fn read_request(reader: &mut impl BufRead) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        let path = line.trim_end_matches(['\n', '\r']);
+        if path.is_empty() {
+            break;
+        }
+        files.push(PathBuf::from(path));
+        line.clear();
+    }
+    Ok(files)
+}
+
+/// Ask a running instance to open `files`
+///
+/// Returns false when no instance is listening or it does not reply in
+/// time, in which case this process should start normally.
+pub fn send_to_running(socket: &Path, files: &[PathBuf]) -> bool {
+    let Ok(mut stream) = UnixStream::connect(socket) else {
+        return false;
+    };
+    if stream.set_read_timeout(Some(REPLY_TIMEOUT)).is_err()
+        || stream.set_write_timeout(Some(REPLY_TIMEOUT)).is_err()
+        || stream.write_all(encode_request(files).as_bytes()).is_err()
+    {
+        return false;
+    }
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).is_ok() && reply.trim_end() == REPLY
+}
+
+/// Files a later launch asked to open, waiting for a reply
+#[derive(Debug)]
+pub struct InstanceRequest {
+    pub files: Vec<PathBuf>,
+    stream: UnixStream,
+}
+
+impl InstanceRequest {
+    /// Tell the launch its files are taken, so it exits
+    ///
+    /// Returns false when the launch stopped waiting and started on its
+    /// own, in which case the files should not be opened here too.
+    pub fn reply(&self) -> bool {
+        (&self.stream).write_all(format!("{}\n", REPLY).as_bytes()).is_ok()
+    }
+}
+
+/// The socket of the running instance
+#[derive(Debug)]
+pub struct InstanceSocket {
+    path: PathBuf,
+    listener: UnixListener,
+    /// Set once the socket is closed, for the thread waiting in `accept`
+    closed: AtomicBool,
+}
+
+impl InstanceSocket {
+    /// Listen at `path`
+    ///
+    /// A socket file nobody accepts connections on was left behind by an
+    /// instance that crashed, and is replaced. Fails when another instance
+    /// is still listening.
+    pub fn bind(path: PathBuf) -> std::io::Result<Self> {
+        let listener = match UnixListener::bind(&path) {
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                if UnixStream::connect(&path).is_ok() {
+                    return Err(e);
+                }
+                std::fs::remove_file(&path)?;
+                UnixListener::bind(&path)?
+            }
+            result => result?,
+        };
+        Ok(Self {
+            path,
+            listener,
+            closed: AtomicBool::new(false),
+        })
+    }
+
+    /// Wait for the next launch and return its request
+    ///
+    /// Blocks until a request arrives, so call it off the UI thread.
+    /// Connections that do not complete a request are skipped. Returns
+    /// `None` once the socket is closed.
+    pub fn accept(&self) -> std::io::Result<Option<InstanceRequest>> {
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            if self.closed.load(Ordering::SeqCst) {
+                return Ok(None);
+            }
+            stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+            stream.set_write_timeout(Some(REPLY_TIMEOUT))?;
+            let Ok(files) = read_request(&mut BufReader::new(&stream)) else {
+                continue;
+            };
+            return Ok(Some(InstanceRequest { files, stream }));
+        }
+    }
+
+    /// Stop accepting and remove the socket file, so later launches start
+    /// their own instance
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        // Wake the thread waiting in `accept` so it sees the socket closed
+        let _ = UnixStream::connect(&self.path);
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            log::warn!("Failed to remove {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_round_trip() {
+        let files = vec![PathBuf::from("/tmp/a.md"), PathBuf::from("/tmp/notes b.md")];
+        let request = encode_request(&files);
+        let mut reader = BufReader::new(request.as_bytes());
+        assert_eq!(read_request(&mut reader).unwrap(), files);
+
+        let mut empty = BufReader::new("\n".as_bytes());
+        assert!(read_request(&mut empty).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_send_to_running_instance() {
+        let path = std::env::temp_dir().join(format!("cosmic-notebook-ipc-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert!(!send_to_running(&path, &[]));
+
+        let socket = InstanceSocket::bind(path.clone()).unwrap();
+        // A second instance can't take over a socket that is listened on
+        assert!(InstanceSocket::bind(path.clone()).is_err());
+
+        let files = vec![PathBuf::from("/tmp/a.md")];
+        let sender = {
+            let (path, files) = (path.clone(), files.clone());
+            std::thread::spawn(move || send_to_running(&path, &files))
+        };
+        let request = socket.accept().unwrap().unwrap();
+        assert_eq!(request.files, files);
+        assert!(request.reply());
+        assert!(sender.join().unwrap());
+
+        // A launch that got no reply starts on its own
+        let sender = {
+            let path = path.clone();
+            std::thread::spawn(move || send_to_running(&path, &[]))
+        };
+        let request = socket.accept().unwrap().unwrap();
+        assert!(!sender.join().unwrap());
+        assert!(!request.reply());
+
+        socket.close();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_close_wakes_accept() {
+        let path = std::env::temp_dir().join(format!("cosmic-notebook-close-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let socket = std::sync::Arc::new(InstanceSocket::bind(path.clone()).unwrap());
+        let waiting = {
+            let socket = socket.clone();
+            std::thread::spawn(move || socket.accept().map(|request| request.is_none()))
+        };
+
+        socket.close();
+        assert!(waiting.join().unwrap().unwrap());
+    }
+
+    #[test]
+    fn test_bind_replaces_stale_socket() {
+        let path = std::env::temp_dir().join(format!("cosmic-notebook-stale-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let socket = InstanceSocket::bind(path.clone()).unwrap();
+        socket.close();
+    }
+}
@@ -12,6 +12,7 @@ mod state;
 // Module stubs for future phases
 mod editor;
 mod file_handler;
+mod git;
 mod markdown;
 mod search;
 mod ui;
@@ -25,6 +26,10 @@ mod menu;
 
 // Internationalization
 mod i18n;
+// Single-instance mode
+mod ipc;
+// D-Bus service for other applications
+mod dbus;
 
 use app::{CosmicNotebook, Flags};
 use std::path::PathBuf;
@@ -36,11 +41,19 @@ fn main() -> cosmic::iced::Result {
     // Initialize logging
     init_logging();
 
+    // Pick the translations for the desktop language
+    i18n::init();
+
     log::info!("Starting Cosmic Notebook");
 
     // Parse command line arguments
     let flags = parse_args();
 
+    // Hand the files to a running instance in single-instance mode
+    if forward_to_running_instance(&flags) {
+        return Ok(());
+    }
+
     // Initialize and run the Cosmic application
     // Note: Don't use .size() with cosmic apps - it can cause Wayland protocol errors
     // The window size is managed by the compositor
@@ -88,14 +101,21 @@ fn parse_args() -> Flags {
                     std::process::exit(1);
                 }
             }
+            "-w" | "--watch" => {
+                flags.watch = true;
+            }
+            "-" => {
+                flags.stdin_content = Some(read_stdin());
+            }
             arg if arg.starts_with('-') => {
                 eprintln!("Unknown option: {}", arg);
                 eprintln!("Use --help for usage information");
                 std::process::exit(1);
             }
-            _ => {
-                // Treat as a file path
-                let path = PathBuf::from(&args[i]);
+            arg => {
+                // Treat as a file path; notebook:// and file:// URLs, as
+                // the desktop file passes them, name local files
+                let path = utils::path::path_from_url(arg).unwrap_or_else(|| PathBuf::from(arg));
                 if path.exists() {
                     flags.files.push(path);
                 } else {
@@ -122,6 +142,50 @@ fn parse_args() -> Flags {
     flags
 }
 
+/// Send the files to open to the running instance, in single-instance mode
+///
+/// Returns true when a running instance took them. Launches without files,
+/// with piped input or in watch mode start their own window, as does any
+/// launch the running instance doesn't answer.
+fn forward_to_running_instance(flags: &Flags) -> bool {
+    let single_instance = config::Config::load().is_ok_and(|config| config.single_instance);
+    if !single_instance || flags.files.is_empty() || flags.stdin_content.is_some() || flags.watch {
+        return false;
+    }
+
+    // The running instance has its own working directory
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let files: Vec<PathBuf> = flags.files.iter().map(|file| cwd.join(file)).collect();
+    let sent = ipc::send_to_running(&ipc::socket_path(), &files);
+    if sent {
+        log::info!("Opened {} file(s) in the running instance", files.len());
+    } else {
+        log::info!("No running instance answered; starting a new one");
+    }
+    sent
+}
+
+/// Read piped standard input for the `-` argument, exiting on failure
+fn read_stdin() -> String {
+    use std::io::IsTerminal;
+
+    if std::io::stdin().is_terminal() {
+        eprintln!("Error: '-' reads a document from a pipe, but standard input is a terminal");
+        std::process::exit(1);
+    }
+    match file_handler::read_stdin() {
+        Ok(text) => text,
+        Err(error::FileError::EncodingError { .. }) => {
+            eprintln!("Error: standard input is not text (it looks like binary data)");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: could not read standard input: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 /// Print help message
 fn print_help() {
     println!(
@@ -129,17 +193,22 @@ fn print_help() {
 
 USAGE:
     cosmic-notebook [OPTIONS] [FILES...]
+    some-command | cosmic-notebook -
 
 OPTIONS:
     -h, --help          Show this help message
     -v, --version       Show version information
     -d, --directory     Set working directory for file browser
+    -w, --watch         Reload files whenever they change on disk
+    -                   Read a document from standard input
 
 EXAMPLES:
     cosmic-notebook                     Open with empty document
     cosmic-notebook README.md           Open a specific file
     cosmic-notebook *.md                Open multiple files
     cosmic-notebook -d ~/Documents      Open with specific working directory
+    cosmic-notebook --watch notes.md    Follow edits made in another editor
+    git log | cosmic-notebook -         Open piped text as a new document
 
 KEYBOARD SHORTCUTS:
     Ctrl+N              New file
@@ -147,13 +216,24 @@ KEYBOARD SHORTCUTS:
     Ctrl+S              Save file
     Ctrl+Shift+S        Save as
     Ctrl+W              Close tab
+    Ctrl+Shift+T        Reopen closed tab
+    Ctrl+1..Ctrl+8      Go to tab 1 to 8
+    Ctrl+9              Go to last tab
+    Ctrl+Tab            Switch to the previously active tab
     Ctrl+Q              Quit
     Ctrl+Z              Undo
     Ctrl+Y              Redo
     Ctrl+F              Find
     Ctrl+H              Find and replace
+    Ctrl+F2             Toggle bookmark
+    F2 / Shift+F2       Next / previous bookmark
+    Ctrl+Shift+V        Paste as plain text
+    Ctrl+Alt+V          Paste from history (press again for older)
     Ctrl+Shift+P        Command palette
-    Ctrl+B              Toggle sidebar
+    Ctrl+B              Bold
+    Ctrl+I              Italic
+    Ctrl+Shift+B        Toggle sidebar
+    Ctrl+\              Split editor into two panes
 "#
     );
 }
@@ -0,0 +1,234 @@
+//! Callouts (admonitions)
+//!
+//! A blockquote whose first line is a `[!TYPE]` marker is a callout, as on
+//! GitHub:
+//!
+//! ```markdown
+//! > [!NOTE]
+//! > Worth knowing.
+//! ```
+//!
+//! The preview and HTML export draw callouts as a styled box with a title.
+//! A marker of an unknown type leaves an ordinary blockquote.
+
+use pulldown_cmark::{CowStr, Event, Tag};
+
+/// Type of a callout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CalloutKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl CalloutKind {
+    /// Look up a type by the name in its marker, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "NOTE" => Some(CalloutKind::Note),
+            "TIP" => Some(CalloutKind::Tip),
+            "IMPORTANT" => Some(CalloutKind::Important),
+            "WARNING" => Some(CalloutKind::Warning),
+            "CAUTION" => Some(CalloutKind::Caution),
+            _ => None,
+        }
+    }
+
+    /// Name written in the marker
+    pub fn name(self) -> &'static str {
+        match self {
+            CalloutKind::Note => "NOTE",
+            CalloutKind::Tip => "TIP",
+            CalloutKind::Important => "IMPORTANT",
+            CalloutKind::Warning => "WARNING",
+            CalloutKind::Caution => "CAUTION",
+        }
+    }
+
+    /// Title shown at the top of the box
+    pub fn title(self) -> &'static str {
+        match self {
+            CalloutKind::Note => "Note",
+            CalloutKind::Tip => "Tip",
+            CalloutKind::Important => "Important",
+            CalloutKind::Warning => "Warning",
+            CalloutKind::Caution => "Caution",
+        }
+    }
+
+    /// CSS class of the box in HTML
+    pub fn class(self) -> String {
+        format!("callout callout-{}", self.name().to_ascii_lowercase())
+    }
+}
+
+/// Parse a callout marker at the start of a blockquote's text
+///
+/// Returns the type and the marker's length in bytes. The marker must be
+/// followed by whitespace or the end of the text.
+pub fn parse_marker(text: &str) -> Option<(CalloutKind, usize)> {
+    let rest = text.strip_prefix("[!")?;
+    let end = rest.find(']')?;
+    let kind = CalloutKind::from_name(&rest[..end])?;
+    if !rest[end + 1..].chars().next().map_or(true, char::is_whitespace) {
+        return None;
+    }
+    Some((kind, end + 3))
+}
+
+/// Quote text as a callout of a type
+///
+/// Blank lines are kept inside the callout as bare `>` lines.
+pub fn wrap(text: &str, kind: CalloutKind) -> String {
+    let mut callout = format!("> [!{}]", kind.name());
+    for line in text.lines() {
+        callout.push('\n');
+        if line.trim().is_empty() {
+            callout.push('>');
+        } else {
+            callout.push_str("> ");
+            callout.push_str(line);
+        }
+    }
+    callout
+}
+
+/// Find the marker opening a blockquote, from the events after its start
+///
+/// pulldown-cmark splits the bracketed marker into several text events.
+/// Returns the type, how many events the marker ends in, and the text of
+/// the last of them left after the marker.
+fn find_marker(events: &[Event<'_>]) -> Option<(CalloutKind, usize, String)> {
+    if !matches!(events.first(), Some(Event::Start(Tag::Paragraph))) {
+        return None;
+    }
+    let texts: Vec<&str> = events[1..]
+        .iter()
+        .map_while(|event| match event {
+            Event::Text(text) => Some(text.as_ref()),
+            _ => None,
+        })
+        .collect();
+    let (kind, len) = parse_marker(&texts.concat())?;
+
+    let mut consumed = 0;
+    for (count, text) in texts.iter().enumerate() {
+        if consumed + text.len() >= len {
+            let rest = text[len - consumed..].trim_start().to_string();
+            return Some((kind, count + 2, rest));
+        }
+        consumed += text.len();
+    }
+    None
+}
+
+/// Render callouts as HTML boxes, leaving other blockquotes as they are
+///
+/// The marker line becomes the box's title.
+pub fn html_events<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let events: Vec<Event<'a>> = events.collect();
+    let mut output = Vec::with_capacity(events.len());
+    // Whether each open blockquote is a callout
+    let mut open = Vec::new();
+    let mut i = 0;
+
+    while i < events.len() {
+        match &events[i] {
+            Event::Start(Tag::BlockQuote) => {
+                let Some((kind, len, rest)) = find_marker(&events[i + 1..]) else {
+                    open.push(false);
+                    output.push(events[i].clone());
+                    i += 1;
+                    continue;
+                };
+                open.push(true);
+                output.push(html(format!(
+                    "<div class=\"{}\">\n<p class=\"callout-title\">{}</p>\n",
+                    kind.class(),
+                    kind.title()
+                )));
+                i += 1 + len;
+
+                // Text after the marker carries on the first paragraph
+                if rest.is_empty() {
+                    if matches!(events.get(i), Some(Event::SoftBreak | Event::HardBreak)) {
+                        i += 1;
+                    }
+                    if matches!(events.get(i), Some(Event::End(Tag::Paragraph))) {
+                        i += 1;
+                        continue;
+                    }
+                }
+                output.push(Event::Start(Tag::Paragraph));
+                if !rest.is_empty() {
+                    output.push(Event::Text(CowStr::from(rest)));
+                }
+            }
+            Event::End(Tag::BlockQuote) => {
+                if open.pop() == Some(true) {
+                    output.push(html("</div>\n".to_string()));
+                } else {
+                    output.push(events[i].clone());
+                }
+                i += 1;
+            }
+            event => {
+                output.push(event.clone());
+                i += 1;
+            }
+        }
+    }
+    output
+}
+
+fn html<'a>(html: String) -> Event<'a> {
+    Event::Html(CowStr::from(html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html::push_html, Parser};
+
+    fn render(markdown: &str) -> String {
+        let mut output = String::new();
+        push_html(&mut output, html_events(Parser::new(markdown)).into_iter());
+        output
+    }
+
+    #[test]
+    fn test_parse_marker() {
+        assert_eq!(parse_marker("[!NOTE]"), Some((CalloutKind::Note, 7)));
+        assert_eq!(parse_marker("[!warning] text"), Some((CalloutKind::Warning, 10)));
+        assert_eq!(parse_marker("[!UNKNOWN]"), None);
+        assert_eq!(parse_marker("[!NOTE]x"), None);
+        assert_eq!(parse_marker("[NOTE]"), None);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("one\n\ntwo", CalloutKind::Tip), "> [!TIP]\n> one\n>\n> two");
+    }
+
+    #[test]
+    fn test_html_callout() {
+        let html = render("> [!WARNING]\n> Careful.\n");
+        assert!(html.contains("<div class=\"callout callout-warning\">"));
+        assert!(html.contains("<p class=\"callout-title\">Warning</p>"));
+        assert!(html.contains("<p>Careful.</p>"));
+        assert!(!html.contains("[!WARNING]"));
+        assert!(!html.contains("<blockquote>"));
+
+        // Unknown types stay blockquotes
+        let html = render("> [!FOO]\n> Text.\n");
+        assert!(html.contains("<blockquote>"));
+        assert!(html.contains("[!FOO]"));
+
+        // A callout nested in a blockquote closes before its parent
+        let html = render("> outer\n>\n> > [!NOTE]\n> > inner\n");
+        let div = html.find("</div>").unwrap();
+        assert!(div < html.find("</blockquote>").unwrap());
+    }
+}
@@ -0,0 +1,281 @@
+//! Diagram rendering for fenced code blocks
+//!
+//! This module recognizes diagram languages in fenced code blocks and renders them:
+//! - Native preview: via the external `mmdc` (mermaid-cli) tool when installed
+//! - HTML export: via the mermaid JS library loaded by the page
+
+use std::ops::Range;
+use std::path::PathBuf;
+use std::process::Command;
+use pulldown_cmark::{Event, Tag, CodeBlockKind, CowStr, Options, Parser};
+
+/// Mermaid script included in exported HTML
+const MERMAID_SCRIPT_URL: &str = "https://cdn.jsdelivr.net/npm/mermaid@10/dist/mermaid.min.js";
+
+/// Diagram languages recognized in fenced code blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramKind {
+    Mermaid,
+}
+
+impl DiagramKind {
+    /// Detect a diagram kind from a fenced code block info string
+    pub fn from_language(info: &str) -> Option<Self> {
+        let lang = info.split_whitespace().next().unwrap_or("");
+        match lang.to_lowercase().as_str() {
+            "mermaid" => Some(DiagramKind::Mermaid),
+            _ => None,
+        }
+    }
+
+    /// Get the fenced code block language for the kind
+    pub fn language(&self) -> &'static str {
+        match self {
+            DiagramKind::Mermaid => "mermaid",
+        }
+    }
+
+    /// Get the HTML block used to render the diagram in exported documents
+    pub fn html_block(&self, source: &str) -> String {
+        match self {
+            DiagramKind::Mermaid => format!(
+                "<pre class=\"mermaid\">{}</pre>\n",
+                escape_html(source)
+            ),
+        }
+    }
+
+    /// Get the scripts that render this kind of diagram in exported documents
+    pub fn html_scripts(&self, dark_mode: bool) -> String {
+        match self {
+            DiagramKind::Mermaid => format!(
+                r#"<script src="{}"></script>
+    <script>mermaid.initialize({{ startOnLoad: true, theme: "{}" }});</script>"#,
+                MERMAID_SCRIPT_URL,
+                if dark_mode { "dark" } else { "default" }
+            ),
+        }
+    }
+}
+
+/// Result of rendering a diagram for the native preview
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagramOutput {
+    /// Diagram rendered to an SVG file
+    Svg(PathBuf),
+    /// No renderer available; show the source as a code block
+    Source,
+    /// The renderer rejected the diagram; show the error text
+    Error(String),
+}
+
+/// Renders diagrams to SVG files using external tools
+pub struct DiagramRenderer {
+    /// Directory where rendered diagrams are cached
+    output_dir: PathBuf,
+}
+
+impl DiagramRenderer {
+    /// Create a renderer caching output in the application cache directory
+    pub fn new() -> Self {
+        let output_dir = crate::config::Config::cache_dir()
+            .map(|p| p.join("diagrams"))
+            .unwrap_or_else(|_| std::env::temp_dir().join("cosmic-notebook-diagrams"));
+
+        Self { output_dir }
+    }
+
+    /// Create a renderer caching output in the given directory
+    pub fn with_output_dir(output_dir: impl Into<PathBuf>) -> Self {
+        Self { output_dir: output_dir.into() }
+    }
+
+    /// Render a diagram, falling back to the source when no renderer is installed
+    pub fn render(&self, kind: DiagramKind, source: &str) -> DiagramOutput {
+        match kind {
+            DiagramKind::Mermaid => self.render_mermaid(source),
+        }
+    }
+
+    /// Get the path a diagram's SVG render is cached at
+    pub fn svg_path(&self, kind: DiagramKind, source: &str) -> PathBuf {
+        let hash = simple_hash(source);
+        self.output_dir.join(format!("{:016x}.{}.svg", hash, kind.language()))
+    }
+
+    /// Render a mermaid diagram with `mmdc`
+    fn render_mermaid(&self, source: &str) -> DiagramOutput {
+        let output_path = self.svg_path(DiagramKind::Mermaid, source);
+        let input_path = output_path.with_extension("mmd");
+        // Rendered under another name and moved into place once complete,
+        // so a failed or interrupted run never leaves a cached SVG
+        let partial_path = output_path.with_extension(format!("{}.svg", std::process::id()));
+
+        // Reuse a previous render of identical source
+        if output_path.exists() {
+            return DiagramOutput::Svg(output_path);
+        }
+
+        if let Err(e) = std::fs::create_dir_all(&self.output_dir) {
+            return DiagramOutput::Error(e.to_string());
+        }
+        if let Err(e) = std::fs::write(&input_path, source) {
+            return DiagramOutput::Error(e.to_string());
+        }
+
+        let result = Command::new("mmdc")
+            .arg("--quiet")
+            .arg("-i")
+            .arg(&input_path)
+            .arg("-o")
+            .arg(&partial_path)
+            .output();
+
+        let _ = std::fs::remove_file(&input_path);
+        let rendered = matches!(&result, Ok(output) if output.status.success())
+            && std::fs::rename(&partial_path, &output_path).is_ok();
+        if !rendered {
+            let _ = std::fs::remove_file(&partial_path);
+        }
+
+        match result {
+            Ok(_) if rendered => DiagramOutput::Svg(output_path),
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                if stderr.is_empty() {
+                    DiagramOutput::Error(format!("mmdc failed ({})", output.status))
+                } else {
+                    DiagramOutput::Error(stderr)
+                }
+            }
+            // mmdc is not installed
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => DiagramOutput::Source,
+            Err(e) => DiagramOutput::Error(e.to_string()),
+        }
+    }
+}
+
+impl Default for DiagramRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Find the diagram code blocks in Markdown, as byte ranges of the source
+///
+/// The blocks come in the same order as the preview's diagram elements.
+pub fn diagram_ranges(markdown: &str) -> Vec<Range<usize>> {
+    Parser::new_ext(markdown, Options::all())
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))
+                if DiagramKind::from_language(&info).is_some() =>
+            {
+                Some(range)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Render markdown events to HTML, turning diagram code blocks into diagram blocks
+///
+/// Returns the diagram kinds found so the caller can include their scripts.
+pub fn push_html<'a>(output: &mut String, events: impl Iterator<Item = Event<'a>>) -> Vec<DiagramKind> {
+    let mut rewritten = Vec::new();
+    let mut found = Vec::new();
+    let mut current: Option<(DiagramKind, String)> = None;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info)))
+                if DiagramKind::from_language(info).is_some() =>
+            {
+                current = DiagramKind::from_language(info).map(|kind| (kind, String::new()));
+            }
+            Event::Text(text) if current.is_some() => {
+                if let Some((_, source)) = current.as_mut() {
+                    source.push_str(&text);
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) if current.is_some() => {
+                if let Some((kind, source)) = current.take() {
+                    rewritten.push(Event::Html(CowStr::from(kind.html_block(&source))));
+                    if !found.contains(&kind) {
+                        found.push(kind);
+                    }
+                }
+            }
+            other => rewritten.push(other),
+        }
+    }
+
+    pulldown_cmark::html::push_html(output, rewritten.into_iter());
+    found
+}
+
+/// Build the script tags for the given diagram kinds
+pub fn html_scripts(kinds: &[DiagramKind], dark_mode: bool) -> String {
+    kinds
+        .iter()
+        .map(|kind| kind.html_scripts(dark_mode))
+        .collect::<Vec<_>>()
+        .join("\n    ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Simple hash function for cache file names
+fn simple_hash(content: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    use std::collections::hash_map::DefaultHasher;
+
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagram_kind_from_language() {
+        assert_eq!(DiagramKind::from_language("mermaid"), Some(DiagramKind::Mermaid));
+        assert_eq!(DiagramKind::from_language("Mermaid title"), Some(DiagramKind::Mermaid));
+        assert_eq!(DiagramKind::from_language("rust"), None);
+        assert_eq!(DiagramKind::from_language(""), None);
+    }
+
+    #[test]
+    fn test_push_html_rewrites_diagrams() {
+        let markdown = "```mermaid\ngraph TD; A-->B;\n```\n\n```rust\nfn main() {}\n```\n";
+        let mut html = String::new();
+        let kinds = push_html(&mut html, Parser::new(markdown));
+
+        assert_eq!(kinds, vec![DiagramKind::Mermaid]);
+        assert!(html.contains("<pre class=\"mermaid\">graph TD; A--&gt;B;\n</pre>"));
+        assert!(html.contains("language-rust"));
+    }
+
+    #[test]
+    fn test_diagram_ranges() {
+        let markdown = "# Title\n\n```mermaid\ngraph TD;\n```\n\n```rust\nfn main() {}\n```\n";
+        let ranges = diagram_ranges(markdown);
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(markdown[ranges[0].clone()].trim_end(), "```mermaid\ngraph TD;\n```");
+    }
+
+    #[test]
+    fn test_push_html_without_diagrams() {
+        let mut html = String::new();
+        let kinds = push_html(&mut html, Parser::new("# Title"));
+
+        assert!(kinds.is_empty());
+        assert!(html_scripts(&kinds, false).is_empty());
+    }
+}
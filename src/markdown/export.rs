@@ -2,13 +2,20 @@
 //!
 //! This module provides export capabilities for markdown documents:
 //! - HTML export with embedded styles
+//! - EPUB export, one chapter per H1
+//! - Plain text export
 //! - Future: PDF export
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::io::Write;
 use thiserror::Error;
-use pulldown_cmark::{Parser, Options};
+use pulldown_cmark::{CowStr, Event, Parser, Options, Tag};
+use super::preview::{css_font_family, css_font_size, line_break_event, parser_options};
+use super::wiki_link::NoteIndex;
+use crate::config::{ExportConfig, ExportConflict, MarkdownOptions, DEFAULT_EXPORT_TEMPLATE};
+use crate::error::FileError;
 
 /// Errors that can occur during export
 #[derive(Debug, Error)]
@@ -21,15 +28,26 @@ pub enum ExportError {
     
     #[error("Export format not supported: {0}")]
     UnsupportedFormat(String),
+
+    #[error("Could not write archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error(transparent)]
+    File(#[from] FileError),
 }
 
 /// Result type for export operations
 pub type ExportResult<T> = Result<T, ExportError>;
 
+/// Largest custom CSS file read for export (1 MB)
+pub const MAX_CUSTOM_CSS_SIZE: u64 = 1024 * 1024;
+
 /// Export format options
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExportFormat {
     Html,
+    Epub,
+    PlainText,
     // Future: Pdf,
 }
 
@@ -38,6 +56,8 @@ impl ExportFormat {
     pub fn extension(&self) -> &'static str {
         match self {
             ExportFormat::Html => "html",
+            ExportFormat::Epub => "epub",
+            ExportFormat::PlainText => "txt",
         }
     }
     
@@ -45,6 +65,8 @@ impl ExportFormat {
     pub fn display_name(&self) -> &'static str {
         match self {
             ExportFormat::Html => "HTML",
+            ExportFormat::Epub => "EPUB",
+            ExportFormat::PlainText => "Plain Text",
         }
     }
 }
@@ -56,14 +78,22 @@ pub struct HtmlExportOptions {
     pub include_styles: bool,
     /// Embed images as base64 data URIs
     pub embed_images: bool,
+    /// Directory that relative image paths resolve against
+    pub base_path: Option<PathBuf>,
     /// Document title
     pub title: Option<String>,
     /// Use dark mode styles
     pub dark_mode: bool,
     /// Custom CSS to include
     pub custom_css: Option<String>,
+    /// CSS file to include after the default styles
+    pub custom_css_path: Option<PathBuf>,
     /// Include table of contents
     pub include_toc: bool,
+    /// Body font family (empty = system default)
+    pub font_family: String,
+    /// Base font size in pixels
+    pub font_size: f32,
 }
 
 impl Default for HtmlExportOptions {
@@ -71,47 +101,302 @@ impl Default for HtmlExportOptions {
         Self {
             include_styles: true,
             embed_images: false,
+            base_path: None,
             title: None,
             dark_mode: false,
             custom_css: None,
+            custom_css_path: None,
             include_toc: false,
+            font_family: String::new(),
+            font_size: crate::config::DEFAULT_PREVIEW_FONT_SIZE,
+        }
+    }
+}
+
+impl HtmlExportOptions {
+    /// Options from the user's preview font and export settings
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        Self {
+            font_family: config.editor.preview_font_family.clone(),
+            font_size: config.editor.preview_font_size,
+            custom_css_path: config.export.custom_css_path.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Fill in the `{stem}`, `{date}` and `{ext}` placeholders of a file name template
+///
+/// An empty template or one that renders to nothing falls back to the default.
+fn render_filename_template(template: &str, stem: &str, ext: &str, date: &str) -> String {
+    let render = |template: &str| {
+        template
+            .replace("{stem}", stem)
+            .replace("{date}", date)
+            .replace("{ext}", ext)
+    };
+    let name = render(template);
+    if name.trim().is_empty() {
+        render(DEFAULT_EXPORT_TEMPLATE)
+    } else {
+        name
+    }
+}
+
+/// First of `path`, `name-1.ext`, `name-2.ext`, ... that does not exist
+fn numbered_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().into_owned());
+
+    (1..)
+        .map(|n| {
+            let name = match &extension {
+                Some(ext) => format!("{}-{}.{}", stem, n, ext),
+                None => format!("{}-{}", stem, n),
+            };
+            path.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded range")
+}
+
+/// Read a custom CSS file for export
+///
+/// A missing, unreadable or oversized file is skipped with a warning so
+/// the export still succeeds with the default styles.
+fn load_custom_css(path: &Path) -> Option<String> {
+    let path = crate::utils::path::expand_tilde(path);
+    let result = fs::metadata(&path).and_then(|metadata| {
+        if metadata.len() > MAX_CUSTOM_CSS_SIZE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("larger than {} bytes", MAX_CUSTOM_CSS_SIZE),
+            ));
+        }
+        fs::read_to_string(&path)
+    });
+
+    match result {
+        Ok(css) => Some(css),
+        Err(e) => {
+            log::warn!("Skipping custom CSS {}: {}", path.display(), e);
+            None
         }
     }
 }
 
+/// A heading and the anchor an export gives it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    pub level: usize,
+    pub text: String,
+    pub anchor: String,
+}
+
+/// Anchors handed out to headings, in document order
+///
+/// A repeated heading gets `-1`, `-2`, ... appended, as on GitHub, so every
+/// anchor in a document is unique.
+#[derive(Debug, Default)]
+pub struct AnchorSet {
+    seen: HashMap<String, usize>,
+}
+
+impl AnchorSet {
+    /// Anchor for the next heading with this text
+    pub fn unique(&mut self, text: &str) -> String {
+        let anchor = MarkdownExporter::generate_anchor(text);
+        let seen = self.seen.entry(anchor.clone()).or_insert(0);
+        *seen += 1;
+        if *seen > 1 {
+            format!("{}-{}", anchor, *seen - 1)
+        } else {
+            anchor
+        }
+    }
+}
+
+/// Plain text of a heading's inline events, which its anchor is made from
+fn heading_text(events: &[Event]) -> String {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Text(t) | Event::Code(t) => Some(t.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Give each heading an `id` with its anchor
+///
+/// Returns the events and the headings in document order.
+pub fn heading_ids<'a>(events: impl Iterator<Item = Event<'a>>) -> (Vec<Event<'a>>, Vec<Heading>) {
+    let mut output = Vec::new();
+    let mut headings = Vec::new();
+    let mut anchors = AnchorSet::default();
+    // Open heading: level, its events so far
+    let mut heading: Option<(usize, Vec<Event<'a>>)> = None;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading(level, _, _)) => {
+                heading = Some((level as usize, Vec::new()));
+            }
+            Event::End(Tag::Heading(..)) => {
+                let Some((level, inner)) = heading.take() else {
+                    continue;
+                };
+                let text = heading_text(&inner);
+                let anchor = anchors.unique(&text);
+                output.push(Event::Html(CowStr::from(format!("<h{} id=\"{}\">", level, anchor))));
+                output.extend(inner);
+                output.push(Event::Html(CowStr::from(format!("</h{}>\n", level))));
+                headings.push(Heading { level, text, anchor });
+            }
+            event => match heading.as_mut() {
+                Some((_, inner)) => inner.push(event),
+                None => output.push(event),
+            },
+        }
+    }
+    (output, headings)
+}
+
 /// Main exporter for markdown documents
 pub struct MarkdownExporter {
     options: Options,
+    /// List footnotes nothing references in HTML
+    keep_unreferenced_footnotes: bool,
+    /// Pass raw HTML through the allow-list sanitizer in HTML and EPUB
+    sanitize_html: bool,
+    /// Render `[[wiki links]]` in HTML and EPUB
+    wiki_links: bool,
+    /// Notes wiki links resolve against
+    notes: NoteIndex,
+    /// Render soft breaks as line breaks in HTML and EPUB
+    soft_break_as_newline: bool,
 }
 
 impl MarkdownExporter {
     /// Create a new exporter
     pub fn new() -> Self {
-        let mut options = Options::empty();
-        options.insert(Options::ENABLE_TABLES);
-        options.insert(Options::ENABLE_FOOTNOTES);
-        options.insert(Options::ENABLE_STRIKETHROUGH);
-        options.insert(Options::ENABLE_TASKLISTS);
-        options.insert(Options::ENABLE_SMART_PUNCTUATION);
-        
-        Self { options }
+        Self::with_options(&MarkdownOptions::default())
+    }
+    
+    /// Create an exporter with the given markdown extensions
+    pub fn with_options(markdown: &MarkdownOptions) -> Self {
+        Self {
+            options: parser_options(markdown),
+            keep_unreferenced_footnotes: markdown.keep_unreferenced_footnotes,
+            sanitize_html: markdown.sanitize_html,
+            wiki_links: markdown.wiki_links,
+            notes: NoteIndex::default(),
+            soft_break_as_newline: false,
+        }
+    }
+    
+    /// Render single newlines within a paragraph as line breaks
+    pub fn with_soft_break_as_newline(mut self, enabled: bool) -> Self {
+        self.soft_break_as_newline = enabled;
+        self
+    }
+    
+    /// Resolve wiki links against the notes of the open folder
+    pub fn with_notes(mut self, notes: NoteIndex) -> Self {
+        self.notes = notes;
+        self
+    }
+    
+    /// Parse markdown for HTML output of a document in `dir`
+    ///
+    /// Raw HTML is sanitized and wiki links rendered when enabled.
+    fn html_events<'a>(&self, source: &'a str, dir: Option<&Path>) -> Vec<Event<'a>> {
+        let events = Parser::new_ext(source, self.options)
+            .flat_map(super::math::split_placeholders)
+            .map(|event| line_break_event(event, self.soft_break_as_newline));
+        let events = if self.sanitize_html {
+            super::sanitize::html_events(events)
+        } else {
+            events.collect()
+        };
+        if self.wiki_links {
+            super::wiki_link::html_events(events.into_iter(), &self.notes, dir)
+        } else {
+            events
+        }
+    }
+    
+    /// Render markdown to the HTML of a document body
+    ///
+    /// Returns the body, its headings and the scripts its diagrams and math
+    /// need.
+    fn render_body(&self, markdown: &str, options: &HtmlExportOptions) -> (String, Vec<Heading>, String) {
+        let (source, math_spans) = super::math::extract(&super::image::normalize_size_hints(markdown));
+        let parser = self
+            .html_events(&source, options.base_path.as_deref())
+            .into_iter()
+            .map(|event| super::math::html_event(event, &math_spans));
+        let parser = super::footnote::html_events(parser, self.keep_unreferenced_footnotes).into_iter();
+        let parser = super::callout::html_events(parser).into_iter();
+        let mut html_content = String::new();
+        let events = if options.embed_images {
+            super::image::embed_images(parser, options.base_path.as_deref())
+        } else {
+            parser.collect()
+        };
+        let events = super::image::sized_image_events(events.into_iter());
+        let (events, headings) = heading_ids(events.into_iter());
+        let diagrams = super::diagram::push_html(&mut html_content, events.into_iter());
+        let mut scripts = super::diagram::html_scripts(&diagrams, options.dark_mode);
+        if !math_spans.is_empty() {
+            scripts.push_str(&super::math::html_scripts());
+        }
+        (html_content, headings, scripts)
+    }
+    
+    /// Export markdown to an HTML fragment, without the page around it
+    ///
+    /// Styles and scripts are left out so the fragment pastes cleanly into
+    /// other documents; the table of contents is kept when asked for.
+    pub fn export_html_fragment(&self, markdown: &str, options: &HtmlExportOptions) -> String {
+        let (html_content, headings, _) = self.render_body(markdown, options);
+        if options.include_toc {
+            format!("{}{}", Self::toc_html(&headings), html_content)
+        } else {
+            html_content
+        }
     }
     
     /// Export markdown to HTML string
     pub fn export_html(&self, markdown: &str, options: &HtmlExportOptions) -> String {
-        let parser = Parser::new_ext(markdown, self.options);
-        let mut html_content = String::new();
-        pulldown_cmark::html::push_html(&mut html_content, parser);
+        let (html_content, headings, scripts) = self.render_body(markdown, options);
         
         let title = options.title.as_deref().unwrap_or("Document");
         let styles = if options.include_styles {
-            Self::get_styles(options.dark_mode, options.custom_css.as_deref())
+            // Inline CSS first, then the file, so the file has the last word
+            let file_css = options.custom_css_path.as_deref().and_then(load_custom_css);
+            let custom_css = match (options.custom_css.as_deref(), file_css) {
+                (Some(inline), Some(file)) => Some(format!("{}\n{}", inline, file)),
+                (inline, file) => file.or(inline.map(String::from)),
+            };
+            Self::get_styles(
+                options.dark_mode,
+                custom_css.as_deref(),
+                &options.font_family,
+                options.font_size,
+            )
         } else {
             String::new()
         };
         
         let toc = if options.include_toc {
-            Self::generate_toc(markdown)
+            Self::toc_html(&headings)
         } else {
             String::new()
         };
@@ -131,12 +416,14 @@ impl MarkdownExporter {
         {}
         {}
     </article>
+    {}
 </body>
 </html>"#,
             Self::escape_html(title),
             styles,
             toc,
-            html_content
+            html_content,
+            scripts
         )
     }
     
@@ -153,6 +440,224 @@ impl MarkdownExporter {
         Ok(())
     }
     
+    /// Export markdown to an EPUB file
+    ///
+    /// Each H1 starts a new chapter, and the navigation lists every heading.
+    pub fn export_epub_file(
+        &self,
+        markdown: &str,
+        output_path: &Path,
+        options: &EpubExportOptions,
+    ) -> ExportResult<()> {
+        let book = self.build_epub(markdown, options);
+        let file = fs::File::create(output_path)?;
+        book.write_zip(file)
+    }
+
+    /// Lay out the files of an EPUB container
+    fn build_epub(&self, markdown: &str, options: &EpubExportOptions) -> EpubBook {
+        let (front, body) = split_front_matter(markdown);
+        let title = front
+            .title
+            .or_else(|| options.title.clone())
+            .unwrap_or_else(|| "Document".to_string());
+        let author = front.author.or_else(|| options.author.clone());
+        let language = options.language.clone().unwrap_or_else(|| "en".to_string());
+
+        let source = super::image::normalize_size_hints(body);
+        let (source, math_spans) = super::math::extract(&source);
+        let parser = self
+            .html_events(&source, options.base_path.as_deref())
+            .into_iter()
+            .map(|event| super::math::html_event(event, &math_spans));
+
+        let mut chapters: Vec<Vec<Event>> = vec![Vec::new()];
+        // First heading of each chapter, used as its title
+        let mut chapter_titles: Vec<Option<String>> = vec![None];
+        let mut nav = Vec::new();
+        let mut anchors = AnchorSet::default();
+        let mut images: Vec<(PathBuf, String)> = Vec::new();
+        // Open heading: level, its events so far
+        let mut heading: Option<(usize, Vec<Event>)> = None;
+
+        for event in parser {
+            match event {
+                Event::Start(Tag::Heading(level, _, _)) => {
+                    let level = level as usize;
+                    let current = chapters.last().map_or(true, Vec::is_empty);
+                    if level == 1 && !current {
+                        chapters.push(Vec::new());
+                        chapter_titles.push(None);
+                    }
+                    heading = Some((level, Vec::new()));
+                }
+                Event::End(Tag::Heading(..)) => {
+                    let Some((level, inner)) = heading.take() else {
+                        continue;
+                    };
+                    let text = heading_text(&inner);
+                    let anchor = anchors.unique(&text);
+
+                    let chapter = chapters.len();
+                    chapter_titles[chapter - 1].get_or_insert_with(|| text.clone());
+                    nav.push((level, text, format!("chapter-{}.xhtml#{}", chapter, anchor)));
+                    let events = chapters.last_mut().expect("at least one chapter");
+                    events.push(Event::Html(CowStr::from(format!("<h{} id=\"{}\">", level, anchor))));
+                    events.extend(inner);
+                    events.push(Event::Html(CowStr::from(format!("</h{}>\n", level))));
+                }
+                Event::Start(Tag::Image(link_type, url, image_title)) => {
+                    let url = match epub_image(&url, options.base_path.as_deref(), &mut images) {
+                        Some(href) => CowStr::from(href),
+                        None => url,
+                    };
+                    let event = Event::Start(Tag::Image(link_type, url, image_title));
+                    match heading.as_mut() {
+                        Some((_, inner)) => inner.push(event),
+                        None => chapters.last_mut().expect("at least one chapter").push(event),
+                    }
+                }
+                other => match heading.as_mut() {
+                    Some((_, inner)) => inner.push(other),
+                    None => chapters.last_mut().expect("at least one chapter").push(other),
+                },
+            }
+        }
+
+        // A document without headings still needs a navigation entry
+        if nav.is_empty() {
+            nav.push((1, title.clone(), "chapter-1.xhtml".to_string()));
+        }
+
+        let mut book = EpubBook::default();
+        book.add("META-INF/container.xml", EPUB_CONTAINER.as_bytes().to_vec());
+
+        let mut manifest = String::from(
+            "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+        );
+        let mut spine = String::new();
+        for (index, (events, chapter_title)) in chapters.into_iter().zip(chapter_titles).enumerate() {
+            let number = index + 1;
+            let chapter_title = chapter_title.as_deref().unwrap_or(&title);
+            let mut html = String::new();
+            let events = super::image::sized_image_events(events.into_iter());
+            pulldown_cmark::html::push_html(&mut html, events.into_iter());
+
+            book.add(
+                &format!("OEBPS/chapter-{}.xhtml", number),
+                xhtml_document(&language, chapter_title, &html).into_bytes(),
+            );
+            manifest.push_str(&format!(
+                "    <item id=\"chapter-{0}\" href=\"chapter-{0}.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+                number
+            ));
+            spine.push_str(&format!("    <itemref idref=\"chapter-{}\"/>\n", number));
+        }
+
+        for (index, (path, href)) in images.iter().enumerate() {
+            let Ok(data) = fs::read(path) else {
+                continue;
+            };
+            let media_type = super::image::ImageFormat::from_bytes(&data)
+                .or_else(|| {
+                    path.extension()
+                        .and_then(|e| e.to_str())
+                        .and_then(super::image::ImageFormat::from_extension)
+                })
+                .map_or("application/octet-stream", |format| format.mime_type());
+            manifest.push_str(&format!(
+                "    <item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>\n",
+                index + 1,
+                href,
+                media_type
+            ));
+            book.add(&format!("OEBPS/{}", href), data);
+        }
+
+        let nav_html = format!(
+            "<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n{}</nav>\n",
+            nav_list(&nav)
+        );
+        book.add(
+            "OEBPS/nav.xhtml",
+            xhtml_document(&language, "Contents", &nav_html).into_bytes(),
+        );
+
+        let creator = author
+            .map(|a| format!("    <dc:creator>{}</dc:creator>\n", Self::escape_html(&a)))
+            .unwrap_or_default();
+        let package = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">urn:uuid:{}</dc:identifier>
+    <dc:title>{}</dc:title>
+    <dc:language>{}</dc:language>
+{}    <meta property="dcterms:modified">{}</meta>
+  </metadata>
+  <manifest>
+{}  </manifest>
+  <spine>
+{}  </spine>
+</package>
+"#,
+            uuid::Uuid::new_v4(),
+            Self::escape_html(&title),
+            Self::escape_html(&language),
+            creator,
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+            manifest,
+            spine
+        );
+        book.add("OEBPS/content.opf", package.into_bytes());
+        book
+    }
+
+    /// Export markdown to plain text
+    ///
+    /// Markup is dropped, links become "text (url)", code blocks keep their
+    /// content verbatim and tables are laid out as aligned columns.
+    pub fn export_plain(&self, markdown: &str) -> String {
+        let (source, math_spans) = super::math::extract(markdown);
+        let mut writer = PlainTextWriter::default();
+
+        for event in Parser::new_ext(&source, self.options).flat_map(super::math::split_placeholders) {
+            match event {
+                Event::Start(tag) => writer.start(tag),
+                Event::End(tag) => writer.end(tag),
+                Event::Text(text) | Event::Code(text) => writer.text(&text),
+                Event::Html(html) => {
+                    let span = super::math::placeholder_index(&html).and_then(|i| math_spans.get(i));
+                    match span {
+                        Some(span) if span.inline => writer.text(&span.source),
+                        Some(span) => {
+                            writer.start_block();
+                            writer.text(&span.source);
+                            writer.end_block();
+                        }
+                        None => {}
+                    }
+                }
+                Event::SoftBreak | Event::HardBreak => writer.text("\n"),
+                Event::Rule => {
+                    writer.start_block();
+                    writer.text("* * *");
+                    writer.end_block();
+                }
+                Event::FootnoteReference(label) => writer.text(&format!("[{}]", label)),
+                Event::TaskListMarker(checked) => {
+                    writer.text(if checked { "[x] " } else { "[ ] " })
+                }
+            }
+        }
+
+        let mut output = writer.output;
+        let trimmed = output.trim_end().len();
+        output.truncate(trimmed);
+        output.push('\n');
+        output
+    }
+
     /// Export with automatic format detection from path
     pub fn export_to_file(
         &self,
@@ -174,63 +679,97 @@ impl MarkdownExporter {
                 };
                 self.export_html_file(markdown, output_path, &options)
             }
+            "epub" => {
+                let options = EpubExportOptions {
+                    title: output_path.file_stem()
+                        .and_then(|s| s.to_str())
+                        .map(String::from),
+                    ..Default::default()
+                };
+                self.export_epub_file(markdown, output_path, &options)
+            }
+            "txt" => {
+                fs::write(output_path, self.export_plain(markdown))?;
+                Ok(())
+            }
             other => Err(ExportError::UnsupportedFormat(other.to_string())),
         }
     }
     
     /// Generate suggested output path from input path
-    pub fn suggest_output_path(input_path: &Path, format: ExportFormat) -> PathBuf {
+    ///
+    /// The name comes from the export file name template and goes in the
+    /// export directory, or next to the input when none is set. An existing
+    /// file gets a numbered alternative unless the config says to overwrite.
+    pub fn suggest_output_path(
+        input_path: &Path,
+        format: ExportFormat,
+        config: &ExportConfig,
+    ) -> PathBuf {
         let stem = input_path
             .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("document");
-        
-        let mut output = input_path.parent()
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let name = render_filename_template(&config.filename_template, stem, format.extension(), &date);
+
+        let source_dir = input_path.parent()
             .map(|p| p.to_path_buf())
             .unwrap_or_default();
-        
-        output.push(format!("{}.{}", stem, format.extension()));
-        output
+        let dir = match config.output_dir.as_deref() {
+            Some(dir) => {
+                let dir = crate::utils::path::expand_tilde(dir);
+                if dir.is_relative() {
+                    source_dir.join(dir)
+                } else {
+                    dir
+                }
+            }
+            None => source_dir,
+        };
+
+        let output = dir.join(name);
+        match config.on_conflict {
+            ExportConflict::Overwrite => output,
+            ExportConflict::AppendNumber => numbered_path(output),
+        }
+    }
+
+    /// Pick the output path and create its directory if missing
+    pub async fn prepare_output_path(
+        input_path: &Path,
+        format: ExportFormat,
+        config: &ExportConfig,
+    ) -> ExportResult<PathBuf> {
+        let output = Self::suggest_output_path(input_path, format, config);
+        crate::file_handler::ensure_parent_dir(&output).await?;
+        Ok(output)
     }
     
-    /// Generate a table of contents from markdown
-    fn generate_toc(markdown: &str) -> String {
+    /// Lay out a table of contents linking to each heading's anchor
+    fn toc_html(headings: &[Heading]) -> String {
         let mut toc = String::from("<nav class=\"toc\">\n<h2>Table of Contents</h2>\n<ul>\n");
         let mut current_level = 0;
         
-        for line in markdown.lines() {
-            let trimmed = line.trim_start();
-            if !trimmed.starts_with('#') {
-                continue;
-            }
-            
-            let level = trimmed.chars().take_while(|c| *c == '#').count();
-            if level == 0 || level > 6 {
+        for heading in headings {
+            if heading.text.is_empty() {
                 continue;
             }
             
-            let title = trimmed[level..].trim_start_matches(' ').trim();
-            if title.is_empty() {
-                continue;
-            }
-            
-            // Generate anchor ID
-            let anchor = Self::generate_anchor(title);
-            
             // Handle nesting
-            while current_level < level {
+            while current_level < heading.level {
                 toc.push_str("<ul>\n");
                 current_level += 1;
             }
-            while current_level > level {
+            while current_level > heading.level {
                 toc.push_str("</ul>\n");
                 current_level -= 1;
             }
             
             toc.push_str(&format!(
                 "<li><a href=\"#{}\">{}</a></li>\n",
-                anchor,
-                Self::escape_html(title)
+                heading.anchor,
+                Self::escape_html(&heading.text)
             ));
         }
         
@@ -243,8 +782,50 @@ impl MarkdownExporter {
         toc
     }
     
+    /// Anchor of the heading on `line` (0-based), as exports give it
+    ///
+    /// Exports de-duplicate repeated headings, so this counts the headings
+    /// above. Returns `None` when the line is not part of a heading.
+    pub fn heading_anchor_at_line(&self, markdown: &str, line: usize) -> Option<String> {
+        let mut anchors = AnchorSet::default();
+        // First line of the open heading, and its events so far
+        let mut heading: Option<(usize, Vec<Event>)> = None;
+        let mut lines_before = 0;
+        let mut scanned = 0;
+        
+        for (event, range) in Parser::new_ext(markdown, self.options).into_offset_iter() {
+            match event {
+                Event::Start(Tag::Heading(..)) => {
+                    lines_before += markdown[scanned..range.start].matches('\n').count();
+                    scanned = range.start;
+                    heading = Some((lines_before, Vec::new()));
+                }
+                Event::End(Tag::Heading(..)) => {
+                    let Some((first_line, inner)) = heading.take() else {
+                        continue;
+                    };
+                    let anchor = anchors.unique(&heading_text(&inner));
+                    let last_line = first_line
+                        + markdown[range.clone()].trim_end_matches('\n').matches('\n').count();
+                    if (first_line..=last_line).contains(&line) {
+                        return Some(anchor);
+                    }
+                    if line < first_line {
+                        return None;
+                    }
+                }
+                event => {
+                    if let Some((_, inner)) = heading.as_mut() {
+                        inner.push(event);
+                    }
+                }
+            }
+        }
+        None
+    }
+    
     /// Generate URL-safe anchor from heading text
-    fn generate_anchor(text: &str) -> String {
+    pub fn generate_anchor(text: &str) -> String {
         text.to_lowercase()
             .chars()
             .map(|c| {
@@ -269,7 +850,7 @@ impl MarkdownExporter {
             .replace('\'', "&#39;")
     }
     
-    fn get_styles(dark_mode: bool, custom_css: Option<&str>) -> String {
+    fn get_styles(dark_mode: bool, custom_css: Option<&str>, font_family: &str, font_size: f32) -> String {
         let theme_styles = if dark_mode {
             r#"
             :root {
@@ -305,8 +886,8 @@ impl MarkdownExporter {
         }}
         
         body {{
-            font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif;
-            font-size: 16px;
+            font-family: {};
+            font-size: {}px;
             line-height: 1.6;
             color: var(--color-text);
             background-color: var(--color-bg);
@@ -373,6 +954,38 @@ impl MarkdownExporter {
             color: var(--color-blockquote);
             border-left: .25em solid var(--color-border);
         }}
+
+        .markdown-body .callout {{
+            margin: 16px 0;
+            padding: .5em 1em;
+            border-left: .25em solid var(--callout-color);
+            border-radius: 6px;
+        }}
+        
+        .markdown-body .callout-title {{
+            margin: 0 0 .5em;
+            font-weight: 600;
+            color: var(--callout-color);
+        }}
+        
+        .markdown-body .callout > :last-child {{ margin-bottom: 0; }}
+        .markdown-body .callout-note {{ --callout-color: #0969da; }}
+        .markdown-body .callout-tip {{ --callout-color: #1a7f37; }}
+        .markdown-body .callout-important {{ --callout-color: #8250df; }}
+        .markdown-body .callout-warning {{ --callout-color: #9a6700; }}
+        .markdown-body .callout-caution {{ --callout-color: #cf222e; }}
+        
+        .markdown-body .wiki-link-unresolved {{
+            opacity: .7;
+            text-decoration: underline dashed;
+        }}
+        
+        .markdown-body .footnotes {{
+            margin-top: 2em;
+            padding-top: 1em;
+            border-top: 1px solid var(--color-border);
+            font-size: .875em;
+        }}
         
         .markdown-body table {{
             border-collapse: collapse;
@@ -470,7 +1083,7 @@ impl MarkdownExporter {
                 page-break-after: always;
             }}
             
-            pre, blockquote {{
+            pre, blockquote, .callout {{
                 page-break-inside: avoid;
             }}
             
@@ -482,11 +1095,462 @@ impl MarkdownExporter {
         {}
     </style>"#,
             theme_styles,
+            css_font_family(font_family),
+            css_font_size(font_size),
             custom
         )
     }
 }
 
+/// A table being collected for plain-text export
+struct PlainTable {
+    alignments: Vec<pulldown_cmark::Alignment>,
+    rows: Vec<Vec<String>>,
+}
+
+impl PlainTable {
+    /// Lay the table out as columns padded to the widest cell
+    fn lines(&self) -> Vec<String> {
+        use pulldown_cmark::Alignment;
+
+        let columns = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut lines = Vec::with_capacity(self.rows.len() + 1);
+        for (index, row) in self.rows.iter().enumerate() {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(column, &width)| {
+                    let cell = row.get(column).map_or("", String::as_str);
+                    let padding = width - cell.chars().count();
+                    match self.alignments.get(column) {
+                        Some(Alignment::Right) => format!("{}{}", " ".repeat(padding), cell),
+                        Some(Alignment::Center) => format!(
+                            "{}{}{}",
+                            " ".repeat(padding / 2),
+                            cell,
+                            " ".repeat(padding - padding / 2)
+                        ),
+                        _ => format!("{}{}", cell, " ".repeat(padding)),
+                    }
+                })
+                .collect();
+            lines.push(cells.join("  ").trim_end().to_string());
+
+            // Rule under the header row
+            if index == 0 {
+                let rule: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+                lines.push(rule.join("  "));
+            }
+        }
+        lines
+    }
+}
+
+/// Plain-text renderer state for `export_plain`
+struct PlainTextWriter {
+    output: String,
+    /// Continuation indent of each open list item, quote and code block
+    indents: Vec<String>,
+    /// Marker written in place of the innermost indent on an item's first line
+    bullet: Option<String>,
+    /// Next number of each open list, `None` for bullet lists
+    lists: Vec<Option<u64>>,
+    /// Text of each open link or image, with its URL
+    links: Vec<(String, String)>,
+    /// Heading level and where its text starts in `output`
+    heading: Option<(usize, usize)>,
+    table: Option<PlainTable>,
+    at_line_start: bool,
+    blank_pending: bool,
+}
+
+impl Default for PlainTextWriter {
+    fn default() -> Self {
+        Self {
+            output: String::new(),
+            indents: Vec::new(),
+            bullet: None,
+            lists: Vec::new(),
+            links: Vec::new(),
+            heading: None,
+            table: None,
+            at_line_start: true,
+            blank_pending: false,
+        }
+    }
+}
+
+impl PlainTextWriter {
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.start_block(),
+            Tag::Table(alignments) => {
+                self.start_block();
+                self.table = Some(PlainTable { alignments, rows: Vec::new() });
+            }
+            Tag::Heading(level, _, _) => {
+                self.start_block();
+                self.heading = Some((level as usize, self.output.len()));
+            }
+            Tag::BlockQuote => {
+                self.start_block();
+                self.indents.push("> ".to_string());
+            }
+            Tag::CodeBlock(_) => {
+                self.start_block();
+                self.indents.push("    ".to_string());
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.start_block();
+                } else {
+                    self.end_line();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.end_line();
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.indents.push(" ".repeat(bullet.chars().count()));
+                self.bullet = Some(bullet);
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.start_block();
+                self.indents.push("    ".to_string());
+                self.bullet = Some(format!("[{}]: ", label));
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(row) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.push(String::new());
+                }
+            }
+            Tag::Link(_, url, _) | Tag::Image(_, url, _) => {
+                self.links.push((String::new(), url.to_string()));
+            }
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough => {}
+        }
+    }
+
+    fn end(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.end_block(),
+            Tag::Heading(..) => {
+                if let Some((level, start)) = self.heading.take() {
+                    let width = self.output[start..].chars().count();
+                    match level {
+                        1 => self.underline('=', width),
+                        2 => self.underline('-', width),
+                        _ => {}
+                    }
+                }
+                self.end_block();
+            }
+            Tag::BlockQuote | Tag::CodeBlock(_) | Tag::FootnoteDefinition(_) => {
+                self.end_line();
+                self.indents.pop();
+                self.bullet = None;
+                self.blank_pending = true;
+            }
+            Tag::List(_) => {
+                self.end_line();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_pending = true;
+                }
+            }
+            Tag::Item => {
+                self.end_line();
+                self.indents.pop();
+                self.bullet = None;
+            }
+            Tag::Table(_) => {
+                if let Some(table) = self.table.take() {
+                    for line in table.lines() {
+                        self.text(&line);
+                        self.end_line();
+                    }
+                }
+                self.end_block();
+            }
+            Tag::Link(_, url, _) | Tag::Image(_, url, _) => {
+                let Some((text, _)) = self.links.pop() else {
+                    return;
+                };
+                let bare = url.strip_prefix("mailto:").unwrap_or(&url);
+                if !url.is_empty() && !url.starts_with('#') && text != bare {
+                    self.text(&format!(" ({})", url));
+                }
+            }
+            Tag::TableHead
+            | Tag::TableRow
+            | Tag::TableCell
+            | Tag::Emphasis
+            | Tag::Strong
+            | Tag::Strikethrough => {}
+        }
+    }
+
+    /// Write inline text, indenting each new line
+    fn text(&mut self, text: &str) {
+        for (text_so_far, _) in self.links.iter_mut() {
+            text_so_far.push_str(text);
+        }
+        if let Some(cell) = self
+            .table
+            .as_mut()
+            .and_then(|t| t.rows.last_mut())
+            .and_then(|row| row.last_mut())
+        {
+            cell.push_str(&text.replace('\n', " "));
+            return;
+        }
+
+        for (index, part) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.output.push('\n');
+                self.at_line_start = true;
+            }
+            if part.is_empty() {
+                continue;
+            }
+            if self.at_line_start {
+                self.write_indent();
+            }
+            self.output.push_str(part);
+        }
+    }
+
+    fn write_indent(&mut self) {
+        let bullet = self.bullet.take();
+        let last = self.indents.len().saturating_sub(1);
+        for (index, indent) in self.indents.iter().enumerate() {
+            match &bullet {
+                Some(bullet) if index == last => self.output.push_str(bullet),
+                _ => self.output.push_str(indent),
+            }
+        }
+        self.at_line_start = false;
+    }
+
+    fn underline(&mut self, marker: char, width: usize) {
+        self.end_line();
+        self.text(&marker.to_string().repeat(width.max(1)));
+    }
+
+    /// Separate a new block from the previous one with a blank line
+    fn start_block(&mut self) {
+        self.end_line();
+        if self.blank_pending && !self.output.is_empty() {
+            let indent: String = self.indents.concat();
+            self.output.push_str(indent.trim_end());
+            self.output.push('\n');
+        }
+        self.blank_pending = false;
+    }
+
+    fn end_block(&mut self) {
+        self.end_line();
+        self.blank_pending = true;
+    }
+
+    fn end_line(&mut self) {
+        if !self.at_line_start {
+            self.output.push('\n');
+            self.at_line_start = true;
+        }
+    }
+}
+
+/// Container file pointing readers at the package document
+const EPUB_CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+/// Options for EPUB export
+#[derive(Debug, Clone, Default)]
+pub struct EpubExportOptions {
+    /// Book title when the front matter has none
+    pub title: Option<String>,
+    /// Author when the front matter has none
+    pub author: Option<String>,
+    /// Language tag (default "en")
+    pub language: Option<String>,
+    /// Directory that relative image paths resolve against
+    pub base_path: Option<PathBuf>,
+}
+
+/// Metadata from a YAML front matter block
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub author: Option<String>,
+}
+
+/// Split a leading `---` front matter block off a document
+///
+/// Only simple `key: value` lines are read; values may be quoted. Without
+/// a closed block the whole document is returned as the body.
+pub fn split_front_matter(markdown: &str) -> (FrontMatter, &str) {
+    let mut front = FrontMatter::default();
+    let Some(rest) = markdown
+        .strip_prefix("---\n")
+        .or_else(|| markdown.strip_prefix("---\r\n"))
+    else {
+        return (front, markdown);
+    };
+
+    let mut offset = markdown.len() - rest.len();
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return (front, &markdown[offset..]);
+        }
+        if let Some((key, value)) = trimmed.split_once(':') {
+            let value = value.trim().trim_matches('"').trim_matches('\'').to_string();
+            if value.is_empty() {
+                continue;
+            }
+            match key.trim() {
+                "title" => front.title = Some(value),
+                "author" => front.author = Some(value),
+                _ => {}
+            }
+        }
+    }
+
+    (FrontMatter::default(), markdown)
+}
+
+/// Files of an EPUB container, in the order they are written
+#[derive(Debug, Default)]
+struct EpubBook {
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl EpubBook {
+    fn add(&mut self, name: &str, data: Vec<u8>) {
+        self.files.push((name.to_string(), data));
+    }
+
+    /// Write the container; `mimetype` comes first and uncompressed
+    fn write_zip(&self, writer: impl std::io::Write + std::io::Seek) -> ExportResult<()> {
+        use zip::write::FileOptions;
+        use zip::CompressionMethod;
+
+        let mut zip = zip::ZipWriter::new(writer);
+        zip.start_file("mimetype", FileOptions::default().compression_method(CompressionMethod::Stored))?;
+        zip.write_all(b"application/epub+zip")?;
+
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, data) in &self.files {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(data)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+/// Copy a local image into the book, returning its path inside `OEBPS`
+fn epub_image(url: &str, base_dir: Option<&Path>, images: &mut Vec<(PathBuf, String)>) -> Option<String> {
+    if url.is_empty() || url.contains("://") || url.starts_with("data:") {
+        return None;
+    }
+    let path = Path::new(url);
+    let path = match base_dir {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path.to_path_buf(),
+    };
+    if !path.is_file() {
+        log::warn!("Image not found for EPUB export: {}", path.display());
+        return None;
+    }
+
+    if let Some((_, href)) = images.iter().find(|(p, _)| *p == path) {
+        return Some(href.clone());
+    }
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("img");
+    let href = format!("images/image-{}.{}", images.len() + 1, extension);
+    images.push((path, href.clone()));
+    Some(href)
+}
+
+/// Nested `<ol>` navigation list from (level, title, href) entries
+fn nav_list(entries: &[(usize, String, String)]) -> String {
+    let mut output = String::new();
+    let mut levels: Vec<usize> = Vec::new();
+
+    for (level, title, href) in entries {
+        while levels.last().is_some_and(|top| level < top) {
+            output.push_str("</li>\n</ol>\n");
+            levels.pop();
+        }
+        match levels.last() {
+            Some(top) if top == level => output.push_str("</li>\n"),
+            Some(_) => {
+                output.push_str("\n<ol>\n");
+                levels.push(*level);
+            }
+            None => {
+                output.push_str("<ol>\n");
+                levels.push(*level);
+            }
+        }
+        output.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>",
+            MarkdownExporter::escape_html(href),
+            MarkdownExporter::escape_html(title)
+        ));
+    }
+    for _ in levels {
+        output.push_str("</li>\n</ol>\n");
+    }
+    output
+}
+
+/// Wrap body HTML in an XHTML document for EPUB
+fn xhtml_document(language: &str, title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" lang="{0}" xml:lang="{0}">
+<head>
+  <meta charset="UTF-8"/>
+  <title>{1}</title>
+</head>
+<body>
+{2}</body>
+</html>
+"#,
+        MarkdownExporter::escape_html(language),
+        MarkdownExporter::escape_html(title),
+        body
+    )
+}
+
 impl Default for MarkdownExporter {
     fn default() -> Self {
         Self::new()
@@ -508,13 +1572,205 @@ mod tests {
         let html = exporter.export_html("# Hello\n\nWorld", &options);
         
         assert!(html.contains("<title>Test</title>"));
-        assert!(html.contains("<h1>Hello</h1>"));
+        assert!(html.contains("<h1 id=\"hello\">Hello</h1>"));
+        assert!(html.contains("<p>World</p>"));
+    }
+    
+    #[test]
+    fn test_html_fragment_export() {
+        let exporter = MarkdownExporter::new();
+        let options = HtmlExportOptions {
+            include_styles: false,
+            ..Default::default()
+        };
+        
+        let html = exporter.export_html_fragment("# Hello\n\nWorld", &options);
+        
+        assert!(html.starts_with("<h1 id=\"hello\">Hello</h1>"));
         assert!(html.contains("<p>World</p>"));
+        assert!(!html.contains("<html"));
+        assert!(!html.contains("<style"));
+    }
+    
+    #[test]
+    fn test_html_export_font_options() {
+        let exporter = MarkdownExporter::new();
+        let options = HtmlExportOptions {
+            font_family: "IBM Plex Sans".to_string(),
+            font_size: 15.0,
+            ..Default::default()
+        };
+        
+        let html = exporter.export_html("Text", &options);
+        assert!(html.contains("font-family: \"IBM Plex Sans\","));
+        assert!(html.contains("font-size: 15px;"));
     }
     
+    #[test]
+    fn test_html_export_embeds_images() {
+        let dir = std::env::temp_dir().join(format!("cosmic-notebook-embed-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // A 1x1 transparent PNG
+        let png: &[u8] = &[
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00,
+            0x00, 0x1F, 0x15, 0xC4, 0x89, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x44, 0x41, 0x54, 0x78,
+            0x9C, 0x63, 0x00, 0x01, 0x00, 0x00, 0x05, 0x00, 0x01, 0x0D, 0x0A, 0x2D, 0xB4, 0x00,
+            0x00, 0x00, 0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+        ];
+        fs::write(dir.join("dot.png"), png).unwrap();
+
+        let exporter = MarkdownExporter::new();
+        let options = HtmlExportOptions {
+            embed_images: true,
+            base_path: Some(dir.clone()),
+            ..Default::default()
+        };
+        let markdown = "![dot](dot.png =10x10) ![gone](missing.png) ![web](https://example.com/a.png)";
+        let html = exporter.export_html(markdown, &options);
+
+        assert!(html.contains(r#"<img src="data:image/png;base64,iVBORw0KGgo"#));
+        assert!(html.contains(r#"width="10""#));
+        assert!(html.contains(r#"src="missing.png""#));
+        assert!(html.contains(r#"src="https://example.com/a.png""#));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_html_export_custom_css_file() {
+        let path = std::env::temp_dir().join(format!("cosmic-notebook-house-{}.css", std::process::id()));
+        fs::write(&path, ".markdown-body { color: rebeccapurple; }").unwrap();
+
+        let exporter = MarkdownExporter::new();
+        let mut options = HtmlExportOptions {
+            custom_css: Some("p { margin: 0; }".to_string()),
+            custom_css_path: Some(path.clone()),
+            ..Default::default()
+        };
+        let html = exporter.export_html("Text", &options);
+        let inline = html.find("p { margin: 0; }").unwrap();
+        let file = html.find("color: rebeccapurple").unwrap();
+        assert!(inline < file);
+
+        // A missing file falls back to the default styles
+        let _ = fs::remove_file(&path);
+        options.custom_css = None;
+        let html = exporter.export_html("Text", &options);
+        assert!(!html.contains("rebeccapurple"));
+        assert!(html.contains("<p>Text</p>"));
+    }
+    
+    #[test]
+    fn test_split_front_matter() {
+        let (front, body) = split_front_matter("---\ntitle: \"My Book\"\nauthor: Ada\ntags: [a]\n---\n# One\n");
+        assert_eq!(front.title.as_deref(), Some("My Book"));
+        assert_eq!(front.author.as_deref(), Some("Ada"));
+        assert_eq!(body, "# One\n");
+
+        // An unclosed block is part of the document
+        let (front, body) = split_front_matter("---\ntitle: Nope\n");
+        assert_eq!(front, FrontMatter::default());
+        assert_eq!(body, "---\ntitle: Nope\n");
+    }
+
+    #[test]
+    fn test_epub_chapters() {
+        let exporter = MarkdownExporter::new();
+        let options = EpubExportOptions::default();
+
+        let book = exporter.build_epub("Just text.", &options);
+        let names: Vec<&str> = book.files.iter().map(|(n, _)| n.as_str()).collect();
+        assert!(names.contains(&"OEBPS/chapter-1.xhtml"));
+        assert!(!names.contains(&"OEBPS/chapter-2.xhtml"));
+        let nav = book.files.iter().find(|(n, _)| n == "OEBPS/nav.xhtml").unwrap();
+        assert!(String::from_utf8_lossy(&nav.1).contains(r#"<a href="chapter-1.xhtml">Document</a>"#));
+
+        let markdown = "---\ntitle: Guide\nauthor: Ada\n---\n# Intro\nHello\n## Setup\n# Usage\n## Setup\n";
+        let book = exporter.build_epub(markdown, &options);
+        let file = |name: &str| {
+            let (_, data) = book.files.iter().find(|(n, _)| n == name).unwrap();
+            String::from_utf8(data.clone()).unwrap()
+        };
+        let nav = file("OEBPS/nav.xhtml");
+        assert!(nav.contains(r#"<a href="chapter-1.xhtml#intro">Intro</a>"#));
+        assert!(nav.contains(r#"<a href="chapter-1.xhtml#setup">Setup</a>"#));
+        assert!(nav.contains(r#"<a href="chapter-2.xhtml#usage">Usage</a>"#));
+        assert!(nav.contains(r#"<a href="chapter-2.xhtml#setup-1">Setup</a>"#));
+        assert_eq!(nav.matches("<ol>").count(), nav.matches("</ol>").count());
+        assert!(file("OEBPS/chapter-2.xhtml").contains(r#"<h1 id="usage">Usage</h1>"#));
+        let package = file("OEBPS/content.opf");
+        assert!(package.contains("<dc:title>Guide</dc:title>"));
+        assert!(package.contains("<dc:creator>Ada</dc:creator>"));
+        assert!(package.contains(r#"<itemref idref="chapter-2"/>"#));
+    }
+
+    #[test]
+    fn test_epub_export_file() {
+        let dir = std::env::temp_dir().join(format!("cosmic-notebook-epub-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("dot.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        let out = dir.join("book.epub");
+
+        let exporter = MarkdownExporter::new();
+        let options = EpubExportOptions {
+            base_path: Some(dir.clone()),
+            ..Default::default()
+        };
+        exporter
+            .export_epub_file("# Title\n\n![dot](dot.png)\n", &out, &options)
+            .unwrap();
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&out).unwrap()).unwrap();
+        let first = archive.by_index(0).unwrap();
+        assert_eq!(first.name(), "mimetype");
+        assert_eq!(first.compression(), zip::CompressionMethod::Stored);
+        drop(first);
+        assert!(archive.by_name("META-INF/container.xml").is_ok());
+        assert!(archive.by_name("OEBPS/images/image-1.png").is_ok());
+
+        let mut chapter = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("OEBPS/chapter-1.xhtml").unwrap(), &mut chapter).unwrap();
+        assert!(chapter.contains(r#"src="images/image-1.png""#));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_plain_export() {
+        let exporter = MarkdownExporter::new();
+        let markdown = "# Title\n\nSome *emphasis* and a [link](https://example.com), <https://a.org>.\n\n\
+            - one\n- two\n  - nested\n\n1. first\n2. second\n\n> quoted\n";
+        let plain = exporter.export_plain(markdown);
+
+        assert_eq!(
+            plain,
+            "Title\n=====\n\n\
+             Some emphasis and a link (https://example.com), https://a.org.\n\n\
+             • one\n• two\n  • nested\n\n\
+             1. first\n2. second\n\n\
+             > quoted\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_export_code_and_tables() {
+        let exporter = MarkdownExporter::new();
+        let markdown = "```\nfn main() {\n    body();\n}\n```\n\n\
+            | Name | Qty |\n|:-----|----:|\n| apple | 3 |\n| kiwi | 12 |\n";
+        let plain = exporter.export_plain(markdown);
+
+        assert_eq!(
+            plain,
+            "    fn main() {\n        body();\n    }\n\n\
+             Name   Qty\n-----  ---\napple    3\nkiwi    12\n"
+        );
+    }
+
     #[test]
     fn test_toc_generation() {
-        let toc = MarkdownExporter::generate_toc("# One\n## Two\n### Three\n# Four");
+        let (_, headings) = heading_ids(Parser::new("# One\n## Two\n### Three\n# Four"));
+        let toc = MarkdownExporter::toc_html(&headings);
         
         assert!(toc.contains("One"));
         assert!(toc.contains("Two"));
@@ -522,6 +1778,30 @@ mod tests {
         assert!(toc.contains("Four"));
     }
     
+    #[test]
+    fn test_duplicate_heading_anchors() {
+        let source = "# Notes\n\n```\n# Not a heading\n```\n\nNotes\n-----\n\n## `Notes`\n";
+        let exporter = MarkdownExporter::new();
+        let html = exporter.export_html(source, &HtmlExportOptions {
+            include_toc: true,
+            ..Default::default()
+        });
+        
+        assert!(html.contains("<h1 id=\"notes\">Notes</h1>"));
+        assert!(html.contains("<h2 id=\"notes-1\">Notes</h2>"));
+        assert!(html.contains("<h2 id=\"notes-2\"><code>Notes</code></h2>"));
+        assert!(html.contains("<a href=\"#notes-2\">Notes</a>"));
+        assert!(!html.contains("not-a-heading"));
+        
+        // Copied links name the same anchors
+        assert_eq!(exporter.heading_anchor_at_line(source, 0).as_deref(), Some("notes"));
+        assert_eq!(exporter.heading_anchor_at_line(source, 3), None);
+        assert_eq!(exporter.heading_anchor_at_line(source, 6).as_deref(), Some("notes-1"));
+        assert_eq!(exporter.heading_anchor_at_line(source, 7).as_deref(), Some("notes-1"));
+        assert_eq!(exporter.heading_anchor_at_line(source, 8), None);
+        assert_eq!(exporter.heading_anchor_at_line(source, 9).as_deref(), Some("notes-2"));
+    }
+    
     #[test]
     fn test_anchor_generation() {
         assert_eq!(MarkdownExporter::generate_anchor("Hello World"), "hello-world");
@@ -531,7 +1811,54 @@ mod tests {
     #[test]
     fn test_suggest_output_path() {
         let input = PathBuf::from("/docs/readme.md");
-        let output = MarkdownExporter::suggest_output_path(&input, ExportFormat::Html);
+        let output = MarkdownExporter::suggest_output_path(&input, ExportFormat::Html, &ExportConfig::default());
         assert_eq!(output, PathBuf::from("/docs/readme.html"));
+
+        let config = ExportConfig {
+            output_dir: Some(PathBuf::from("exports")),
+            filename_template: "{stem}-{date}.{ext}".to_string(),
+            ..Default::default()
+        };
+        let output = MarkdownExporter::suggest_output_path(&input, ExportFormat::Epub, &config);
+        let name = output.file_name().unwrap().to_str().unwrap();
+        assert_eq!(output.parent(), Some(Path::new("/docs/exports")));
+        assert!(name.starts_with("readme-") && name.ends_with(".epub"));
+
+        assert_eq!(render_filename_template("{stem}_{date}.{ext}", "a", "txt", "2024-01-02"), "a_2024-01-02.txt");
+        assert_eq!(render_filename_template("", "a", "txt", "2024-01-02"), "a.txt");
+    }
+
+    #[tokio::test]
+    async fn test_prepare_output_path() {
+        let dir = std::env::temp_dir().join(format!("cosmic-notebook-export-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("notes.md");
+        let config = ExportConfig {
+            output_dir: Some(PathBuf::from("out")),
+            ..Default::default()
+        };
+
+        // The export directory is created on demand
+        let first = MarkdownExporter::prepare_output_path(&input, ExportFormat::Html, &config).await.unwrap();
+        assert_eq!(first, dir.join("out/notes.html"));
+        assert!(dir.join("out").is_dir());
+
+        // Existing files get a numbered name unless overwriting
+        fs::write(&first, "old").unwrap();
+        let second = MarkdownExporter::suggest_output_path(&input, ExportFormat::Html, &config);
+        assert_eq!(second, dir.join("out/notes-1.html"));
+        fs::write(&second, "old").unwrap();
+        let third = MarkdownExporter::suggest_output_path(&input, ExportFormat::Html, &config);
+        assert_eq!(third, dir.join("out/notes-2.html"));
+
+        let config = ExportConfig {
+            on_conflict: ExportConflict::Overwrite,
+            ..config
+        };
+        let output = MarkdownExporter::suggest_output_path(&input, ExportFormat::Html, &config);
+        assert_eq!(output, first);
+
+        let _ = fs::remove_dir_all(&dir);
     }
 }
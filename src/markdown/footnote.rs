@@ -0,0 +1,294 @@
+//! Footnote numbering and layout
+//!
+//! pulldown-cmark reports footnote definitions where they appear in the
+//! source. The preview and HTML export instead number footnotes in order of
+//! first reference and list the definitions together at the end:
+//! - A reference with no definition is left as its `[^label]` text
+//! - A definition gets one back-link per reference to it
+//! - Definitions nobody references come last, or are dropped
+
+use pulldown_cmark::{CowStr, Event, Tag};
+use std::collections::HashMap;
+
+/// A footnote that has a definition
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Footnote {
+    /// Number shown at references and in the footnote list, from 1
+    pub number: usize,
+    /// How many times the footnote is referenced
+    pub references: usize,
+}
+
+/// The footnotes of a document, by label
+#[derive(Debug, Clone, Default)]
+pub struct Footnotes {
+    notes: HashMap<String, Footnote>,
+    /// References rendered so far, by label
+    seen: HashMap<String, usize>,
+}
+
+impl Footnotes {
+    /// Look up a footnote by its label
+    pub fn get(&self, label: &str) -> Option<Footnote> {
+        self.notes.get(&normalize(label)).copied()
+    }
+
+    /// Number and occurrence (from 1) of the next reference to `label`
+    ///
+    /// Called once per reference in rendering order, so the occurrences
+    /// match the back-links of the definition.
+    pub fn next_reference(&mut self, label: &str) -> Option<(usize, usize)> {
+        let key = normalize(label);
+        let number = self.notes.get(&key)?.number;
+        let seen = self.seen.entry(key).or_insert(0);
+        *seen += 1;
+        Some((number, *seen))
+    }
+}
+
+/// Anchor id of a footnote definition
+pub fn note_id(number: usize) -> String {
+    format!("fn-{}", number)
+}
+
+/// Anchor id of a reference to a footnote
+pub fn reference_id(number: usize, occurrence: usize) -> String {
+    if occurrence == 1 {
+        format!("fnref-{}", number)
+    } else {
+        format!("fnref-{}-{}", number, occurrence)
+    }
+}
+
+/// Labels match case-insensitively, as in GFM
+fn normalize(label: &str) -> String {
+    label.to_lowercase()
+}
+
+/// Move footnote definitions to the end of the document, in number order
+///
+/// Returns the rearranged events and the footnotes they define. References
+/// without a definition are turned back into text.
+pub fn arrange<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    keep_unreferenced: bool,
+) -> (Vec<Event<'a>>, Footnotes) {
+    let mut body = Vec::new();
+    // (label, events including the start and end tags), in source order
+    let mut definitions: Vec<(String, Vec<Event<'a>>)> = Vec::new();
+    let mut current: Option<(String, Vec<Event<'a>>)> = None;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) if current.is_none() => {
+                let key = normalize(&label);
+                current = Some((key, vec![Event::Start(Tag::FootnoteDefinition(label))]));
+            }
+            Event::End(Tag::FootnoteDefinition(label)) if current.is_some() => {
+                if let Some((key, mut events)) = current.take() {
+                    events.push(Event::End(Tag::FootnoteDefinition(label)));
+                    definitions.push((key, events));
+                }
+            }
+            event => match current.as_mut() {
+                Some((_, events)) => events.push(event),
+                None => body.push(event),
+            },
+        }
+    }
+
+    // The first definition of a label wins
+    let mut index = HashMap::new();
+    for (i, (key, _)) in definitions.iter().enumerate() {
+        index.entry(key.clone()).or_insert(i);
+    }
+
+    // Number by first reference: the document, then the footnotes in turn
+    let mut order = Vec::new();
+    let mut notes = HashMap::new();
+    count_references(&body, &index, &mut order, &mut notes);
+    let mut next = 0;
+    while next < order.len() {
+        let i = order[next];
+        count_references(&definitions[i].1, &index, &mut order, &mut notes);
+        next += 1;
+    }
+    if keep_unreferenced {
+        for (i, (key, _)) in definitions.iter().enumerate() {
+            if index[key] == i && !notes.contains_key(key) {
+                order.push(i);
+                notes.insert(key.clone(), Footnote { number: order.len(), references: 0 });
+            }
+        }
+    }
+
+    let footnotes = Footnotes { notes, seen: HashMap::new() };
+    let mut arranged = Vec::with_capacity(body.len());
+    arranged.extend(body);
+    for i in order {
+        arranged.append(&mut definitions[i].1);
+    }
+    let arranged = arranged
+        .into_iter()
+        .map(|event| match event {
+            Event::FootnoteReference(label) if footnotes.get(&label).is_none() => {
+                Event::Text(CowStr::from(format!("[^{}]", label)))
+            }
+            event => event,
+        })
+        .collect();
+    (arranged, footnotes)
+}
+
+/// Count the references in `events`, numbering footnotes on first sight
+fn count_references(
+    events: &[Event<'_>],
+    index: &HashMap<String, usize>,
+    order: &mut Vec<usize>,
+    notes: &mut HashMap<String, Footnote>,
+) {
+    for event in events {
+        if let Event::FootnoteReference(label) = event {
+            let key = normalize(label);
+            let Some(&i) = index.get(&key) else {
+                continue;
+            };
+            let note = notes.entry(key).or_insert_with(|| {
+                order.push(i);
+                Footnote { number: order.len(), references: 0 }
+            });
+            note.references += 1;
+        }
+    }
+}
+
+/// Rearrange footnotes and render them as HTML
+///
+/// References become numbered superscript links, and the definitions end
+/// the document as a list with back-links to each reference.
+pub fn html_events<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    keep_unreferenced: bool,
+) -> Vec<Event<'a>> {
+    let (events, mut footnotes) = arrange(events, keep_unreferenced);
+    let mut output = Vec::with_capacity(events.len());
+    let mut in_list = false;
+    let mut note = Footnote::default();
+
+    for event in events {
+        match event {
+            Event::FootnoteReference(label) => {
+                if let Some((number, occurrence)) = footnotes.next_reference(&label) {
+                    output.push(html(format!(
+                        "<sup class=\"footnote-ref\"><a href=\"#{}\" id=\"{}\">{}</a></sup>",
+                        note_id(number),
+                        reference_id(number, occurrence),
+                        number
+                    )));
+                }
+            }
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                if !in_list {
+                    output.push(html("<section class=\"footnotes\">\n<ol>\n".to_string()));
+                    in_list = true;
+                }
+                note = footnotes.get(&label).unwrap_or_default();
+                output.push(html(format!("<li id=\"{}\">\n", note_id(note.number))));
+            }
+            Event::End(Tag::FootnoteDefinition(_)) => {
+                let links = back_links(note);
+                // Back-links go at the end of the last paragraph, like GitHub
+                match output.pop() {
+                    Some(Event::End(Tag::Paragraph)) => {
+                        output.push(html(links));
+                        output.push(Event::End(Tag::Paragraph));
+                    }
+                    last => {
+                        output.extend(last);
+                        output.push(html(links));
+                    }
+                }
+                output.push(html("</li>\n".to_string()));
+            }
+            event => output.push(event),
+        }
+    }
+    if in_list {
+        output.push(html("</ol>\n</section>\n".to_string()));
+    }
+    output
+}
+
+/// Links from a definition back to each of its references
+fn back_links(note: Footnote) -> String {
+    (1..=note.references)
+        .map(|occurrence| {
+            let label = if note.references > 1 {
+                format!("↩<sup>{}</sup>", occurrence)
+            } else {
+                "↩".to_string()
+            };
+            format!(
+                " <a href=\"#{}\" class=\"footnote-backref\">{}</a>",
+                reference_id(note.number, occurrence),
+                label
+            )
+        })
+        .collect()
+}
+
+fn html<'a>(html: String) -> Event<'a> {
+    Event::Html(CowStr::from(html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html::push_html, Options, Parser};
+
+    fn render(markdown: &str, keep_unreferenced: bool) -> String {
+        let parser = Parser::new_ext(markdown, Options::ENABLE_FOOTNOTES);
+        let mut output = String::new();
+        push_html(&mut output, html_events(parser, keep_unreferenced).into_iter());
+        output
+    }
+
+    #[test]
+    fn test_numbered_by_first_reference() {
+        let html = render("[^b]: Bee.\n\nOne[^a] two[^b].\n\n[^a]: Ay.\n", true);
+
+        assert!(html.contains("One<sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1\">1</a></sup>"));
+        assert!(html.contains("<a href=\"#fn-2\" id=\"fnref-2\">2</a>"));
+        // Definitions are listed at the end, in number order
+        let ay = html.find("<li id=\"fn-1\">").unwrap();
+        let bee = html.find("<li id=\"fn-2\">").unwrap();
+        assert!(html.find("One").unwrap() < ay && ay < bee);
+        assert!(html.contains("<p>Ay. <a href=\"#fnref-1\" class=\"footnote-backref\">↩</a></p>"));
+    }
+
+    #[test]
+    fn test_repeated_reference() {
+        let html = render("One[^n] and two[^N].\n\n[^n]: Note.\n", true);
+
+        assert!(html.contains("id=\"fnref-1\">1</a>"));
+        assert!(html.contains("id=\"fnref-1-2\">1</a>"));
+        assert!(html.contains("<a href=\"#fnref-1\" class=\"footnote-backref\">↩<sup>1</sup></a>"));
+        assert!(html.contains("<a href=\"#fnref-1-2\" class=\"footnote-backref\">↩<sup>2</sup></a>"));
+    }
+
+    #[test]
+    fn test_missing_and_unreferenced() {
+        let source = "Dangling[^missing].\n\n[^unused]: Spare.\n";
+        let (events, footnotes) =
+            arrange(Parser::new_ext(source, Options::ENABLE_FOOTNOTES), true);
+        assert!(events.contains(&Event::Text("[^missing]".into())));
+        assert_eq!(footnotes.get("unused"), Some(Footnote { number: 1, references: 0 }));
+
+        let kept = render(source, true);
+        assert!(kept.contains("Spare."));
+        assert!(!kept.contains("footnote-backref"));
+        let dropped = render(source, false);
+        assert!(!dropped.contains("Spare."));
+        assert!(!dropped.contains("<section"));
+    }
+}
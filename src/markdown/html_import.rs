@@ -0,0 +1,770 @@
+//! HTML to Markdown conversion for pasting rich clipboard content
+//!
+//! The HTML is parsed into a small element tree and rendered back as
+//! GitHub-flavored Markdown:
+//! - Headings, paragraphs, blockquotes, and horizontal rules
+//! - Bold, italic, strikethrough, inline code, links, and images
+//! - Nested ordered, unordered, and task lists
+//! - Tables with column alignment
+//! - Preformatted blocks as fenced code
+//!
+//! Malformed input (unterminated tags, stray closing tags, unclosed
+//! elements) is rejected so callers can fall back to plain text.
+
+/// Elements that never have children
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "wbr",
+];
+
+/// Elements whose closing tag may be omitted
+const OPTIONAL_CLOSE: &[&str] = &[
+    "html", "head", "body", "p", "li", "dt", "dd", "tr", "td", "th", "thead", "tbody", "tfoot",
+    "colgroup", "option",
+];
+
+/// Elements whose contents are never shown
+const HIDDEN_ELEMENTS: &[&str] = &["head", "script", "style", "title", "template", "noscript"];
+
+/// Elements that implicitly close an open paragraph
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "div", "dl", "fieldset", "figure", "footer",
+    "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "main", "nav", "ol", "p", "pre",
+    "section", "table", "ul",
+];
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child_elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+}
+
+/// Convert an HTML fragment to Markdown
+///
+/// Returns `None` for malformed HTML or when nothing visible remains.
+pub fn html_to_markdown(html: &str) -> Option<String> {
+    let root = parse(html)?;
+    let markdown = render_blocks(&root.children).join("\n\n");
+    let markdown = markdown.trim_matches('\n');
+    (!markdown.trim().is_empty()).then(|| format!("{}\n", markdown))
+}
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+/// Build the element tree, or `None` if the HTML is malformed
+fn parse(html: &str) -> Option<Element> {
+    let mut stack = vec![Element::default()];
+    let mut text = String::new();
+    let mut rest = html;
+
+    while let Some(lt) = rest.find('<') {
+        text.push_str(&rest[..lt]);
+        let tag = &rest[lt..];
+
+        if let Some(comment) = tag.strip_prefix("<!--") {
+            let end = comment.find("-->")?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+        if tag.starts_with("<!") || tag.starts_with("<?") {
+            let end = tag.find('>')?;
+            rest = &tag[end + 1..];
+            continue;
+        }
+
+        let closing = tag.starts_with("</");
+        let name_start = if closing { 2 } else { 1 };
+        if !tag[name_start..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            // A literal `<` in text
+            text.push('<');
+            rest = &tag[1..];
+            continue;
+        }
+
+        let end = tag_end(tag)?;
+        let inner = &tag[name_start..end];
+        rest = &tag[end + 1..];
+        flush_text(&mut stack, &mut text);
+
+        let (name, attrs) = parse_tag(inner);
+        if closing {
+            close_element(&mut stack, &name)?;
+            continue;
+        }
+
+        open_element(&mut stack, &name);
+        let element = Element {
+            name,
+            attrs,
+            children: Vec::new(),
+        };
+        if VOID_ELEMENTS.contains(&element.name.as_str()) || inner.trim_end().ends_with('/') {
+            push_node(&mut stack, Node::Element(element));
+        } else if matches!(element.name.as_str(), "script" | "style") {
+            // Raw text elements: skip straight to the closing tag
+            let close = format!("</{}", element.name);
+            let end = rest.to_ascii_lowercase().find(&close)?;
+            let after = &rest[end..];
+            rest = &after[after.find('>')? + 1..];
+        } else {
+            stack.push(element);
+        }
+    }
+    text.push_str(rest);
+    flush_text(&mut stack, &mut text);
+
+    while stack.len() > 1 {
+        let element = stack.pop()?;
+        if !OPTIONAL_CLOSE.contains(&element.name.as_str()) {
+            return None;
+        }
+        push_node(&mut stack, Node::Element(element));
+    }
+    stack.pop()
+}
+
+/// Find the `>` ending a tag, skipping quoted attribute values
+pub(super) fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split the inside of a tag into a lowercase name and attributes
+pub(super) fn parse_tag(inner: &str) -> (String, Vec<(String, String)>) {
+    let inner = inner.trim_end_matches('/');
+    let name_end = inner
+        .find(|c: char| c.is_whitespace() || c == '/')
+        .unwrap_or(inner.len());
+    let name = inner[..name_end].to_ascii_lowercase();
+
+    let mut attrs = Vec::new();
+    let mut rest = inner[name_end..].trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let body = &after[1..];
+                    let end = body.find(q).unwrap_or(body.len());
+                    (&body[..end], body.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining;
+        }
+
+        if !key.is_empty() {
+            attrs.push((key, value));
+        }
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+    }
+
+    (name, attrs)
+}
+
+/// Close elements that the opening of `name` ends implicitly
+fn open_element(stack: &mut Vec<Element>, name: &str) {
+    let implicit_close = |stack: &Vec<Element>, target: &[&str], scope: &[&str]| {
+        stack
+            .iter()
+            .rposition(|e| target.contains(&e.name.as_str()) || scope.contains(&e.name.as_str()))
+            .filter(|&i| target.contains(&stack[i].name.as_str()))
+    };
+
+    let position = match name {
+        "li" => implicit_close(stack, &["li"], &["ul", "ol"]),
+        "dt" | "dd" => implicit_close(stack, &["dt", "dd"], &["dl"]),
+        "td" | "th" => implicit_close(stack, &["td", "th"], &["tr", "table"]),
+        "tr" => implicit_close(stack, &["tr"], &["table"]),
+        _ if BLOCK_ELEMENTS.contains(&name) => {
+            stack.last().filter(|e| e.name == "p").map(|_| stack.len() - 1)
+        }
+        _ => None,
+    };
+    if let Some(position) = position {
+        pop_to(stack, position);
+    }
+}
+
+/// Close the innermost open `name`, or fail on a stray closing tag
+fn close_element(stack: &mut Vec<Element>, name: &str) -> Option<()> {
+    match stack.iter().skip(1).rposition(|e| e.name == name) {
+        Some(position) => {
+            let position = position + 1;
+            // Elements that must be closed explicitly can't be skipped over
+            if stack[position + 1..]
+                .iter()
+                .any(|e| !OPTIONAL_CLOSE.contains(&e.name.as_str()))
+            {
+                return None;
+            }
+            pop_to(stack, position);
+            Some(())
+        }
+        // `</br>` and `</p>` without an opening tag are common and harmless
+        None if VOID_ELEMENTS.contains(&name) || name == "p" => Some(()),
+        None => None,
+    }
+}
+
+/// Pop elements down to and including `position`, attaching each to its parent
+fn pop_to(stack: &mut Vec<Element>, position: usize) {
+    while stack.len() > position {
+        if let Some(element) = stack.pop() {
+            push_node(stack, Node::Element(element));
+        }
+    }
+}
+
+fn push_node(stack: &mut [Element], node: Node) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(node);
+    }
+}
+
+fn flush_text(stack: &mut [Element], text: &mut String) {
+    if !text.is_empty() {
+        push_node(stack, Node::Text(decode_entities(text)));
+        text.clear();
+    }
+}
+
+/// Decode character references
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        output.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity.strip_prefix('#').and_then(|code| {
+                    let value = match code.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => code.parse().ok(),
+                    };
+                    value.and_then(char::from_u32)
+                }),
+            };
+            c.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                output.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+// ---------------------------------------------------------------------------
+// Rendering
+// ---------------------------------------------------------------------------
+
+/// Render a sequence of nodes as Markdown blocks
+fn render_blocks(nodes: &[Node]) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut inline = String::new();
+
+    let flush = |inline: &mut String, blocks: &mut Vec<String>| {
+        let paragraph = tidy_inline(inline);
+        if !paragraph.is_empty() {
+            blocks.push(paragraph);
+        }
+        inline.clear();
+    };
+
+    for node in nodes {
+        match node {
+            Node::Text(text) => inline.push_str(&escape_text(text)),
+            Node::Element(element) if is_inline(element) => inline.push_str(&render_inline(element)),
+            Node::Element(element) => {
+                flush(&mut inline, &mut blocks);
+                blocks.extend(render_block(element));
+            }
+        }
+    }
+    flush(&mut inline, &mut blocks);
+    blocks
+}
+
+fn is_inline(element: &Element) -> bool {
+    matches!(
+        element.name.as_str(),
+        "a" | "abbr" | "b" | "bdi" | "big" | "br" | "cite" | "code" | "del" | "em" | "font" | "i"
+            | "img" | "input" | "ins" | "kbd" | "label" | "mark" | "q" | "s" | "samp" | "small"
+            | "span" | "strike" | "strong" | "sub" | "sup" | "time" | "tt" | "u" | "var" | "wbr"
+    )
+}
+
+/// Render a block element as zero or more Markdown blocks
+fn render_block(element: &Element) -> Vec<String> {
+    let name = element.name.as_str();
+    if HIDDEN_ELEMENTS.contains(&name) {
+        return Vec::new();
+    }
+
+    match name {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse().unwrap_or(1);
+            let text = render_inline_children(element).replace('\n', " ");
+            let text = text.trim();
+            if text.is_empty() {
+                Vec::new()
+            } else {
+                vec![format!("{} {}", "#".repeat(level), text)]
+            }
+        }
+        "hr" => vec!["---".to_string()],
+        "pre" => vec![render_code_block(element)],
+        "blockquote" => {
+            let inner = render_blocks(&element.children).join("\n\n");
+            if inner.is_empty() {
+                return Vec::new();
+            }
+            let quoted = inner
+                .lines()
+                .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                .collect::<Vec<_>>()
+                .join("\n");
+            vec![quoted]
+        }
+        "ul" | "ol" => {
+            let list = render_list(element);
+            if list.is_empty() {
+                Vec::new()
+            } else {
+                vec![list]
+            }
+        }
+        "table" => render_table(element).into_iter().collect(),
+        _ => render_blocks(&element.children),
+    }
+}
+
+/// Render a list, indenting item continuation lines under the marker
+fn render_list(list: &Element) -> String {
+    let ordered = list.name == "ol";
+    let mut number: usize = list.attr("start").and_then(|s| s.parse().ok()).unwrap_or(1);
+    let mut items = Vec::new();
+
+    for item in list.child_elements() {
+        let blocks = if item.name == "li" {
+            render_list_item(item)
+        } else {
+            render_block(item)
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+
+        let marker = if ordered {
+            let marker = format!("{}. ", number);
+            number += 1;
+            marker
+        } else {
+            "- ".to_string()
+        };
+        let indent = " ".repeat(marker.len());
+        let body = blocks.join("\n");
+        let mut lines = body.lines();
+        let mut rendered = format!("{}{}", marker, lines.next().unwrap_or(""));
+        for line in lines {
+            rendered.push('\n');
+            if !line.is_empty() {
+                rendered.push_str(&indent);
+                rendered.push_str(line);
+            }
+        }
+        items.push(rendered);
+    }
+
+    items.join("\n")
+}
+
+/// Render a list item, turning a leading checkbox into a task marker
+fn render_list_item(item: &Element) -> Vec<String> {
+    let first = item
+        .children
+        .iter()
+        .find(|node| !matches!(node, Node::Text(text) if text.trim().is_empty()));
+    let checkbox = match first {
+        Some(Node::Element(e)) if e.name == "input" && e.attr("type") == Some("checkbox") => Some(e),
+        _ => None,
+    };
+
+    let mut blocks = render_blocks(&item.children);
+    if let Some(checkbox) = checkbox {
+        let task = if checkbox.attr("checked").is_some() { "[x] " } else { "[ ] " };
+        match blocks.first_mut() {
+            Some(first) => first.insert_str(0, task),
+            None => blocks.push(task.trim_end().to_string()),
+        }
+    }
+    blocks
+}
+
+/// Render a preformatted block as a fenced code block
+fn render_code_block(pre: &Element) -> String {
+    let code = pre.child_elements().find(|e| e.name == "code");
+    let language = [Some(pre), code]
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.attr("class"))
+        .flat_map(str::split_whitespace)
+        .find_map(|class| class.strip_prefix("language-").or_else(|| class.strip_prefix("lang-")))
+        .unwrap_or("");
+
+    let mut text = String::new();
+    collect_text(&pre.children, &mut text);
+    let text = text.strip_prefix('\n').unwrap_or(&text).trim_end();
+
+    let mut fence = "```".to_string();
+    while text.contains(&fence) {
+        fence.push('`');
+    }
+    format!("{}{}\n{}\n{}", fence, language, text, fence)
+}
+
+/// Render a table as a GFM table
+fn render_table(table: &Element) -> Option<String> {
+    let mut rows: Vec<(Vec<String>, bool)> = Vec::new();
+    let mut alignments: Vec<Option<&str>> = Vec::new();
+    collect_rows(table, &mut rows, &mut alignments);
+
+    let columns = rows.iter().map(|(cells, _)| cells.len()).max()?;
+    if columns == 0 {
+        return None;
+    }
+    alignments.resize(columns, None);
+
+    let format_row = |cells: &[String]| {
+        let mut row = String::from("|");
+        for i in 0..columns {
+            row.push(' ');
+            row.push_str(cells.get(i).map(String::as_str).unwrap_or(""));
+            row.push_str(" |");
+        }
+        row
+    };
+
+    // GFM requires a header row; an empty one is used when the table has none
+    let (header, body) = match rows.first() {
+        Some((cells, true)) => (format_row(cells), &rows[1..]),
+        _ => (format_row(&[]), &rows[..]),
+    };
+    let separator = alignments
+        .iter()
+        .map(|align| match *align {
+            Some("left") => " :--- |",
+            Some("center") => " :---: |",
+            Some("right") => " ---: |",
+            _ => " --- |",
+        })
+        .collect::<String>();
+
+    let mut lines = vec![header, format!("|{}", separator)];
+    lines.extend(body.iter().map(|(cells, _)| format_row(cells)));
+    Some(lines.join("\n"))
+}
+
+/// Gather table rows as rendered cells, noting whether each row is a header
+fn collect_rows<'a>(
+    element: &'a Element,
+    rows: &mut Vec<(Vec<String>, bool)>,
+    alignments: &mut Vec<Option<&'a str>>,
+) {
+    for child in element.child_elements() {
+        match child.name.as_str() {
+            "tr" => {
+                let mut cells = Vec::new();
+                let mut header = true;
+                for cell in child.child_elements().filter(|e| matches!(e.name.as_str(), "td" | "th")) {
+                    header &= cell.name == "th";
+                    if alignments.len() <= cells.len() {
+                        alignments.push(None);
+                    }
+                    if alignments[cells.len()].is_none() {
+                        alignments[cells.len()] = cell_alignment(cell);
+                    }
+                    let text = render_blocks(&cell.children).join(" ");
+                    cells.push(text.replace('\n', " ").replace('|', "\\|"));
+                }
+                let header = header && !cells.is_empty() && (rows.is_empty() || element.name == "thead");
+                rows.push((cells, header));
+            }
+            "thead" | "tbody" | "tfoot" => collect_rows(child, rows, alignments),
+            _ => {}
+        }
+    }
+}
+
+fn cell_alignment(cell: &Element) -> Option<&str> {
+    if let Some(align) = cell.attr("align") {
+        return Some(align);
+    }
+    let style = cell.attr("style")?;
+    let (_, value) = style.split_once("text-align")?;
+    let value = value.trim_start_matches([' ', ':']);
+    ["left", "center", "right"].into_iter().find(|a| value.starts_with(a))
+}
+
+fn render_inline_children(element: &Element) -> String {
+    let mut output = String::new();
+    for node in &element.children {
+        match node {
+            Node::Text(text) => output.push_str(&escape_text(text)),
+            Node::Element(child) if is_inline(child) => output.push_str(&render_inline(child)),
+            Node::Element(child) => {
+                output.push(' ');
+                output.push_str(&render_block(child).join(" "));
+                output.push(' ');
+            }
+        }
+    }
+    output
+}
+
+/// Render an inline element
+fn render_inline(element: &Element) -> String {
+    match element.name.as_str() {
+        "br" => "\\\n".to_string(),
+        "img" => {
+            let src = element.attr("src").unwrap_or("");
+            if src.is_empty() {
+                return String::new();
+            }
+            let alt = escape_text(element.attr("alt").unwrap_or(""));
+            format!("![{}]({})", alt.trim(), link_destination(src))
+        }
+        "input" | "wbr" => String::new(),
+        "code" | "kbd" | "samp" | "tt" => {
+            let mut text = String::new();
+            collect_text(&element.children, &mut text);
+            let text = collapse_whitespace(&text);
+            if text.trim().is_empty() {
+                return text;
+            }
+            let mut ticks = "`".to_string();
+            while text.contains(&ticks) {
+                ticks.push('`');
+            }
+            let pad = if text.starts_with('`') || text.ends_with('`') { " " } else { "" };
+            format!("{}{}{}{}{}", ticks, pad, text, pad, ticks)
+        }
+        "a" => {
+            let text = render_inline_children(element);
+            match element.attr("href").filter(|href| !href.is_empty()) {
+                Some(href) if !text.trim().is_empty() => {
+                    wrap_preserving_space(&text, |inner| {
+                        format!("[{}]({})", inner, link_destination(href))
+                    })
+                }
+                _ => text,
+            }
+        }
+        "b" | "strong" => emphasize(&render_inline_children(element), "**"),
+        "i" | "em" | "cite" | "var" => emphasize(&render_inline_children(element), "*"),
+        "s" | "del" | "strike" => emphasize(&render_inline_children(element), "~~"),
+        _ => render_inline_children(element),
+    }
+}
+
+fn emphasize(text: &str, marker: &str) -> String {
+    if text.trim().is_empty() {
+        return text.to_string();
+    }
+    wrap_preserving_space(text, |inner| format!("{}{}{}", marker, inner, marker))
+}
+
+/// Apply `wrap` to the text without its surrounding whitespace
+fn wrap_preserving_space(text: &str, wrap: impl FnOnce(&str) -> String) -> String {
+    let inner = text.trim();
+    let start = text.len() - text.trim_start().len();
+    let end = start + inner.len();
+    format!("{}{}{}", &text[..start], wrap(inner), &text[end..])
+}
+
+fn link_destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url)
+    } else {
+        url.to_string()
+    }
+}
+
+/// Append the raw text of all descendants
+fn collect_text(nodes: &[Node], output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Element(element) if element.name == "br" => output.push('\n'),
+            Node::Element(element) => collect_text(&element.children, output),
+        }
+    }
+}
+
+/// Escape characters that Markdown would treat as formatting
+fn escape_text(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in collapse_whitespace(text).chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']') {
+            output.push('\\');
+        }
+        output.push(c);
+    }
+    output
+}
+
+/// Collapse HTML whitespace runs into single spaces
+fn collapse_whitespace(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !space {
+                output.push(' ');
+            }
+            space = true;
+        } else {
+            output.push(if c == '\u{a0}' { ' ' } else { c });
+            space = false;
+        }
+    }
+    output
+}
+
+/// Trim paragraph text, including around hard line breaks
+fn tidy_inline(text: &str) -> String {
+    let text = text.trim();
+    let text = text.trim_end_matches("\\\n").trim_end();
+    text.split("\\\n")
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_formatting() {
+        let html = "<p>Some <b>bold</b>, <em>italic </em>and <code>a*b</code> with \
+                    <a href=\"https://example.com\">a link</a>.</p>";
+        assert_eq!(
+            html_to_markdown(html).unwrap(),
+            "Some **bold**, *italic* and `a*b` with [a link](https://example.com).\n"
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let html = "<h2>Title &amp; more</h2><p>One</p><blockquote><p>Quoted</p></blockquote>\
+                    <pre><code class=\"language-rust\">fn main() {\n    x &lt; 1\n}</code></pre><hr>";
+        assert_eq!(
+            html_to_markdown(html).unwrap(),
+            "## Title & more\n\nOne\n\n> Quoted\n\n```rust\nfn main() {\n    x < 1\n}\n```\n\n---\n"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let html = "<ul><li>One<ul><li>Nested</li></ul></li><li>Two</ul>\
+                    <ol start=\"3\"><li><p>Three</p></li><li>Four</li></ol>\
+                    <ul><li><input type=\"checkbox\" checked> Done</li><li><input type=\"checkbox\"> Todo</li></ul>";
+        assert_eq!(
+            html_to_markdown(html).unwrap(),
+            "- One\n  - Nested\n- Two\n\n3. Three\n4. Four\n\n- [x] Done\n- [ ] Todo\n"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let html = "<table><thead><tr><th>Name</th><th align=\"right\">Count</th></tr></thead>\
+                    <tbody><tr><td>a|b</td><td>1</td></tr><tr><td><b>c</b><td>2</tr></tbody></table>";
+        assert_eq!(
+            html_to_markdown(html).unwrap(),
+            "| Name | Count |\n| --- | ---: |\n| a\\|b | 1 |\n| **c** | 2 |\n"
+        );
+    }
+
+    #[test]
+    fn test_clipboard_fragment() {
+        let html = "<meta charset='utf-8'><html><body><!--StartFragment--><span style=\"color: red\">\
+                    Hello</span> <i>world</i><!--EndFragment--></body></html>";
+        assert_eq!(html_to_markdown(html).unwrap(), "Hello *world*\n");
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(html_to_markdown("<p>Unterminated <b"), None);
+        assert_eq!(html_to_markdown("<div><b>Mismatched</div></b>"), None);
+        assert_eq!(html_to_markdown("Stray </span> closing"), None);
+        assert_eq!(html_to_markdown("<em>Never closed"), None);
+        assert_eq!(html_to_markdown("<script>alert(1)</script>"), None);
+    }
+}
@@ -3,13 +3,17 @@
 //! This module provides functionality for handling images in the markdown editor:
 //! - Drag & drop image support
 //! - Clipboard image paste
+//! - Downloading images from a URL
 //! - Saving images to assets folder
 //! - Generating markdown image links
+//! - Image size hints (`=WxH` and `{width=200}`)
 
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::io::Write;
+use std::io::{Read, Write};
+use std::time::Duration;
 use chrono::Utc;
+use pulldown_cmark::{CowStr, Event, Tag};
 use thiserror::Error;
 
 /// Errors that can occur during image handling
@@ -37,6 +41,24 @@ pub enum ImageError {
 /// Result type for image operations
 pub type ImageResult<T> = Result<T, ImageError>;
 
+/// Longest an image download may take
+pub const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Largest image downloaded, in bytes
+pub const DOWNLOAD_LIMIT: u64 = 10 * 1024 * 1024;
+
+/// Why an image couldn't be downloaded
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DownloadError {
+    /// The URL points at something other than an image
+    #[error("Not an image: {0}")]
+    NotAnImage(String),
+
+    /// The image couldn't be fetched
+    #[error("{0}")]
+    Failed(String),
+}
+
 /// Configuration for image handling
 #[derive(Debug, Clone)]
 pub struct ImageConfig {
@@ -115,6 +137,17 @@ impl ImageFormat {
         None
     }
     
+    /// Get the MIME type for the format
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Svg => "image/svg+xml",
+        }
+    }
+    
     /// Detect format from file extension
     pub fn from_extension(ext: &str) -> Option<Self> {
         match ext.to_lowercase().as_str() {
@@ -345,6 +378,323 @@ impl Default for ImageHandler {
     }
 }
 
+/// Download an image over `http(s)`
+///
+/// Blocks for at most `timeout` and reads at most `limit` bytes. Responses
+/// that are text, or whose content isn't a known image format, are
+/// rejected as not an image.
+pub fn download_image(url: &str, timeout: Duration, limit: u64) -> Result<Vec<u8>, DownloadError> {
+    let lower = url.to_ascii_lowercase();
+    if !lower.starts_with("http://") && !lower.starts_with("https://") {
+        return Err(DownloadError::NotAnImage(url.to_string()));
+    }
+
+    let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+    let response = agent.get(url).call().map_err(|e| match e {
+        ureq::Error::Status(code, _) => DownloadError::Failed(format!("HTTP {}", code)),
+        e => DownloadError::Failed(e.to_string()),
+    })?;
+
+    let content_type = response.content_type().to_ascii_lowercase();
+    if content_type.starts_with("text/") || content_type.contains("json") {
+        return Err(DownloadError::NotAnImage(content_type));
+    }
+    let too_large = || DownloadError::Failed(format!("larger than {} MB", limit / (1024 * 1024)));
+    let length = response.header("Content-Length").and_then(|len| len.parse::<u64>().ok());
+    if length.is_some_and(|len| len > limit) {
+        return Err(too_large());
+    }
+
+    let mut data = Vec::new();
+    response
+        .into_reader()
+        .take(limit + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| DownloadError::Failed(e.to_string()))?;
+    if data.len() as u64 > limit {
+        return Err(too_large());
+    }
+    if ImageFormat::from_bytes(&data).is_none() {
+        return Err(DownloadError::NotAnImage(content_type));
+    }
+    Ok(data)
+}
+
+/// Display size from an image size hint
+///
+/// Two syntaxes are understood: `![alt](img.png =200x100)` and the
+/// attribute block `![alt](img.png){width=200 height=100}`. Either side of
+/// `=WxH` may be left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImageSize {
+    /// Width in pixels
+    pub width: Option<u32>,
+    /// Height in pixels
+    pub height: Option<u32>,
+}
+
+impl ImageSize {
+    /// Parse a `=WxH` hint; `None` when malformed or empty
+    pub fn parse_hint(hint: &str) -> Option<Self> {
+        let hint = hint.strip_prefix('=')?;
+        let (width, height) = hint.split_once('x').unwrap_or((hint, ""));
+        let size = Self {
+            width: parse_pixels(width)?,
+            height: parse_pixels(height)?,
+        };
+        (size != Self::default()).then_some(size)
+    }
+
+    /// Parse the inside of a `{width=200 height=100}` attribute block
+    ///
+    /// Other attributes are ignored; `None` when there is no valid size.
+    pub fn parse_attributes(attributes: &str) -> Option<Self> {
+        let mut size = Self::default();
+        for attribute in attributes.split_whitespace() {
+            let Some((key, value)) = attribute.split_once('=') else {
+                continue;
+            };
+            let value = value.trim_matches('"');
+            match key {
+                "width" => size.width = Some(parse_pixels(value)??),
+                "height" => size.height = Some(parse_pixels(value)??),
+                _ => {}
+            }
+        }
+        (size != Self::default()).then_some(size)
+    }
+
+    /// Split a leading `{...}` size block off the text after an image
+    ///
+    /// Returns the size and the remaining text.
+    pub fn split_attributes(text: &str) -> Option<(Self, &str)> {
+        let inner = text.strip_prefix('{')?;
+        let end = inner.find('}')?;
+        let size = Self::parse_attributes(&inner[..end])?;
+        Some((size, &inner[end + 1..]))
+    }
+
+    /// The size as a markdown attribute block
+    fn to_attributes(self) -> String {
+        let mut parts = Vec::new();
+        if let Some(width) = self.width {
+            parts.push(format!("width={}", width));
+        }
+        if let Some(height) = self.height {
+            parts.push(format!("height={}", height));
+        }
+        format!("{{{}}}", parts.join(" "))
+    }
+
+    /// The size as HTML attributes, each with a leading space
+    pub fn html_attributes(&self) -> String {
+        let mut attributes = String::new();
+        if let Some(width) = self.width {
+            attributes.push_str(&format!(" width=\"{}\"", width));
+        }
+        if let Some(height) = self.height {
+            attributes.push_str(&format!(" height=\"{}\"", height));
+        }
+        attributes
+    }
+}
+
+/// Parse a pixel count, allowing a `px` suffix
+///
+/// Empty input is `Some(None)`; anything else unparsable is `None`.
+fn parse_pixels(value: &str) -> Option<Option<u32>> {
+    let value = value.strip_suffix("px").unwrap_or(value);
+    if value.is_empty() {
+        return Some(None);
+    }
+    value.parse().ok().filter(|&n| n > 0).map(Some)
+}
+
+/// Rewrite `=WxH` size hints into attribute blocks before parsing
+///
+/// pulldown-cmark rejects `![alt](img.png =200x100)` as a link, so the hint
+/// becomes `![alt](img.png){width=200 height=100}`. A malformed hint is
+/// dropped so the image still renders. Code blocks and code spans are left
+/// alone.
+pub fn normalize_size_hints(markdown: &str) -> String {
+    static HINT: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let hint = HINT.get_or_init(|| {
+        regex::Regex::new(r#"(!\[[^\]]*\]\([^\s()]+(?:\s+"[^"]*")?)\s+(=[^\s()]*)\)"#)
+            .expect("valid size hint pattern")
+    });
+    if !markdown.contains('=') {
+        return markdown.to_string();
+    }
+
+    let rewrite = |text: &str| {
+        hint.replace_all(text, |caps: &regex::Captures| match ImageSize::parse_hint(&caps[2]) {
+            Some(size) => format!("{}){}", &caps[1], size.to_attributes()),
+            None => format!("{})", &caps[1]),
+        })
+        .into_owned()
+    };
+
+    let mut output = String::with_capacity(markdown.len());
+    let mut fence: Option<(char, usize)> = None;
+    for raw_line in markdown.split_inclusive('\n') {
+        if let Some(marker) = super::math::code_fence(raw_line.trim()) {
+            match fence {
+                None => fence = Some(marker),
+                Some((c, count)) if marker.0 == c && marker.1 >= count => fence = None,
+                Some(_) => {}
+            }
+            output.push_str(raw_line);
+            continue;
+        }
+        if fence.is_some() {
+            output.push_str(raw_line);
+            continue;
+        }
+
+        // Rewrite the text between code spans
+        let chars: Vec<char> = raw_line.chars().collect();
+        let mut pos = 0;
+        let mut text_start = 0;
+        while pos < chars.len() {
+            if chars[pos] != '`' {
+                pos += 1;
+                continue;
+            }
+            let run = chars[pos..].iter().take_while(|c| **c == '`').count();
+            match super::math::find_code_span_end(&chars, pos + run, run) {
+                Some(end) => {
+                    output.push_str(&rewrite(&chars[text_start..pos].iter().collect::<String>()));
+                    output.extend(&chars[pos..end]);
+                    pos = end;
+                    text_start = end;
+                }
+                None => pos += run,
+            }
+        }
+        output.push_str(&rewrite(&chars[text_start..].iter().collect::<String>()));
+    }
+    output
+}
+
+/// Turn images followed by a size block into sized `<img>` tags
+///
+/// Images without a size are passed through for the HTML writer.
+pub fn sized_image_events<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let events: Vec<Event<'a>> = events.collect();
+    let mut output = Vec::with_capacity(events.len());
+    let mut pos = 0;
+
+    while pos < events.len() {
+        if let Event::Start(Tag::Image(_, url, title)) = &events[pos] {
+            let end = events[pos..]
+                .iter()
+                .position(|e| matches!(e, Event::End(Tag::Image(..))))
+                .map(|offset| pos + offset);
+            let sized = end.and_then(|end| match events.get(end + 1) {
+                Some(Event::Text(text)) => ImageSize::split_attributes(text).map(|s| (end, s)),
+                _ => None,
+            });
+            if let Some((end, (size, rest))) = sized {
+                let alt: String = events[pos + 1..end]
+                    .iter()
+                    .filter_map(|e| match e {
+                        Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+                        _ => None,
+                    })
+                    .collect();
+                let title = if title.is_empty() {
+                    String::new()
+                } else {
+                    format!(" title=\"{}\"", escape_attribute(title))
+                };
+                output.push(Event::Html(CowStr::from(format!(
+                    "<img src=\"{}\" alt=\"{}\"{}{} />",
+                    escape_attribute(url),
+                    escape_attribute(&alt),
+                    title,
+                    size.html_attributes()
+                ))));
+                if !rest.is_empty() {
+                    output.push(Event::Text(CowStr::from(rest.to_string())));
+                }
+                pos = end + 2;
+                continue;
+            }
+        }
+        output.push(events[pos].clone());
+        pos += 1;
+    }
+
+    output
+}
+
+/// Read an image file into a base64 `data:` URI
+pub fn data_uri(path: &Path) -> ImageResult<String> {
+    use base64::Engine;
+
+    let data = fs::read(path)?;
+    let format = ImageFormat::from_bytes(&data)
+        .or_else(|| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .and_then(ImageFormat::from_extension)
+        })
+        .ok_or(ImageError::InvalidFormat)?;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(&data);
+    Ok(format!("data:{};base64,{}", format.mime_type(), encoded))
+}
+
+/// Inline local images in an event stream as `data:` URIs
+///
+/// Relative paths resolve against `base_dir`. Remote and `data:` URLs are
+/// left alone, and an image that cannot be read keeps its original source.
+pub fn embed_images<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    base_dir: Option<&Path>,
+) -> Vec<Event<'a>> {
+    events
+        .map(|event| match event {
+            Event::Start(Tag::Image(link_type, url, title)) => {
+                let url = match embed_url(&url, base_dir) {
+                    Some(uri) => CowStr::from(uri),
+                    None => url,
+                };
+                Event::Start(Tag::Image(link_type, url, title))
+            }
+            other => other,
+        })
+        .collect()
+}
+
+/// The `data:` URI for a local image URL, if it can be read
+fn embed_url(url: &str, base_dir: Option<&Path>) -> Option<String> {
+    let is_remote = url.contains("://") && !url.starts_with("file://");
+    if url.is_empty() || is_remote || url.starts_with("data:") || url.starts_with('#') {
+        return None;
+    }
+
+    let path = Path::new(url.strip_prefix("file://").unwrap_or(url));
+    let path = match base_dir {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path.to_path_buf(),
+    };
+    match data_uri(&path) {
+        Ok(uri) => Some(uri),
+        Err(e) => {
+            log::warn!("Could not embed image {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Escape text for an HTML attribute value
+fn escape_attribute(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 /// Small utility module for path difference calculation
 /// (If pathdiff crate is not available)
 mod pathdiff {
@@ -387,7 +737,56 @@ mod pathdiff {
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
+    /// Serve one response per request on a local port
+    fn serve(responses: Vec<(&'static str, Vec<u8>)>) -> String {
+        use std::io::{BufRead, BufReader};
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for (content_type, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    line.clear();
+                }
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    content_type,
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+        format!("http://127.0.0.1:{}/image", port)
+    }
+
+    #[test]
+    fn test_download_image() {
+        let png = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00];
+        let url = serve(vec![
+            ("image/png", png.clone()),
+            ("text/html", b"<html></html>".to_vec()),
+            ("application/octet-stream", b"not an image at all".to_vec()),
+            ("image/png", png.clone()),
+        ]);
+        let timeout = Duration::from_secs(5);
+        assert_eq!(download_image(&url, timeout, DOWNLOAD_LIMIT), Ok(png));
+        assert!(matches!(
+            download_image(&url, timeout, DOWNLOAD_LIMIT),
+            Err(DownloadError::NotAnImage(_))
+        ));
+        assert!(matches!(
+            download_image(&url, timeout, DOWNLOAD_LIMIT),
+            Err(DownloadError::NotAnImage(_))
+        ));
+        assert!(matches!(download_image(&url, timeout, 4), Err(DownloadError::Failed(_))));
+    }
+
     #[test]
     fn test_image_format_detection() {
         // PNG signature
@@ -0,0 +1,411 @@
+//! Markdown document checks
+//!
+//! Provides:
+//! - Finding links and images that point at missing local files
+//! - Finding heading anchors that don't match a heading in the document
+//! - Style checks in the spirit of markdownlint
+
+use super::export::AnchorSet;
+use super::{LineState, MarkdownTokenizer, Token, TokenType};
+use crate::config::LintConfig;
+use crate::editor::LinkTarget;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Why a link is broken
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkProblem {
+    /// The linked file doesn't exist
+    MissingFile(PathBuf),
+    /// No heading in the document has this anchor
+    MissingAnchor(String),
+}
+
+/// A broken link found in a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkDiagnostic {
+    /// Line of the link (0-indexed)
+    pub line: usize,
+    /// Character column where the link destination starts
+    pub start: usize,
+    /// Character column where the link destination ends (exclusive)
+    pub end: usize,
+    /// What is wrong with the link
+    pub problem: LinkProblem,
+}
+
+impl LinkDiagnostic {
+    /// Describe the problem for display
+    pub fn message(&self) -> String {
+        match &self.problem {
+            LinkProblem::MissingFile(path) => format!("File not found: {}", path.display()),
+            LinkProblem::MissingAnchor(anchor) => format!("Heading #{} not found", anchor),
+        }
+    }
+}
+
+/// Find links and images pointing at missing files or headings
+///
+/// Relative paths are resolved against `base_path`, the document's
+/// directory; without one only heading anchors are checked. URLs such as
+/// `http:` and `mailto:` links are skipped.
+pub fn check_links(content: &str, base_path: Option<&Path>) -> Vec<LinkDiagnostic> {
+    let lines: Vec<&str> = content.split('\n').collect();
+    let mut tokenizer = MarkdownTokenizer::new();
+    let line_tokens = tokenizer.tokenize_document(&lines);
+
+    // Headings outside code blocks, as anchors; repeats are numbered
+    let mut unique = AnchorSet::default();
+    let mut anchors = HashSet::new();
+    for (line, tokens) in lines.iter().zip(&line_tokens) {
+        if !tokens.tokens.first().is_some_and(|t| t.token_type.is_heading()) {
+            continue;
+        }
+        let trimmed = line.trim_start();
+        if let Some((_, content_start)) = MarkdownTokenizer::parse_heading(trimmed) {
+            anchors.insert(unique.unique(trimmed[content_start..].trim()));
+        } else if MarkdownTokenizer::parse_setext_underline(line).is_none() {
+            // Setext heading text; its underline has no anchor
+            anchors.insert(unique.unique(trimmed.trim()));
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    for (line_num, (line, tokens)) in lines.iter().zip(&line_tokens).enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        for token in tokens.tokens.iter().flat_map(Token::styles) {
+            if !matches!(token.token_type, TokenType::LinkUrl | TokenType::ImageUrl) {
+                continue;
+            }
+
+            // Drop the surrounding parentheses
+            let start = (token.start + 1).min(chars.len());
+            let end = token.end.saturating_sub(1).clamp(start, chars.len());
+            let destination: String = chars[start..end].iter().collect();
+
+            let problem = match LinkTarget::parse(&destination) {
+                Some(LinkTarget::Anchor(anchor)) => (!anchors.contains(&anchor.to_lowercase()))
+                    .then_some(LinkProblem::MissingAnchor(anchor)),
+                Some(LinkTarget::File { path, .. }) => base_path
+                    .map(|base| base.join(&path))
+                    .filter(|resolved| !resolved.exists())
+                    .map(|_| LinkProblem::MissingFile(path)),
+                Some(LinkTarget::Url(_) | LinkTarget::Note(_)) | None => None,
+            };
+            if let Some(problem) = problem {
+                diagnostics.push(LinkDiagnostic {
+                    line: line_num,
+                    start,
+                    end,
+                    problem,
+                });
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// A style rule checked by the linter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintRule {
+    /// More than one blank line in a row
+    MultipleBlankLines,
+    /// Heading without a blank line above or below
+    BlankAroundHeadings,
+    /// Whitespace at the end of a line
+    TrailingSpaces,
+    /// Bullet marker different from the document's first one
+    ListMarkerStyle,
+}
+
+impl LintRule {
+    /// Get the markdownlint rule code
+    pub fn code(&self) -> &'static str {
+        match self {
+            LintRule::MultipleBlankLines => "MD012",
+            LintRule::BlankAroundHeadings => "MD022",
+            LintRule::TrailingSpaces => "MD009",
+            LintRule::ListMarkerStyle => "MD004",
+        }
+    }
+
+    /// Check whether the rule is turned on
+    pub fn is_enabled(&self, config: &LintConfig) -> bool {
+        match self {
+            LintRule::MultipleBlankLines => config.multiple_blank_lines,
+            LintRule::BlankAroundHeadings => config.blank_around_headings,
+            LintRule::TrailingSpaces => config.trailing_spaces,
+            LintRule::ListMarkerStyle => config.list_marker_style,
+        }
+    }
+}
+
+/// A style issue found by the linter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Line of the issue (0-indexed)
+    pub line: usize,
+    /// Character column of the issue (0-indexed)
+    pub col: usize,
+    /// Rule that flagged the issue
+    pub rule: LintRule,
+    /// Description for display
+    pub message: String,
+}
+
+/// What kind of construct a line is, as far as the rules care
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Blank,
+    Heading,
+    /// Bullet list item with its marker and the marker's column
+    ListItem(char, usize),
+    /// Inside (or fencing) a code block, front matter, or math block
+    Code,
+    Other,
+}
+
+/// Per-line facts the rules are checked against
+#[derive(Debug, Clone, Copy)]
+struct LineFacts {
+    kind: LineKind,
+    /// Column where disallowed trailing whitespace starts
+    trailing: Option<usize>,
+    end_state: LineState,
+}
+
+/// Incremental markdown linter
+///
+/// Line facts are cached by line content and starting state, so after an
+/// edit only the changed lines are analyzed again; the rules themselves are
+/// a cheap pass over the cached facts.
+pub struct Linter {
+    tokenizer: MarkdownTokenizer,
+    cache: HashMap<(u64, LineState, bool), LineFacts>,
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self {
+            tokenizer: MarkdownTokenizer::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Check a document against the enabled rules
+    pub fn lint(&mut self, content: &str, config: &LintConfig) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = content.split('\n').collect();
+
+        // Collect line facts, keeping only cache entries still in use
+        let mut cache = HashMap::with_capacity(lines.len());
+        let mut facts = Vec::with_capacity(lines.len());
+        let mut state = LineState::Normal;
+        for (i, line) in lines.iter().enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            // Front matter can only open on the first line
+            let key = (hash_line(line), state, i == 0);
+            let line_facts = match self.cache.remove(&key).or_else(|| cache.get(&key).copied()) {
+                Some(cached) => cached,
+                None => self.analyze(line, state, i == 0),
+            };
+            cache.insert(key, line_facts);
+            facts.push(line_facts);
+            state = line_facts.end_state;
+        }
+        self.cache = cache;
+
+        let enabled = |rule: LintRule| rule.is_enabled(config);
+        let mut diagnostics = Vec::new();
+        let mut push = |line: usize, col: usize, rule: LintRule, message: String| {
+            diagnostics.push(Diagnostic { line, col, rule, message });
+        };
+
+        let mut blank_run = 0;
+        let mut list_marker = None;
+        for (i, line) in facts.iter().enumerate() {
+            if let Some(col) = line.trailing.filter(|_| enabled(LintRule::TrailingSpaces)) {
+                push(i, col, LintRule::TrailingSpaces, "Trailing whitespace".to_string());
+            }
+
+            blank_run = if line.kind == LineKind::Blank { blank_run + 1 } else { 0 };
+            if blank_run > 1 && enabled(LintRule::MultipleBlankLines) {
+                let message = "Multiple consecutive blank lines".to_string();
+                push(i, 0, LintRule::MultipleBlankLines, message);
+            }
+
+            match line.kind {
+                LineKind::Heading if enabled(LintRule::BlankAroundHeadings) => {
+                    if i > 0 && facts[i - 1].kind != LineKind::Blank {
+                        push(
+                            i,
+                            0,
+                            LintRule::BlankAroundHeadings,
+                            "Heading should have a blank line above".to_string(),
+                        );
+                    }
+                    if facts.get(i + 1).is_some_and(|next| next.kind != LineKind::Blank) {
+                        push(
+                            i,
+                            0,
+                            LintRule::BlankAroundHeadings,
+                            "Heading should have a blank line below".to_string(),
+                        );
+                    }
+                }
+                LineKind::ListItem(marker, col) if enabled(LintRule::ListMarkerStyle) => {
+                    match list_marker {
+                        None => list_marker = Some(marker),
+                        Some(expected) if expected != marker => push(
+                            i,
+                            col,
+                            LintRule::ListMarkerStyle,
+                            format!("List marker '{}' should be '{}'", marker, expected),
+                        ),
+                        Some(_) => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Work out the facts for one line
+    fn analyze(&mut self, line: &str, start_state: LineState, first_line: bool) -> LineFacts {
+        // Tokenize the line on its own, without the tokenizer's line cache
+        self.tokenizer.clear_cache();
+        let line_num = if first_line { 0 } else { 1 };
+        let end_state = self.tokenizer.tokenize_line(line_num, line, start_state).end_state;
+
+        let trimmed = line.trim_end_matches([' ', '\t']);
+        let trailing_len = line.len() - trimmed.len();
+        let line_break = trailing_len == 2 && !trimmed.is_empty() && line.ends_with("  ");
+        let trailing = (trailing_len > 0 && !line_break).then(|| trimmed.chars().count());
+
+        let content = line.trim_start();
+        let indent = line.chars().count() - content.chars().count();
+        let kind = if start_state != LineState::Normal || end_state != LineState::Normal {
+            LineKind::Code
+        } else if content.is_empty() {
+            LineKind::Blank
+        } else if MarkdownTokenizer::parse_heading(content).is_some() {
+            LineKind::Heading
+        } else {
+            match content.chars().next() {
+                Some(marker @ ('-' | '*' | '+'))
+                    if content[1..].starts_with([' ', '\t']) && !is_thematic_break(content) =>
+                {
+                    LineKind::ListItem(marker, indent)
+                }
+                _ => LineKind::Other,
+            }
+        };
+
+        LineFacts {
+            kind,
+            trailing,
+            end_state,
+        }
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hash a line for the fact cache
+fn hash_line(line: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Check whether a line is a `---`, `***`, or `___` rule rather than a list item
+fn is_thematic_break(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && marks.iter().all(|&c| c == marks[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_links() {
+        let dir = std::env::temp_dir().join(format!("cosmic-notebook-lint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("present.md"), "").unwrap();
+
+        let content = "# Intro Section\n\
+            [ok](present.md) [gone](missing.md#top) ![img](images/none.png)\n\
+            [here](#intro-section) [nowhere](#outro)\n\
+            [web](https://example.com/none.md) [mail](mailto:me@example.com)\n\
+            ```\n[code](not-a-link.md)\n```";
+        let diagnostics = check_links(content, Some(&dir));
+        std::fs::remove_dir_all(&dir).ok();
+
+        let problems: Vec<_> = diagnostics.iter().map(|d| (d.line, d.problem.clone())).collect();
+        assert_eq!(
+            problems,
+            vec![
+                (1, LinkProblem::MissingFile(PathBuf::from("missing.md"))),
+                (1, LinkProblem::MissingFile(PathBuf::from("images/none.png"))),
+                (2, LinkProblem::MissingAnchor("outro".to_string())),
+            ]
+        );
+        assert_eq!(&content.lines().nth(1).unwrap()[diagnostics[0].start..diagnostics[0].end], "missing.md#top");
+    }
+
+    #[test]
+    fn test_check_links_without_base() {
+        let diagnostics = check_links("[a](missing.md) [b](#nope)", None);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message(), "Heading #nope not found");
+
+        // Setext headings are anchors too
+        assert!(check_links("Setup Guide\n---\n\n[a](#setup-guide)", None).is_empty());
+    }
+
+    #[test]
+    fn test_lint_rules() {
+        let config = LintConfig { enabled: true, ..LintConfig::default() };
+        let content = "# Title\nText right below  \n\n\n- one \n* two\n\n```\n\n\n## not a heading\n- x\n```\n## Tail";
+        let diagnostics = Linter::new().lint(content, &config);
+        let found: Vec<_> = diagnostics.iter().map(|d| (d.line, d.col, d.rule)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (0, 0, LintRule::BlankAroundHeadings),
+                (3, 0, LintRule::MultipleBlankLines),
+                (4, 5, LintRule::TrailingSpaces),
+                (5, 0, LintRule::ListMarkerStyle),
+                (13, 0, LintRule::BlankAroundHeadings),
+            ]
+        );
+
+        let config = LintConfig { trailing_spaces: false, ..config };
+        assert!(Linter::new()
+            .lint(content, &config)
+            .iter()
+            .all(|d| d.rule != LintRule::TrailingSpaces));
+    }
+
+    #[test]
+    fn test_lint_reuses_cache() {
+        let config = LintConfig::default();
+        let mut linter = Linter::new();
+        let first = linter.lint("# A\n\ntext\n\n\n", &config);
+        assert_eq!(linter.cache.len(), 3);
+
+        let second = linter.lint("# A\n\ntext\n\n\n", &config);
+        assert_eq!(first, second);
+
+        linter.lint("# B", &config);
+        assert_eq!(linter.cache.len(), 1);
+    }
+}
@@ -0,0 +1,478 @@
+//! LaTeX math support for Markdown
+//!
+//! pulldown-cmark has no math extension, so math spans are extracted from the
+//! source before parsing and replaced with placeholders:
+//! - Inline math: `$...$` closed on the same line
+//! - Display math: `$$...$$` on its own line(s)
+//! - `\$` and a lone `$` (e.g. "$5") are left as plain text
+//! - Only paragraph, heading and table text is searched, so code blocks,
+//!   HTML, code spans and link destinations keep their `$` as written
+//!
+//! Display math becomes an HTML comment, parsed as an HTML block of its
+//! own. Inline math becomes a token of private-use characters instead, as
+//! a comment starting a line would open an HTML block and swallow the rest
+//! of the line. [`split_placeholders`] turns the tokens in parsed text into
+//! the same comments, so later stages only look for those. Both wrap the
+//! span index in private-use characters, so a comment typed in the document
+//! is never taken for a placeholder.
+
+use std::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, LinkType, Options, Parser, Tag};
+
+/// Characters around the index of a math token
+const TOKEN_OPEN: char = '\u{E000}';
+const TOKEN_CLOSE: char = '\u{E001}';
+
+/// KaTeX assets included in exported HTML
+const KATEX_BASE_URL: &str = "https://cdn.jsdelivr.net/npm/katex@0.16/dist";
+
+/// A math span extracted from Markdown source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MathSpan {
+    /// Whether the span is inline (`$...$`) or display (`$$...$$`)
+    pub inline: bool,
+    /// The LaTeX source without delimiters
+    pub source: String,
+}
+
+impl MathSpan {
+    /// Render the span as HTML for KaTeX auto-render
+    pub fn to_html(&self) -> String {
+        if self.inline {
+            format!("<span class=\"math math-inline\">\\({}\\)</span>", escape_html(&self.source))
+        } else {
+            format!("<div class=\"math math-display\">\\[{}\\]</div>\n", escape_html(&self.source))
+        }
+    }
+}
+
+/// Byte ranges of Markdown source where math may appear
+struct Regions {
+    /// Paragraphs, headings and table cells
+    text: Vec<Range<usize>>,
+    /// Code spans, inline HTML and link destinations inside that text
+    excluded: Vec<Range<usize>>,
+}
+
+impl Regions {
+    /// Find the regions from the parsed source
+    fn scan(markdown: &str) -> Self {
+        let mut regions = Self { text: Vec::new(), excluded: Vec::new() };
+        // Open links and images: (whole range, end of their text so far)
+        let mut links: Vec<(Range<usize>, usize)> = Vec::new();
+
+        for (event, range) in Parser::new_ext(markdown, Options::all()).into_offset_iter() {
+            let end = range.end;
+            match event {
+                Event::Start(Tag::Paragraph | Tag::Heading(..) | Tag::TableCell) => {
+                    regions.text.push(range);
+                }
+                Event::Start(Tag::Link(..) | Tag::Image(..)) => {
+                    let start = range.start;
+                    links.push((range, start));
+                    continue;
+                }
+                Event::End(Tag::Link(kind, ..) | Tag::Image(kind, ..)) => {
+                    if let Some((whole, text_end)) = links.pop() {
+                        // An autolink is all destination
+                        let start = match kind {
+                            LinkType::Autolink | LinkType::Email => whole.start,
+                            _ => text_end,
+                        };
+                        regions.excluded.push(start..whole.end);
+                    }
+                }
+                Event::Code(_) | Event::Html(_) => regions.excluded.push(range),
+                _ => {}
+            }
+            if let Some((_, text_end)) = links.last_mut() {
+                *text_end = (*text_end).max(end);
+            }
+        }
+
+        regions
+    }
+
+    /// Whether a span of source lies in one text region, clear of exclusions
+    fn allows(&self, span: Range<usize>) -> bool {
+        self.text.iter().any(|text| text.start <= span.start && span.end <= text.end)
+            && !self
+                .excluded
+                .iter()
+                .any(|excluded| excluded.start < span.end && span.start < excluded.end)
+    }
+}
+
+/// Extract math spans from Markdown, returning the rewritten source and the spans
+pub fn extract(markdown: &str) -> (String, Vec<MathSpan>) {
+    let regions = Regions::scan(markdown);
+    let mut output = String::with_capacity(markdown.len());
+    let mut spans = Vec::new();
+    // Display block being collected: (source, raw lines)
+    let mut block: Option<(String, String)> = None;
+    let mut offset = 0;
+
+    for raw_line in markdown.split_inclusive('\n') {
+        let line_start = offset;
+        offset += raw_line.len();
+        let line = raw_line.trim_end_matches('\n').trim_end_matches('\r');
+        let newline = &raw_line[line.len()..];
+        let trimmed = line.trim();
+
+        // Inside a display block
+        if let Some((ref mut source, ref mut raw)) = block {
+            raw.push_str(raw_line);
+            if let Some(before) = trimmed.strip_suffix("$$") {
+                source.push_str(before);
+                spans.push(MathSpan { inline: false, source: source.trim().to_string() });
+                output.push_str(&placeholder(spans.len() - 1));
+                output.push_str(newline);
+                block = None;
+            } else {
+                source.push_str(line);
+                source.push('\n');
+            }
+            continue;
+        }
+
+        // Display math opening line, in text rather than code or HTML
+        let indent = line_start + line.len() - line.trim_start().len();
+        if trimmed.starts_with("$$") && regions.allows(indent..indent + 2) {
+            let rest = &trimmed[2..];
+            if rest.len() >= 2 && rest.ends_with("$$") {
+                spans.push(MathSpan {
+                    inline: false,
+                    source: rest[..rest.len() - 2].trim().to_string(),
+                });
+                output.push_str(&placeholder(spans.len() - 1));
+                output.push_str(newline);
+            } else {
+                let mut source = String::from(rest);
+                if !rest.is_empty() {
+                    source.push('\n');
+                }
+                block = Some((source, raw_line.to_string()));
+            }
+            continue;
+        }
+
+        output.push_str(&extract_inline(line, line_start, &regions, &mut spans));
+        output.push_str(newline);
+    }
+
+    // An unclosed display block is plain text
+    if let Some((_, raw)) = block {
+        output.push_str(&raw);
+    }
+
+    (output, spans)
+}
+
+/// Replace inline math in a single line, starting at byte `line_start` of
+/// the source, with placeholders
+fn extract_inline(
+    line: &str,
+    line_start: usize,
+    regions: &Regions,
+    spans: &mut Vec<MathSpan>,
+) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    // Source byte offset of each character, and of the line end
+    let offsets: Vec<usize> = line
+        .char_indices()
+        .map(|(index, _)| line_start + index)
+        .chain(std::iter::once(line_start + line.len()))
+        .collect();
+    let mut output = String::with_capacity(line.len());
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        match chars[pos] {
+            // Escapes (including \$) are passed through for the parser
+            '\\' if pos + 1 < chars.len() => {
+                output.push(chars[pos]);
+                output.push(chars[pos + 1]);
+                pos += 2;
+            }
+            // Display delimiters mid-line are not math
+            '$' if chars.get(pos + 1) == Some(&'$') => {
+                let run = chars[pos..].iter().take_while(|c| **c == '$').count();
+                output.extend(&chars[pos..pos + run]);
+                pos += run;
+            }
+            '$' => {
+                let found = find_inline_math(&chars, pos)
+                    .filter(|end| regions.allows(offsets[pos]..offsets[*end]));
+                if let Some(end) = found {
+                    spans.push(MathSpan {
+                        inline: true,
+                        source: chars[pos + 1..end - 1].iter().collect(),
+                    });
+                    output.push_str(&inline_placeholder(spans.len() - 1));
+                    pos = end;
+                } else {
+                    output.push('$');
+                    pos += 1;
+                }
+            }
+            c => {
+                output.push(c);
+                pos += 1;
+            }
+        }
+    }
+
+    output
+}
+
+/// Find the end (exclusive) of inline math starting at a `$`
+///
+/// The content must not start or end with whitespace, and the closing `$`
+/// must not be followed by a digit, so prices like "$5 and $6" are not math.
+pub fn find_inline_math(chars: &[char], start: usize) -> Option<usize> {
+    if chars.get(start) != Some(&'$') || chars.get(start + 1) == Some(&'$') {
+        return None;
+    }
+    match chars.get(start + 1) {
+        Some(c) if !c.is_whitespace() => {}
+        _ => return None,
+    }
+
+    let mut pos = start + 1;
+    while pos < chars.len() {
+        match chars[pos] {
+            '\\' => pos += 2,
+            '$' => {
+                let closes = !chars[pos - 1].is_whitespace()
+                    && !chars.get(pos + 1).map(|c| c.is_ascii_digit() || *c == '$').unwrap_or(false);
+                if closes {
+                    return Some(pos + 1);
+                }
+                pos += 1;
+            }
+            _ => pos += 1,
+        }
+    }
+
+    None
+}
+
+/// Find the end (exclusive) of a code span whose opening run ends at `start`
+pub(super) fn find_code_span_end(chars: &[char], start: usize, run: usize) -> Option<usize> {
+    let mut pos = start;
+    while pos < chars.len() {
+        if chars[pos] == '`' {
+            let count = chars[pos..].iter().take_while(|c| **c == '`').count();
+            if count == run {
+                return Some(pos + count);
+            }
+            pos += count;
+        } else {
+            pos += 1;
+        }
+    }
+    None
+}
+
+/// Detect a code fence line, returning its marker character and length
+pub(super) fn code_fence(trimmed: &str) -> Option<(char, usize)> {
+    let first = trimmed.chars().next()?;
+    if first != '`' && first != '~' {
+        return None;
+    }
+    let count = trimmed.chars().take_while(|c| *c == first).count();
+    if count >= 3 {
+        Some((first, count))
+    } else {
+        None
+    }
+}
+
+/// Build the placeholder comment for a span index
+pub(super) fn placeholder(index: usize) -> String {
+    format!("<!--{}-->", inline_placeholder(index))
+}
+
+/// Build the private-use token for an inline span index
+fn inline_placeholder(index: usize) -> String {
+    format!("{}{}{}", TOKEN_OPEN, index, TOKEN_CLOSE)
+}
+
+/// Split the inline math tokens out of a text event as placeholder HTML
+/// events, leaving other events as they are
+pub fn split_placeholders(event: Event<'_>) -> Vec<Event<'_>> {
+    let Event::Text(text) = event else {
+        return vec![event];
+    };
+    if !text.contains(TOKEN_OPEN) {
+        return vec![Event::Text(text)];
+    }
+
+    let mut events = Vec::new();
+    let mut rest: &str = &text;
+    while let Some(open) = rest.find(TOKEN_OPEN) {
+        let after = &rest[open + TOKEN_OPEN.len_utf8()..];
+        let Some(close) = after.find(TOKEN_CLOSE) else {
+            break;
+        };
+        let Ok(index) = after[..close].parse::<usize>() else {
+            break;
+        };
+        if open > 0 {
+            events.push(Event::Text(CowStr::from(rest[..open].to_string())));
+        }
+        events.push(Event::Html(CowStr::from(placeholder(index))));
+        rest = &after[close + TOKEN_CLOSE.len_utf8()..];
+    }
+    if !rest.is_empty() {
+        events.push(Event::Text(CowStr::from(rest.to_string())));
+    }
+    events
+}
+
+/// Get the span index from a placeholder HTML event, if it is one
+pub fn placeholder_index(html: &str) -> Option<usize> {
+    html.trim()
+        .strip_prefix("<!--")?
+        .strip_suffix("-->")?
+        .strip_prefix(TOKEN_OPEN)?
+        .strip_suffix(TOKEN_CLOSE)?
+        .parse()
+        .ok()
+}
+
+/// Replace a placeholder event with the rendered math HTML
+pub fn html_event<'a>(event: Event<'a>, spans: &[MathSpan]) -> Event<'a> {
+    if let Event::Html(ref html) = event {
+        if let Some(span) = placeholder_index(html).and_then(|i| spans.get(i)) {
+            return Event::Html(CowStr::from(span.to_html()));
+        }
+    }
+    event
+}
+
+/// Build the KaTeX stylesheet and scripts for exported HTML
+pub fn html_scripts() -> String {
+    format!(
+        r#"<link rel="stylesheet" href="{base}/katex.min.css">
+    <script defer src="{base}/katex.min.js"></script>
+    <script defer src="{base}/contrib/auto-render.min.js"
+        onload="renderMathInElement(document.body, {{ delimiters: [{{ left: '\\[', right: '\\]', display: true }}, {{ left: '\\(', right: '\\)', display: false }}], throwOnError: false }});"></script>"#,
+        base = KATEX_BASE_URL
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_inline_math() {
+        let (output, spans) = extract("Euler: $e^{i\\pi} + 1 = 0$ done");
+        assert_eq!(output, "Euler: \u{E000}0\u{E001} done");
+        assert_eq!(spans, vec![MathSpan { inline: true, source: "e^{i\\pi} + 1 = 0".to_string() }]);
+    }
+
+    #[test]
+    fn test_prices_are_not_math() {
+        let (output, spans) = extract("It costs $5, or $6 with tax. Escaped \\$x\\$ too.");
+        assert!(spans.is_empty());
+        assert_eq!(output, "It costs $5, or $6 with tax. Escaped \\$x\\$ too.");
+    }
+
+    #[test]
+    fn test_inline_math_requires_same_line() {
+        let (_, spans) = extract("Start $x\nend$ here");
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn test_extract_display_math() {
+        let (output, spans) = extract("Before\n\n$$\na^2 + b^2 = c^2\n$$\n\nAfter $$x$$\n");
+        assert_eq!(output, format!("Before\n\n{}\n\nAfter $$x$$\n", placeholder(0)));
+        assert_eq!(spans, vec![MathSpan { inline: false, source: "a^2 + b^2 = c^2".to_string() }]);
+    }
+
+    #[test]
+    fn test_code_is_not_math() {
+        let markdown = "`$x$` and\n```\n$y$\n```\n";
+        let (output, spans) = extract(markdown);
+        assert!(spans.is_empty());
+        assert_eq!(output, markdown);
+    }
+
+    #[test]
+    fn test_inline_math_starting_a_line() {
+        use pulldown_cmark::Parser;
+
+        let (source, spans) = extract("$x$ rest\nmore");
+        let events: Vec<Event> = Parser::new(&source).flat_map(split_placeholders).collect();
+        assert_eq!(spans, vec![MathSpan { inline: true, source: "x".to_string() }]);
+        assert!(matches!(events[0], Event::Start(pulldown_cmark::Tag::Paragraph)));
+        assert_eq!(events[1], Event::Html(CowStr::from(placeholder(0))));
+        assert_eq!(events[2], Event::Text(CowStr::from(" rest")));
+
+        let html: Vec<Event> = events.into_iter().map(|event| html_event(event, &spans)).collect();
+        let mut output = String::new();
+        pulldown_cmark::html::push_html(&mut output, html.into_iter());
+        assert_eq!(output, "<p><span class=\"math math-inline\">\\(x\\)</span> rest\nmore</p>\n");
+    }
+
+    #[test]
+    fn test_code_blocks_in_containers_are_not_math() {
+        for markdown in [
+            "Text\n\n    $x$ indented\n\tand $y$ tabbed\n",
+            "> ```\n> $x$\n> ```\n",
+            "- item\n\n  ```\n  $x$\n  ```\n",
+        ] {
+            let (output, spans) = extract(markdown);
+            assert!(spans.is_empty(), "{markdown:?}");
+            assert_eq!(output, markdown);
+        }
+    }
+
+    #[test]
+    fn test_html_is_not_math() {
+        let markdown = "<div>\n$x$ here\n</div>\n\nA <span title=\"$y$\">tip</span>\n";
+        let (output, spans) = extract(markdown);
+        assert!(spans.is_empty());
+        assert_eq!(output, markdown);
+    }
+
+    #[test]
+    fn test_link_destinations_are_not_math() {
+        let markdown = "[x](https://h/$a$b) <https://h/$c$d> [$y$](https://h/$e$f)\n";
+        let (output, spans) = extract(markdown);
+        assert_eq!(spans, vec![MathSpan { inline: true, source: "y".to_string() }]);
+        let expected = format!(
+            "[x](https://h/$a$b) <https://h/$c$d> [{}](https://h/$e$f)\n",
+            inline_placeholder(0)
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_math_in_blockquote() {
+        let (output, spans) = extract("> Quote $x$\n");
+        assert_eq!(spans, vec![MathSpan { inline: true, source: "x".to_string() }]);
+        assert_eq!(output, format!("> Quote {}\n", inline_placeholder(0)));
+    }
+
+    #[test]
+    fn test_placeholder_index() {
+        assert_eq!(placeholder_index(&format!("{}\n", placeholder(3))), Some(3));
+        assert_eq!(placeholder_index("<!-- comment -->"), None);
+        // A typed comment in the old placeholder form is left alone
+        assert_eq!(placeholder_index("<!--math:0-->"), None);
+        let spans = vec![MathSpan { inline: true, source: "x".to_string() }];
+        let event = Event::Html(CowStr::from("<!--math:0-->"));
+        assert_eq!(html_event(event.clone(), &spans), event);
+    }
+}
@@ -6,32 +6,71 @@
 //! - Preview rendering
 //! - Image handling
 //! - Export functionality (HTML, PDF)
+//! - Diagram rendering (mermaid)
+//! - LaTeX math
+//! - Footnote numbering
+//! - Callouts (`> [!NOTE]` admonitions)
+//! - HTML import for pasting rich text
+//! - Sanitizing raw HTML from untrusted notes
+//! - Wiki-style `[[Note]]` links between notes
+//! - Broken link checks
 
 pub mod syntax;
 pub mod preview;
 pub mod image;
 pub mod export;
+pub mod diagram;
+pub mod math;
+pub mod footnote;
+pub mod callout;
+pub mod html_import;
+pub mod sanitize;
+pub mod wiki_link;
+pub mod lint;
 
 pub use syntax::{
     MarkdownTokenizer, Token, TokenType, TokenStyle,
     SyntaxColorScheme, LineState, LineTokens,
 };
+pub use math::MathSpan;
+pub use callout::CalloutKind;
+pub use wiki_link::{NoteIndex, WikiLink};
+pub use html_import::html_to_markdown;
+pub use lint::{check_links, Diagnostic, LinkDiagnostic, LinkProblem, LintRule, Linter};
 pub use preview::{
-    ViewModeExt, PreviewRenderer, PreviewElement, StyledText,
+    parser_options, ViewModeExt, PreviewRenderer, PreviewElement, StyledText,
     ListItem, TaskItem, TableAlignment, HtmlExporter,
 };
 pub use image::{
-    ImageHandler, ImageConfig, ImageFormat, ImageError, ImageResult,
+    DownloadError, ImageHandler, ImageConfig, ImageFormat, ImageError, ImageResult, ImageSize,
 };
+pub use diagram::{DiagramKind, DiagramOutput, DiagramRenderer};
 pub use export::{
-    MarkdownExporter, ExportFormat, HtmlExportOptions, ExportError, ExportResult,
+    MarkdownExporter, ExportFormat, HtmlExportOptions, EpubExportOptions, ExportError, ExportResult,
 };
 
+use crate::config::MarkdownOptions;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+/// Number of rendered previews kept for reuse
+const PREVIEW_CACHE_SIZE: usize = 4;
+
 /// Main Markdown renderer combining tokenization and preview
 pub struct MarkdownRenderer {
     tokenizer: MarkdownTokenizer,
     preview_renderer: PreviewRenderer,
     color_scheme: SyntaxColorScheme,
+    custom_color_scheme: Option<SyntaxColorScheme>,
+    markdown_options: MarkdownOptions,
+    /// Directory relative URLs in the preview resolve against
+    base_path: Option<PathBuf>,
+    /// Render soft breaks as line breaks
+    soft_break_as_newline: bool,
+    /// Notes wiki links resolve against
+    notes: NoteIndex,
+    /// Recent previews by content and options key, most recent first
+    preview_cache: VecDeque<(u64, Vec<PreviewElement>)>,
 }
 
 impl MarkdownRenderer {
@@ -40,10 +79,68 @@ impl MarkdownRenderer {
             tokenizer: MarkdownTokenizer::new(),
             preview_renderer: PreviewRenderer::new(),
             color_scheme: SyntaxColorScheme::light(),
+            custom_color_scheme: None,
+            markdown_options: MarkdownOptions::default(),
+            base_path: None,
+            soft_break_as_newline: false,
+            notes: NoteIndex::default(),
+            preview_cache: VecDeque::new(),
+        }
+    }
+    
+    /// Set which markdown extensions are enabled
+    ///
+    /// Previews rendered with the old options are stale, so caches are cleared.
+    pub fn set_markdown_options(&mut self, options: MarkdownOptions) {
+        if options == self.markdown_options {
+            return;
+        }
+        self.markdown_options = options;
+        self.rebuild_preview_renderer();
+        self.clear_cache();
+    }
+
+    /// Set the directory relative URLs in the preview resolve against
+    pub fn set_base_path(&mut self, path: Option<PathBuf>) {
+        if path == self.base_path {
+            return;
         }
+        self.base_path = path;
+        self.rebuild_preview_renderer();
+    }
+
+    /// Render single newlines within a paragraph as line breaks
+    pub fn set_soft_break_as_newline(&mut self, enabled: bool) {
+        if enabled == self.soft_break_as_newline {
+            return;
+        }
+        self.soft_break_as_newline = enabled;
+        self.rebuild_preview_renderer();
+    }
+
+    /// Set the notes wiki links resolve against
+    ///
+    /// Links may now resolve differently, so previews are dropped.
+    pub fn set_notes(&mut self, notes: NoteIndex) {
+        self.notes = notes;
+        self.rebuild_preview_renderer();
+        self.preview_cache.clear();
+    }
+
+    fn rebuild_preview_renderer(&mut self) {
+        let renderer = PreviewRenderer::new()
+            .with_markdown_options(&self.markdown_options)
+            .with_soft_break_as_newline(self.soft_break_as_newline)
+            .with_notes(self.notes.clone());
+        self.preview_renderer = match &self.base_path {
+            Some(path) => renderer.with_base_path(path),
+            None => renderer,
+        };
     }
     
     /// Set the color scheme for syntax highlighting
+    ///
+    /// A custom color scheme, if one is loaded, stays in use.
     pub fn set_color_scheme(&mut self, dark_mode: bool) {
         self.color_scheme = if dark_mode {
             SyntaxColorScheme::dark()
@@ -51,10 +148,15 @@ impl MarkdownRenderer {
             SyntaxColorScheme::light()
         };
     }
+
+    /// Use a custom color scheme instead of the built-in light and dark ones
+    pub fn set_custom_color_scheme(&mut self, scheme: Option<SyntaxColorScheme>) {
+        self.custom_color_scheme = scheme;
+    }
     
     /// Get the current color scheme
     pub fn color_scheme(&self) -> &SyntaxColorScheme {
-        &self.color_scheme
+        self.custom_color_scheme.as_ref().unwrap_or(&self.color_scheme)
     }
     
     /// Get the tokenizer
@@ -73,24 +175,74 @@ impl MarkdownRenderer {
     }
     
     /// Render markdown to preview elements
-    pub fn render_preview(&self, markdown: &str) -> Vec<PreviewElement> {
-        self.preview_renderer.render(markdown)
+    ///
+    /// The last few previews are cached by content, extensions, base path
+    /// and line break handling, so rendering an unchanged document again is free.
+    pub fn render_preview(&mut self, markdown: &str) -> &[PreviewElement] {
+        let key = self.preview_key(markdown);
+        match self.preview_cache.iter().position(|(cached, _)| *cached == key) {
+            Some(index) => {
+                let entry = self.preview_cache.remove(index).expect("index from position");
+                self.preview_cache.push_front(entry);
+            }
+            None => {
+                let elements = self.preview_renderer.render(markdown);
+                self.preview_cache.truncate(PREVIEW_CACHE_SIZE - 1);
+                self.preview_cache.push_front((key, elements));
+            }
+        }
+        &self.preview_cache[0].1
+    }
+
+    /// Preview cache key for a document under the current options
+    fn preview_key(&self, markdown: &str) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        markdown.hash(&mut hasher);
+        self.markdown_options.hash(&mut hasher);
+        self.base_path.hash(&mut hasher);
+        self.soft_break_as_newline.hash(&mut hasher);
+        hasher.finish()
     }
 
     /// Render markdown to HTML
     pub fn render_html(&self, markdown: &str) -> String {
-        let exporter = HtmlExporter::new();
+        let exporter = HtmlExporter::new()
+            .with_markdown_options(&self.markdown_options)
+            .with_soft_break_as_newline(self.soft_break_as_newline)
+            .with_notes(self.notes.clone());
         exporter.export(markdown, None)
     }
     
+    /// Render markdown to an HTML fragment for rich-text copies
+    ///
+    /// Local images are embedded so they survive pasting into other apps.
+    pub fn render_html_fragment(&self, markdown: &str) -> String {
+        let exporter = HtmlExporter::new()
+            .with_markdown_options(&self.markdown_options)
+            .with_soft_break_as_newline(self.soft_break_as_newline)
+            .with_embedded_images(true)
+            .with_notes(self.notes.clone());
+        let exporter = match &self.base_path {
+            Some(path) => exporter.with_base_path(path),
+            None => exporter,
+        };
+        exporter.export_fragment(markdown)
+    }
+    
     /// Invalidate cache from a specific line
+    ///
+    /// The most recent preview belongs to the document being edited, so it
+    /// is dropped as well.
     pub fn invalidate_from_line(&mut self, line_num: usize) {
         self.tokenizer.invalidate_from_line(line_num);
+        self.preview_cache.pop_front();
     }
     
     /// Clear all caches
     pub fn clear_cache(&mut self) {
         self.tokenizer.clear_cache();
+        self.preview_cache.clear();
     }
 }
 
@@ -100,3 +252,34 @@ impl Default for MarkdownRenderer {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_cache() {
+        let mut renderer = MarkdownRenderer::new();
+        let first = renderer.render_preview("# Title\n\n~~gone~~").as_ptr();
+        assert_eq!(renderer.render_preview("# Title\n\n~~gone~~").as_ptr(), first);
+
+        // Toggling an extension renders again
+        renderer.set_markdown_options(MarkdownOptions {
+            strikethrough: false,
+            ..MarkdownOptions::default()
+        });
+        renderer.render_preview("# Title\n\n~~gone~~");
+        assert_eq!(renderer.preview_cache.len(), 1);
+
+        // Only the most recent documents are kept
+        for i in 0..10 {
+            renderer.render_preview(&format!("doc {}", i));
+        }
+        assert_eq!(renderer.preview_cache.len(), PREVIEW_CACHE_SIZE);
+
+        renderer.set_base_path(Some(PathBuf::from("/docs")));
+        renderer.render_preview("doc 9");
+        assert_eq!(renderer.preview_cache.len(), PREVIEW_CACHE_SIZE);
+        renderer.clear_cache();
+        assert!(renderer.preview_cache.is_empty());
+    }
+}
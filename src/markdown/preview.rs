@@ -3,8 +3,18 @@
 //! This module provides functionality to render Markdown content as a rich preview
 //! using pulldown-cmark for parsing and cosmic/iced widgets for display.
 
+pub mod server;
+
 use std::path::{Path, PathBuf};
 use pulldown_cmark::{Parser, Event, Tag, Options, CodeBlockKind, HeadingLevel, CowStr};
+use super::callout::{self, CalloutKind};
+use super::diagram::{self, DiagramKind, DiagramOutput, DiagramRenderer};
+use super::footnote::{self, Footnotes};
+use super::image;
+use super::math::{self, MathSpan};
+use super::sanitize;
+use super::wiki_link::{self, NoteIndex};
+use crate::config::{MarkdownOptions, DEFAULT_PREVIEW_FONT_SIZE};
 
 // Note: ViewMode is defined in crate::config and re-exported from there
 // We extend it here with helper methods via an extension trait
@@ -61,10 +71,27 @@ pub enum PreviewElement {
         language: Option<String>,
         code: String,
     },
+    /// A diagram from a fenced code block (e.g. mermaid)
+    Diagram {
+        source: String,
+        kind: DiagramKind,
+        /// The render, or `Source` when no diagram renderer is set
+        output: DiagramOutput,
+    },
+    /// LaTeX math; inline math splits the surrounding paragraph
+    Math {
+        inline: bool,
+        source: String,
+    },
     /// An inline code span (kept separate for convenience)
     InlineCode(String),
     /// A blockquote
     Blockquote(Vec<PreviewElement>),
+    /// A blockquote opening with a `[!TYPE]` marker, without the marker
+    Callout {
+        kind: CalloutKind,
+        content: Vec<PreviewElement>,
+    },
     /// An unordered list
     UnorderedList(Vec<ListItem>),
     /// An ordered list with starting number
@@ -87,6 +114,10 @@ pub enum PreviewElement {
         alt: String,
         url: String,
         title: Option<String>,
+        /// Width in pixels from a size hint
+        width: Option<u32>,
+        /// Height in pixels from a size hint
+        height: Option<u32>,
     },
     /// A link (for block-level links)
     Link {
@@ -96,9 +127,13 @@ pub enum PreviewElement {
     },
     /// Raw HTML (display as code or render carefully)
     Html(String),
-    /// Footnote definition
+    /// Footnote definition, listed after the document in number order
+    ///
+    /// Back-links point at the anchors named by `footnote::reference_id`.
     FootnoteDefinition {
         label: String,
+        number: usize,
+        references: usize,
         content: Vec<PreviewElement>,
     },
     /// A thematic break / soft break
@@ -138,7 +173,11 @@ pub struct StyledText {
     pub italic: bool,
     pub strikethrough: bool,
     pub code: bool,
+    /// Raised text, used for footnote reference markers
+    pub superscript: bool,
     pub link: Option<String>,
+    /// The link is a wiki link to a note that doesn't exist yet
+    pub unresolved: bool,
 }
 
 impl StyledText {
@@ -149,7 +188,9 @@ impl StyledText {
             italic: false,
             strikethrough: false,
             code: false,
+            superscript: false,
             link: None,
+            unresolved: false,
         }
     }
     
@@ -173,6 +214,11 @@ impl StyledText {
         self
     }
     
+    pub fn with_superscript(mut self) -> Self {
+        self.superscript = true;
+        self
+    }
+    
     pub fn with_link(mut self, url: String) -> Self {
         self.link = Some(url);
         self
@@ -191,6 +237,8 @@ struct ParseContext {
     link_url: Option<String>,
     /// Accumulated styled text
     text_buffer: Vec<StyledText>,
+    /// Footnote numbers and references rendered so far
+    footnotes: Footnotes,
 }
 
 impl Default for ParseContext {
@@ -202,6 +250,7 @@ impl Default for ParseContext {
             code: false,
             link_url: None,
             text_buffer: Vec::new(),
+            footnotes: Footnotes::default(),
         }
     }
 }
@@ -236,49 +285,202 @@ impl ParseContext {
     }
 }
 
+/// System font stack used when no (valid) preview font is configured
+pub const DEFAULT_FONT_STACK: &str =
+    r#"-apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif"#;
+
+/// Generic CSS font families that must not be quoted
+const GENERIC_FONT_FAMILIES: &[&str] = &[
+    "serif", "sans-serif", "monospace", "cursive", "fantasy", "system-ui",
+];
+
+/// Check if a font family name is usable (non-empty, no CSS/markup syntax)
+pub fn is_valid_font_family(family: &str) -> bool {
+    let family = family.trim();
+    !family.is_empty()
+        && family.chars().all(|c| {
+            !c.is_control() && !matches!(c, '"' | '\'' | ';' | '{' | '}' | '<' | '>' | '\\' | ',')
+        })
+}
+
+/// Build a CSS `font-family` value for a configured family name
+pub fn css_font_family(family: &str) -> String {
+    let family = family.trim();
+    if !is_valid_font_family(family) {
+        return DEFAULT_FONT_STACK.to_string();
+    }
+    
+    if GENERIC_FONT_FAMILIES.contains(&family.to_lowercase().as_str()) {
+        format!("{}, {}", family.to_lowercase(), DEFAULT_FONT_STACK)
+    } else {
+        format!("\"{}\", {}", family, DEFAULT_FONT_STACK)
+    }
+}
+
+/// Build a CSS font size in pixels, falling back to the default for invalid values
+pub fn css_font_size(size: f32) -> f32 {
+    if size.is_finite() && size > 0.0 {
+        size
+    } else {
+        DEFAULT_PREVIEW_FONT_SIZE
+    }
+}
+
+/// Build parser options from the configured markdown extensions
+///
+/// Shared by the preview and the exporters so both parse identically.
+pub fn parser_options(markdown: &MarkdownOptions) -> Options {
+    let mut options = Options::empty();
+    options.set(Options::ENABLE_TABLES, markdown.tables);
+    options.set(Options::ENABLE_FOOTNOTES, markdown.footnotes);
+    options.set(Options::ENABLE_STRIKETHROUGH, markdown.strikethrough);
+    options.set(Options::ENABLE_TASKLISTS, markdown.tasklists);
+    options.set(Options::ENABLE_SMART_PUNCTUATION, markdown.smart_punctuation);
+    options
+}
+
+/// Turn a soft break into a hard break when single newlines break lines
+///
+/// Hard breaks from trailing spaces or backslashes pass through either way.
+pub fn line_break_event(event: Event<'_>, soft_break_as_newline: bool) -> Event<'_> {
+    match event {
+        Event::SoftBreak if soft_break_as_newline => Event::HardBreak,
+        event => event,
+    }
+}
+
 /// Markdown preview renderer
 pub struct PreviewRenderer {
     /// Base path for resolving relative URLs
     base_path: Option<PathBuf>,
     /// Parser options
     options: Options,
+    /// List footnotes nothing references
+    keep_unreferenced_footnotes: bool,
+    /// Pass raw HTML through the allow-list sanitizer
+    sanitize_html: bool,
+    /// Render `[[wiki links]]` as links to notes
+    wiki_links: bool,
+    /// Notes wiki links resolve against
+    notes: NoteIndex,
+    /// Render soft breaks as line breaks
+    soft_break_as_newline: bool,
+    /// Renders diagram code blocks; they are left as source without one
+    diagram_renderer: Option<DiagramRenderer>,
 }
 
 impl PreviewRenderer {
     pub fn new() -> Self {
-        let mut options = Options::empty();
-        options.insert(Options::ENABLE_TABLES);
-        options.insert(Options::ENABLE_FOOTNOTES);
-        options.insert(Options::ENABLE_STRIKETHROUGH);
-        options.insert(Options::ENABLE_TASKLISTS);
-        options.insert(Options::ENABLE_SMART_PUNCTUATION);
-        
         Self {
             base_path: None,
-            options,
+            options: parser_options(&MarkdownOptions::default()),
+            keep_unreferenced_footnotes: true,
+            sanitize_html: false,
+            wiki_links: false,
+            notes: NoteIndex::default(),
+            soft_break_as_newline: false,
+            diagram_renderer: None,
         }
     }
     
+    /// Set which markdown extensions are enabled
+    pub fn with_markdown_options(mut self, markdown: &MarkdownOptions) -> Self {
+        self.options = parser_options(markdown);
+        self.keep_unreferenced_footnotes = markdown.keep_unreferenced_footnotes;
+        self.sanitize_html = markdown.sanitize_html;
+        self.wiki_links = markdown.wiki_links;
+        self
+    }
+    
+    /// Render single newlines within a paragraph as line breaks
+    pub fn with_soft_break_as_newline(mut self, enabled: bool) -> Self {
+        self.soft_break_as_newline = enabled;
+        self
+    }
+    
     /// Set the base path for resolving relative URLs
     pub fn with_base_path(mut self, path: impl AsRef<Path>) -> Self {
         self.base_path = Some(path.as_ref().to_path_buf());
         self
     }
     
+    /// Resolve wiki links against the notes of the open folder
+    pub fn with_notes(mut self, notes: NoteIndex) -> Self {
+        self.notes = notes;
+        self
+    }
+    
+    /// Render diagram code blocks with an external renderer
+    ///
+    /// Rendering may run a slow external tool, so only use this off the UI thread.
+    pub fn with_diagram_renderer(mut self, renderer: DiagramRenderer) -> Self {
+        self.diagram_renderer = Some(renderer);
+        self
+    }
+    
     /// Parse and render Markdown content to preview elements
     pub fn render(&self, markdown: &str) -> Vec<PreviewElement> {
-        let parser = Parser::new_ext(markdown, self.options);
+        // Front matter is metadata, as in the editor's highlighting
+        let (_, markdown) = super::export::split_front_matter(markdown);
+        let (source, math_spans) = math::extract(&image::normalize_size_hints(markdown));
+        let events = Parser::new_ext(&source, self.options)
+            .flat_map(math::split_placeholders)
+            .map(|event| line_break_event(event, self.soft_break_as_newline));
+        let events = if self.sanitize_html {
+            sanitize::html_events(events)
+        } else {
+            events.collect()
+        };
+        // Wiki links can span the text events pulldown-cmark splits at brackets
+        let events = if self.wiki_links {
+            wiki_link::merge_text(events.into_iter())
+        } else {
+            events
+        };
+        let (events, footnotes) = footnote::arrange(events.into_iter(), self.keep_unreferenced_footnotes);
+        let mut parser = events.into_iter().peekable();
         let mut elements = Vec::new();
-        let mut context = ParseContext::default();
+        let mut context = ParseContext {
+            footnotes,
+            ..ParseContext::default()
+        };
         let mut element_stack: Vec<ElementBuilder> = Vec::new();
         
-        for event in parser {
+        while let Some(event) = parser.next() {
             match event {
                 Event::Start(tag) => {
                     self.handle_start_tag(tag, &mut context, &mut element_stack);
                 }
                 Event::End(tag) => {
-                    if let Some(element) = self.handle_end_tag(tag, &mut context, &mut element_stack) {
+                    let element = self.handle_end_tag(tag, &mut context, &mut element_stack);
+                    if let Some(PreviewElement::Image { alt, url, title, .. }) = element {
+                        // A size block right after an image belongs to it
+                        let mut size = image::ImageSize::default();
+                        let mut rest = None;
+                        if let Some(Event::Text(text)) = parser.peek() {
+                            if let Some((hint, after)) = image::ImageSize::split_attributes(text) {
+                                size = hint;
+                                rest = Some(after.to_string());
+                            }
+                        }
+                        if rest.is_some() {
+                            parser.next();
+                        }
+
+                        let image = PreviewElement::Image {
+                            alt: alt.clone(),
+                            url,
+                            title,
+                            width: size.width,
+                            height: size.height,
+                        };
+                        if !Self::push_block(image, &mut context, &mut element_stack, &mut elements) {
+                            context.push_text(&alt);
+                        }
+                        if let Some(rest) = rest {
+                            context.push_text(&rest);
+                        }
+                    } else if let Some(element) = element {
                         if element_stack.is_empty() {
                             elements.push(element);
                         } else if let Some(parent) = element_stack.last_mut() {
@@ -287,7 +489,15 @@ impl PreviewRenderer {
                     }
                 }
                 Event::Text(text) => {
-                    context.push_text(&text);
+                    let literal = matches!(
+                        element_stack.last(),
+                        Some(ElementBuilder::CodeBlock { .. } | ElementBuilder::Image { .. })
+                    );
+                    if self.wiki_links && !literal && context.link_url.is_none() {
+                        self.push_wiki_text(&text, &mut context);
+                    } else {
+                        context.push_text(&text);
+                    }
                 }
                 Event::Code(code) => {
                     let mut styled = StyledText::plain(code.to_string()).with_code();
@@ -303,18 +513,9 @@ impl PreviewRenderer {
                     context.push_text(" ");
                 }
                 Event::HardBreak => {
-                    // Finalize current text and add break
-                    let buffer = context.take_buffer();
-                    if !buffer.is_empty() {
-                        if let Some(parent) = element_stack.last_mut() {
-                            parent.add_text(buffer);
-                        }
-                    }
-                    if element_stack.is_empty() {
-                        elements.push(PreviewElement::HardBreak);
-                    } else if let Some(parent) = element_stack.last_mut() {
-                        parent.add_child(PreviewElement::HardBreak);
-                    }
+                    // Breaks only occur in text, and paragraphs, headings, list
+                    // items and table cells all hold runs, so it is a newline run
+                    context.text_buffer.push(StyledText::plain("\n"));
                 }
                 Event::Rule => {
                     if element_stack.is_empty() {
@@ -324,6 +525,10 @@ impl PreviewRenderer {
                     }
                 }
                 Event::Html(html) => {
+                    if let Some(span) = math::placeholder_index(&html).and_then(|i| math_spans.get(i)) {
+                        Self::push_math(span, &mut context, &mut element_stack, &mut elements);
+                        continue;
+                    }
                     let element = PreviewElement::Html(html.to_string());
                     if element_stack.is_empty() {
                         elements.push(element);
@@ -332,7 +537,13 @@ impl PreviewRenderer {
                     }
                 }
                 Event::FootnoteReference(label) => {
-                    context.push_text(&format!("[^{}]", label));
+                    // References without a definition are already text
+                    if let Some((number, _)) = context.footnotes.next_reference(&label) {
+                        let marker = StyledText::plain(number.to_string())
+                            .with_superscript()
+                            .with_link(format!("#{}", footnote::note_id(number)));
+                        context.text_buffer.push(marker);
+                    }
                 }
                 Event::TaskListMarker(checked) => {
                     if let Some(parent) = element_stack.last_mut() {
@@ -345,6 +556,82 @@ impl PreviewRenderer {
         elements
     }
     
+    /// Add text whose wiki links become links to their notes
+    fn push_wiki_text(&self, text: &str, context: &mut ParseContext) {
+        let mut last = 0;
+        for (range, link) in wiki_link::find(text) {
+            context.push_text(&text[last..range.start]);
+            let (href, exists) = self.notes.href(&link, self.base_path.as_deref());
+            context.link_url = Some(href);
+            context.push_text(&link.text());
+            if let Some(styled) = context.text_buffer.last_mut() {
+                styled.unresolved = !exists;
+            }
+            context.link_url = None;
+            last = range.end;
+        }
+        context.push_text(&text[last..]);
+    }
+    
+    /// Emit a math span at the current position in the document
+    fn push_math(
+        span: &MathSpan,
+        context: &mut ParseContext,
+        stack: &mut Vec<ElementBuilder>,
+        elements: &mut Vec<PreviewElement>,
+    ) {
+        let element = PreviewElement::Math {
+            inline: span.inline,
+            source: span.source.clone(),
+        };
+        if !Self::push_block(element, context, stack, elements) {
+            // Headings, table cells, etc. only hold text; keep the source visible
+            context.push_text(&format!("${}$", span.source));
+        }
+    }
+
+    /// Emit a block-level element found inside text, such as math or an image
+    ///
+    /// A surrounding paragraph is split around the element. Returns `false`
+    /// when the parent only holds text.
+    fn push_block(
+        element: PreviewElement,
+        context: &mut ParseContext,
+        stack: &mut Vec<ElementBuilder>,
+        elements: &mut Vec<PreviewElement>,
+    ) -> bool {
+        let in_paragraph = matches!(stack.last(), Some(ElementBuilder::Paragraph(_)));
+        let in_container = matches!(
+            stack.last(),
+            None | Some(ElementBuilder::Blockquote(_))
+                | Some(ElementBuilder::ListItem { .. })
+                | Some(ElementBuilder::FootnoteDefinition { .. })
+        );
+        
+        if in_paragraph {
+            // Close the paragraph before the element and reopen it after
+            let buffer = context.take_buffer();
+            if let Some(ElementBuilder::Paragraph(mut content)) = stack.pop() {
+                content.extend(buffer);
+                if !content.is_empty() {
+                    emit_element(PreviewElement::Paragraph(content), stack, elements);
+                }
+            }
+            emit_element(element, stack, elements);
+            stack.push(ElementBuilder::Paragraph(Vec::new()));
+            true
+        } else if in_container {
+            let buffer = context.take_buffer();
+            if !buffer.is_empty() {
+                emit_element(PreviewElement::Paragraph(buffer), stack, elements);
+            }
+            emit_element(element, stack, elements);
+            true
+        } else {
+            false
+        }
+    }
+    
     fn handle_start_tag(&self, tag: Tag<'_>, context: &mut ParseContext, stack: &mut Vec<ElementBuilder>) {
         match tag {
             Tag::Paragraph => {
@@ -428,8 +715,11 @@ impl PreviewRenderer {
                 });
             }
             Tag::FootnoteDefinition(label) => {
+                let note = context.footnotes.get(&label).unwrap_or_default();
                 stack.push(ElementBuilder::FootnoteDefinition {
                     label: label.to_string(),
+                    number: note.number,
+                    references: note.references,
                     content: Vec::new(),
                 });
             }
@@ -442,6 +732,10 @@ impl PreviewRenderer {
                 let buffer = context.take_buffer();
                 if let Some(ElementBuilder::Paragraph(mut content)) = stack.pop() {
                     content.extend(buffer);
+                    // Inline math can leave an empty paragraph behind it
+                    if content.is_empty() {
+                        return None;
+                    }
                     return Some(PreviewElement::Paragraph(content));
                 }
             }
@@ -453,7 +747,10 @@ impl PreviewRenderer {
                 }
             }
             Tag::BlockQuote => {
-                if let Some(ElementBuilder::Blockquote(content)) = stack.pop() {
+                if let Some(ElementBuilder::Blockquote(mut content)) = stack.pop() {
+                    if let Some(kind) = take_callout_marker(&mut content) {
+                        return Some(PreviewElement::Callout { kind, content });
+                    }
                     return Some(PreviewElement::Blockquote(content));
                 }
             }
@@ -464,6 +761,14 @@ impl PreviewRenderer {
                     for styled in buffer {
                         code.push_str(&styled.text);
                     }
+                    // Diagram languages are rendered instead of shown as code
+                    if let Some(kind) = language.as_deref().and_then(DiagramKind::from_language) {
+                        let output = self
+                            .diagram_renderer
+                            .as_ref()
+                            .map_or(DiagramOutput::Source, |renderer| renderer.render(kind, &code));
+                        return Some(PreviewElement::Diagram { source: code, kind, output });
+                    }
                     return Some(PreviewElement::CodeBlock { language, code });
                 }
             }
@@ -546,12 +851,18 @@ impl PreviewRenderer {
                     for styled in buffer {
                         alt.push_str(&styled.text);
                     }
-                    return Some(PreviewElement::Image { alt, url, title });
+                    return Some(PreviewElement::Image {
+                        alt,
+                        url,
+                        title,
+                        width: None,
+                        height: None,
+                    });
                 }
             }
             Tag::FootnoteDefinition(_) => {
-                if let Some(ElementBuilder::FootnoteDefinition { label, content }) = stack.pop() {
-                    return Some(PreviewElement::FootnoteDefinition { label, content });
+                if let Some(ElementBuilder::FootnoteDefinition { label, number, references, content }) = stack.pop() {
+                    return Some(PreviewElement::FootnoteDefinition { label, number, references, content });
                 }
             }
         }
@@ -581,6 +892,49 @@ impl Default for PreviewRenderer {
     }
 }
 
+/// Remove a callout marker from the start of a blockquote's content
+///
+/// Returns the callout's type, or `None` with the content left as it is
+/// when the first line isn't a known marker.
+fn take_callout_marker(content: &mut Vec<PreviewElement>) -> Option<CalloutKind> {
+    let Some(PreviewElement::Paragraph(runs)) = content.first_mut() else {
+        return None;
+    };
+    let text: String = runs.iter().take_while(|run| !run.code).map(|run| run.text.as_str()).collect();
+    let (kind, mut len) = callout::parse_marker(&text)?;
+
+    while len > 0 {
+        let run = runs.remove(0);
+        if run.text.len() > len {
+            runs.insert(0, StyledText { text: run.text[len..].to_string(), ..run });
+            break;
+        }
+        len -= run.text.len();
+    }
+    // The line break or space after the marker
+    while let Some(run) = runs.first_mut() {
+        let trimmed = run.text.trim_start();
+        if !trimmed.is_empty() {
+            run.text = trimmed.to_string();
+            break;
+        }
+        runs.remove(0);
+    }
+    if runs.is_empty() {
+        content.remove(0);
+    }
+    Some(kind)
+}
+
+/// Add an element to the innermost open container, or the top level
+fn emit_element(element: PreviewElement, stack: &mut [ElementBuilder], elements: &mut Vec<PreviewElement>) {
+    if let Some(parent) = stack.last_mut() {
+        parent.add_child(element);
+    } else {
+        elements.push(element);
+    }
+}
+
 /// Builder for constructing preview elements during parsing
 #[derive(Debug)]
 enum ElementBuilder {
@@ -600,7 +954,7 @@ enum ElementBuilder {
     TableRow(Vec<Vec<StyledText>>),
     TableCell(Vec<StyledText>),
     Image { url: String, title: Option<String>, alt: String },
-    FootnoteDefinition { label: String, content: Vec<PreviewElement> },
+    FootnoteDefinition { label: String, number: usize, references: usize, content: Vec<PreviewElement> },
 }
 
 impl ElementBuilder {
@@ -645,15 +999,41 @@ pub struct HtmlExporter {
     /// Include embedded styles
     include_styles: bool,
     /// Embed images as base64
-    #[allow(dead_code)]
     embed_images: bool,
+    /// Directory that relative image paths resolve against
+    base_path: Option<PathBuf>,
+    /// Body font family (empty = system default)
+    font_family: String,
+    /// Base font size in pixels
+    font_size: f32,
+    /// Parser options
+    options: Options,
+    /// List footnotes nothing references
+    keep_unreferenced_footnotes: bool,
+    /// Pass raw HTML through the allow-list sanitizer
+    sanitize_html: bool,
+    /// Render `[[wiki links]]` as links to notes
+    wiki_links: bool,
+    /// Notes wiki links resolve against
+    notes: NoteIndex,
+    /// Render soft breaks as `<br />`
+    soft_break_as_newline: bool,
 }
 
 impl HtmlExporter {
     pub fn new() -> Self {
         Self {
+            options: parser_options(&MarkdownOptions::default()),
+            keep_unreferenced_footnotes: true,
+            sanitize_html: false,
+            wiki_links: false,
+            notes: NoteIndex::default(),
+            soft_break_as_newline: false,
             include_styles: true,
             embed_images: false,
+            base_path: None,
+            font_family: String::new(),
+            font_size: DEFAULT_PREVIEW_FONT_SIZE,
         }
     }
     
@@ -667,18 +1047,85 @@ impl HtmlExporter {
         self
     }
     
+    /// Set the directory that relative image paths resolve against
+    pub fn with_base_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.base_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+    
+    /// Resolve wiki links against the notes of the open folder
+    pub fn with_notes(mut self, notes: NoteIndex) -> Self {
+        self.notes = notes;
+        self
+    }
+    
+    /// Set the body font family and base size
+    pub fn with_font(mut self, family: impl Into<String>, size: f32) -> Self {
+        self.font_family = family.into();
+        self.font_size = size;
+        self
+    }
+    
+    /// Set which markdown extensions are enabled
+    pub fn with_markdown_options(mut self, markdown: &MarkdownOptions) -> Self {
+        self.options = parser_options(markdown);
+        self.keep_unreferenced_footnotes = markdown.keep_unreferenced_footnotes;
+        self.sanitize_html = markdown.sanitize_html;
+        self.wiki_links = markdown.wiki_links;
+        self
+    }
+    
+    /// Render single newlines within a paragraph as line breaks
+    pub fn with_soft_break_as_newline(mut self, enabled: bool) -> Self {
+        self.soft_break_as_newline = enabled;
+        self
+    }
+    
+    /// Export markdown to an HTML fragment, without page, styles or scripts
+    ///
+    /// Used for rich-text copies. Any markdown renders, including a partial
+    /// construct such as half a list or an unclosed code fence.
+    pub fn export_fragment(&self, markdown: &str) -> String {
+        self.render_body(markdown).0
+    }
+    
+    /// Render the document body, with the scripts its diagrams and math need
+    fn render_body(&self, markdown: &str) -> (String, String) {
+        let (source, math_spans) = math::extract(&image::normalize_size_hints(markdown));
+        let parser = Parser::new_ext(&source, self.options)
+            .flat_map(math::split_placeholders)
+            .map(|event| line_break_event(event, self.soft_break_as_newline));
+        let parser = if self.sanitize_html {
+            sanitize::html_events(parser)
+        } else {
+            parser.collect()
+        };
+        let parser = if self.wiki_links {
+            wiki_link::html_events(parser.into_iter(), &self.notes, self.base_path.as_deref())
+        } else {
+            parser
+        };
+        let parser = parser.into_iter().map(|event| math::html_event(event, &math_spans));
+        let parser = footnote::html_events(parser, self.keep_unreferenced_footnotes).into_iter();
+        let parser = callout::html_events(parser).into_iter();
+        let mut html_output = String::new();
+        let events = if self.embed_images {
+            image::embed_images(parser, self.base_path.as_deref())
+        } else {
+            parser.collect()
+        };
+        let events = image::sized_image_events(events.into_iter());
+        let diagrams = diagram::push_html(&mut html_output, events.into_iter());
+        let mut scripts = diagram::html_scripts(&diagrams, false);
+        if !math_spans.is_empty() {
+            scripts.push_str(&math::html_scripts());
+        }
+        (html_output, scripts)
+    }
+    
     /// Export markdown to HTML
     pub fn export(&self, markdown: &str, title: Option<&str>) -> String {
-        let mut options = Options::empty();
-        options.insert(Options::ENABLE_TABLES);
-        options.insert(Options::ENABLE_FOOTNOTES);
-        options.insert(Options::ENABLE_STRIKETHROUGH);
-        options.insert(Options::ENABLE_TASKLISTS);
-        options.insert(Options::ENABLE_SMART_PUNCTUATION);
-        
-        let parser = Parser::new_ext(markdown, options);
-        let mut html_output = String::new();
-        pulldown_cmark::html::push_html(&mut html_output, parser);
+        let (html_output, scripts) = self.render_body(markdown);
         
         let styles = if self.include_styles {
             self.get_default_styles()
@@ -701,17 +1148,20 @@ impl HtmlExporter {
     <article class="markdown-body">
 {}
     </article>
+    {}
 </body>
 </html>"#,
             Self::escape_html(title),
             styles,
-            html_output
+            html_output,
+            scripts
         )
     }
     
     fn get_default_styles(&self) -> String {
-        r#"<style>
-        :root {
+        format!(
+            r#"<style>
+        :root {{
             --color-bg: #ffffff;
             --color-text: #24292e;
             --color-heading: #24292e;
@@ -719,10 +1169,10 @@ impl HtmlExporter {
             --color-code-bg: #f6f8fa;
             --color-border: #e1e4e8;
             --color-blockquote: #6a737d;
-        }
+        }}
         
-        @media (prefers-color-scheme: dark) {
-            :root {
+        @media (prefers-color-scheme: dark) {{
+            :root {{
                 --color-bg: #0d1117;
                 --color-text: #c9d1d9;
                 --color-heading: #c9d1d9;
@@ -730,114 +1180,144 @@ impl HtmlExporter {
                 --color-code-bg: #161b22;
                 --color-border: #30363d;
                 --color-blockquote: #8b949e;
-            }
-        }
+            }}
+        }}
         
-        body {
-            font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif;
-            font-size: 16px;
+        body {{
+            font-family: {};
+            font-size: {}px;
             line-height: 1.6;
             color: var(--color-text);
             background-color: var(--color-bg);
             max-width: 800px;
             margin: 0 auto;
             padding: 2rem;
-        }
+        }}
         
         .markdown-body h1, .markdown-body h2, .markdown-body h3,
-        .markdown-body h4, .markdown-body h5, .markdown-body h6 {
+        .markdown-body h4, .markdown-body h5, .markdown-body h6 {{
             color: var(--color-heading);
             margin-top: 24px;
             margin-bottom: 16px;
             font-weight: 600;
             line-height: 1.25;
-        }
+        }}
         
-        .markdown-body h1 { font-size: 2em; border-bottom: 1px solid var(--color-border); padding-bottom: .3em; }
-        .markdown-body h2 { font-size: 1.5em; border-bottom: 1px solid var(--color-border); padding-bottom: .3em; }
-        .markdown-body h3 { font-size: 1.25em; }
-        .markdown-body h4 { font-size: 1em; }
-        .markdown-body h5 { font-size: .875em; }
-        .markdown-body h6 { font-size: .85em; color: var(--color-blockquote); }
+        .markdown-body h1 {{ font-size: 2em; border-bottom: 1px solid var(--color-border); padding-bottom: .3em; }}
+        .markdown-body h2 {{ font-size: 1.5em; border-bottom: 1px solid var(--color-border); padding-bottom: .3em; }}
+        .markdown-body h3 {{ font-size: 1.25em; }}
+        .markdown-body h4 {{ font-size: 1em; }}
+        .markdown-body h5 {{ font-size: .875em; }}
+        .markdown-body h6 {{ font-size: .85em; color: var(--color-blockquote); }}
         
-        .markdown-body a {
+        .markdown-body a {{
             color: var(--color-link);
             text-decoration: none;
-        }
+        }}
         
-        .markdown-body a:hover {
+        .markdown-body a:hover {{
             text-decoration: underline;
-        }
+        }}
         
-        .markdown-body code {
+        .markdown-body code {{
             background-color: var(--color-code-bg);
             padding: .2em .4em;
             border-radius: 3px;
             font-size: 85%;
             font-family: "SFMono-Regular", Consolas, "Liberation Mono", Menlo, monospace;
-        }
+        }}
         
-        .markdown-body pre {
+        .markdown-body pre {{
             background-color: var(--color-code-bg);
             padding: 16px;
             overflow: auto;
             border-radius: 6px;
             line-height: 1.45;
-        }
+        }}
         
-        .markdown-body pre code {
+        .markdown-body pre code {{
             background: transparent;
             padding: 0;
             border-radius: 0;
-        }
+        }}
         
-        .markdown-body blockquote {
+        .markdown-body blockquote {{
             margin: 0;
             padding: 0 1em;
             color: var(--color-blockquote);
             border-left: .25em solid var(--color-border);
-        }
+        }}
+
+        .markdown-body .callout {{
+            margin: 0 0 16px;
+            padding: .5em 1em;
+            border-left: .25em solid var(--callout-color);
+            border-radius: 6px;
+        }}
         
-        .markdown-body table {
+        .markdown-body .callout-title {{
+            margin: 0 0 .5em;
+            font-weight: 600;
+            color: var(--callout-color);
+        }}
+        
+        .markdown-body .callout > :last-child {{ margin-bottom: 0; }}
+        .markdown-body .callout-note {{ --callout-color: #0969da; }}
+        .markdown-body .callout-tip {{ --callout-color: #1a7f37; }}
+        .markdown-body .callout-important {{ --callout-color: #8250df; }}
+        .markdown-body .callout-warning {{ --callout-color: #9a6700; }}
+        .markdown-body .callout-caution {{ --callout-color: #cf222e; }}
+        
+        .markdown-body .footnotes {{
+            margin-top: 2em;
+            padding-top: 1em;
+            border-top: 1px solid var(--color-border);
+            font-size: .875em;
+        }}
+        
+        .markdown-body table {{
             border-collapse: collapse;
             width: 100%;
             margin: 16px 0;
-        }
+        }}
         
         .markdown-body table th,
-        .markdown-body table td {
+        .markdown-body table td {{
             padding: 6px 13px;
             border: 1px solid var(--color-border);
-        }
+        }}
         
-        .markdown-body table tr:nth-child(even) {
+        .markdown-body table tr:nth-child(even) {{
             background-color: var(--color-code-bg);
-        }
+        }}
         
-        .markdown-body img {
+        .markdown-body img {{
             max-width: 100%;
             height: auto;
-        }
+        }}
         
-        .markdown-body hr {
+        .markdown-body hr {{
             border: 0;
             border-top: 1px solid var(--color-border);
             margin: 24px 0;
-        }
+        }}
         
-        .markdown-body ul, .markdown-body ol {
+        .markdown-body ul, .markdown-body ol {{
             padding-left: 2em;
             margin: 16px 0;
-        }
+        }}
         
-        .markdown-body li {
+        .markdown-body li {{
             margin: 4px 0;
-        }
+        }}
         
-        .markdown-body input[type="checkbox"] {
+        .markdown-body input[type="checkbox"] {{
             margin-right: 8px;
-        }
-    </style>"#.to_string()
+        }}
+    </style>"#,
+            css_font_family(&self.font_family),
+            css_font_size(self.font_size)
+        )
     }
     
     fn escape_html(text: &str) -> String {
@@ -869,6 +1349,33 @@ mod tests {
         assert!(matches!(elements[0], PreviewElement::Heading { level: 1, .. }));
     }
     
+    #[test]
+    fn test_image_size_hints() {
+        let renderer = PreviewRenderer::new();
+        let sizes = |markdown: &str| -> Vec<(Option<u32>, Option<u32>)> {
+            renderer
+                .render(markdown)
+                .iter()
+                .filter_map(|e| match e {
+                    PreviewElement::Image { width, height, .. } => Some((*width, *height)),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        assert_eq!(sizes("![a](img.png =200x100)"), vec![(Some(200), Some(100))]);
+        assert_eq!(sizes("![a](img.png =200x)"), vec![(Some(200), None)]);
+        assert_eq!(sizes("Before ![a](img.png){width=50} after"), vec![(Some(50), None)]);
+        // Malformed hints are dropped and the image still renders
+        assert_eq!(sizes("![a](img.png =wide)"), vec![(None, None)]);
+        assert_eq!(sizes("![a](img.png){width=big}"), vec![(None, None)]);
+        // Code is left alone
+        assert!(sizes("`![a](img.png =200x100)`").is_empty());
+
+        let html = HtmlExporter::new().export("![a](img.png =200x100)", None);
+        assert!(html.contains(r#"<img src="img.png" alt="a" width="200" height="100" />"#));
+    }
+
     #[test]
     fn test_html_export() {
         let exporter = HtmlExporter::new();
@@ -878,4 +1385,283 @@ mod tests {
         assert!(html.contains("<p>Paragraph.</p>"));
         assert!(html.contains("<title>Test Doc</title>"));
     }
+    
+    #[test]
+    fn test_mermaid_block_renders_as_diagram() {
+        let renderer = PreviewRenderer::new();
+        let elements = renderer.render("```mermaid\ngraph TD; A-->B;\n```\n\n```rust\nfn main() {}\n```");
+        
+        assert!(matches!(
+            &elements[0],
+            PreviewElement::Diagram { kind: DiagramKind::Mermaid, source, output: DiagramOutput::Source }
+                if source.contains("A-->B")
+        ));
+        assert!(matches!(elements[1], PreviewElement::CodeBlock { .. }));
+    }
+    
+    #[test]
+    fn test_mermaid_block_uses_diagram_renderer() {
+        let dir = std::env::temp_dir().join(format!("cosmic-notebook-diagram-{}", std::process::id()));
+        let diagrams = DiagramRenderer::with_output_dir(&dir);
+        // A cached render is reused without running mmdc
+        let svg = diagrams.svg_path(DiagramKind::Mermaid, "graph TD; A-->B;\n");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&svg, "<svg/>").unwrap();
+
+        let renderer = PreviewRenderer::new().with_diagram_renderer(diagrams);
+        let elements = renderer.render("```mermaid\ngraph TD; A-->B;\n```");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(
+            &elements[0],
+            PreviewElement::Diagram { output: DiagramOutput::Svg(path), .. } if *path == svg
+        ));
+    }
+    
+    #[test]
+    fn test_html_export_includes_mermaid_script() {
+        let exporter = HtmlExporter::new();
+        let html = exporter.export("```mermaid\ngraph TD; A-->B;\n```", None);
+        
+        assert!(html.contains("<pre class=\"mermaid\">"));
+        assert!(html.contains("mermaid.initialize"));
+        
+        let plain = exporter.export("Paragraph.", None);
+        assert!(!plain.contains("mermaid"));
+    }
+    
+    #[test]
+    fn test_css_font_family() {
+        assert_eq!(css_font_family("Noto Serif"), format!("\"Noto Serif\", {}", DEFAULT_FONT_STACK));
+        assert_eq!(css_font_family("Serif"), format!("serif, {}", DEFAULT_FONT_STACK));
+        assert_eq!(css_font_family("  "), DEFAULT_FONT_STACK);
+        assert_eq!(css_font_family("x; } body { color: red"), DEFAULT_FONT_STACK);
+        assert_eq!(css_font_size(f32::NAN), DEFAULT_PREVIEW_FONT_SIZE);
+    }
+    
+    #[test]
+    fn test_html_export_font() {
+        let html = HtmlExporter::new()
+            .with_font("Source Sans Pro", 18.0)
+            .export("Text", None);
+        
+        assert!(html.contains("font-family: \"Source Sans Pro\", -apple-system"));
+        assert!(html.contains("font-size: 18px;"));
+    }
+    
+    #[test]
+    fn test_math_elements() {
+        let renderer = PreviewRenderer::new();
+        let elements = renderer.render("Mass $E = mc^2$ energy.\n\n$$\n\\int_0^1 x\\,dx\n$$\n\nCosts $5.");
+        
+        assert!(matches!(&elements[0], PreviewElement::Paragraph(_)));
+        assert!(matches!(&elements[1], PreviewElement::Math { inline: true, source } if source == "E = mc^2"));
+        assert!(matches!(&elements[2], PreviewElement::Paragraph(_)));
+        assert!(matches!(&elements[3], PreviewElement::Math { inline: false, .. }));
+        assert!(matches!(&elements[4], PreviewElement::Paragraph(text) if text[0].text.contains("$5")));
+    }
+    
+    #[test]
+    fn test_smart_punctuation_toggle() {
+        let markdown = MarkdownOptions {
+            smart_punctuation: false,
+            ..MarkdownOptions::default()
+        };
+        let source = "\"Quoted\" -- text";
+        
+        let paragraph = |elements: Vec<PreviewElement>| match &elements[0] {
+            PreviewElement::Paragraph(text) => text.iter().map(|t| t.text.as_str()).collect::<String>(),
+            _ => String::new(),
+        };
+        
+        let elements = PreviewRenderer::new().with_markdown_options(&markdown).render(source);
+        assert_eq!(paragraph(elements), source);
+        let html = HtmlExporter::new().with_markdown_options(&markdown).export(source, None);
+        assert!(html.contains("&quot;Quoted&quot; --"));
+        
+        let elements = PreviewRenderer::new().render(source);
+        assert!(paragraph(elements).contains('\u{201c}'));
+        let html = HtmlExporter::new().export(source, None);
+        assert!(html.contains('\u{201c}'));
+    }
+    
+    #[test]
+    fn test_sanitize_html_toggle() {
+        let markdown = MarkdownOptions {
+            sanitize_html: true,
+            ..MarkdownOptions::default()
+        };
+        let source = "<div onclick=\"steal()\">\n<script>alert(1)</script>\n<u>safe</u>\n</div>\n\nInline <img src=x onerror=alert(1)> $x$";
+        
+        let html = HtmlExporter::new().with_markdown_options(&markdown).export(source, None);
+        assert!(!html.contains("onclick") && !html.contains("onerror") && !html.contains("alert(1)"));
+        assert!(html.contains("<u>safe</u>"));
+        assert!(html.contains("<img src=\"x\">"));
+        assert!(html.contains("class=\"math"));
+        
+        let elements = PreviewRenderer::new().with_markdown_options(&markdown).render(source);
+        assert!(elements.iter().all(|element| match element {
+            PreviewElement::Html(html) => !html.contains("onclick") && !html.contains("alert"),
+            _ => true,
+        }));
+        
+        // Off by default
+        let html = HtmlExporter::new().export(source, None);
+        assert!(html.contains("onclick") && html.contains("<script>alert(1)</script>"));
+    }
+    
+    #[test]
+    fn test_wiki_links() {
+        let markdown = MarkdownOptions {
+            wiki_links: true,
+            ..MarkdownOptions::default()
+        };
+        let notes = NoteIndex::new(
+            Some(PathBuf::from("/notes")),
+            vec![PathBuf::from("/notes/Ideas.md")],
+        );
+        let source = "See [[Ideas|my ideas]] and [[Later]], not `[[Ideas]]`.";
+        
+        let elements = PreviewRenderer::new()
+            .with_markdown_options(&markdown)
+            .with_base_path("/notes")
+            .with_notes(notes)
+            .render(source);
+        let PreviewElement::Paragraph(content) = &elements[0] else {
+            panic!("expected a paragraph");
+        };
+        let links: Vec<_> = content.iter().filter(|t| t.link.is_some()).collect();
+        assert_eq!(links.len(), 2);
+        assert_eq!((links[0].text.as_str(), links[0].link.as_deref()), ("my ideas", Some("Ideas.md")));
+        assert!(!links[0].unresolved);
+        assert_eq!((links[1].text.as_str(), links[1].unresolved), ("Later", true));
+        assert!(content.iter().any(|t| t.code && t.text == "[[Ideas]]"));
+        
+        // Off by default
+        let elements = PreviewRenderer::new().render(source);
+        assert!(matches!(&elements[0], PreviewElement::Paragraph(content) if content.iter().all(|t| t.link.is_none())));
+    }
+    
+    #[test]
+    fn test_commonmark_disables_tables() {
+        let source = "| a | b |\n|---|---|\n| 1 | 2 |";
+        let html = HtmlExporter::new()
+            .with_markdown_options(&MarkdownOptions::commonmark())
+            .export(source, None);
+        assert!(!html.contains("<table>"));
+        assert!(HtmlExporter::new().export(source, None).contains("<table>"));
+    }
+    
+    #[test]
+    fn test_html_export_math() {
+        let exporter = HtmlExporter::new();
+        let html = exporter.export("Inline $x^2$ here.", None);
+        
+        assert!(html.contains("<span class=\"math math-inline\">\\(x^2\\)</span>"));
+        assert!(html.contains("katex"));
+    }
+    
+    fn paragraph_text(element: &PreviewElement) -> String {
+        match element {
+            PreviewElement::Paragraph(content) => content.iter().map(|t| t.text.as_str()).collect(),
+            other => panic!("expected a paragraph, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_soft_break_as_newline() {
+        let source = "one\ntwo";
+        let joined = PreviewRenderer::new().render(source);
+        assert_eq!(paragraph_text(&joined[0]), "one two");
+        
+        let renderer = PreviewRenderer::new().with_soft_break_as_newline(true);
+        assert_eq!(paragraph_text(&renderer.render(source)[0]), "one\ntwo");
+        
+        // Tight list items keep the text on both sides of the break
+        let elements = renderer.render("- one\n  two");
+        let PreviewElement::UnorderedList(items) = &elements[0] else {
+            panic!("expected a list, got {:?}", elements[0]);
+        };
+        assert_eq!(paragraph_text(&items[0].content[0]), "one\ntwo");
+        
+        let html = HtmlExporter::new().with_soft_break_as_newline(true).export(source, None);
+        assert!(html.contains("one<br />\ntwo"));
+        assert!(!HtmlExporter::new().export(source, None).contains("<br />"));
+    }
+    
+    #[test]
+    fn test_trailing_spaces_hard_break() {
+        let elements = PreviewRenderer::new().render("one  \ntwo");
+        assert_eq!(paragraph_text(&elements[0]), "one\ntwo");
+        assert!(HtmlExporter::new().export("one  \ntwo", None).contains("<br />"));
+    }
+    
+    #[test]
+    fn test_preview_footnotes() {
+        let source = "[^note]: The note.\n\nText[^note] and more[^note], not[^none].\n\n[^spare]: Unused.";
+        let elements = PreviewRenderer::new().render(source);
+        
+        let PreviewElement::Paragraph(content) = &elements[0] else {
+            panic!("expected a paragraph, got {:?}", elements[0]);
+        };
+        let markers: Vec<_> = content.iter().filter(|t| t.superscript).collect();
+        assert_eq!(markers.len(), 2);
+        assert_eq!(markers[0].text, "1");
+        assert_eq!(markers[0].link.as_deref(), Some("#fn-1"));
+        assert!(paragraph_text(&elements[0]).ends_with("not[^none]."));
+        
+        // Definitions follow the document, referenced ones first
+        assert!(matches!(
+            &elements[1],
+            PreviewElement::FootnoteDefinition { number: 1, references: 2, label, .. } if label == "note"
+        ));
+        assert!(matches!(
+            &elements[2],
+            PreviewElement::FootnoteDefinition { number: 2, references: 0, .. }
+        ));
+        
+        let markdown = MarkdownOptions {
+            keep_unreferenced_footnotes: false,
+            ..MarkdownOptions::default()
+        };
+        let elements = PreviewRenderer::new().with_markdown_options(&markdown).render(source);
+        assert_eq!(elements.len(), 2);
+        
+        let html = HtmlExporter::new().export(source, None);
+        assert!(html.contains("<section class=\"footnotes\">"));
+        assert!(html.contains("href=\"#fnref-1-2\""));
+    }
+    
+    #[test]
+    fn test_preview_callout() {
+        let renderer = PreviewRenderer::new();
+        let elements = renderer.render("> [!TIP]\n> Try **this**.\n\n> [!NOTE]\n\n> [!OTHER]\n> Plain.");
+        match &elements[0] {
+            PreviewElement::Callout { kind: CalloutKind::Tip, content } => match &content[..] {
+                [PreviewElement::Paragraph(runs)] => {
+                    assert_eq!(runs[0].text, "Try ");
+                    assert!(runs[1].bold);
+                }
+                other => panic!("unexpected callout content: {:?}", other),
+            },
+            other => panic!("expected a callout: {:?}", other),
+        }
+        // A marker alone leaves an empty callout
+        assert!(matches!(&elements[1], PreviewElement::Callout { kind: CalloutKind::Note, content } if content.is_empty()));
+        // Unknown types fall back to a blockquote
+        assert!(matches!(elements[2], PreviewElement::Blockquote(_)));
+
+        let html = HtmlExporter::new().export("> [!TIP]\n> Try this.", None);
+        assert!(html.contains("<div class=\"callout callout-tip\">"));
+        assert!(html.contains(".markdown-body .callout-tip"));
+    }
+
+    #[test]
+    fn test_html_fragment() {
+        // Half a list still renders, with no page around it
+        let html = HtmlExporter::new().export_fragment("  - **one**\n- two");
+        assert!(html.starts_with("<ul>"));
+        assert!(html.contains("<strong>one</strong>"));
+        assert!(!html.contains("<html"));
+    }
 }
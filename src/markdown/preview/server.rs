@@ -0,0 +1,414 @@
+//! Local HTTP server for previewing a document in a browser
+//!
+//! Serves one page of rendered HTML at `http://localhost:PORT/` on the
+//! loopback interface. The page is served with a script that listens for
+//! server-sent events on `/events` and reloads whenever the page is
+//! updated.
+//!
+//! Connections are handled by a few worker threads, and turned away when
+//! too many are waiting. Event streams are passed on to one thread that
+//! writes to all of them, so open tabs don't keep the workers busy.
+//! Requests must name the server as their host, so other sites can't read
+//! the page through a rebound DNS name. Stopping the server, or dropping
+//! it, closes the listening socket so the port is free again.
+
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, TrySendError};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How many ports after the configured one are tried when it is busy
+const PORT_ATTEMPTS: u16 = 10;
+
+/// How often an idle event stream is written to, to notice closed tabs
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long a browser may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connections served at once
+const WORKERS: usize = 4;
+
+/// Event streams kept open; the oldest is closed to make room for another
+const MAX_EVENT_STREAMS: usize = 32;
+
+/// Connections waiting for a worker before more are turned away
+const QUEUE_LENGTH: usize = 16;
+
+/// Script added to the page to reload it when it is updated
+const RELOAD_SCRIPT: &str =
+    "<script>new EventSource(\"/events\").onmessage = () => location.reload();</script>";
+
+/// The page being served, and how many times it has been updated
+struct Page {
+    html: String,
+    version: u64,
+}
+
+/// State shared with the connection threads
+struct Shared {
+    page: Mutex<Page>,
+    /// Signalled when the page is updated or the server stops
+    changed: Condvar,
+    stopped: AtomicBool,
+    /// Open event streams, oldest first
+    streams: Mutex<Vec<TcpStream>>,
+}
+
+/// A running preview server
+pub struct PreviewServer {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PreviewServer {
+    /// Start serving `html` on `port`
+    ///
+    /// When the port is busy the next few are tried, then any free port.
+    /// Port 0 picks any free port straight away.
+    pub fn start(port: u16, html: String) -> std::io::Result<Self> {
+        let listener = bind(port)?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            page: Mutex::new(Page { html, version: 0 }),
+            changed: Condvar::new(),
+            stopped: AtomicBool::new(false),
+            streams: Mutex::new(Vec::new()),
+        });
+        let thread = {
+            let shared = shared.clone();
+            std::thread::spawn(move || serve(listener, shared))
+        };
+        Ok(Self {
+            addr,
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// Port the server listens on
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// Address to open in a browser
+    pub fn url(&self) -> String {
+        format!("http://localhost:{}/", self.port())
+    }
+
+    /// Replace the page, reloading it in every browser showing it
+    pub fn update(&self, html: String) {
+        let mut page = self.shared.page.lock().unwrap_or_else(PoisonError::into_inner);
+        page.html = html;
+        page.version += 1;
+        self.shared.changed.notify_all();
+    }
+
+    /// Stop serving and free the port
+    pub fn stop(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        self.shared.stopped.store(true, Ordering::SeqCst);
+        self.shared.changed.notify_all();
+        // Wake the accept loop so it sees the server stopped
+        let _ = TcpStream::connect(self.addr);
+        let _ = thread.join();
+    }
+}
+
+impl Drop for PreviewServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Listen on the loopback interface, moving past busy ports
+fn bind(port: u16) -> std::io::Result<TcpListener> {
+    if port != 0 {
+        for candidate in port..=port.saturating_add(PORT_ATTEMPTS) {
+            match TcpListener::bind((Ipv4Addr::LOCALHOST, candidate)) {
+                Err(e) if e.kind() == ErrorKind::AddrInUse => continue,
+                result => return result,
+            }
+        }
+    }
+    TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+}
+
+/// Accept connections until the server stops, handing them to the workers
+fn serve(listener: TcpListener, shared: Arc<Shared>) {
+    let port = listener.local_addr().map_or(0, |addr| addr.port());
+    let broadcaster = {
+        let shared = shared.clone();
+        std::thread::spawn(move || broadcast_events(&shared))
+    };
+    let (sender, receiver) = sync_channel::<TcpStream>(QUEUE_LENGTH);
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers: Vec<_> = (0..WORKERS)
+        .map(|_| {
+            let (receiver, shared) = (receiver.clone(), shared.clone());
+            std::thread::spawn(move || loop {
+                // The lock is only held while waiting for a connection
+                let stream = receiver.lock().unwrap_or_else(PoisonError::into_inner).recv();
+                let Ok(stream) = stream else {
+                    return;
+                };
+                if let Err(e) = handle(&stream, &shared, port) {
+                    log::debug!("Preview connection closed: {}", e);
+                }
+            })
+        })
+        .collect();
+
+    for stream in listener.incoming() {
+        if shared.stopped.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if let Err(TrySendError::Full(mut stream)) = sender.try_send(stream) {
+            let _ = stream.write_all(
+                b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            );
+        }
+    }
+
+    // Workers finish once the queue is closed and empty
+    drop(sender);
+    for worker in workers {
+        let _ = worker.join();
+    }
+    let _ = broadcaster.join();
+}
+
+/// Whether a request's Host header names this server on the loopback
+/// interface
+fn is_local_host(host: &str, port: u16) -> bool {
+    let Some((name, host_port)) = host.trim().rsplit_once(':') else {
+        return false;
+    };
+    matches!(name, "127.0.0.1" | "localhost") && host_port.parse::<u16>() == Ok(port)
+}
+
+/// Path asked for by a request line, without its query
+fn request_path(request: &str) -> Option<&str> {
+    let mut parts = request.split_whitespace();
+    if parts.next()? != "GET" {
+        return None;
+    }
+    parts.next()?.split('?').next()
+}
+
+/// Add the reload script to the end of a page's body
+fn with_reload_script(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(end) => format!("{}{}\n{}", &html[..end], RELOAD_SCRIPT, &html[end..]),
+        None => format!("{}{}", html, RELOAD_SCRIPT),
+    }
+}
+
+/// Answer one request
+fn handle(stream: &TcpStream, shared: &Shared, port: u16) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Only the host changes the answer
+    let mut host = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+        header.clear();
+    }
+
+    let mut stream = stream;
+    if !host.is_some_and(|host| is_local_host(&host, port)) {
+        return stream.write_all(
+            b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+    }
+    match request_path(&request) {
+        Some("/") | Some("/index.html") => {
+            let html = {
+                let page = shared.page.lock().unwrap_or_else(PoisonError::into_inner);
+                with_reload_script(&page.html)
+            };
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+                html.len()
+            )?;
+            stream.write_all(html.as_bytes())
+        }
+        Some("/events") => add_event_stream(stream, shared),
+        _ => stream.write_all(
+            b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ),
+    }
+}
+
+/// Start an event stream and hand it to the broadcasting thread
+fn add_event_stream(mut stream: &TcpStream, shared: &Shared) -> std::io::Result<()> {
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\n\r\n",
+    )?;
+    // A browser that stops reading must not hold up the others
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let stream = stream.try_clone()?;
+
+    let mut streams = shared.streams.lock().unwrap_or_else(PoisonError::into_inner);
+    if streams.len() >= MAX_EVENT_STREAMS {
+        streams.remove(0);
+    }
+    streams.push(stream);
+    Ok(())
+}
+
+/// Send an event to every stream each time the page is updated, dropping
+/// the streams of browsers that went away, until the server stops
+fn broadcast_events(shared: &Shared) {
+    let mut version = shared.page.lock().unwrap_or_else(PoisonError::into_inner).version;
+
+    loop {
+        let page = shared.page.lock().unwrap_or_else(PoisonError::into_inner);
+        let (page, _) = shared
+            .changed
+            .wait_timeout_while(page, KEEPALIVE_INTERVAL, |page| {
+                page.version == version && !shared.stopped.load(Ordering::SeqCst)
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+        if shared.stopped.load(Ordering::SeqCst) {
+            break;
+        }
+        let event: &[u8] = if page.version != version {
+            version = page.version;
+            b"data: reload\n\n"
+        } else {
+            b": keepalive\n\n"
+        };
+        drop(page);
+        shared
+            .streams
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|mut stream| stream.write_all(event).is_ok());
+    }
+
+    // Closing the streams tells the browsers the server is gone
+    shared.streams.lock().unwrap_or_else(PoisonError::into_inner).clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn connect_as(port: u16, path: &str, host: &str) -> TcpStream {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, host).unwrap();
+        stream
+    }
+
+    fn connect(port: u16, path: &str) -> TcpStream {
+        connect_as(port, path, &format!("localhost:{}", port))
+    }
+
+    fn get(port: u16, path: &str) -> String {
+        let mut response = String::new();
+        connect(port, path).read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_is_local_host() {
+        assert!(is_local_host("localhost:8080", 8080));
+        assert!(is_local_host("127.0.0.1:8080", 8080));
+        assert!(!is_local_host("localhost:8081", 8080));
+        assert!(!is_local_host("localhost", 8080));
+        assert!(!is_local_host("attacker.example:8080", 8080));
+    }
+
+    #[test]
+    fn test_foreign_host_is_rejected() {
+        let server = PreviewServer::start(0, "<p>Secret</p>".to_string()).unwrap();
+        let port = server.port();
+        let mut response = String::new();
+        connect_as(port, "/", &format!("attacker.example:{}", port))
+            .read_to_string(&mut response)
+            .unwrap();
+        assert!(response.starts_with("HTTP/1.1 403"));
+        assert!(!response.contains("Secret"));
+
+        let mut response = String::new();
+        connect_as(port, "/", &format!("127.0.0.1:{}", port))
+            .read_to_string(&mut response)
+            .unwrap();
+        assert!(response.contains("Secret"));
+    }
+
+    #[test]
+    fn test_serves_page() {
+        let mut server = PreviewServer::start(0, "<html><body><p>Hi</p></body></html>".to_string()).unwrap();
+        let page = get(server.port(), "/");
+        assert!(page.starts_with("HTTP/1.1 200 OK"));
+        assert!(page.contains("<p>Hi</p><script>new EventSource"));
+        assert!(get(server.port(), "/missing").starts_with("HTTP/1.1 404"));
+
+        // Stopping frees the port
+        let port = server.port();
+        server.stop();
+        assert!(TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_ok());
+    }
+
+    #[test]
+    fn test_busy_port_is_skipped() {
+        let busy = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = busy.local_addr().unwrap().port();
+        let server = PreviewServer::start(port, String::new()).unwrap();
+        assert_ne!(server.port(), port);
+    }
+
+    #[test]
+    fn test_update_sends_reload() {
+        let server = PreviewServer::start(0, "old".to_string()).unwrap();
+        let mut events = BufReader::new(connect(server.port(), "/events"));
+        let mut line = String::new();
+        while events.read_line(&mut line).unwrap() > 0 && !line.trim().is_empty() {
+            line.clear();
+        }
+
+        server.update("new".to_string());
+        line.clear();
+        events.read_line(&mut line).unwrap();
+        assert_eq!(line, "data: reload\n");
+        assert!(get(server.port(), "/").ends_with("new<script>new EventSource(\"/events\").onmessage = () => location.reload();</script>"));
+    }
+
+    #[test]
+    fn test_event_streams_leave_workers_free() {
+        let server = PreviewServer::start(0, "<p>Page</p>".to_string()).unwrap();
+        let _streams: Vec<_> = (0..WORKERS + 2)
+            .map(|_| {
+                let mut events = BufReader::new(connect(server.port(), "/events"));
+                let mut line = String::new();
+                while events.read_line(&mut line).unwrap() > 0 && !line.trim().is_empty() {
+                    line.clear();
+                }
+                events
+            })
+            .collect();
+
+        assert!(get(server.port(), "/").contains("<p>Page</p>"));
+    }
+}
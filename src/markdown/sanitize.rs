@@ -0,0 +1,345 @@
+//! Allow-list sanitizing of raw HTML embedded in Markdown
+//!
+//! With `markdown.sanitize_html` on, HTML in a note passes through the
+//! preview and export only as far as it is known to be harmless:
+//! - Allowed tags keep only their allowed attributes, so event handlers
+//!   and inline styles go
+//! - Links and images keep only `http`, `https`, `mailto` and relative
+//!   URLs (and `data:image/` sources)
+//! - Other tags are stripped but the text inside them is kept
+//! - Scripts, styles, frames and similar elements are dropped along with
+//!   their contents, as are comments and doctypes
+
+use super::html_import::{parse_tag, tag_end};
+use pulldown_cmark::{CowStr, Event};
+
+/// Elements kept, with only their allowed attributes
+const ALLOWED_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "blockquote", "br", "caption", "cite", "code", "col",
+    "colgroup", "dd", "del", "details", "dfn", "div", "dl", "dt", "em", "figcaption", "figure",
+    "h1", "h2", "h3", "h4", "h5", "h6", "hr", "i", "img", "ins", "kbd", "li", "mark", "ol", "p",
+    "pre", "q", "rp", "rt", "ruby", "s", "samp", "small", "span", "strike", "strong", "sub",
+    "summary", "sup", "table", "tbody", "td", "tfoot", "th", "thead", "time", "tr", "u", "ul",
+    "var", "wbr",
+];
+
+/// Elements dropped together with everything inside them
+const DROPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "iframe", "object", "template", "noscript", "textarea", "title", "svg",
+    "math",
+];
+
+/// Attributes allowed on any kept element
+const GLOBAL_ATTRIBUTES: &[&str] = &["title", "lang", "dir", "align"];
+
+/// Attributes allowed on particular elements
+const ELEMENT_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["href"]),
+    ("img", &["src", "alt", "width", "height"]),
+    ("td", &["colspan", "rowspan"]),
+    ("th", &["colspan", "rowspan"]),
+    ("col", &["span"]),
+    ("colgroup", &["span"]),
+    ("ol", &["start", "reversed"]),
+    ("details", &["open"]),
+    ("time", &["datetime"]),
+];
+
+/// URL schemes links and images may use
+const ALLOWED_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Sanitizer for HTML arriving in fragments
+///
+/// pulldown-cmark gives an HTML block one event per line and inline HTML
+/// one event per tag, so a dropped element or a tag can span several
+/// fragments. The sanitizer remembers both between calls.
+#[derive(Debug, Default)]
+pub struct HtmlSanitizer {
+    /// Dropped element whose contents are being skipped
+    hidden: Option<String>,
+    /// Start of a tag not yet closed at the end of the last fragment
+    pending: String,
+}
+
+impl HtmlSanitizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the sanitizer is inside a dropped element
+    pub fn is_hidden(&self) -> bool {
+        self.hidden.is_some()
+    }
+
+    /// Stop skipping the contents of a dropped element
+    ///
+    /// Used when the block holding an unclosed element ends.
+    pub fn reset(&mut self) {
+        self.hidden = None;
+    }
+
+    /// Sanitize the next fragment of HTML
+    pub fn clean(&mut self, html: &str) -> String {
+        let input = std::mem::take(&mut self.pending) + html;
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input.as_str();
+
+        while let Some(lt) = rest.find('<') {
+            if !self.is_hidden() {
+                output.push_str(&rest[..lt]);
+            }
+            rest = &rest[lt..];
+
+            let end = if rest.starts_with("<!--") {
+                // Comments may hold `>`
+                rest.find("-->").map(|end| end + 3)
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                rest.find('>').map(|end| end + 1)
+            } else if rest[1..].starts_with(|c: char| c == '/' || c.is_ascii_alphabetic()) {
+                match tag_end(&rest[1..]) {
+                    Some(end) => {
+                        self.tag(&rest[1..end + 1], &mut output);
+                        Some(end + 2)
+                    }
+                    None => None,
+                }
+            } else {
+                // Not a tag: a literal `<`
+                if !self.is_hidden() {
+                    output.push_str("&lt;");
+                }
+                rest = &rest[1..];
+                continue;
+            };
+
+            match end {
+                Some(end) => rest = &rest[end..],
+                None => {
+                    // Completed by the next fragment, or escaped by `finish`
+                    self.pending = rest.to_string();
+                    rest = "";
+                }
+            }
+        }
+        if !self.is_hidden() {
+            output.push_str(rest);
+        }
+        output
+    }
+
+    /// Flush a tag left unterminated, as escaped text
+    pub fn finish(&mut self) -> String {
+        let pending = std::mem::take(&mut self.pending);
+        if self.is_hidden() {
+            String::new()
+        } else {
+            escape(&pending)
+        }
+    }
+
+    /// Handle the inside of one tag, between `<` and `>`
+    fn tag(&mut self, inner: &str, output: &mut String) {
+        let (closing, inner) = match inner.strip_prefix('/') {
+            Some(inner) => (true, inner),
+            None => (false, inner),
+        };
+        let self_closing = inner.trim_end().ends_with('/');
+        let (name, attrs) = parse_tag(inner);
+
+        if let Some(hidden) = &self.hidden {
+            if closing && *hidden == name {
+                self.hidden = None;
+            }
+            return;
+        }
+        if DROPPED_ELEMENTS.contains(&name.as_str()) {
+            if !closing && !self_closing {
+                self.hidden = Some(name);
+            }
+            return;
+        }
+        if !ALLOWED_ELEMENTS.contains(&name.as_str()) {
+            return;
+        }
+
+        if closing {
+            output.push_str("</");
+            output.push_str(&name);
+            output.push('>');
+            return;
+        }
+        output.push('<');
+        output.push_str(&name);
+        for (key, value) in attrs {
+            if !is_allowed_attribute(&name, &key) {
+                continue;
+            }
+            if matches!(key.as_str(), "href" | "src") && !is_safe_url(&value, key == "src") {
+                continue;
+            }
+            output.push_str(&format!(" {}=\"{}\"", key, escape(&value)));
+        }
+        if name == "a" {
+            output.push_str(" rel=\"noopener noreferrer\"");
+        }
+        output.push('>');
+    }
+}
+
+/// Sanitize a complete piece of HTML
+pub fn sanitize(html: &str) -> String {
+    let mut sanitizer = HtmlSanitizer::new();
+    let mut output = sanitizer.clean(html);
+    output.push_str(&sanitizer.finish());
+    output
+}
+
+/// Sanitize the raw HTML in a Markdown event stream
+///
+/// Text inside a dropped element is removed with it. An element left open
+/// stops hiding text when the block it was opened in ends. Math
+/// placeholders pass through for the math stage after this one.
+pub fn html_events<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let mut output = Vec::new();
+    let mut sanitizer = HtmlSanitizer::new();
+    let mut depth = 0usize;
+    // Block depth at which the current dropped element was opened
+    let mut hidden_depth = 0;
+
+    for event in events {
+        if !matches!(event, Event::Html(_)) {
+            push_html(&mut output, sanitizer.finish());
+        }
+        match event {
+            Event::Html(html) if super::math::placeholder_index(&html).is_some() => {
+                push_html(&mut output, sanitizer.finish());
+                output.push(Event::Html(html));
+            }
+            Event::Html(html) => {
+                let was_hidden = sanitizer.is_hidden();
+                push_html(&mut output, sanitizer.clean(&html));
+                if !was_hidden && sanitizer.is_hidden() {
+                    hidden_depth = depth;
+                }
+            }
+            Event::Text(_) | Event::Code(_) if sanitizer.is_hidden() => {}
+            Event::Start(tag) => {
+                depth += 1;
+                output.push(Event::Start(tag));
+            }
+            Event::End(tag) => {
+                depth = depth.saturating_sub(1);
+                if depth < hidden_depth {
+                    sanitizer.reset();
+                    hidden_depth = 0;
+                }
+                output.push(Event::End(tag));
+            }
+            event => output.push(event),
+        }
+    }
+    push_html(&mut output, sanitizer.finish());
+    output
+}
+
+fn push_html<'a>(output: &mut Vec<Event<'a>>, html: String) {
+    if !html.is_empty() {
+        output.push(Event::Html(CowStr::from(html)));
+    }
+}
+
+fn is_allowed_attribute(element: &str, attribute: &str) -> bool {
+    GLOBAL_ATTRIBUTES.contains(&attribute)
+        || ELEMENT_ATTRIBUTES
+            .iter()
+            .any(|(name, attributes)| *name == element && attributes.contains(&attribute))
+}
+
+/// Whether a decoded URL is relative or uses an allowed scheme
+///
+/// Browsers ignore whitespace and control characters inside a scheme, so
+/// they are removed before it is read.
+fn is_safe_url(url: &str, image: bool) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let scheme_end = url.find(|c: char| matches!(c, ':' | '/' | '?' | '#'));
+    match scheme_end {
+        Some(end) if url[end..].starts_with(':') => {
+            let scheme = &url[..end];
+            ALLOWED_SCHEMES.contains(&scheme) || (image && url.starts_with("data:image/"))
+        }
+        _ => true,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html::push_html, Parser};
+
+    fn render(markdown: &str) -> String {
+        let mut output = String::new();
+        push_html(&mut output, html_events(Parser::new(markdown)).into_iter());
+        output
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("<p onclick=\"x()\" title=\"t\">Hi</p>"), "<p title=\"t\">Hi</p>");
+        assert_eq!(sanitize("<script>alert(1)</script>after"), "after");
+        assert_eq!(sanitize("<font color=red>kept</font>"), "kept");
+        assert_eq!(sanitize("<!-- note -->a < b"), "a &lt; b");
+        assert_eq!(sanitize("<img src=x.png onerror=alert(1)>"), "<img src=\"x.png\">");
+        assert_eq!(sanitize("<b>open"), "<b>open");
+        assert_eq!(sanitize("<a href=\"x"), "&lt;a href=&quot;x");
+    }
+
+    #[test]
+    fn test_unsafe_urls() {
+        assert_eq!(
+            sanitize("<a href=\"java\tscript:alert(1)\">x</a>"),
+            "<a rel=\"noopener noreferrer\">x</a>"
+        );
+        assert_eq!(
+            sanitize("<a href=\"&#106;avascript:alert(1)\">x</a>"),
+            "<a rel=\"noopener noreferrer\">x</a>"
+        );
+        assert_eq!(
+            sanitize("<a href=\"https://example.com/?a=1&amp;b=2\">x</a>"),
+            "<a href=\"https://example.com/?a=1&amp;b=2\" rel=\"noopener noreferrer\">x</a>"
+        );
+        assert!(sanitize("<img src=\"data:image/png;base64,AA==\">").contains("src="));
+        assert!(!sanitize("<a href=\"data:text/html,x\">x</a>").contains("href="));
+        assert!(sanitize("<a href=\"notes/a.md#top\">x</a>").contains("href=\"notes/a.md#top\""));
+    }
+
+    #[test]
+    fn test_html_events() {
+        // A block's tag split over lines
+        let html = render("<div\nonmouseover=\"x()\">\ntext\n</div>\n");
+        assert_eq!(html, "<div>\ntext\n</div>\n");
+
+        // Inline scripts drop the text between their tags
+        let html = render("Before <script>alert(1)</script> after *em*");
+        assert_eq!(html, "<p>Before  after <em>em</em></p>\n");
+
+        // An unclosed element hides text only to the end of its paragraph
+        let html = render("a <style>b\n\nc");
+        assert_eq!(html, "<p>a </p>\n<p>c</p>\n");
+
+        // Math placeholders are left for the math stage
+        let placeholder = super::super::math::placeholder(0);
+        let events = html_events(vec![Event::Html(CowStr::from(placeholder.clone()))].into_iter());
+        assert_eq!(events, vec![Event::Html(CowStr::from(placeholder))]);
+    }
+}
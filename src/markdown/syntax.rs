@@ -4,8 +4,15 @@
 //! supporting both standard Markdown and GitHub Flavored Markdown (GFM) extensions.
 
 use std::collections::HashMap;
+use std::ops::Range;
 use cosmic::iced_core::Color;
 
+/// Deepest level of inline styles inside one another that is tokenized
+const MAX_INLINE_DEPTH: usize = 4;
+
+/// Columns of indent that make a line indented code
+const CODE_INDENT: usize = 4;
+
 /// Types of Markdown tokens recognized by the tokenizer
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TokenType {
@@ -42,12 +49,50 @@ pub enum TokenType {
     Footnote,
     FootnoteReference,
     
+    // Extensions
+    Math,
+    WikiLink,
+    
     // Special
     Frontmatter,
     PlainText,
     Escape,
 }
 
+impl TokenType {
+    /// Whether this is a heading of any level
+    pub fn is_heading(self) -> bool {
+        matches!(
+            self,
+            TokenType::Heading1
+                | TokenType::Heading2
+                | TokenType::Heading3
+                | TokenType::Heading4
+                | TokenType::Heading5
+                | TokenType::Heading6
+        )
+    }
+
+    /// Whether a line starting with this token begins a block other than a paragraph
+    fn starts_block(self) -> bool {
+        self.is_heading()
+            || matches!(
+                self,
+                TokenType::CodeBlockDelimiter
+                    | TokenType::CodeBlockContent
+                    | TokenType::HorizontalRule
+                    | TokenType::Blockquote
+                    | TokenType::UnorderedListMarker
+                    | TokenType::OrderedListMarker
+                    | TokenType::TaskListChecked
+                    | TokenType::TaskListUnchecked
+                    | TokenType::TableDelimiter
+                    | TokenType::TableCell
+                    | TokenType::Frontmatter
+            )
+    }
+}
+
 /// A single token in a line of Markdown text
 #[derive(Debug, Clone)]
 pub struct Token {
@@ -75,6 +120,11 @@ impl Token {
         self.nested_style = Some(Box::new(nested));
         self
     }
+
+    /// This token followed by its nested styles, outermost first
+    pub fn styles(&self) -> impl Iterator<Item = &Token> {
+        std::iter::successors(Some(self), |token| token.nested_style.as_deref())
+    }
     
     /// Length of this token in bytes
     pub fn len(&self) -> usize {
@@ -257,6 +307,7 @@ impl SyntaxColorScheme {
             foreground: Color::from_rgb(0.5, 0.5, 0.5),
             ..Default::default()
         });
+        styles.insert(TokenType::TableCell, TokenStyle::default());
         
         // Autolinks
         styles.insert(TokenType::Autolink, TokenStyle {
@@ -276,6 +327,20 @@ impl SyntaxColorScheme {
             ..Default::default()
         });
         
+        // Math
+        styles.insert(TokenType::Math, TokenStyle {
+            foreground: Color::from_rgb(0.0, 0.45, 0.45),
+            background: Some(code_bg),
+            ..Default::default()
+        });
+        
+        // Wiki links
+        styles.insert(TokenType::WikiLink, TokenStyle {
+            foreground: Color::from_rgb(0.4, 0.2, 0.7),
+            underline: true,
+            ..Default::default()
+        });
+        
         // Frontmatter
         styles.insert(TokenType::Frontmatter, TokenStyle {
             foreground: Color::from_rgb(0.6, 0.0, 0.6),
@@ -436,6 +501,10 @@ impl SyntaxColorScheme {
             foreground: Color::from_rgb(0.5, 0.5, 0.5),
             ..Default::default()
         });
+        styles.insert(TokenType::TableCell, TokenStyle {
+            foreground: Color::from_rgb(0.9, 0.9, 0.9),
+            ..Default::default()
+        });
         
         // Autolinks
         styles.insert(TokenType::Autolink, TokenStyle {
@@ -455,6 +524,20 @@ impl SyntaxColorScheme {
             ..Default::default()
         });
         
+        // Math
+        styles.insert(TokenType::Math, TokenStyle {
+            foreground: Color::from_rgb(0.4, 0.85, 0.85),
+            background: Some(code_bg),
+            ..Default::default()
+        });
+        
+        // Wiki links
+        styles.insert(TokenType::WikiLink, TokenStyle {
+            foreground: Color::from_rgb(0.75, 0.6, 1.0),
+            underline: true,
+            ..Default::default()
+        });
+        
         // Frontmatter
         styles.insert(TokenType::Frontmatter, TokenStyle {
             foreground: Color::from_rgb(0.8, 0.4, 0.8),
@@ -487,7 +570,7 @@ impl SyntaxColorScheme {
 }
 
 /// Line-level tokenization state for multi-line constructs
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LineState {
     /// Normal state
     Normal,
@@ -495,6 +578,13 @@ pub enum LineState {
     InCodeBlock { fence_char: char, fence_count: usize },
     /// Inside a frontmatter block
     InFrontmatter,
+    /// Inside a display math block ($$)
+    InMathBlock,
+    /// Inside an indented code block whose lines are indented at least
+    /// `indent` columns
+    InIndentedCode { indent: usize },
+    /// In the body of a table, after its header and delimiter rows
+    InTable,
 }
 
 /// Cached tokens for a single line
@@ -518,81 +608,244 @@ impl LineTokens {
     }
 }
 
+/// Default number of lines the tokenizer keeps cached
+pub const DEFAULT_TOKEN_CACHE_SIZE: usize = 10_000;
+
 /// The main syntax tokenizer for Markdown
+///
+/// Line tokens are cached by the line's content and starting state rather
+/// than its line number, so inserting or removing lines never leaves stale
+/// entries behind. The cache is bounded; the least recently used lines are
+/// evicted first.
 pub struct MarkdownTokenizer {
-    /// Cached tokens per line
-    line_cache: HashMap<usize, LineTokens>,
+    /// Cached tokens with the tick they were last used, by line key
+    line_cache: HashMap<u64, (LineTokens, u64)>,
+    /// Most lines kept in the cache
+    capacity: usize,
+    /// Incremented on every lookup, for least-recently-used eviction
+    tick: u64,
+    /// Keys of the lines of the last tokenized document, in order
+    document_keys: Vec<u64>,
 }
 
 impl MarkdownTokenizer {
     pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_TOKEN_CACHE_SIZE)
+    }
+
+    /// Create a tokenizer caching at most `capacity` lines
+    pub fn with_capacity(capacity: usize) -> Self {
         Self {
             line_cache: HashMap::new(),
+            capacity: capacity.max(1),
+            tick: 0,
+            document_keys: Vec::new(),
         }
     }
     
     /// Clear the cache
     pub fn clear_cache(&mut self) {
         self.line_cache.clear();
+        self.document_keys.clear();
     }
     
-    /// Invalidate cache for a specific line and all following lines
+    /// Drop cached tokens for the last tokenized document's lines from
+    /// `line_num` on
+    ///
+    /// Not needed for correctness, since entries are keyed by content, but
+    /// frees lines an edit is known to have replaced.
     pub fn invalidate_from_line(&mut self, line_num: usize) {
-        self.line_cache.retain(|&k, _| k < line_num);
+        if line_num >= self.document_keys.len() {
+            return;
+        }
+        for key in self.document_keys.split_off(line_num) {
+            self.line_cache.remove(&key);
+        }
     }
     
-    /// Calculate a simple hash for content
-    fn hash_content(content: &str) -> u64 {
+    /// Cache key for a line's content, the state it starts in, and whether
+    /// it is the first line (where front matter can open)
+    fn cache_key(content: &str, state: LineState, first_line: bool) -> u64 {
         use std::hash::{Hash, Hasher};
         let mut hasher = std::collections::hash_map::DefaultHasher::new();
         content.hash(&mut hasher);
+        state.hash(&mut hasher);
+        first_line.hash(&mut hasher);
         hasher.finish()
     }
-    
-    /// Get the starting state for a line
-    fn get_start_state(&self, line_num: usize) -> LineState {
-        if line_num == 0 {
-            LineState::Normal
-        } else if let Some(prev) = self.line_cache.get(&(line_num - 1)) {
-            prev.end_state
-        } else {
-            LineState::Normal
+
+    /// Make room for one more entry, dropping the least recently used
+    /// quarter of the cache once it is full
+    fn evict(&mut self) {
+        if self.line_cache.len() < self.capacity {
+            return;
         }
+        let mut ticks: Vec<u64> = self.line_cache.values().map(|(_, tick)| *tick).collect();
+        let drop = (self.capacity / 4).max(1).min(ticks.len());
+        let (_, &mut threshold, _) = ticks.select_nth_unstable(drop - 1);
+        self.line_cache.retain(|_, (_, tick)| *tick > threshold);
     }
     
     /// Tokenize a single line
     pub fn tokenize_line(&mut self, line_num: usize, content: &str, start_state: LineState) -> &LineTokens {
-        let content_hash = Self::hash_content(content);
-        
-        // Check cache
-        if let Some(cached) = self.line_cache.get(&line_num) {
-            if cached.content_hash == content_hash {
-                return self.line_cache.get(&line_num).unwrap();
-            }
+        let key = Self::cache_key(content, start_state, line_num == 0);
+        self.tick += 1;
+
+        if !self.line_cache.contains_key(&key) {
+            let (tokens, end_state) = self.do_tokenize(content, start_state, line_num == 0);
+            self.evict();
+            self.line_cache.insert(key, (LineTokens::new(tokens, end_state, key), self.tick));
         }
-        
-        let (tokens, end_state) = self.do_tokenize(content, start_state);
-        let line_tokens = LineTokens::new(tokens, end_state, content_hash);
-        self.line_cache.insert(line_num, line_tokens);
-        self.line_cache.get(&line_num).unwrap()
+
+        let (line_tokens, tick) = self.line_cache.get_mut(&key).expect("entry was just ensured");
+        *tick = self.tick;
+        line_tokens
     }
     
     /// Tokenize all lines in a document
+    ///
+    /// Unlike single lines, this also recognizes constructs that depend on
+    /// the lines around them:
+    /// - Setext headings: the paragraph text above a `===` or `---`
+    ///   underline becomes a heading
+    /// - Indented code: after a blank line, a line indented four columns
+    ///   past the enclosing list item's content (or the margin) starts code
+    /// - Tables: a paragraph line followed by a `---|---` delimiter row with
+    ///   as many cells becomes the header, and the table's body rows follow
     pub fn tokenize_document(&mut self, lines: &[&str]) -> Vec<LineTokens> {
-        let mut result = Vec::with_capacity(lines.len());
+        let mut result: Vec<LineTokens> = Vec::with_capacity(lines.len());
         let mut state = LineState::Normal;
+        // First line of the paragraph run ending at the previous line
+        let mut paragraph_start: Option<usize> = None;
+        // Content column of the innermost open list item
+        let mut list_indent: Option<usize> = None;
+        let mut after_blank = true;
+        self.document_keys.clear();
         
         for (i, line) in lines.iter().enumerate() {
-            let line_tokens = self.tokenize_line(i, line, state);
+            let blank = line.trim().is_empty();
+            let (columns, _) = Self::indent_width(line);
+            if state == LineState::Normal && after_blank && !blank {
+                // Less indented than the item's content closes the list
+                if list_indent.is_some_and(|indent| columns < indent)
+                    && self.list_content_column(line).is_none()
+                {
+                    list_indent = None;
+                }
+                let indent = list_indent.unwrap_or(0) + CODE_INDENT;
+                if columns >= indent {
+                    state = LineState::InIndentedCode { indent };
+                }
+            }
+
+            let start_state = state;
+            let mut line_tokens = self.tokenize_line(i, line, state).clone();
+            self.document_keys.push(line_tokens.content_hash);
             state = line_tokens.end_state;
-            result.push(line_tokens.clone());
+            after_blank = blank;
+
+            let header = (i > 0 && start_state == LineState::Normal && paragraph_start.is_some())
+                .then(|| lines[i - 1])
+                .filter(|header| Self::is_table_header(header, line));
+            let underline = paragraph_start
+                .filter(|_| start_state == LineState::Normal)
+                .and_then(|start| Some((start, Self::parse_setext_underline(line)?)));
+            if let Some(header) = header {
+                result[i - 1].tokens = self.table_row_tokens(header);
+                line_tokens.tokens = vec![Token::new(TokenType::TableDelimiter, 0, line.len())];
+                line_tokens.end_state = LineState::InTable;
+                state = LineState::InTable;
+                paragraph_start = None;
+            } else if let Some((start, level)) = underline {
+                let token_type = if level == 1 { TokenType::Heading1 } else { TokenType::Heading2 };
+                for (text, tokens) in lines[start..i].iter().zip(&mut result[start..i]) {
+                    tokens.tokens.retain(|t| t.token_type != TokenType::PlainText);
+                    tokens.tokens.insert(0, Token::new(token_type, 0, text.len()));
+                }
+                line_tokens.tokens = vec![Token::new(token_type, 0, line.len())];
+                line_tokens.end_state = LineState::Normal;
+                state = LineState::Normal;
+                paragraph_start = None;
+            } else if Self::is_paragraph_text(line, start_state, &line_tokens) {
+                paragraph_start.get_or_insert(i);
+            } else {
+                paragraph_start = None;
+            }
+
+            if state == LineState::Normal && !blank {
+                if let Some(column) = self.list_content_column(line) {
+                    list_indent = Some(column);
+                } else if list_indent.is_some_and(|indent| columns < indent) && paragraph_start.is_none() {
+                    list_indent = None;
+                }
+            }
+
+            result.push(line_tokens);
         }
         
         result
     }
+
+    /// Parse a setext heading underline, returning the heading level
+    ///
+    /// `===` makes an H1 and `---` an H2; up to three spaces of indent and
+    /// trailing spaces are allowed, but not spaces between the markers.
+    pub fn parse_setext_underline(line: &str) -> Option<usize> {
+        let trimmed = line.trim_end();
+        let content = trimmed.trim_start_matches(' ');
+        if trimmed.len() - content.len() > 3 {
+            return None;
+        }
+        match content.chars().next()? {
+            '=' if content.chars().all(|c| c == '=') => Some(1),
+            '-' if content.chars().all(|c| c == '-') => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Indent of a line in columns, with tabs to the next multiple of four,
+    /// and its length in bytes
+    pub fn indent_width(line: &str) -> (usize, usize) {
+        let mut columns = 0;
+        for (i, c) in line.char_indices() {
+            match c {
+                ' ' => columns += 1,
+                '\t' => columns += CODE_INDENT - columns % CODE_INDENT,
+                _ => return (columns, i),
+            }
+        }
+        (columns, line.len())
+    }
+
+    /// Column where a list item's content starts, if the line is one
+    fn list_content_column(&self, line: &str) -> Option<usize> {
+        let (columns, bytes) = Self::indent_width(line);
+        let rest = &line[bytes..];
+        if self.parse_unordered_list(rest).is_some() {
+            Some(columns + 2)
+        } else {
+            self.parse_ordered_list(rest).map(|marker_end| columns + marker_end)
+        }
+    }
+
+    /// Check whether a tokenized line is paragraph text a setext underline can apply to
+    fn is_paragraph_text(line: &str, start_state: LineState, tokens: &LineTokens) -> bool {
+        let trimmed = line.trim_start();
+        let continues = matches!(start_state, LineState::Normal | LineState::InIndentedCode { .. });
+        if !continues
+            || tokens.end_state != LineState::Normal
+            || trimmed.is_empty()
+            || trimmed.starts_with("$$")
+        {
+            return false;
+        }
+        !tokens.tokens.first().is_some_and(|token| token.token_type.starts_block())
+    }
     
     /// Perform the actual tokenization
-    fn do_tokenize(&self, line: &str, state: LineState) -> (Vec<Token>, LineState) {
+    ///
+    /// A `---` line opens front matter only as the first line of the document.
+    fn do_tokenize(&self, line: &str, state: LineState, first_line: bool) -> (Vec<Token>, LineState) {
         let mut tokens = Vec::new();
         let trimmed = line.trim_start();
         let leading_spaces = line.len() - trimmed.len();
@@ -610,18 +863,47 @@ impl MarkdownTokenizer {
                 return (tokens, state);
             }
             LineState::InFrontmatter => {
-                if trimmed == "---" {
+                if matches!(trimmed.trim_end(), "---" | "...") {
                     tokens.push(Token::new(TokenType::Frontmatter, 0, line.len()));
                     return (tokens, LineState::Normal);
                 }
                 tokens.push(Token::new(TokenType::Frontmatter, 0, line.len()));
                 return (tokens, state);
             }
+            LineState::InMathBlock => {
+                tokens.push(Token::new(TokenType::Math, 0, line.len()));
+                if trimmed.trim_end().ends_with("$$") {
+                    return (tokens, LineState::Normal);
+                }
+                return (tokens, state);
+            }
+            LineState::InIndentedCode { indent } => {
+                // Blank lines may sit between indented code lines
+                if trimmed.is_empty() {
+                    return (tokens, state);
+                }
+                if Self::indent_width(line).0 >= indent {
+                    tokens.push(Token::new(TokenType::CodeBlockContent, 0, line.len()));
+                    return (tokens, state);
+                }
+                return self.do_tokenize(line, LineState::Normal, first_line);
+            }
+            LineState::InTable => {
+                // A blank line or another block ends the table
+                if trimmed.is_empty() {
+                    return (tokens, LineState::Normal);
+                }
+                let (block, end_state) = self.do_tokenize(line, LineState::Normal, first_line);
+                if end_state != LineState::Normal || block.first().is_some_and(|t| t.token_type.starts_block()) {
+                    return (block, end_state);
+                }
+                return (self.table_row_tokens(line), state);
+            }
             LineState::Normal => {}
         }
         
         // Check for frontmatter start (only at beginning of document)
-        if line == "---" {
+        if first_line && line.trim_end() == "---" {
             tokens.push(Token::new(TokenType::Frontmatter, 0, line.len()));
             return (tokens, LineState::InFrontmatter);
         }
@@ -643,8 +925,18 @@ impl MarkdownTokenizer {
             });
         }
         
+        // Check for display math ($$)
+        if leading_spaces < 4 && trimmed.starts_with("$$") {
+            tokens.push(Token::new(TokenType::Math, 0, line.len()));
+            let rest = trimmed[2..].trim_end();
+            if rest.len() >= 2 && rest.ends_with("$$") {
+                return (tokens, LineState::Normal);
+            }
+            return (tokens, LineState::InMathBlock);
+        }
+        
         // Check for heading
-        if let Some((level, content_start)) = self.parse_heading(trimmed) {
+        if let Some((level, content_start)) = Self::parse_heading(trimmed) {
             let token_type = match level {
                 1 => TokenType::Heading1,
                 2 => TokenType::Heading2,
@@ -667,9 +959,8 @@ impl MarkdownTokenizer {
         }
         
         // Check for blockquote
-        if trimmed.starts_with('>') {
+        if let Some(content_start) = Self::parse_blockquote(trimmed) {
             tokens.push(Token::new(TokenType::Blockquote, leading_spaces, leading_spaces + 1));
-            let content_start = if trimmed.len() > 1 && trimmed.chars().nth(1) == Some(' ') { 2 } else { 1 };
             let inline_tokens = self.tokenize_inline(&trimmed[content_start..], leading_spaces + content_start);
             tokens.extend(inline_tokens);
             return (tokens, LineState::Normal);
@@ -700,12 +991,6 @@ impl MarkdownTokenizer {
             return (tokens, LineState::Normal);
         }
         
-        // Check for table row
-        if trimmed.contains('|') && self.is_table_row(trimmed) {
-            tokens.push(Token::new(TokenType::TableDelimiter, 0, line.len()));
-            return (tokens, LineState::Normal);
-        }
-        
         // Regular line - tokenize inline elements
         let inline_tokens = self.tokenize_inline(line, 0);
         if inline_tokens.is_empty() && !line.is_empty() {
@@ -751,8 +1036,8 @@ impl MarkdownTokenizer {
         })
     }
     
-    /// Parse a heading
-    fn parse_heading(&self, line: &str) -> Option<(usize, usize)> {
+    /// Parse a heading, returning (level, content_start)
+    pub fn parse_heading(line: &str) -> Option<(usize, usize)> {
         let chars: Vec<char> = line.chars().collect();
         if chars.is_empty() || chars[0] != '#' {
             return None;
@@ -780,6 +1065,18 @@ impl MarkdownTokenizer {
         Some((level, content_start))
     }
     
+    /// Parse one level of blockquote marker, returning content_start
+    pub fn parse_blockquote(line: &str) -> Option<usize> {
+        if !line.starts_with('>') {
+            return None;
+        }
+        if line.len() > 1 && line.chars().nth(1) == Some(' ') {
+            Some(2)
+        } else {
+            Some(1)
+        }
+    }
+    
     /// Check if line is a horizontal rule
     fn is_horizontal_rule(&self, line: &str) -> bool {
         let trimmed = line.trim();
@@ -853,19 +1150,101 @@ impl MarkdownTokenizer {
         None
     }
     
-    /// Check if line is a table row
-    fn is_table_row(&self, line: &str) -> bool {
-        let trimmed = line.trim();
-        // Simple heuristic: contains | and doesn't look like other constructs
-        trimmed.starts_with('|') || trimmed.ends_with('|') || trimmed.contains(" | ")
+    /// Split a table row into its cells' content and its pipes
+    ///
+    /// Ranges are in chars and trimmed of spaces; escaped pipes (`\|`) don't
+    /// split, and leading or trailing pipes don't add empty cells.
+    fn split_table_row(chars: &[char]) -> (Vec<Range<usize>>, Vec<usize>) {
+        let mut pipes = Vec::new();
+        let mut escaped = false;
+        for (i, &c) in chars.iter().enumerate() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '|' => pipes.push(i),
+                _ => {}
+            }
+        }
+
+        let mut cells = Vec::with_capacity(pipes.len() + 1);
+        let mut start = 0;
+        for &end in pipes.iter().chain(std::iter::once(&chars.len())) {
+            let mut range = start..end;
+            while range.start < range.end && chars[range.start].is_whitespace() {
+                range.start += 1;
+            }
+            while range.end > range.start && chars[range.end - 1].is_whitespace() {
+                range.end -= 1;
+            }
+            cells.push(range);
+            start = end + 1;
+        }
+
+        if !pipes.is_empty() {
+            if cells.last().is_some_and(Range::is_empty) {
+                cells.pop();
+            }
+            if cells.first().is_some_and(Range::is_empty) {
+                cells.remove(0);
+            }
+        }
+        (cells, pipes)
+    }
+
+    /// Number of cells in a table delimiter row such as `| :--- | ---: |`
+    pub fn parse_table_delimiter(line: &str) -> Option<usize> {
+        let chars: Vec<char> = line.chars().collect();
+        let (cells, _) = Self::split_table_row(&chars);
+        let valid = !cells.is_empty()
+            && cells.iter().all(|cell| {
+                let cell: String = chars[cell.clone()].iter().collect();
+                let dashes = cell.strip_prefix(':').unwrap_or(&cell);
+                let dashes = dashes.strip_suffix(':').unwrap_or(dashes);
+                !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+            });
+        valid.then_some(cells.len())
+    }
+
+    /// Check whether a line and the delimiter row after it start a table
+    ///
+    /// The header needs as many cells as the delimiter row, and one of the
+    /// two must contain a pipe so `text` over `---` stays a setext heading.
+    fn is_table_header(header: &str, delimiter: &str) -> bool {
+        let Some(columns) = Self::parse_table_delimiter(delimiter) else {
+            return false;
+        };
+        let chars: Vec<char> = header.chars().collect();
+        let (cells, pipes) = Self::split_table_row(&chars);
+        cells.len() == columns && (!pipes.is_empty() || delimiter.contains('|'))
+    }
+
+    /// Tokens for a table row: its pipes, and a cell token per cell with
+    /// the cell's inline styles nested
+    fn table_row_tokens(&self, line: &str) -> Vec<Token> {
+        let chars: Vec<char> = line.chars().collect();
+        let (cells, pipes) = Self::split_table_row(&chars);
+        let mut tokens: Vec<Token> = pipes
+            .into_iter()
+            .map(|pipe| Token::new(TokenType::TableDelimiter, pipe, pipe + 1))
+            .collect();
+        for cell in cells.into_iter().filter(|cell| !cell.is_empty()) {
+            tokens.extend(self.nested_span(TokenType::TableCell, &chars, cell.clone(), cell, 0, 0));
+        }
+        tokens.sort_by_key(|token| token.start);
+        tokens
     }
     
     /// Tokenize inline elements
     fn tokenize_inline(&self, text: &str, offset: usize) -> Vec<Token> {
+        let chars: Vec<char> = text.chars().collect();
+        self.tokenize_chars(&chars, offset, 0)
+    }
+
+    /// Tokenize inline elements at a nesting depth
+    fn tokenize_chars(&self, chars: &[char], offset: usize, depth: usize) -> Vec<Token> {
         let mut tokens = Vec::new();
         let mut pos = 0;
-        let chars: Vec<char> = text.chars().collect();
-        
+
         while pos < chars.len() {
             // Check for escape
             if chars[pos] == '\\' && pos + 1 < chars.len() {
@@ -876,17 +1255,30 @@ impl MarkdownTokenizer {
             
             // Check for inline code
             if chars[pos] == '`' {
-                if let Some((end, _)) = self.find_inline_code(&chars, pos) {
+                if let Some((end, _)) = self.find_inline_code(chars, pos) {
                     tokens.push(Token::new(TokenType::InlineCode, offset + pos, offset + end));
                     pos = end;
                     continue;
                 }
             }
             
+            // Check for inline math ($...$)
+            if chars[pos] == '$' {
+                if pos + 1 < chars.len() && chars[pos + 1] == '$' {
+                    pos += 2;
+                    continue;
+                }
+                if let Some(end) = super::math::find_inline_math(chars, pos) {
+                    tokens.push(Token::new(TokenType::Math, offset + pos, offset + end));
+                    pos = end;
+                    continue;
+                }
+            }
+            
             // Check for bold italic (***)
             if pos + 2 < chars.len() && chars[pos] == '*' && chars[pos + 1] == '*' && chars[pos + 2] == '*' {
-                if let Some(end) = self.find_closing(&chars, pos + 3, "***") {
-                    tokens.push(Token::new(TokenType::BoldItalic, offset + pos, offset + end));
+                if let Some(end) = self.find_closing(chars, pos + 3, "***") {
+                    tokens.extend(self.nested_span(TokenType::BoldItalic, chars, pos..end, pos + 3..end - 3, offset, depth));
                     pos = end;
                     continue;
                 }
@@ -894,8 +1286,8 @@ impl MarkdownTokenizer {
             
             // Check for bold (**)
             if pos + 1 < chars.len() && chars[pos] == '*' && chars[pos + 1] == '*' {
-                if let Some(end) = self.find_closing(&chars, pos + 2, "**") {
-                    tokens.push(Token::new(TokenType::Bold, offset + pos, offset + end));
+                if let Some(end) = self.find_closing(chars, pos + 2, "**") {
+                    tokens.extend(self.nested_span(TokenType::Bold, chars, pos..end, pos + 2..end - 2, offset, depth));
                     pos = end;
                     continue;
                 }
@@ -903,8 +1295,8 @@ impl MarkdownTokenizer {
             
             // Check for bold (__)
             if pos + 1 < chars.len() && chars[pos] == '_' && chars[pos + 1] == '_' {
-                if let Some(end) = self.find_closing(&chars, pos + 2, "__") {
-                    tokens.push(Token::new(TokenType::Bold, offset + pos, offset + end));
+                if let Some(end) = self.find_closing(chars, pos + 2, "__") {
+                    tokens.extend(self.nested_span(TokenType::Bold, chars, pos..end, pos + 2..end - 2, offset, depth));
                     pos = end;
                     continue;
                 }
@@ -912,8 +1304,8 @@ impl MarkdownTokenizer {
             
             // Check for strikethrough (~~)
             if pos + 1 < chars.len() && chars[pos] == '~' && chars[pos + 1] == '~' {
-                if let Some(end) = self.find_closing(&chars, pos + 2, "~~") {
-                    tokens.push(Token::new(TokenType::Strikethrough, offset + pos, offset + end));
+                if let Some(end) = self.find_closing(chars, pos + 2, "~~") {
+                    tokens.extend(self.nested_span(TokenType::Strikethrough, chars, pos..end, pos + 2..end - 2, offset, depth));
                     pos = end;
                     continue;
                 }
@@ -921,8 +1313,8 @@ impl MarkdownTokenizer {
             
             // Check for italic (*)
             if chars[pos] == '*' {
-                if let Some(end) = self.find_closing(&chars, pos + 1, "*") {
-                    tokens.push(Token::new(TokenType::Italic, offset + pos, offset + end));
+                if let Some(end) = self.find_closing(chars, pos + 1, "*") {
+                    tokens.extend(self.nested_span(TokenType::Italic, chars, pos..end, pos + 1..end - 1, offset, depth));
                     pos = end;
                     continue;
                 }
@@ -930,8 +1322,8 @@ impl MarkdownTokenizer {
             
             // Check for italic (_)
             if chars[pos] == '_' {
-                if let Some(end) = self.find_closing(&chars, pos + 1, "_") {
-                    tokens.push(Token::new(TokenType::Italic, offset + pos, offset + end));
+                if let Some(end) = self.find_closing(chars, pos + 1, "_") {
+                    tokens.extend(self.nested_span(TokenType::Italic, chars, pos..end, pos + 1..end - 1, offset, depth));
                     pos = end;
                     continue;
                 }
@@ -939,8 +1331,15 @@ impl MarkdownTokenizer {
             
             // Check for image ![]()
             if chars[pos] == '!' && pos + 1 < chars.len() && chars[pos + 1] == '[' {
-                if let Some((alt_end, url_end)) = self.find_link(&chars, pos + 1) {
-                    tokens.push(Token::new(TokenType::ImageAlt, offset + pos, offset + alt_end));
+                if let Some((alt_end, mut url_end)) = self.find_link(chars, pos + 1) {
+                    // A `{width=200}` size block is part of the image
+                    if chars.get(url_end) == Some(&'{') {
+                        if let Some(close) = chars[url_end..].iter().position(|&c| c == '}') {
+                            url_end += close + 1;
+                        }
+                    }
+                    let alt = pos + 2..alt_end - 1;
+                    tokens.extend(self.nested_span(TokenType::ImageAlt, chars, pos..alt_end, alt, offset, depth));
                     tokens.push(Token::new(TokenType::ImageUrl, offset + alt_end, offset + url_end));
                     pos = url_end;
                     continue;
@@ -949,17 +1348,26 @@ impl MarkdownTokenizer {
             
             // Check for link []()
             if chars[pos] == '[' {
-                if let Some((text_end, url_end)) = self.find_link(&chars, pos) {
-                    tokens.push(Token::new(TokenType::LinkText, offset + pos, offset + text_end));
+                if let Some((text_end, url_end)) = self.find_link(chars, pos) {
+                    tokens.extend(self.nested_span(TokenType::LinkText, chars, pos..text_end, pos + 1..text_end - 1, offset, depth));
                     tokens.push(Token::new(TokenType::LinkUrl, offset + text_end, offset + url_end));
                     pos = url_end;
                     continue;
                 }
             }
             
+            // Check for wiki link [[Note]]
+            if chars[pos] == '[' && pos + 1 < chars.len() && chars[pos + 1] == '[' {
+                if let Some(end) = self.find_wiki_link(chars, pos) {
+                    tokens.push(Token::new(TokenType::WikiLink, offset + pos, offset + end));
+                    pos = end;
+                    continue;
+                }
+            }
+            
             // Check for footnote reference [^id]
             if chars[pos] == '[' && pos + 1 < chars.len() && chars[pos + 1] == '^' {
-                if let Some(end) = self.find_footnote_ref(&chars, pos) {
+                if let Some(end) = self.find_footnote_ref(chars, pos) {
                     tokens.push(Token::new(TokenType::FootnoteReference, offset + pos, offset + end));
                     pos = end;
                     continue;
@@ -967,8 +1375,8 @@ impl MarkdownTokenizer {
             }
             
             // Check for autolink
-            if self.is_autolink_start(&chars, pos) {
-                if let Some(end) = self.find_autolink_end(&chars, pos) {
+            if self.is_autolink_start(chars, pos) {
+                if let Some(end) = self.find_autolink_end(chars, pos) {
                     tokens.push(Token::new(TokenType::Autolink, offset + pos, offset + end));
                     pos = end;
                     continue;
@@ -981,6 +1389,40 @@ impl MarkdownTokenizer {
         tokens
     }
     
+    /// Tokens for a span, split around the styles nested in its content
+    ///
+    /// `span` includes the markers and `content` is the part between them.
+    /// The pieces keep the span's type, in order and without overlap, and a
+    /// piece covering a nested token carries it.
+    fn nested_span(
+        &self,
+        token_type: TokenType,
+        chars: &[char],
+        span: Range<usize>,
+        content: Range<usize>,
+        offset: usize,
+        depth: usize,
+    ) -> Vec<Token> {
+        let (start, end) = (offset + span.start, offset + span.end);
+        if depth + 1 >= MAX_INLINE_DEPTH || content.is_empty() {
+            return vec![Token::new(token_type, start, end)];
+        }
+
+        let mut pieces = Vec::new();
+        let mut pos = start;
+        for nested in self.tokenize_chars(&chars[content.clone()], offset + content.start, depth + 1) {
+            if nested.start > pos {
+                pieces.push(Token::new(token_type, pos, nested.start));
+            }
+            pos = nested.end;
+            pieces.push(Token::new(token_type, nested.start, nested.end).with_nested(nested));
+        }
+        if end > pos {
+            pieces.push(Token::new(token_type, pos, end));
+        }
+        pieces
+    }
+
     /// Find inline code end
     fn find_inline_code(&self, chars: &[char], start: usize) -> Option<(usize, usize)> {
         let mut backticks = 0;
@@ -1082,6 +1524,15 @@ impl MarkdownTokenizer {
         Some((text_end, pos))
     }
     
+    /// Find wiki link end [[Note]]
+    fn find_wiki_link(&self, chars: &[char], start: usize) -> Option<usize> {
+        let inner_start = start + 2;
+        let close = chars[inner_start..].windows(2).position(|pair| pair == [']', ']'])?;
+        let inner: String = chars[inner_start..inner_start + close].iter().collect();
+        super::wiki_link::WikiLink::parse(&inner)?;
+        Some(inner_start + close + 2)
+    }
+    
     /// Find footnote reference end
     fn find_footnote_ref(&self, chars: &[char], start: usize) -> Option<usize> {
         if start + 2 >= chars.len() || chars[start] != '[' || chars[start + 1] != '^' {
@@ -1201,4 +1652,264 @@ mod tests {
         let checked = tokenizer.tokenize_line(1, "- [x] Done item", LineState::Normal);
         assert!(checked.tokens.iter().any(|t| t.token_type == TokenType::TaskListChecked));
     }
+    
+    #[test]
+    fn test_image_size_tokenization() {
+        let mut tokenizer = MarkdownTokenizer::new();
+
+        for line in ["![a](img.png =200x100) x", "![a](img.png){width=200} x"] {
+            let tokens = &tokenizer.tokenize_line(0, line, LineState::Normal).tokens;
+            let url = tokens.iter().find(|t| t.token_type == TokenType::ImageUrl).unwrap();
+            assert_eq!(url.end, line.len() - 2);
+        }
+    }
+
+    #[test]
+    fn test_nested_inline_tokenization() {
+        let mut tokenizer = MarkdownTokenizer::new();
+        let spans = |tokens: &[Token]| -> Vec<(TokenType, usize, usize, Option<TokenType>)> {
+            tokens
+                .iter()
+                .map(|t| (t.token_type, t.start, t.end, t.nested_style.as_ref().map(|n| n.token_type)))
+                .collect()
+        };
+
+        let line = tokenizer.tokenize_line(0, "[*em*](u)", LineState::Normal);
+        assert_eq!(
+            spans(&line.tokens),
+            vec![
+                (TokenType::LinkText, 0, 1, None),
+                (TokenType::LinkText, 1, 5, Some(TokenType::Italic)),
+                (TokenType::LinkText, 5, 6, None),
+                (TokenType::LinkUrl, 6, 9, None),
+            ]
+        );
+
+        let line = tokenizer.tokenize_line(1, "**[link](u)**", LineState::Normal);
+        assert_eq!(
+            spans(&line.tokens),
+            vec![
+                (TokenType::Bold, 0, 2, None),
+                (TokenType::Bold, 2, 8, Some(TokenType::LinkText)),
+                (TokenType::Bold, 8, 11, Some(TokenType::LinkUrl)),
+                (TokenType::Bold, 11, 13, None),
+            ]
+        );
+
+        // Brackets inside link text, and bold alt text
+        let line = tokenizer.tokenize_line(2, "[a [b] c](u) ![**x**](i.png)", LineState::Normal);
+        assert_eq!(spans(&line.tokens[..2]), vec![
+            (TokenType::LinkText, 0, 9, None),
+            (TokenType::LinkUrl, 9, 12, None),
+        ]);
+        assert!(line.tokens.iter().any(|t| t.token_type == TokenType::ImageAlt
+            && t.nested_style.as_ref().is_some_and(|n| n.token_type == TokenType::Bold)));
+
+        // Nesting stops at the depth limit
+        let line = tokenizer.tokenize_line(3, "[**a _b ~~c `d`~~_**](u)", LineState::Normal);
+        let deepest = line.tokens.iter().map(|t| t.styles().count()).max().unwrap();
+        assert_eq!(deepest, MAX_INLINE_DEPTH);
+        assert!(!line
+            .tokens
+            .iter()
+            .flat_map(Token::styles)
+            .any(|t| t.token_type == TokenType::InlineCode));
+    }
+
+    #[test]
+    fn test_setext_headings() {
+        let mut tokenizer = MarkdownTokenizer::new();
+        let first = |lines: &[&str], tokenizer: &mut MarkdownTokenizer| -> Vec<TokenType> {
+            tokenizer.clear_cache();
+            tokenizer
+                .tokenize_document(lines)
+                .iter()
+                .map(|l| l.tokens.first().map_or(TokenType::PlainText, |t| t.token_type))
+                .collect()
+        };
+
+        assert_eq!(
+            first(&["Title", "=====", "", "Sub *title*", "---"], &mut tokenizer),
+            vec![
+                TokenType::Heading1,
+                TokenType::Heading1,
+                TokenType::PlainText,
+                TokenType::Heading2,
+                TokenType::Heading2,
+            ]
+        );
+
+        // Front matter only on the first line, a rule after a blank line
+        let lines = ["---", "title: x", "---", "Text", "", "---", "- item", "---"];
+        assert_eq!(
+            first(&lines, &mut tokenizer),
+            vec![
+                TokenType::Frontmatter,
+                TokenType::Frontmatter,
+                TokenType::Frontmatter,
+                TokenType::PlainText,
+                TokenType::PlainText,
+                TokenType::HorizontalRule,
+                TokenType::UnorderedListMarker,
+                TokenType::HorizontalRule,
+            ]
+        );
+
+        // Multi-line paragraphs become one heading; indented underlines don't count
+        let tokens = tokenizer.tokenize_document(&["a", "b", "==", "c", "    ---"]);
+        assert_eq!(tokens[0].tokens[0].token_type, TokenType::Heading1);
+        assert_eq!(tokens[1].tokens[0].token_type, TokenType::Heading1);
+        assert_ne!(tokens[3].tokens[0].token_type, TokenType::Heading2);
+    }
+
+    #[test]
+    fn test_indented_code() {
+        let mut tokenizer = MarkdownTokenizer::new();
+        let lines = [
+            "Text",
+            "    not code",
+            "",
+            "    code",
+            "",
+            "\tmore code",
+            "Back to text",
+            "",
+            "- item",
+            "",
+            "    item text",
+            "",
+            "      item code",
+            "",
+            "\t\tcode too",
+            "Done",
+        ];
+        let tokens = tokenizer.tokenize_document(&lines);
+        let code: Vec<usize> = tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.tokens.first().is_some_and(|t| t.token_type == TokenType::CodeBlockContent))
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(code, vec![3, 5, 12, 14]);
+        assert_eq!(tokens[4].end_state, LineState::InIndentedCode { indent: 4 });
+        assert_eq!(tokens[12].end_state, LineState::InIndentedCode { indent: 6 });
+        assert_eq!(tokens[15].end_state, LineState::Normal);
+
+        assert_eq!(MarkdownTokenizer::indent_width("\t  x"), (6, 3));
+        assert_eq!(MarkdownTokenizer::indent_width("  \tx"), (4, 3));
+    }
+
+    #[test]
+    fn test_table_tokenization() {
+        let mut tokenizer = MarkdownTokenizer::new();
+        let lines = ["| Name | **Qty** |", "|:-----|----:|", "| apple | 3 |", "kiwi | 12", "", "after"];
+        let tokens = tokenizer.tokenize_document(&lines);
+        let cells = |i: usize| -> Vec<(usize, usize)> {
+            tokens[i]
+                .tokens
+                .iter()
+                .filter(|t| t.token_type == TokenType::TableCell)
+                .map(|t| (t.start, t.end))
+                .collect()
+        };
+
+        assert_eq!(tokens[0].tokens[0].token_type, TokenType::TableDelimiter);
+        assert_eq!(cells(0), vec![(2, 6), (9, 16)]);
+        assert!(tokens[0].tokens.iter().any(|t| t.token_type == TokenType::TableCell
+            && t.nested_style.as_ref().is_some_and(|n| n.token_type == TokenType::Bold)));
+        assert_eq!(tokens[1].tokens[0].token_type, TokenType::TableDelimiter);
+        assert_eq!(tokens[1].end_state, LineState::InTable);
+        assert_eq!(cells(2), vec![(2, 7), (10, 11)]);
+        assert_eq!(cells(3), vec![(0, 4), (7, 9)]);
+        assert_eq!(tokens[4].end_state, LineState::Normal);
+        assert_eq!(tokens[5].tokens[0].token_type, TokenType::PlainText);
+    }
+
+    #[test]
+    fn test_pipe_in_prose() {
+        let mut tokenizer = MarkdownTokenizer::new();
+
+        // No delimiter row, or one with the wrong number of cells
+        for lines in [&["Use a | b to pipe.", "Next line | here"][..], &["a | b", "---|---|---"]] {
+            let tokens = tokenizer.tokenize_document(lines);
+            assert!(tokens
+                .iter()
+                .flat_map(|l| &l.tokens)
+                .all(|t| !matches!(t.token_type, TokenType::TableCell | TokenType::TableDelimiter)));
+        }
+
+        // Without any pipes, text over `---` is a heading
+        let tokens = tokenizer.tokenize_document(&["a", "---"]);
+        assert_eq!(tokens[0].tokens[0].token_type, TokenType::Heading2);
+
+        assert_eq!(MarkdownTokenizer::parse_table_delimiter("| :--: | --- |"), Some(2));
+        assert_eq!(MarkdownTokenizer::parse_table_delimiter("| a | --- |"), None);
+    }
+
+    #[test]
+    fn test_cache_after_line_insert() {
+        let summary = |tokens: &[LineTokens]| -> Vec<(Vec<TokenType>, LineState)> {
+            tokens
+                .iter()
+                .map(|l| (l.tokens.iter().map(|t| t.token_type).collect(), l.end_state))
+                .collect()
+        };
+
+        let mut tokenizer = MarkdownTokenizer::new();
+        tokenizer.tokenize_document(&["# Title", "text", "```", "code", "```"]);
+
+        // A fence inserted at the top shifts every line and flips their state
+        let lines = ["```", "# Title", "text", "```", "code", "```"];
+        let cached = tokenizer.tokenize_document(&lines);
+        let fresh = MarkdownTokenizer::new().tokenize_document(&lines);
+        assert_eq!(summary(&cached), summary(&fresh));
+        assert_eq!(cached[1].tokens[0].token_type, TokenType::CodeBlockContent);
+        assert_eq!(cached[4].tokens[0].token_type, TokenType::PlainText);
+    }
+
+    #[test]
+    fn test_cache_capacity() {
+        let mut tokenizer = MarkdownTokenizer::with_capacity(8);
+        let lines: Vec<String> = (0..50).map(|i| format!("line {}", i)).collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        tokenizer.tokenize_document(&lines);
+        assert!(tokenizer.line_cache.len() <= 8);
+
+        // Recently used lines survive eviction
+        let key = MarkdownTokenizer::cache_key("line 49", LineState::Normal, false);
+        assert!(tokenizer.line_cache.contains_key(&key));
+
+        tokenizer.invalidate_from_line(45);
+        assert!(!tokenizer.line_cache.contains_key(&key));
+    }
+
+    #[test]
+    fn test_math_tokenization() {
+        let mut tokenizer = MarkdownTokenizer::new();
+        
+        let inline = tokenizer.tokenize_line(0, "Area is $\\pi r^2$ for $5", LineState::Normal);
+        let math: Vec<_> = inline.tokens.iter().filter(|t| t.token_type == TokenType::Math).collect();
+        assert_eq!(math.len(), 1);
+        assert_eq!((math[0].start, math[0].end), (8, 17));
+        
+        let open = tokenizer.tokenize_line(1, "$$", LineState::Normal);
+        assert_eq!(open.end_state, LineState::InMathBlock);
+        let close = tokenizer.tokenize_line(2, "x^2 $$", LineState::InMathBlock);
+        assert_eq!(close.tokens[0].token_type, TokenType::Math);
+        assert_eq!(close.end_state, LineState::Normal);
+    }
+    
+    #[test]
+    fn test_wiki_link_tokenization() {
+        let mut tokenizer = MarkdownTokenizer::new();
+        
+        let line = tokenizer.tokenize_line(0, "See [[Plan|the plan]] and [[]] or [[a](b)", LineState::Normal);
+        let links: Vec<_> = line.tokens.iter().filter(|t| t.token_type == TokenType::WikiLink).collect();
+        assert_eq!(links.len(), 1);
+        assert_eq!((links[0].start, links[0].end), (4, 21));
+        
+        // Link text in brackets is still a markdown link
+        let line = tokenizer.tokenize_line(1, "[[x]](url)", LineState::Normal);
+        assert!(line.tokens.iter().all(|t| t.token_type != TokenType::WikiLink));
+    }
 }
@@ -0,0 +1,470 @@
+//! Wiki-style `[[Note Name]]` links
+//!
+//! With `markdown.wiki_links` on, `[[Note]]` links to the markdown file
+//! named `Note` anywhere in the open folder:
+//! - `[[Note|text]]` shows `text` instead of the note's name
+//! - `[[Note#Heading]]` links to a heading in the note
+//! - `[[folder/Note]]` picks one of several notes sharing a name by its path
+//!
+//! A name shared by several notes resolves to the one nearest the linking
+//! document. Links to notes that don't exist yet are drawn as unresolved,
+//! and following one starts the note.
+
+use super::export::MarkdownExporter;
+use pulldown_cmark::{CowStr, Event, Tag};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Extensions of the files wiki links point at, the first for new notes
+const NOTE_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+/// CSS class of wiki links in HTML
+const LINK_CLASS: &str = "wiki-link";
+
+/// CSS class added to links whose note doesn't exist
+const UNRESOLVED_CLASS: &str = "wiki-link-unresolved";
+
+/// A `[[target#heading|label]]` link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikiLink {
+    /// Name or path of the note, without its extension
+    pub target: String,
+    /// Heading in the note
+    pub heading: Option<String>,
+    /// Text shown instead of the target
+    pub label: Option<String>,
+}
+
+impl WikiLink {
+    /// Parse the text between `[[` and `]]`
+    pub fn parse(inner: &str) -> Option<Self> {
+        if inner.contains(['[', ']', '\n']) {
+            return None;
+        }
+        let non_empty = |text: &str| Some(text.trim()).filter(|t| !t.is_empty()).map(String::from);
+        let (link, label) = match inner.split_once('|') {
+            Some((link, label)) => (link, non_empty(label)),
+            None => (inner, None),
+        };
+        let (target, heading) = match link.split_once('#') {
+            Some((target, heading)) => (target, non_empty(heading)),
+            None => (link, None),
+        };
+        Some(Self {
+            target: non_empty(target)?,
+            heading,
+            label,
+        })
+    }
+
+    /// Text the link shows: its label, or the target as written
+    pub fn text(&self) -> String {
+        match (&self.label, &self.heading) {
+            (Some(label), _) => label.clone(),
+            (None, Some(heading)) => format!("{}#{}", self.target, heading),
+            (None, None) => self.target.clone(),
+        }
+    }
+
+    /// Anchor of the linked heading
+    pub fn anchor(&self) -> Option<String> {
+        self.heading.as_deref().map(MarkdownExporter::generate_anchor)
+    }
+}
+
+/// Find the wiki links in a piece of text, with their byte ranges
+pub fn find(text: &str) -> Vec<(Range<usize>, WikiLink)> {
+    let mut links = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = text[pos..].find("[[").map(|i| pos + i) {
+        let inner_start = start + 2;
+        let Some(inner_end) = text[inner_start..].find("]]").map(|i| inner_start + i) else {
+            break;
+        };
+        match WikiLink::parse(&text[inner_start..inner_end]) {
+            Some(link) => {
+                links.push((start..inner_end + 2, link));
+                pos = inner_end + 2;
+            }
+            // `[[[Note]]` links from its last pair of brackets
+            None => pos = start + 1,
+        }
+    }
+    links
+}
+
+/// Markdown files in the open folder, for resolving wiki links
+///
+/// Cheap to clone, so renderers can each hold one.
+#[derive(Debug, Clone, Default)]
+pub struct NoteIndex {
+    root: Option<PathBuf>,
+    notes: Arc<Vec<PathBuf>>,
+}
+
+impl NoteIndex {
+    /// Index the notes among the files of a folder scan
+    pub fn new(root: Option<PathBuf>, files: Vec<PathBuf>) -> Self {
+        let notes = files.into_iter().filter(|path| is_note(path)).collect();
+        Self {
+            root,
+            notes: Arc::new(notes),
+        }
+    }
+
+    /// Folder the notes were scanned from
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Add a note saved since the scan
+    pub fn insert(&mut self, path: PathBuf) {
+        let inside = self.root.as_deref().is_some_and(|root| path.starts_with(root));
+        if inside && is_note(&path) && !self.notes.contains(&path) {
+            Arc::make_mut(&mut self.notes).push(path);
+        }
+    }
+
+    /// Find the note a link target names, from a document in `from_dir`
+    ///
+    /// Names match file names without their extension, ignoring case. A
+    /// target with folders must match the end of the note's path. Among
+    /// several matches the note in `from_dir` wins, then the one fewest
+    /// folders deep, then the first by path. Without an open folder, notes
+    /// next to the document are found on disk.
+    pub fn resolve(&self, target: &str, from_dir: Option<&Path>) -> Option<PathBuf> {
+        let parts = target_parts(target);
+        let rank = |note: &Path| (note.parent() != from_dir, self.depth(note));
+        let found = self
+            .notes
+            .iter()
+            .filter(|note| path_ends_with(note, &parts))
+            .min_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.cmp(b)));
+        if let Some(note) = found {
+            return Some(note.clone());
+        }
+        if self.root.is_some() {
+            return None;
+        }
+        let dir = from_dir?;
+        NOTE_EXTENSIONS
+            .iter()
+            .map(|ext| dir.join(format!("{}.{}", parts.join("/"), ext)))
+            .find(|path| path.is_file())
+    }
+
+    /// Where a new note for a link target is created
+    ///
+    /// Plain names go next to the linking document, and targets with
+    /// folders under the open folder. When the folder doesn't exist, or
+    /// the path would leave the base folder, the note goes in the base
+    /// folder instead.
+    pub fn new_note_path(&self, target: &str, from_dir: Option<&Path>) -> PathBuf {
+        let parts = target_parts(target);
+        let base = if parts.len() > 1 {
+            self.root.as_deref().or(from_dir)
+        } else {
+            from_dir.or(self.root.as_deref())
+        }
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+        let file = |parts: &[&str]| format!("{}.{}", parts.join("/"), NOTE_EXTENSIONS[0]);
+
+        let path = base.join(file(&parts));
+        let inside = path.starts_with(&base);
+        if parts.len() > 1 && !(inside && path.parent().is_some_and(Path::is_dir)) {
+            return base.join(file(&parts[parts.len() - 1..]));
+        }
+        path
+    }
+
+    /// Destination of a link from a document in `from_dir`, and whether
+    /// its note exists
+    ///
+    /// Missing notes link to where they would be created.
+    pub fn href(&self, link: &WikiLink, from_dir: Option<&Path>) -> (String, bool) {
+        let (note, exists) = match self.resolve(&link.target, from_dir) {
+            Some(note) => (note, true),
+            None => (self.new_note_path(&link.target, from_dir), false),
+        };
+        let path = match from_dir {
+            Some(dir) => relative_path(&note, dir),
+            None => note,
+        };
+        let mut href = path.to_string_lossy().replace('\\', "/").replace(' ', "%20");
+        if let Some(anchor) = link.anchor() {
+            href.push('#');
+            href.push_str(&anchor);
+        }
+        (href, exists)
+    }
+
+    /// Folders between the root and a note
+    fn depth(&self, note: &Path) -> usize {
+        let relative = self
+            .root
+            .as_deref()
+            .and_then(|root| note.strip_prefix(root).ok())
+            .unwrap_or(note);
+        relative.components().count()
+    }
+}
+
+fn is_note(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| NOTE_EXTENSIONS.iter().any(|note| ext.eq_ignore_ascii_case(note)))
+}
+
+/// Path components of a link target, without a note extension
+///
+/// `.` and `..` are dropped, so a target never leads out of the folder it
+/// is looked up in.
+fn target_parts(target: &str) -> Vec<&str> {
+    let mut parts: Vec<&str> = target
+        .split(['/', '\\'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect();
+    if let Some(last) = parts.last_mut() {
+        for ext in NOTE_EXTENSIONS {
+            let len = last.len();
+            if len > ext.len() + 1
+                && last[..len - ext.len()].ends_with('.')
+                && last[len - ext.len()..].eq_ignore_ascii_case(ext)
+            {
+                *last = &last[..len - ext.len() - 1];
+            }
+        }
+    }
+    parts
+}
+
+/// Whether a note's path without its extension ends with the target's
+/// components, ignoring case
+fn path_ends_with(note: &Path, parts: &[&str]) -> bool {
+    let stem = note.with_extension("");
+    let components: Vec<String> = stem
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().to_lowercase()),
+            _ => None,
+        })
+        .collect();
+    !parts.is_empty()
+        && components.len() >= parts.len()
+        && components[components.len() - parts.len()..]
+            .iter()
+            .zip(parts)
+            .all(|(component, part)| *component == part.to_lowercase())
+}
+
+/// Path of `path` relative to the folder `dir`
+///
+/// Paths on another drive or root stay as they are.
+fn relative_path(path: &Path, dir: &Path) -> PathBuf {
+    let path_parts: Vec<Component> = path.components().collect();
+    let dir_parts: Vec<Component> = dir.components().collect();
+    let common = path_parts
+        .iter()
+        .zip(&dir_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 && path.has_root() {
+        return path.to_path_buf();
+    }
+    let mut relative: PathBuf = dir_parts[common..].iter().map(|_| Component::ParentDir).collect();
+    relative.extend(&path_parts[common..]);
+    relative
+}
+
+/// Join runs of text events, which pulldown-cmark splits at brackets
+///
+/// Text in code blocks, links and images is left as it is.
+pub fn merge_text<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let mut output: Vec<Event<'a>> = Vec::new();
+    let mut literal = 0usize;
+    for event in events {
+        match event {
+            Event::Text(text) if literal == 0 => {
+                if let Some(Event::Text(last)) = output.last_mut() {
+                    *last = CowStr::from([&**last, &*text].concat());
+                } else {
+                    output.push(Event::Text(text));
+                }
+            }
+            event => {
+                match &event {
+                    Event::Start(Tag::CodeBlock(_) | Tag::Link(..) | Tag::Image(..)) => literal += 1,
+                    Event::End(Tag::CodeBlock(_) | Tag::Link(..) | Tag::Image(..)) => {
+                        literal = literal.saturating_sub(1)
+                    }
+                    _ => {}
+                }
+                output.push(event);
+            }
+        }
+    }
+    output
+}
+
+/// Render wiki links as HTML links, for a document in `from_dir`
+///
+/// Links to missing notes get the `wiki-link-unresolved` class as well as
+/// `wiki-link`.
+pub fn html_events<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    notes: &NoteIndex,
+    from_dir: Option<&Path>,
+) -> Vec<Event<'a>> {
+    let mut output = Vec::new();
+    let mut literal = 0usize;
+    for event in merge_text(events) {
+        match event {
+            Event::Text(text) if literal == 0 => {
+                let mut last = 0;
+                for (range, link) in find(&text) {
+                    if range.start > last {
+                        output.push(Event::Text(CowStr::from(text[last..range.start].to_string())));
+                    }
+                    let (href, exists) = notes.href(&link, from_dir);
+                    let class = if exists {
+                        LINK_CLASS.to_string()
+                    } else {
+                        format!("{} {}", LINK_CLASS, UNRESOLVED_CLASS)
+                    };
+                    output.push(Event::Html(CowStr::from(format!(
+                        "<a class=\"{}\" href=\"{}\">",
+                        class,
+                        href.replace('&', "&amp;").replace('"', "&quot;")
+                    ))));
+                    output.push(Event::Text(CowStr::from(link.text())));
+                    output.push(Event::Html(CowStr::from("</a>")));
+                    last = range.end;
+                }
+                if last == 0 {
+                    output.push(Event::Text(text));
+                } else if last < text.len() {
+                    output.push(Event::Text(CowStr::from(text[last..].to_string())));
+                }
+            }
+            event => {
+                match &event {
+                    Event::Start(Tag::CodeBlock(_) | Tag::Link(..) | Tag::Image(..)) => literal += 1,
+                    Event::End(Tag::CodeBlock(_) | Tag::Link(..) | Tag::Image(..)) => {
+                        literal = literal.saturating_sub(1)
+                    }
+                    _ => {}
+                }
+                output.push(event);
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html::push_html, Parser};
+
+    fn index() -> NoteIndex {
+        NoteIndex::new(
+            Some(PathBuf::from("/notes")),
+            vec![
+                PathBuf::from("/notes/Ideas.md"),
+                PathBuf::from("/notes/work/Plan.md"),
+                PathBuf::from("/notes/home/Plan.md"),
+                PathBuf::from("/notes/home/deep/Plan.markdown"),
+                PathBuf::from("/notes/image.png"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            WikiLink::parse("Plan#Goals|the plan"),
+            Some(WikiLink {
+                target: "Plan".to_string(),
+                heading: Some("Goals".to_string()),
+                label: Some("the plan".to_string()),
+            })
+        );
+        assert_eq!(WikiLink::parse("Plan|").map(|l| l.text()), Some("Plan".to_string()));
+        assert_eq!(WikiLink::parse(" |text"), None);
+        assert_eq!(WikiLink::parse("a]b"), None);
+
+        let text = "See [[Ideas]] and [[[Plan|it]]]";
+        let links = find(text);
+        assert_eq!(links.len(), 2);
+        assert_eq!(&text[links[0].0.clone()], "[[Ideas]]");
+        assert_eq!(&text[links[1].0.clone()], "[[Plan|it]]");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let notes = index();
+        assert_eq!(notes.resolve("ideas", None), Some(PathBuf::from("/notes/Ideas.md")));
+        assert_eq!(notes.resolve("Ideas.md", None), Some(PathBuf::from("/notes/Ideas.md")));
+        assert_eq!(notes.resolve("image", None), None);
+        assert_eq!(notes.resolve("Missing", Some(Path::new("/notes"))), None);
+
+        // Shared names prefer the linking document's folder, then the
+        // shallowest note, then the path
+        let work = Path::new("/notes/work");
+        assert_eq!(notes.resolve("Plan", Some(work)), Some(PathBuf::from("/notes/work/Plan.md")));
+        let deep = Path::new("/notes/home/deep");
+        assert_eq!(
+            notes.resolve("Plan", Some(deep)),
+            Some(PathBuf::from("/notes/home/deep/Plan.markdown"))
+        );
+        assert_eq!(notes.resolve("Plan", None), Some(PathBuf::from("/notes/home/Plan.md")));
+        assert_eq!(
+            notes.resolve("work/plan", Some(deep)),
+            Some(PathBuf::from("/notes/work/Plan.md"))
+        );
+    }
+
+    #[test]
+    fn test_new_note_path() {
+        let dir = std::env::temp_dir().join(format!("cosmic-notebook-wiki-{}", std::process::id()));
+        let docs = dir.join("work");
+        std::fs::create_dir_all(&docs).unwrap();
+        let notes = NoteIndex::new(Some(dir.clone()), Vec::new());
+
+        assert_eq!(notes.new_note_path("Todo", Some(&docs)), docs.join("Todo.md"));
+        assert_eq!(notes.new_note_path("work/Todo", None), docs.join("Todo.md"));
+        assert_eq!(notes.new_note_path("later/Todo", None), dir.join("Todo.md"));
+
+        // Parent folders can't lead out of the open folder
+        assert_eq!(notes.new_note_path("../work/Todo", Some(&docs)), docs.join("Todo.md"));
+        let escaped = notes.new_note_path("../../somewhere/Note", Some(&docs));
+        assert_eq!(escaped, dir.join("Note.md"));
+        assert!(escaped.starts_with(&dir));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_html_events() {
+        let notes = index();
+        let render = |markdown: &str| {
+            let mut html = String::new();
+            let events = html_events(Parser::new(markdown), &notes, Some(Path::new("/notes/work")));
+            push_html(&mut html, events.into_iter());
+            html
+        };
+
+        let html = render("[[Ideas#Next Steps|ideas]], [[New note]] and `[[Ideas]]`");
+        assert!(html.contains("<a class=\"wiki-link\" href=\"../Ideas.md#next-steps\">ideas</a>"));
+        assert!(html.contains(
+            "<a class=\"wiki-link wiki-link-unresolved\" href=\"New%20note.md\">New note</a>"
+        ));
+        assert!(html.contains("<code>[[Ideas]]</code>"));
+
+        let html = render("```\n[[Ideas]]\n```\n");
+        assert!(!html.contains("<a"));
+    }
+}
@@ -10,9 +10,12 @@ use cosmic::widget::menu::key_bind::Modifier;
 use cosmic::iced::keyboard::Key;
 use std::collections::HashMap;
 
+use crate::editor::EmphasisKind;
+use crate::markdown::CalloutKind;
+use crate::fl;
 use crate::message::{
-    ClipboardMessage, DialogMessage, EditorMessage, FileMessage, Message, SearchMessage,
-    SystemMessage, ViewMessage,
+    ClipboardMessage, CopyPathKind, DialogMessage, EditorMessage, FileMessage, Message, SearchMessage,
+    SidebarMessage, SystemMessage, TabMessage, ViewMessage,
 };
 
 /// Menu actions that can be triggered from the menu bar or keyboard shortcuts
@@ -26,6 +29,9 @@ pub enum Action {
     SaveAll,
     CloseFile,
     CloseAll,
+    ReopenClosedTab,
+    ToggleWatch,
+    ToggleSingleInstance,
     Quit,
 
     // Edit actions
@@ -33,18 +39,81 @@ pub enum Action {
     Redo,
     Cut,
     Copy,
+    CopyAsHtml,
+    CopySelectionHtml,
+    CopyHeadingLink,
+    CopyHeadingLinkWithPath,
+    CopyFilePath,
+    CopyRelativePath,
+    CopyFileName,
     Paste,
+    PastePlain,
+    PasteAsMarkdown,
+    PasteImageFromUrl,
+    PasteFromHistory,
     SelectAll,
     Find,
     FindReplace,
+    ToggleReadOnly,
+    ToggleBookmark,
+    NextBookmark,
+    PreviousBookmark,
+    ClearBookmarks,
+    OverrideReadOnly,
+    SortLines,
+    SortLinesDescending,
+    SortLinesCaseSensitive,
+    DedupeLines,
+    JoinLines,
+    TransposeChars,
+    TransposeWords,
+
+    // Format actions
+    Bold,
+    Italic,
+    InlineCode,
+    Strikethrough,
+    HeadingIncrease,
+    HeadingDecrease,
+    ToggleBlockquote,
+    ReflowParagraph,
+    CalloutNote,
+    CalloutTip,
+    CalloutWarning,
+    OpenLink,
+
+    // Insert actions
+    InsertDateTime,
+    InsertDateIso,
+    InsertDateLocale,
 
     // View actions
     ToggleSidebar,
+    ExpandAllFolders,
+    CollapseAllFolders,
+    SwapSidebarSide,
     ToggleViewMode,
+    ToggleRenderWhitespace,
+    ToggleLivePreview,
+    ToggleSanitizeHtml,
+    ToggleWikiLinks,
+    StartLivePreview,
+    StopLivePreview,
     ZoomIn,
     ZoomOut,
     ZoomReset,
     ToggleFullscreen,
+    ShowChanges,
+    ShowProblems,
+    SplitEditorVertical,
+    SplitEditorHorizontal,
+    MoveTabToOtherPane,
+    UnsplitEditor,
+
+    // Tab actions
+    /// Tab by index, for Ctrl+1 to Ctrl+8
+    SelectTab(usize),
+    LastTab,
 
     // Help actions
     CommandPalette,
@@ -71,6 +140,9 @@ impl Action {
             Action::SaveAll => Message::File(FileMessage::SaveAll),
             Action::CloseFile => Message::File(FileMessage::Close),
             Action::CloseAll => Message::File(FileMessage::CloseAll),
+            Action::ReopenClosedTab => Message::File(FileMessage::ReopenClosed),
+            Action::ToggleWatch => Message::File(FileMessage::ToggleWatch),
+            Action::ToggleSingleInstance => Message::File(FileMessage::ToggleSingleInstance),
             Action::Quit => Message::System(SystemMessage::CloseRequested),
 
             // Edit
@@ -78,24 +150,181 @@ impl Action {
             Action::Redo => Message::Editor(EditorMessage::Redo),
             Action::Cut => Message::Clipboard(ClipboardMessage::Cut),
             Action::Copy => Message::Clipboard(ClipboardMessage::Copy),
+            Action::CopyAsHtml => Message::Clipboard(ClipboardMessage::CopyAsHtml),
+            Action::CopySelectionHtml => Message::Clipboard(ClipboardMessage::CopySelectionHtml),
+            Action::CopyHeadingLink => {
+                Message::Clipboard(ClipboardMessage::CopyHeadingLink { with_path: false })
+            }
+            Action::CopyHeadingLinkWithPath => {
+                Message::Clipboard(ClipboardMessage::CopyHeadingLink { with_path: true })
+            }
+            Action::CopyFilePath => Message::Clipboard(ClipboardMessage::CopyPath {
+                document_id: None,
+                kind: CopyPathKind::Absolute,
+            }),
+            Action::CopyRelativePath => Message::Clipboard(ClipboardMessage::CopyPath {
+                document_id: None,
+                kind: CopyPathKind::Relative,
+            }),
+            Action::CopyFileName => Message::Clipboard(ClipboardMessage::CopyPath {
+                document_id: None,
+                kind: CopyPathKind::FileName,
+            }),
             Action::Paste => Message::Clipboard(ClipboardMessage::Paste),
+            Action::PastePlain => Message::Clipboard(ClipboardMessage::PastePlain),
+            Action::PasteAsMarkdown => Message::Clipboard(ClipboardMessage::PasteAsMarkdown),
+            Action::PasteImageFromUrl => Message::Clipboard(ClipboardMessage::PasteImageFromUrl),
+            Action::PasteFromHistory => Message::Clipboard(ClipboardMessage::PasteFromHistory),
             Action::SelectAll => Message::Editor(EditorMessage::SelectAll),
             Action::Find => Message::Search(SearchMessage::OpenFind),
             Action::FindReplace => Message::Search(SearchMessage::OpenFindReplace),
+            Action::ToggleReadOnly => Message::Editor(EditorMessage::ToggleReadOnly),
+            Action::ToggleBookmark => Message::Editor(EditorMessage::ToggleBookmark),
+            Action::NextBookmark => Message::Editor(EditorMessage::NextBookmark),
+            Action::PreviousBookmark => Message::Editor(EditorMessage::PreviousBookmark),
+            Action::ClearBookmarks => Message::Editor(EditorMessage::ClearBookmarks),
+            Action::OverrideReadOnly => Message::Editor(EditorMessage::OverrideReadOnly),
+            Action::SortLines => Message::Editor(EditorMessage::SortLines {
+                descending: false,
+                case_sensitive: false,
+            }),
+            Action::SortLinesDescending => Message::Editor(EditorMessage::SortLines {
+                descending: true,
+                case_sensitive: false,
+            }),
+            Action::SortLinesCaseSensitive => Message::Editor(EditorMessage::SortLines {
+                descending: false,
+                case_sensitive: true,
+            }),
+            Action::DedupeLines => Message::Editor(EditorMessage::DedupeLines),
+            Action::JoinLines => Message::Editor(EditorMessage::JoinLines),
+            Action::TransposeChars => Message::Editor(EditorMessage::TransposeChars),
+            Action::TransposeWords => Message::Editor(EditorMessage::TransposeWords),
+
+            // Format
+            Action::Bold => Message::Editor(EditorMessage::ToggleEmphasis(EmphasisKind::Bold)),
+            Action::Italic => Message::Editor(EditorMessage::ToggleEmphasis(EmphasisKind::Italic)),
+            Action::InlineCode => Message::Editor(EditorMessage::ToggleEmphasis(EmphasisKind::Code)),
+            Action::Strikethrough => {
+                Message::Editor(EditorMessage::ToggleEmphasis(EmphasisKind::Strikethrough))
+            }
+            Action::HeadingIncrease => Message::Editor(EditorMessage::AdjustHeading(1)),
+            Action::HeadingDecrease => Message::Editor(EditorMessage::AdjustHeading(-1)),
+            Action::ToggleBlockquote => Message::Editor(EditorMessage::ToggleBlockquote),
+            Action::ReflowParagraph => Message::Editor(EditorMessage::ReflowParagraph),
+            Action::CalloutNote => Message::Editor(EditorMessage::WrapInCallout(CalloutKind::Note)),
+            Action::CalloutTip => Message::Editor(EditorMessage::WrapInCallout(CalloutKind::Tip)),
+            Action::CalloutWarning => Message::Editor(EditorMessage::WrapInCallout(CalloutKind::Warning)),
+            Action::OpenLink => Message::Editor(EditorMessage::OpenLinkAtCursor),
+
+            // Insert
+            Action::InsertDateTime => Message::Editor(EditorMessage::InsertDateTime(None)),
+            Action::InsertDateIso => Message::Editor(EditorMessage::InsertDateTime(Some(
+                crate::utils::datetime::ISO_8601_FORMAT,
+            ))),
+            Action::InsertDateLocale => Message::Editor(EditorMessage::InsertDateTime(Some(
+                crate::utils::datetime::LOCALE_DATE_FORMAT,
+            ))),
 
             // View
             Action::ToggleSidebar => Message::View(ViewMessage::ToggleSidebar),
+            Action::ExpandAllFolders => Message::Sidebar(SidebarMessage::ExpandAll),
+            Action::CollapseAllFolders => Message::Sidebar(SidebarMessage::CollapseAll),
+            Action::SwapSidebarSide => Message::View(ViewMessage::SwapSidebarSide),
+            Action::ToggleRenderWhitespace => Message::View(ViewMessage::ToggleRenderWhitespace),
+            Action::ToggleLivePreview => Message::View(ViewMessage::ToggleLivePreview),
+            Action::ToggleSanitizeHtml => Message::View(ViewMessage::ToggleSanitizeHtml),
+            Action::ToggleWikiLinks => Message::View(ViewMessage::ToggleWikiLinks),
+            Action::StartLivePreview => Message::View(ViewMessage::StartLivePreview),
+            Action::StopLivePreview => Message::View(ViewMessage::StopLivePreview),
             Action::ToggleViewMode => Message::View(ViewMessage::ToggleViewMode),
             Action::ZoomIn => Message::View(ViewMessage::ZoomIn),
             Action::ZoomOut => Message::View(ViewMessage::ZoomOut),
             Action::ZoomReset => Message::View(ViewMessage::ZoomReset),
             Action::ToggleFullscreen => Message::View(ViewMessage::ToggleFullscreen),
+            Action::ShowChanges => Message::View(ViewMessage::ShowChanges),
+            Action::ShowProblems => Message::View(ViewMessage::ShowProblems),
+            Action::SplitEditorVertical => Message::View(ViewMessage::SplitEditorVertical),
+            Action::SplitEditorHorizontal => Message::View(ViewMessage::SplitEditorHorizontal),
+            Action::MoveTabToOtherPane => Message::View(ViewMessage::MoveTabToOtherPane),
+            Action::UnsplitEditor => Message::View(ViewMessage::UnsplitEditor),
+
+            // Tabs
+            Action::SelectTab(index) => Message::Tab(TabMessage::SelectIndex(index)),
+            Action::LastTab => Message::Tab(TabMessage::SelectLast),
 
             // Help
             Action::CommandPalette => Message::Dialog(DialogMessage::OpenCommandPalette),
             Action::About => Message::Dialog(DialogMessage::ShowAbout),
         }
     }
+
+    /// Whether the action works on the active document, so it can't run
+    /// while no document is open
+    pub fn needs_document(self) -> bool {
+        !matches!(
+            self,
+            Action::NewFile
+                | Action::OpenFile
+                | Action::SaveAll
+                | Action::CloseAll
+                | Action::ReopenClosedTab
+                | Action::ToggleWatch
+                | Action::ToggleSingleInstance
+                | Action::Quit
+                | Action::ToggleSidebar
+                | Action::ExpandAllFolders
+                | Action::CollapseAllFolders
+                | Action::SwapSidebarSide
+                | Action::ToggleRenderWhitespace
+                | Action::ToggleLivePreview
+                | Action::ToggleSanitizeHtml
+                | Action::ToggleWikiLinks
+                | Action::StopLivePreview
+                | Action::ZoomIn
+                | Action::ZoomOut
+                | Action::ZoomReset
+                | Action::ToggleFullscreen
+                | Action::UnsplitEditor
+                | Action::SelectTab(_)
+                | Action::LastTab
+                | Action::CommandPalette
+                | Action::About
+        )
+    }
+}
+
+/// Every menu action with its label and keyboard shortcut, in menu order
+///
+/// An action bound to several keys gets the one that sorts first.
+pub fn all_actions() -> Vec<(String, Action, Option<KeyBind>)> {
+    fn collect(items: Vec<Item<Action, String>>, out: &mut Vec<(String, Action)>) {
+        for item in items {
+            match item {
+                Item::Button(label, _, action) => out.push((label, action)),
+                Item::Folder(_, children) => collect(children, out),
+                _ => {}
+            }
+        }
+    }
+
+    let binds = key_binds();
+    let mut actions = Vec::new();
+    for (_, items) in menu_items(&binds) {
+        collect(items, &mut actions);
+    }
+    actions
+        .into_iter()
+        .map(|(label, action)| {
+            let bind = binds
+                .iter()
+                .filter(|(_, bound)| **bound == action)
+                .map(|(bind, _)| bind)
+                .min_by_key(|bind| bind.to_string())
+                .cloned();
+            (label, action, bind)
+        })
+        .collect()
 }
 
 /// Create default keyboard shortcuts
@@ -138,6 +367,13 @@ pub fn key_binds() -> HashMap<KeyBind, Action> {
         },
         Action::CloseFile,
     );
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl, Modifier::Shift],
+            key: Key::Character("t".into()),
+        },
+        Action::ReopenClosedTab,
+    );
     binds.insert(
         KeyBind {
             modifiers: vec![Modifier::Ctrl],
@@ -189,6 +425,20 @@ pub fn key_binds() -> HashMap<KeyBind, Action> {
         },
         Action::Paste,
     );
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl, Modifier::Shift],
+            key: Key::Character("v".into()),
+        },
+        Action::PastePlain,
+    );
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl, Modifier::Alt],
+            key: Key::Character("v".into()),
+        },
+        Action::PasteFromHistory,
+    );
     binds.insert(
         KeyBind {
             modifiers: vec![Modifier::Ctrl],
@@ -211,10 +461,92 @@ pub fn key_binds() -> HashMap<KeyBind, Action> {
         Action::FindReplace,
     );
 
-    // View shortcuts
     binds.insert(
         KeyBind {
             modifiers: vec![Modifier::Ctrl],
+            key: Key::Character("j".into()),
+        },
+        Action::JoinLines,
+    );
+
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl],
+            key: Key::Character("t".into()),
+        },
+        Action::TransposeChars,
+    );
+
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Alt],
+            key: Key::Character("t".into()),
+        },
+        Action::TransposeWords,
+    );
+
+    // Format shortcuts
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl],
+            key: Key::Character("b".into()),
+        },
+        Action::Bold,
+    );
+
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl],
+            key: Key::Character("i".into()),
+        },
+        Action::Italic,
+    );
+
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl],
+            key: Key::Character("`".into()),
+        },
+        Action::InlineCode,
+    );
+
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl, Modifier::Shift],
+            key: Key::Character("]".into()),
+        },
+        Action::HeadingIncrease,
+    );
+
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl, Modifier::Shift],
+            key: Key::Character("[".into()),
+        },
+        Action::HeadingDecrease,
+    );
+
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl, Modifier::Shift],
+            key: Key::Character("o".into()),
+        },
+        Action::OpenLink,
+    );
+
+    // Insert shortcuts
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl, Modifier::Shift],
+            key: Key::Character("d".into()),
+        },
+        Action::InsertDateTime,
+    );
+
+    // View shortcuts
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl, Modifier::Shift],
             key: Key::Character("b".into()),
         },
         Action::ToggleSidebar,
@@ -247,6 +579,31 @@ pub fn key_binds() -> HashMap<KeyBind, Action> {
         },
         Action::ZoomReset,
     );
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl],
+            key: Key::Character("\\".into()),
+        },
+        Action::SplitEditorVertical,
+    );
+
+    // Tab shortcuts: Ctrl+1 to Ctrl+8 by position, Ctrl+9 the last tab
+    for index in 0..8 {
+        binds.insert(
+            KeyBind {
+                modifiers: vec![Modifier::Ctrl],
+                key: Key::Character((index + 1).to_string().into()),
+            },
+            Action::SelectTab(index),
+        );
+    }
+    binds.insert(
+        KeyBind {
+            modifiers: vec![Modifier::Ctrl],
+            key: Key::Character("9".into()),
+        },
+        Action::LastTab,
+    );
 
     // Help shortcuts
     binds.insert(
@@ -261,62 +618,137 @@ pub fn key_binds() -> HashMap<KeyBind, Action> {
 }
 
 /// Type alias for menu items with our action type
-pub type MenuItems = Vec<(&'static str, Vec<Item<Action, &'static str>>)>;
+pub type MenuItems = Vec<(String, Vec<Item<Action, String>>)>;
 
 /// Create menu bar items
 pub fn menu_items(_key_binds: &HashMap<KeyBind, Action>) -> MenuItems {
     vec![
         (
-            "File",
+            fl!("menu-file"),
             vec![
-                Item::Button("New", None, Action::NewFile),
-                Item::Button("Open", None, Action::OpenFile),
+                Item::Button(fl!("file-new"), None, Action::NewFile),
+                Item::Button(fl!("file-open"), None, Action::OpenFile),
+                Item::Divider,
+                Item::Button(fl!("file-save"), None, Action::Save),
+                Item::Button(fl!("file-save-as"), None, Action::SaveAs),
+                Item::Button(fl!("file-save-all"), None, Action::SaveAll),
+                Item::Divider,
+                Item::Button(fl!("file-close"), None, Action::CloseFile),
+                Item::Button(fl!("file-close-all"), None, Action::CloseAll),
+                Item::Button(fl!("file-reopen-closed"), None, Action::ReopenClosedTab),
+                Item::Divider,
+                Item::Button(fl!("file-watch"), None, Action::ToggleWatch),
+                Item::Button(fl!("file-single-instance"), None, Action::ToggleSingleInstance),
+                Item::Divider,
+                Item::Button(fl!("file-quit"), None, Action::Quit),
+            ],
+        ),
+        (
+            fl!("menu-edit"),
+            vec![
+                Item::Button(fl!("edit-undo"), None, Action::Undo),
+                Item::Button(fl!("edit-redo"), None, Action::Redo),
+                Item::Divider,
+                Item::Button(fl!("edit-cut"), None, Action::Cut),
+                Item::Button(fl!("edit-copy"), None, Action::Copy),
+                Item::Button(fl!("edit-copy-html"), None, Action::CopyAsHtml),
+                Item::Button(fl!("edit-copy-html-source"), None, Action::CopySelectionHtml),
+                Item::Button(fl!("edit-copy-heading-link"), None, Action::CopyHeadingLink),
+                Item::Button(fl!("edit-copy-heading-link-path"), None, Action::CopyHeadingLinkWithPath),
+                Item::Button(fl!("edit-copy-file-path"), None, Action::CopyFilePath),
+                Item::Button(fl!("edit-copy-relative-path"), None, Action::CopyRelativePath),
+                Item::Button(fl!("edit-copy-file-name"), None, Action::CopyFileName),
+                Item::Button(fl!("edit-paste"), None, Action::Paste),
+                Item::Button(fl!("edit-paste-plain"), None, Action::PastePlain),
+                Item::Button(fl!("edit-paste-markdown"), None, Action::PasteAsMarkdown),
+                Item::Button(fl!("edit-paste-image-url"), None, Action::PasteImageFromUrl),
+                Item::Button(fl!("edit-paste-history"), None, Action::PasteFromHistory),
+                Item::Divider,
+                Item::Button(fl!("edit-select-all"), None, Action::SelectAll),
                 Item::Divider,
-                Item::Button("Save", None, Action::Save),
-                Item::Button("Save As...", None, Action::SaveAs),
-                Item::Button("Save All", None, Action::SaveAll),
+                Item::Button(fl!("edit-sort-lines"), None, Action::SortLines),
+                Item::Button(fl!("edit-sort-lines-descending"), None, Action::SortLinesDescending),
+                Item::Button(fl!("edit-sort-lines-case-sensitive"), None, Action::SortLinesCaseSensitive),
+                Item::Button(fl!("edit-dedupe-lines"), None, Action::DedupeLines),
+                Item::Button(fl!("edit-join-lines"), None, Action::JoinLines),
+                Item::Button(fl!("edit-transpose-chars"), None, Action::TransposeChars),
+                Item::Button(fl!("edit-transpose-words"), None, Action::TransposeWords),
                 Item::Divider,
-                Item::Button("Close", None, Action::CloseFile),
-                Item::Button("Close All", None, Action::CloseAll),
+                Item::Button(fl!("edit-find"), None, Action::Find),
+                Item::Button(fl!("edit-find-replace"), None, Action::FindReplace),
                 Item::Divider,
-                Item::Button("Quit", None, Action::Quit),
+                Item::Button(fl!("edit-toggle-bookmark"), None, Action::ToggleBookmark),
+                Item::Button(fl!("edit-next-bookmark"), None, Action::NextBookmark),
+                Item::Button(fl!("edit-previous-bookmark"), None, Action::PreviousBookmark),
+                Item::Button(fl!("edit-clear-bookmarks"), None, Action::ClearBookmarks),
+                Item::Divider,
+                Item::Button(fl!("edit-toggle-read-only"), None, Action::ToggleReadOnly),
+                Item::Button(fl!("edit-override-read-only"), None, Action::OverrideReadOnly),
             ],
         ),
         (
-            "Edit",
+            fl!("menu-format"),
             vec![
-                Item::Button("Undo", None, Action::Undo),
-                Item::Button("Redo", None, Action::Redo),
+                Item::Button(fl!("format-bold"), None, Action::Bold),
+                Item::Button(fl!("format-italic"), None, Action::Italic),
+                Item::Button(fl!("format-inline-code"), None, Action::InlineCode),
+                Item::Button(fl!("format-strikethrough"), None, Action::Strikethrough),
                 Item::Divider,
-                Item::Button("Cut", None, Action::Cut),
-                Item::Button("Copy", None, Action::Copy),
-                Item::Button("Paste", None, Action::Paste),
+                Item::Button(fl!("format-heading-increase"), None, Action::HeadingIncrease),
+                Item::Button(fl!("format-heading-decrease"), None, Action::HeadingDecrease),
+                Item::Button(fl!("format-blockquote"), None, Action::ToggleBlockquote),
+                Item::Button(fl!("format-reflow-paragraph"), None, Action::ReflowParagraph),
                 Item::Divider,
-                Item::Button("Select All", None, Action::SelectAll),
+                Item::Button(fl!("format-callout-note"), None, Action::CalloutNote),
+                Item::Button(fl!("format-callout-tip"), None, Action::CalloutTip),
+                Item::Button(fl!("format-callout-warning"), None, Action::CalloutWarning),
                 Item::Divider,
-                Item::Button("Find", None, Action::Find),
-                Item::Button("Find & Replace", None, Action::FindReplace),
+                Item::Button(fl!("format-open-link"), None, Action::OpenLink),
+            ],
+        ),
+        (
+            fl!("menu-insert"),
+            vec![
+                Item::Button(fl!("insert-date-time"), None, Action::InsertDateTime),
+                Item::Button(fl!("insert-date-iso"), None, Action::InsertDateIso),
+                Item::Button(fl!("insert-date-locale"), None, Action::InsertDateLocale),
             ],
         ),
         (
-            "View",
+            fl!("menu-view"),
             vec![
-                Item::Button("Toggle Sidebar", None, Action::ToggleSidebar),
-                Item::Button("Toggle Preview", None, Action::ToggleViewMode),
+                Item::Button(fl!("view-toggle-sidebar"), None, Action::ToggleSidebar),
+                Item::Button(fl!("view-swap-sidebar-side"), None, Action::SwapSidebarSide),
+                Item::Button(fl!("view-expand-all-folders"), None, Action::ExpandAllFolders),
+                Item::Button(fl!("view-collapse-all-folders"), None, Action::CollapseAllFolders),
+                Item::Button(fl!("view-toggle-preview"), None, Action::ToggleViewMode),
+                Item::Button(fl!("view-render-whitespace"), None, Action::ToggleRenderWhitespace),
+                Item::Button(fl!("view-live-preview"), None, Action::ToggleLivePreview),
+                Item::Button(fl!("view-sanitize-html"), None, Action::ToggleSanitizeHtml),
+                Item::Button(fl!("view-wiki-links"), None, Action::ToggleWikiLinks),
+                Item::Button(fl!("view-browser-preview"), None, Action::StartLivePreview),
+                Item::Button(fl!("view-stop-browser-preview"), None, Action::StopLivePreview),
+                Item::Button(fl!("view-show-changes"), None, Action::ShowChanges),
+                Item::Button(fl!("view-show-problems"), None, Action::ShowProblems),
                 Item::Divider,
-                Item::Button("Zoom In", None, Action::ZoomIn),
-                Item::Button("Zoom Out", None, Action::ZoomOut),
-                Item::Button("Reset Zoom", None, Action::ZoomReset),
+                Item::Button(fl!("view-split-vertical"), None, Action::SplitEditorVertical),
+                Item::Button(fl!("view-split-horizontal"), None, Action::SplitEditorHorizontal),
+                Item::Button(fl!("view-move-tab"), None, Action::MoveTabToOtherPane),
+                Item::Button(fl!("view-unsplit"), None, Action::UnsplitEditor),
                 Item::Divider,
-                Item::Button("Fullscreen", None, Action::ToggleFullscreen),
+                Item::Button(fl!("view-zoom-in"), None, Action::ZoomIn),
+                Item::Button(fl!("view-zoom-out"), None, Action::ZoomOut),
+                Item::Button(fl!("view-zoom-reset"), None, Action::ZoomReset),
+                Item::Divider,
+                Item::Button(fl!("view-fullscreen"), None, Action::ToggleFullscreen),
             ],
         ),
         (
-            "Help",
+            fl!("menu-help"),
             vec![
-                Item::Button("Command Palette", None, Action::CommandPalette),
+                Item::Button(fl!("help-command-palette"), None, Action::CommandPalette),
                 Item::Divider,
-                Item::Button("About", None, Action::About),
+                Item::Button(fl!("help-about"), None, Action::About),
             ],
         ),
     ]
@@ -327,6 +759,11 @@ pub fn menu_items(_key_binds: &HashMap<KeyBind, Action>) -> MenuItems {
 /// Listens for keyboard events and matches against defined shortcuts.
 pub fn keyboard_shortcuts_subscription() -> Subscription<Message> {
     listen_raw(|event, status, _| {
+        // Track modifiers even when a widget handled the event (for Ctrl+Click)
+        if let Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = event {
+            return Some(Message::System(SystemMessage::ModifiersChanged(modifiers)));
+        }
+
         // Only process if event wasn't already handled
         if event::Status::Ignored != status {
             return None;
@@ -349,6 +786,14 @@ pub fn keyboard_shortcuts_subscription() -> Subscription<Message> {
                                 "s" => return Some(Action::SaveAs.to_message()),
                                 "z" => return Some(Action::Redo.to_message()),
                                 "p" => return Some(Action::CommandPalette.to_message()),
+                                "d" => return Some(Action::InsertDateTime.to_message()),
+                                "t" => return Some(Action::ReopenClosedTab.to_message()),
+                                "b" => return Some(Action::ToggleSidebar.to_message()),
+                                "o" => return Some(Action::OpenLink.to_message()),
+                                "v" => return Some(Action::PastePlain.to_message()),
+                                // Shift turns brackets into braces on most layouts
+                                "]" | "}" => return Some(Action::HeadingIncrease.to_message()),
+                                "[" | "{" => return Some(Action::HeadingDecrease.to_message()),
                                 _ => {}
                             }
                         } else {
@@ -367,20 +812,64 @@ pub fn keyboard_shortcuts_subscription() -> Subscription<Message> {
                                 "a" => return Some(Action::SelectAll.to_message()),
                                 "f" => return Some(Action::Find.to_message()),
                                 "h" => return Some(Action::FindReplace.to_message()),
-                                "b" => return Some(Action::ToggleSidebar.to_message()),
+                                "j" => return Some(Action::JoinLines.to_message()),
+                                "t" => return Some(Action::TransposeChars.to_message()),
+                                "b" => return Some(Action::Bold.to_message()),
+                                "i" => return Some(Action::Italic.to_message()),
+                                "`" => return Some(Action::InlineCode.to_message()),
                                 "e" => return Some(Action::ToggleViewMode.to_message()),
                                 "=" | "+" => return Some(Action::ZoomIn.to_message()),
                                 "-" => return Some(Action::ZoomOut.to_message()),
                                 "0" => return Some(Action::ZoomReset.to_message()),
+                                "\\" => return Some(Action::SplitEditorVertical.to_message()),
+                                "9" => return Some(Action::LastTab.to_message()),
+                                digit @ ("1" | "2" | "3" | "4" | "5" | "6" | "7" | "8") => {
+                                    let index = digit.parse::<usize>().unwrap_or(1) - 1;
+                                    return Some(Action::SelectTab(index).to_message());
+                                }
                                 _ => {}
                             }
                         }
+                    } else if modifiers.control() && modifiers.alt() && c_lower == "v" {
+                        return Some(Action::PasteFromHistory.to_message());
+                    } else if modifiers.alt() && !modifiers.control() && c_lower == "t" {
+                        // Alt+T, as in Emacs
+                        return Some(Action::TransposeWords.to_message());
                     }
                 }
                 
-                // Handle F keys
-                if let Key::Named(keyboard::key::Named::F11) = key {
-                    return Some(Action::ToggleFullscreen.to_message());
+                // Handle named keys
+                match key {
+                    Key::Named(keyboard::key::Named::F11) => {
+                        return Some(Action::ToggleFullscreen.to_message());
+                    }
+                    Key::Named(keyboard::key::Named::Tab) if modifiers.control() => {
+                        return Some(Message::Tab(TabMessage::CycleRecent {
+                            backwards: modifiers.shift(),
+                        }));
+                    }
+                    Key::Named(keyboard::key::Named::F2) => {
+                        let action = if modifiers.control() {
+                            Action::ToggleBookmark
+                        } else if modifiers.shift() {
+                            Action::PreviousBookmark
+                        } else {
+                            Action::NextBookmark
+                        };
+                        return Some(action.to_message());
+                    }
+                    // These only get here when no widget used them, so they
+                    // can drive the command palette
+                    Key::Named(keyboard::key::Named::ArrowUp) => {
+                        return Some(Message::Dialog(DialogMessage::CommandPaletteMove(-1)));
+                    }
+                    Key::Named(keyboard::key::Named::ArrowDown) => {
+                        return Some(Message::Dialog(DialogMessage::CommandPaletteMove(1)));
+                    }
+                    Key::Named(keyboard::key::Named::Escape) => {
+                        return Some(Message::Dialog(DialogMessage::CloseCommandPalette));
+                    }
+                    _ => {}
                 }
             }
             _ => {}
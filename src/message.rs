@@ -3,7 +3,10 @@
 //! Defines all messages that can be sent to the application's update function.
 //! Messages are organized by category for clear handling and routing.
 
-use crate::state::{DocumentId, FileEntry};
+use crate::editor::EmphasisKind;
+use crate::markdown::CalloutKind;
+use crate::state::{DocumentId, FileEntry, NewEntryKind};
+use cosmic::iced::keyboard;
 use cosmic::widget::text_editor;
 use std::path::PathBuf;
 
@@ -31,6 +34,9 @@ pub enum Message {
     /// Dialog operations
     Dialog(DialogMessage),
 
+    /// Sidebar file browser operations
+    Sidebar(SidebarMessage),
+
     /// System/window events
     System(SystemMessage),
 
@@ -56,6 +62,10 @@ pub enum FileMessage {
     /// Open a specific file path
     OpenPath(PathBuf),
 
+    /// Open a path at another application's request, raising the window
+    /// (a path that doesn't exist opens as a new unsaved document)
+    OpenExternal(PathBuf),
+
     /// File was loaded from disk
     Loaded {
         path: PathBuf,
@@ -86,6 +96,13 @@ pub enum FileMessage {
         path: PathBuf,
     },
 
+    /// Document was autosaved to its file
+    Autosaved {
+        document_id: DocumentId,
+        path: PathBuf,
+        content: String,
+    },
+
     /// Error saving file
     SaveError {
         document_id: DocumentId,
@@ -104,9 +121,36 @@ pub enum FileMessage {
     /// Close all documents
     CloseAll,
 
+    /// Reopen the most recently closed tab
+    ReopenClosed,
+
+    /// A closed tab's file was read back from disk
+    ReopenLoaded {
+        closed: crate::state::ClosedDocument,
+        content: String,
+    },
+
+    /// A closed tab's file could not be read back
+    ReopenError {
+        closed: crate::state::ClosedDocument,
+        error: String,
+    },
+
     /// Reload file from disk
     Reload(DocumentId),
 
+    /// A file was read back from disk for reloading
+    Reloaded {
+        document_id: DocumentId,
+        content: String,
+    },
+
+    /// Turn watch mode on or off
+    ToggleWatch,
+
+    /// Turn single-instance mode on or off
+    ToggleSingleInstance,
+
     /// File changed externally
     ExternalChange {
         path: PathBuf,
@@ -121,6 +165,24 @@ pub enum FileMessage {
 
     /// Reveal file in system file manager
     RevealInFileManager(PathBuf),
+
+    /// Create an empty file and open it
+    CreateFile(PathBuf),
+
+    /// Create a folder
+    CreateFolder(PathBuf),
+
+    /// A file or folder was created on disk
+    Created {
+        path: PathBuf,
+        kind: NewEntryKind,
+    },
+
+    /// Error creating a file or folder
+    CreateError {
+        path: PathBuf,
+        error: String,
+    },
 }
 
 /// Kind of external file change
@@ -140,12 +202,19 @@ pub enum TabMessage {
     /// Select a tab by index
     SelectIndex(usize),
 
+    /// Select the last tab
+    SelectLast,
+
     /// Switch to next tab
     Next,
 
     /// Switch to previous tab
     Previous,
 
+    /// Step through documents in the order they were last active, for
+    /// Ctrl+Tab; the switch is committed once Ctrl is released
+    CycleRecent { backwards: bool },
+
     /// Close a tab
     Close(DocumentId),
 
@@ -232,6 +301,9 @@ pub enum EditorMessage {
     /// Outdent selection
     Outdent,
 
+    /// Set the language of the code block around the cursor (empty for plain text)
+    SetCodeBlockLanguage(String),
+
     /// Toggle comment on selection
     ToggleComment,
 
@@ -256,6 +328,42 @@ pub enum EditorMessage {
     /// Format document
     Format,
 
+    /// Sort the selected lines
+    SortLines {
+        descending: bool,
+        case_sensitive: bool,
+    },
+
+    /// Remove duplicate lines within the selection
+    DedupeLines,
+
+    /// Join the next line onto the current one, or join the selected lines
+    JoinLines,
+
+    /// Swap the two characters around the cursor
+    TransposeChars,
+
+    /// Swap the words before and after the cursor
+    TransposeWords,
+
+    /// Toggle emphasis markers around the selection
+    ToggleEmphasis(EmphasisKind),
+
+    /// Change the heading level of the current or selected lines
+    AdjustHeading(i32),
+
+    /// Add or remove one blockquote level on the selected lines
+    ToggleBlockquote,
+
+    /// Rewrap the paragraph at the cursor to the reflow width
+    ReflowParagraph,
+
+    /// Wrap the selected lines in a callout of a type
+    WrapInCallout(CalloutKind),
+
+    /// Open the link under the cursor
+    OpenLinkAtCursor,
+
     /// Scroll to position
     ScrollTo {
         document_id: DocumentId,
@@ -267,6 +375,113 @@ pub enum EditorMessage {
         document_id: DocumentId,
         action: text_editor::Action,
     },
+
+    /// Insert the current date/time (None uses the configured format)
+    InsertDateTime(Option<&'static str>),
+
+    /// Tab pressed in the editor (expands snippets and cycles tab-stops)
+    Tab {
+        document_id: DocumentId,
+        backwards: bool,
+    },
+
+    /// Enter pressed in the editor (auto-indents the new line)
+    Newline { document_id: DocumentId },
+
+    /// A surround character typed in the editor (wraps the selection in
+    /// it and its closing character, or types it when nothing is selected)
+    Surround { document_id: DocumentId, open: char },
+
+    /// An arrow key pressed in the editor, moving the cursor (extending
+    /// the selection with `select`)
+    MoveCursor {
+        document_id: DocumentId,
+        motion: CursorMotion,
+        select: bool,
+    },
+
+    /// Toggle the read-only lock on the active document
+    ToggleReadOnly,
+
+    /// Make the active document's read-only file writable and unlock it
+    OverrideReadOnly,
+
+    /// Bookmark the cursor line, or remove its bookmark
+    ToggleBookmark,
+
+    /// Move the cursor to the next bookmark
+    NextBookmark,
+
+    /// Move the cursor to the previous bookmark
+    PreviousBookmark,
+
+    /// Remove every bookmark in the active document
+    ClearBookmarks,
+
+    /// A file dragged from the sidebar is over an editor, at a position in
+    /// its text area, or left it
+    FileDragOver {
+        document_id: DocumentId,
+        position: Option<cosmic::iced::Point>,
+    },
+}
+
+impl EditorMessage {
+    /// Check whether the message modifies document content
+    pub fn is_edit(&self) -> bool {
+        match self {
+            EditorMessage::TextEditorAction { action, .. } => action.is_edit(),
+            EditorMessage::TextChanged { .. }
+            | EditorMessage::Insert { .. }
+            | EditorMessage::Delete { .. }
+            | EditorMessage::Undo
+            | EditorMessage::Redo
+            | EditorMessage::Indent
+            | EditorMessage::Outdent
+            | EditorMessage::ToggleComment
+            | EditorMessage::DuplicateLine
+            | EditorMessage::MoveLineUp
+            | EditorMessage::MoveLineDown
+            | EditorMessage::DeleteLine
+            | EditorMessage::InsertLineBelow
+            | EditorMessage::InsertLineAbove
+            | EditorMessage::Format
+            | EditorMessage::SortLines { .. }
+            | EditorMessage::DedupeLines
+            | EditorMessage::JoinLines
+            | EditorMessage::TransposeChars
+            | EditorMessage::TransposeWords
+            | EditorMessage::ToggleEmphasis(_)
+            | EditorMessage::AdjustHeading(_)
+            | EditorMessage::ToggleBlockquote
+            | EditorMessage::ReflowParagraph
+            | EditorMessage::WrapInCallout(_)
+            | EditorMessage::SetCodeBlockLanguage(_)
+            | EditorMessage::Tab { .. }
+            | EditorMessage::Newline { .. }
+            | EditorMessage::Surround { .. }
+            | EditorMessage::InsertDateTime(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Get the document the message targets, if it names one
+    pub fn document_id(&self) -> Option<DocumentId> {
+        match self {
+            EditorMessage::TextChanged { document_id, .. }
+            | EditorMessage::Insert { document_id, .. }
+            | EditorMessage::Delete { document_id, .. }
+            | EditorMessage::CursorMoved { document_id, .. }
+            | EditorMessage::SelectionChanged { document_id, .. }
+            | EditorMessage::ScrollTo { document_id, .. }
+            | EditorMessage::TextEditorAction { document_id, .. }
+            | EditorMessage::Tab { document_id, .. }
+            | EditorMessage::Newline { document_id }
+            | EditorMessage::Surround { document_id, .. }
+            | EditorMessage::MoveCursor { document_id, .. } => Some(*document_id),
+            _ => None,
+        }
+    }
 }
 
 /// Clipboard-related messages
@@ -278,16 +493,92 @@ pub enum ClipboardMessage {
     /// Copy selection to clipboard
     Copy,
 
+    /// Copy selection to clipboard as HTML rendered from the markdown,
+    /// with the markdown as plain text
+    CopyAsHtml,
+
+    /// Copy the HTML rendered from the selection, or from the whole
+    /// document without one, as plain text
+    CopySelectionHtml,
+
+    /// Copy a link to the heading under the cursor, `#anchor` or
+    /// `path#anchor`
+    CopyHeadingLink { with_path: bool },
+
+    /// Copy a document's path, the active document's when `None`
+    CopyPath {
+        document_id: Option<DocumentId>,
+        kind: CopyPathKind,
+    },
+
     /// Paste from clipboard
     Paste,
 
+    /// Paste clipboard text without formatting
+    PastePlain,
+
+    /// Paste clipboard HTML converted to markdown
+    PasteAsMarkdown,
+
+    /// Download the image at the URL on the clipboard to the assets folder
+    /// and paste a link to it
+    PasteImageFromUrl,
+
+    /// Open the clipboard history picker, or highlight the next older entry
+    PasteFromHistory,
+
+    /// Paste a clipboard history entry, most recent first
+    PasteHistoryEntry(usize),
+
+    /// Close the clipboard history picker
+    CloseHistory,
+
     /// Clipboard content received
     Content(String),
 
+    /// Page title lookup for a smart-pasted URL finished
+    LinkTitleFetched {
+        document_id: DocumentId,
+        url: String,
+        title: Option<String>,
+    },
+
+    /// Download for Paste Image from URL finished
+    ImageDownloaded {
+        document_id: DocumentId,
+        url: String,
+        result: Result<Vec<u8>, crate::markdown::DownloadError>,
+    },
+
     /// Clipboard error
     Error(String),
 }
 
+/// Cursor movement the editor handles itself rather than the text editor
+/// widget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMotion {
+    /// One character left on screen
+    Left,
+    /// One character right on screen
+    Right,
+    /// To the start of the previous word
+    WordLeft,
+    /// To the start of the next word
+    WordRight,
+}
+
+/// Which form of a document's path to copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyPathKind {
+    /// The absolute path
+    Absolute,
+    /// Relative to the workspace root, or absolute outside it
+    Relative,
+    /// The file name alone
+    FileName,
+}
+
 /// Search-related messages
 #[derive(Debug, Clone)]
 pub enum SearchMessage {
@@ -300,6 +591,13 @@ pub enum SearchMessage {
     /// Close search dialogs
     CloseFind,
 
+    /// Close search dialogs, returning the cursor to where the search
+    /// started
+    CancelFind,
+
+    /// Keep the previewed match, or move to the next one once kept
+    Commit,
+
     /// Update find query
     UpdateQuery(String),
 
@@ -315,6 +613,10 @@ pub enum SearchMessage {
     /// Toggle regex mode
     ToggleRegex,
 
+    /// Toggle limiting matches to the selection pinned when the find bar
+    /// opened
+    ToggleInSelection,
+
     /// Find next match
     FindNext,
 
@@ -324,9 +626,15 @@ pub enum SearchMessage {
     /// Replace current match
     Replace,
 
-    /// Replace all matches
+    /// Replace all matches, asking first when there are many
     ReplaceAll,
 
+    /// Go ahead with the Replace All waiting for confirmation
+    ConfirmReplaceAll,
+
+    /// Drop the Replace All waiting for confirmation
+    CancelReplaceAll,
+
     /// Global search across files
     GlobalSearch(String),
 
@@ -359,6 +667,55 @@ pub enum ViewMessage {
     /// Set sidebar width
     SetSidebarWidth(u32),
 
+    /// Show the sidebar on the other side of the window
+    SwapSidebarSide,
+
+    /// Cycle which whitespace the editor draws markers for
+    ToggleRenderWhitespace,
+
+    /// Switch live preview of markdown formatting in the editor
+    ToggleLivePreview,
+
+    /// Switch sanitizing of raw HTML in previews and exports
+    ToggleSanitizeHtml,
+
+    /// Switch `[[wiki links]]` between notes on or off
+    ToggleWikiLinks,
+
+    /// Serve the active document's HTML over a local port and open it in
+    /// a browser, reloading it on save
+    StartLivePreview,
+
+    /// Stop serving the browser preview
+    StopLivePreview,
+
+    /// Show the sidebar with this folder expanded and selected
+    RevealInSidebar(std::path::PathBuf),
+
+    /// Change which files the sidebar lists
+    SetSidebarFilter(crate::state::FileFilter),
+
+    /// Start dragging the sidebar resize handle
+    StartSidebarResize,
+
+    /// The pointer moved to this x position while resizing the sidebar
+    SidebarResizeMoved(f32),
+
+    /// Stop dragging the sidebar resize handle
+    EndSidebarResize,
+
+    /// The pointer moved over a minimap, to this position below its top
+    MinimapHovered(f32),
+
+    /// A document's minimap was pressed at the last hovered position
+    MinimapPressed(DocumentId),
+
+    /// The pointer moved to this window y position while dragging a minimap
+    MinimapDragged(f32),
+
+    /// Stop dragging a minimap
+    MinimapReleased,
+
     /// Toggle view mode (edit/preview/split)
     ToggleViewMode,
 
@@ -391,6 +748,30 @@ pub enum ViewMessage {
 
     /// Focus sidebar
     FocusSidebar,
+
+    /// Toggle the panel showing changes since the last save
+    ShowChanges,
+
+    /// Close the changes panel
+    HideChanges,
+
+    /// Toggle the panel listing lint issues and broken links
+    ShowProblems,
+
+    /// Close the problems panel
+    HideProblems,
+
+    /// Split the editor into two panes side by side
+    SplitEditorVertical,
+
+    /// Split the editor into two panes one above the other
+    SplitEditorHorizontal,
+
+    /// Move the active tab to the other editor pane
+    MoveTabToOtherPane,
+
+    /// Return to a single editor pane
+    UnsplitEditor,
 }
 
 /// Dialog-related messages
@@ -405,8 +786,14 @@ pub enum DialogMessage {
     /// Command palette input changed
     CommandPaletteInput(String),
 
+    /// Move the command palette highlight up (negative) or down
+    CommandPaletteMove(isize),
+
+    /// Run the highlighted command palette entry
+    ExecuteSelectedCommand,
+
     /// Execute command from palette
-    ExecuteCommand(String),
+    ExecuteCommand(crate::menu::Action),
 
     /// Show confirmation dialog
     ShowConfirm {
@@ -455,8 +842,8 @@ pub enum SystemMessage {
     /// Force quit (skip unsaved check)
     ForceQuit,
 
-    /// Theme changed
-    ThemeChanged,
+    /// System theme changed (true when dark)
+    ThemeChanged(bool),
 
     /// Tick for periodic tasks (autosave, etc.)
     Tick,
@@ -472,6 +859,18 @@ pub enum SystemMessage {
 
     /// Clear status message
     ClearStatus,
+
+    /// Show or hide the details of the status message
+    ToggleStatusDetails,
+
+    /// Show why the active document's recovery snapshot failed
+    ShowSnapshotError,
+
+    /// Open a URL with the default handler (browser, mail client, ...)
+    OpenUrl(String),
+
+    /// Keyboard modifiers changed
+    ModifiersChanged(keyboard::Modifiers),
 }
 
 /// Internal messages for async operations
@@ -483,6 +882,22 @@ pub enum InternalMessage {
     /// Directory scan failed
     DirectoryScanError(String),
 
+    /// A sidebar folder's children were listed
+    DirectoryListed(crate::file_handler::DirListing),
+
+    /// The sidebar's whole tree was listed for expanding every folder,
+    /// unless `stopped` early
+    TreeListed {
+        listings: Vec<crate::file_handler::DirListing>,
+        stopped: bool,
+    },
+
+    /// The open folder was scanned for the notes wiki links resolve against
+    NotesScanned {
+        root: PathBuf,
+        notes: Vec<PathBuf>,
+    },
+
     /// Autosave triggered
     AutosaveTrigger,
 
@@ -494,6 +909,54 @@ pub enum InternalMessage {
 
     /// Config changed
     ConfigChanged,
+
+    /// A later launch asked this instance to open files
+    InstanceRequest(std::sync::Arc<crate::ipc::InstanceRequest>),
+
+    /// Time to step an animated jump
+    ScrollAnimationTick,
+
+    /// Time to collect file system events in watch mode
+    WatchTick,
+
+    /// Diff against the saved version finished
+    DiffComputed {
+        document_id: DocumentId,
+        diff: Vec<crate::editor::DiffLine>,
+        all_new: bool,
+    },
+
+    /// Git line status finished (None when the file is not under git)
+    GitStatusComputed {
+        document_id: DocumentId,
+        changes: Option<Vec<crate::editor::LineChange>>,
+    },
+
+    /// Editing paused long enough to lint a document and check its links
+    ChecksDue { document_id: DocumentId },
+
+    /// Link check finished
+    LinksChecked {
+        document_id: DocumentId,
+        diagnostics: Vec<crate::markdown::LinkDiagnostic>,
+    },
+
+    /// The browser preview's page was rendered
+    ///
+    /// `render` counts renders so stale ones can be dropped, and `open`
+    /// opens the page in the browser.
+    PreviewPageRendered {
+        document_id: DocumentId,
+        render: u64,
+        html: String,
+        open: bool,
+    },
+
+    /// A document's diagrams were rendered for the preview
+    DiagramsRendered {
+        document_id: DocumentId,
+        diagrams: Vec<crate::markdown::DiagramOutput>,
+    },
 }
 
 /// Sidebar-specific messages (can be nested in other messages)
@@ -523,6 +986,40 @@ pub enum SidebarMessage {
     /// Create new folder
     NewFolder,
 
+    /// Create a new file or folder next to an entry, from its context menu
+    NewEntryAt {
+        entry_index: usize,
+        kind: NewEntryKind,
+    },
+
+    /// Name typed in the new file or folder prompt
+    NewEntryName(String),
+
+    /// Create the entry named in the prompt
+    SubmitNewEntry,
+
+    /// Close the new file or folder prompt
+    CancelNewEntry,
+
+    /// Pointer button pressed on a file, which opens it on release or
+    /// drags it once the pointer moves
+    PressFile(PathBuf),
+
+    /// The pointer moved to a window position while a file is pressed
+    FileDragMoved { x: f32, y: f32 },
+
+    /// The pointer button was released while a file is pressed
+    EndFileDrag,
+
+    /// Expand every folder in the tree
+    ExpandAll,
+
+    /// Stop expanding every folder, keeping those expanded so far
+    CancelExpandAll,
+
+    /// Collapse every folder in the tree
+    CollapseAll,
+
     /// Rename entry
     Rename(PathBuf),
 
@@ -591,6 +1088,12 @@ impl From<DialogMessage> for Message {
     }
 }
 
+impl From<SidebarMessage> for Message {
+    fn from(msg: SidebarMessage) -> Self {
+        Message::Sidebar(msg)
+    }
+}
+
 impl From<SystemMessage> for Message {
     fn from(msg: SystemMessage) -> Self {
         Message::System(msg)
@@ -5,9 +5,15 @@
 //! - Case-insensitive search
 //! - Whole word matching
 //! - Regular expressions
+//!
+//! All offsets are character offsets, matching the document rope; byte
+//! offsets never leave this module.
 
 use std::ops::Range;
 
+/// Default cap on the number of matches a search returns
+pub const DEFAULT_MAX_RESULTS: usize = 10_000;
+
 /// Search direction
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum SearchDirection {
@@ -27,6 +33,10 @@ pub struct FindOptions {
     pub use_regex: bool,
     /// Wrap around at document boundaries
     pub wrap_around: bool,
+    /// Stop after this many matches
+    pub max_results: usize,
+    /// Only match inside this character range, as for find in selection
+    pub within: Option<Range<usize>>,
 }
 
 impl Default for FindOptions {
@@ -36,6 +46,8 @@ impl Default for FindOptions {
             whole_word: false,
             use_regex: false,
             wrap_around: true,
+            max_results: DEFAULT_MAX_RESULTS,
+            within: None,
         }
     }
 }
@@ -117,20 +129,20 @@ impl SearchEngine {
             self.last_options = options.clone();
         }
 
-        if options.use_regex {
-            self.find_regex(text)
-        } else {
-            self.find_plain(text, query, options)
-        }
+        self.find_regex(text, options)
     }
 
     /// Update the regex pattern based on query and options
+    ///
+    /// Plain text queries are escaped into a pattern too, so case folding
+    /// and word boundaries follow the same Unicode rules in both modes.
     fn update_pattern(&mut self, query: &str, options: &FindOptions) {
         if options.use_regex {
+            // `^` and `$` match at every line, as users expect in an editor
             let pattern = if options.case_sensitive {
-                query.to_string()
+                format!("(?m){}", query)
             } else {
-                format!("(?i){}", query)
+                format!("(?mi){}", query)
             };
 
             self.regex_pattern = regex::Regex::new(&pattern).ok();
@@ -153,93 +165,62 @@ impl SearchEngine {
         }
     }
 
-    /// Find matches using regex
-    fn find_regex(&self, text: &str) -> Vec<FindResult> {
+    /// Matches of the compiled pattern lying wholly inside `within`
+    ///
+    /// The whole text is searched so anchors and word boundaries at the
+    /// range's edges see the text around it.
+    fn matches<'t>(
+        regex: &regex::Regex,
+        text: &'t str,
+        within: Option<&Range<usize>>,
+    ) -> impl Iterator<Item = regex::Match<'t>> {
+        let byte_offset = |char_offset: usize| {
+            text.char_indices()
+                .nth(char_offset)
+                .map_or(text.len(), |(i, _)| i)
+        };
+        let (start, end) = within.map_or((0, text.len()), |r| (byte_offset(r.start), byte_offset(r.end)));
+        // Matches come in order, so the first past the end ends the search
+        regex
+            .find_iter(text)
+            .skip_while(move |mat| mat.start() < start)
+            .take_while(move |mat| mat.end() <= end)
+    }
+
+    /// Find matches using the compiled pattern
+    ///
+    /// Converts the regex byte offsets to character offsets in a single pass.
+    /// Matches never overlap: after an empty match, as from `a*` or `^`, the
+    /// search resumes one character further on.
+    fn find_regex(&self, text: &str, options: &FindOptions) -> Vec<FindResult> {
         let Some(ref regex) = self.regex_pattern else {
             return Vec::new();
         };
 
         let mut results = Vec::new();
-        let mut line_starts: Vec<usize> = vec![0];
-        
-        // Build line start index
-        for (i, c) in text.char_indices() {
-            if c == '\n' {
-                line_starts.push(i + 1);
+        let (mut byte_pos, mut char_pos) = (0, 0);
+        let (mut line, mut line_start) = (0, 0);
+
+        for mat in Self::matches(regex, text, options.within.as_ref()).take(options.max_results) {
+            for c in text[byte_pos..mat.start()].chars() {
+                char_pos += 1;
+                if c == '\n' {
+                    line += 1;
+                    line_start = char_pos;
+                }
             }
-        }
-
-        for mat in regex.find_iter(text) {
-            let start = mat.start();
-            let end = mat.end();
-            
-            // Find line and column
-            let line = line_starts.partition_point(|&ls| ls <= start).saturating_sub(1);
-            let line_start = line_starts.get(line).copied().unwrap_or(0);
-            let column = text[line_start..start].chars().count();
+            let start = char_pos;
+            let end = start + mat.as_str().chars().count();
 
             results.push(FindResult::new(
                 start,
                 end,
                 line,
-                column,
+                start - line_start,
                 mat.as_str().to_string(),
             ));
-        }
-
-        results
-    }
-
-    /// Find matches using plain text search
-    fn find_plain(&self, text: &str, query: &str, options: &FindOptions) -> Vec<FindResult> {
-        let mut results = Vec::new();
-        
-        let (search_text, search_query): (String, String) = if options.case_sensitive {
-            (text.to_string(), query.to_string())
-        } else {
-            (text.to_lowercase(), query.to_lowercase())
-        };
 
-        // Build line start index
-        let mut line_starts: Vec<usize> = vec![0];
-        for (i, c) in text.char_indices() {
-            if c == '\n' {
-                line_starts.push(i + 1);
-            }
-        }
-
-        let mut start = 0;
-        while let Some(pos) = search_text[start..].find(&search_query) {
-            let match_start = start + pos;
-            let match_end = match_start + query.len();
-
-            // Check whole word if needed
-            if options.whole_word {
-                let before_ok = match_start == 0 
-                    || !text.chars().nth(match_start - 1).map(|c| c.is_alphanumeric() || c == '_').unwrap_or(false);
-                let after_ok = match_end >= text.len()
-                    || !text.chars().nth(match_end).map(|c| c.is_alphanumeric() || c == '_').unwrap_or(false);
-                
-                if !before_ok || !after_ok {
-                    start = match_start + 1;
-                    continue;
-                }
-            }
-
-            // Find line and column
-            let line = line_starts.partition_point(|&ls| ls <= match_start).saturating_sub(1);
-            let line_start = line_starts.get(line).copied().unwrap_or(0);
-            let column = text[line_start..match_start].chars().count();
-
-            results.push(FindResult::new(
-                match_start,
-                match_end,
-                line,
-                column,
-                text[match_start..match_end].to_string(),
-            ));
-
-            start = match_start + 1;
+            byte_pos = mat.start();
         }
 
         results
@@ -296,6 +277,8 @@ impl SearchEngine {
     }
 
     /// Replace all occurrences and return the new text
+    ///
+    /// With `options.within` set, only matches inside that range change.
     pub fn replace_all(
         &mut self,
         text: &str,
@@ -303,33 +286,99 @@ impl SearchEngine {
         replacement: &str,
         options: &FindOptions,
     ) -> (String, usize) {
-        let results = self.find_all(text, query, options);
-        let count = results.len();
-
-        if results.is_empty() {
+        if query.is_empty() {
             return (text.to_string(), 0);
         }
-
-        // Build new text by replacing matches
+        self.find_all(text, query, options);
+        // Every match is replaced, so count past the result cap
+        let Some(regex) = self.regex_pattern.as_ref() else {
+            return (text.to_string(), 0);
+        };
+        // The replacement is literal text, even in regex mode
         let mut new_text = String::with_capacity(text.len());
-        let mut last_end = 0;
-
-        for result in results {
-            new_text.push_str(&text[last_end..result.start]);
+        let (mut last, mut count) = (0, 0);
+        for mat in Self::matches(regex, text, options.within.as_ref()) {
+            new_text.push_str(&text[last..mat.start()]);
             new_text.push_str(replacement);
-            last_end = result.end;
+            last = mat.end();
+            count += 1;
         }
-        new_text.push_str(&text[last_end..]);
-
+        if count == 0 {
+            return (text.to_string(), 0);
+        }
+        new_text.push_str(&text[last..]);
         (new_text, count)
     }
 
-    /// Replace a single occurrence at the given range
+    /// Count the matches Replace All would replace, without building the
+    /// new text
+    ///
+    /// Returns the number of matches and their total length in bytes. Like
+    /// [`replace_all`](Self::replace_all), this counts past the result cap.
+    pub fn count_matches(&mut self, text: &str, query: &str, options: &FindOptions) -> (usize, usize) {
+        if query.is_empty() {
+            return (0, 0);
+        }
+        self.find_all(text, query, options);
+        let Some(regex) = self.regex_pattern.as_ref() else {
+            return (0, 0);
+        };
+        Self::matches(regex, text, options.within.as_ref())
+            .fold((0, 0), |(count, len), mat| (count + 1, len + mat.len()))
+    }
+
+    /// Check that a stored character range is still a match in `text`
+    ///
+    /// The document may have changed since the search ran, so a range is
+    /// only trusted when a fresh search finds exactly that match.
+    pub fn is_match_at(
+        &mut self,
+        text: &str,
+        range: &Range<usize>,
+        query: &str,
+        options: &FindOptions,
+    ) -> bool {
+        if range.start > range.end || range.end > text.chars().count() {
+            return false;
+        }
+        self.find_all(text, query, options)
+            .iter()
+            .any(|result| result.range() == *range)
+    }
+
+    /// Replace a previous match in a rope, if it is still a match
+    ///
+    /// Returns whether the rope was changed.
+    pub fn replace_match(
+        &mut self,
+        rope: &mut ropey::Rope,
+        range: Range<usize>,
+        query: &str,
+        replacement: &str,
+        options: &FindOptions,
+    ) -> bool {
+        if !self.is_match_at(&rope.to_string(), &range, query, options) {
+            return false;
+        }
+
+        rope.remove(range.clone());
+        rope.insert(range.start, replacement);
+        true
+    }
+
+    /// Replace a single occurrence at the given character range
     pub fn replace_at(text: &str, range: Range<usize>, replacement: &str) -> String {
+        let byte_offset = |char_offset: usize| {
+            text.char_indices()
+                .nth(char_offset)
+                .map_or(text.len(), |(i, _)| i)
+        };
+        let (start, end) = (byte_offset(range.start), byte_offset(range.end));
+
         let mut new_text = String::with_capacity(text.len());
-        new_text.push_str(&text[..range.start]);
+        new_text.push_str(&text[..start]);
         new_text.push_str(replacement);
-        new_text.push_str(&text[range.end..]);
+        new_text.push_str(&text[end.max(start)..]);
         new_text
     }
 }
@@ -395,6 +444,47 @@ mod tests {
         assert_eq!(results[0].column, 5);
     }
 
+    #[test]
+    fn test_find_zero_width_regex() {
+        let mut engine = SearchEngine::new();
+        let text = "one\nbaa\nthree";
+        let options = FindOptions {
+            use_regex: true,
+            ..Default::default()
+        };
+
+        let starts = |results: &[FindResult]| results.iter().map(|r| r.start).collect::<Vec<_>>();
+        let line_starts = engine.find_all(text, "^", &options);
+        assert_eq!(starts(&line_starts), vec![0, 4, 8]);
+        assert_eq!(line_starts[2].line, 2);
+        let line_ends = engine.find_all(text, "$", &options);
+        assert_eq!(starts(&line_ends), vec![3, 7, 13]);
+        assert!(line_ends.iter().all(FindResult::is_empty));
+
+        // Empty matches everywhere except inside the run of "a"s
+        let results = engine.find_all(text, "a*", &options);
+        assert!(results.len() <= text.len() + 1);
+        assert!(results.windows(2).all(|w| w[0].end <= w[1].start && w[0].start < w[1].start));
+        let run = results.iter().find(|r| !r.is_empty()).unwrap();
+        assert_eq!((run.start, run.end), (5, 7));
+    }
+
+    #[test]
+    fn test_find_max_results() {
+        let mut engine = SearchEngine::new();
+        let text = "a".repeat(100);
+        let options = FindOptions {
+            use_regex: true,
+            max_results: 10,
+            ..Default::default()
+        };
+
+        assert_eq!(engine.find_all(&text, "a", &options).len(), 10);
+        let (replaced, count) = engine.replace_all(&text, "a", "b", &options);
+        assert_eq!(count, 100);
+        assert_eq!(replaced, "b".repeat(100));
+    }
+
     #[test]
     fn test_replace_all() {
         let mut engine = SearchEngine::new();
@@ -406,6 +496,37 @@ mod tests {
         assert_eq!(count, 3);
     }
 
+    #[test]
+    fn test_count_matches() {
+        let mut engine = SearchEngine::new();
+        let options = FindOptions {
+            use_regex: true,
+            ..Default::default()
+        };
+        assert_eq!(engine.count_matches("ab abc a", "ab?c?", &options), (3, 6));
+        assert_eq!(engine.count_matches("text", "", &options), (0, 0));
+    }
+
+    #[test]
+    fn test_find_within_range() {
+        let mut engine = SearchEngine::new();
+        let text = "foo bar\nfoo baz\nfoo qux";
+        let options = FindOptions {
+            within: Some(4..18),
+            ..Default::default()
+        };
+
+        // Only the second line's "foo" lies wholly in the range
+        let results = engine.find_all(text, "foo", &options);
+        assert_eq!(results.len(), 1);
+        assert_eq!((results[0].line, results[0].column), (1, 0));
+        assert!(engine.find_all(text, "qux", &options).is_empty());
+
+        let (new_text, count) = engine.replace_all(text, "foo", "x", &options);
+        assert_eq!(count, 1);
+        assert_eq!(new_text, "foo bar\nx baz\nfoo qux");
+    }
+
     #[test]
     fn test_replace_at() {
         let text = "Hello world";
@@ -425,6 +546,49 @@ mod tests {
         assert_eq!(result.unwrap().start, 0);
     }
 
+    #[test]
+    fn test_find_multibyte_offsets() {
+        let mut engine = SearchEngine::new();
+        let text = "🎉 Café\nnaïve CAFÉ";
+        let options = FindOptions::default();
+
+        let results = engine.find_all(text, "café", &options);
+        assert_eq!(results.len(), 2);
+        assert_eq!((results[0].start, results[0].end), (2, 6));
+        assert_eq!((results[1].line, results[1].column), (1, 6));
+        assert_eq!(results[1].start, 13);
+        assert_eq!(results[1].matched_text, "CAFÉ");
+
+        let (replaced, count) = engine.replace_all(text, "café", "$tea", &options);
+        assert_eq!(replaced, "🎉 $tea\nnaïve $tea");
+        assert_eq!(count, 2);
+        assert_eq!(SearchEngine::replace_at(text, 2..6, "Bar"), "🎉 Bar\nnaïve CAFÉ");
+    }
+
+    #[test]
+    fn test_replace_match_after_edit() {
+        let mut engine = SearchEngine::new();
+        let options = FindOptions::default();
+        let mut rope = ropey::Rope::from_str("one fish two fish");
+        let ranges: Vec<_> = engine
+            .find_all(&rope.to_string(), "fish", &options)
+            .iter()
+            .map(FindResult::range)
+            .collect();
+
+        // Shrink the document so the second match runs past the end
+        rope.remove(4..13);
+        assert!(!engine.replace_match(&mut rope, ranges[1].clone(), "fish", "cat", &options));
+        // Delete part of the first match so its range straddles other text
+        rope.remove(5..7);
+        assert!(!engine.replace_match(&mut rope, ranges[0].clone(), "fish", "cat", &options));
+        assert_eq!(rope.to_string(), "one fsh");
+
+        let mut rope = ropey::Rope::from_str("one fish two fish");
+        assert!(engine.replace_match(&mut rope, ranges[1].clone(), "fish", "cat", &options));
+        assert_eq!(rope.to_string(), "one fish two cat");
+    }
+
     #[test]
     fn test_empty_query() {
         let mut engine = SearchEngine::new();
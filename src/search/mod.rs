@@ -8,7 +8,7 @@
 
 mod find;
 
-pub use find::{FindOptions, FindResult, SearchEngine, SearchDirection};
+pub use find::{FindOptions, FindResult, SearchEngine, SearchDirection, DEFAULT_MAX_RESULTS};
 
 /// Search state for the UI
 #[derive(Debug, Clone, Default)]
@@ -3,13 +3,24 @@
 //! Contains the central state for the entire application, including
 //! document management, active document tracking, and UI state.
 
-use super::{EditorState, SidebarState, TabState};
-use crate::config::ViewMode;
+use super::{
+    CommandPalette, CursorPosition, EditorSplit, EditorState, Pane, SidebarState,
+    SplitOrientation, Tab, TabState,
+};
+use crate::config::{ThemePreference, ViewMode};
+use crate::editor::diff::{DiffLine, LineChange};
+use crate::markdown::{Diagnostic, DiagramOutput, LinkDiagnostic};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
 
+/// Height taken by the header, tab bar, status bar, and editor padding
+const EDITOR_CHROME_HEIGHT: f32 = 136.0;
+
+/// Line height as a multiple of the font size (iced's default)
+const LINE_HEIGHT_FACTOR: f32 = 1.3;
+
 /// Unique identifier for documents
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct DocumentId(Uuid);
@@ -38,6 +49,21 @@ impl std::fmt::Display for DocumentId {
     }
 }
 
+/// Where a document's latest changes are kept, for the autosave indicator
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveStatus {
+    /// Everything is in the file, last modified at the time when known
+    Saved(Option<std::time::SystemTime>),
+    /// The file is being written
+    Saving,
+    /// Changes are only in memory
+    Unsaved,
+    /// Changes are in a recovery snapshot taken at the time, not the file
+    Snapshot(std::time::SystemTime),
+    /// The last recovery snapshot failed, with the error
+    SnapshotFailed(String),
+}
+
 /// A single document in the editor
 #[derive(Debug, Clone)]
 pub struct Document {
@@ -59,6 +85,15 @@ pub struct Document {
     /// Whether the document is read-only
     pub read_only: bool,
 
+    /// Whether the read-only warning was shown since the lock was set
+    pub read_only_notified: bool,
+
+    /// Lines changed since the last save (for gutter markers)
+    pub saved_changes: Vec<LineChange>,
+
+    /// Lines changed relative to git HEAD (for gutter markers)
+    pub git_changes: Vec<LineChange>,
+
     /// Last known modification time of the file on disk
     pub last_disk_mtime: Option<std::time::SystemTime>,
 
@@ -67,6 +102,27 @@ pub struct Document {
 
     /// Document encoding
     pub encoding: DocumentEncoding,
+
+    /// View mode for this document (None follows the global view mode)
+    pub view_mode: Option<ViewMode>,
+
+    /// Links pointing at missing files or headings
+    pub link_diagnostics: Vec<LinkDiagnostic>,
+
+    /// Style issues found by the linter
+    pub lint_diagnostics: Vec<Diagnostic>,
+
+    /// Diagrams rendered for the preview, in document order
+    pub diagrams: Vec<DiagramOutput>,
+
+    /// Line shapes for the minimap (empty while it is disabled)
+    pub minimap: Vec<crate::editor::minimap::MinimapLine>,
+
+    /// Where the latest changes are kept
+    pub save_status: SaveStatus,
+
+    /// Indentation detected in the file when it was opened
+    pub indent: crate::editor::indent::IndentStyle,
 }
 
 impl Document {
@@ -79,9 +135,19 @@ impl Document {
             editor_state: EditorState::default(),
             modified: false,
             read_only: false,
+            read_only_notified: false,
+            saved_changes: Vec::new(),
+            git_changes: Vec::new(),
             last_disk_mtime: None,
             display_name: "Untitled".to_string(),
             encoding: DocumentEncoding::default(),
+            view_mode: None,
+            link_diagnostics: Vec::new(),
+            lint_diagnostics: Vec::new(),
+            diagrams: Vec::new(),
+            minimap: Vec::new(),
+            save_status: SaveStatus::Saved(None),
+            indent: crate::editor::indent::IndentStyle::Undetected,
         }
     }
 
@@ -99,9 +165,19 @@ impl Document {
             editor_state: EditorState::default(),
             modified: false,
             read_only: false,
+            read_only_notified: false,
+            saved_changes: Vec::new(),
+            git_changes: Vec::new(),
             last_disk_mtime: None,
             display_name,
             encoding: DocumentEncoding::default(),
+            view_mode: None,
+            link_diagnostics: Vec::new(),
+            lint_diagnostics: Vec::new(),
+            diagrams: Vec::new(),
+            minimap: Vec::new(),
+            save_status: SaveStatus::Saved(None),
+            indent: crate::editor::indent::IndentStyle::Undetected,
         }
     }
 
@@ -127,14 +203,107 @@ impl Document {
         self.path.is_some()
     }
 
+    /// Check if the document is markdown (untitled documents count)
+    pub fn is_markdown(&self) -> bool {
+        self.path
+            .as_deref()
+            .map_or(true, crate::utils::path::is_markdown)
+    }
+
+    /// Replace the text after an edit
+    ///
+    /// Bookmarks below the edited text move with their lines.
+    pub fn set_text(&mut self, text: &str) {
+        let new = ropey::Rope::from_str(text);
+        if !self.editor_state.bookmarks.is_empty() {
+            let old = self.content.to_string();
+            let (pos, removed, inserted) = crate::editor::snippets::locate_edit(&old, text, 0);
+            let line = self.content.char_to_line(pos);
+            let count_breaks = |slice: ropey::RopeSlice| slice.chars().filter(|&c| c == '\n').count();
+            self.editor_state.shift_bookmarks(
+                line,
+                self.content.line_to_char(line) == pos,
+                count_breaks(self.content.slice(pos..pos + removed)),
+                count_breaks(new.slice(pos..pos + inserted)),
+            );
+        }
+        self.content = new;
+    }
+
     /// Mark the document as modified
+    ///
+    /// A failed recovery snapshot stays shown until a snapshot or save
+    /// succeeds.
     pub fn mark_modified(&mut self) {
         self.modified = true;
+        if !matches!(self.save_status, SaveStatus::SnapshotFailed(_)) {
+            self.save_status = SaveStatus::Unsaved;
+        }
+    }
+
+    /// Get the markers to show in the editor gutter
+    ///
+    /// Changes since the last save take priority over git changes.
+    pub fn gutter_markers(&self) -> &[LineChange] {
+        if self.saved_changes.is_empty() {
+            &self.git_changes
+        } else {
+            &self.saved_changes
+        }
+    }
+
+    /// Lock or unlock the document for editing
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+        self.read_only_notified = false;
     }
 
     /// Mark the document as saved
+    ///
+    /// Set `last_disk_mtime` first, so it is shown as the save time.
     pub fn mark_saved(&mut self) {
         self.modified = false;
+        self.save_status = SaveStatus::Saved(self.last_disk_mtime);
+    }
+
+    /// Record the result of taking a recovery snapshot of the changes
+    pub fn snapshot_taken(&mut self, result: Result<(), String>) {
+        self.save_status = match result {
+            Ok(()) => SaveStatus::Snapshot(std::time::SystemTime::now()),
+            Err(error) => SaveStatus::SnapshotFailed(error),
+        };
+    }
+
+    /// Text of the heading whose section contains `line` (0-based)
+    ///
+    /// Looks for the nearest ATX heading at or above the line, skipping
+    /// fenced code blocks. Returns `None` above the first heading and for
+    /// documents that aren't markdown.
+    pub fn section_heading(&self, line: usize) -> Option<String> {
+        if !self.is_markdown() {
+            return None;
+        }
+        let mut heading = None;
+        let mut fence: Option<char> = None;
+        for text in self.content.lines().take(line + 1) {
+            let text = text.to_string();
+            let trimmed = text.trim();
+            if let Some(marker) = fence {
+                if trimmed.starts_with(&marker.to_string().repeat(3)) {
+                    fence = None;
+                }
+                continue;
+            }
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                fence = trimmed.chars().next();
+                continue;
+            }
+            if let Some((_, start)) = crate::markdown::MarkdownTokenizer::parse_heading(trimmed) {
+                let title = trimmed[start..].trim_end_matches('#').trim();
+                heading = (!title.is_empty()).then(|| title.to_string());
+            }
+        }
+        heading
     }
 
     /// Get content as string
@@ -190,17 +359,29 @@ pub struct AppState {
     /// Currently active document ID
     pub active_document: Option<DocumentId>,
 
-    /// Tab bar state
+    /// Open documents, most recently active first
+    pub recent_documents: Vec<DocumentId>,
+
+    /// Position in `recent_documents` while Ctrl+Tab is held
+    ///
+    /// The order is left alone while cycling, and updated once Ctrl is
+    /// released.
+    pub recent_cycle: Option<usize>,
+
+    /// Tab bar state (of the first pane when the editor is split)
     pub tabs: TabState,
 
+    /// Second editor pane, while the editor is split
+    pub split: Option<EditorSplit>,
+
     /// Sidebar state
     pub sidebar: SidebarState,
 
     /// Current view mode
     pub view_mode: ViewMode,
 
-    /// Whether command palette is open
-    pub command_palette_open: bool,
+    /// Command palette, while open
+    pub command_palette: Option<CommandPalette>,
 
     /// Whether find dialog is open
     pub find_dialog_open: bool,
@@ -223,6 +404,18 @@ pub struct AppState {
     /// Find options: use regex
     pub find_use_regex: bool,
 
+    /// Find options: only match inside the pinned selection
+    pub find_in_selection: bool,
+
+    /// Selection pinned when the find bar opened, as a character range
+    pub find_selection: Option<(DocumentId, std::ops::Range<usize>)>,
+
+    /// Cursor when the find bar opened, while the search is live
+    ///
+    /// Matches are previewed from here as the query is typed, and cancelling
+    /// the search returns here. Committing a match clears it.
+    pub find_origin: Option<(DocumentId, CursorPosition)>,
+
     /// Status bar message
     pub status_message: Option<StatusMessage>,
 
@@ -237,6 +430,84 @@ pub struct AppState {
 
     /// Whether global search is in progress
     pub global_search_in_progress: bool,
+
+    /// Preview base font size in pixels
+    pub preview_font_size: f32,
+
+    /// Preview font family (None = system default)
+    pub preview_font_family: Option<&'static str>,
+
+    /// Editor font size in pixels (before zoom)
+    pub editor_font_size: f32,
+
+    /// Zoom multiplier applied to editor, preview, and UI text
+    pub zoom_level: f32,
+
+    /// Changes panel diffing a document against its saved version
+    pub diff_view: Option<DiffView>,
+
+    /// Whether the problems panel is open
+    pub problems_panel_open: bool,
+
+    /// Clipboard history picker, while open
+    pub clipboard_picker: Option<ClipboardPicker>,
+
+    /// Replace All waiting for confirmation, while its dialog is open
+    pub replace_all_confirm: Option<ReplaceAllConfirm>,
+
+    /// Recently closed tabs, most recent last
+    pub closed_documents: Vec<ClosedDocument>,
+
+    /// Height of the editor area in pixels
+    pub editor_height: f32,
+
+    /// Columns to draw vertical rulers at in the editor
+    pub rulers: Vec<usize>,
+
+    /// Characters that surround the selection when typed, mapped to their
+    /// closing character
+    pub surround_pairs: HashMap<char, char>,
+
+    /// Which whitespace the editor draws markers for
+    pub render_whitespace: crate::config::RenderWhitespace,
+
+    /// Leave two-space markdown line breaks unmarked as trailing whitespace
+    pub preserve_md_hard_breaks: bool,
+
+    /// How the editor gutter numbers lines
+    pub line_numbers: crate::config::LineNumbers,
+
+    /// Whether the editor shows a minimap
+    pub minimap_enabled: bool,
+
+    /// Whether markdown documents are edited in live preview
+    pub live_preview: bool,
+
+    /// Whether the status bar shows where the active document's changes
+    /// are kept
+    pub show_save_status: bool,
+
+    /// Whether open files reload on external changes and can't be edited
+    pub watch_mode: bool,
+
+    /// Last pointer position over a minimap, below its top
+    pub minimap_pointer: f32,
+
+    /// Editor under a file dragged from the sidebar, with the pointer's
+    /// position in its text area
+    pub drop_target: Option<(DocumentId, cosmic::iced::Point)>,
+
+    /// Drag on a minimap in progress
+    pub minimap_drag: Option<crate::editor::minimap::MinimapDrag>,
+
+    /// Width of the window in pixels
+    pub window_width: u32,
+
+    /// Whether the system theme is dark
+    pub system_dark_mode: bool,
+
+    /// Whether the editor, preview, and exports use dark colors
+    pub dark_mode: bool,
 }
 
 impl AppState {
@@ -245,10 +516,13 @@ impl AppState {
         Self {
             documents: HashMap::new(),
             active_document: None,
+            recent_documents: Vec::new(),
+            recent_cycle: None,
             tabs: TabState::new(),
+            split: None,
             sidebar: SidebarState::new(),
             view_mode: ViewMode::Edit,
-            command_palette_open: false,
+            command_palette: None,
             find_dialog_open: false,
             find_replace_open: false,
             find_query: String::new(),
@@ -256,14 +530,130 @@ impl AppState {
             find_case_sensitive: false,
             find_whole_word: false,
             find_use_regex: false,
+            find_in_selection: false,
+            find_selection: None,
+            find_origin: None,
             status_message: None,
             quit_requested: false,
             pending_saves: Vec::new(),
             global_search_results: Vec::new(),
             global_search_in_progress: false,
+            preview_font_size: crate::config::DEFAULT_PREVIEW_FONT_SIZE,
+            preview_font_family: None,
+            editor_font_size: 14.0,
+            zoom_level: 1.0,
+            diff_view: None,
+            problems_panel_open: false,
+            clipboard_picker: None,
+            replace_all_confirm: None,
+            closed_documents: Vec::new(),
+            editor_height: crate::config::DEFAULT_WINDOW_HEIGHT as f32 - EDITOR_CHROME_HEIGHT,
+            window_width: crate::config::DEFAULT_WINDOW_WIDTH,
+            rulers: Vec::new(),
+            surround_pairs: HashMap::new(),
+            render_whitespace: crate::config::RenderWhitespace::None,
+            preserve_md_hard_breaks: true,
+            line_numbers: crate::config::LineNumbers::Absolute,
+            minimap_enabled: false,
+            live_preview: false,
+            show_save_status: true,
+            watch_mode: false,
+            minimap_pointer: 0.0,
+            drop_target: None,
+            minimap_drag: None,
+            system_dark_mode: false,
+            dark_mode: false,
         }
     }
 
+    /// Recompute dark mode from the theme preference and the system theme
+    ///
+    /// Returns whether dark mode changed.
+    pub fn update_dark_mode(&mut self, preference: ThemePreference) -> bool {
+        let dark_mode = preference.is_dark(self.system_dark_mode);
+        let changed = dark_mode != self.dark_mode;
+        self.dark_mode = dark_mode;
+        changed
+    }
+
+    /// Track the window size to size the editor viewport and sidebar
+    pub fn set_window_size(&mut self, width: u32, height: u32) {
+        self.window_width = width;
+        self.editor_height = (height as f32 - EDITOR_CHROME_HEIGHT).max(0.0);
+    }
+
+    /// Approximate width of one editor column at the current zoom
+    pub fn editor_char_width(&self) -> f32 {
+        self.scaled(self.editor_font_size) * crate::editor::widget::MONOSPACE_ADVANCE
+    }
+
+    /// Most rows a minimap as tall as the editor area can draw
+    pub fn minimap_rows(&self) -> usize {
+        ((self.editor_height / crate::editor::minimap::ROW_HEIGHT) as usize).max(1)
+    }
+
+    /// Height of one editor line at the current zoom
+    pub fn editor_line_height(&self) -> f32 {
+        self.scaled(self.editor_font_size) * LINE_HEIGHT_FACTOR
+    }
+
+    /// Number of editor lines visible at the current height and zoom
+    pub fn editor_viewport_lines(&self) -> usize {
+        let line_height = self.editor_line_height();
+        if line_height > 0.0 {
+            ((self.editor_height / line_height).floor() as usize).max(1)
+        } else {
+            1
+        }
+    }
+
+    /// Lines of a document in view in the editor, as far as its scroll
+    /// position is tracked, plus one partly shown at the bottom
+    pub fn editor_visible_lines(&self, doc: &Document) -> std::ops::Range<usize> {
+        let line_count = doc.line_count();
+        let first = doc.editor_state.scroll_line.min(line_count.saturating_sub(1));
+        first..(first + self.editor_viewport_lines() + 1).min(line_count)
+    }
+
+    /// Set the zoom level, clamped to the supported range
+    pub fn set_zoom_level(&mut self, level: f32) {
+        use crate::config::{MAX_ZOOM_LEVEL, MIN_ZOOM_LEVEL};
+
+        self.zoom_level = if level.is_finite() {
+            // Round to whole percents so repeated steps don't drift
+            ((level * 100.0).round() / 100.0).clamp(MIN_ZOOM_LEVEL, MAX_ZOOM_LEVEL)
+        } else {
+            1.0
+        };
+    }
+
+    /// Get the zoom level as a percentage
+    pub fn zoom_percent(&self) -> u32 {
+        (self.zoom_level * 100.0).round() as u32
+    }
+
+    /// Scale a text size by the current zoom level
+    pub fn scaled(&self, size: f32) -> f32 {
+        size * self.zoom_level
+    }
+
+    /// Set the preview font from the configured family and size
+    pub fn set_preview_font(&mut self, family: &str, size: f32) {
+        use crate::markdown::preview::{css_font_size, is_valid_font_family};
+
+        self.preview_font_size = css_font_size(size);
+        self.preview_font_family = if is_valid_font_family(family) {
+            Some(intern_font_family(family.trim()))
+        } else {
+            None
+        };
+    }
+
+    /// Folder open in the sidebar, which paths are shown relative to
+    pub fn workspace_root(&self) -> Option<&std::path::Path> {
+        self.sidebar.root.as_deref()
+    }
+
     /// Get the currently active document
     pub fn active_document(&self) -> Option<&Document> {
         self.active_document
@@ -276,36 +666,244 @@ impl AppState {
             .and_then(|id| self.documents.get_mut(&id))
     }
 
-    /// Add a new document and make it active
+    /// Add a new document to the focused pane and make it active
     pub fn add_document(&mut self, document: Document) -> DocumentId {
         let id = document.id;
-        self.tabs.add_tab(id, document.display_name.clone());
+        self.focused_tabs_mut().add_tab(id, document.display_name.clone());
         self.documents.insert(id, document);
         self.active_document = Some(id);
+        self.touch_recent(id);
         id
     }
 
     /// Close a document by ID
+    ///
+    /// Closing the active document activates the one active before it.
+    /// Closing the last tab of a pane returns to a single pane.
     pub fn close_document(&mut self, id: DocumentId) -> Option<Document> {
         let doc = self.documents.remove(&id);
-        self.tabs.remove_tab(id);
+        if let Some(pane) = self.pane_of(id) {
+            self.pane_tabs_mut(pane).remove_tab(id);
+        }
+        self.collapse_empty_pane();
+        self.recent_documents.retain(|&recent| recent != id);
+        self.recent_cycle = None;
 
         // Update active document if we closed the active one
         if self.active_document == Some(id) {
-            self.active_document = self.tabs.active_tab();
+            self.active_document = self.focused_tabs().active_tab();
+            if let Some(&recent) = self.recent_documents.first() {
+                self.set_active_document(recent);
+            }
         }
 
         doc
     }
 
-    /// Set the active document
+    /// Set the active document, focusing the pane that shows it
     pub fn set_active_document(&mut self, id: DocumentId) {
         if self.documents.contains_key(&id) {
+            if let Some(pane) = self.pane_of(id) {
+                if let Some(split) = self.split.as_mut() {
+                    split.focused = pane;
+                }
+                self.pane_tabs_mut(pane).set_active(id);
+            }
             self.active_document = Some(id);
-            self.tabs.set_active(id);
+            self.touch_recent(id);
         }
     }
 
+    /// Make the focused pane's active tab the active document
+    ///
+    /// Call after changing the active tab through `focused_tabs_mut`.
+    pub fn sync_active_document(&mut self) {
+        if let Some(id) = self.focused_tabs().active_tab() {
+            self.active_document = Some(id);
+            self.touch_recent(id);
+        }
+    }
+
+    /// Move a document to the front of the recently active order
+    ///
+    /// Does nothing while Ctrl+Tab cycles through the order.
+    fn touch_recent(&mut self, id: DocumentId) {
+        if self.recent_cycle.is_some() {
+            return;
+        }
+        self.recent_documents.retain(|&recent| recent != id);
+        self.recent_documents.insert(0, id);
+    }
+
+    /// Activate the next (or previous) document in the recently active
+    /// order, starting from the active document
+    ///
+    /// Repeated calls keep stepping through the order until
+    /// `finish_recent_cycle`.
+    pub fn cycle_recent(&mut self, forward: bool) {
+        let len = self.recent_documents.len();
+        if len < 2 {
+            return;
+        }
+        let current = self.recent_cycle.unwrap_or(0);
+        let next = if forward { (current + 1) % len } else { (current + len - 1) % len };
+        self.recent_cycle = Some(next);
+        let id = self.recent_documents[next];
+        self.set_active_document(id);
+    }
+
+    /// End a Ctrl+Tab cycle, making its document the most recent
+    pub fn finish_recent_cycle(&mut self) {
+        if self.recent_cycle.take().is_some() {
+            if let Some(id) = self.active_document {
+                self.touch_recent(id);
+            }
+        }
+    }
+
+    /// Remember a closed tab, forgetting the oldest beyond the limit
+    pub fn remember_closed(&mut self, closed: ClosedDocument) {
+        self.closed_documents.push(closed);
+        let excess = self.closed_documents.len().saturating_sub(MAX_CLOSED_DOCUMENTS);
+        self.closed_documents.drain(..excess);
+    }
+
+    /// Take the most recently closed tab
+    pub fn pop_closed(&mut self) -> Option<ClosedDocument> {
+        self.closed_documents.pop()
+    }
+
+    /// Pane with keyboard focus
+    pub fn focused_pane(&self) -> Pane {
+        self.split.as_ref().map_or(Pane::First, |split| split.focused)
+    }
+
+    /// Tabs of a pane (the first pane's when the editor is not split)
+    pub fn pane_tabs(&self, pane: Pane) -> &TabState {
+        match (&self.split, pane) {
+            (Some(split), Pane::Second) => &split.tabs,
+            _ => &self.tabs,
+        }
+    }
+
+    fn pane_tabs_mut(&mut self, pane: Pane) -> &mut TabState {
+        match (&mut self.split, pane) {
+            (Some(split), Pane::Second) => &mut split.tabs,
+            _ => &mut self.tabs,
+        }
+    }
+
+    /// Tabs of the focused pane
+    pub fn focused_tabs(&self) -> &TabState {
+        self.pane_tabs(self.focused_pane())
+    }
+
+    /// Tabs of the focused pane, mutably
+    pub fn focused_tabs_mut(&mut self) -> &mut TabState {
+        self.pane_tabs_mut(self.focused_pane())
+    }
+
+    /// Pane whose tabs include a document
+    pub fn pane_of(&self, id: DocumentId) -> Option<Pane> {
+        if self.tabs.find_tab_index(id).is_some() {
+            Some(Pane::First)
+        } else if self
+            .split
+            .as_ref()
+            .is_some_and(|split| split.tabs.find_tab_index(id).is_some())
+        {
+            Some(Pane::Second)
+        } else {
+            None
+        }
+    }
+
+    /// All tabs, the first pane's followed by the second's
+    pub fn all_tabs(&self) -> impl Iterator<Item = &Tab> {
+        self.tabs
+            .iter()
+            .chain(self.split.iter().flat_map(|split| split.tabs.iter()))
+    }
+
+    /// Update a document's tab title in whichever pane shows it
+    pub fn update_tab_title(&mut self, id: DocumentId, title: String) {
+        if let Some(pane) = self.pane_of(id) {
+            self.pane_tabs_mut(pane).update_title(id, title);
+        }
+    }
+
+    /// Split the editor, moving the active document into a new pane
+    ///
+    /// An already split editor only changes orientation. Returns false when
+    /// there aren't two tabs to show.
+    pub fn split_editor(&mut self, orientation: SplitOrientation) -> bool {
+        if let Some(split) = self.split.as_mut() {
+            split.orientation = orientation;
+            return true;
+        }
+        if self.tabs.count() < 2 {
+            return false;
+        }
+        self.split = Some(EditorSplit::new(orientation));
+        self.move_to_pane(Pane::First, Pane::Second)
+    }
+
+    /// Move the active document to the other pane and focus it
+    ///
+    /// Splits the editor vertically if it isn't split yet. Moving the last
+    /// tab out of a pane returns to a single pane.
+    pub fn move_to_other_pane(&mut self) -> bool {
+        if self.split.is_none() {
+            return self.split_editor(SplitOrientation::Vertical);
+        }
+        let from = self.focused_pane();
+        self.move_to_pane(from, from.other())
+    }
+
+    fn move_to_pane(&mut self, from: Pane, to: Pane) -> bool {
+        let Some(id) = self.active_document else {
+            return false;
+        };
+        let Some(tab) = self.pane_tabs_mut(from).take_tab(id) else {
+            return false;
+        };
+        self.pane_tabs_mut(to).insert_tab(tab);
+        if let Some(split) = self.split.as_mut() {
+            split.focused = to;
+        }
+        self.collapse_empty_pane();
+        self.set_active_document(id);
+        true
+    }
+
+    /// Return to a single pane, keeping the tabs of both
+    pub fn unsplit_editor(&mut self) {
+        if let Some(split) = self.split.take() {
+            for tab in split.tabs.tabs {
+                self.tabs.tabs.push(tab);
+            }
+            if let Some(id) = self.active_document {
+                self.tabs.set_active(id);
+            }
+        }
+    }
+
+    /// Return to a single pane once either pane has no tabs left
+    fn collapse_empty_pane(&mut self) {
+        let Some(split) = &self.split else {
+            return;
+        };
+        if !split.tabs.is_empty() && !self.tabs.is_empty() {
+            return;
+        }
+        if let Some(split) = self.split.take() {
+            if self.tabs.is_empty() {
+                self.tabs = split.tabs;
+            }
+        }
+        self.active_document = self.tabs.active_tab();
+    }
+
     /// Get a document by ID
     pub fn get_document(&self, id: DocumentId) -> Option<&Document> {
         self.documents.get(&id)
@@ -351,13 +949,28 @@ impl AppState {
         !self.documents.is_empty()
     }
 
-    /// Set a status message
+    /// Set a status message, replacing any current one
     pub fn set_status(&mut self, message: impl Into<String>, level: StatusLevel) {
-        self.status_message = Some(StatusMessage {
-            text: message.into(),
-            level,
-            timestamp: std::time::Instant::now(),
-        });
+        self.status_message = Some(StatusMessage::new(message, level));
+    }
+
+    /// Set a status message with details that can be expanded
+    pub fn set_status_with_details(
+        &mut self,
+        message: impl Into<String>,
+        level: StatusLevel,
+        details: impl Into<String>,
+    ) {
+        let mut status = StatusMessage::new(message, level);
+        status.details = Some(details.into());
+        self.status_message = Some(status);
+    }
+
+    /// Show or hide the details of the status message
+    pub fn toggle_status_details(&mut self) {
+        if let Some(status) = self.status_message.as_mut() {
+            status.expanded = !status.expanded && status.details.is_some();
+        }
     }
 
     /// Clear the status message
@@ -370,18 +983,37 @@ impl AppState {
         self.sidebar.visible = !self.sidebar.visible;
     }
 
+    /// Get the view mode of the active document
+    ///
+    /// A document's own view mode overrides the global one, and documents
+    /// that aren't markdown are always shown in edit mode.
+    pub fn effective_view_mode(&self) -> ViewMode {
+        match self.active_document() {
+            Some(doc) if !doc.is_markdown() => ViewMode::Edit,
+            Some(doc) => doc.view_mode.unwrap_or(self.view_mode),
+            None => self.view_mode,
+        }
+    }
+
     /// Toggle view mode
     pub fn cycle_view_mode(&mut self) {
-        self.view_mode = match self.view_mode {
+        let mode = match self.effective_view_mode() {
             ViewMode::Edit => ViewMode::Preview,
             ViewMode::Preview => ViewMode::Split,
             ViewMode::Split => ViewMode::Edit,
         };
+        self.set_view_mode(mode);
     }
 
     /// Set view mode
+    ///
+    /// Changes the active document's own view mode if it has one,
+    /// otherwise the global view mode.
     pub fn set_view_mode(&mut self, mode: ViewMode) {
-        self.view_mode = mode;
+        match self.active_document_mut() {
+            Some(doc) if doc.view_mode.is_some() => doc.view_mode = Some(mode),
+            _ => self.view_mode = mode,
+        }
     }
 }
 
@@ -391,6 +1023,71 @@ impl Default for AppState {
     }
 }
 
+/// Diff of a document against its saved version
+#[derive(Debug, Clone)]
+pub struct DiffView {
+    /// Document being diffed
+    pub document_id: DocumentId,
+
+    /// Diff lines from saved to current content
+    pub lines: Vec<DiffLine>,
+
+    /// Whether the document has no saved version
+    pub all_new: bool,
+}
+
+/// Most closed tabs remembered for reopening
+pub const MAX_CLOSED_DOCUMENTS: usize = 20;
+
+/// A closed tab that can be reopened
+#[derive(Debug, Clone)]
+pub struct ClosedDocument {
+    /// File the tab showed (None for scratch documents)
+    pub path: Option<PathBuf>,
+
+    /// Tab title
+    pub display_name: String,
+
+    /// Unsaved content to restore; None re-reads the file
+    pub unsaved_content: Option<String>,
+
+    /// Cursor position when the tab was closed
+    pub cursor: CursorPosition,
+}
+
+/// A Replace All asked to be confirmed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplaceAllConfirm {
+    /// Document the replacement runs in
+    pub document_id: DocumentId,
+
+    /// Number of matches to replace
+    pub count: usize,
+
+    /// Document length in bytes before and after, when a regex
+    /// replacement changes it a lot
+    pub size_change: Option<(usize, usize)>,
+}
+
+/// Clipboard history shown for Paste from History
+#[derive(Debug, Clone)]
+pub struct ClipboardPicker {
+    /// History entries when the picker opened, most recent first
+    pub entries: Vec<String>,
+
+    /// Entry pasted by Paste while the picker is open
+    pub selected: usize,
+}
+
+impl ClipboardPicker {
+    /// Highlight the next older entry, wrapping back to the latest
+    pub fn select_next(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = (self.selected + 1) % self.entries.len();
+        }
+    }
+}
+
 /// Status message for the status bar
 #[derive(Debug, Clone)]
 pub struct StatusMessage {
@@ -401,7 +1098,37 @@ pub struct StatusMessage {
     pub level: StatusLevel,
 
     /// When the message was set
-    pub timestamp: std::time::Instant,
+    pub created_at: std::time::Instant,
+
+    /// Longer explanation shown when the message is expanded
+    pub details: Option<String>,
+
+    /// Whether the details are shown
+    pub expanded: bool,
+}
+
+impl StatusMessage {
+    /// Create a status message stamped with the current time
+    pub fn new(text: impl Into<String>, level: StatusLevel) -> Self {
+        Self {
+            text: text.into(),
+            level,
+            created_at: std::time::Instant::now(),
+            details: None,
+            expanded: false,
+        }
+    }
+
+    /// Time left before the message is dismissed
+    ///
+    /// Returns `None` if the message stays until dismissed: when the
+    /// timeout is zero or the details are expanded.
+    pub fn remaining(&self, timeout: std::time::Duration) -> Option<std::time::Duration> {
+        if timeout.is_zero() || self.expanded {
+            return None;
+        }
+        Some(timeout.saturating_sub(self.created_at.elapsed()))
+    }
 }
 
 /// Status message level
@@ -431,10 +1158,55 @@ pub struct SearchResult {
     pub context: String,
 }
 
+/// Get a `'static` copy of a font family name, as iced needs
+///
+/// Each name is leaked once and reused, so switching fonts back and forth
+/// doesn't leak more.
+fn intern_font_family(family: &str) -> &'static str {
+    use std::collections::HashSet;
+    use std::sync::{Mutex, OnceLock, PoisonError};
+
+    static FAMILIES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+    let mut families = FAMILIES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(interned) = families.get(family) {
+        return *interned;
+    }
+    let interned: &'static str = Box::leak(family.to_string().into_boxed_str());
+    families.insert(interned);
+    interned
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_intern_font_family() {
+        let first = intern_font_family("Noto Serif");
+        assert_eq!(first, "Noto Serif");
+        assert!(std::ptr::eq(first, intern_font_family("Noto Serif")));
+        assert!(!std::ptr::eq(first, intern_font_family("Noto Sans")));
+    }
+
+    #[test]
+    fn test_section_heading() {
+        let mut doc = Document::new();
+        doc.content = ropey::Rope::from_str(
+            "intro\n# Title\ntext\n```\n# not a heading\n```\n## Part two ##\nmore",
+        );
+        assert_eq!(doc.section_heading(0), None);
+        assert_eq!(doc.section_heading(1).as_deref(), Some("Title"));
+        assert_eq!(doc.section_heading(4).as_deref(), Some("Title"));
+        assert_eq!(doc.section_heading(7).as_deref(), Some("Part two"));
+
+        doc.path = Some(PathBuf::from("notes.txt"));
+        assert_eq!(doc.section_heading(7), None);
+    }
+
     #[test]
     fn test_document_new() {
         let doc = Document::new();
@@ -443,6 +1215,29 @@ mod tests {
         assert_eq!(doc.display_name, "Untitled");
     }
 
+    #[test]
+    fn test_save_status() {
+        let mut doc = Document::new();
+        assert_eq!(doc.save_status, SaveStatus::Saved(None));
+
+        doc.mark_modified();
+        assert_eq!(doc.save_status, SaveStatus::Unsaved);
+        doc.snapshot_taken(Ok(()));
+        assert!(matches!(doc.save_status, SaveStatus::Snapshot(_)));
+        doc.mark_modified();
+        assert_eq!(doc.save_status, SaveStatus::Unsaved);
+
+        // A failed snapshot stays shown while editing continues
+        doc.snapshot_taken(Err("disk full".to_string()));
+        doc.mark_modified();
+        assert_eq!(doc.save_status, SaveStatus::SnapshotFailed("disk full".to_string()));
+
+        let time = std::time::SystemTime::UNIX_EPOCH;
+        doc.last_disk_mtime = Some(time);
+        doc.mark_saved();
+        assert_eq!(doc.save_status, SaveStatus::Saved(Some(time)));
+    }
+
     #[test]
     fn test_document_from_file() {
         let path = PathBuf::from("/test/file.md");
@@ -454,6 +1249,20 @@ mod tests {
         assert!(!doc.modified);
     }
 
+    #[test]
+    fn test_set_text_moves_bookmarks() {
+        let mut doc = Document::new();
+        doc.set_text("one\ntwo\nthree\n");
+        doc.editor_state.bookmarks = [0, 2].into_iter().collect();
+
+        doc.set_text("one\nnew\ntwo\nthree\n");
+        assert_eq!(doc.editor_state.bookmarks, [0, 3].into_iter().collect());
+
+        doc.set_text("one\nthree\n");
+        assert_eq!(doc.editor_state.bookmarks, [0, 1].into_iter().collect());
+        assert_eq!(doc.content.to_string(), "one\nthree\n");
+    }
+
     #[test]
     fn test_document_title() {
         let mut doc = Document::new();
@@ -496,4 +1305,188 @@ mod tests {
         assert!(state.has_unsaved_changes());
         assert_eq!(state.unsaved_documents().len(), 1);
     }
+
+    #[test]
+    fn test_zoom_level_clamped() {
+        let mut state = AppState::new();
+        assert_eq!(state.zoom_percent(), 100);
+
+        state.set_zoom_level(1.1 + 0.1);
+        assert_eq!(state.zoom_percent(), 120);
+
+        state.set_zoom_level(10.0);
+        assert_eq!(state.zoom_level, crate::config::MAX_ZOOM_LEVEL);
+
+        state.set_zoom_level(0.0);
+        assert_eq!(state.zoom_level, crate::config::MIN_ZOOM_LEVEL);
+
+        state.set_zoom_level(f32::NAN);
+        assert_eq!(state.zoom_level, 1.0);
+    }
+
+    #[test]
+    fn test_document_view_mode_override() {
+        let mut state = AppState::new();
+        state.set_view_mode(ViewMode::Preview);
+
+        let mut doc = Document::from_file(PathBuf::from("/test/notes.md"), String::new());
+        doc.view_mode = Some(ViewMode::Split);
+        state.add_document(doc);
+        assert_eq!(state.effective_view_mode(), ViewMode::Split);
+
+        state.cycle_view_mode();
+        assert_eq!(state.effective_view_mode(), ViewMode::Edit);
+        assert_eq!(state.view_mode, ViewMode::Preview);
+
+        state.add_document(Document::from_file(PathBuf::from("/test/todo.txt"), String::new()));
+        assert_eq!(state.effective_view_mode(), ViewMode::Edit);
+
+        state.add_document(Document::new());
+        assert_eq!(state.effective_view_mode(), ViewMode::Preview);
+    }
+
+    #[test]
+    fn test_status_message_replaced() {
+        let mut state = AppState::new();
+        state.set_status_with_details("Failed to save", StatusLevel::Error, "Permission denied");
+        let first = state.status_message.as_ref().unwrap().created_at;
+
+        state.toggle_status_details();
+        let status = state.status_message.as_ref().unwrap();
+        assert!(status.expanded);
+        assert_eq!(status.remaining(std::time::Duration::from_secs(10)), None);
+
+        state.set_status("Saved", StatusLevel::Info);
+        let status = state.status_message.as_ref().unwrap();
+        assert_eq!(status.text, "Saved");
+        assert!(!status.expanded);
+        assert!(status.created_at >= first);
+        assert_eq!(status.remaining(std::time::Duration::ZERO), None);
+        assert!(status.remaining(std::time::Duration::from_secs(4)).is_some());
+
+        state.toggle_status_details();
+        assert!(!state.status_message.as_ref().unwrap().expanded);
+    }
+
+    #[test]
+    fn test_clipboard_picker_cycles() {
+        let mut picker = ClipboardPicker {
+            entries: vec!["new".to_string(), "old".to_string()],
+            selected: 1,
+        };
+        picker.select_next();
+        assert_eq!(picker.selected, 0);
+        picker.select_next();
+        assert_eq!(picker.selected, 1);
+    }
+
+    #[test]
+    fn test_split_editor_panes() {
+        let mut state = AppState::new();
+        let first = state.add_document(Document::new());
+        assert!(!state.split_editor(SplitOrientation::Vertical));
+
+        let second = state.add_document(Document::new());
+        assert!(state.split_editor(SplitOrientation::Vertical));
+        assert_eq!(state.focused_pane(), Pane::Second);
+        assert_eq!(state.pane_of(second), Some(Pane::Second));
+        assert_eq!(state.pane_tabs(Pane::First).active_tab(), Some(first));
+
+        // Selecting a document focuses its pane
+        state.set_active_document(first);
+        assert_eq!(state.focused_pane(), Pane::First);
+
+        // New documents open in the focused pane
+        let third = state.add_document(Document::new());
+        assert_eq!(state.pane_of(third), Some(Pane::First));
+        assert_eq!(state.all_tabs().count(), 3);
+
+        // Moving a tab follows it to the other pane
+        assert!(state.move_to_other_pane());
+        assert_eq!(state.pane_of(third), Some(Pane::Second));
+        assert_eq!(state.focused_pane(), Pane::Second);
+        assert_eq!(state.active_document, Some(third));
+    }
+
+    #[test]
+    fn test_split_collapses_when_pane_empties() {
+        let mut state = AppState::new();
+        let first = state.add_document(Document::new());
+        let second = state.add_document(Document::new());
+        state.split_editor(SplitOrientation::Horizontal);
+
+        state.close_document(second);
+        assert!(state.split.is_none());
+        assert_eq!(state.focused_pane(), Pane::First);
+        assert_eq!(state.active_document, Some(first));
+
+        // Emptying the first pane keeps the second pane's tabs
+        let second = state.add_document(Document::new());
+        state.split_editor(SplitOrientation::Vertical);
+        state.close_document(first);
+        assert!(state.split.is_none());
+        assert_eq!(state.tabs.document_ids(), vec![second]);
+        assert_eq!(state.active_document, Some(second));
+    }
+
+    #[test]
+    fn test_recent_documents_cycle() {
+        let mut state = AppState::new();
+        let first = state.add_document(Document::new());
+        let second = state.add_document(Document::new());
+        let third = state.add_document(Document::new());
+        assert_eq!(state.recent_documents, vec![third, second, first]);
+
+        // A single press switches to the previous document
+        state.cycle_recent(true);
+        state.finish_recent_cycle();
+        assert_eq!(state.active_document, Some(second));
+        assert_eq!(state.recent_documents, vec![second, third, first]);
+
+        // Holding Ctrl keeps stepping without reordering
+        state.cycle_recent(true);
+        state.cycle_recent(true);
+        assert_eq!(state.active_document, Some(first));
+        assert_eq!(state.recent_documents, vec![second, third, first]);
+        state.finish_recent_cycle();
+        assert_eq!(state.recent_documents, vec![first, second, third]);
+
+        state.cycle_recent(false);
+        state.finish_recent_cycle();
+        assert_eq!(state.active_document, Some(third));
+    }
+
+    #[test]
+    fn test_close_activates_previous_recent() {
+        let mut state = AppState::new();
+        let first = state.add_document(Document::new());
+        let second = state.add_document(Document::new());
+        let third = state.add_document(Document::new());
+        state.set_active_document(first);
+
+        // The adjacent tab is second, but third was active before first
+        state.close_document(first);
+        assert_eq!(state.active_document, Some(third));
+        assert_eq!(state.tabs.active_tab(), Some(third));
+        assert_eq!(state.recent_documents, vec![third, second]);
+        assert!(state.documents.contains_key(&second));
+    }
+
+    #[test]
+    fn test_closed_documents_bounded() {
+        let mut state = AppState::new();
+        for i in 0..MAX_CLOSED_DOCUMENTS + 2 {
+            state.remember_closed(ClosedDocument {
+                path: None,
+                display_name: format!("{}", i),
+                unsaved_content: Some(String::new()),
+                cursor: CursorPosition::default(),
+            });
+        }
+        assert_eq!(state.closed_documents.len(), MAX_CLOSED_DOCUMENTS);
+        assert_eq!(state.closed_documents[0].display_name, "2");
+
+        let last = state.pop_closed().unwrap();
+        assert_eq!(last.display_name, format!("{}", MAX_CLOSED_DOCUMENTS + 1));
+    }
 }
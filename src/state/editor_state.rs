@@ -5,6 +5,7 @@
 
 use crate::config::MAX_UNDO_HISTORY;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 /// Cursor position in the document
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -27,6 +28,13 @@ impl CursorPosition {
         Self::default()
     }
 
+    /// Create a cursor at a character offset into the document
+    pub fn from_char_offset(rope: &ropey::Rope, offset: usize) -> Self {
+        let offset = offset.min(rope.len_chars());
+        let line = rope.char_to_line(offset);
+        Self::new(line, offset - rope.line_to_char(line))
+    }
+
     /// Get 1-indexed line number for display
     pub fn display_line(&self) -> usize {
         self.line + 1
@@ -266,7 +274,10 @@ pub struct EditorState {
     /// (remembers column when moving through shorter lines)
     pub preferred_column: Option<usize>,
 
-    /// Find results for this document (character offsets)
+    /// Find results for this document
+    ///
+    /// Ranges are character offsets into the document rope, as returned by
+    /// `SearchEngine::find_all`.
     pub find_results: Vec<(usize, usize)>,
 
     /// Current find result index
@@ -274,6 +285,15 @@ pub struct EditorState {
 
     /// Whether the editor has focus
     pub has_focus: bool,
+
+    /// Tab-stops of the snippet being filled in
+    pub snippet: Option<crate::editor::snippets::SnippetSession>,
+
+    /// Language of the fenced code block around the cursor (empty for plain text)
+    pub code_block_language: Option<String>,
+
+    /// Bookmarked lines (0-indexed)
+    pub bookmarks: BTreeSet<usize>,
 }
 
 impl EditorState {
@@ -291,6 +311,9 @@ impl EditorState {
             find_results: Vec::new(),
             current_find_index: None,
             has_focus: false,
+            snippet: None,
+            code_block_language: None,
+            bookmarks: BTreeSet::new(),
         }
     }
 
@@ -315,6 +338,83 @@ impl EditorState {
         self.preferred_column = None;
     }
 
+    /// Scroll the viewport by `lines`, keeping its top line in the document
+    pub fn scroll_by(&mut self, lines: i32, line_count: usize) {
+        let top = self.scroll_line as i64 + lines as i64;
+        self.scroll_line = top.clamp(0, line_count.saturating_sub(1) as i64) as usize;
+    }
+
+    /// Scroll the viewport only as far as needed to show the cursor line
+    pub fn follow_cursor(&mut self, viewport_lines: usize) {
+        let line = self.cursor.line;
+        if line < self.scroll_line {
+            self.scroll_line = line;
+        } else if line >= self.scroll_line + viewport_lines.max(1) {
+            self.scroll_line = line + 1 - viewport_lines.max(1);
+        }
+    }
+
+    /// Bookmark a line, or remove its bookmark
+    ///
+    /// Returns whether the line is bookmarked now.
+    pub fn toggle_bookmark(&mut self, line: usize) -> bool {
+        if self.bookmarks.remove(&line) {
+            false
+        } else {
+            self.bookmarks.insert(line);
+            true
+        }
+    }
+
+    /// First bookmark below `line`, wrapping around to the first one
+    pub fn next_bookmark(&self, line: usize) -> Option<usize> {
+        self.bookmarks
+            .range(line + 1..)
+            .next()
+            .or_else(|| self.bookmarks.first())
+            .copied()
+    }
+
+    /// Last bookmark above `line`, wrapping around to the last one
+    pub fn prev_bookmark(&self, line: usize) -> Option<usize> {
+        self.bookmarks
+            .range(..line)
+            .next_back()
+            .or_else(|| self.bookmarks.last())
+            .copied()
+    }
+
+    /// Move bookmarks with their lines after an edit
+    ///
+    /// The edit starts on `line`, at its first column when `at_line_start`,
+    /// and replaced `removed_lines` line breaks with `inserted_lines`.
+    /// Bookmarks on removed lines move to the line the edit starts on.
+    pub fn shift_bookmarks(
+        &mut self,
+        line: usize,
+        at_line_start: bool,
+        removed_lines: usize,
+        inserted_lines: usize,
+    ) {
+        // Lines inserted before a line's text push it down
+        let pushed = at_line_start && removed_lines == 0;
+        self.bookmarks = self
+            .bookmarks
+            .iter()
+            .map(|&bookmark| {
+                if bookmark < line || (bookmark == line && !pushed) {
+                    bookmark
+                } else if bookmark == line {
+                    bookmark + inserted_lines
+                } else if bookmark <= line + removed_lines {
+                    line
+                } else {
+                    bookmark - removed_lines + inserted_lines
+                }
+            })
+            .collect();
+    }
+
     /// Select all (requires document length info)
     pub fn select_all(&mut self, end_position: CursorPosition) {
         self.selection = Selection::new(CursorPosition::start(), end_position);
@@ -517,4 +617,81 @@ mod tests {
         assert_eq!(result, Some((0, 5)));
         assert_eq!(state.current_find_number(), Some(1));
     }
+
+    #[test]
+    fn test_find_next_multibyte() {
+        use crate::search::{FindOptions, SearchEngine};
+
+        let text = "Résumé 🎉 notes\n😀 naïve résumé";
+        let rope = ropey::Rope::from_str(text);
+        let mut state = EditorState::new();
+        state.find_results = SearchEngine::new()
+            .find_all(text, "résumé", &FindOptions::default())
+            .iter()
+            .map(|r| (r.start, r.end))
+            .collect();
+
+        state.next_find_result();
+        let (start, end) = state.next_find_result().unwrap();
+        assert_eq!(rope.slice(start..end).to_string(), "résumé");
+        assert_eq!(CursorPosition::from_char_offset(&rope, start), CursorPosition::new(1, 8));
+    }
+
+    #[test]
+    fn test_bookmark_navigation() {
+        let mut state = EditorState::new();
+        assert_eq!(state.next_bookmark(0), None);
+
+        assert!(state.toggle_bookmark(3));
+        assert!(state.toggle_bookmark(10));
+        assert_eq!(state.next_bookmark(3), Some(10));
+        assert_eq!(state.next_bookmark(10), Some(3));
+        assert_eq!(state.prev_bookmark(10), Some(3));
+        assert_eq!(state.prev_bookmark(2), Some(10));
+
+        assert!(!state.toggle_bookmark(3));
+        assert_eq!(state.next_bookmark(10), Some(10));
+    }
+
+    #[test]
+    fn test_bookmarks_follow_edits() {
+        let mut state = EditorState::new();
+        state.bookmarks = [2, 5, 8].into_iter().collect();
+
+        // Two lines typed at the end of line 3
+        state.shift_bookmarks(3, false, 0, 2);
+        assert_eq!(state.bookmarks, [2, 7, 10].into_iter().collect());
+
+        // A line break typed at the start of a bookmarked line
+        state.shift_bookmarks(7, true, 0, 1);
+        assert_eq!(state.bookmarks, [2, 8, 10].into_iter().collect());
+
+        // Lines 4 to 9 deleted from the end of line 3
+        state.shift_bookmarks(3, false, 6, 0);
+        assert_eq!(state.bookmarks, [2, 3, 4].into_iter().collect());
+
+        // Edits below bookmarks leave them alone
+        state.shift_bookmarks(20, false, 3, 1);
+        assert_eq!(state.bookmarks, [2, 3, 4].into_iter().collect());
+    }
+
+    #[test]
+    fn test_scroll_tracking() {
+        let mut state = EditorState::new();
+        state.scroll_by(-3, 100);
+        assert_eq!(state.scroll_line, 0);
+        state.scroll_by(120, 100);
+        assert_eq!(state.scroll_line, 99);
+
+        state.scroll_line = 10;
+        state.set_cursor(CursorPosition::new(25, 0));
+        state.follow_cursor(10);
+        assert_eq!(state.scroll_line, 16);
+        state.set_cursor(CursorPosition::new(20, 0));
+        state.follow_cursor(10);
+        assert_eq!(state.scroll_line, 16);
+        state.set_cursor(CursorPosition::new(3, 0));
+        state.follow_cursor(10);
+        assert_eq!(state.scroll_line, 3);
+    }
 }
@@ -0,0 +1,147 @@
+//! Command palette state
+//!
+//! The palette lists every menu command. Typing filters the list with a
+//! fuzzy match; commands that can't run right now stay listed but greyed
+//! out below the ones that can.
+
+use crate::menu::Action;
+use crate::utils::text::fuzzy_score;
+
+/// A command listed in the palette
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    /// Name shown in the list
+    pub label: String,
+
+    /// Keyboard shortcut, if the command has one
+    pub shortcut: Option<String>,
+
+    /// Action run when the entry is chosen
+    pub action: Action,
+
+    /// Whether the command can run now
+    pub enabled: bool,
+}
+
+/// Command palette, while open
+#[derive(Debug, Clone)]
+pub struct CommandPalette {
+    /// Filter text
+    pub query: String,
+
+    /// Every command, recently used first
+    entries: Vec<PaletteEntry>,
+
+    /// Indices of the entries matching the query, best first
+    matches: Vec<usize>,
+
+    /// Position in `matches` of the highlighted entry
+    pub selected: usize,
+}
+
+impl CommandPalette {
+    /// Open the palette on these commands, recently used first
+    pub fn new(entries: Vec<PaletteEntry>) -> Self {
+        let mut palette = Self {
+            query: String::new(),
+            entries,
+            matches: Vec::new(),
+            selected: 0,
+        };
+        palette.set_query(String::new());
+        palette
+    }
+
+    /// Filter the commands, highlighting the best match
+    ///
+    /// Enabled commands come first, then by match score; equal scores keep
+    /// the recently used order.
+    pub fn set_query(&mut self, query: String) {
+        let mut scored: Vec<(usize, u32)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| Some((i, fuzzy_score(&query, &entry.label)?)))
+            .collect();
+        scored.sort_by_key(|&(i, score)| (!self.entries[i].enabled, std::cmp::Reverse(score)));
+        self.matches = scored.into_iter().map(|(i, _)| i).collect();
+        self.query = query;
+        self.selected = 0;
+    }
+
+    /// Commands matching the query, best first
+    pub fn matches(&self) -> impl Iterator<Item = &PaletteEntry> {
+        self.matches.iter().map(|&i| &self.entries[i])
+    }
+
+    /// Move the highlight by `delta` entries, wrapping around
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = self.matches.len() as isize;
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    /// The highlighted command, if it can run
+    pub fn selected_action(&self) -> Option<Action> {
+        let entry = &self.entries[*self.matches.get(self.selected)?];
+        entry.enabled.then_some(entry.action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(label: &str, action: Action, enabled: bool) -> PaletteEntry {
+        PaletteEntry {
+            label: label.to_string(),
+            shortcut: None,
+            action,
+            enabled,
+        }
+    }
+
+    fn palette() -> CommandPalette {
+        CommandPalette::new(vec![
+            entry("Zoom In", Action::ZoomIn, true),
+            entry("Save", Action::Save, false),
+            entry("Save All", Action::SaveAll, true),
+            entry("Toggle Sidebar", Action::ToggleSidebar, true),
+        ])
+    }
+
+    fn labels(palette: &CommandPalette) -> Vec<&str> {
+        palette.matches().map(|e| e.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_palette_order() {
+        let palette = palette();
+        // Without a query the given order stays, disabled commands last
+        assert_eq!(labels(&palette), vec!["Zoom In", "Save All", "Toggle Sidebar", "Save"]);
+        assert_eq!(palette.selected_action(), Some(Action::ZoomIn));
+    }
+
+    #[test]
+    fn test_palette_filter() {
+        let mut palette = palette();
+        palette.set_query("sa".to_string());
+        assert_eq!(labels(&palette), vec!["Save All", "Toggle Sidebar", "Save"]);
+
+        palette.set_query("nothing".to_string());
+        assert_eq!(labels(&palette), Vec::<&str>::new());
+        assert_eq!(palette.selected_action(), None);
+    }
+
+    #[test]
+    fn test_palette_selection() {
+        let mut palette = palette();
+        palette.move_selection(-1);
+        // Disabled commands can be highlighted but not run
+        assert_eq!(palette.selected, 3);
+        assert_eq!(palette.selected_action(), None);
+        palette.move_selection(1);
+        assert_eq!(palette.selected_action(), Some(Action::ZoomIn));
+    }
+}
@@ -0,0 +1,60 @@
+//! Editor pane state
+//!
+//! The editor area shows one pane of tabs, or two side by side (or one
+//! above the other) when split. Each pane has its own tabs and active tab;
+//! the focused pane is the one keyboard input and menu commands act on.
+
+use super::TabState;
+
+/// One of the two editor panes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pane {
+    /// The main pane, shown alone when the editor is not split
+    #[default]
+    First,
+    /// The pane added by splitting the editor
+    Second,
+}
+
+impl Pane {
+    /// The other pane
+    pub fn other(self) -> Self {
+        match self {
+            Pane::First => Pane::Second,
+            Pane::Second => Pane::First,
+        }
+    }
+}
+
+/// How the two panes of a split editor are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitOrientation {
+    /// Side by side, divided by a vertical line
+    Vertical,
+    /// One above the other, divided by a horizontal line
+    Horizontal,
+}
+
+/// State of a split editor
+#[derive(Debug, Clone)]
+pub struct EditorSplit {
+    /// How the panes are arranged
+    pub orientation: SplitOrientation,
+
+    /// Tabs of the second pane (the first pane uses the main tab state)
+    pub tabs: TabState,
+
+    /// Pane with keyboard focus
+    pub focused: Pane,
+}
+
+impl EditorSplit {
+    /// Create a split with an empty, focused second pane
+    pub fn new(orientation: SplitOrientation) -> Self {
+        Self {
+            orientation,
+            tabs: TabState::new(),
+            focused: Pane::Second,
+        }
+    }
+}
@@ -3,6 +3,7 @@
 //! Contains state that should be persisted across application restarts,
 //! including window state, open files, and session data.
 
+use super::{CursorPosition, DocumentId};
 use crate::config::ViewMode;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -40,6 +41,22 @@ pub struct SessionState {
     /// Recent files (limited list)
     pub recent_files: Vec<RecentFile>,
 
+    /// Zoom level multiplier
+    #[serde(default = "default_zoom_level")]
+    pub zoom_level: f32,
+
+    /// Unsaved documents without a file, reopened on next launch
+    #[serde(default)]
+    pub scratch_documents: Vec<ScratchDocument>,
+
+    /// Last find query used in each file, most recent first
+    #[serde(default)]
+    pub find_queries: Vec<(PathBuf, String)>,
+
+    /// Bookmarked lines of each file, most recent first
+    #[serde(default)]
+    pub bookmarks: Vec<(PathBuf, Vec<usize>)>,
+
     /// Session version for migration
     pub version: u32,
 }
@@ -57,11 +74,20 @@ impl Default for SessionState {
             active_file_index: None,
             last_directory: None,
             recent_files: Vec::new(),
+            zoom_level: default_zoom_level(),
+            scratch_documents: Vec::new(),
+            find_queries: Vec::new(),
+            bookmarks: Vec::new(),
             version: 1,
         }
     }
 }
 
+/// Zoom level for sessions saved before zoom was persisted
+fn default_zoom_level() -> f32 {
+    1.0
+}
+
 impl SessionState {
     /// Create a new session state
     pub fn new() -> Self {
@@ -168,6 +194,88 @@ impl SessionState {
         self.open_files = files;
         self.active_file_index = active_index;
     }
+
+    /// Replace the scratch documents, skipping empty ones
+    pub fn update_scratch_documents(&mut self, documents: Vec<ScratchDocument>) {
+        self.scratch_documents = documents
+            .into_iter()
+            .filter(|doc| !doc.content.is_empty())
+            .take(MAX_SCRATCH_DOCUMENTS)
+            .collect();
+    }
+
+    /// Remember the find query last used in a file
+    ///
+    /// An empty query forgets the file's query.
+    pub fn remember_find_query(&mut self, path: PathBuf, query: &str) {
+        self.find_queries.retain(|(p, _)| *p != path);
+        if !query.is_empty() {
+            self.find_queries.insert(0, (path, query.to_string()));
+            self.find_queries.truncate(MAX_FIND_QUERIES);
+        }
+    }
+
+    /// The find query last used in a file
+    pub fn find_query(&self, path: &std::path::Path) -> Option<&str> {
+        self.find_queries
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, query)| query.as_str())
+    }
+
+    /// Remember a file's bookmarked lines
+    ///
+    /// A file without bookmarks is forgotten.
+    pub fn set_bookmarks(&mut self, path: PathBuf, lines: impl IntoIterator<Item = usize>) {
+        self.bookmarks.retain(|(p, _)| *p != path);
+        let lines: Vec<usize> = lines.into_iter().collect();
+        if !lines.is_empty() {
+            self.bookmarks.insert(0, (path, lines));
+            self.bookmarks.truncate(MAX_BOOKMARKED_FILES);
+        }
+    }
+
+    /// A file's bookmarked lines
+    pub fn bookmarks(&self, path: &std::path::Path) -> &[usize] {
+        self.bookmarks
+            .iter()
+            .find(|(p, _)| p == path)
+            .map_or(&[], |(_, lines)| lines.as_slice())
+    }
+
+    /// Remove a scratch document, e.g. once it has been saved to a file
+    ///
+    /// Returns whether the document was a scratch document.
+    pub fn remove_scratch_document(&mut self, id: DocumentId) -> bool {
+        let count = self.scratch_documents.len();
+        self.scratch_documents.retain(|doc| doc.id != id);
+        self.scratch_documents.len() != count
+    }
+}
+
+/// Maximum number of scratch documents kept between sessions
+pub const MAX_SCRATCH_DOCUMENTS: usize = 10;
+
+/// Maximum number of files whose last find query is remembered
+pub const MAX_FIND_QUERIES: usize = 50;
+
+/// Maximum number of files whose bookmarks are remembered
+pub const MAX_BOOKMARKED_FILES: usize = 100;
+
+/// An unsaved document without a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScratchDocument {
+    /// Document ID, kept so the entry can be removed once saved
+    pub id: DocumentId,
+
+    /// Tab title
+    pub display_name: String,
+
+    /// Document content
+    pub content: String,
+
+    /// Cursor position
+    pub cursor: CursorPosition,
 }
 
 /// A recently opened file
@@ -314,6 +422,76 @@ mod tests {
         assert_eq!(session.recent_files[0].path, PathBuf::from("/test1.md"));
     }
 
+    #[test]
+    fn test_session_without_zoom_level() {
+        let mut value = serde_json::to_value(SessionState::default()).unwrap();
+        value.as_object_mut().unwrap().remove("zoom_level");
+
+        let session: SessionState = serde_json::from_value(value).unwrap();
+        assert_eq!(session.zoom_level, 1.0);
+    }
+
+    #[test]
+    fn test_scratch_documents() {
+        let scratch = |content: &str| ScratchDocument {
+            id: DocumentId::new(),
+            display_name: "Untitled".to_string(),
+            content: content.to_string(),
+            cursor: CursorPosition::default(),
+        };
+
+        let mut session = SessionState::new();
+        let mut documents = vec![scratch(""), scratch("notes")];
+        documents.extend((0..MAX_SCRATCH_DOCUMENTS).map(|_| scratch("more")));
+        session.update_scratch_documents(documents);
+
+        assert_eq!(session.scratch_documents.len(), MAX_SCRATCH_DOCUMENTS);
+        assert_eq!(session.scratch_documents[0].content, "notes");
+
+        let id = session.scratch_documents[0].id;
+        assert!(session.remove_scratch_document(id));
+        assert!(!session.remove_scratch_document(id));
+
+        // Sessions saved before scratch documents load without them
+        let mut value = serde_json::to_value(SessionState::default()).unwrap();
+        value.as_object_mut().unwrap().remove("scratch_documents");
+        let session: SessionState = serde_json::from_value(value).unwrap();
+        assert!(session.scratch_documents.is_empty());
+    }
+
+    #[test]
+    fn test_find_queries() {
+        let mut session = SessionState::new();
+        let notes = PathBuf::from("/tmp/notes.md");
+        session.remember_find_query(notes.clone(), "todo");
+        session.remember_find_query(PathBuf::from("/tmp/other.md"), "fixme");
+        session.remember_find_query(notes.clone(), "done");
+
+        assert_eq!(session.find_query(&notes), Some("done"));
+        assert_eq!(session.find_queries.len(), 2);
+        assert_eq!(session.find_queries[0].0, notes);
+
+        session.remember_find_query(notes.clone(), "");
+        assert_eq!(session.find_query(&notes), None);
+
+        for i in 0..MAX_FIND_QUERIES + 5 {
+            session.remember_find_query(PathBuf::from(format!("/tmp/{}.md", i)), "q");
+        }
+        assert_eq!(session.find_queries.len(), MAX_FIND_QUERIES);
+    }
+
+    #[test]
+    fn test_bookmarks() {
+        let mut session = SessionState::new();
+        let notes = PathBuf::from("/tmp/notes.md");
+        session.set_bookmarks(notes.clone(), [4, 12]);
+        assert_eq!(session.bookmarks(&notes), &[4, 12]);
+        assert!(session.bookmarks(&PathBuf::from("/tmp/other.md")).is_empty());
+
+        session.set_bookmarks(notes.clone(), []);
+        assert!(session.bookmarks.is_empty());
+    }
+
     #[test]
     fn test_recent_file_display_name() {
         let recent = RecentFile {
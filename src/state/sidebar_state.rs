@@ -4,9 +4,47 @@
 //! file tree entries, expanded folders, and filtering.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::path::PathBuf;
-use std::time::SystemTime;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::{
+    SidebarPosition, DEFAULT_SIDEBAR_MAX_WIDTH, DEFAULT_SIDEBAR_MIN_WIDTH, DEFAULT_SIDEBAR_WIDTH,
+};
+
+/// Longest gap between two presses of the resize handle that counts as a
+/// double click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Distance in pixels the pointer moves before pressing a file starts
+/// dragging it, rather than clicking it
+const DRAG_THRESHOLD: f32 = 4.0;
+
+/// Bounds for resizing the sidebar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SidebarLimits {
+    /// Narrowest width in pixels
+    pub min_width: u32,
+    /// Widest width in pixels
+    pub max_width: u32,
+    /// Width restored by double-clicking the resize handle
+    pub default_width: u32,
+    /// Hide the sidebar when it is dragged well below the minimum width
+    pub collapse_on_drag: bool,
+}
+
+impl Default for SidebarLimits {
+    fn default() -> Self {
+        Self {
+            min_width: DEFAULT_SIDEBAR_MIN_WIDTH,
+            max_width: DEFAULT_SIDEBAR_MAX_WIDTH,
+            default_width: DEFAULT_SIDEBAR_WIDTH,
+            collapse_on_drag: true,
+        }
+    }
+}
 
 /// A single entry in the file tree
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,7 +105,9 @@ impl FileEntry {
 
     /// Check if this is a markdown file
     pub fn is_markdown(&self) -> bool {
-        matches!(self.extension(), Some("md" | "markdown"))
+        self.extension().is_some_and(|ext| {
+            ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown")
+        })
     }
 
     /// Check if this is a hidden file (starts with dot)
@@ -97,6 +137,28 @@ impl FileEntry {
     }
 }
 
+/// Which files the sidebar lists
+///
+/// Folders are always listed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FileFilter {
+    /// Every file
+    All,
+    /// Markdown files only
+    #[default]
+    Markdown,
+    /// Files matching a glob, by name, or by path below the root when the
+    /// glob has a `/`
+    Glob(String),
+}
+
+impl FileFilter {
+    /// Whether listing needs a scan that collected more than markdown files
+    pub fn needs_all_files(&self) -> bool {
+        !matches!(self, FileFilter::Markdown)
+    }
+}
+
 /// Sidebar state
 #[derive(Debug, Clone, Default)]
 pub struct SidebarState {
@@ -104,8 +166,22 @@ pub struct SidebarState {
     pub root: Option<PathBuf>,
 
     /// Flat list of file entries
+    ///
+    /// Each folder's children follow it, one level deeper. Folders are
+    /// listed the first time they are expanded, so only folders in
+    /// `loaded_dirs` have their children here.
     pub entries: Vec<FileEntry>,
 
+    /// Folders whose children are in `entries`, with when each was last
+    /// modified as of its listing
+    loaded_dirs: HashMap<PathBuf, Option<SystemTime>>,
+
+    /// Folders being listed
+    pending_listings: HashSet<PathBuf>,
+
+    /// Stops the expand-all in progress when set
+    pub expand_all: Option<Arc<AtomicBool>>,
+
     /// Set of expanded directory paths
     pub expanded_folders: HashSet<PathBuf>,
 
@@ -115,6 +191,16 @@ pub struct SidebarState {
     /// Search/filter input text
     pub filter_text: String,
 
+    /// Which files are listed
+    pub filter: FileFilter,
+
+    /// Compiled glob of a `FileFilter::Glob`, None when it is malformed
+    filter_glob: Option<globset::GlobMatcher>,
+
+    /// Whether the entries were scanned with every file, rather than only
+    /// markdown ones
+    pub scanned_all_files: bool,
+
     /// Indices of entries matching current filter
     pub filtered_indices: Vec<usize>,
 
@@ -124,6 +210,18 @@ pub struct SidebarState {
     /// Sidebar width in pixels
     pub width: u32,
 
+    /// Side of the window the sidebar is shown on
+    pub position: SidebarPosition,
+
+    /// Bounds for resizing
+    pub limits: SidebarLimits,
+
+    /// Width when the resize handle started being dragged, while dragging
+    pub resize_start: Option<u32>,
+
+    /// When the resize handle was last pressed, to detect double clicks
+    last_handle_press: Option<Instant>,
+
     /// Whether a directory scan is in progress
     pub is_scanning: bool,
 
@@ -138,6 +236,12 @@ pub struct SidebarState {
 
     /// Context menu state
     pub context_menu: Option<ContextMenuState>,
+
+    /// Name prompt for a file or folder being created, while open
+    pub new_entry: Option<NewEntryPrompt>,
+
+    /// File pressed in the tree, while the pointer button is held
+    pub file_drag: Option<FileDrag>,
 }
 
 impl SidebarState {
@@ -146,23 +250,100 @@ impl SidebarState {
         Self {
             root: None,
             entries: Vec::new(),
+            loaded_dirs: HashMap::new(),
+            pending_listings: HashSet::new(),
+            expand_all: None,
             expanded_folders: HashSet::new(),
             selected_path: None,
             filter_text: String::new(),
+            filter: FileFilter::default(),
+            filter_glob: None,
+            scanned_all_files: false,
             filtered_indices: Vec::new(),
             visible: true,
             width: 250,
+            position: SidebarPosition::default(),
+            limits: SidebarLimits::default(),
+            resize_start: None,
+            last_handle_press: None,
             is_scanning: false,
             error_message: None,
             focused_index: None,
             has_focus: false,
             context_menu: None,
+            new_entry: None,
+            file_drag: None,
+        }
+    }
+
+    /// Set the width, clamped to the allowed range
+    pub fn set_width(&mut self, width: u32) {
+        let min = self.limits.min_width;
+        self.width = width.clamp(min, self.limits.max_width.max(min));
+    }
+
+    /// Change the resize bounds, clamping the current width to them
+    pub fn set_limits(&mut self, limits: SidebarLimits) {
+        self.limits = limits;
+        self.set_width(self.width);
+    }
+
+    /// Whether the resize handle is being dragged
+    pub fn is_resizing(&self) -> bool {
+        self.resize_start.is_some()
+    }
+
+    /// Handle a press on the resize handle at `now`
+    ///
+    /// Starts a drag, or resets the width to the default on the second
+    /// press of a double click.
+    pub fn press_handle(&mut self, now: Instant) {
+        let double_click = self
+            .last_handle_press
+            .is_some_and(|last| now.duration_since(last) <= DOUBLE_CLICK_INTERVAL);
+        if double_click {
+            self.last_handle_press = None;
+            self.resize_start = None;
+            self.set_width(self.limits.default_width);
+        } else {
+            self.last_handle_press = Some(now);
+            self.resize_start = Some(self.width);
+        }
+    }
+
+    /// Stop dragging the resize handle
+    pub fn end_resize(&mut self) {
+        self.resize_start = None;
+    }
+
+    /// Resize to follow the resize handle at `cursor_x` in the window
+    ///
+    /// A sidebar on the right grows as the handle moves left, so its width
+    /// is measured from the right edge of the window. Dragging below half
+    /// the minimum width hides the sidebar, keeping the width it had before
+    /// the drag for when it is shown again; dragging back out shows it.
+    pub fn drag_to(&mut self, cursor_x: f32, window_width: u32) {
+        let width = match self.position {
+            SidebarPosition::Left => cursor_x,
+            SidebarPosition::Right => window_width as f32 - cursor_x,
+        };
+        if self.limits.collapse_on_drag && width < self.limits.min_width as f32 / 2.0 {
+            self.visible = false;
+            if let Some(start) = self.resize_start {
+                self.width = start;
+            }
+        } else {
+            self.visible = true;
+            self.set_width(width.max(0.0).round() as u32);
         }
     }
 
     /// Set the root directory
     pub fn set_root(&mut self, path: PathBuf) {
         self.root = Some(path.clone());
+        self.loaded_dirs.clear();
+        self.pending_listings.clear();
+        self.cancel_expand_all();
         self.expanded_folders.clear();
         self.expanded_folders.insert(path);
         self.clear_filter();
@@ -173,6 +354,8 @@ impl SidebarState {
     pub fn clear(&mut self) {
         self.root = None;
         self.entries.clear();
+        self.loaded_dirs.clear();
+        self.cancel_expand_all();
         self.expanded_folders.clear();
         self.selected_path = None;
         self.clear_filter();
@@ -186,6 +369,126 @@ impl SidebarState {
         self.apply_filter();
     }
 
+    /// Whether a folder has to be listed before its children can show
+    ///
+    /// Listed folders reuse their children until the folder changes on
+    /// disk.
+    pub fn needs_listing(&self, dir: &Path) -> bool {
+        match self.loaded_dirs.get(dir) {
+            Some(modified) => dir.metadata().and_then(|m| m.modified()).ok() != *modified,
+            None => true,
+        }
+    }
+
+    /// Expanded folders shown in the tree whose children aren't listed yet
+    ///
+    /// Re-listing a folder drops the listings below it, so these are
+    /// listed again afterwards to keep their children showing.
+    pub fn unlisted_expanded(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.is_directory && self.is_expanded(&entry.path))
+            .filter(|entry| !self.loaded_dirs.contains_key(&entry.path))
+            .map(|entry| entry.path.clone())
+            .collect();
+        if let Some(root) = self.root.clone().filter(|r| !self.loaded_dirs.contains_key(r)) {
+            dirs.insert(0, root);
+        }
+        dirs
+    }
+
+    /// Note that `dirs` are about to be listed, returning those that
+    /// aren't being listed already
+    pub fn start_listing(&mut self, dirs: Vec<PathBuf>) -> Vec<PathBuf> {
+        dirs.into_iter()
+            .filter(|dir| self.pending_listings.insert(dir.clone()))
+            .collect()
+    }
+
+    /// Put a folder's listed children in the tree, replacing any listed
+    /// before
+    ///
+    /// Listings of folders that aren't in the tree (anymore) are dropped.
+    pub fn apply_listing(&mut self, listing: crate::file_handler::DirListing) {
+        self.pending_listings.remove(&listing.dir);
+        let Some(root) = self.root.as_ref() else {
+            return;
+        };
+        let (start, depth) = if listing.dir == *root {
+            (0, 0)
+        } else {
+            let Some(index) = self
+                .entries
+                .iter()
+                .position(|entry| entry.is_directory && entry.path == listing.dir)
+            else {
+                return;
+            };
+            (index + 1, self.entries[index].depth + 1)
+        };
+        let end = start
+            + self.entries[start..]
+                .iter()
+                .take_while(|entry| entry.depth >= depth)
+                .count();
+
+        for entry in &self.entries[start..end] {
+            if entry.is_directory {
+                self.loaded_dirs.remove(&entry.path);
+            }
+        }
+        let focused = self.focused_entry().map(|entry| entry.path.clone());
+        let children = listing.children.into_iter().map(|mut child| {
+            child.depth = depth;
+            child
+        });
+        self.entries.splice(start..end, children);
+        if depth == 0 {
+            self.is_scanning = false;
+        }
+        self.loaded_dirs.insert(listing.dir, listing.modified);
+
+        self.link_parents();
+        self.focused_index = focused.and_then(|path| self.entries.iter().position(|e| e.path == path));
+        self.apply_filter();
+    }
+
+    /// Point each entry's `parent_index` at the folder it is listed under
+    fn link_parents(&mut self) {
+        let mut folders: Vec<usize> = Vec::new();
+        for index in 0..self.entries.len() {
+            let depth = self.entries[index].depth;
+            folders.truncate(depth);
+            self.entries[index].parent_index = folders.last().copied();
+            if self.entries[index].is_directory {
+                folders.push(index);
+            }
+        }
+    }
+
+    /// Expand every folder a tree listing reached
+    pub fn apply_tree_listing(&mut self, listings: Vec<crate::file_handler::DirListing>) {
+        self.expand_all = None;
+        for listing in listings {
+            self.expanded_folders.insert(listing.dir.clone());
+            self.apply_listing(listing);
+        }
+    }
+
+    /// Collapse every folder below the root
+    pub fn collapse_all(&mut self) {
+        self.cancel_expand_all();
+        self.expanded_folders.retain(|path| Some(path) == self.root.as_ref());
+    }
+
+    /// Stop the expand-all in progress, if any
+    pub fn cancel_expand_all(&mut self) {
+        if let Some(cancel) = self.expand_all.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
     /// Check if a folder is expanded
     pub fn is_expanded(&self, path: &PathBuf) -> bool {
         self.expanded_folders.contains(path)
@@ -210,11 +513,124 @@ impl SidebarState {
         self.expanded_folders.remove(path);
     }
 
+    /// Show the sidebar with `folder` expanded and selected
+    ///
+    /// Expands every folder between the root and `folder`; does nothing
+    /// for folders outside the root.
+    pub fn reveal(&mut self, folder: &Path) {
+        let Some(root) = self.root.clone() else {
+            return;
+        };
+        if !folder.starts_with(&root) {
+            return;
+        }
+        for ancestor in folder.ancestors().take_while(|a| a.starts_with(&root)) {
+            self.expanded_folders.insert(ancestor.to_path_buf());
+        }
+        self.selected_path = Some(folder.to_path_buf());
+        self.visible = true;
+    }
+
+    /// Whether the root has a parent folder to go up to
+    pub fn can_go_up(&self) -> bool {
+        self.root.as_deref().and_then(Path::parent).is_some()
+    }
+
+    /// Make the root's parent folder the root
+    ///
+    /// The folder that was the root stays expanded and selected, so the
+    /// tree still shows where it came from. Returns false at the
+    /// filesystem root.
+    pub fn go_up(&mut self) -> bool {
+        let Some(previous) = self.root.clone() else {
+            return false;
+        };
+        let Some(parent) = previous.parent() else {
+            return false;
+        };
+        self.set_root(parent.to_path_buf());
+        self.expanded_folders.insert(previous.clone());
+        self.selected_path = Some(previous);
+        true
+    }
+
+    /// Folder new files and folders are created in
+    ///
+    /// That is the selected folder, or the folder of the selected file,
+    /// falling back to the root when nothing inside it is selected.
+    pub fn target_directory(&self) -> Option<PathBuf> {
+        let root = self.root.as_ref()?;
+        let Some(selected) = self.selected_path.as_ref().filter(|p| p.starts_with(root)) else {
+            return Some(root.clone());
+        };
+        match self.entries.iter().find(|entry| &entry.path == selected) {
+            Some(entry) if !entry.is_directory => {
+                Some(selected.parent().map_or_else(|| root.clone(), Path::to_path_buf))
+            }
+            // Revealed folders are selected without being scanned
+            _ => Some(selected.clone()),
+        }
+    }
+
+    /// Open the name prompt for a new file or folder in `parent`
+    pub fn prompt_new_entry(&mut self, parent: PathBuf, kind: NewEntryKind) {
+        self.new_entry = Some(NewEntryPrompt {
+            parent,
+            kind,
+            name: String::new(),
+            exists: false,
+        });
+    }
+
     /// Set the selected path
     pub fn set_selected(&mut self, path: Option<PathBuf>) {
         self.selected_path = path;
     }
 
+    /// Change which files are listed
+    ///
+    /// Returns whether the folder has to be scanned again: narrowing the
+    /// listing filters the entries already scanned, but widening it past
+    /// markdown files needs the files the last scan skipped.
+    pub fn set_file_filter(&mut self, filter: FileFilter) -> bool {
+        self.filter_glob = match &filter {
+            FileFilter::Glob(pattern) => globset::Glob::new(pattern.trim())
+                .map(|glob| glob.compile_matcher())
+                .ok(),
+            _ => None,
+        };
+        self.filter = filter;
+        self.apply_filter();
+        self.filter.needs_all_files() && !self.scanned_all_files
+    }
+
+    /// Whether an entry passes the file filter
+    ///
+    /// A malformed glob lists every file, rather than none while it is
+    /// still being typed.
+    fn is_listed(&self, entry: &FileEntry) -> bool {
+        if entry.is_directory {
+            return true;
+        }
+        match &self.filter {
+            FileFilter::All => true,
+            FileFilter::Markdown => entry.is_markdown(),
+            FileFilter::Glob(pattern) => {
+                let Some(glob) = &self.filter_glob else {
+                    return true;
+                };
+                if !pattern.contains('/') {
+                    return glob.is_match(&entry.name);
+                }
+                let relative = self
+                    .root
+                    .as_ref()
+                    .and_then(|root| entry.path.strip_prefix(root).ok());
+                glob.is_match(relative.unwrap_or(&entry.path))
+            }
+        }
+    }
+
     /// Set filter text and apply filtering
     pub fn set_filter(&mut self, text: String) {
         self.filter_text = text;
@@ -240,6 +656,7 @@ impl SidebarState {
             .iter()
             .enumerate()
             .filter(|(_, entry)| entry.name.to_lowercase().contains(&filter_lower))
+            .filter(|(_, entry)| self.is_listed(entry))
             .map(|(i, _)| i)
             .collect();
     }
@@ -269,6 +686,9 @@ impl SidebarState {
                 }
             }
 
+            if !self.is_listed(entry) {
+                continue;
+            }
             visible.push((index, entry));
 
             // If this is a collapsed folder, skip its contents
@@ -363,6 +783,79 @@ impl SidebarState {
     }
 }
 
+/// A file pressed in the sidebar, which becomes a drag once the pointer
+/// moves far enough
+///
+/// Pointer positions are in window coordinates, so the drag measures from
+/// the first position seen after the press.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDrag {
+    /// The pressed file
+    pub path: PathBuf,
+    /// Window position of the pointer when it first moved
+    origin: Option<(f32, f32)>,
+    /// Whether the pointer moved far enough to drag the file
+    pub dragging: bool,
+}
+
+impl FileDrag {
+    /// Start tracking a press on `path`
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, origin: None, dragging: false }
+    }
+
+    /// Follow the pointer to a window position
+    pub fn pointer_moved(&mut self, x: f32, y: f32) {
+        let (ox, oy) = *self.origin.get_or_insert((x, y));
+        if (x - ox).hypot(y - oy) >= DRAG_THRESHOLD {
+            self.dragging = true;
+        }
+    }
+}
+
+/// What a new sidebar entry is created as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewEntryKind {
+    File,
+    Folder,
+}
+
+/// Name prompt for a file or folder being created from the sidebar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewEntryPrompt {
+    /// Folder the entry is created in
+    pub parent: PathBuf,
+
+    /// Whether a file or a folder is created
+    pub kind: NewEntryKind,
+
+    /// Name typed so far
+    pub name: String,
+
+    /// Whether the last submitted name was already taken
+    pub exists: bool,
+}
+
+impl NewEntryPrompt {
+    /// Path of the entry to create, or None while the name is blank or
+    /// not a plain file name
+    ///
+    /// File names without an extension get `.md`, so the new file shows
+    /// up in the sidebar.
+    pub fn path(&self) -> Option<PathBuf> {
+        let name = self.name.trim();
+        let plain = Path::new(name).file_name().is_some_and(|n| n == name);
+        if name.is_empty() || !plain {
+            return None;
+        }
+        let mut path = self.parent.join(name);
+        if self.kind == NewEntryKind::File && path.extension().is_none() {
+            path.set_extension("md");
+        }
+        Some(path)
+    }
+}
+
 /// Context menu state
 #[derive(Debug, Clone)]
 pub struct ContextMenuState {
@@ -441,6 +934,193 @@ mod tests {
         assert!(state.is_expanded(&path));
     }
 
+    #[test]
+    fn test_sidebar_reveal() {
+        let mut state = SidebarState::new();
+        state.set_root(PathBuf::from("/notes"));
+        state.visible = false;
+        state.reveal(Path::new("/notes/work/2024"));
+        assert!(state.visible);
+        assert!(state.is_expanded(&PathBuf::from("/notes/work")));
+        assert!(state.is_expanded(&PathBuf::from("/notes/work/2024")));
+        assert_eq!(state.selected_path, Some(PathBuf::from("/notes/work/2024")));
+
+        state.reveal(Path::new("/elsewhere"));
+        assert_eq!(state.selected_path, Some(PathBuf::from("/notes/work/2024")));
+    }
+
+    #[test]
+    fn test_file_drag_threshold() {
+        let mut drag = FileDrag::new(PathBuf::from("/notes/a.md"));
+        drag.pointer_moved(100.0, 50.0);
+        drag.pointer_moved(102.0, 51.0);
+        assert!(!drag.dragging);
+        drag.pointer_moved(100.0, 56.0);
+        assert!(drag.dragging);
+        // Coming back doesn't turn the drag into a click
+        drag.pointer_moved(100.0, 50.0);
+        assert!(drag.dragging);
+    }
+
+    #[test]
+    fn test_sidebar_go_up() {
+        let mut state = SidebarState::new();
+        state.set_root(PathBuf::from("/notes/work"));
+        assert!(state.can_go_up());
+        assert!(state.go_up());
+        assert_eq!(state.root, Some(PathBuf::from("/notes")));
+        assert!(state.is_expanded(&PathBuf::from("/notes/work")));
+        assert_eq!(state.selected_path, Some(PathBuf::from("/notes/work")));
+
+        state.set_root(PathBuf::from("/"));
+        assert!(!state.can_go_up());
+        assert!(!state.go_up());
+    }
+
+    #[test]
+    fn test_sidebar_target_directory() {
+        let mut state = SidebarState::new();
+        assert_eq!(state.target_directory(), None);
+
+        state.set_root(PathBuf::from("/notes"));
+        let mut folder = FileEntry::new(PathBuf::from("/notes/work"), 0, None);
+        folder.is_directory = true;
+        let file = FileEntry::new(PathBuf::from("/notes/work/todo.md"), 1, Some(0));
+        state.entries = vec![folder, file];
+        assert_eq!(state.target_directory(), Some(PathBuf::from("/notes")));
+
+        state.set_selected(Some(PathBuf::from("/notes/work/todo.md")));
+        assert_eq!(state.target_directory(), Some(PathBuf::from("/notes/work")));
+        state.set_selected(Some(PathBuf::from("/notes/work")));
+        assert_eq!(state.target_directory(), Some(PathBuf::from("/notes/work")));
+        state.set_selected(Some(PathBuf::from("/elsewhere/a.md")));
+        assert_eq!(state.target_directory(), Some(PathBuf::from("/notes")));
+    }
+
+    #[test]
+    fn test_new_entry_path() {
+        let mut state = SidebarState::new();
+        state.prompt_new_entry(PathBuf::from("/notes"), NewEntryKind::File);
+        let prompt = state.new_entry.as_mut().unwrap();
+        assert_eq!(prompt.path(), None);
+
+        prompt.name = " ideas ".to_string();
+        assert_eq!(prompt.path(), Some(PathBuf::from("/notes/ideas.md")));
+        prompt.name = "list.txt".to_string();
+        assert_eq!(prompt.path(), Some(PathBuf::from("/notes/list.txt")));
+        prompt.name = "../escape".to_string();
+        assert_eq!(prompt.path(), None);
+
+        prompt.kind = NewEntryKind::Folder;
+        prompt.name = "drafts".to_string();
+        assert_eq!(prompt.path(), Some(PathBuf::from("/notes/drafts")));
+    }
+
+    fn listing(dir: &str, children: &[(&str, bool)]) -> crate::file_handler::DirListing {
+        crate::file_handler::DirListing {
+            dir: PathBuf::from(dir),
+            children: children
+                .iter()
+                .map(|&(path, is_directory)| {
+                    let mut entry = FileEntry::new(PathBuf::from(path), 0, None);
+                    entry.is_directory = is_directory;
+                    entry
+                })
+                .collect(),
+            modified: None,
+        }
+    }
+
+    #[test]
+    fn test_sidebar_lazy_listing() {
+        let mut state = SidebarState::new();
+        state.set_root(PathBuf::from("/notes"));
+        assert_eq!(state.unlisted_expanded(), vec![PathBuf::from("/notes")]);
+        let dirs = vec![PathBuf::from("/notes")];
+        assert_eq!(state.start_listing(dirs.clone()), dirs);
+        assert!(state.start_listing(dirs).is_empty());
+
+        state.apply_listing(listing("/notes", &[("/notes/work", true), ("/notes/a.md", false)]));
+        assert!(state.unlisted_expanded().is_empty());
+        assert!(state.needs_listing(Path::new("/notes/work")));
+
+        state.toggle_folder(&PathBuf::from("/notes/work"));
+        state.apply_listing(listing("/notes/work", &[("/notes/work/b.md", false)]));
+        let names: Vec<_> = state.visible_entries().iter().map(|(_, e)| e.name.clone()).collect();
+        assert_eq!(names, vec!["work", "b.md", "a.md"]);
+        assert_eq!(state.entries[1].depth, 1);
+        assert_eq!(state.entries[1].parent_index, Some(0));
+        assert_eq!(state.entries[2].parent_index, None);
+
+        // Listing a folder that isn't in the tree does nothing
+        state.apply_listing(listing("/notes/gone", &[("/notes/gone/c.md", false)]));
+        assert_eq!(state.entries.len(), 3);
+
+        // Listing the root again drops the listings below it
+        state.focused_index = Some(2);
+        state.apply_listing(listing("/notes", &[("/notes/work", true), ("/notes/a.md", false)]));
+        assert_eq!(state.entries.len(), 2);
+        assert_eq!(state.focused_index, Some(1));
+        assert_eq!(state.unlisted_expanded(), vec![PathBuf::from("/notes/work")]);
+    }
+
+    #[test]
+    fn test_sidebar_expand_collapse_all() {
+        let mut state = SidebarState::new();
+        state.set_root(PathBuf::from("/notes"));
+        let cancel = Arc::new(AtomicBool::new(false));
+        state.expand_all = Some(cancel.clone());
+        state.apply_tree_listing(vec![
+            listing("/notes", &[("/notes/work", true)]),
+            listing("/notes/work", &[("/notes/work/old", true)]),
+            listing("/notes/work/old", &[("/notes/work/old/a.md", false)]),
+        ]);
+        assert!(state.expand_all.is_none());
+        assert_eq!(state.visible_entries().len(), 3);
+
+        state.expand_all = Some(cancel.clone());
+        state.collapse_all();
+        assert!(cancel.load(Ordering::Relaxed));
+        assert!(state.is_expanded(&PathBuf::from("/notes")));
+        assert_eq!(state.visible_entries().len(), 1);
+        // Collapsed folders keep their children for when they are reopened
+        assert_eq!(state.entries.len(), 3);
+        assert!(!state.needs_listing(Path::new("/notes/work")));
+    }
+
+    #[test]
+    fn test_sidebar_file_filter() {
+        let mut state = SidebarState::new();
+        state.set_root(PathBuf::from("/notes"));
+        let mut folder = FileEntry::new(PathBuf::from("/notes/img"), 0, None);
+        folder.is_directory = true;
+        state.entries = vec![
+            folder,
+            FileEntry::new(PathBuf::from("/notes/img/cat.png"), 1, Some(0)),
+            FileEntry::new(PathBuf::from("/notes/README.MD"), 0, None),
+            FileEntry::new(PathBuf::from("/notes/todo.txt"), 0, None),
+        ];
+        let names = |state: &SidebarState| -> Vec<String> {
+            state.visible_entries().iter().map(|(_, e)| e.name.clone()).collect()
+        };
+        assert_eq!(names(&state), vec!["img", "README.MD"]);
+
+        // Widening past markdown needs the files the scan skipped
+        assert!(state.set_file_filter(FileFilter::All));
+        state.scanned_all_files = true;
+        assert_eq!(names(&state).len(), 4);
+
+        // Narrowing again filters what was scanned
+        assert!(!state.set_file_filter(FileFilter::Glob("*.txt".to_string())));
+        assert_eq!(names(&state), vec!["img", "todo.txt"]);
+        assert!(!state.set_file_filter(FileFilter::Glob("img/*".to_string())));
+        assert_eq!(names(&state), vec!["img", "cat.png"]);
+        assert!(!state.set_file_filter(FileFilter::Glob("[".to_string())));
+        assert_eq!(names(&state).len(), 4);
+        assert!(!state.set_file_filter(FileFilter::Markdown));
+        assert_eq!(names(&state), vec!["img", "README.MD"]);
+    }
+
     #[test]
     fn test_sidebar_filter() {
         let mut state = SidebarState::new();
@@ -469,4 +1149,79 @@ mod tests {
         assert_eq!(state.filtered_indices.len(), 1);
         assert_eq!(state.filtered_indices[0], 0);
     }
+
+    #[test]
+    fn test_sidebar_resize() {
+        let mut state = SidebarState::new();
+        state.drag_to(300.0, 1200);
+        assert_eq!(state.width, 300);
+        state.drag_to(100.0, 1200);
+        assert_eq!(state.width, DEFAULT_SIDEBAR_MIN_WIDTH);
+        assert!(state.visible);
+
+        // On the right, dragging the handle left widens the sidebar
+        state.position = SidebarPosition::Right;
+        state.drag_to(900.0, 1200);
+        assert_eq!(state.width, 300);
+        state.drag_to(800.0, 1200);
+        assert_eq!(state.width, 400);
+        state.drag_to(100.0, 1200);
+        assert_eq!(state.width, DEFAULT_SIDEBAR_MAX_WIDTH);
+
+        state.set_width(50);
+        assert_eq!(state.width, DEFAULT_SIDEBAR_MIN_WIDTH);
+    }
+
+    #[test]
+    fn test_sidebar_drag_collapse() {
+        let mut state = SidebarState::new();
+        state.set_width(320);
+        state.press_handle(Instant::now());
+        state.drag_to(200.0, 1200);
+        assert_eq!(state.width, 200);
+
+        // Well past the minimum the sidebar hides and keeps its old width
+        state.drag_to(20.0, 1200);
+        assert!(!state.visible);
+        assert_eq!(state.width, 320);
+
+        // Dragging back out shows it again
+        state.drag_to(250.0, 1200);
+        assert!(state.visible);
+        assert_eq!(state.width, 250);
+
+        state.limits.collapse_on_drag = false;
+        state.drag_to(20.0, 1200);
+        assert!(state.visible);
+        assert_eq!(state.width, DEFAULT_SIDEBAR_MIN_WIDTH);
+    }
+
+    #[test]
+    fn test_sidebar_double_click_reset() {
+        let mut state = SidebarState::new();
+        state.set_width(400);
+        let now = Instant::now();
+        state.press_handle(now);
+        assert!(state.is_resizing());
+        state.end_resize();
+        state.press_handle(now + Duration::from_millis(200));
+        assert!(!state.is_resizing());
+        assert_eq!(state.width, DEFAULT_SIDEBAR_WIDTH);
+
+        // Presses further apart start separate drags
+        state.press_handle(now + Duration::from_secs(2));
+        assert!(state.is_resizing());
+    }
+
+    #[test]
+    fn test_sidebar_limits() {
+        let mut state = SidebarState::new();
+        state.set_width(500);
+        state.set_limits(SidebarLimits {
+            min_width: 200,
+            max_width: 300,
+            ..SidebarLimits::default()
+        });
+        assert_eq!(state.width, 300);
+    }
 }
@@ -60,8 +60,11 @@ impl TabState {
 
     /// Add a new tab at the end (or after active tab based on preference)
     pub fn add_tab(&mut self, document_id: DocumentId, title: String) {
-        let tab = Tab::new(document_id, title);
+        self.insert_tab(Tab::new(document_id, title));
+    }
 
+    /// Add an existing tab after the active one and make it active
+    pub fn insert_tab(&mut self, tab: Tab) {
         // Insert after active tab if there is one, otherwise at end
         let insert_index = self
             .active_index
@@ -92,6 +95,13 @@ impl TabState {
         }
     }
 
+    /// Remove a tab by document ID and return it
+    pub fn take_tab(&mut self, document_id: DocumentId) -> Option<Tab> {
+        let tab = self.tabs.get(self.find_tab_index(document_id)?).cloned();
+        self.remove_tab(document_id);
+        tab
+    }
+
     /// Set the active tab by document ID
     pub fn set_active(&mut self, document_id: DocumentId) {
         if let Some(index) = self.find_tab_index(document_id) {
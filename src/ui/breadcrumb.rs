@@ -0,0 +1,101 @@
+//! Breadcrumb path bar
+//!
+//! Shows where the document in a pane lives: its folders relative to the
+//! workspace root, the file name, and the heading of the section the
+//! cursor is in. Clicking a folder reveals it in the sidebar.
+
+use std::path::{Path, PathBuf};
+
+use crate::fl;
+use crate::message::{Message, ViewMessage};
+use crate::state::Document;
+use crate::utils::path::{contract_tilde, relative_to};
+use cosmic::iced::Length;
+use cosmic::widget::{button, container, text, Row};
+use cosmic::Element;
+
+/// One segment of the breadcrumb
+struct Crumb {
+    label: String,
+    /// Folder revealed in the sidebar when the segment is clicked
+    folder: Option<PathBuf>,
+}
+
+/// Segments for a document's path
+///
+/// A file inside the workspace starts at the workspace folder, and each
+/// folder can be revealed. Other files show their whole path, with the
+/// home directory as `~`.
+fn path_crumbs(path: Option<&Path>, root: Option<&Path>) -> Vec<Crumb> {
+    let Some(path) = path else {
+        return vec![Crumb {
+            label: fl!("breadcrumb-untitled"),
+            folder: None,
+        }];
+    };
+
+    let inside = root.and_then(|root| Some((root, relative_to(path, root)?)));
+    let Some((root, relative)) = inside else {
+        return contract_tilde(path)
+            .iter()
+            .map(|part| Crumb {
+                label: part.to_string_lossy().into_owned(),
+                folder: None,
+            })
+            .collect();
+    };
+
+    let mut crumbs = vec![Crumb {
+        label: root
+            .file_name()
+            .map_or_else(|| root.display().to_string(), |n| n.to_string_lossy().into_owned()),
+        folder: Some(root.to_path_buf()),
+    }];
+    let mut folder = root.to_path_buf();
+    let parts: Vec<_> = relative.iter().collect();
+    for (i, part) in parts.iter().enumerate() {
+        folder.push(part);
+        let is_file = i + 1 == parts.len();
+        crumbs.push(Crumb {
+            label: part.to_string_lossy().into_owned(),
+            folder: (!is_file).then(|| folder.clone()),
+        });
+    }
+    crumbs
+}
+
+/// Build the breadcrumb for a document
+pub fn build_breadcrumb<'a>(
+    doc: &Document,
+    root: Option<&Path>,
+    text_size: f32,
+) -> Element<'a, Message> {
+    let mut crumbs = path_crumbs(doc.path.as_deref(), root);
+    if let Some(heading) = doc.section_heading(doc.editor_state.cursor.line) {
+        crumbs.push(Crumb {
+            label: heading,
+            folder: None,
+        });
+    }
+
+    let mut row = Row::new().spacing(4);
+    for (i, crumb) in crumbs.into_iter().enumerate() {
+        if i > 0 {
+            row = row.push(text("›").size(text_size));
+        }
+        row = match crumb.folder {
+            Some(folder) => row.push(
+                button::custom(text(crumb.label).size(text_size))
+                    .on_press(Message::View(ViewMessage::RevealInSidebar(folder)))
+                    .class(cosmic::theme::Button::Text)
+                    .padding([0, 2]),
+            ),
+            None => row.push(text(crumb.label).size(text_size)),
+        };
+    }
+
+    container(row.align_y(cosmic::iced::Alignment::Center))
+        .width(Length::Fill)
+        .padding([2, 12])
+        .into()
+}
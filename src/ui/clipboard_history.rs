@@ -0,0 +1,75 @@
+//! Clipboard history picker component
+//!
+//! Lists recent copies for Paste from History; clicking an entry pastes it,
+//! and Paste pastes the highlighted one.
+
+use crate::message::{ClipboardMessage, Message};
+use crate::state::ClipboardPicker;
+use cosmic::iced::Length;
+use cosmic::widget::{button, container, scrollable, text, Column, Row};
+use cosmic::Element;
+
+/// Longest entry preview shown, in characters
+const PREVIEW_CHARS: usize = 80;
+
+/// Build the clipboard history picker widget
+pub fn build_clipboard_history<'a>(picker: &ClipboardPicker, text_size: f32) -> Element<'a, Message> {
+    let close_button = button::icon(cosmic::widget::icon::from_name("window-close-symbolic"))
+        .on_press(Message::Clipboard(ClipboardMessage::CloseHistory))
+        .padding(4);
+
+    let header = Row::new()
+        .push(
+            container(text("Paste from History  (Ctrl+Alt+V for older, Ctrl+V to paste)").size(text_size))
+                .width(Length::Fill),
+        )
+        .push(close_button)
+        .spacing(8);
+
+    let mut entries = Column::new();
+    for (index, entry) in picker.entries.iter().enumerate() {
+        let class = if index == picker.selected {
+            cosmic::theme::Button::Suggested
+        } else {
+            cosmic::theme::Button::Text
+        };
+        entries = entries.push(
+            button::custom(text(entry_preview(entry)).size(text_size))
+                .on_press(Message::Clipboard(ClipboardMessage::PasteHistoryEntry(index)))
+                .class(class)
+                .width(Length::Fill)
+                .padding([2, 4]),
+        );
+    }
+
+    let content = Column::new()
+        .push(header)
+        .push(
+            scrollable(entries)
+                .width(Length::Fill)
+                .height(Length::Fill),
+        )
+        .spacing(4)
+        .padding(8);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fixed(180.0))
+        .class(cosmic::theme::Container::Card)
+        .into()
+}
+
+/// First line of an entry, shortened, with a count of the lines left out
+fn entry_preview(entry: &str) -> String {
+    let mut lines = entry.lines();
+    let first = lines.next().unwrap_or("").trim_end();
+    let mut preview: String = first.chars().take(PREVIEW_CHARS).collect();
+    if first.chars().count() > PREVIEW_CHARS {
+        preview.push('…');
+    }
+    match lines.count() {
+        0 => preview,
+        1 => format!("{}  (+1 line)", preview),
+        n => format!("{}  (+{} lines)", preview, n),
+    }
+}
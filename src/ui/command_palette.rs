@@ -0,0 +1,69 @@
+//! Command palette component
+//!
+//! A filter box over the list of menu commands. Enter runs the highlighted
+//! command and clicking runs any enabled one; commands that can't run now
+//! are shown without a click action.
+
+use crate::fl;
+use crate::message::{DialogMessage, Message};
+use crate::state::CommandPalette;
+use cosmic::iced::Length;
+use cosmic::widget::{button, container, scrollable, text, text_input, Column, Row};
+use cosmic::Element;
+use once_cell::sync::Lazy;
+
+/// Id of the filter box, focused when the palette opens
+pub static COMMAND_PALETTE_INPUT: Lazy<cosmic::widget::Id> =
+    Lazy::new(|| cosmic::widget::Id::new("command-palette-input"));
+
+/// Build the command palette widget
+pub fn build_command_palette<'a>(palette: &'a CommandPalette, text_size: f32) -> Element<'a, Message> {
+    let input = text_input(fl!("command-palette-placeholder"), &palette.query)
+        .id(COMMAND_PALETTE_INPUT.clone())
+        .on_input(|query| Message::Dialog(DialogMessage::CommandPaletteInput(query)))
+        .on_submit(|_| Message::Dialog(DialogMessage::ExecuteSelectedCommand))
+        .width(Length::Fill);
+
+    let mut entries = Column::new();
+    for (index, entry) in palette.matches().enumerate() {
+        let mut line = Row::new()
+            .push(container(text(entry.label.clone()).size(text_size)).width(Length::Fill));
+        if let Some(shortcut) = &entry.shortcut {
+            line = line.push(text(shortcut.clone()).size(text_size));
+        }
+        let class = if index == palette.selected {
+            cosmic::theme::Button::Suggested
+        } else {
+            cosmic::theme::Button::Text
+        };
+        let item = button::custom(line)
+            .class(class)
+            .width(Length::Fill)
+            .padding([2, 4]);
+        // A button without a press action is drawn greyed out
+        entries = entries.push(if entry.enabled {
+            item.on_press(Message::Dialog(DialogMessage::ExecuteCommand(entry.action)))
+        } else {
+            item
+        });
+    }
+    if palette.matches().next().is_none() {
+        entries = entries.push(text(fl!("command-palette-empty")).size(text_size));
+    }
+
+    let content = Column::new()
+        .push(input)
+        .push(
+            scrollable(entries)
+                .width(Length::Fill)
+                .height(Length::Fill),
+        )
+        .spacing(4)
+        .padding(8);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fixed(260.0))
+        .class(cosmic::theme::Container::Card)
+        .into()
+}
@@ -0,0 +1,134 @@
+//! Changes panel component
+//!
+//! Shows the lines added and removed since the document was last saved.
+//! Edited lines highlight the words that changed, and edits that only
+//! change whitespace are dimmed.
+
+use crate::editor::diff::{diff_stats, DiffKind, DiffLine};
+use crate::message::{Message, ViewMessage};
+use crate::state::DiffView;
+use cosmic::iced::{Background, Color, Length};
+use cosmic::widget::{button, container, scrollable, text, Column, Row};
+use cosmic::Element;
+
+/// How strongly a piece of a diff line is highlighted
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Highlight {
+    /// Text that didn't change in an edited line
+    Unchanged,
+    /// A line that was added or removed whole
+    Line,
+    /// Words that changed in an edited line
+    Word,
+    /// Whitespace that changed
+    Whitespace,
+}
+
+/// A piece of a diff line, colored for its kind of change
+fn segment<'a>(
+    content: String,
+    kind: DiffKind,
+    highlight: Highlight,
+    dimmed: bool,
+    size: f32,
+) -> Element<'a, Message> {
+    container(text(content).size(size))
+        .class(cosmic::theme::Container::custom(move |theme| {
+            let cosmic = theme.cosmic();
+            let color: Color = match kind {
+                DiffKind::Added => cosmic.success_color().into(),
+                DiffKind::Removed => cosmic.destructive_color().into(),
+                DiffKind::Equal => return Default::default(),
+            };
+            let alpha = match highlight {
+                Highlight::Unchanged => 0.1,
+                Highlight::Line | Highlight::Whitespace => 0.2,
+                Highlight::Word => 0.45,
+            };
+            let text_color: Color = cosmic.on_bg_color().into();
+            cosmic::iced::widget::container::Style {
+                background: Some(Background::Color(Color { a: alpha, ..color })),
+                text_color: dimmed.then(|| Color { a: 0.5, ..text_color }),
+                ..Default::default()
+            }
+        }))
+        .into()
+}
+
+/// Build one diff line, highlighting its changed words when it was edited
+fn view_line<'a>(line: &DiffLine, size: f32) -> Element<'a, Message> {
+    let number = line
+        .new_line
+        .or(line.old_line)
+        .map(|n| format!("{:>5}", n + 1))
+        .unwrap_or_default();
+    let prefix = text(format!("{} {} ", number, line.kind.prefix())).size(size);
+
+    let mut row = Row::new().push(prefix);
+    match (&line.words, line.kind) {
+        (_, DiffKind::Equal) => row = row.push(text(line.text.clone()).size(size)),
+        (None, kind) => row = row.push(segment(line.text.clone(), kind, Highlight::Line, false, size)),
+        (Some(words), kind) => {
+            let dimmed = line.is_whitespace_change();
+            let mut end = 0;
+            for word in words {
+                if word.range.start > end {
+                    let unchanged = line.text[end..word.range.start].to_string();
+                    row = row.push(segment(unchanged, kind, Highlight::Unchanged, dimmed, size));
+                }
+                let highlight = if word.whitespace { Highlight::Whitespace } else { Highlight::Word };
+                let changed = line.text[word.range.clone()].to_string();
+                row = row.push(segment(changed, kind, highlight, dimmed, size));
+                end = word.range.end;
+            }
+            if end < line.text.len() {
+                let rest = line.text[end..].to_string();
+                row = row.push(segment(rest, kind, Highlight::Unchanged, dimmed, size));
+            }
+        }
+    }
+    row.into()
+}
+
+/// Build the changes panel widget
+pub fn build_diff_panel<'a>(view: &'a DiffView, text_size: f32) -> Element<'a, Message> {
+    let (added, removed) = diff_stats(&view.lines);
+
+    let summary = if view.all_new {
+        format!("All new: no saved version ({} lines)", added)
+    } else if added == 0 && removed == 0 {
+        "No changes since last save".to_string()
+    } else {
+        format!("Changes since last save: +{} -{}", added, removed)
+    };
+
+    let close_button = button::icon(cosmic::widget::icon::from_name("window-close-symbolic"))
+        .on_press(Message::View(ViewMessage::HideChanges))
+        .padding(4);
+
+    let header = Row::new()
+        .push(container(text(summary).size(text_size)).width(Length::Fill))
+        .push(close_button)
+        .spacing(8);
+
+    let mut lines = Column::new();
+    for line in &view.lines {
+        lines = lines.push(view_line(line, text_size));
+    }
+
+    let content = Column::new()
+        .push(header)
+        .push(
+            scrollable(lines)
+                .width(Length::Fill)
+                .height(Length::Fill),
+        )
+        .spacing(4)
+        .padding(8);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fixed(220.0))
+        .class(cosmic::theme::Container::Card)
+        .into()
+}
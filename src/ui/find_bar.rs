@@ -2,6 +2,7 @@
 //!
 //! Provides a search bar UI for finding and replacing text in the editor.
 
+use crate::fl;
 use crate::message::{Message, SearchMessage};
 use cosmic::iced::Length;
 use cosmic::widget::{button, container, row, text, text_input, toggler, Column, Row};
@@ -16,6 +17,7 @@ pub struct FindBarState<'a> {
     pub case_sensitive: bool,
     pub whole_word: bool,
     pub use_regex: bool,
+    pub in_selection: bool,
     pub result_count: usize,
     pub current_result: Option<usize>,
 }
@@ -47,20 +49,20 @@ pub fn build_find_bar<'a>(state: &FindBarState<'a>) -> Element<'a, Message> {
 /// Build the find input row
 fn build_find_row<'a>(state: &FindBarState<'a>) -> Element<'a, Message> {
     // Find input
-    let find_input = text_input("Find...", state.query)
+    let find_input = text_input(fl!("search-placeholder"), state.query)
         .on_input(|s| Message::Search(SearchMessage::UpdateQuery(s)))
-        .on_submit(|_| Message::Search(SearchMessage::FindNext))
+        .on_submit(|_| Message::Search(SearchMessage::Commit))
         .width(Length::Fixed(250.0));
 
     // Result count display
     let result_text = if state.result_count > 0 {
         if let Some(current) = state.current_result {
-            format!("{} of {}", current, state.result_count)
+            fl!("search-results", current = current, total = state.result_count)
         } else {
-            format!("{} results", state.result_count)
+            fl!("search-result-count", count = state.result_count)
         }
     } else if !state.query.is_empty() {
-        "No results".to_string()
+        fl!("search-no-results")
     } else {
         String::new()
     };
@@ -102,6 +104,15 @@ fn build_find_row<'a>(state: &FindBarState<'a>) -> Element<'a, Message> {
         })
         .padding([4, 8]);
 
+    let selection_toggle = button::icon(cosmic::widget::icon::from_name("edit-select-all-symbolic"))
+        .on_press(Message::Search(SearchMessage::ToggleInSelection))
+        .class(if state.in_selection {
+            cosmic::theme::Button::Suggested
+        } else {
+            cosmic::theme::Button::Standard
+        })
+        .padding(4);
+
     // Close button
     let close_button = button::icon(cosmic::widget::icon::from_name("window-close-symbolic"))
         .on_press(Message::Search(SearchMessage::CloseFind))
@@ -116,6 +127,7 @@ fn build_find_row<'a>(state: &FindBarState<'a>) -> Element<'a, Message> {
         .push(case_toggle)
         .push(word_toggle)
         .push(regex_toggle)
+        .push(selection_toggle)
         .push(container(Row::new()).width(Length::Fill)) // Flex spacer
         .push(close_button)
         .spacing(4)
@@ -126,16 +138,16 @@ fn build_find_row<'a>(state: &FindBarState<'a>) -> Element<'a, Message> {
 /// Build the replace input row
 fn build_replace_row<'a>(state: &FindBarState<'a>) -> Element<'a, Message> {
     // Replace input
-    let replace_input = text_input("Replace with...", state.replace_text)
+    let replace_input = text_input(fl!("search-replace-placeholder"), state.replace_text)
         .on_input(|s| Message::Search(SearchMessage::UpdateReplaceText(s)))
         .width(Length::Fixed(250.0));
 
     // Replace buttons
-    let replace_button = button::text("Replace")
+    let replace_button = button::text(fl!("search-replace"))
         .on_press(Message::Search(SearchMessage::Replace))
         .padding([4, 8]);
 
-    let replace_all_button = button::text("Replace All")
+    let replace_all_button = button::text(fl!("search-replace-all"))
         .on_press(Message::Search(SearchMessage::ReplaceAll))
         .padding([4, 8]);
 
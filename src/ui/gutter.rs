@@ -0,0 +1,92 @@
+//! Gutter at the left edge of the editor, with line numbers and markers
+//! next to bookmarks, changed lines and broken links
+//!
+//! The text editor doesn't report where it is scrolled to, so the gutter
+//! follows the top line tracked in the document's editor state, like the
+//! minimap. Each row is one line high, so lines that wrap push the text
+//! out of step with the gutter until the next unwrapped line.
+
+use crate::config::LineNumbers;
+use crate::editor::widget::{gutter_number, gutter_width};
+use crate::message::Message;
+use crate::state::{AppState, Document};
+use cosmic::iced::widget::Space;
+use cosmic::iced::{alignment, Length};
+use cosmic::widget::{column, container, row, text};
+use cosmic::Element;
+
+/// Space on each side of the line numbers
+const GUTTER_PADDING: u16 = 8;
+
+/// Narrowest line number column, in characters
+const MIN_NUMBER_WIDTH: usize = 3;
+
+/// Marker shown next to a line, or a space for an unmarked line
+///
+/// A bookmark is shown ahead of a broken link's warning sign, and both
+/// ahead of a change marker.
+fn marker(doc: &Document, line: usize) -> char {
+    if doc.editor_state.bookmarks.contains(&line) {
+        return '◆';
+    }
+    if doc.link_diagnostics.iter().any(|link| link.line == line) {
+        return '⚠';
+    }
+    doc.gutter_markers()
+        .iter()
+        .find(|change| change.line == line)
+        .map_or(' ', |change| change.kind.symbol())
+}
+
+/// Build the gutter for a document, or `None` when it has nothing to show
+///
+/// `top_padding` is the editor's own padding above its first line.
+pub fn build_gutter<'a>(
+    state: &AppState,
+    doc: &Document,
+    top_padding: f32,
+) -> Option<Element<'a, Message>> {
+    let mode = state.line_numbers;
+    let show_markers = !doc.gutter_markers().is_empty()
+        || !doc.link_diagnostics.is_empty()
+        || !doc.editor_state.bookmarks.is_empty();
+    if mode == LineNumbers::Off && !show_markers {
+        return None;
+    }
+    let line_count = doc.line_count();
+    let cursor_line = doc.editor_state.cursor.line;
+
+    let size = state.scaled(state.editor_font_size);
+    let line_height = state.editor_line_height();
+    let number_width = gutter_width(MIN_NUMBER_WIDTH, line_count);
+    let lines = state.editor_visible_lines(doc);
+
+    let mut rows = column::with_capacity(lines.len() + 1).push(Space::with_height(top_padding));
+    for line in lines {
+        let mut cells = row::with_capacity(2).spacing(GUTTER_PADDING);
+        if show_markers {
+            cells = cells.push(
+                text(marker(doc, line).to_string())
+                    .size(size)
+                    .class(cosmic::theme::Text::Accent),
+            );
+        }
+        if let Some(number) = gutter_number(mode, line, cursor_line) {
+            cells =
+                cells.push(text(format!("{:>width$}", number, width = number_width)).size(size));
+        }
+        rows = rows.push(
+            container(cells)
+                .height(Length::Fixed(line_height))
+                .align_y(alignment::Vertical::Center),
+        );
+    }
+
+    Some(
+        container(rows)
+            .height(Length::Fill)
+            .padding([0, GUTTER_PADDING, 0, GUTTER_PADDING])
+            .clip(true)
+            .into(),
+    )
+}
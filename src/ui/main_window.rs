@@ -3,12 +3,23 @@
 //! Handles the overall window structure including sidebar, editor area,
 //! tab bar, find bar, and status bar arrangement.
 
-use crate::config::ViewMode;
-use crate::message::{EditorMessage, Message};
-use crate::state::{AppState, DocumentId};
+use crate::config::{SidebarPosition, ViewMode};
+use crate::fl;
+use crate::markdown::DiagramOutput;
+use crate::message::{
+    ClipboardMessage, CopyPathKind, CursorMotion, EditorMessage, Message, SystemMessage, TabMessage, ViewMessage,
+};
+use crate::state::{AppState, DocumentId, Pane, SplitOrientation, StatusLevel, TabState};
+use crate::ui::breadcrumb::build_breadcrumb;
+use crate::ui::clipboard_history::build_clipboard_history;
+use crate::ui::command_palette::build_command_palette;
+use crate::ui::diff_panel::build_diff_panel;
 use crate::ui::find_bar::{build_find_bar, FindBarState};
+use crate::ui::problems_panel::build_problems_panel;
+use crate::ui::status_bar::{code_block_picker, save_status_label, StatusBarInfo};
 use cosmic::iced::Length;
-use cosmic::widget::{container, text, text_editor, Column, Row};
+use cosmic::widget::menu::action::MenuAction;
+use cosmic::widget::{button, container, scrollable, svg, text, text_editor, Column, Row};
 use cosmic::Element;
 use std::collections::HashMap;
 
@@ -17,7 +28,7 @@ pub fn view<'a>(
     state: &'a AppState,
     editor_contents: &'a HashMap<DocumentId, text_editor::Content>,
 ) -> Element<'a, Message> {
-    match state.view_mode {
+    match state.effective_view_mode() {
         ViewMode::Edit => view_edit_mode(state, editor_contents),
         ViewMode::Preview => view_preview_mode(state),
         ViewMode::Split => view_split_mode(state, editor_contents),
@@ -31,19 +42,22 @@ fn view_edit_mode<'a>(
 ) -> Element<'a, Message> {
     let mut main_row = Row::new();
 
-    // Sidebar (if visible)
-    if state.sidebar.visible {
-        let sidebar = build_sidebar_simple(state);
-        main_row = main_row.push(
-            container(sidebar)
-                .width(Length::Fixed(state.sidebar.width as f32))
-                .height(Length::Fill),
-        );
-    }
-
     // Editor area (tabs + editor + status)
     let editor_area = build_editor_area(state, editor_contents);
-    main_row = main_row.push(editor_area);
+
+    // Sidebar (if visible), with the resize handle on its editor side
+    if state.sidebar.visible {
+        let sidebar = container(build_sidebar_simple(state))
+            .width(Length::Fixed(state.sidebar.width as f32))
+            .height(Length::Fill);
+        let handle = build_sidebar_handle();
+        main_row = match state.sidebar.position {
+            SidebarPosition::Left => main_row.push(sidebar).push(handle).push(editor_area),
+            SidebarPosition::Right => main_row.push(editor_area).push(handle).push(sidebar),
+        };
+    } else {
+        main_row = main_row.push(editor_area);
+    }
 
     container(main_row)
         .width(Length::Fill)
@@ -63,15 +77,13 @@ fn view_preview_mode(state: &AppState) -> Element<'_, Message> {
 
     Column::new()
         .push(
-            container(text(preview_text))
+            container(build_preview_text(state, preview_text))
                 .width(Length::Fill)
                 .height(Length::Fill)
                 .padding(20),
         )
         .push(
-            container(text(status_text).size(12))
-                .width(Length::Fill)
-                .padding([4, 12]),
+            build_status_bar(state, status_text),
         )
         .into()
 }
@@ -91,7 +103,7 @@ fn view_split_mode<'a>(
     // Editor side
     let editor_view = if let Some(doc_id) = state.active_document {
         if let Some(content) = editor_contents.get(&doc_id) {
-            build_text_editor(doc_id, content)
+            build_text_editor(state, doc_id, content)
         } else {
             text("No editor content").into()
         }
@@ -100,7 +112,7 @@ fn view_split_mode<'a>(
     };
     
     // Preview side
-    let preview_content = text(doc_content);
+    let preview_content = build_preview_text(state, doc_content);
 
     let split_view = Row::new()
         .push(
@@ -119,9 +131,7 @@ fn view_split_mode<'a>(
     Column::new()
         .push(split_view)
         .push(
-            container(text(status_text).size(12))
-                .width(Length::Fill)
-                .padding([4, 12]),
+            build_status_bar(state, status_text),
         )
         .into()
 }
@@ -155,7 +165,7 @@ pub fn view_distraction_free<'a>(
     // Editor view
     let editor_view: Element<'a, Message> = if let Some(doc_id) = state.active_document {
         if let Some(content) = editor_contents.get(&doc_id) {
-            build_text_editor(doc_id, content)
+            build_text_editor(state, doc_id, content)
         } else {
             text("No editor content").into()
         }
@@ -165,7 +175,7 @@ pub fn view_distraction_free<'a>(
 
     Column::new()
         .push(
-            container(text(title_display).size(12))
+            container(text(title_display).size(state.scaled(12.0)))
                 .width(Length::Fill)
                 .padding([4, 8])
                 .center_x(Length::Fill),
@@ -177,7 +187,7 @@ pub fn view_distraction_free<'a>(
                 .padding([8, 80]), // Wide margins for focus
         )
         .push(
-            container(text(cursor_info).size(11))
+            container(text(cursor_info).size(state.scaled(11.0)))
                 .width(Length::Fill)
                 .padding([2, 8])
                 .center_x(Length::Fill),
@@ -185,6 +195,20 @@ pub fn view_distraction_free<'a>(
         .into()
 }
 
+/// Build the handle dragged to resize the sidebar (double-click resets the width)
+fn build_sidebar_handle<'a>() -> Element<'a, Message> {
+    use cosmic::widget::{divider, mouse_area};
+
+    mouse_area(
+        container(divider::vertical::default())
+            .center_x(Length::Fixed(6.0))
+            .height(Length::Fill),
+    )
+    .on_press(Message::View(ViewMessage::StartSidebarResize))
+    .on_release(Message::View(ViewMessage::EndSidebarResize))
+    .into()
+}
+
 /// Build a simple sidebar view
 fn build_sidebar_simple(state: &AppState) -> Element<'_, Message> {
     use cosmic::widget::divider;
@@ -199,41 +223,39 @@ fn build_sidebar_simple(state: &AppState) -> Element<'_, Message> {
             .unwrap_or_else(|| "Files".to_string());
         
         Column::new()
-            .push(text(root_name).size(14))
-            .push(text(format!("{} items", entries_count)).size(12))
+            .push(text(root_name).size(state.scaled(14.0)))
+            .push(text(format!("{} items", entries_count)).size(state.scaled(12.0)))
             .spacing(4)
     } else {
         // No folder open - show prompt
         Column::new()
-            .push(text("No folder open").size(14))
-            .push(text("Use Ctrl+Shift+O to").size(11))
-            .push(text("open a folder").size(11))
+            .push(text("No folder open").size(state.scaled(14.0)))
+            .push(text("Use Ctrl+Shift+O to").size(state.scaled(11.0)))
+            .push(text("open a folder").size(state.scaled(11.0)))
             .spacing(4)
     };
 
-    // File list content
-    let file_list_text = if entries_count > 0 {
-        "Files will appear here"
-    } else if state.sidebar.root.is_some() {
-        "Folder is empty"
-    } else {
-        ""
-    };
+    let mut content = Column::new().push(
+        container(header)
+            .width(Length::Fill)
+            .padding(8)
+    );
 
-    let content = Column::new()
-        .push(
-            container(header)
-                .width(Length::Fill)
-                .padding(8)
-        )
-        .push(divider::horizontal::default())
-        .push(
-            container(text(file_list_text).size(11))
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .padding(8)
-        )
-        .spacing(0);
+    // Toolbar, name prompt, and the file tree of the open folder
+    if state.sidebar.root.is_some() {
+        content = content.push(
+            container(super::sidebar::view_sidebar_toolbar(&state.sidebar)).padding([0, 4]),
+        );
+        content = content
+            .push(container(super::sidebar::view_file_filter(&state.sidebar)).padding([0, 8]));
+        if let Some(prompt) = &state.sidebar.new_entry {
+            content = content
+                .push(container(super::sidebar::view_new_entry_prompt(prompt)).padding([4, 8]));
+        }
+        content = content
+            .push(divider::horizontal::default())
+            .push(container(super::sidebar::view_file_list(&state.sidebar)).padding(4));
+    }
 
     container(content)
         .width(Length::Fill)
@@ -243,26 +265,27 @@ fn build_sidebar_simple(state: &AppState) -> Element<'_, Message> {
 }
 
 /// Build a simple editor area with the text_editor widget
+///
+/// A split editor shows two panes, each with its own tab bar; the find bar
+/// sits in the focused pane.
 fn build_editor_area<'a>(
     state: &'a AppState,
     editor_contents: &'a HashMap<DocumentId, text_editor::Content>,
 ) -> Element<'a, Message> {
-    // Tab bar info
-    let tab_info = build_tab_bar_text(state);
+    use cosmic::widget::divider;
 
     // Status bar info
     let status_text = build_status_text(state);
 
     let mut column = Column::new();
 
-    // Tab bar
-    column = column.push(
-        container(text(tab_info).size(13))
-            .width(Length::Fill)
-            .padding([6, 12]),
-    );
+    // Command palette (if open)
+    if let Some(palette) = &state.command_palette {
+        column = column.push(build_command_palette(palette, state.scaled(13.0)));
+    }
 
     // Find bar (if open)
+    let mut find_bar = None;
     if state.find_dialog_open {
         let find_result_count = state
             .active_document()
@@ -280,20 +303,128 @@ fn build_editor_area<'a>(
             case_sensitive: state.find_case_sensitive,
             whole_word: state.find_whole_word,
             use_regex: state.find_use_regex,
+            in_selection: state.find_in_selection,
             result_count: find_result_count,
             current_result: current_find_result,
         };
-        column = column.push(build_find_bar(&find_state));
+        find_bar = Some(build_find_bar(&find_state));
+    }
+
+    // Editor panes
+    if let Some(split) = &state.split {
+        let first_find_bar = if split.focused == Pane::First {
+            find_bar.take()
+        } else {
+            None
+        };
+        let first = build_pane(state, Pane::First, first_find_bar, editor_contents);
+        let second = build_pane(state, Pane::Second, find_bar, editor_contents);
+        let panes: Element<'a, Message> = match split.orientation {
+            SplitOrientation::Vertical => Row::new()
+                .push(first)
+                .push(divider::vertical::default())
+                .push(second)
+                .into(),
+            SplitOrientation::Horizontal => Column::new()
+                .push(first)
+                .push(divider::horizontal::default())
+                .push(second)
+                .into(),
+        };
+        column = column.push(panes);
+    } else {
+        column = column.push(build_pane(state, Pane::First, find_bar, editor_contents));
+    }
+
+    // Changes panel (if open for the active document)
+    if let Some(diff_view) = state
+        .diff_view
+        .as_ref()
+        .filter(|v| Some(v.document_id) == state.active_document)
+    {
+        column = column.push(build_diff_panel(diff_view, state.scaled(12.0)));
+    }
+
+    // Problems panel (lint issues and broken links)
+    if let Some(doc) = state.active_document().filter(|_| state.problems_panel_open) {
+        column = column.push(build_problems_panel(doc, state.scaled(12.0)));
+    }
+
+    // Clipboard history picker
+    if let Some(picker) = &state.clipboard_picker {
+        column = column.push(build_clipboard_history(picker, state.scaled(12.0)));
+    }
+
+    // Status bar
+    column = column.push(
+        build_status_bar(state, status_text),
+    );
+
+    container(column)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+/// Build one editor pane: its tab bar, the find bar, and its active document
+///
+/// The focused pane of a split editor is drawn as a card so it's clear
+/// where typing goes.
+fn build_pane<'a>(
+    state: &'a AppState,
+    pane: Pane,
+    find_bar: Option<Element<'a, Message>>,
+    editor_contents: &'a HashMap<DocumentId, text_editor::Content>,
+) -> Element<'a, Message> {
+    let focused = pane == state.focused_pane();
+    let tabs = state.pane_tabs(pane);
+    let shown = if focused {
+        state.active_document
+    } else {
+        tabs.active_tab()
+    };
+
+    let mut column = Column::new();
+
+    // Tab bar, with a context menu for the shown document
+    let tab_bar = container(text(build_tab_bar_text(state, tabs, shown)).size(state.scaled(13.0)))
+        .width(Length::Fill)
+        .padding([6, 12]);
+    column = match shown {
+        Some(id) => {
+            use cosmic::widget::menu::Item;
+
+            let copy_path = |label, kind| Item::Button(label, None, TabAction::CopyPath(id, kind));
+            let menu = cosmic::widget::menu::items(
+                &HashMap::new(),
+                vec![
+                    copy_path(fl!("edit-copy-file-path"), CopyPathKind::Absolute),
+                    copy_path(fl!("edit-copy-relative-path"), CopyPathKind::Relative),
+                    copy_path(fl!("edit-copy-file-name"), CopyPathKind::FileName),
+                ],
+            );
+            column.push(cosmic::widget::context_menu(tab_bar, Some(menu)))
+        }
+        None => column.push(tab_bar),
+    };
+
+    // Breadcrumb for the shown document
+    if let Some(doc) = shown.and_then(|id| state.documents.get(&id)) {
+        column = column.push(build_breadcrumb(doc, state.workspace_root(), state.scaled(12.0)));
+    }
+
+    if let Some(find_bar) = find_bar {
+        column = column.push(find_bar);
     }
 
     // Editor content
     if state.documents.is_empty() {
         // Welcome screen
         let welcome = Column::new()
-            .push(text("Welcome to Cosmic Notebook").size(24))
+            .push(text("Welcome to Cosmic Notebook").size(state.scaled(24.0)))
             .push(text(""))
-            .push(text("Press Ctrl+N to create a new file"))
-            .push(text("Press Ctrl+O to open a file"))
+            .push(text("Press Ctrl+N to create a new file").size(state.scaled(14.0)))
+            .push(text("Press Ctrl+O to open a file").size(state.scaled(14.0)))
             .spacing(8);
 
         column = column.push(
@@ -303,10 +434,10 @@ fn build_editor_area<'a>(
                 .center_x(Length::Fill)
                 .center_y(Length::Fill),
         );
-    } else if let Some(doc_id) = state.active_document {
+    } else if let Some(doc_id) = shown {
         // Show interactive text editor
         if let Some(content) = editor_contents.get(&doc_id) {
-            let editor_widget = build_text_editor(doc_id, content);
+            let editor_widget = build_text_editor(state, doc_id, content);
             column = column.push(
                 container(editor_widget)
                     .width(Length::Fill)
@@ -325,44 +456,263 @@ fn build_editor_area<'a>(
         }
     }
 
-    // Status bar
-    column = column.push(
-        container(text(status_text).size(12))
-            .width(Length::Fill)
-            .padding([4, 12]),
-    );
-
-    container(column)
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .into()
+    let pane_container = container(column).width(Length::Fill).height(Length::Fill);
+    if focused && state.split.is_some() {
+        pane_container.class(cosmic::theme::Container::Card).into()
+    } else {
+        pane_container.into()
+    }
 }
 
+/// Padding between the text editor's border and its text
+const EDITOR_PADDING: u16 = 10;
+
 /// Build the text editor widget
+///
+/// Rulers from `editor.ruler_column` and whitespace markers are stacked
+/// over it, assuming a monospace font. The gutter is shown at its left, and the minimap at its
+/// right when enabled.
 fn build_text_editor<'a>(
+    state: &AppState,
     doc_id: DocumentId,
     content: &'a text_editor::Content,
 ) -> Element<'a, Message> {
-    text_editor(content)
+    use cosmic::iced::keyboard::{key::Named, Key};
+    use cosmic::widget::text_editor::{Binding, Status};
+
+    use crate::editor::live_preview::{self, LivePreviewHighlighter, LivePreviewSettings};
+
+    let doc = state.get_document(doc_id);
+    let live_preview = LivePreviewSettings {
+        enabled: state.live_preview && doc.is_some_and(|doc| doc.is_markdown()),
+        cursor_line: doc.map_or(0, |doc| doc.editor_state.cursor.line),
+    };
+    let surround_pairs = state.surround_pairs.clone();
+    let editor = text_editor(content)
+        .highlight_with::<LivePreviewHighlighter>(live_preview, live_preview::format)
         .on_action(move |action| {
             Message::Editor(EditorMessage::TextEditorAction {
                 document_id: doc_id,
                 action,
             })
         })
+        .key_binding(move |key_press| {
+            // Ctrl+Tab switches to the previously active document
+            if key_press.key == Key::Named(Named::Tab) && key_press.modifiers.control() {
+                return Some(Binding::Custom(Message::Tab(TabMessage::CycleRecent {
+                    backwards: key_press.modifiers.shift(),
+                })));
+            }
+            // Tab expands snippets and cycles their tab-stops
+            if key_press.key == Key::Named(Named::Tab) && key_press.status == Status::Focused {
+                return Some(Binding::Custom(Message::Editor(EditorMessage::Tab {
+                    document_id: doc_id,
+                    backwards: key_press.modifiers.shift(),
+                })));
+            }
+            // Enter auto-indents the new line
+            if key_press.key == Key::Named(Named::Enter)
+                && key_press.status == Status::Focused
+                && !key_press.modifiers.control()
+                && !key_press.modifiers.alt()
+            {
+                return Some(Binding::Custom(Message::Editor(EditorMessage::Newline {
+                    document_id: doc_id,
+                })));
+            }
+            // Left and right step through bidirectional text in visual
+            // order, and by Unicode words with Ctrl
+            if key_press.status == Status::Focused && !key_press.modifiers.alt() {
+                let word = key_press.modifiers.control();
+                let motion = match key_press.key {
+                    Key::Named(Named::ArrowLeft) if word => Some(CursorMotion::WordLeft),
+                    Key::Named(Named::ArrowRight) if word => Some(CursorMotion::WordRight),
+                    Key::Named(Named::ArrowLeft) => Some(CursorMotion::Left),
+                    Key::Named(Named::ArrowRight) => Some(CursorMotion::Right),
+                    _ => None,
+                };
+                if let Some(motion) = motion {
+                    return Some(Binding::Custom(Message::Editor(EditorMessage::MoveCursor {
+                        document_id: doc_id,
+                        motion,
+                        select: key_press.modifiers.shift(),
+                    })));
+                }
+            }
+            // Surround characters wrap the selection instead of replacing it
+            if key_press.status == Status::Focused
+                && !key_press.modifiers.control()
+                && !key_press.modifiers.alt()
+            {
+                let mut chars = key_press.text.as_deref().unwrap_or_default().chars();
+                if let (Some(open), None) = (chars.next(), chars.next()) {
+                    if surround_pairs.contains_key(&open) {
+                        return Some(Binding::Custom(Message::Editor(EditorMessage::Surround {
+                            document_id: doc_id,
+                            open,
+                        })));
+                    }
+                }
+            }
+            Binding::from_key_press(key_press)
+        })
         .height(Length::Fill)
-        .padding(10)
+        .padding(EDITOR_PADDING)
+        .size(state.scaled(state.editor_font_size));
+
+    let whitespace = doc
+        .filter(|_| state.render_whitespace != crate::config::RenderWhitespace::None)
+        .map(|doc| {
+            let lines = state
+                .editor_visible_lines(doc)
+                .map(|line| {
+                    let text = doc.content.line(line).to_string();
+                    crate::editor::widget::whitespace_markers(
+                        text.trim_end_matches(['\n', '\r']),
+                        state.render_whitespace,
+                        state.preserve_md_hard_breaks,
+                    )
+                })
+                .collect();
+            crate::editor::widget::whitespace_overlay(
+                lines,
+                state.scaled(state.editor_font_size),
+                state.editor_line_height(),
+                EDITOR_PADDING as f32,
+                EDITOR_PADDING as f32,
+            )
+        });
+    let rulers = (!state.rulers.is_empty()).then(|| {
+        crate::editor::widget::ruler_overlay(
+            &state.rulers,
+            state.editor_char_width(),
+            EDITOR_PADDING as f32,
+        )
+    });
+    let editor: Element<'a, Message> = if whitespace.is_none() && rulers.is_none() {
+        editor.into()
+    } else {
+        cosmic::iced::widget::Stack::new()
+            .push(editor)
+            .push_maybe(whitespace)
+            .push_maybe(rulers)
+            .into()
+    };
+
+    // Track the pointer while a file from the sidebar is dragged, so
+    // dropping it links the file where it is let go. The editor is always
+    // wrapped so starting a drag doesn't rebuild its state.
+    let mut drop_area = cosmic::iced::widget::mouse_area(editor);
+    if state.sidebar.file_drag.as_ref().is_some_and(|drag| drag.dragging) {
+        let padding = cosmic::iced::Vector::new(EDITOR_PADDING as f32, EDITOR_PADDING as f32);
+        drop_area = drop_area
+            .on_move(move |point| {
+                Message::Editor(EditorMessage::FileDragOver {
+                    document_id: doc_id,
+                    position: Some(point - padding),
+                })
+            })
+            .on_exit(Message::Editor(EditorMessage::FileDragOver {
+                document_id: doc_id,
+                position: None,
+            }))
+            .interaction(cosmic::iced::mouse::Interaction::Copy);
+    }
+    let editor: Element<'a, Message> = drop_area.into();
+
+    let gutter = doc.and_then(|doc| super::gutter::build_gutter(state, doc, EDITOR_PADDING as f32));
+    let minimap = doc
+        .filter(|_| state.minimap_enabled)
+        .map(|doc| super::minimap::build_minimap(state, doc));
+    if gutter.is_none() && minimap.is_none() {
+        return editor;
+    }
+    Row::with_capacity(3)
+        .push_maybe(gutter)
+        .push(editor)
+        .push_maybe(minimap)
         .into()
 }
 
-/// Build tab bar text representation
-fn build_tab_bar_text(state: &AppState) -> String {
-    if state.tabs.tabs.is_empty() {
+/// Build the preview text with the configured preview font
+///
+/// The active document's rendered diagrams, and any diagram errors, follow
+/// the text.
+fn build_preview_text(state: &AppState, content: String) -> Element<'_, Message> {
+    let diagrams = state.active_document().map_or(&[][..], |doc| doc.diagrams.as_slice());
+    if diagrams.is_empty() {
+        return preview_paragraph(state, content);
+    }
+
+    // Each rendered diagram takes the place of its code block
+    let size = state.scaled(state.preview_font_size);
+    let mut column = Column::new().spacing(12);
+    let mut shown = 0;
+    let blocks = crate::markdown::diagram::diagram_ranges(&content);
+    for (block, diagram) in blocks.into_iter().zip(diagrams) {
+        let (end, widget): (usize, Element<'_, Message>) = match diagram {
+            DiagramOutput::Svg(path) => {
+                (block.start, svg(svg::Handle::from_path(path)).width(Length::Shrink).into())
+            }
+            // The code stays, with the error under it
+            DiagramOutput::Error(error) => (
+                block.end,
+                text(fl!("error-diagram", error = error.clone()))
+                    .size(size)
+                    .class(cosmic::theme::Text::Accent)
+                    .into(),
+            ),
+            // The code stays as it is
+            DiagramOutput::Source => continue,
+        };
+        let before = content[shown..end].trim_matches('\n');
+        if !before.is_empty() {
+            column = column.push(preview_paragraph(state, before.to_string()));
+        }
+        column = column.push(widget);
+        shown = block.end;
+    }
+    let rest = content[shown..].trim_matches('\n');
+    if !rest.is_empty() {
+        column = column.push(preview_paragraph(state, rest.to_string()));
+    }
+    scrollable(column).into()
+}
+
+/// Preview text in the preview font
+fn preview_paragraph(state: &AppState, content: String) -> Element<'_, Message> {
+    let preview = text(content).size(state.scaled(state.preview_font_size));
+    match state.preview_font_family {
+        Some(family) => preview.font(cosmic::iced::Font::with_name(family)).into(),
+        None => preview.into(),
+    }
+}
+
+/// Context menu commands for the document shown in a pane's tab bar
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TabAction {
+    CopyPath(DocumentId, CopyPathKind),
+}
+
+impl MenuAction for TabAction {
+    type Message = Message;
+
+    fn message(&self) -> Self::Message {
+        let TabAction::CopyPath(id, kind) = *self;
+        Message::Clipboard(ClipboardMessage::CopyPath {
+            document_id: Some(id),
+            kind,
+        })
+    }
+}
+
+/// Build tab bar text representation for a pane
+fn build_tab_bar_text(state: &AppState, tabs: &TabState, shown: Option<DocumentId>) -> String {
+    if tabs.tabs.is_empty() {
         return "No documents open".to_string();
     }
 
-    let tabs: Vec<String> = state
-        .tabs
+    let tabs: Vec<String> = tabs
         .tabs
         .iter()
         .map(|tab_entry| {
@@ -371,7 +721,7 @@ fn build_tab_bar_text(state: &AppState) -> String {
                 .map(|d| (d.display_name.clone(), d.modified))
                 .unwrap_or(("?".to_string(), false));
 
-            let prefix = if Some(tab_entry.document_id) == state.active_document {
+            let prefix = if Some(tab_entry.document_id) == shown {
                 "▸ "
             } else {
                 "  "
@@ -385,22 +735,110 @@ fn build_tab_bar_text(state: &AppState) -> String {
     tabs.join("  |  ")
 }
 
+/// Build the status bar, with a language picker inside code blocks
+///
+/// The status message is shown first; clicking it dismisses it, and
+/// messages with details can be expanded below the bar.
+fn build_status_bar<'a>(state: &AppState, status_text: String) -> Element<'a, Message> {
+    let size = state.scaled(12.0);
+    let mut status_row = Row::new()
+        .spacing(8)
+        .align_y(cosmic::iced::Alignment::Center);
+
+    if let Some(status) = &state.status_message {
+        let class = match status.level {
+            StatusLevel::Error => cosmic::theme::Button::Destructive,
+            _ => cosmic::theme::Button::Text,
+        };
+        status_row = status_row.push(
+            button::text(status.text.clone())
+                .on_press(Message::System(SystemMessage::ClearStatus))
+                .class(class)
+                .padding([0, 4]),
+        );
+        if status.details.is_some() {
+            let label = if status.expanded { "Hide details" } else { "Details" };
+            status_row = status_row.push(
+                button::text(label)
+                    .on_press(Message::System(SystemMessage::ToggleStatusDetails))
+                    .class(cosmic::theme::Button::Link)
+                    .padding([0, 4]),
+            );
+        }
+        status_row = status_row.push(text("|").size(size));
+    }
+
+    status_row = status_row
+        .push(text(status_text).size(size))
+        .push(cosmic::widget::horizontal_space());
+
+    let language = state
+        .active_document()
+        .and_then(|doc| doc.editor_state.code_block_language.as_deref());
+    if let Some(language) = language {
+        status_row = status_row.push(code_block_picker(language, size));
+    }
+
+    // Where the active document's changes are kept; a failed recovery
+    // snapshot stands out and shows its error when pressed
+    if let Some(doc) = state.active_document().filter(|_| state.show_save_status) {
+        let label = save_status_label(&doc.save_status);
+        if matches!(doc.save_status, crate::state::SaveStatus::SnapshotFailed(_)) {
+            status_row = status_row.push(
+                button::text(label)
+                    .on_press(Message::System(SystemMessage::ShowSnapshotError))
+                    .class(cosmic::theme::Button::Destructive)
+                    .padding([0, 4]),
+            );
+        } else {
+            status_row = status_row.push(text(label).size(size));
+        }
+    }
+
+    let mut status_bar = Column::new().push(status_row).spacing(4);
+    if let Some(details) = state
+        .status_message
+        .as_ref()
+        .filter(|status| status.expanded)
+        .and_then(|status| status.details.clone())
+    {
+        status_bar = status_bar.push(text(details).size(size));
+    }
+
+    container(status_bar)
+        .width(Length::Fill)
+        .padding([4, 12])
+        .into()
+}
+
 /// Build status bar text
 fn build_status_text(state: &AppState) -> String {
     match state.active_document() {
         Some(doc) => {
-            let cursor = &doc.editor_state.cursor;
-            let lines = doc.line_count();
-            let chars = doc.char_count();
-            let modified = if doc.modified { " ●" } else { "" };
+            let info = StatusBarInfo::from_document(doc);
+            let selection = info
+                .selection_display()
+                .map(|s| format!(" {}", s))
+                .unwrap_or_default();
+            let modified = if info.is_modified { " ●" } else { "" };
+            let read_only = if info.is_readonly { "  |  [Read Only]" } else { "" };
+            let links = info
+                .broken_links_display()
+                .map(|s| format!("  |  {}", s))
+                .unwrap_or_default();
 
             format!(
-                "Ln {}, Col {}  |  {} lines, {} chars  |  UTF-8  |  LF  |  Markdown{}",
-                cursor.line + 1,
-                cursor.column + 1,
-                lines,
-                chars,
-                modified
+                "{}{}  |  {} lines, {} chars  |  {}  |  {}  |  {}{}{}{}",
+                info.cursor_display(),
+                selection,
+                info.line_count,
+                info.char_count,
+                info.encoding,
+                info.line_ending.display_name(),
+                info.language,
+                modified,
+                read_only,
+                links
             )
         }
         None => "Ready".to_string(),
@@ -0,0 +1,81 @@
+//! Minimap of the document at the right edge of the editor
+//!
+//! Draws the shapes from `editor::minimap` as thin colored bars, with the
+//! lines currently in view shaded. Pressing the minimap scrolls the editor
+//! to that part of the document, and dragging keeps scrolling with the
+//! pointer.
+
+use crate::editor::minimap::{self, MAX_COLUMNS, MINIMAP_WIDTH, ROW_HEIGHT};
+use crate::message::{Message, ViewMessage};
+use crate::state::{AppState, Document};
+use cosmic::iced::widget::{mouse_area, Space, Stack};
+use cosmic::iced::{Background, Color, Length};
+use cosmic::widget::{column, container, row};
+use cosmic::Element;
+
+/// Opacity of the shade over the lines in view
+const VIEWPORT_ALPHA: f32 = 0.15;
+
+/// A block filled with a solid color
+fn filled<'a>(width: Length, height: f32, color: Color) -> Element<'a, Message> {
+    container(Space::new(width, Length::Fixed(height)))
+        .class(cosmic::theme::Container::custom(move |_| {
+            cosmic::iced::widget::container::Style {
+                background: Some(Background::Color(color)),
+                ..Default::default()
+            }
+        }))
+        .into()
+}
+
+/// Build the minimap for a document
+pub fn build_minimap<'a>(state: &AppState, doc: &Document) -> Element<'a, Message> {
+    let max_rows = state.minimap_rows();
+    let rows = minimap::downsample(&doc.minimap, max_rows);
+    let column_width = MINIMAP_WIDTH / MAX_COLUMNS as f32;
+
+    let mut lines = column::with_capacity(rows.len());
+    for line in &rows {
+        if line.is_blank() {
+            lines = lines.push(Space::new(Length::Fill, Length::Fixed(ROW_HEIGHT)));
+            continue;
+        }
+        let color = Color { a: 0.6, ..line.color };
+        lines = lines.push(
+            row::with_capacity(2)
+                .push(Space::with_width(line.indent as f32 * column_width))
+                .push(filled(
+                    Length::Fixed((line.length as f32 * column_width).max(1.0)),
+                    ROW_HEIGHT,
+                    color,
+                )),
+        );
+    }
+
+    let view = minimap::viewport_rows(
+        doc.editor_state.scroll_line,
+        state.editor_viewport_lines(),
+        doc.line_count(),
+        max_rows,
+    );
+    let shade_color = if state.dark_mode { Color::WHITE } else { Color::BLACK };
+    let shade = column::with_capacity(2)
+        .push(Space::with_height(view.start as f32 * ROW_HEIGHT))
+        .push(filled(
+            Length::Fill,
+            view.len() as f32 * ROW_HEIGHT,
+            Color { a: VIEWPORT_ALPHA, ..shade_color },
+        ));
+
+    let document_id = doc.id;
+    mouse_area(
+        Stack::new()
+            .push(lines)
+            .push(shade)
+            .width(Length::Fixed(MINIMAP_WIDTH))
+            .height(Length::Fill),
+    )
+    .on_move(|point| Message::View(ViewMessage::MinimapHovered(point.y)))
+    .on_press(Message::View(ViewMessage::MinimapPressed(document_id)))
+    .into()
+}
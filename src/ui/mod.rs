@@ -2,15 +2,31 @@
 //!
 //! Contains all user interface components including:
 //! - Main window layout
+//! - Breadcrumb path bar
 //! - Editor widget
 //! - Sidebar file browser
 //! - Tab bar
 //! - Status bar
 //! - Find bar
+//! - Editor gutter
+//! - Changes panel
+//! - Problems panel
+//! - Clipboard history picker
+//! - Command palette
+//! - Minimap
+//! - Replace All confirmation
 //! - Dialogs and modals
 
+mod breadcrumb;
+mod clipboard_history;
+mod command_palette;
+mod diff_panel;
 mod find_bar;
+mod gutter;
 mod main_window;
+mod minimap;
+mod problems_panel;
+mod replace_all_dialog;
 mod sidebar;
 mod status_bar;
 mod tab_bar;
@@ -21,7 +37,13 @@ use cosmic::widget::text_editor;
 use cosmic::Element;
 use std::collections::HashMap;
 
+pub use breadcrumb::build_breadcrumb;
+pub use clipboard_history::build_clipboard_history;
+pub use command_palette::{build_command_palette, COMMAND_PALETTE_INPUT};
+pub use diff_panel::build_diff_panel;
 pub use find_bar::{build_find_bar, FindBarState};
+pub use problems_panel::build_problems_panel;
+pub use replace_all_dialog::build_replace_all_dialog;
 pub use sidebar::*;
 pub use status_bar::{build_status_info, StatusBar, StatusBarInfo};
 pub use tab_bar::{TabBar, TabContextAction, TabInfo};
@@ -0,0 +1,69 @@
+//! Problems panel component
+//!
+//! Lists lint issues and broken links in the active document; clicking an
+//! entry jumps to its line.
+
+use crate::message::{EditorMessage, Message, ViewMessage};
+use crate::state::Document;
+use cosmic::iced::Length;
+use cosmic::widget::{button, container, scrollable, text, Column, Row};
+use cosmic::Element;
+
+/// Build the problems panel widget
+pub fn build_problems_panel<'a>(doc: &Document, text_size: f32) -> Element<'a, Message> {
+    // (line, column, label, message), in document order
+    let mut problems: Vec<(usize, usize, &str, String)> = doc
+        .link_diagnostics
+        .iter()
+        .map(|d| (d.line, d.start, "link", d.message()))
+        .chain(
+            doc.lint_diagnostics
+                .iter()
+                .map(|d| (d.line, d.col, d.rule.code(), d.message.clone())),
+        )
+        .collect();
+    problems.sort_by_key(|&(line, col, ..)| (line, col));
+
+    let summary = match problems.len() {
+        0 => "No problems".to_string(),
+        1 => "1 problem".to_string(),
+        n => format!("{} problems", n),
+    };
+
+    let close_button = button::icon(cosmic::widget::icon::from_name("window-close-symbolic"))
+        .on_press(Message::View(ViewMessage::HideProblems))
+        .padding(4);
+
+    let header = Row::new()
+        .push(container(text(summary).size(text_size)).width(Length::Fill))
+        .push(close_button)
+        .spacing(8);
+
+    let mut entries = Column::new();
+    for (line, col, label, message) in problems {
+        let entry = format!("Ln {}, Col {}  {}  {}", line + 1, col + 1, label, message);
+        entries = entries.push(
+            button::custom(text(entry).size(text_size))
+                .on_press(Message::Editor(EditorMessage::GoToLine(line + 1)))
+                .class(cosmic::theme::Button::Text)
+                .width(Length::Fill)
+                .padding([2, 4]),
+        );
+    }
+
+    let content = Column::new()
+        .push(header)
+        .push(
+            scrollable(entries)
+                .width(Length::Fill)
+                .height(Length::Fill),
+        )
+        .spacing(4)
+        .padding(8);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fixed(180.0))
+        .class(cosmic::theme::Container::Card)
+        .into()
+}
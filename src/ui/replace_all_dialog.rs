@@ -0,0 +1,47 @@
+//! Replace All confirmation dialog
+//!
+//! Asks before a Replace All that changes many matches, or a regex one
+//! that changes the document's size a lot.
+
+use crate::fl;
+use crate::message::{Message, SearchMessage};
+use crate::state::ReplaceAllConfirm;
+use cosmic::widget::{button, dialog};
+use cosmic::Element;
+
+/// Build the modal asking to confirm a Replace All
+pub fn build_replace_all_dialog<'a>(confirm: &ReplaceAllConfirm) -> Element<'a, Message> {
+    let mut body = fl!("replace-all-confirm-body");
+    if let Some((before, after)) = confirm.size_change {
+        body.push_str("\n\n");
+        body.push_str(&fl!(
+            "replace-all-confirm-size",
+            before = format_size(before),
+            after = format_size(after)
+        ));
+    }
+
+    dialog()
+        .title(fl!("replace-all-confirm-title", count = confirm.count))
+        .body(body)
+        .primary_action(
+            button::destructive(fl!("replace-all-confirm-replace"))
+                .on_press(Message::Search(SearchMessage::ConfirmReplaceAll)),
+        )
+        .secondary_action(
+            button::standard(fl!("replace-all-confirm-cancel"))
+                .on_press(Message::Search(SearchMessage::CancelReplaceAll)),
+        )
+        .into()
+}
+
+/// Format a length in bytes for the dialog
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
@@ -6,12 +6,22 @@
 //! - File icons
 //! - Click handlers
 //! - Keyboard navigation
+//! - Toolbar and context menu for creating files and folders
+//! - Dragging files into the editor to link them
 
-use crate::message::{Message, FileMessage};
-use crate::state::SidebarState;
+use crate::fl;
+use crate::message::{Message, SidebarMessage, ViewMessage};
+use crate::state::{FileFilter, NewEntryKind, NewEntryPrompt, SidebarState};
 use cosmic::iced::Length;
-use cosmic::widget::{container, scrollable, text, Column, Row};
+use cosmic::widget::menu::action::MenuAction;
+use cosmic::widget::{button, container, icon, scrollable, text, text_input, Column, Row};
 use cosmic::Element;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+/// Id of the name input, focused when a new file or folder is started
+pub static NEW_ENTRY_INPUT: Lazy<cosmic::widget::Id> =
+    Lazy::new(|| cosmic::widget::Id::new("sidebar-new-entry-input"));
 
 /// Indentation per depth level in pixels
 const INDENT_PER_LEVEL: u16 = 16;
@@ -28,8 +38,14 @@ pub fn view_sidebar<'a>(state: &'a SidebarState) -> Element<'a, Message> {
     let width = state.width as u16;
 
     // Build the content
-    let content = Column::new()
+    let mut content = Column::new()
         .push(view_search_bar(state))
+        .push(view_sidebar_toolbar(state))
+        .push(view_file_filter(state));
+    if let Some(prompt) = &state.new_entry {
+        content = content.push(view_new_entry_prompt(prompt));
+    }
+    let content = content
         .push(view_file_list(state))
         .spacing(4)
         .padding(4);
@@ -55,8 +71,111 @@ fn view_search_bar<'a>(state: &'a SidebarState) -> Element<'a, Message> {
         .into()
 }
 
+/// Build the toolbar for creating entries, moving up a folder, and
+/// expanding or collapsing the tree
+///
+/// The up button is left out at the filesystem root, and expand-all turns
+/// into a stop button while it runs.
+pub fn view_sidebar_toolbar<'a>(state: &'a SidebarState) -> Element<'a, Message> {
+    let tool = |name: &'static str, message: SidebarMessage| {
+        button::icon(icon::from_name(name))
+            .on_press(Message::Sidebar(message))
+            .padding(4)
+    };
+
+    let mut toolbar = Row::new().spacing(2);
+    if state.can_go_up() {
+        toolbar = toolbar.push(tool("go-up-symbolic", SidebarMessage::NavigateToParent));
+    }
+    let expand = if state.expand_all.is_some() {
+        tool("process-stop-symbolic", SidebarMessage::CancelExpandAll)
+    } else {
+        tool("view-list-tree-symbolic", SidebarMessage::ExpandAll)
+    };
+    toolbar
+        .push(cosmic::widget::horizontal_space())
+        .push(expand)
+        .push(tool("view-list-symbolic", SidebarMessage::CollapseAll))
+        .push(tool("document-new-symbolic", SidebarMessage::NewFile))
+        .push(tool("folder-new-symbolic", SidebarMessage::NewFolder))
+        .push(tool("view-refresh-symbolic", SidebarMessage::Refresh))
+        .align_y(cosmic::iced::Alignment::Center)
+        .into()
+}
+
+/// Build the file filter: markdown only, every file, or a glob
+///
+/// Clearing the glob goes back to markdown only.
+pub fn view_file_filter<'a>(state: &'a SidebarState) -> Element<'a, Message> {
+    let choice = |label: String, filter: FileFilter| {
+        let active = state.filter == filter;
+        button::text(label)
+            .class(if active {
+                cosmic::theme::Button::Suggested
+            } else {
+                cosmic::theme::Button::Text
+            })
+            .on_press(Message::View(ViewMessage::SetSidebarFilter(filter)))
+            .padding([2, 6])
+    };
+    let glob = match &state.filter {
+        FileFilter::Glob(pattern) => pattern.as_str(),
+        _ => "",
+    };
+    let glob_input = text_input(fl!("sidebar-filter-glob-placeholder"), glob)
+        .on_input(|pattern| {
+            let filter = if pattern.trim().is_empty() {
+                FileFilter::Markdown
+            } else {
+                FileFilter::Glob(pattern)
+            };
+            Message::View(ViewMessage::SetSidebarFilter(filter))
+        })
+        .width(Length::Fill);
+
+    Row::new()
+        .push(choice(fl!("sidebar-filter-markdown"), FileFilter::Markdown))
+        .push(choice(fl!("sidebar-filter-all"), FileFilter::All))
+        .push(glob_input)
+        .spacing(4)
+        .align_y(cosmic::iced::Alignment::Center)
+        .into()
+}
+
+/// Build the name prompt for a new file or folder
+///
+/// A name that is already taken is pointed out below the input.
+pub fn view_new_entry_prompt<'a>(prompt: &'a NewEntryPrompt) -> Element<'a, Message> {
+    let placeholder = match prompt.kind {
+        NewEntryKind::File => fl!("sidebar-new-file-placeholder"),
+        NewEntryKind::Folder => fl!("sidebar-new-folder-placeholder"),
+    };
+    let input = text_input(placeholder, &prompt.name)
+        .id(NEW_ENTRY_INPUT.clone())
+        .on_input(|name| Message::Sidebar(SidebarMessage::NewEntryName(name)))
+        .on_submit(|_| Message::Sidebar(SidebarMessage::SubmitNewEntry))
+        .width(Length::Fill);
+    let cancel = button::icon(icon::from_name("window-close-symbolic"))
+        .on_press(Message::Sidebar(SidebarMessage::CancelNewEntry))
+        .padding(4);
+
+    let mut content = Column::new()
+        .push(
+            Row::new()
+                .push(input)
+                .push(cancel)
+                .spacing(4)
+                .align_y(cosmic::iced::Alignment::Center),
+        )
+        .spacing(4);
+    if prompt.exists {
+        content = content.push(text(fl!("sidebar-name-taken", name = prompt.name.trim())).size(12));
+    }
+    content.into()
+}
+
 /// Build the file list view
-fn view_file_list<'a>(state: &'a SidebarState) -> Element<'a, Message> {
+pub fn view_file_list<'a>(state: &'a SidebarState) -> Element<'a, Message> {
     // Handle different states
     if state.is_scanning {
         return container(text("Scanning...").size(14))
@@ -99,7 +218,7 @@ fn view_file_list<'a>(state: &'a SidebarState) -> Element<'a, Message> {
         let is_focused = state.focused_index == Some(index);
         let is_selected = state.selected_path.as_ref() == Some(&entry.path);
 
-        items = items.push(view_file_entry(entry, state, is_focused, is_selected));
+        items = items.push(view_file_entry(index, entry, state, is_focused, is_selected));
     }
 
     scrollable(items)
@@ -124,8 +243,60 @@ fn view_no_folder_open<'a>() -> Element<'a, Message> {
     .into()
 }
 
+/// Context menu commands for a file list entry, by entry index
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum EntryAction {
+    NewFile(usize),
+    NewFolder(usize),
+}
+
+impl MenuAction for EntryAction {
+    type Message = Message;
+
+    fn message(&self) -> Self::Message {
+        let (entry_index, kind) = match *self {
+            EntryAction::NewFile(index) => (index, NewEntryKind::File),
+            EntryAction::NewFolder(index) => (index, NewEntryKind::Folder),
+        };
+        Message::Sidebar(SidebarMessage::NewEntryAt { entry_index, kind })
+    }
+}
+
+/// Background of a file row that is selected or has keyboard focus
+fn file_row_style(
+    is_focused: bool,
+    is_selected: bool,
+) -> impl Fn(&cosmic::Theme) -> cosmic::iced::widget::container::Style {
+    move |theme| {
+        let cosmic = theme.cosmic();
+        let background = if is_selected {
+            Some(cosmic::iced::Color {
+                a: 0.3,
+                ..cosmic.accent_color().into()
+            })
+        } else if is_focused {
+            Some(cosmic.bg_component_color().into())
+        } else {
+            None
+        };
+        cosmic::iced::widget::container::Style {
+            background: background.map(cosmic::iced::Background::Color),
+            border: cosmic::iced::Border {
+                radius: cosmic.corner_radii.radius_s.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
 /// Build a single file entry row
+///
+/// Right-clicking the row offers to create a file or folder next to it,
+/// or inside it for a folder. Files open when released without moving,
+/// and are dragged into the editor when the pointer moves off them.
 fn view_file_entry<'a>(
+    index: usize,
     entry: &'a crate::state::FileEntry,
     state: &'a SidebarState,
     is_focused: bool,
@@ -163,25 +334,52 @@ fn view_file_entry<'a>(
     let path = entry.path.clone();
     let is_dir = entry.is_directory;
 
-    // Use cosmic button for clickability
-    let clickable = cosmic::widget::button::custom(indented)
-        .class(if is_selected {
-            cosmic::theme::Button::Suggested
-        } else if is_focused {
-            cosmic::theme::Button::Standard
-        } else {
-            cosmic::theme::Button::Text
-        })
-        .on_press(if is_dir {
-            Message::Internal(crate::message::InternalMessage::DirectoryScanComplete(vec![]))
-            // TODO: Proper message for folder toggle
-        } else {
-            Message::File(FileMessage::OpenPath(path))
-        })
-        .width(Length::Fill)
-        .padding(0);
+    // Folders are buttons; files take the press themselves so it can
+    // start a drag, which a button would capture
+    let clickable: Element<'a, Message> = if is_dir {
+        cosmic::widget::button::custom(indented)
+            .class(if is_selected {
+                cosmic::theme::Button::Suggested
+            } else if is_focused {
+                cosmic::theme::Button::Standard
+            } else {
+                cosmic::theme::Button::Text
+            })
+            .on_press(Message::Sidebar(SidebarMessage::ToggleFolder(path)))
+            .width(Length::Fill)
+            .padding(0)
+            .into()
+    } else {
+        cosmic::iced::widget::mouse_area(
+            container(indented)
+                .width(Length::Fill)
+                .class(cosmic::theme::Container::custom(file_row_style(
+                    is_focused,
+                    is_selected,
+                ))),
+        )
+        .on_press(Message::Sidebar(SidebarMessage::PressFile(path)))
+        .interaction(cosmic::iced::mouse::Interaction::Pointer)
+        .into()
+    };
+
+    let menu = cosmic::widget::menu::items(
+        &HashMap::new(),
+        vec![
+            cosmic::widget::menu::Item::Button(
+                fl!("sidebar-new-file"),
+                None,
+                EntryAction::NewFile(index),
+            ),
+            cosmic::widget::menu::Item::Button(
+                fl!("sidebar-new-folder"),
+                None,
+                EntryAction::NewFolder(index),
+            ),
+        ],
+    );
 
-    container(clickable)
+    container(cosmic::widget::context_menu(clickable, Some(menu)))
         .width(Length::Fill)
         .height(Length::Fixed(ROW_HEIGHT as f32))
         .into()
@@ -203,9 +401,7 @@ pub fn view_sidebar_header<'a>(state: &'a SidebarState) -> Element<'a, Message>
             cosmic::widget::button::text("⟳")
                 .class(cosmic::theme::Button::Text)
                 .padding([4, 8])
-                .on_press(Message::Internal(
-                    crate::message::InternalMessage::DirectoryScanComplete(vec![]),
-                )),
+                .on_press(Message::Sidebar(SidebarMessage::Refresh)),
         )
         .spacing(8)
         .padding(8)
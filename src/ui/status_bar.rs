@@ -4,12 +4,14 @@
 //! line/column, word count, file encoding, and line endings.
 
 use cosmic::iced::Length;
-use cosmic::widget::{container, horizontal_space, row, text, Row};
+use cosmic::widget::{container, dropdown, horizontal_space, row, text, Row};
 use cosmic::Element;
 
 use crate::editor::buffer::LineEnding;
-use crate::message::Message;
-use crate::state::CursorPosition;
+use crate::editor::formatting::COMMON_CODE_LANGUAGES;
+use crate::message::{EditorMessage, Message};
+use crate::fl;
+use crate::state::{CursorPosition, Document, SaveStatus};
 
 /// Information to display in the status bar
 #[derive(Debug, Clone, Default)]
@@ -38,6 +40,8 @@ pub struct StatusBarInfo {
     pub is_modified: bool,
     /// Read-only status
     pub is_readonly: bool,
+    /// Number of links pointing at missing files or headings
+    pub broken_links: usize,
 }
 
 impl StatusBarInfo {
@@ -50,26 +54,72 @@ impl StatusBarInfo {
         }
     }
 
+    /// Build status bar info from a document's editor state
+    ///
+    /// The word count is left at zero, since it needs a full pass over the text.
+    pub fn from_document(doc: &Document) -> Self {
+        let selection = &doc.editor_state.selection;
+        let has_selection = !selection.is_collapsed();
+        let (selection_chars, selection_lines) = if has_selection {
+            let (start, end) = selection.normalized();
+            let offset = |pos: CursorPosition| {
+                let line = pos.line.min(doc.content.len_lines().saturating_sub(1));
+                (doc.content.line_to_char(line) + pos.column).min(doc.content.len_chars())
+            };
+            let chars = offset(end).saturating_sub(offset(start));
+            (Some(chars), Some(end.line - start.line + 1))
+        } else {
+            (None, None)
+        };
+
+        Self {
+            cursor: doc.editor_state.cursor,
+            has_selection,
+            selection_chars,
+            selection_lines,
+            line_count: doc.line_count(),
+            char_count: doc.char_count(),
+            word_count: 0,
+            encoding: doc.encoding.display_name().to_string(),
+            line_ending: LineEnding::default(),
+            language: if doc.is_markdown() { "Markdown" } else { "Plain Text" }.to_string(),
+            is_modified: doc.modified,
+            is_readonly: doc.read_only,
+            broken_links: doc.link_diagnostics.len(),
+        }
+    }
+
     /// Format cursor position for display
     pub fn cursor_display(&self) -> String {
-        format!("Ln {}, Col {}", self.cursor.line + 1, self.cursor.column + 1)
+        self.cursor.to_string()
     }
 
     /// Format selection info for display
+    ///
+    /// Returns `None` for a collapsed selection.
     pub fn selection_display(&self) -> Option<String> {
         if self.has_selection {
             match (self.selection_chars, self.selection_lines) {
                 (Some(chars), Some(lines)) if lines > 1 => {
-                    Some(format!("{} chars, {} lines selected", chars, lines))
+                    Some(format!("({} selected, {} lines)", chars, lines))
                 }
-                (Some(chars), _) => Some(format!("{} chars selected", chars)),
-                _ => Some("Selection".to_string()),
+                (Some(chars), _) => Some(format!("({} selected)", chars)),
+                _ => Some("(selection)".to_string()),
             }
         } else {
             None
         }
     }
 
+    /// Format the broken link count for display
+    pub fn broken_links_display(&self) -> Option<String> {
+        match self.broken_links {
+            0 => None,
+            1 => Some("⚠ 1 broken link".to_string()),
+            n => Some(format!("⚠ {} broken links", n)),
+        }
+    }
+
     /// Format document statistics
     pub fn stats_display(&self) -> String {
         format!(
@@ -109,6 +159,11 @@ impl StatusBar {
             status_row = status_row.push(text("[Read Only]").size(12));
         }
 
+        // Broken links
+        if let Some(links) = info.broken_links_display() {
+            status_row = status_row.push(text(links).size(12));
+        }
+
         // Document statistics
         status_row = status_row.push(text(info.stats_display()).size(12));
 
@@ -177,6 +232,49 @@ impl StatusBar {
     }
 }
 
+/// Label for where a document's changes are kept
+///
+/// Saves to the file and recovery snapshots read differently, since only
+/// a save leaves the file up to date.
+pub fn save_status_label(status: &SaveStatus) -> String {
+    let time = |time: &std::time::SystemTime| {
+        chrono::DateTime::<chrono::Local>::from(*time)
+            .format("%H:%M")
+            .to_string()
+    };
+    match status {
+        SaveStatus::Saved(None) => fl!("save-status-saved"),
+        SaveStatus::Saved(Some(saved)) => fl!("save-status-saved-at", time = time(saved)),
+        SaveStatus::Saving => fl!("save-status-saving"),
+        SaveStatus::Unsaved => fl!("save-status-unsaved"),
+        SaveStatus::Snapshot(taken) => fl!("save-status-snapshot", time = time(taken)),
+        SaveStatus::SnapshotFailed(_) => fl!("save-status-snapshot-failed"),
+    }
+}
+
+/// Language label and picker for the code block around the cursor
+///
+/// An empty language is shown as "plain text", and picking it clears the
+/// fence's info string.
+pub fn code_block_picker<'a>(language: &str, size: f32) -> Element<'a, Message> {
+    let label = if language.is_empty() { COMMON_CODE_LANGUAGES[0] } else { language };
+    let selected = COMMON_CODE_LANGUAGES.iter().position(|l| *l == label);
+    let picker = dropdown(COMMON_CODE_LANGUAGES, selected, |index| {
+        let language = match index {
+            0 => String::new(),
+            _ => COMMON_CODE_LANGUAGES[index].to_string(),
+        };
+        Message::Editor(EditorMessage::SetCodeBlockLanguage(language))
+    });
+
+    row()
+        .push(text(format!("Code: {}", label)).size(size))
+        .push(picker)
+        .spacing(8)
+        .align_y(cosmic::iced::Alignment::Center)
+        .into()
+}
+
 /// Build StatusBarInfo from an Editor
 pub fn build_status_info(
     editor: &crate::editor::Editor,
@@ -211,5 +309,6 @@ pub fn build_status_info(
         language: language.to_string(),
         is_modified: editor.is_modified(),
         is_readonly,
+        broken_links: 0,
     }
 }
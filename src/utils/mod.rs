@@ -5,45 +5,116 @@
 //! - Path utilities
 //! - Platform-specific helpers
 //! - Text utilities
+//! - Date/time formatting
 
 use std::path::{Path, PathBuf};
 
+/// Which edges of a burst of activity a [`Debouncer`] fires on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebounceEdge {
+    /// Fire on the first trigger after a quiet period
+    #[default]
+    Leading,
+    /// Fire once activity has stopped for the delay
+    Trailing,
+    /// Fire on the first trigger, and again after the burst if it continued
+    Both,
+}
+
 /// Debounce helper for rate-limiting operations
+///
+/// The debouncer keeps no timer of its own. When [`Debouncer::trailing`]
+/// reports a deferred trigger, schedule a message after
+/// [`Debouncer::delay`] (a `Task` that sleeps) and call
+/// [`Debouncer::fire_trailing`] when it arrives; only the timer from the
+/// last trigger of a burst fires. The `_at` variants take the current time
+/// so the timing logic can be tested without sleeping.
+#[derive(Debug, Clone)]
 pub struct Debouncer {
-    delay_ms: u64,
+    delay: std::time::Duration,
+    edge: DebounceEdge,
     last_trigger: Option<std::time::Instant>,
+    trailing_pending: bool,
 }
 
 impl Debouncer {
+    /// Create a leading-edge debouncer
     pub fn new(delay_ms: u64) -> Self {
+        Self::with_edge(std::time::Duration::from_millis(delay_ms), DebounceEdge::Leading)
+    }
+
+    /// Create a debouncer firing on the given edges
+    pub fn with_edge(delay: std::time::Duration, edge: DebounceEdge) -> Self {
         Self {
-            delay_ms,
+            delay,
+            edge,
             last_trigger: None,
+            trailing_pending: false,
         }
     }
 
-    /// Check if enough time has passed since last trigger
+    /// How long activity must pause before a trailing trigger
+    pub fn delay(&self) -> std::time::Duration {
+        self.delay
+    }
+
+    /// Record activity; returns whether the leading edge fires now
     pub fn should_trigger(&mut self) -> bool {
-        let now = std::time::Instant::now();
-        match self.last_trigger {
-            Some(last) => {
-                if now.duration_since(last).as_millis() >= self.delay_ms as u128 {
-                    self.last_trigger = Some(now);
-                    true
-                } else {
-                    false
-                }
-            }
-            None => {
-                self.last_trigger = Some(now);
+        self.should_trigger_at(std::time::Instant::now())
+    }
+
+    /// Record activity at `now`; returns whether the leading edge fires
+    pub fn should_trigger_at(&mut self, now: std::time::Instant) -> bool {
+        let quiet = self
+            .last_trigger
+            .map_or(true, |last| now.duration_since(last) >= self.delay);
+        self.last_trigger = Some(now);
+
+        let leading = quiet && self.edge != DebounceEdge::Trailing;
+        // After a leading fire, `Both` only owes a trailing one if the burst goes on
+        self.trailing_pending = match self.edge {
+            DebounceEdge::Leading => false,
+            DebounceEdge::Trailing => true,
+            DebounceEdge::Both => self.trailing_pending || !leading,
+        };
+        leading
+    }
+
+    /// Whether a deferred trigger should be scheduled after the last activity
+    pub fn trailing(&self) -> bool {
+        self.trailing_pending
+    }
+
+    /// When the pending trailing trigger is due
+    pub fn deadline(&self) -> Option<std::time::Instant> {
+        self.last_trigger
+            .filter(|_| self.trailing_pending)
+            .map(|last| last + self.delay)
+    }
+
+    /// Consume the trailing trigger if it is due
+    ///
+    /// Call this when a scheduled timer arrives. Timers from earlier in a
+    /// burst find the deadline moved on and return `false`.
+    pub fn fire_trailing(&mut self) -> bool {
+        self.fire_trailing_at(std::time::Instant::now())
+    }
+
+    /// Consume the trailing trigger if it is due at `now`
+    pub fn fire_trailing_at(&mut self, now: std::time::Instant) -> bool {
+        match self.deadline() {
+            Some(deadline) if now >= deadline => {
+                self.trailing_pending = false;
                 true
             }
+            _ => false,
         }
     }
 
-    /// Reset the debouncer
+    /// Reset the debouncer, dropping any pending trailing trigger
     pub fn reset(&mut self) {
         self.last_trigger = None;
+        self.trailing_pending = false;
     }
 }
 
@@ -78,6 +149,53 @@ pub mod path {
         path.strip_prefix(base).ok().map(|p| p.to_path_buf())
     }
 
+    /// Path relative to the workspace root, or the whole path when the
+    /// file is outside it or no folder is open
+    pub fn workspace_relative(path: &Path, root: Option<&Path>) -> PathBuf {
+        root.and_then(|root| relative_to(path, root))
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// Path to `path` from the folder `base`, going up with `..` as far as
+    /// needed
+    ///
+    /// None when the two share no ancestor, such as a relative and an
+    /// absolute path.
+    pub fn relative_from(path: &Path, base: &Path) -> Option<PathBuf> {
+        base.ancestors().enumerate().find_map(|(ups, ancestor)| {
+            let rest = relative_to(path, ancestor)?;
+            let mut relative: PathBuf = std::iter::repeat("..").take(ups).collect();
+            relative.push(rest);
+            Some(relative)
+        })
+    }
+
+    /// Compile glob patterns into one matcher
+    ///
+    /// A leading `~` expands to the home directory. Malformed patterns are
+    /// left out and returned, so one typo doesn't discard the others.
+    pub fn glob_set(patterns: &[String]) -> (globset::GlobSet, Vec<String>) {
+        let mut builder = globset::GlobSetBuilder::new();
+        let mut invalid = Vec::new();
+        for pattern in patterns {
+            let expanded = expand_tilde(Path::new(pattern));
+            match globset::Glob::new(&expanded.to_string_lossy()) {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(e) => {
+                    log::warn!("Ignoring glob {:?}: {}", pattern, e);
+                    invalid.push(pattern.clone());
+                }
+            }
+        }
+        let set = builder.build().unwrap_or_else(|e| {
+            log::warn!("Ignoring globs: {}", e);
+            globset::GlobSet::empty()
+        });
+        (set, invalid)
+    }
+
     /// Expand tilde to home directory
     pub fn expand_tilde(path: &Path) -> PathBuf {
         if let Ok(stripped) = path.strip_prefix("~") {
@@ -87,6 +205,47 @@ pub mod path {
         }
         path.to_path_buf()
     }
+
+    /// Replace the home directory at the start of a path with `~`
+    pub fn contract_tilde(path: &Path) -> PathBuf {
+        if let Some(home) = dirs::home_dir() {
+            if let Ok(stripped) = path.strip_prefix(&home) {
+                return Path::new("~").join(stripped);
+            }
+        }
+        path.to_path_buf()
+    }
+
+    /// Local path named by a `notebook://` or `file://` URL
+    ///
+    /// Both name an absolute path after the scheme, percent-encoded the way
+    /// file managers write file URLs, e.g. `notebook:///home/me/notes.md`.
+    /// Returns None for other URLs and for paths on another host.
+    pub fn path_from_url(url: &str) -> Option<PathBuf> {
+        use std::os::unix::ffi::OsStringExt;
+
+        let (scheme, rest) = url.split_once("://")?;
+        if !scheme.eq_ignore_ascii_case("notebook") && !scheme.eq_ignore_ascii_case("file") {
+            return None;
+        }
+        let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+        if !rest.starts_with('/') {
+            return None;
+        }
+
+        let mut bytes = Vec::with_capacity(rest.len());
+        let mut input = rest.bytes();
+        while let Some(byte) = input.next() {
+            if byte == b'%' {
+                let hex = [input.next()?, input.next()?];
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            } else {
+                bytes.push(byte);
+            }
+        }
+        Some(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+    }
 }
 
 /// Text utilities
@@ -105,13 +264,64 @@ pub mod text {
         }
     }
 
-    /// Get the line at a given character offset
-    pub fn line_at_offset(text: &str, offset: usize) -> Option<usize> {
-        if offset > text.len() {
-            return None;
+    /// Get the zero-based line at a character offset
+    ///
+    /// Returns `None` past the end of the text. An offset equal to the
+    /// character count is the end of the last line.
+    pub fn line_at_char_offset(text: &str, offset: usize) -> Option<usize> {
+        let mut line = 0;
+        let mut chars = 0;
+        for ch in text.chars() {
+            if chars == offset {
+                return Some(line);
+            }
+            if ch == '\n' {
+                line += 1;
+            }
+            chars += 1;
         }
+        (chars == offset).then_some(line)
+    }
 
-        Some(text[..offset].matches('\n').count())
+    /// Get the zero-based line at a byte offset
+    ///
+    /// Returns `None` past the end of the text or when the offset falls
+    /// inside a multibyte character.
+    pub fn line_at_byte_offset(text: &str, offset: usize) -> Option<usize> {
+        let before = text.get(..offset)?;
+        Some(before.matches('\n').count())
+    }
+
+    /// Score how well `query` fuzzily matches `candidate`
+    ///
+    /// The query's characters must appear in order, ignoring case. Higher is
+    /// better: matches at the start of words and runs of consecutive
+    /// characters score extra. Returns `None` when the query doesn't match;
+    /// an empty query matches everything with a score of 0.
+    pub fn fuzzy_score(query: &str, candidate: &str) -> Option<u32> {
+        let mut score = 0;
+        let mut wanted = query.chars().flat_map(char::to_lowercase).peekable();
+        let mut prev: Option<char> = None;
+        let mut prev_matched = false;
+        for ch in candidate.chars() {
+            let Some(&next) = wanted.peek() else {
+                break;
+            };
+            let matched = ch.to_lowercase().eq(std::iter::once(next));
+            if matched {
+                wanted.next();
+                score += 1;
+                if prev.map_or(true, |p| !p.is_alphanumeric()) {
+                    score += 8;
+                }
+                if prev_matched {
+                    score += 5;
+                }
+            }
+            prev = Some(ch);
+            prev_matched = matched;
+        }
+        wanted.peek().is_none().then_some(score)
     }
 
     /// Truncate string with ellipsis
@@ -126,6 +336,41 @@ pub mod text {
     }
 }
 
+/// Date/time formatting
+pub mod datetime {
+    use chrono::format::{Item, StrftimeItems};
+    use chrono::{DateTime, TimeZone};
+
+    /// ISO 8601 date and time format
+    pub const ISO_8601_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%:z";
+
+    /// Locale date format
+    pub const LOCALE_DATE_FORMAT: &str = "%x";
+
+    /// Check whether a strftime-style format string is valid
+    pub fn is_valid_format(format: &str) -> bool {
+        !format.is_empty() && StrftimeItems::new(format).all(|item| item != Item::Error)
+    }
+
+    /// Format the current local time
+    pub fn format_now(format: &str) -> String {
+        format_time(&chrono::Local::now(), format)
+    }
+
+    /// Format a time, falling back to ISO 8601 for an invalid format
+    pub fn format_time<Tz: TimeZone>(time: &DateTime<Tz>, format: &str) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        if is_valid_format(format) {
+            time.format(format).to_string()
+        } else {
+            log::warn!("Invalid date/time format {:?}, using ISO 8601", format);
+            time.format(ISO_8601_FORMAT).to_string()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,6 +382,48 @@ mod tests {
         assert!(!debouncer.should_trigger());
     }
 
+    #[test]
+    fn test_debouncer_edges() {
+        use std::time::{Duration, Instant};
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let delay = Duration::from_millis(100);
+
+        // Leading fires again only after a quiet period
+        let mut leading = Debouncer::with_edge(delay, DebounceEdge::Leading);
+        assert!(leading.should_trigger_at(at(0)));
+        assert!(!leading.should_trigger_at(at(50)));
+        assert!(!leading.should_trigger_at(at(120)));
+        assert!(!leading.trailing());
+        assert!(leading.should_trigger_at(at(300)));
+
+        // Trailing fires once, after the last trigger of a burst
+        let mut trailing = Debouncer::with_edge(delay, DebounceEdge::Trailing);
+        assert!(!trailing.should_trigger_at(at(0)));
+        assert!(!trailing.should_trigger_at(at(60)));
+        assert!(trailing.trailing());
+        assert_eq!(trailing.deadline(), Some(at(160)));
+        // The timer scheduled by the first trigger is stale
+        assert!(!trailing.fire_trailing_at(at(100)));
+        assert!(trailing.fire_trailing_at(at(160)));
+        assert!(!trailing.fire_trailing_at(at(200)));
+        assert!(!trailing.trailing());
+
+        // Both skips the trailing fire for a single trigger
+        let mut both = Debouncer::with_edge(delay, DebounceEdge::Both);
+        assert!(both.should_trigger_at(at(0)));
+        assert!(!both.trailing());
+        assert!(both.should_trigger_at(at(500)));
+        assert!(!both.should_trigger_at(at(550)));
+        assert!(both.trailing());
+        assert!(both.fire_trailing_at(at(650)));
+
+        both.should_trigger_at(at(700));
+        both.should_trigger_at(at(720));
+        both.reset();
+        assert!(!both.fire_trailing_at(at(900)));
+    }
+
     #[test]
     fn test_is_markdown() {
         assert!(path::is_markdown(Path::new("test.md")));
@@ -144,6 +431,80 @@ mod tests {
         assert!(!path::is_markdown(Path::new("test.txt")));
     }
 
+    #[test]
+    fn test_contract_tilde() {
+        if let Some(home) = dirs::home_dir() {
+            let path = home.join("notes/todo.md");
+            assert_eq!(path::contract_tilde(&path), PathBuf::from("~/notes/todo.md"));
+            assert_eq!(path::expand_tilde(&path::contract_tilde(&path)), path);
+        }
+        assert_eq!(path::contract_tilde(Path::new("/tmp/a.md")), PathBuf::from("/tmp/a.md"));
+    }
+
+    #[test]
+    fn test_path_from_url() {
+        assert_eq!(
+            path::path_from_url("notebook:///home/me/My%20Notes.md"),
+            Some(PathBuf::from("/home/me/My Notes.md"))
+        );
+        assert_eq!(
+            path::path_from_url("file://localhost/tmp/a.md"),
+            Some(PathBuf::from("/tmp/a.md"))
+        );
+        assert_eq!(path::path_from_url("FILE:///tmp/%C3%A9.md"), Some(PathBuf::from("/tmp/é.md")));
+        assert_eq!(path::path_from_url("notebook://server/a.md"), None);
+        assert_eq!(path::path_from_url("https://example.com/a.md"), None);
+        assert_eq!(path::path_from_url("notebook:///a%2"), None);
+        assert_eq!(path::path_from_url("/tmp/a.md"), None);
+    }
+
+    #[test]
+    fn test_relative_from() {
+        let from = |p: &str, base: &str| path::relative_from(Path::new(p), Path::new(base));
+        assert_eq!(from("/notes/img/cat.png", "/notes"), Some(PathBuf::from("img/cat.png")));
+        assert_eq!(from("/notes/todo.md", "/notes/work/2024"), Some(PathBuf::from("../../todo.md")));
+        assert_eq!(from("/other/a.md", "/notes"), Some(PathBuf::from("../other/a.md")));
+        assert_eq!(from("a.md", "/notes"), None);
+    }
+
+    #[test]
+    fn test_workspace_relative() {
+        let root = Some(Path::new("/notes"));
+        assert_eq!(path::workspace_relative(Path::new("/notes/work/a.md"), root), PathBuf::from("work/a.md"));
+        assert_eq!(path::workspace_relative(Path::new("/other/a.md"), root), PathBuf::from("/other/a.md"));
+        assert_eq!(path::workspace_relative(Path::new("/notes/a.md"), None), PathBuf::from("/notes/a.md"));
+    }
+
+    #[test]
+    fn test_glob_set() {
+        let patterns = vec![
+            "/home/*/Dropbox/**".to_string(),
+            "a{b".to_string(),
+            "*.tmp".to_string(),
+        ];
+        let (set, invalid) = path::glob_set(&patterns);
+        assert_eq!(invalid, vec!["a{b".to_string()]);
+        assert!(set.is_match("/home/me/Dropbox/notes/todo.md"));
+        assert!(set.is_match("/var/tmp/scratch.tmp"));
+        assert!(!set.is_match("/home/me/notes/todo.md"));
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(text::fuzzy_score("", "Save"), Some(0));
+        assert!(text::fuzzy_score("sva", "Save As").is_some());
+        assert_eq!(text::fuzzy_score("xyz", "Save"), None);
+        assert_eq!(text::fuzzy_score("as", "Sa"), None);
+
+        // Word starts and consecutive letters beat scattered matches
+        let word_starts = text::fuzzy_score("sa", "Save All").unwrap();
+        let scattered = text::fuzzy_score("sa", "Toggle Sidebar").unwrap();
+        assert!(word_starts > scattered);
+        let consecutive = text::fuzzy_score("zoom", "Zoom In").unwrap();
+        let spread = text::fuzzy_score("zoom", "Zone Bottom").unwrap();
+        assert!(consecutive > spread);
+    }
+
     #[test]
     fn test_word_count() {
         assert_eq!(text::word_count("hello world"), 2);
@@ -151,9 +512,39 @@ mod tests {
         assert_eq!(text::word_count("one"), 1);
     }
 
+    #[test]
+    fn test_line_at_offset() {
+        let text = "héllo\n日本\nend";
+
+        assert_eq!(text::line_at_char_offset(text, 0), Some(0));
+        assert_eq!(text::line_at_char_offset(text, 5), Some(0));
+        assert_eq!(text::line_at_char_offset(text, 6), Some(1));
+        assert_eq!(text::line_at_char_offset(text, 9), Some(2));
+        assert_eq!(text::line_at_char_offset(text, 12), Some(2));
+        assert_eq!(text::line_at_char_offset(text, 13), None);
+
+        // "é" is two bytes and each ideograph three
+        assert_eq!(text::line_at_byte_offset(text, 7), Some(1));
+        assert_eq!(text::line_at_byte_offset(text, 2), None);
+        assert_eq!(text::line_at_byte_offset(text, 8), None);
+        assert_eq!(text::line_at_byte_offset(text, text.len()), Some(2));
+        assert_eq!(text::line_at_byte_offset(text, text.len() + 1), None);
+    }
+
     #[test]
     fn test_truncate() {
         assert_eq!(text::truncate("hello", 10), "hello");
         assert_eq!(text::truncate("hello world", 8), "hello...");
     }
+
+    #[test]
+    fn test_datetime_format() {
+        use chrono::TimeZone;
+        let time = chrono::Utc.with_ymd_and_hms(2024, 3, 9, 14, 5, 0).unwrap();
+
+        assert_eq!(datetime::format_time(&time, "%Y-%m-%d"), "2024-03-09");
+        assert_eq!(datetime::format_time(&time, "%Q"), "2024-03-09T14:05:00+00:00");
+        assert_eq!(datetime::format_time(&time, ""), "2024-03-09T14:05:00+00:00");
+        assert!(!datetime::is_valid_format("%Y-%"));
+    }
 }